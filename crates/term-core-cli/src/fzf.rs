@@ -0,0 +1,165 @@
+//! Candidate feed and selection for fzf pickers:
+//!
+//! ```sh
+//! term-core-cli fzf-feed --query api \
+//!   | fzf --delimiter '\t' --with-nth 1,3 \
+//!   | term-core-cli fzf-select --launch
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use term_core::{api, CoreError, LaunchRequest, RankWeights, SearchOptions, TaggedPath};
+
+use crate::output::{emit_json, emit_records, emit_string};
+
+#[derive(Default)]
+pub struct Candidate {
+    pub score: f64,
+    pub favorite: bool,
+    pub opened_utc: Option<i64>,
+    pub tags: Vec<TaggedPath>,
+    /// Found by the search for the query rather than only known from the store.
+    pub matched: bool,
+}
+
+impl Candidate {
+    pub fn annotations(&self, now: i64) -> String {
+        let mut parts = Vec::new();
+        if self.favorite {
+            parts.push(String::from("★"));
+        }
+        if let Some(opened) = self.opened_utc {
+            parts.push(format!("opened {}", age((now - opened).max(0))));
+        }
+        if self.matched {
+            parts.push(String::from("match"));
+        }
+        parts.extend(self.tags.iter().map(|tag| format!("#{}", tag.tag)));
+        parts.join(" ")
+    }
+}
+
+fn age(seconds: i64) -> String {
+    match seconds {
+        0..=59 => String::from("just now"),
+        60..=3_599 => format!("{}m ago", seconds / 60),
+        3_600..=86_399 => format!("{}h ago", seconds / 3_600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+/// Favorites, recents, and (with a query) search results below `start`, best first, one
+/// candidate per path.
+pub fn ranked(query: Option<&str>, start: &str, limit: usize) -> Result<Vec<(String, Candidate)>> {
+    let mut candidates: BTreeMap<String, Candidate> = BTreeMap::new();
+    for path in api::list_favorites() {
+        candidates.entry(path).or_default().favorite = true;
+    }
+    for entry in api::list_recents() {
+        candidates.entry(entry.path).or_default().opened_utc = Some(entry.last_opened_utc);
+    }
+    for tag in api::list_tags() {
+        candidates
+            .entry(tag.path.clone())
+            .or_default()
+            .tags
+            .push(tag);
+    }
+    let paths: Vec<String> = candidates.keys().cloned().collect();
+    let weights = RankWeights::default();
+    for (path, rank) in paths.iter().zip(api::rank_paths(&paths, &weights)) {
+        if let Some(candidate) = candidates.get_mut(path) {
+            candidate.score = rank.total;
+        }
+    }
+    if let Some(query) = query.filter(|query| !query.trim().is_empty()) {
+        let options = SearchOptions {
+            record_history: false,
+            ..SearchOptions::default()
+        };
+        for result in api::search(start, query, limit, &options)? {
+            let candidate = candidates.entry(result.path).or_default();
+            candidate.score = candidate.score.max(result.rank.total);
+            candidate.matched = true;
+        }
+    }
+
+    let mut ranked: Vec<(String, Candidate)> = candidates.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then(a.0.cmp(&b.0)));
+    Ok(ranked)
+}
+
+/// Prints [`ranked`] candidates as `path<D>score<D>notes` lines.
+pub fn feed(query: Option<&str>, start: &str, limit: usize, delimiter: &str) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let records: Vec<String> = ranked(query, start, limit)?
+        .iter()
+        .map(|(path, candidate)| {
+            format!(
+                "{path}{delimiter}{:.1}{delimiter}{}",
+                candidate.score,
+                candidate.annotations(now)
+            )
+        })
+        .collect();
+    emit_records(&records)
+}
+
+/// Resolves a line picked in fzf back to its path, then opens a terminal there (`launch`) or
+/// prints the path for `cd`, copying it too with `copy`. Either way the path counts as opened.
+pub fn select(
+    line: Option<String>,
+    delimiter: &str,
+    launch: bool,
+    terminal: Option<String>,
+    copy: bool,
+) -> Result<()> {
+    let line = match line {
+        Some(line) => line,
+        None => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .context("read selection")?;
+            input
+        }
+    };
+    let path = line
+        .split(['\n', '\0'])
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| line.split(delimiter).next())
+        .map(str::trim)
+        .ok_or_else(|| CoreError::invalid_input("nothing selected"))?;
+    let path = api::normalize_path(path)?;
+    if !std::path::Path::new(&path).is_dir() {
+        anyhow::bail!(CoreError::not_found(format!("not a directory: {path}")));
+    }
+    if copy {
+        api::copy_to_clipboard(&path)?;
+    }
+    if !launch {
+        api::touch_recent(&path)?;
+        return emit_string(path);
+    }
+    let request = LaunchRequest {
+        path: path.clone(),
+        terminal,
+        command: None,
+        args: Vec::new(),
+        use_shell: false,
+        pre_command: None,
+        post_launch_command: None,
+        background: false,
+        profile: None,
+        profile_id: None,
+        windows: 1,
+        env: BTreeMap::new(),
+        wsl_distro: None,
+    };
+    api::launch(&request)?;
+    api::touch_recent(&path)?;
+    emit_json(&request)
+}
@@ -0,0 +1,231 @@
+//! `term-core-cli init`: a first-run wizard that picks a terminal, imports directory history
+//! from other tools, and registers the folders projects live in, so a blank install starts
+//! out useful.
+//!
+//! Questions go to stderr and the summary to stdout. With `--yes`, or when stdin is not a
+//! terminal, every question takes its default.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use term_core::{api, ImportReport, ImportSource, ScanReport};
+
+use crate::output::emit_json;
+
+/// Folders under the home directory where checkouts usually live.
+const PROJECT_DIRS: [&str; 10] = [
+    "dev",
+    "Developer",
+    "src",
+    "code",
+    "projects",
+    "Projects",
+    "work",
+    "repos",
+    "git",
+    "workspace",
+];
+
+/// History imported as recents, in the order offered.
+const HISTORY_SOURCES: [(ImportSource, &str); 3] = [
+    (ImportSource::Zoxide, "zoxide"),
+    (ImportSource::Vscode, "VS Code"),
+    (ImportSource::Jetbrains, "JetBrains IDEs"),
+];
+
+#[derive(Serialize)]
+struct InitReport {
+    config_path: String,
+    /// `terminal.default` as set by the wizard, or as it already was.
+    terminal: Option<String>,
+    imports: Vec<ImportReport>,
+    /// Project roots added to `projects.roots`.
+    roots_added: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan: Option<ScanReport>,
+}
+
+struct Prompter {
+    interactive: bool,
+}
+
+impl Prompter {
+    /// The typed answer, or `None` for an empty one, at end of input, or without a terminal.
+    fn read_answer(&self, prompt: &str) -> Result<Option<String>> {
+        if !self.interactive {
+            return Ok(None);
+        }
+        eprint!("{prompt}: ");
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        let answer = answer.trim();
+        Ok((!answer.is_empty()).then(|| answer.to_string()))
+    }
+
+    fn ask(&self, question: &str, default: &str) -> Result<String> {
+        let answer = self.read_answer(&format!("{question} [{default}]"))?;
+        Ok(answer.unwrap_or_else(|| default.to_string()))
+    }
+
+    fn confirm(&self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let Some(answer) = self.read_answer(&format!("{question} [{hint}]"))? else {
+                return Ok(default);
+            };
+            match answer.to_ascii_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => eprintln!("Please answer y or n."),
+            }
+        }
+    }
+}
+
+/// `~/dev` for paths under the home directory.
+fn tilde(path: &Path) -> String {
+    match home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+fn home_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(Into::into)
+}
+
+fn choose_terminal(prompter: &Prompter) -> Result<Option<String>> {
+    let config = api::get_config()?;
+    if let Some(terminal) = config.terminal.default {
+        eprintln!("Terminal: keeping {terminal} from the config.");
+        return Ok(Some(terminal));
+    }
+    let installed = api::installed_terminals();
+    if installed.is_empty() {
+        eprintln!("Terminal: none of the supported terminals were found; using the OS default.");
+        return Ok(None);
+    }
+    let system = api::default_terminal().terminal;
+    let default = installed
+        .iter()
+        .find(|terminal| Some(&terminal.name) == system.as_ref())
+        .unwrap_or(&installed[0])
+        .name
+        .clone();
+    let names: Vec<&str> = installed
+        .iter()
+        .map(|terminal| terminal.name.as_str())
+        .collect();
+    loop {
+        let answer = prompter.ask(
+            &format!("Terminal to open folders in ({})", names.join(", ")),
+            &default,
+        )?;
+        let answer = answer.to_ascii_lowercase();
+        if names.contains(&answer.as_str()) || answer == "system" {
+            api::set_config("terminal.default", &format!("{answer:?}"))?;
+            return Ok(Some(answer));
+        }
+        eprintln!("Pick one of: {}, or system.", names.join(", "));
+    }
+}
+
+fn import_history(prompter: &Prompter) -> Result<Vec<ImportReport>> {
+    let mut reports = Vec::new();
+    for (source, name) in HISTORY_SOURCES {
+        if !source.is_available()
+            || !prompter.confirm(&format!("Import recent folders from {name}?"), true)?
+        {
+            continue;
+        }
+        match api::import_projects(source, false, false) {
+            Ok(report) => {
+                eprintln!("  {} recents added.", report.recents_added.len());
+                reports.push(report);
+            }
+            Err(err) => eprintln!("  Skipped {name}: {err:#}"),
+        }
+    }
+    let bookmarks = ImportSource::platform_bookmarks();
+    if bookmarks.is_available()
+        && prompter.confirm(
+            "Import the file manager's sidebar bookmarks as favorites?",
+            true,
+        )?
+    {
+        match api::import_bookmarks(Some(bookmarks), false) {
+            Ok(report) => {
+                eprintln!("  {} favorites added.", report.favorites_added.len());
+                reports.push(report);
+            }
+            Err(err) => eprintln!("  Skipped bookmarks: {err:#}"),
+        }
+    }
+    Ok(reports)
+}
+
+/// Offers each usual project folder that exists and isn't configured yet as a scan root.
+fn choose_roots(prompter: &Prompter) -> Result<Vec<String>> {
+    let Some(home) = home_dir() else {
+        return Ok(Vec::new());
+    };
+    let mut roots = api::get_config()?.projects.roots;
+    let configured: Vec<String> = roots
+        .iter()
+        .filter_map(|root| {
+            Path::new(&api::normalize_path(root).ok()?)
+                .canonicalize()
+                .ok()
+        })
+        .map(|root| root.display().to_string())
+        .collect();
+    let mut added = Vec::new();
+    for name in PROJECT_DIRS {
+        let dir = home.join(name);
+        // Case-insensitive filesystems list `projects` and `Projects` twice.
+        let Ok(dir) = dir.canonicalize() else {
+            continue;
+        };
+        let key = dir.display().to_string();
+        if !dir.is_dir() || configured.contains(&key) || added.contains(&key) {
+            continue;
+        }
+        if prompter.confirm(&format!("Scan {} for projects?", tilde(&dir)), true)? {
+            added.push(key);
+        }
+    }
+    if !added.is_empty() {
+        roots.extend(added.iter().cloned());
+        let value = serde_json::to_string(&roots)?;
+        api::set_config("projects.roots", &value)?;
+    }
+    Ok(added)
+}
+
+pub fn run(yes: bool) -> Result<()> {
+    let prompter = Prompter {
+        interactive: !yes && std::io::stdin().is_terminal(),
+    };
+    let terminal = choose_terminal(&prompter)?;
+    let imports = import_history(&prompter)?;
+    let roots_added = choose_roots(&prompter)?;
+    let scan = match roots_added.is_empty() {
+        true => None,
+        false => {
+            eprintln!("Scanning for projects...");
+            Some(api::refresh_projects(&roots_added)?)
+        }
+    };
+    emit_json(&InitReport {
+        config_path: api::config_path().display().to_string(),
+        terminal,
+        imports,
+        roots_added,
+        scan,
+    })
+}
@@ -1,27 +1,109 @@
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use term_core::api;
+use clap::{Args, Parser, Subcommand};
+use term_core::{
+    api, CaseMatching, CheckStatus, CoreError, ErrorCode, GrepOptions, ImportSource, KubeTarget,
+    ListOptions, ListSort, LogConfig, LogLevel, MatchOptions, MatcherKind, PathFlavor, RankWeights,
+    RecentGrouping, RecentsSort, SearchOptions, SessionEntry, SymlinkPolicy, TagRule, TaskCallback,
+    TaskLine, TaskOptions, Transport, UnicodeNormalization,
+};
 use uuid::Uuid;
 
+mod fzf;
+mod init;
+mod output;
+mod script_filter;
+
+use output::{emit_error, emit_json, emit_line, emit_ok, emit_string, error_body, Format};
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  unexpected failure
+  2  invalid command line
+  3  not found
+  4  invalid input
+  5  I/O error
+  6  state file is corrupt
+  7  the state changed since the expected generation
+
+With JSON output, errors are printed to stderr as {\"code\", \"message\", \"hint\"}.";
+
 #[derive(Parser)]
 #[command(
     name = "term-core-cli",
     author,
     version,
-    about = "JSON surface for Terminaut core"
+    about = "JSON surface for Terminaut core",
+    after_help = EXIT_CODES
 )]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Output format. `ndjson` prints list results one JSON object per line.
+    #[arg(long, global = true, value_enum)]
+    format: Option<Format>,
+    /// Print only paths, each terminated by NUL, for `xargs -0` and `fzf --read0`.
+    #[arg(long, global = true)]
+    print0: bool,
+    /// Core log messages at or above this level go to stderr (off, error, warn, info, debug, trace).
+    #[arg(long, global = true, default_value = "warn")]
+    log_level: LogLevel,
+    /// Apply changes in memory only: nothing is saved and hooks don't run. Also enabled by
+    /// `TERMINAUT_READONLY=1`. To start from empty state as well, without reading the state
+    /// file, set `TERMINAUT_EPHEMERAL=1`.
+    #[arg(long, global = true)]
+    read_only: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Normalize {
         path: String,
+        /// `resolve` or `preserve`; defaults to `paths.symlinks` from the config.
+        #[arg(long)]
+        symlinks: Option<SymlinkPolicy>,
+        /// Also put the normalized path on the clipboard.
+        #[arg(long)]
+        copy: bool,
     },
+    /// Print `path` with the home directory as `~`, or relative to `--relative-to`.
+    Display {
+        path: String,
+        #[arg(long)]
+        relative_to: Option<String>,
+    },
+    /// Open the default terminal at `path`, or `--terminal` (terminal, iterm, ghostty,
+    /// alacritty, windows-terminal).
+    Open {
+        path: String,
+        #[arg(long)]
+        terminal: Option<String>,
+    },
+    /// Entries of a directory, or of a directory inside a .zip, .tar, .tar.gz, or .7z file
+    /// (`list downloads/src.zip/lib`).
     List {
         path: String,
+        /// Annotate entries with their `git status` state.
+        #[arg(long)]
+        git: bool,
+        /// `name`, or `frecency` to put the subdirectories you open most first.
+        #[arg(long, default_value = "name")]
+        sort: ListSort,
+    },
+    Roots,
+    Ancestors {
+        path: String,
+    },
+    /// Directories below or beside `path` you are likely to open next, from your recents.
+    Suggest {
+        #[arg(default_value = ".")]
+        path: String,
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
     },
     Favorites {
         #[command(subcommand)]
@@ -31,55 +113,621 @@ enum Commands {
         #[command(subcommand)]
         action: RecentsCommand,
     },
+    /// Project roots enclosing `path`, or the project registry with `scan` and `list`.
+    #[command(args_conflicts_with_subcommands = true)]
     Projects {
+        #[command(subcommand)]
+        action: Option<ProjectsCommand>,
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    /// Show the enclosing project, merged with its `.terminaut.toml`.
+    Project {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    /// Run a task from the enclosing project's `.terminaut.toml`, printing each output line
+    /// and then the result as JSON lines; exits 1 if the task fails.
+    Task {
+        name: String,
+        #[arg(long, default_value = ".")]
+        path: String,
+        /// Add the project's `env` table to the task's environment.
+        #[arg(long)]
+        env: bool,
+        /// Kill the task after this many seconds.
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// Last result of each task of the enclosing project run with `task`.
+    Status {
+        #[arg(default_value = ".")]
         path: String,
     },
+    /// Title, summary, and repository of the enclosing project, from its README and manifest.
+    Describe {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    /// Host, owner, repo, and web URL of the enclosing project's git remote.
+    Remote {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    Preview {
+        path: String,
+    },
+    Du {
+        path: String,
+    },
+    Fs {
+        #[command(subcommand)]
+        action: FsCommand,
+    },
     Tags {
         #[command(subcommand)]
         action: TagCommand,
     },
+    /// Markdown notes attached to directories.
+    Notes {
+        #[command(subcommand)]
+        action: NoteCommand,
+    },
+    /// Custom commands offered per path or tag, e.g. "Deploy".
+    Actions {
+        #[command(subcommand)]
+        action: ActionCommand,
+    },
     Profiles {
         #[command(subcommand)]
         action: ProfileCommand,
     },
+    Sessions {
+        #[command(subcommand)]
+        action: SessionCommand,
+    },
+    Prune {
+        /// Print what would be dropped or rewritten without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    State {
+        #[command(subcommand)]
+        action: StateCommand,
+    },
+    /// Bring in favorites and recents from other apps.
+    Import {
+        #[command(subcommand)]
+        action: ImportCommand,
+    },
+    /// Keychain secrets for `{secret:NAME}` references in saved profiles' commands.
+    Secrets {
+        #[command(subcommand)]
+        action: SecretCommand,
+    },
+    /// Front-end settings kept with the saved state, by namespace and key.
+    Settings {
+        #[command(subcommand)]
+        action: SettingCommand,
+    },
+    /// Windows/WSL path translation and installed distros.
+    Wsl {
+        #[command(subcommand)]
+        action: WslCommand,
+    },
+    /// Contexts of the kubeconfig, for pod targets of profiles.
+    KubeContexts,
+    Editor {
+        #[command(subcommand)]
+        action: EditorCommand,
+    },
+    /// The OS default terminal or login shell, and where it was read from.
+    Defaults {
+        #[command(subcommand)]
+        action: DefaultsCommand,
+    },
+    /// Revert the last favorite removal, tag removal, profile deletion, or prune.
+    Undo {
+        /// List the undoable operations, most recent first, instead of undoing one.
+        #[arg(long)]
+        list: bool,
+    },
+    /// Check the config, state store, terminals, and search speed; exits 1 if a check fails.
+    Doctor,
+    /// Run the index daemon: watch `projects.roots` and rescan them as projects appear or go,
+    /// serving status and read-only queries on a socket only you can open. Runs until stopped.
+    Indexd {
+        /// Print the running daemon's status instead.
+        #[arg(long, conflicts_with = "stop")]
+        status: bool,
+        /// Stop the running daemon.
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Set up a new install: pick a terminal, import recent folders from zoxide, VS Code,
+    /// and JetBrains IDEs, and choose folders to scan for projects.
+    Init {
+        /// Accept every default without asking.
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Local usage counts per week (enable with `config set stats.enabled true`).
+    Stats {
+        #[arg(long, default_value_t = 4)]
+        weeks: usize,
+        /// Delete all recorded stats instead of printing them.
+        #[arg(long)]
+        clear: bool,
+        #[arg(long, requires = "clear")]
+        dry_run: bool,
+    },
+    SavedSearch {
+        #[command(subcommand)]
+        action: SavedSearchCommand,
+    },
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
     Search {
         query: String,
         #[arg(long, default_value = "~")]
         start: String,
         #[arg(short, long, default_value_t = 20)]
         limit: usize,
+        #[command(flatten)]
+        options: SearchArgs,
+        /// Put the best match's path on the clipboard.
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Search file contents.
+    Grep {
+        pattern: String,
+        #[arg(long, default_value = ".")]
+        start: String,
+        #[arg(short, long, default_value_t = 200)]
+        limit: usize,
+        /// Treat the pattern as a literal string.
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        #[arg(long)]
+        max_depth: Option<usize>,
+        #[arg(long)]
+        no_gitignore: bool,
+        #[arg(long)]
+        hidden: bool,
+        #[arg(long = "include", value_name = "GLOB")]
+        include: Vec<String>,
+        #[arg(long = "exclude", value_name = "GLOB")]
+        exclude: Vec<String>,
+    },
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    Script {
+        #[command(subcommand)]
+        action: ScriptCommand,
+    },
+    /// Handle a `terminaut://` deep link.
+    Url {
+        link: String,
+        /// Print the parsed link without acting on it.
+        #[arg(long)]
+        dry_run: bool,
+        /// Act on links that run commands or change state instead of printing the question
+        /// to ask first.
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Run commands read from stdin, one per line, printing one JSON result per line.
+    ///
+    /// Each line is a JSON array of arguments, e.g. `["search", "api", "--limit", "5"]`, or
+    /// an object `{"id": ..., "args": [...]}` whose `id` is echoed back in the result.
+    Batch,
+    /// Print JSON Schema for the output types, or just the one named.
+    Schema {
+        name: Option<String>,
+    },
+    /// Print the core version, ABI and schema versions, features, and invoke methods.
+    Capabilities,
+    /// Print favorites, recents, and search matches as `path<TAB>score<TAB>notes` for fzf.
+    FzfFeed {
+        /// Also search below `--start` for directories matching this query.
+        #[arg(short, long)]
+        query: Option<String>,
+        #[arg(long, default_value = "~")]
+        start: String,
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value = "\t")]
+        delimiter: String,
+    },
+    /// Resolve a line chosen in fzf (argument or stdin) and open it.
+    FzfSelect {
+        line: Option<String>,
+        #[arg(long, default_value = "\t")]
+        delimiter: String,
+        /// Open a terminal at the path instead of printing it.
+        #[arg(long)]
+        launch: bool,
+        #[arg(long)]
+        terminal: Option<String>,
+        /// Also put the path on the clipboard.
+        #[arg(long)]
+        copy: bool,
+    },
+    /// Raycast list items as JSON.
+    Raycast {
+        #[command(subcommand)]
+        action: RaycastCommand,
+    },
+    /// Alfred Script Filter JSON.
+    Alfred {
+        #[command(subcommand)]
+        action: AlfredCommand,
     },
     Version,
 }
 
+#[derive(Args)]
+struct ScriptFilterArgs {
+    /// Empty lists favorites and recents.
+    query: Option<String>,
+    #[arg(long, default_value = "~")]
+    start: String,
+    #[arg(short, long, default_value_t = 20)]
+    limit: usize,
+}
+
+#[derive(Subcommand)]
+enum RaycastCommand {
+    Search {
+        #[command(flatten)]
+        args: ScriptFilterArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlfredCommand {
+    Search {
+        #[command(flatten)]
+        args: ScriptFilterArgs,
+        /// Directory of `<tag>.png` icons for tagged paths.
+        #[arg(long)]
+        tag_icons: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScriptCommand {
+    /// Run a Rhai script; extra arguments are available to it as `ARGS`.
+    Run {
+        file: String,
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the whole config, or one dotted key such as `search.max_depth`.
+    Get { key: Option<String> },
+    /// Set a dotted key. Values use TOML syntax; bare words are taken as strings.
+    Set { key: String, value: String },
+    /// Print the config file location.
+    Path,
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Maximum depth below the start directory (0 = unlimited). Defaults to `search.max_depth`.
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Stop walking after this many milliseconds and rank what was found (0 = no limit).
+    /// Defaults to `search.time_budget_ms`.
+    #[arg(long)]
+    time_budget_ms: Option<u64>,
+    /// Do not honor .gitignore/.ignore files.
+    #[arg(long)]
+    no_gitignore: bool,
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Include hidden directories.
+    #[arg(long)]
+    hidden: bool,
+    /// Only return directories matching this glob (repeatable).
+    #[arg(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+    /// Skip directories matching this glob (repeatable).
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Walker threads (0 = automatic).
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+    /// Case matching: smart, insensitive, or sensitive.
+    #[arg(long, default_value = "smart")]
+    case: CaseMatching,
+    /// Unicode normalization applied before matching: none, nfc, or nfkd.
+    #[arg(long, default_value = "none")]
+    normalize: UnicodeNormalization,
+    /// Ignore accents, so "tést" matches "test".
+    #[arg(long)]
+    fold_diacritics: bool,
+    /// Matching backend: skim or nucleo (default: search.matcher from the config).
+    #[arg(long)]
+    matcher: Option<MatcherKind>,
+    /// Rank by fuzzy score only, ignoring recents, favorites, and tags.
+    #[arg(long)]
+    no_frecency: bool,
+    /// Do not record the query in the search history.
+    #[arg(long)]
+    no_history: bool,
+    /// Match folder names only, not the README titles, package names, and descriptions of
+    /// registered projects.
+    #[arg(long)]
+    names_only: bool,
+}
+
+impl From<SearchArgs> for SearchOptions {
+    fn from(args: SearchArgs) -> Self {
+        let defaults = SearchOptions::default();
+        let mut exclude = defaults.exclude;
+        exclude.extend(args.exclude);
+        Self {
+            max_depth: match args.max_depth {
+                Some(depth) => (depth > 0).then_some(depth),
+                None => defaults.max_depth,
+            },
+            respect_gitignore: !args.no_gitignore,
+            follow_symlinks: args.follow_symlinks,
+            hidden: args.hidden,
+            include: args.include,
+            exclude,
+            threads: args.threads,
+            matching: MatchOptions {
+                case: args.case,
+                normalization: args.normalize,
+                fold_diacritics: args.fold_diacritics,
+                matcher: args.matcher,
+            },
+            ranking: if args.no_frecency {
+                RankWeights {
+                    recent: 0.0,
+                    favorite: 0.0,
+                    tagged: 0.0,
+                    ..RankWeights::default()
+                }
+            } else {
+                RankWeights::default()
+            },
+            record_history: !args.no_history,
+            project_text: !args.names_only,
+            time_budget_ms: match args.time_budget_ms {
+                Some(budget) => (budget > 0).then_some(budget),
+                None => defaults.time_budget_ms,
+            },
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum FavoritesCommand {
-    List,
-    Add { path: String },
-    Remove { path: String },
+    List {
+        /// Annotate each entry with `exists` and `is_dir`.
+        #[arg(long)]
+        status: bool,
+    },
+    Add {
+        path: String,
+    },
+    Remove {
+        path: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum RecentsCommand {
+    List {
+        #[arg(long)]
+        status: bool,
+        /// Group by `project`, `day`, or `week` instead of listing flat.
+        #[arg(long, conflicts_with = "status")]
+        by: Option<RecentGrouping>,
+        /// `frecency` or `recency`; defaults to `recents.sort` from the config.
+        #[arg(long, conflicts_with_all = ["status", "by", "source"])]
+        sort: Option<RecentsSort>,
+        /// Only recents touched by this source, e.g. `gui` or `shell`.
+        #[arg(long, conflicts_with_all = ["status", "by"])]
+        source: Option<String>,
+    },
+    Touch {
+        path: String,
+        /// Record which terminal, profile, or app opened the directory.
+        #[arg(long)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectsCommand {
+    /// Walk `roots` (default: the due `projects.roots` from the config) into the registry.
+    Scan { roots: Vec<String> },
+    /// Projects found by earlier scans.
     List,
-    Touch { path: String },
+    /// Repositories checked out more than once below ROOT, with sizes and last use.
+    Duplicates {
+        #[arg(default_value = ".")]
+        root: String,
+    },
 }
 
 #[derive(Subcommand)]
-enum TagCommand {
+enum SavedSearchCommand {
+    List,
+    Save {
+        name: String,
+        /// Fuzzy query; may be omitted when --include globs select the results.
+        #[arg(default_value = "")]
+        query: String,
+        #[arg(long, default_value = "~")]
+        start: String,
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+        #[command(flatten)]
+        options: SearchArgs,
+    },
+    Run {
+        name: String,
+    },
+    Delete {
+        name: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    List {
+        #[arg(long)]
+        root: Option<String>,
+        #[arg(short, long, default_value_t = 50)]
+        limit: usize,
+    },
+    Suggest {
+        prefix: String,
+        #[arg(long)]
+        root: Option<String>,
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+    },
+    Clear {
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FsCommand {
+    Mkdir { path: String },
+    Rename { path: String, new_name: String },
+    Move { path: String, destination: String },
+    Trash { path: String },
+}
+
+#[derive(Subcommand)]
+enum NoteCommand {
+    /// Attach MARKDOWN to PATH, replacing its note; `-` reads it from stdin, and an empty
+    /// note removes it.
+    Set {
+        path: String,
+        markdown: String,
+    },
+    Get {
+        #[arg(default_value = ".")]
+        path: String,
+    },
+    List,
+    /// Notes containing every word of QUERY.
+    Search {
+        query: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ActionCommand {
+    /// Offer LABEL, running COMMAND, for --path or for every directory with --tag. `{path}`
+    /// and `{name}` in COMMAND become the directory and its folder name.
+    Add {
+        label: String,
+        command: String,
+        #[arg(long, conflicts_with = "tag", required_unless_present = "tag")]
+        path: Option<String>,
+        #[arg(long)]
+        tag: Option<String>,
+        /// Run without a terminal, logging the output.
+        #[arg(long)]
+        background: bool,
+    },
+    Remove {
+        id: String,
+    },
     List,
+    /// Actions offered for PATH, its own first, then those of its tags.
     For {
+        #[arg(default_value = ".")]
         path: String,
     },
+    Run {
+        id: String,
+        #[arg(default_value = ".")]
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    List {
+        #[arg(long)]
+        status: bool,
+    },
+    For {
+        path: String,
+    },
+    /// Paths tagged PREFIX or a tag namespaced below it, e.g. `client/`.
+    Prefix {
+        prefix: String,
+    },
+    /// Tags as a tree of `/`-separated namespaces, with path counts.
+    Tree,
     Add {
         path: String,
         tag: String,
-        #[arg(long, default_value = "#0a84ff")]
-        color: String,
+        /// #rrggbb or a name from `tags colors` (default: blue).
+        #[arg(long)]
+        color: Option<String>,
     },
+    /// Named tag colors: the built-ins and the config's `tags.palette`.
+    Colors,
     Remove {
         path: String,
         tag: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Import macOS Finder tags from `path`, or from every favorite and recent.
+    FinderImport {
+        path: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write tags to macOS Finder for `path`, or for every tagged path.
+    FinderExport {
+        path: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Tag projects, favorites, and recents by rule: TAG with --glob and/or --marker, or the
+    /// config's `projects.tag_rules` when TAG is omitted.
+    Auto {
+        tag: Option<String>,
+        /// Paths matching this glob, or below a directory matching it (`~` is home).
+        #[arg(long, requires = "tag")]
+        glob: Option<String>,
+        /// File that must exist directly inside the path, e.g. Cargo.toml.
+        #[arg(long, requires = "tag")]
+        marker: Option<String>,
+        #[arg(long, requires = "tag")]
+        color: Option<String>,
+        /// Report the tags that would be added without adding them.
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -90,7 +738,8 @@ enum ProfileCommand {
         #[arg(long)]
         id: Option<String>,
         name: String,
-        #[arg(long)]
+        /// Command line to run; split into arguments unless it needs a shell.
+        #[arg(long, conflicts_with = "args")]
         command: Option<String>,
         #[arg(long)]
         working_dir: Option<String>,
@@ -98,39 +747,638 @@ enum ProfileCommand {
         terminal: Option<String>,
         #[arg(short, long)]
         windows: Option<u8>,
+        /// Open in this WSL distro (Windows only); pass "" for the default distro.
+        #[arg(long)]
+        wsl_distro: Option<String>,
+        /// Shell command run before the terminal opens, e.g. "git pull"; "" clears it.
+        #[arg(long)]
+        pre_command: Option<String>,
+        /// Shell command run as soon as the terminal has opened, while the session is still
+        /// running; "" clears it.
+        #[arg(long, alias = "post-command")]
+        post_launch_command: Option<String>,
+        /// Run ARGS joined into one line by the shell instead of as a program and arguments.
+        #[arg(long, requires = "args")]
+        use_shell: bool,
+        /// Program and arguments to run, after `--`; each is passed as one word.
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Open a terminal with the profile and report each phase of the launch.
+    Launch {
+        id: String,
+        /// Directory to open; defaults to the profile's working directory.
+        #[arg(long)]
+        path: Option<String>,
+        /// Run the command without a terminal, logging its output; see `profiles logs`.
+        #[arg(long)]
+        background: bool,
+    },
+    /// Open the profile's shell on an SSH host, or make it local again without HOST.
+    Remote {
+        id: String,
+        /// `host`, `user@host`, or a Host alias from ~/.ssh/config.
+        host: Option<String>,
+        /// Directory on the host to start in.
+        #[arg(long, requires = "host")]
+        dir: Option<String>,
+        /// `ssh`, `mosh`, or `et`; falls back to ssh when the client isn't installed.
+        #[arg(long, default_value = "ssh", requires = "host")]
+        transport: Transport,
+    },
+    /// Open the profile's shell in a pod with `kubectl exec -it`, or make it local again
+    /// without POD.
+    Kube {
+        id: String,
+        pod: Option<String>,
+        #[arg(long, requires = "pod")]
+        container: Option<String>,
+        #[arg(long, requires = "pod")]
+        namespace: Option<String>,
+        /// kubeconfig context; defaults to kubectl's current context.
+        #[arg(long, requires = "pod")]
+        context: Option<String>,
+    },
+    /// Launch the profile when you log in, or stop doing so with --off.
+    Autostart {
+        id: String,
+        #[arg(long)]
+        off: bool,
+    },
+    /// List the output logs of the profile's background launches, newest first.
+    Logs {
+        id: String,
+        /// Print the newest log instead of listing them.
+        #[arg(long)]
+        last: bool,
     },
     Delete {
         id: String,
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretCommand {
+    /// Store a secret; the value is read from stdin so it stays out of shell history.
+    Set {
+        name: String,
+    },
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SettingCommand {
+    /// Print the value; exits 3 when it is not set.
+    Get {
+        namespace: String,
+        key: String,
+    },
+    Set {
+        namespace: String,
+        key: String,
+        value: String,
+    },
+    Delete {
+        namespace: String,
+        key: String,
+    },
+    /// Every key of NAMESPACE with its value.
+    List {
+        namespace: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WslCommand {
+    /// Print `path` in its Windows (`C:\x`) or WSL (`/mnt/c/x`) form.
+    Translate {
+        path: String,
+        /// `windows` or `wsl`.
+        #[arg(long)]
+        to: PathFlavor,
+        /// Distro for Linux paths outside `/mnt`; defaults to the current one inside WSL.
+        #[arg(long)]
+        distro: Option<String>,
+    },
+    Distros,
+}
+
+#[derive(Subcommand)]
+enum EditorCommand {
+    /// Known editors and where each is installed.
+    List,
+    /// Open `path` in `--editor`, its preferred editor, or the first installed one.
+    Open {
+        path: String,
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// Print the editor preferred for `path`, if any.
+    Get {
+        path: String,
+    },
+    /// Prefer `editor` for `path` and everything under it.
+    Set {
+        path: String,
+        editor: String,
+    },
+    Clear {
+        path: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DefaultsCommand {
+    Terminal,
+    Shell,
+    /// The shell and environment a terminal's own config file sets (Ghostty, Alacritty).
+    TerminalConfig {
+        /// Defaults to `terminal.default`, then the OS default terminal.
+        terminal: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommand {
+    /// File manager bookmarks as favorites: GTK bookmarks on Linux, the Finder sidebar on macOS.
+    Bookmarks {
+        /// `gtk` or `finder`; defaults to this platform's file manager.
+        #[arg(long)]
+        from: Option<ImportSource>,
+        /// Report what would be added without adding it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Folders recently opened in VS Code as recents.
+    Vscode {
+        /// Add them as favorites instead.
+        #[arg(long)]
+        favorites: bool,
+        /// Report what would be added without adding it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Projects recently opened in JetBrains IDEs as recents.
+    Jetbrains {
+        /// Add them as favorites instead.
+        #[arg(long)]
+        favorites: bool,
+        /// Report what would be added without adding it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Directories zoxide has learned as recents.
+    Zoxide {
+        /// Add them as favorites instead.
+        #[arg(long)]
+        favorites: bool,
+        /// Report what would be added without adding it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Merge another state file (e.g. from a second machine) into this one.
+    Merge { path: String },
+    /// How the state loaded: loaded, ephemeral, quarantined, or unreadable.
+    Status,
+    /// Merge duplicates, trim recents to the cap, and rewrite the state file.
+    Compact {
+        /// Also drop paths that no longer exist, as `prune` does.
+        #[arg(long)]
+        drop_missing: bool,
+        /// Report what compaction would do without rewriting the state.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionCommand {
+    List,
+    Save {
+        name: String,
+        /// `PATH` or `PATH=PROFILE`, where PROFILE is a launch profile name or id.
+        #[arg(required = true)]
+        entries: Vec<String>,
+    },
+    Delete {
+        name: String,
+        #[arg(long)]
+        dry_run: bool,
     },
+    Restore {
+        name: String,
+        /// Print the launches without opening any terminals.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+fn exit_code(code: ErrorCode) -> u8 {
+    match code {
+        ErrorCode::Internal => 1,
+        ErrorCode::NotFound => 3,
+        ErrorCode::InvalidInput => 4,
+        ErrorCode::Io => 5,
+        ErrorCode::StoreCorrupt => 6,
+        ErrorCode::Conflict => 7,
+    }
 }
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    match cli.command {
-        Commands::Normalize { path } => emit_string(api::normalize_path(&path)?),
-        Commands::List { path } => emit_json(&api::list_directory(&path)?),
+    output::init(cli.format, cli.print0);
+    let logging = LogConfig {
+        stderr: cli.log_level,
+        ..LogConfig::default()
+    };
+    if let Err(err) = api::configure_logging(&logging) {
+        return ExitCode::from(exit_code(emit_error(&err)));
+    }
+    if cli.read_only {
+        api::set_read_only(true);
+    }
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => ExitCode::from(exit_code(emit_error(&err))),
+    }
+}
+
+fn run(command: Commands) -> Result<()> {
+    match command {
+        Commands::Normalize {
+            path,
+            symlinks,
+            copy,
+        } => {
+            let normalized = match symlinks {
+                Some(symlinks) => api::normalize_path_with(&path, symlinks)?,
+                None => api::normalize_path(&path)?,
+            };
+            if copy {
+                api::copy_to_clipboard(&normalized)?;
+            }
+            emit_string(normalized)
+        }
+        Commands::Display { path, relative_to } => {
+            emit_string(api::display_path(&path, relative_to.as_deref())?)
+        }
+        Commands::Open { path, terminal } => {
+            api::open_terminal(&path, terminal.as_deref())?;
+            emit_ok()
+        }
+        Commands::List { path, git, sort } => emit_json(&api::list_directory_with(
+            &path,
+            &ListOptions { sort, git },
+        )?),
+        Commands::Roots => emit_json(&api::list_roots()?),
+        Commands::Ancestors { path } => emit_json(&api::ancestors(&path)?),
+        Commands::Suggest { path, limit } => emit_json(&api::suggest_next(&path, limit)?),
         Commands::Favorites { action } => handle_favorites(action),
         Commands::Recents { action } => handle_recents(action),
-        Commands::Projects { path } => emit_json(&api::detect_projects(&path)?),
+        Commands::Projects { action: None, path } => emit_json(&api::detect_projects(&path)?),
+        Commands::Projects {
+            action: Some(ProjectsCommand::Scan { roots }),
+            ..
+        } => emit_json(&api::refresh_projects(&roots)?),
+        Commands::Projects {
+            action: Some(ProjectsCommand::List),
+            ..
+        } => emit_json(&api::list_projects()),
+        Commands::Projects {
+            action: Some(ProjectsCommand::Duplicates { root }),
+            ..
+        } => emit_json(&api::find_duplicate_projects(&root)?),
+        Commands::Project { path } => emit_json(&api::project_info(&path)?),
+        Commands::Status { path } => emit_json(&api::project_status(&path)?),
+        Commands::Task {
+            name,
+            path,
+            env,
+            timeout,
+        } => {
+            let options = TaskOptions {
+                project_env: env,
+                timeout_secs: timeout,
+            };
+            let on_line: TaskCallback = Arc::new(|line: &TaskLine| {
+                let _ = emit_json(line);
+            });
+            let result = api::run_task(&path, &name, &options, Some(on_line))?;
+            emit_json(&result)?;
+            if !result.succeeded() {
+                anyhow::bail!("task {name} did not succeed");
+            }
+            Ok(())
+        }
+        Commands::Describe { path } => emit_json(&api::project_description(&path)?),
+        Commands::Remote { path } => emit_json(&api::project_remote(&path)?),
+        Commands::Preview { path } => emit_json(&api::preview(&path)?),
+        Commands::Du { path } => emit_json(&api::dir_size_blocking(&path)?),
+        Commands::Fs { action } => handle_fs(action),
         Commands::Tags { action } => handle_tags(action),
+        Commands::Notes { action } => handle_notes(action),
+        Commands::Actions { action } => handle_actions(action),
         Commands::Profiles { action } => handle_profiles(action),
+        Commands::Sessions { action } => handle_sessions(action),
+        Commands::Prune { dry_run: true } => emit_dry_run(&api::preview_prune()),
+        Commands::Prune { dry_run: false } => emit_json(&api::prune_state()?),
+        Commands::State {
+            action: StateCommand::Merge { path },
+        } => emit_json(&api::merge_state(&path)?),
+        Commands::State {
+            action: StateCommand::Status,
+        } => emit_json(&api::store_status()),
+        Commands::State {
+            action:
+                StateCommand::Compact {
+                    drop_missing,
+                    dry_run,
+                },
+        } => emit_json(&api::compact_state(drop_missing, dry_run)?),
+        Commands::Import {
+            action: ImportCommand::Bookmarks { from, dry_run },
+        } => emit_json(&api::import_bookmarks(from, dry_run)?),
+        Commands::Import {
+            action: ImportCommand::Vscode { favorites, dry_run },
+        } => emit_json(&api::import_projects(
+            ImportSource::Vscode,
+            favorites,
+            dry_run,
+        )?),
+        Commands::Import {
+            action: ImportCommand::Jetbrains { favorites, dry_run },
+        } => emit_json(&api::import_projects(
+            ImportSource::Jetbrains,
+            favorites,
+            dry_run,
+        )?),
+        Commands::Import {
+            action: ImportCommand::Zoxide { favorites, dry_run },
+        } => emit_json(&api::import_projects(
+            ImportSource::Zoxide,
+            favorites,
+            dry_run,
+        )?),
+        Commands::Secrets { action } => handle_secrets(action),
+        Commands::Settings { action } => handle_settings(action),
+        Commands::Wsl {
+            action: WslCommand::Translate { path, to, distro },
+        } => emit_string(api::translate_path(&path, to, distro.as_deref())?),
+        Commands::Wsl {
+            action: WslCommand::Distros,
+        } => emit_json(&api::wsl_distros()),
+        Commands::KubeContexts => emit_json(&api::list_kube_contexts()?),
+        Commands::Editor { action } => handle_editor(action),
+        Commands::Defaults {
+            action: DefaultsCommand::Terminal,
+        } => emit_json(&api::default_terminal()),
+        Commands::Defaults {
+            action: DefaultsCommand::Shell,
+        } => emit_json(&api::default_shell()),
+        Commands::Defaults {
+            action: DefaultsCommand::TerminalConfig { terminal },
+        } => emit_json(&api::terminal_settings(terminal.as_deref())?),
+        Commands::Undo { list: true } => emit_json(&api::undo_history()),
+        Commands::Undo { list: false } => emit_json(&api::undo_last()?),
+        Commands::Init { yes } => init::run(yes),
+        Commands::Indexd { status: true, .. } => emit_json(&api::indexd_status()?),
+        Commands::Indexd { stop: true, .. } => {
+            api::stop_indexd()?;
+            emit_ok()
+        }
+        Commands::Indexd { .. } => {
+            api::run_indexd()?;
+            emit_ok()
+        }
+        Commands::Doctor => {
+            let report = api::doctor();
+            emit_json(&report)?;
+            let failed = report
+                .checks
+                .iter()
+                .filter(|check| check.status == CheckStatus::Fail)
+                .count();
+            if failed > 0 {
+                anyhow::bail!("{failed} doctor check(s) failed");
+            }
+            Ok(())
+        }
+        Commands::Stats {
+            clear: true,
+            dry_run: true,
+            ..
+        } => emit_dry_run(&api::stats(usize::MAX).weeks),
+        Commands::Stats { clear: true, .. } => api::clear_stats(),
+        Commands::Stats { weeks, .. } => emit_json(&api::stats(weeks)),
+        Commands::SavedSearch { action } => handle_saved_search(action),
+        Commands::History { action } => handle_history(action),
         Commands::Search {
             query,
             start,
             limit,
-        } => emit_json(&api::search(&start, &query, limit)?),
+            options,
+            copy,
+        } => {
+            let results = api::search(&start, &query, limit, &options.into())?;
+            if let Some(best) = results.first().filter(|_| copy) {
+                api::copy_to_clipboard(&best.path)?;
+            }
+            emit_json(&results)
+        }
+        Commands::Grep {
+            pattern,
+            start,
+            limit,
+            fixed_strings,
+            ignore_case,
+            max_depth,
+            no_gitignore,
+            hidden,
+            include,
+            exclude,
+        } => {
+            let defaults = GrepOptions::default();
+            let options = GrepOptions {
+                fixed_strings,
+                case_insensitive: ignore_case,
+                max_results: limit,
+                max_depth,
+                respect_gitignore: !no_gitignore,
+                hidden,
+                include,
+                exclude: defaults.exclude.iter().cloned().chain(exclude).collect(),
+                ..defaults
+            };
+            emit_json(&api::grep(&start, &pattern, &options)?)
+        }
+        Commands::Config { action } => handle_config(action),
+        Commands::Url {
+            link,
+            dry_run: true,
+            ..
+        } => emit_json(&api::parse_url(&link)?),
+        Commands::Url {
+            link,
+            dry_run: false,
+            confirm,
+        } => emit_json(&api::handle_url(&link, confirm)?),
+        Commands::Script {
+            action: ScriptCommand::Run { file, args },
+        } => match api::run_script(&file, &args)? {
+            serde_json::Value::Null => Ok(()),
+            value => emit_json(&value),
+        },
+        Commands::Batch => run_batch(),
+        Commands::Capabilities => emit_json(&api::capabilities()),
+        Commands::Schema { name: None } => emit_json(&api::json_schemas()),
+        Commands::Schema { name: Some(name) } => {
+            let schema = api::json_schemas()
+                .into_iter()
+                .find(|(candidate, _)| candidate.eq_ignore_ascii_case(&name))
+                .map(|(_, schema)| schema)
+                .ok_or_else(|| CoreError::not_found(format!("no schema named {name:?}")))?;
+            emit_json(&schema)
+        }
+        Commands::FzfFeed {
+            query,
+            start,
+            limit,
+            delimiter,
+        } => fzf::feed(query.as_deref(), &start, limit, &delimiter),
+        Commands::FzfSelect {
+            line,
+            delimiter,
+            launch,
+            terminal,
+            copy,
+        } => fzf::select(line, &delimiter, launch, terminal, copy),
+        Commands::Raycast {
+            action: RaycastCommand::Search { args },
+        } => script_filter::raycast(args.query.as_deref(), &args.start, args.limit),
+        Commands::Alfred {
+            action: AlfredCommand::Search { args, tag_icons },
+        } => script_filter::alfred(
+            args.query.as_deref(),
+            &args.start,
+            args.limit,
+            tag_icons.as_deref(),
+        ),
         Commands::Version => emit_string(env!("CARGO_PKG_VERSION")),
     }
 }
 
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum BatchRequest {
+    Args(Vec<String>),
+    Tagged {
+        #[serde(default)]
+        id: serde_json::Value,
+        args: Vec<String>,
+    },
+}
+
+fn run_batch_line(line: &str) -> (serde_json::Value, Result<serde_json::Value>) {
+    let (id, args) = match serde_json::from_str(line) {
+        Ok(BatchRequest::Args(args)) => (serde_json::Value::Null, args),
+        Ok(BatchRequest::Tagged { id, args }) => (id, args),
+        Err(err) => {
+            let err = anyhow::Error::new(err).context("batch lines are JSON argument arrays");
+            return (serde_json::Value::Null, Err(err));
+        }
+    };
+    let command =
+        match Cli::try_parse_from(std::iter::once("term-core-cli".to_string()).chain(args)) {
+            Ok(Cli {
+                command: Commands::Batch,
+                ..
+            }) => Err(anyhow::Error::new(CoreError::invalid_input(
+                "batch cannot be nested",
+            ))),
+            Ok(cli) => Ok(cli.command),
+            Err(err) => Err(anyhow::Error::new(CoreError::invalid_input(
+                err.render().to_string().trim().to_string(),
+            ))),
+        };
+    (
+        id,
+        command.and_then(|command| output::capture(|| run(command))),
+    )
+}
+
+/// Results are flushed line by line so callers can pipeline requests.
+fn run_batch() -> Result<()> {
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context("read batch input")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, result) = run_batch_line(&line);
+        let mut reply = serde_json::Map::new();
+        if !id.is_null() {
+            reply.insert("id".into(), id);
+        }
+        match result {
+            Ok(value) => {
+                reply.insert("ok".into(), true.into());
+                reply.insert("result".into(), value);
+            }
+            Err(err) => {
+                reply.insert("ok".into(), false.into());
+                reply.insert("error".into(), serde_json::to_value(error_body(&err))?);
+            }
+        }
+        emit_line(&reply.into())?;
+    }
+    Ok(())
+}
+
+fn handle_editor(cmd: EditorCommand) -> Result<()> {
+    match cmd {
+        EditorCommand::List => emit_json(&api::editors()),
+        EditorCommand::Open { path, editor } => {
+            emit_json(&api::open_in_editor(&path, editor.as_deref())?)
+        }
+        EditorCommand::Get { path } => emit_json(&api::preferred_editor(&path)?),
+        EditorCommand::Set { path, editor } => {
+            api::set_preferred_editor(&path, Some(&editor))?;
+            emit_ok()
+        }
+        EditorCommand::Clear { path } => {
+            api::set_preferred_editor(&path, None)?;
+            emit_ok()
+        }
+    }
+}
+
 fn handle_favorites(cmd: FavoritesCommand) -> Result<()> {
     match cmd {
-        FavoritesCommand::List => emit_json(&api::list_favorites()),
+        FavoritesCommand::List { status: false } => emit_json(&api::list_favorites()),
+        FavoritesCommand::List { status: true } => emit_json(&api::list_favorites_with_status()),
         FavoritesCommand::Add { path } => {
             api::add_favorite(&path)?;
             emit_ok()
         }
-        FavoritesCommand::Remove { path } => {
+        FavoritesCommand::Remove {
+            path,
+            dry_run: true,
+        } => {
+            let normalized = api::normalize_path(&path)?;
+            let removed: Vec<String> = api::list_favorites()
+                .into_iter()
+                .filter(|favorite| *favorite == normalized)
+                .collect();
+            emit_dry_run(&removed)
+        }
+        FavoritesCommand::Remove { path, .. } => {
             api::remove_favorite(&path)?;
             emit_ok()
         }
@@ -139,26 +1387,234 @@ fn handle_favorites(cmd: FavoritesCommand) -> Result<()> {
 
 fn handle_recents(cmd: RecentsCommand) -> Result<()> {
     match cmd {
-        RecentsCommand::List => emit_json(&api::list_recents()),
-        RecentsCommand::Touch { path } => {
-            api::touch_recent(&path)?;
+        RecentsCommand::List { by: Some(by), .. } => emit_json(&api::recents_grouped(by)),
+        RecentsCommand::List {
+            source: Some(source),
+            ..
+        } => emit_json(&api::list_recents_from(&source)),
+        RecentsCommand::List {
+            sort: Some(sort), ..
+        } => emit_json(&api::list_recents_sorted(sort)),
+        RecentsCommand::List { status: false, .. } => emit_json(&api::list_recents()),
+        RecentsCommand::List { status: true, .. } => emit_json(&api::list_recents_with_status()),
+        RecentsCommand::Touch { path, source } => {
+            match source {
+                Some(source) => api::touch_recent_from(&path, &source)?,
+                None => api::touch_recent(&path)?,
+            }
             emit_ok()
         }
     }
 }
 
+fn handle_saved_search(cmd: SavedSearchCommand) -> Result<()> {
+    match cmd {
+        SavedSearchCommand::List => emit_json(&api::list_saved_searches()),
+        SavedSearchCommand::Save {
+            name,
+            query,
+            start,
+            limit,
+            options,
+        } => emit_json(&api::save_saved_search(
+            &name,
+            &start,
+            &query,
+            limit,
+            options.into(),
+        )?),
+        SavedSearchCommand::Run { name } => emit_json(&api::run_saved_search(&name)?),
+        SavedSearchCommand::Delete {
+            name,
+            dry_run: true,
+        } => {
+            let search = api::list_saved_searches()
+                .into_iter()
+                .find(|search| search.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| CoreError::not_found("saved search not found"))?;
+            emit_dry_run(&[search])
+        }
+        SavedSearchCommand::Delete { name, .. } => {
+            api::delete_saved_search(&name)?;
+            emit_ok()
+        }
+    }
+}
+
+fn handle_history(cmd: HistoryCommand) -> Result<()> {
+    match cmd {
+        HistoryCommand::List { root, limit } => {
+            emit_json(&api::search_history(root.as_deref(), limit)?)
+        }
+        HistoryCommand::Suggest {
+            prefix,
+            root,
+            limit,
+        } => emit_json(&api::search_suggestions(root.as_deref(), &prefix, limit)?),
+        HistoryCommand::Clear { dry_run: true } => {
+            emit_dry_run(&api::search_history(None, usize::MAX)?)
+        }
+        HistoryCommand::Clear { dry_run: false } => {
+            api::clear_search_history()?;
+            emit_ok()
+        }
+    }
+}
+
+fn handle_fs(cmd: FsCommand) -> Result<()> {
+    match cmd {
+        FsCommand::Mkdir { path } => emit_string(api::create_dir(&path)?),
+        FsCommand::Rename { path, new_name } => emit_string(api::rename(&path, &new_name)?),
+        FsCommand::Move { path, destination } => emit_string(api::move_path(&path, &destination)?),
+        FsCommand::Trash { path } => {
+            api::trash(&path)?;
+            emit_ok()
+        }
+    }
+}
+
+fn handle_notes(cmd: NoteCommand) -> Result<()> {
+    match cmd {
+        NoteCommand::Set { path, markdown } => {
+            let markdown = match markdown.as_str() {
+                "-" => std::io::read_to_string(std::io::stdin())?,
+                _ => markdown,
+            };
+            emit_json(&api::set_note(&path, &markdown)?)
+        }
+        NoteCommand::Get { path } => emit_json(&api::get_note(&path)?),
+        NoteCommand::List => emit_json(&api::list_notes()),
+        NoteCommand::Search { query } => emit_json(&api::search_notes(&query)),
+    }
+}
+
+fn handle_actions(cmd: ActionCommand) -> Result<()> {
+    match cmd {
+        ActionCommand::Add {
+            label,
+            command,
+            path,
+            tag,
+            background,
+        } => emit_json(&api::add_action(
+            &label,
+            &command,
+            path.as_deref(),
+            tag.as_deref(),
+            background,
+        )?),
+        ActionCommand::Remove { id } => {
+            api::remove_action(Uuid::parse_str(&id).context("invalid uuid")?)?;
+            emit_ok()
+        }
+        ActionCommand::List => emit_json(&api::list_actions()),
+        ActionCommand::For { path } => emit_json(&api::actions_for(&path)?),
+        ActionCommand::Run { id, path } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            emit_json(&api::run_action(&path, uuid)?)
+        }
+    }
+}
+
+fn handle_secrets(cmd: SecretCommand) -> Result<()> {
+    match cmd {
+        SecretCommand::Set { name } => {
+            let mut value = String::new();
+            std::io::stdin().read_line(&mut value)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            api::set_secret(&name, value)?;
+            emit_ok()
+        }
+        SecretCommand::Delete { name } => {
+            api::delete_secret(&name)?;
+            emit_ok()
+        }
+    }
+}
+
+fn handle_settings(cmd: SettingCommand) -> Result<()> {
+    match cmd {
+        SettingCommand::Get { namespace, key } => {
+            let value = api::setting_get(&namespace, &key)?
+                .ok_or_else(|| CoreError::not_found(format!("{namespace}.{key} is not set")))?;
+            emit_string(value)
+        }
+        SettingCommand::Set {
+            namespace,
+            key,
+            value,
+        } => {
+            api::setting_set(&namespace, &key, &value)?;
+            emit_ok()
+        }
+        SettingCommand::Delete { namespace, key } => {
+            emit_json(&serde_json::json!({ "removed": api::setting_delete(&namespace, &key)? }))
+        }
+        SettingCommand::List { namespace } => emit_json(&api::settings(&namespace)?),
+    }
+}
+
 fn handle_tags(cmd: TagCommand) -> Result<()> {
     match cmd {
-        TagCommand::List => emit_json(&api::list_tags()),
+        TagCommand::List { status: false } => emit_json(&api::list_tags()),
+        TagCommand::List { status: true } => emit_json(&api::list_tags_with_status()),
         TagCommand::For { path } => emit_json(&api::tags_for(&path)?),
+        TagCommand::Prefix { prefix } => emit_json(&api::paths_with_tag_prefix(&prefix)),
+        TagCommand::Tree => emit_json(&api::tag_tree()),
+        TagCommand::Colors => emit_json(&api::list_tag_colors()),
         TagCommand::Add { path, tag, color } => {
-            api::set_tag(&path, &tag, Some(&color))?;
+            api::set_tag(&path, &tag, color.as_deref())?;
             emit_ok()
         }
-        TagCommand::Remove { path, tag } => {
+        TagCommand::Remove {
+            path,
+            tag,
+            dry_run: true,
+        } => {
+            let removed: Vec<_> = api::tags_for(&path)?
+                .into_iter()
+                .filter(|entry| entry.tag.eq_ignore_ascii_case(&tag))
+                .collect();
+            emit_dry_run(&removed)
+        }
+        TagCommand::Remove { path, tag, .. } => {
             api::remove_tag(&path, &tag)?;
             emit_ok()
         }
+        TagCommand::FinderImport {
+            path,
+            dry_run: true,
+        } => emit_dry_run(&api::import_finder_tags(path.as_deref(), true)?),
+        TagCommand::FinderImport {
+            path,
+            dry_run: false,
+        } => emit_json(&api::import_finder_tags(path.as_deref(), false)?),
+        TagCommand::FinderExport {
+            path,
+            dry_run: true,
+        } => emit_dry_run(&api::export_finder_tags(path.as_deref(), true)?),
+        TagCommand::FinderExport {
+            path,
+            dry_run: false,
+        } => emit_json(&api::export_finder_tags(path.as_deref(), false)?),
+        TagCommand::Auto {
+            tag,
+            glob,
+            marker,
+            color,
+            dry_run,
+        } => {
+            let rules: Vec<TagRule> = tag
+                .map(|tag| TagRule {
+                    tag,
+                    glob,
+                    marker,
+                    color,
+                })
+                .into_iter()
+                .collect();
+            emit_json(&api::auto_tag(&rules, dry_run)?)
+        }
     }
 }
 
@@ -172,31 +1628,168 @@ fn handle_profiles(cmd: ProfileCommand) -> Result<()> {
             working_dir,
             terminal,
             windows,
+            wsl_distro,
+            pre_command,
+            post_launch_command,
+            use_shell,
+            args,
         } => {
             let parsed_id = id.as_deref().map(Uuid::parse_str).transpose()?;
-            let profile =
-                api::save_profile(parsed_id, &name, command, working_dir, terminal, windows)?;
+            let mut profile = api::save_profile(
+                parsed_id,
+                &name,
+                command,
+                working_dir,
+                terminal,
+                windows,
+                wsl_distro,
+            )?;
+            if !args.is_empty() {
+                profile = api::set_profile_args(profile.id, args, use_shell)?;
+            }
+            if pre_command.is_some() || post_launch_command.is_some() {
+                profile = api::set_profile_hooks(
+                    profile.id,
+                    pre_command.or(profile.pre_command.clone()),
+                    post_launch_command.or(profile.post_launch_command.clone()),
+                )?;
+            }
             emit_json(&profile)
         }
-        ProfileCommand::Delete { id } => {
+        ProfileCommand::Launch {
+            id,
+            path,
+            background,
+        } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            let report = api::launch_profile(uuid, path.as_deref(), background)?;
+            emit_json(&report)?;
+            if !report.succeeded() {
+                anyhow::bail!("launch did not complete; see the failed phase");
+            }
+            Ok(())
+        }
+        ProfileCommand::Remote {
+            id,
+            host,
+            dir,
+            transport,
+        } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            emit_json(&api::set_profile_remote(uuid, host, dir, transport)?)
+        }
+        ProfileCommand::Kube {
+            id,
+            pod,
+            container,
+            namespace,
+            context,
+        } => {
             let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            let target = KubeTarget {
+                pod,
+                container,
+                namespace,
+                context,
+            };
+            emit_json(&api::set_profile_kube(uuid, target)?)
+        }
+        ProfileCommand::Autostart { id, off } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            emit_json(&api::set_autostart(uuid, !off)?)
+        }
+        ProfileCommand::Logs { id, last } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            let logs = api::launch_logs(uuid);
+            if !last {
+                return emit_json(&logs);
+            }
+            let newest = logs.first().ok_or_else(|| {
+                CoreError::not_found("no launch logs for this profile")
+                    .with_hint("start one with `profiles launch --background`")
+            })?;
+            let contents = std::fs::read(&newest.path)
+                .with_context(|| format!("failed to read {}", newest.path))?;
+            std::io::stdout().write_all(&contents)?;
+            Ok(())
+        }
+        ProfileCommand::Delete { id, dry_run } => {
+            let uuid = Uuid::parse_str(&id).context("invalid uuid")?;
+            if dry_run {
+                let profile = api::list_profiles()
+                    .into_iter()
+                    .find(|profile| profile.id == uuid)
+                    .ok_or_else(|| CoreError::not_found("profile not found"))?;
+                return emit_dry_run(&[profile]);
+            }
             api::delete_profile(uuid)?;
             emit_ok()
         }
     }
 }
 
-fn emit_ok() -> Result<()> {
-    emit_json(&serde_json::json!({"status": "ok"}))
+fn handle_sessions(cmd: SessionCommand) -> Result<()> {
+    match cmd {
+        SessionCommand::List => emit_json(&api::list_sessions()),
+        SessionCommand::Save { name, entries } => {
+            let entries = entries
+                .iter()
+                .map(|entry| parse_session_entry(entry))
+                .collect::<Result<Vec<_>>>()?;
+            emit_json(&api::save_session(&name, entries)?)
+        }
+        SessionCommand::Delete {
+            name,
+            dry_run: true,
+        } => {
+            let session = api::list_sessions()
+                .into_iter()
+                .find(|session| session.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| CoreError::not_found("session not found"))?;
+            emit_dry_run(&[session])
+        }
+        SessionCommand::Delete { name, .. } => {
+            api::delete_session(&name)?;
+            emit_ok()
+        }
+        SessionCommand::Restore {
+            name,
+            dry_run: true,
+        } => emit_json(&api::session_launches(&name)?),
+        SessionCommand::Restore {
+            name,
+            dry_run: false,
+        } => emit_json(&api::restore_session(&name)?),
+    }
 }
 
-fn emit_string(value: impl AsRef<str>) -> Result<()> {
-    println!("{}", value.as_ref());
-    Ok(())
+/// Reports what a destructive command would remove or change, without running it.
+fn emit_dry_run<T: serde::Serialize>(changes: &T) -> Result<()> {
+    emit_json(&serde_json::json!({ "dry_run": true, "changes": changes }))
 }
 
-fn emit_json<T: serde::Serialize>(value: &T) -> Result<()> {
-    let json = serde_json::to_string(value).context("serialize json output")?;
-    println!("{}", json);
-    Ok(())
+fn parse_session_entry(entry: &str) -> Result<SessionEntry> {
+    let Some((path, profile)) = entry.rsplit_once('=') else {
+        return Ok(SessionEntry {
+            path: entry.to_string(),
+            profile_id: None,
+        });
+    };
+    let profile = api::list_profiles()
+        .into_iter()
+        .find(|p| p.id.to_string() == profile || p.name.eq_ignore_ascii_case(profile))
+        .with_context(|| format!("unknown profile {profile:?}"))?;
+    Ok(SessionEntry {
+        path: path.to_string(),
+        profile_id: Some(profile.id),
+    })
+}
+
+fn handle_config(cmd: ConfigCommand) -> Result<()> {
+    match cmd {
+        ConfigCommand::Get { key: None } => emit_json(&api::get_config()?),
+        ConfigCommand::Get { key: Some(key) } => emit_json(&api::get_config_value(&key)?),
+        ConfigCommand::Set { key, value } => emit_json(&api::set_config(&key, &value)?),
+        ConfigCommand::Path => emit_string(api::config_path().display().to_string()),
+    }
 }
@@ -0,0 +1,314 @@
+use std::cell::RefCell;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use clap::ValueEnum;
+use comfy_table::{Attribute, Cell, Color, ContentArrangement, Table};
+use serde_json::{Map, Value};
+use term_core::{CoreError, ErrorCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One JSON document per command.
+    Json,
+    /// Lists print one JSON object per line.
+    Ndjson,
+    /// Aligned tables for people; the default when stdout is a terminal.
+    Table,
+}
+
+struct Output {
+    format: Format,
+    print0: bool,
+}
+
+static OUTPUT: OnceLock<Output> = OnceLock::new();
+
+thread_local! {
+    /// Set while a batch command runs; emitted values are collected here instead of printed.
+    static CAPTURED: RefCell<Option<Vec<Value>>> = const { RefCell::new(None) };
+}
+
+/// Without an explicit `--format`, terminals get tables and pipes (including the app) get JSON.
+pub fn init(format: Option<Format>, print0: bool) {
+    let format = format.unwrap_or(if std::io::stdout().is_terminal() {
+        Format::Table
+    } else {
+        Format::Json
+    });
+    OUTPUT.get_or_init(|| Output { format, print0 });
+}
+
+fn output() -> &'static Output {
+    OUTPUT.get_or_init(|| Output {
+        format: Format::Json,
+        print0: false,
+    })
+}
+
+/// Writes to stdout, treating a closed pipe (e.g. `| head`) as success.
+fn write_stdout(bytes: &[u8]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    match stdout.write_all(bytes).and_then(|_| stdout.flush()) {
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        other => other.context("write output"),
+    }
+}
+
+/// The path-like field of a list item: the item itself when it's a string, else its `path`.
+fn item_path(item: &Value) -> Option<&str> {
+    item.as_str()
+        .or_else(|| item.get("path").and_then(Value::as_str))
+}
+
+fn print0(value: &Value) -> Result<()> {
+    let items = match value {
+        Value::Array(items) => items.as_slice(),
+        single => std::slice::from_ref(single),
+    };
+    let mut buffer = Vec::new();
+    for item in items {
+        let path = item_path(item).context("--print0 needs a command that outputs paths")?;
+        buffer.extend_from_slice(path.as_bytes());
+        buffer.push(0);
+    }
+    write_stdout(&buffer)
+}
+
+fn colors_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+/// Timestamps are stored as Unix seconds; tables show them in local time.
+fn is_timestamp_key(key: &str) -> bool {
+    key.ends_with("_utc") || key == "mod_date"
+}
+
+fn format_cell(key: &str, value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(true) => String::from("yes"),
+        Value::Bool(false) => String::new(),
+        Value::Number(n) if is_timestamp_key(key) => n
+            .as_i64()
+            .and_then(|secs| Local.timestamp_opt(secs, 0).single())
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| n.to_string()),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.iter().all(|item| !item.is_object()) => items
+            .iter()
+            .map(|item| format_cell(key, item))
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn cell(key: &str, value: &Value, colors: bool) -> Cell {
+    let cell = Cell::new(format_cell(key, value));
+    if !colors {
+        return cell;
+    }
+    match key {
+        "path" | "root" => cell.fg(Color::Cyan),
+        "name" | "tag" => cell.add_attribute(Attribute::Bold),
+        "color" => cell.fg(Color::DarkGrey),
+        _ => cell,
+    }
+}
+
+/// No borders, just a rule under the header.
+const PRESET: &str = "     ──            ";
+
+fn new_table(headers: &[String], colors: bool) -> Table {
+    let mut table = Table::new();
+    table.load_preset(PRESET);
+    // Wrap to the terminal when its width is known; pseudo-terminals sometimes report zero.
+    if table.width().is_some_and(|width| width >= 40) {
+        table.set_content_arrangement(ContentArrangement::Dynamic);
+    }
+    table.set_header(headers.iter().map(|header| {
+        let cell = Cell::new(header.replace('_', " "));
+        if colors {
+            cell.add_attribute(Attribute::Bold)
+        } else {
+            cell
+        }
+    }));
+    table
+}
+
+/// Lists become one row per item with a column per field (in first-seen order); a single
+/// object becomes a field/value table; bare strings become a one-column list.
+fn render_table(value: &Value) -> String {
+    let colors = colors_enabled();
+    match value {
+        Value::Array(items) if items.is_empty() => String::from("(none)\n"),
+        Value::Array(items) if items.iter().all(Value::is_object) => {
+            let mut headers: Vec<String> = Vec::new();
+            for item in items.iter().filter_map(Value::as_object) {
+                for key in item.keys() {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+            let mut table = new_table(&headers, colors);
+            for item in items.iter().filter_map(Value::as_object) {
+                table.add_row(
+                    headers
+                        .iter()
+                        .map(|key| cell(key, item.get(key).unwrap_or(&Value::Null), colors)),
+                );
+            }
+            format!("{table}\n")
+        }
+        Value::Array(items) => {
+            let mut table = new_table(&[String::from("path")], colors);
+            for item in items {
+                table.add_row([cell("path", item, colors)]);
+            }
+            format!("{table}\n")
+        }
+        Value::Object(fields) => render_fields(fields, colors),
+        other => format!("{}\n", format_cell("", other)),
+    }
+}
+
+fn is_record_list(value: &Value) -> bool {
+    matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object))
+}
+
+/// Scalar fields go in a field/value table; lists of objects (e.g. a report's checks)
+/// follow as their own tables under the field name.
+fn render_fields(fields: &Map<String, Value>, colors: bool) -> String {
+    let mut table = new_table(&[String::from("field"), String::from("value")], colors);
+    let mut nested = String::new();
+    for (key, value) in fields {
+        if is_record_list(value) {
+            nested.push_str(&format!(
+                "\n{}:\n{}",
+                key.replace('_', " "),
+                render_table(value)
+            ));
+        } else {
+            table.add_row([Cell::new(key.replace('_', " ")), cell(key, value, colors)]);
+        }
+    }
+    format!("{table}\n{nested}")
+}
+
+/// Runs `run` with output captured, returning what it emitted: `null` for nothing, the value
+/// itself for a single emit, or an array when it emitted several times.
+pub fn capture(run: impl FnOnce() -> Result<()>) -> Result<Value> {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    let result = run();
+    let mut values = CAPTURED
+        .with(|captured| captured.borrow_mut().take())
+        .unwrap_or_default();
+    result?;
+    Ok(match values.len() {
+        0 => Value::Null,
+        1 => values.remove(0),
+        _ => Value::Array(values),
+    })
+}
+
+/// Stores `value` if a capture is active; otherwise hands it back for printing.
+fn captured(value: Value) -> Option<Value> {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(values) => {
+            values.push(value);
+            None
+        }
+        None => Some(value),
+    })
+}
+
+/// The structured form of a failure, as printed by [`emit_error`] and batch results.
+pub fn error_body(err: &anyhow::Error) -> CoreError {
+    CoreError {
+        code: term_core::error_code(err),
+        message: format!("{err:#}"),
+        hint: term_core::error_hint(err),
+    }
+}
+
+/// Writes one JSON value followed by a newline, regardless of `--format`.
+pub fn emit_line(value: &Value) -> Result<()> {
+    write_stdout(format!("{}\n", serde_json::to_string(value)?).as_bytes())
+}
+
+/// Writes plain text records for other tools, ignoring `--format`. Records end in a newline,
+/// or a NUL with `--print0`; captured (batch) output gets them as an array of strings.
+pub fn emit_records(records: &[String]) -> Result<()> {
+    if captured(Value::from(records.to_vec())).is_none() {
+        return Ok(());
+    }
+    let terminator = if output().print0 { '\0' } else { '\n' };
+    let mut text = String::new();
+    for record in records {
+        text.push_str(record);
+        text.push(terminator);
+    }
+    write_stdout(text.as_bytes())
+}
+
+/// Reports a failed command on stderr and returns its category. JSON output gets a JSON
+/// object so scripts and the app can branch on `code`; tables get a plain message.
+pub fn emit_error(err: &anyhow::Error) -> ErrorCode {
+    let error = error_body(err);
+    let code = error.code;
+    let text = match output().format {
+        Format::Json | Format::Ndjson => serde_json::to_string(&error).unwrap_or_default(),
+        Format::Table => match error.hint {
+            Some(hint) => format!("error: {}\nhint: {hint}", error.message),
+            None => format!("error: {}", error.message),
+        },
+    };
+    eprintln!("{text}");
+    code
+}
+
+pub fn emit_ok() -> Result<()> {
+    emit_json(&serde_json::json!({"status": "ok"}))
+}
+
+pub fn emit_string(value: impl AsRef<str>) -> Result<()> {
+    if captured(Value::String(value.as_ref().to_string())).is_none() {
+        return Ok(());
+    }
+    if output().print0 {
+        return print0(&Value::String(value.as_ref().to_string()));
+    }
+    write_stdout(format!("{}\n", value.as_ref()).as_bytes())
+}
+
+pub fn emit_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    let value = serde_json::to_value(value).context("serialize json output")?;
+    let Some(value) = captured(value) else {
+        return Ok(());
+    };
+    let output = output();
+    if output.print0 {
+        return print0(&value);
+    }
+    let mut text = String::new();
+    match (output.format, &value) {
+        (Format::Table, value) => text = render_table(value),
+        (Format::Ndjson, Value::Array(items)) => {
+            for item in items {
+                text.push_str(&serde_json::to_string(item)?);
+                text.push('\n');
+            }
+        }
+        _ => {
+            text.push_str(&serde_json::to_string(&value)?);
+            text.push('\n');
+        }
+    }
+    write_stdout(text.as_bytes())
+}
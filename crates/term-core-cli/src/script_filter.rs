@@ -0,0 +1,123 @@
+//! Result lists for launcher extensions, so a Raycast command or an Alfred workflow only has
+//! to run the CLI and hand its output over:
+//!
+//! ```sh
+//! term-core-cli alfred search "{query}" --tag-icons ./icons
+//! ```
+//!
+//! Both list the same candidates as `fzf-feed`, narrowed to those matching the query.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::{json, Value};
+use term_core::{api, MatchOptions};
+
+use crate::fzf::{self, Candidate};
+use crate::output::emit_json;
+
+/// Ranked candidates; with a query, only search hits and stored paths that fuzzy-match it.
+fn matching(query: Option<&str>, start: &str, limit: usize) -> Result<Vec<(String, Candidate)>> {
+    let query = query.map(str::trim).filter(|query| !query.is_empty());
+    let mut ranked = fzf::ranked(query, start, limit)?;
+    if let Some(query) = query {
+        let paths: Vec<String> = ranked.iter().map(|(path, _)| path.clone()).collect();
+        let fuzzy: HashSet<String> = api::fuzzy_filter(query, &paths, &MatchOptions::default())
+            .into_iter()
+            .map(|found| found.text)
+            .collect();
+        ranked.retain(|(path, candidate)| candidate.matched || fuzzy.contains(path));
+    }
+    ranked.truncate(limit);
+    Ok(ranked)
+}
+
+fn title(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn subtitle(path: &str) -> String {
+    api::display_path(path, None).unwrap_or_else(|_| path.to_string())
+}
+
+/// Raycast list items: a folder icon tinted with the first tag's color, tags as colored
+/// accessories, and the path as `arg`.
+pub fn raycast(query: Option<&str>, start: &str, limit: usize) -> Result<()> {
+    let items: Vec<Value> = matching(query, start, limit)?
+        .into_iter()
+        .map(|(path, candidate)| {
+            let mut icon = json!({ "source": "folder-16" });
+            if let Some(tag) = candidate.tags.first() {
+                icon["tintColor"] = json!(tag.color);
+            }
+            let mut accessories: Vec<Value> = candidate
+                .tags
+                .iter()
+                .map(|tag| json!({ "tag": { "value": tag.tag, "color": tag.color } }))
+                .collect();
+            if let Some(opened) = candidate.opened_utc {
+                accessories.push(json!({ "date": opened * 1000, "tooltip": "Last opened" }));
+            }
+            json!({
+                "id": path,
+                "title": title(&path),
+                "subtitle": subtitle(&path),
+                "arg": path,
+                "icon": icon,
+                "accessories": accessories,
+                "keywords": candidate.tags.iter().map(|tag| &tag.tag).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    emit_json(&json!({ "items": items }))
+}
+
+/// Alfred Script Filter items. Alfred only takes image files as icons, so a tagged path uses
+/// `<tag_icons>/<tag>.png` when that file exists; everything else gets its own file icon.
+pub fn alfred(
+    query: Option<&str>,
+    start: &str,
+    limit: usize,
+    tag_icons: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let items: Vec<Value> = matching(query, start, limit)?
+        .into_iter()
+        .map(|(path, candidate)| {
+            let tag_icon = tag_icons.and_then(|dir| {
+                candidate
+                    .tags
+                    .iter()
+                    .map(|tag| Path::new(dir).join(format!("{}.png", tag.tag)))
+                    .find(|icon| icon.is_file())
+            });
+            let icon = match tag_icon {
+                Some(icon) => json!({ "path": icon.display().to_string() }),
+                None => json!({ "type": "fileicon", "path": path }),
+            };
+            let tags: Vec<&str> = candidate.tags.iter().map(|tag| tag.tag.as_str()).collect();
+            let notes = candidate.annotations(now);
+            let details = match notes.is_empty() {
+                true => subtitle(&path),
+                false => format!("{}  ·  {notes}", subtitle(&path)),
+            };
+            json!({
+                "uid": path,
+                "type": "file:skipcheck",
+                "title": title(&path),
+                "subtitle": details,
+                "arg": path,
+                "autocomplete": title(&path),
+                "match": format!("{} {}", title(&path), tags.join(" ")),
+                "icon": icon,
+                "text": { "copy": path, "largetype": path },
+            })
+        })
+        .collect();
+    emit_json(&json!({ "items": items }))
+}
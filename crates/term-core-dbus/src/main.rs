@@ -0,0 +1,16 @@
+//! Registers `org.terminaut.Core` on the session bus. Only Linux desktops are served; the
+//! binary exits with an error elsewhere.
+
+#[cfg(target_os = "linux")]
+mod service;
+
+#[cfg(target_os = "linux")]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    service::serve().await
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() -> anyhow::Result<()> {
+    anyhow::bail!("terminaut-dbus is only supported on Linux")
+}
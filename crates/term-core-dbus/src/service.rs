@@ -0,0 +1,127 @@
+use anyhow::Context;
+use term_core::{api, LaunchRequest, SearchOptions};
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+
+pub const BUS_NAME: &str = "org.terminaut.Core";
+pub const OBJECT_PATH: &str = "/org/terminaut/Core";
+
+fn failed(err: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(format!("{err:#}"))
+}
+
+/// Store and filesystem calls run on the blocking pool so one slow volume can't stall the bus.
+async fn blocking<T, F>(f: F) -> fdo::Result<T>
+where
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|err| fdo::Error::Failed(err.to_string()))?
+        .map_err(failed)
+}
+
+pub struct Core;
+
+#[zbus::interface(name = "org.terminaut.Core1")]
+impl Core {
+    /// Opens a terminal at `path`. An empty `terminal` uses the configured default.
+    async fn open(&self, path: String, terminal: String) -> fdo::Result<()> {
+        blocking(move || {
+            api::touch_recent(&path)?;
+            api::launch(&LaunchRequest {
+                path,
+                terminal: (!terminal.is_empty()).then_some(terminal),
+                command: None,
+                args: Vec::new(),
+                use_shell: false,
+                pre_command: None,
+                post_launch_command: None,
+                background: false,
+                profile: None,
+                profile_id: None,
+                windows: 1,
+                env: Default::default(),
+                wsl_distro: None,
+            })
+        })
+        .await
+    }
+
+    /// Returns `(path, name, rank)` for the best matches below `path`.
+    async fn search(
+        &self,
+        path: String,
+        query: String,
+        limit: u32,
+    ) -> fdo::Result<Vec<(String, String, f64)>> {
+        blocking(move || {
+            let options = SearchOptions {
+                record_history: false,
+                ..SearchOptions::default()
+            };
+            Ok(api::search(&path, &query, limit.max(1) as usize, &options)?
+                .into_iter()
+                .map(|result| (result.path, result.name, result.rank.total))
+                .collect())
+        })
+        .await
+    }
+
+    async fn list_favorites(&self) -> Vec<String> {
+        api::list_favorites()
+    }
+
+    async fn add_favorite(
+        &self,
+        path: String,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        blocking(move || api::add_favorite(&path)).await?;
+        Self::favorites_changed(&emitter).await?;
+        self.generation_changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn remove_favorite(
+        &self,
+        path: String,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        blocking(move || api::remove_favorite(&path)).await?;
+        Self::favorites_changed(&emitter).await?;
+        self.generation_changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Returns `(path, last_opened_utc)`, most recent first.
+    async fn list_recents(&self) -> Vec<(String, i64)> {
+        api::list_recents()
+            .into_iter()
+            .map(|entry| (entry.path, entry.last_opened_utc))
+            .collect()
+    }
+
+    /// The store generation, which grows with every saved change; see
+    /// `api::store_generation`. Announced through `PropertiesChanged` after each mutation.
+    #[zbus(property)]
+    async fn generation(&self) -> u64 {
+        api::store_generation()
+    }
+
+    #[zbus(signal)]
+    async fn favorites_changed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+pub async fn serve() -> anyhow::Result<()> {
+    let _connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Core)?
+        .build()
+        .await
+        .context("register org.terminaut.Core on the session bus")?;
+    eprintln!("terminaut-dbus serving {BUS_NAME} at {OBJECT_PATH}");
+    std::future::pending::<()>().await;
+    Ok(())
+}
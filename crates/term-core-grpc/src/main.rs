@@ -0,0 +1,67 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+
+mod service;
+
+pub mod proto {
+    tonic::include_proto!("terminaut.v1");
+}
+
+/// Serves term-core over gRPC so other machines and companion apps can query and launch.
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+    /// Require `authorization: Bearer <token>` on every call.
+    #[arg(long, env = "TERMINAUT_GRPC_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// PEM certificate chain; enables TLS together with `--tls-key`.
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// Allow a non-loopback `--listen` address without `--token`. Anyone who can reach it can
+    /// launch commands as you.
+    #[arg(long)]
+    insecure: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    if !cli.listen.ip().is_loopback() && cli.token.is_none() {
+        if !cli.insecure {
+            anyhow::bail!(
+                "refusing to listen on {} without --token: anyone on the network could run commands; pass --insecure to do it anyway",
+                cli.listen
+            );
+        }
+        eprintln!(
+            "warning: listening on {} without --token; anyone on the network can control Terminaut",
+            cli.listen
+        );
+    }
+
+    let mut server = Server::builder();
+    if let (Some(cert), Some(key)) = (&cli.tls_cert, &cli.tls_key) {
+        let cert = std::fs::read(cert).with_context(|| format!("read {}", cert.display()))?;
+        let key = std::fs::read(key).with_context(|| format!("read {}", key.display()))?;
+        server = server
+            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+            .context("configure TLS")?;
+    }
+
+    let auth = service::TokenAuth { token: cli.token };
+    let core = proto::core_server::CoreServer::with_interceptor(service::CoreService, auth);
+    eprintln!("terminaut-grpc listening on {}", cli.listen);
+    server
+        .add_service(core)
+        .serve(cli.listen)
+        .await
+        .context("serve gRPC")
+}
@@ -0,0 +1,407 @@
+use term_core::{api, error_code, error_hint, ErrorCode, SearchOptions};
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Request, Response, Status};
+
+use crate::proto::core_server::Core;
+use crate::proto::*;
+
+/// The gRPC status for an error of category [`error_code`], with its hint appended.
+fn status(err: anyhow::Error) -> Status {
+    let code = match error_code(&err) {
+        ErrorCode::NotFound => Code::NotFound,
+        ErrorCode::InvalidInput => Code::InvalidArgument,
+        ErrorCode::Io => Code::Unavailable,
+        ErrorCode::StoreCorrupt => Code::DataLoss,
+        ErrorCode::Conflict => Code::Aborted,
+        ErrorCode::Internal => Code::Internal,
+    };
+    let message = match error_hint(&err) {
+        Some(hint) => format!("{err:#} (hint: {hint})"),
+        None => format!("{err:#}"),
+    };
+    Status::new(code, message)
+}
+
+/// Response metadata carrying the store generation after the call; see
+/// [`api::store_generation`].
+pub const GENERATION_HEADER: &str = "x-terminaut-generation";
+
+/// Runs a store or filesystem call off the async executor. Replies and errors both carry the
+/// store generation in [`GENERATION_HEADER`].
+async fn blocking<T, F>(f: F) -> Result<Response<T>, Status>
+where
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (result, generation) = tokio::task::spawn_blocking(|| {
+        let result = f();
+        (result, api::store_generation())
+    })
+    .await
+    .map_err(|err| Status::internal(err.to_string()))?;
+    let generation = MetadataValue::from(generation);
+    match result {
+        Ok(reply) => {
+            let mut response = Response::new(reply);
+            response
+                .metadata_mut()
+                .insert(GENERATION_HEADER, generation);
+            Ok(response)
+        }
+        Err(err) => {
+            let mut status = status(err);
+            status.metadata_mut().insert(GENERATION_HEADER, generation);
+            Err(status)
+        }
+    }
+}
+
+impl From<term_core::LaunchRequest> for LaunchRequest {
+    fn from(request: term_core::LaunchRequest) -> Self {
+        Self {
+            path: request.path,
+            terminal: request.terminal,
+            command: request.command,
+            args: request.args,
+            use_shell: request.use_shell,
+            pre_command: request.pre_command,
+            post_launch_command: request.post_launch_command,
+            background: request.background,
+            windows: request.windows.into(),
+            env: request.env.into_iter().collect(),
+            profile: request.profile,
+            profile_id: request.profile_id,
+            wsl_distro: request.wsl_distro,
+        }
+    }
+}
+
+impl From<LaunchRequest> for term_core::LaunchRequest {
+    fn from(request: LaunchRequest) -> Self {
+        Self {
+            path: request.path,
+            terminal: request.terminal,
+            command: request.command,
+            args: request.args,
+            use_shell: request.use_shell,
+            pre_command: request.pre_command,
+            post_launch_command: request.post_launch_command,
+            background: request.background,
+            profile: request.profile,
+            profile_id: request.profile_id,
+            windows: request.windows.clamp(1, u8::MAX.into()) as u8,
+            env: request.env.into_iter().collect(),
+            wsl_distro: request.wsl_distro,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CoreService;
+
+#[tonic::async_trait]
+impl Core for CoreService {
+    async fn normalize_path(
+        &self,
+        request: Request<PathRequest>,
+    ) -> Result<Response<PathReply>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || {
+            Ok(PathReply {
+                path: api::normalize_path(&path)?,
+            })
+        })
+        .await
+    }
+
+    async fn list_directory(
+        &self,
+        request: Request<PathRequest>,
+    ) -> Result<Response<DirectoryListing>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || {
+            let entries = api::list_directory(&path)?
+                .into_iter()
+                .map(|entry| DirectoryEntry {
+                    name: entry.name,
+                    path: entry.path,
+                    is_dir: entry.is_dir,
+                    mod_date: entry.mod_date,
+                    git_status: entry.git_status.map(|status| status.as_str().to_string()),
+                })
+                .collect();
+            Ok(DirectoryListing { entries })
+        })
+        .await
+    }
+
+    async fn detect_projects(
+        &self,
+        request: Request<PathRequest>,
+    ) -> Result<Response<ProjectList>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || {
+            let projects = api::detect_projects(&path)?
+                .into_iter()
+                .map(|project| Project {
+                    path: project.path,
+                    marker: project.marker,
+                })
+                .collect();
+            Ok(ProjectList { projects })
+        })
+        .await
+    }
+
+    async fn list_favorites(&self, _: Request<Empty>) -> Result<Response<PathList>, Status> {
+        blocking(|| {
+            Ok(PathList {
+                paths: api::list_favorites(),
+            })
+        })
+        .await
+    }
+
+    async fn add_favorite(&self, request: Request<PathRequest>) -> Result<Response<Empty>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || api::add_favorite(&path).map(|_| Empty {})).await
+    }
+
+    async fn remove_favorite(
+        &self,
+        request: Request<PathRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || api::remove_favorite(&path).map(|_| Empty {})).await
+    }
+
+    async fn list_recents(&self, _: Request<Empty>) -> Result<Response<RecentList>, Status> {
+        blocking(|| {
+            let recents = api::list_recents()
+                .into_iter()
+                .map(|entry| RecentEntry {
+                    path: entry.path,
+                    last_opened_utc: entry.last_opened_utc,
+                    sources: entry.sources.into_iter().collect(),
+                    visits: entry.visits,
+                    score: entry.score,
+                })
+                .collect();
+            Ok(RecentList { recents })
+        })
+        .await
+    }
+
+    async fn touch_recent(&self, request: Request<PathRequest>) -> Result<Response<Empty>, Status> {
+        let path = request.into_inner().path;
+        blocking(move || api::touch_recent(&path).map(|_| Empty {})).await
+    }
+
+    async fn list_tags(&self, _: Request<Empty>) -> Result<Response<TagList>, Status> {
+        blocking(|| {
+            let tags = api::list_tags()
+                .into_iter()
+                .map(|tag| Tag {
+                    path: tag.path,
+                    tag: tag.tag,
+                    color: tag.color,
+                })
+                .collect();
+            Ok(TagList { tags })
+        })
+        .await
+    }
+
+    async fn set_tag(&self, request: Request<TagRequest>) -> Result<Response<Empty>, Status> {
+        let request = request.into_inner();
+        blocking(move || {
+            api::set_tag(&request.path, &request.tag, request.color.as_deref()).map(|_| Empty {})
+        })
+        .await
+    }
+
+    async fn remove_tag(&self, request: Request<TagRequest>) -> Result<Response<Empty>, Status> {
+        let request = request.into_inner();
+        blocking(move || api::remove_tag(&request.path, &request.tag).map(|_| Empty {})).await
+    }
+
+    async fn list_profiles(&self, _: Request<Empty>) -> Result<Response<ProfileList>, Status> {
+        blocking(|| {
+            let profiles = api::list_profiles()
+                .into_iter()
+                .map(|profile| Profile {
+                    id: profile.id.to_string(),
+                    name: profile.name,
+                    command: profile.command,
+                    working_dir: profile.working_dir,
+                    terminal: profile.terminal,
+                    pre_command: profile.pre_command,
+                    post_launch_command: profile.post_launch_command,
+                    autostart: profile.autostart,
+                    ssh_host: profile.ssh_host,
+                    ssh_dir: profile.ssh_dir,
+                    transport: profile.transport.as_str().to_string(),
+                    kube_pod: profile.kube_pod,
+                    kube_container: profile.kube_container,
+                    kube_namespace: profile.kube_namespace,
+                    kube_context: profile.kube_context,
+                    windows: profile.windows.into(),
+                    wsl_distro: profile.wsl_distro,
+                    args: profile.args,
+                    use_shell: profile.use_shell,
+                })
+                .collect();
+            Ok(ProfileList { profiles })
+        })
+        .await
+    }
+
+    async fn list_sessions(&self, _: Request<Empty>) -> Result<Response<SessionList>, Status> {
+        blocking(|| {
+            let sessions = api::list_sessions()
+                .into_iter()
+                .map(|session| Session {
+                    name: session.name,
+                    entries: session
+                        .entries
+                        .into_iter()
+                        .map(|entry| SessionEntry {
+                            path: entry.path,
+                            profile_id: entry.profile_id.map(|id| id.to_string()),
+                        })
+                        .collect(),
+                    saved_utc: session.saved_utc,
+                })
+                .collect();
+            Ok(SessionList { sessions })
+        })
+        .await
+    }
+
+    async fn restore_session(
+        &self,
+        request: Request<NameRequest>,
+    ) -> Result<Response<RestoreReply>, Status> {
+        let name = request.into_inner().name;
+        blocking(move || {
+            let report = api::restore_session(&name)?;
+            Ok(RestoreReply {
+                launched: report.launched.into_iter().map(Into::into).collect(),
+                failed: report
+                    .failed
+                    .into_iter()
+                    .map(|failure| LaunchFailure {
+                        path: failure.path,
+                        error: failure.error,
+                    })
+                    .collect(),
+            })
+        })
+        .await
+    }
+
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchReply>, Status> {
+        let request = request.into_inner();
+        blocking(move || {
+            // Remote queries are not the user's own, so they stay out of the search history.
+            let mut options = SearchOptions {
+                record_history: false,
+                ..SearchOptions::default()
+            };
+            if request.max_depth > 0 {
+                options.max_depth = Some(request.max_depth as usize);
+            }
+            let limit = if request.limit == 0 {
+                20
+            } else {
+                request.limit
+            };
+            let results = api::search(&request.path, &request.query, limit as usize, &options)?
+                .into_iter()
+                .map(|result| SearchResult {
+                    path: result.path,
+                    name: result.name,
+                    relative_path: result.relative_path,
+                    score: result.score,
+                    indices: result.indices.into_iter().map(|i| i as u32).collect(),
+                    rank: result.rank.total,
+                    pinned: result.rank.pinned,
+                    explanation: result.rank.explanation,
+                    matched_text: result.matched_text,
+                })
+                .collect();
+            Ok(SearchReply { results })
+        })
+        .await
+    }
+
+    async fn launch(&self, request: Request<LaunchRequest>) -> Result<Response<Empty>, Status> {
+        let request: term_core::LaunchRequest = request.into_inner().into();
+        blocking(move || api::launch(&request).map(|_| Empty {})).await
+    }
+}
+
+/// Rejects requests whose `authorization` metadata isn't `Bearer <token>`.
+#[derive(Clone)]
+pub struct TokenAuth {
+    pub token: Option<String>,
+}
+
+impl tonic::service::Interceptor for TokenAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(expected) = &self.token else {
+            return Ok(request);
+        };
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match provided {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(request),
+            _ => Err(Status::unauthenticated("missing or invalid token")),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::service::Interceptor;
+
+    use super::*;
+
+    #[test]
+    fn token_auth_checks_bearer_header() {
+        let mut auth = TokenAuth {
+            token: Some(String::from("s3cret")),
+        };
+        assert!(auth.call(Request::new(())).is_err());
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer s3cret".parse().unwrap());
+        assert!(auth.call(request).is_ok());
+        assert!(TokenAuth { token: None }.call(Request::new(())).is_ok());
+    }
+
+    #[test]
+    fn errors_keep_their_category() {
+        let missing = status(term_core::CoreError::not_found("profile not found").into());
+        assert_eq!(missing.code(), Code::NotFound);
+        let conflict = status(
+            term_core::CoreError::conflict("stale")
+                .with_hint("reload")
+                .into(),
+        );
+        assert_eq!(conflict.code(), Code::Aborted);
+        assert_eq!(conflict.message(), "stale (hint: reload)");
+        assert_eq!(status(anyhow::anyhow!("boom")).code(), Code::Internal);
+    }
+}
@@ -0,0 +1,344 @@
+//! Node.js bindings over `term_core::api`. Every function is async: the core call runs on a
+//! blocking thread and the promise resolves to plain JS objects shaped like the CLI's JSON.
+//! Build with `npx napi build --platform --release` from this directory.
+//!
+//! Failures reject with an `Error` whose message starts with the core error code, e.g.
+//! `not_found: session not found`, so callers can branch without parsing prose.
+
+use napi::bindgen_prelude::spawn_blocking;
+use napi::{Error, Status};
+use napi_derive::napi;
+use serde::Serialize;
+use serde_json::Value;
+use term_core::{api, ErrorCode, GrepOptions, LaunchRequest, SearchOptions, SessionEntry};
+
+fn to_napi(err: anyhow::Error) -> Error {
+    let status = match term_core::error_code(&err) {
+        ErrorCode::InvalidInput => Status::InvalidArg,
+        _ => Status::GenericFailure,
+    };
+    let code = serde_json::to_value(term_core::error_code(&err))
+        .ok()
+        .and_then(|code| code.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let mut reason = format!("{code}: {err:#}");
+    if let Some(hint) = term_core::error_hint(&err) {
+        reason.push_str(&format!(" (hint: {hint})"));
+    }
+    Error::new(status, reason)
+}
+
+/// Runs a core call off the JS thread and converts its result to JSON.
+async fn run<T: Serialize>(
+    call: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> napi::Result<Value> {
+    spawn_blocking(move || {
+        call().and_then(|value| serde_json::to_value(value).map_err(anyhow::Error::from))
+    })
+    .await
+    .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?
+    .map_err(to_napi)
+}
+
+/// Deserializes an optional options object, falling back to the defaults.
+fn options<T: serde::de::DeserializeOwned + Default>(value: Option<Value>) -> anyhow::Result<T> {
+    match value {
+        Some(Value::Null) | None => Ok(T::default()),
+        Some(value) => Ok(serde_json::from_value(value)?),
+    }
+}
+
+#[napi]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+#[napi]
+pub async fn capabilities() -> napi::Result<Value> {
+    run(|| Ok(api::capabilities())).await
+}
+
+/// Calls any api function by name with an object of named parameters.
+#[napi]
+pub async fn invoke(method: String, params: Option<Value>) -> napi::Result<Value> {
+    run(move || api::invoke(&method, params.unwrap_or(Value::Null))).await
+}
+
+#[napi]
+pub async fn normalize_path(path: String) -> napi::Result<Value> {
+    run(move || api::normalize_path(&path)).await
+}
+
+#[napi]
+pub async fn list_directory(path: String) -> napi::Result<Value> {
+    run(move || api::list_directory(&path)).await
+}
+
+#[napi]
+pub async fn list_roots() -> napi::Result<Value> {
+    run(api::list_roots).await
+}
+
+#[napi]
+pub async fn ancestors(path: String) -> napi::Result<Value> {
+    run(move || api::ancestors(&path)).await
+}
+
+#[napi]
+pub async fn list_favorites(with_status: Option<bool>) -> napi::Result<Value> {
+    if with_status.unwrap_or(false) {
+        run(|| Ok(api::list_favorites_with_status())).await
+    } else {
+        run(|| Ok(api::list_favorites())).await
+    }
+}
+
+#[napi]
+pub async fn add_favorite(path: String) -> napi::Result<Value> {
+    run(move || api::add_favorite(&path)).await
+}
+
+#[napi]
+pub async fn remove_favorite(path: String) -> napi::Result<Value> {
+    run(move || api::remove_favorite(&path)).await
+}
+
+#[napi]
+pub async fn list_recents(with_status: Option<bool>) -> napi::Result<Value> {
+    if with_status.unwrap_or(false) {
+        run(|| Ok(api::list_recents_with_status())).await
+    } else {
+        run(|| Ok(api::list_recents())).await
+    }
+}
+
+#[napi]
+pub async fn touch_recent(path: String) -> napi::Result<Value> {
+    run(move || api::touch_recent(&path)).await
+}
+
+#[napi]
+pub async fn detect_projects(path: String) -> napi::Result<Value> {
+    run(move || api::detect_projects(&path)).await
+}
+
+#[napi]
+pub async fn project_info(path: String) -> napi::Result<Value> {
+    run(move || api::project_info(&path)).await
+}
+
+#[napi]
+pub async fn get_config(key: Option<String>) -> napi::Result<Value> {
+    match key {
+        Some(key) => run(move || api::get_config_value(&key)).await,
+        None => run(api::get_config).await,
+    }
+}
+
+/// `value` uses TOML syntax; bare words are strings.
+#[napi]
+pub async fn set_config(key: String, value: String) -> napi::Result<Value> {
+    run(move || api::set_config(&key, &value)).await
+}
+
+#[napi]
+pub async fn list_tags(with_status: Option<bool>) -> napi::Result<Value> {
+    if with_status.unwrap_or(false) {
+        run(|| Ok(api::list_tags_with_status())).await
+    } else {
+        run(|| Ok(api::list_tags())).await
+    }
+}
+
+#[napi]
+pub async fn set_tag(path: String, tag: String, color: Option<String>) -> napi::Result<Value> {
+    run(move || api::set_tag(&path, &tag, color.as_deref())).await
+}
+
+#[napi]
+pub async fn remove_tag(path: String, tag: String) -> napi::Result<Value> {
+    run(move || api::remove_tag(&path, &tag)).await
+}
+
+#[napi]
+pub async fn tags_for(path: String) -> napi::Result<Value> {
+    run(move || api::tags_for(&path)).await
+}
+
+#[napi]
+pub async fn setting_get(namespace: String, key: String) -> napi::Result<Value> {
+    run(move || api::setting_get(&namespace, &key)).await
+}
+
+#[napi]
+pub async fn setting_set(namespace: String, key: String, value: String) -> napi::Result<Value> {
+    run(move || api::setting_set(&namespace, &key, &value)).await
+}
+
+#[napi]
+pub async fn setting_delete(namespace: String, key: String) -> napi::Result<Value> {
+    run(move || api::setting_delete(&namespace, &key)).await
+}
+
+#[napi]
+pub async fn prune_state() -> napi::Result<Value> {
+    run(api::prune_state).await
+}
+
+#[napi]
+pub async fn list_profiles() -> napi::Result<Value> {
+    run(|| Ok(api::list_profiles())).await
+}
+
+#[napi]
+pub async fn launch(request: Value) -> napi::Result<Value> {
+    run(move || {
+        let request: LaunchRequest = serde_json::from_value(request)?;
+        api::launch(&request)
+    })
+    .await
+}
+
+#[napi]
+pub async fn handle_url(url: String, confirmed: Option<bool>) -> napi::Result<Value> {
+    run(move || api::handle_url(&url, confirmed.unwrap_or(false))).await
+}
+
+#[napi]
+pub async fn list_sessions() -> napi::Result<Value> {
+    run(|| Ok(api::list_sessions())).await
+}
+
+/// `entries` is an array of `{path, profile_id?}` objects.
+#[napi]
+pub async fn save_session(name: String, entries: Value) -> napi::Result<Value> {
+    run(move || {
+        let entries: Vec<SessionEntry> = serde_json::from_value(entries)?;
+        api::save_session(&name, entries)
+    })
+    .await
+}
+
+#[napi]
+pub async fn delete_session(name: String) -> napi::Result<Value> {
+    run(move || api::delete_session(&name)).await
+}
+
+#[napi]
+pub async fn restore_session(name: String) -> napi::Result<Value> {
+    run(move || api::restore_session(&name)).await
+}
+
+#[napi]
+pub async fn list_saved_searches() -> napi::Result<Value> {
+    run(|| Ok(api::list_saved_searches())).await
+}
+
+#[napi]
+pub async fn run_saved_search(name: String) -> napi::Result<Value> {
+    run(move || api::run_saved_search(&name)).await
+}
+
+#[napi]
+pub async fn create_dir(path: String) -> napi::Result<Value> {
+    run(move || api::create_dir(&path)).await
+}
+
+#[napi]
+pub async fn rename(path: String, new_name: String) -> napi::Result<Value> {
+    run(move || api::rename(&path, &new_name)).await
+}
+
+#[napi]
+pub async fn move_path(path: String, destination: String) -> napi::Result<Value> {
+    run(move || api::move_path(&path, &destination)).await
+}
+
+#[napi]
+pub async fn trash(path: String) -> napi::Result<Value> {
+    run(move || api::trash(&path)).await
+}
+
+/// `options` is a partial `SearchOptions` object.
+#[napi]
+pub async fn search(
+    path: String,
+    query: String,
+    limit: Option<u32>,
+    options: Option<Value>,
+) -> napi::Result<Value> {
+    run(move || {
+        let options: SearchOptions = self::options(options)?;
+        api::search(&path, &query, limit.unwrap_or(20) as usize, &options)
+    })
+    .await
+}
+
+/// `options` is a partial `SearchOptions` object.
+#[napi]
+pub async fn start_search_session(path: String, options: Option<Value>) -> napi::Result<Value> {
+    run(move || {
+        let options: SearchOptions = self::options(options)?;
+        api::start_search_session(&path, &options)
+    })
+    .await
+}
+
+#[napi]
+pub async fn refine_search_session(
+    id: String,
+    query: String,
+    limit: Option<u32>,
+) -> napi::Result<Value> {
+    run(move || {
+        let id = serde_json::from_value(Value::String(id))?;
+        api::refine_search_session(id, &query, limit.unwrap_or(20) as usize)
+    })
+    .await
+}
+
+#[napi]
+pub async fn end_search_session(id: String) -> napi::Result<Value> {
+    run(move || {
+        Ok(api::end_search_session(serde_json::from_value(
+            Value::String(id),
+        )?))
+    })
+    .await
+}
+
+/// `options` is a partial `GrepOptions` object.
+#[napi]
+pub async fn grep(path: String, pattern: String, options: Option<Value>) -> napi::Result<Value> {
+    run(move || {
+        let options: GrepOptions = self::options(options)?;
+        api::grep(&path, &pattern, &options)
+    })
+    .await
+}
+
+#[napi]
+pub async fn search_suggestions(
+    prefix: String,
+    root: Option<String>,
+    limit: Option<u32>,
+) -> napi::Result<Value> {
+    run(move || api::search_suggestions(root.as_deref(), &prefix, limit.unwrap_or(10) as usize))
+        .await
+}
+
+#[napi]
+pub async fn preview(path: String) -> napi::Result<Value> {
+    run(move || api::preview(&path)).await
+}
+
+/// Resolves once the size is computed; see `invoke("dir_size")` for the polling variant.
+#[napi]
+pub async fn dir_size(path: String) -> napi::Result<Value> {
+    run(move || api::dir_size_blocking(&path)).await
+}
+
+#[napi]
+pub async fn json_schemas() -> napi::Result<Value> {
+    run(|| Ok(api::json_schemas())).await
+}
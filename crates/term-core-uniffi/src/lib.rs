@@ -0,0 +1,431 @@
+//! UniFFI bindings over `term_core::api`. Generate Swift or Kotlin with:
+//!
+//! ```sh
+//! cargo build -p term-core-uniffi --release
+//! cargo run -p term-core-uniffi --bin uniffi-bindgen -- generate \
+//!   --library target/release/libterm_core_uniffi.dylib --language swift --out-dir bindings
+//! ```
+//!
+//! Calls mirror the `api` functions one to one. Results with a mirror record in `types.rs`
+//! come back typed; deeply nested ones (config, project info, previews, reports) come back
+//! as JSON strings, and `invoke` reaches anything else by name.
+
+use term_core::api;
+
+mod types;
+
+pub use types::*;
+
+uniffi::setup_scaffolding!();
+
+#[uniffi::export]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// `Capabilities` as JSON.
+#[uniffi::export]
+pub fn capabilities() -> Result<String> {
+    to_json(&api::capabilities())
+}
+
+#[uniffi::export]
+pub fn invoke(method: String, params_json: Option<String>) -> Result<String> {
+    let params = match params_json.filter(|json| !json.trim().is_empty()) {
+        Some(json) => serde_json::from_str(&json)?,
+        None => serde_json::Value::Null,
+    };
+    to_json(&api::invoke(&method, params)?)
+}
+
+#[uniffi::export]
+pub fn normalize_path(path: String) -> Result<String> {
+    Ok(api::normalize_path(&path)?)
+}
+
+#[uniffi::export]
+pub fn list_directory(path: String) -> Result<Vec<DirectoryEntry>> {
+    convert(api::list_directory(&path)?)
+}
+
+#[uniffi::export]
+pub fn list_roots() -> Result<Vec<VolumeRoot>> {
+    convert(api::list_roots()?)
+}
+
+#[uniffi::export]
+pub fn ancestors(path: String) -> Result<Vec<Breadcrumb>> {
+    convert(api::ancestors(&path)?)
+}
+
+#[uniffi::export]
+pub fn list_favorites() -> Vec<String> {
+    api::list_favorites()
+}
+
+#[uniffi::export]
+pub fn list_favorites_with_status() -> Result<Vec<PathWithStatus>> {
+    convert(api::list_favorites_with_status())
+}
+
+#[uniffi::export]
+pub fn add_favorite(path: String) -> Result<()> {
+    Ok(api::add_favorite(&path)?)
+}
+
+#[uniffi::export]
+pub fn remove_favorite(path: String) -> Result<()> {
+    Ok(api::remove_favorite(&path)?)
+}
+
+#[uniffi::export]
+pub fn list_recents() -> Result<Vec<RecentEntry>> {
+    convert(api::list_recents())
+}
+
+#[uniffi::export]
+pub fn list_recents_with_status() -> Result<Vec<PathWithStatus>> {
+    convert(api::list_recents_with_status())
+}
+
+#[uniffi::export]
+pub fn touch_recent(path: String) -> Result<()> {
+    Ok(api::touch_recent(&path)?)
+}
+
+/// `weights_json` is a `RankWeights` object; `None` uses the defaults.
+#[uniffi::export]
+pub fn rank_paths(paths: Vec<String>, weights_json: Option<String>) -> Result<Vec<RankBreakdown>> {
+    convert(api::rank_paths(&paths, &parse_or_default(weights_json)?))
+}
+
+#[uniffi::export]
+pub fn detect_projects(path: String) -> Result<Vec<ProjectRoot>> {
+    convert(api::detect_projects(&path)?)
+}
+
+/// `ProjectInfo` as JSON.
+#[uniffi::export]
+pub fn project_info(path: String) -> Result<String> {
+    to_json(&api::project_info(&path)?)
+}
+
+/// The full `Config` as JSON.
+#[uniffi::export]
+pub fn get_config() -> Result<String> {
+    to_json(&api::get_config()?)
+}
+
+#[uniffi::export]
+pub fn get_config_value(key: String) -> Result<String> {
+    to_json(&api::get_config_value(&key)?)
+}
+
+#[uniffi::export]
+pub fn set_config(key: String, value: String) -> Result<String> {
+    to_json(&api::set_config(&key, &value)?)
+}
+
+#[uniffi::export]
+pub fn config_path() -> String {
+    api::config_path().display().to_string()
+}
+
+#[uniffi::export]
+pub fn list_tags() -> Result<Vec<TaggedPath>> {
+    convert(api::list_tags())
+}
+
+#[uniffi::export]
+pub fn list_tags_with_status() -> Result<Vec<PathWithStatus>> {
+    convert(api::list_tags_with_status())
+}
+
+#[uniffi::export]
+pub fn set_tag(path: String, tag: String, color: Option<String>) -> Result<()> {
+    Ok(api::set_tag(&path, &tag, color.as_deref())?)
+}
+
+#[uniffi::export]
+pub fn remove_tag(path: String, tag: String) -> Result<()> {
+    Ok(api::remove_tag(&path, &tag)?)
+}
+
+#[uniffi::export]
+pub fn tags_for(path: String) -> Result<Vec<TaggedPath>> {
+    convert(api::tags_for(&path)?)
+}
+
+#[uniffi::export]
+pub fn setting_get(namespace: String, key: String) -> Result<Option<String>> {
+    Ok(api::setting_get(&namespace, &key)?)
+}
+
+#[uniffi::export]
+pub fn setting_set(namespace: String, key: String, value: String) -> Result<()> {
+    Ok(api::setting_set(&namespace, &key, &value)?)
+}
+
+#[uniffi::export]
+pub fn setting_delete(namespace: String, key: String) -> Result<bool> {
+    Ok(api::setting_delete(&namespace, &key)?)
+}
+
+/// `PruneReport` as JSON.
+#[uniffi::export]
+pub fn prune_state() -> Result<String> {
+    to_json(&api::prune_state()?)
+}
+
+#[uniffi::export]
+pub fn list_profiles() -> Result<Vec<LaunchProfile>> {
+    convert(api::list_profiles())
+}
+
+/// Creates a profile when `id` is `None`, otherwise replaces the one with that id.
+#[uniffi::export]
+pub fn save_profile(
+    id: Option<String>,
+    name: String,
+    command: Option<String>,
+    working_dir: Option<String>,
+    terminal: Option<String>,
+    windows: Option<u8>,
+    wsl_distro: Option<String>,
+) -> Result<LaunchProfile> {
+    let id = id.map(convert).transpose()?;
+    convert(api::save_profile(
+        id,
+        &name,
+        command,
+        working_dir,
+        terminal,
+        windows,
+        wsl_distro,
+    )?)
+}
+
+#[uniffi::export]
+pub fn delete_profile(id: String) -> Result<()> {
+    Ok(api::delete_profile(convert(id)?)?)
+}
+
+/// JSON Schema documents keyed by type name, as one JSON object.
+#[uniffi::export]
+pub fn json_schemas() -> Result<String> {
+    to_json(&api::json_schemas())
+}
+
+/// The script's final value as JSON.
+#[uniffi::export]
+pub fn run_script(path: String, args: Vec<String>) -> Result<String> {
+    to_json(&api::run_script(&path, &args)?)
+}
+
+#[uniffi::export]
+pub fn launch(request: LaunchRequest) -> Result<()> {
+    Ok(api::launch(&convert(request)?)?)
+}
+
+/// The parsed `DeepLink` as JSON.
+#[uniffi::export]
+pub fn parse_url(url: String) -> Result<String> {
+    to_json(&api::parse_url(&url)?)
+}
+
+/// The `UrlOutcome` as JSON; a `confirm` outcome until called again with `confirmed`.
+#[uniffi::export]
+pub fn handle_url(url: String, confirmed: bool) -> Result<String> {
+    to_json(&api::handle_url(&url, confirmed)?)
+}
+
+#[uniffi::export]
+pub fn list_sessions() -> Result<Vec<Session>> {
+    convert(api::list_sessions())
+}
+
+#[uniffi::export]
+pub fn save_session(name: String, entries: Vec<SessionEntry>) -> Result<Session> {
+    convert(api::save_session(&name, convert(entries)?)?)
+}
+
+#[uniffi::export]
+pub fn delete_session(name: String) -> Result<()> {
+    Ok(api::delete_session(&name)?)
+}
+
+#[uniffi::export]
+pub fn session_launches(name: String) -> Result<Vec<LaunchRequest>> {
+    convert(api::session_launches(&name)?)
+}
+
+#[uniffi::export]
+pub fn restore_session(name: String) -> Result<RestoreReport> {
+    convert(api::restore_session(&name)?)
+}
+
+#[uniffi::export]
+pub fn list_saved_searches() -> Result<Vec<SavedSearch>> {
+    convert(api::list_saved_searches())
+}
+
+/// `options_json` is a `SearchOptions` object; `None` uses the defaults.
+#[uniffi::export]
+pub fn save_saved_search(
+    name: String,
+    root: String,
+    query: String,
+    limit: u64,
+    options_json: Option<String>,
+) -> Result<SavedSearch> {
+    convert(api::save_saved_search(
+        &name,
+        &root,
+        &query,
+        limit as usize,
+        parse_or_default(options_json)?,
+    )?)
+}
+
+#[uniffi::export]
+pub fn delete_saved_search(name: String) -> Result<()> {
+    Ok(api::delete_saved_search(&name)?)
+}
+
+#[uniffi::export]
+pub fn run_saved_search(name: String) -> Result<Vec<SearchResult>> {
+    convert(api::run_saved_search(&name)?)
+}
+
+#[uniffi::export]
+pub fn create_dir(path: String) -> Result<String> {
+    Ok(api::create_dir(&path)?)
+}
+
+#[uniffi::export]
+pub fn rename(path: String, new_name: String) -> Result<String> {
+    Ok(api::rename(&path, &new_name)?)
+}
+
+#[uniffi::export]
+pub fn move_path(path: String, destination: String) -> Result<String> {
+    Ok(api::move_path(&path, &destination)?)
+}
+
+#[uniffi::export]
+pub fn trash(path: String) -> Result<()> {
+    Ok(api::trash(&path)?)
+}
+
+/// `options_json` is a `SearchOptions` object; `None` uses the defaults.
+#[uniffi::export]
+pub fn search(
+    path: String,
+    query: String,
+    limit: u64,
+    options_json: Option<String>,
+) -> Result<Vec<SearchResult>> {
+    let options = parse_or_default(options_json)?;
+    convert(api::search(&path, &query, limit as usize, &options)?)
+}
+
+/// `options_json` is a `SearchOptions` object; `None` uses the defaults.
+#[uniffi::export]
+pub fn start_search_session(path: String, options_json: Option<String>) -> Result<SearchSession> {
+    let options = parse_or_default(options_json)?;
+    convert(api::start_search_session(&path, &options)?)
+}
+
+#[uniffi::export]
+pub fn refine_search_session(id: String, query: String, limit: u64) -> Result<Vec<SearchResult>> {
+    convert(api::refine_search_session(
+        convert(id)?,
+        &query,
+        limit as usize,
+    )?)
+}
+
+#[uniffi::export]
+pub fn end_search_session(id: String) -> Result<bool> {
+    Ok(api::end_search_session(convert(id)?))
+}
+
+/// `options_json` is a `GrepOptions` object; `None` uses the defaults.
+#[uniffi::export]
+pub fn grep(path: String, pattern: String, options_json: Option<String>) -> Result<Vec<GrepMatch>> {
+    let options = parse_or_default(options_json)?;
+    convert(api::grep(&path, &pattern, &options)?)
+}
+
+#[uniffi::export]
+pub fn search_history(root: Option<String>, limit: u64) -> Result<Vec<SearchHistoryEntry>> {
+    convert(api::search_history(root.as_deref(), limit as usize)?)
+}
+
+#[uniffi::export]
+pub fn search_suggestions(root: Option<String>, prefix: String, limit: u64) -> Result<Vec<String>> {
+    Ok(api::search_suggestions(
+        root.as_deref(),
+        &prefix,
+        limit as usize,
+    )?)
+}
+
+#[uniffi::export]
+pub fn clear_search_history() -> Result<()> {
+    Ok(api::clear_search_history()?)
+}
+
+/// `DirectoryPreview` as JSON.
+#[uniffi::export]
+pub fn preview(path: String) -> Result<String> {
+    to_json(&api::preview(&path)?)
+}
+
+/// `DirSize` as JSON; poll until its `status` is no longer `pending`.
+#[uniffi::export]
+pub fn dir_size(path: String) -> Result<String> {
+    to_json(&api::dir_size(&path)?)
+}
+
+/// Like `dir_size`, but computes the size before returning.
+#[uniffi::export]
+pub fn dir_size_blocking(path: String) -> Result<String> {
+    to_json(&api::dir_size_blocking(&path)?)
+}
+
+#[uniffi::export]
+pub fn cancel_dir_size(path: String) -> Result<bool> {
+    Ok(api::cancel_dir_size(&path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirrors_round_trip_core_types() {
+        let request = LaunchRequest {
+            path: String::from("/tmp"),
+            terminal: None,
+            command: Some(String::from("ls")),
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 2,
+            env: [(String::from("A"), String::from("1"))].into(),
+            wsl_distro: None,
+        };
+        let core: term_core::LaunchRequest = convert(request).unwrap();
+        assert_eq!(core.windows, 2);
+        let back: LaunchRequest = convert(core).unwrap();
+        assert_eq!(back.env["A"], "1");
+
+        let err = convert::<_, SessionEntry>(serde_json::json!({})).unwrap_err();
+        assert!(matches!(err, TerminautError::InvalidInput { .. }));
+    }
+}
@@ -0,0 +1,334 @@
+//! Binding-side copies of the core types. UniFFI has no `usize`, `Uuid`, or flattened
+//! structs, so each record mirrors the JSON shape of its core counterpart and is converted
+//! through serde rather than field by field.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use term_core::{CoreError, ErrorCode};
+
+#[derive(Debug, uniffi::Error)]
+pub enum TerminautError {
+    NotFound {
+        message: String,
+        hint: Option<String>,
+    },
+    InvalidInput {
+        message: String,
+        hint: Option<String>,
+    },
+    Io {
+        message: String,
+        hint: Option<String>,
+    },
+    StoreCorrupt {
+        message: String,
+        hint: Option<String>,
+    },
+    Conflict {
+        message: String,
+        hint: Option<String>,
+    },
+    Internal {
+        message: String,
+        hint: Option<String>,
+    },
+}
+
+impl fmt::Display for TerminautError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Self::NotFound { message, .. }
+        | Self::InvalidInput { message, .. }
+        | Self::Io { message, .. }
+        | Self::StoreCorrupt { message, .. }
+        | Self::Conflict { message, .. }
+        | Self::Internal { message, .. }) = self;
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for TerminautError {}
+
+impl From<anyhow::Error> for TerminautError {
+    fn from(err: anyhow::Error) -> Self {
+        let message = format!("{err:#}");
+        let hint = term_core::error_hint(&err);
+        match term_core::error_code(&err) {
+            ErrorCode::NotFound => Self::NotFound { message, hint },
+            ErrorCode::InvalidInput => Self::InvalidInput { message, hint },
+            ErrorCode::Io => Self::Io { message, hint },
+            ErrorCode::StoreCorrupt => Self::StoreCorrupt { message, hint },
+            ErrorCode::Conflict => Self::Conflict { message, hint },
+            ErrorCode::Internal => Self::Internal { message, hint },
+        }
+    }
+}
+
+impl From<serde_json::Error> for TerminautError {
+    fn from(err: serde_json::Error) -> Self {
+        anyhow::Error::new(err).into()
+    }
+}
+
+pub type Result<T> = std::result::Result<T, TerminautError>;
+
+/// Converts between a core type and its mirror (either direction) via their JSON form.
+pub fn convert<T: Serialize, U: DeserializeOwned>(value: T) -> Result<U> {
+    Ok(serde_json::from_value(serde_json::to_value(value)?)?)
+}
+
+/// Parses an optional JSON argument, falling back to the type's default.
+pub fn parse_or_default<T: DeserializeOwned + Default>(json: Option<String>) -> Result<T> {
+    match json.filter(|json| !json.trim().is_empty()) {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|err| CoreError::invalid_input(format!("invalid options: {err}")))
+            .map_err(|err| anyhow::Error::new(err).into()),
+        None => Ok(T::default()),
+    }
+}
+
+/// Serializes results that have no mirror record (nested configs, reports, previews).
+pub fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    #[serde(default)]
+    pub mod_date: Option<i64>,
+    /// `modified`, `untracked`, `ignored`, ...; see `list_directory_with_git`.
+    #[serde(default)]
+    pub git_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct VolumeRoot {
+    pub path: String,
+    pub name: String,
+    #[serde(default)]
+    pub fs_type: Option<String>,
+    pub is_network: bool,
+    pub is_removable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct Breadcrumb {
+    pub path: String,
+    pub name: String,
+    pub is_home: bool,
+    pub is_volume_root: bool,
+    pub is_project_root: bool,
+    #[serde(default)]
+    pub project_marker: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct RecentEntry {
+    pub path: String,
+    pub last_opened_utc: i64,
+    pub visits: u32,
+    pub score: f64,
+    #[serde(default)]
+    pub sources: std::collections::HashMap<String, i64>,
+}
+
+/// A stored path with whether it still exists, as returned by the `*_with_status` calls.
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct PathWithStatus {
+    pub path: String,
+    #[serde(default)]
+    pub last_opened_utc: Option<i64>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    pub exists: bool,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct TaggedPath {
+    pub path: String,
+    pub tag: String,
+    pub color: String,
+    #[serde(default)]
+    pub inherited: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct ProjectRoot {
+    pub path: String,
+    pub marker: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct LaunchProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub use_shell: bool,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub pre_command: Option<String>,
+    #[serde(default)]
+    pub post_launch_command: Option<String>,
+    #[serde(default)]
+    pub autostart: bool,
+    pub working_dir: Option<String>,
+    pub terminal: Option<String>,
+    pub windows: u8,
+    #[serde(default)]
+    pub wsl_distro: Option<String>,
+    #[serde(default)]
+    pub ssh_host: Option<String>,
+    #[serde(default)]
+    pub ssh_dir: Option<String>,
+    /// `ssh`, `mosh`, or `et`; absent for ssh.
+    #[serde(default)]
+    pub transport: Option<String>,
+    #[serde(default)]
+    pub kube_pod: Option<String>,
+    #[serde(default)]
+    pub kube_container: Option<String>,
+    #[serde(default)]
+    pub kube_namespace: Option<String>,
+    #[serde(default)]
+    pub kube_context: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct LaunchRequest {
+    pub path: String,
+    #[serde(default)]
+    pub terminal: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub use_shell: bool,
+    #[serde(default)]
+    pub pre_command: Option<String>,
+    #[serde(default)]
+    pub post_launch_command: Option<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub background: bool,
+    pub windows: u8,
+    #[serde(default)]
+    pub env: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub wsl_distro: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct SessionEntry {
+    pub path: String,
+    #[serde(default)]
+    pub profile_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct Session {
+    pub name: String,
+    pub entries: Vec<SessionEntry>,
+    pub saved_utc: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct LaunchFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct RestoreReport {
+    pub launched: Vec<LaunchRequest>,
+    pub failed: Vec<LaunchFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct RankBreakdown {
+    pub fuzzy: f64,
+    pub recent: f64,
+    pub favorite: f64,
+    pub tagged: f64,
+    pub rules: f64,
+    pub pinned: bool,
+    pub total: f64,
+    pub explanation: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct SearchResult {
+    pub path: String,
+    pub name: String,
+    pub relative_path: String,
+    pub score: i64,
+    pub indices: Vec<u64>,
+    pub rank: RankBreakdown,
+    #[serde(default)]
+    pub matched_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct SearchSession {
+    pub id: String,
+    pub root: String,
+    pub candidates: u64,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct SavedSearch {
+    pub name: String,
+    pub root: String,
+    pub query: String,
+    pub limit: u64,
+    /// The stored `SearchOptions` as JSON.
+    #[serde(rename = "options", with = "json_string")]
+    pub options_json: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct GrepMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub line_number: u64,
+    pub column: u64,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub root: String,
+    pub last_used_utc: i64,
+    pub count: u32,
+}
+
+/// Carries a nested JSON value across the boundary as a string.
+mod json_string {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(json: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(serde::ser::Error::custom)?;
+        value.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Ok(value.to_string())
+    }
+}
@@ -0,0 +1,43 @@
+//! Compares the skim and nucleo backends of `api::fuzzy_filter` over synthetic names.
+//!
+//! Run with `cargo bench -p term-core --bench matching`; results guide the `search.matcher`
+//! default as candidate sets grow.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use term_core::{api, MatchOptions, MatcherKind};
+
+const WORDS: [&str; 8] = [
+    "terminal", "core", "api", "web", "service", "client", "docs", "tools",
+];
+
+fn candidates(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            let first = WORDS[i % WORDS.len()];
+            let second = WORDS[(i / WORDS.len()) % WORDS.len()];
+            format!("{first}-{second}-{i}")
+        })
+        .collect()
+}
+
+fn bench_matchers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fuzzy_filter");
+    for count in [1_000, 100_000] {
+        let candidates = candidates(count);
+        for kind in [MatcherKind::Skim, MatcherKind::Nucleo] {
+            let options = MatchOptions {
+                matcher: Some(kind),
+                ..MatchOptions::default()
+            };
+            group.bench_with_input(
+                BenchmarkId::new(format!("{kind:?}").to_lowercase(), count),
+                &options,
+                |b, options| b.iter(|| api::fuzzy_filter("srvcl", &candidates, options)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matchers);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! Compares walker thread counts and result limits for `api::search` over a synthetic tree.
+//!
+//! Run with `cargo bench -p term-core --bench search`; the fastest setting on typical
+//! hardware informs the `threads: 0` (automatic) default in `SearchOptions`. The cached walk
+//! is dropped before each search so every iteration walks the tree.
+
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use term_core::{api, SearchOptions};
+
+fn build_tree(root: &Path, depth: usize, fanout: usize) {
+    if depth == 0 {
+        return;
+    }
+    for i in 0..fanout {
+        let child = root.join(format!("dir-{depth}-{i}"));
+        std::fs::create_dir_all(&child).unwrap();
+        build_tree(&child, depth - 1, fanout);
+    }
+}
+
+fn fixture() -> PathBuf {
+    let root = std::env::temp_dir().join("terminaut-search-bench");
+    if !root.exists() {
+        build_tree(&root, 4, 8);
+    }
+    root
+}
+
+fn bench_threads(c: &mut Criterion) {
+    let root = fixture();
+    let root = root.display().to_string();
+    let mut group = c.benchmark_group("search_threads");
+    for threads in [1, 2, 4, 0] {
+        let options = SearchOptions {
+            threads,
+            max_depth: None,
+            ..SearchOptions::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &options,
+            |b, options| b.iter(|| walk_and_search(&root, 20, options)),
+        );
+    }
+    group.finish();
+}
+
+/// The whole tree is walked whatever the limit, so time should barely grow with it.
+fn bench_limits(c: &mut Criterion) {
+    let root = fixture();
+    let root = root.display().to_string();
+    let options = SearchOptions {
+        max_depth: None,
+        time_budget_ms: None,
+        ..SearchOptions::default()
+    };
+    let mut group = c.benchmark_group("search_limits");
+    for limit in [1, 20, 200] {
+        group.bench_with_input(BenchmarkId::from_parameter(limit), &limit, |b, limit| {
+            b.iter(|| walk_and_search(&root, *limit, &options))
+        });
+    }
+    group.finish();
+}
+
+fn walk_and_search(root: &str, limit: usize, options: &SearchOptions) {
+    api::invalidate_search_cache(None).unwrap();
+    api::search(root, "dir-1-7", limit, options).unwrap();
+}
+
+criterion_group!(benches, bench_threads, bench_limits);
+criterion_main!(benches);
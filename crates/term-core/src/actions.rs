@@ -0,0 +1,324 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::command_line::quote;
+use crate::{config, CoreError, LaunchRequest, TaggedPath, STORE};
+
+/// A command offered for one directory or for every directory with a tag, such as "Deploy"
+/// or "Open logs" in a project's context menu.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomAction {
+    pub id: Uuid,
+    pub label: String,
+    /// Shell command run at the directory; `{path}` and `{name}` are replaced with the
+    /// directory and its folder name, each quoted as one word.
+    pub command: String,
+    /// The directory the action is offered for. Exactly one of `path` and `tag` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Offered for every directory with this tag, including inherited ones when
+    /// `tags.inherit` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Run without a terminal; [`crate::api::launch_logs`] lists the output under the
+    /// action's id.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub background: bool,
+}
+
+pub(crate) fn add_action(
+    label: &str,
+    command: &str,
+    path: Option<&str>,
+    tag: Option<&str>,
+    background: bool,
+) -> anyhow::Result<CustomAction> {
+    if label.trim().is_empty() || command.trim().is_empty() {
+        anyhow::bail!(CoreError::invalid_input(
+            "an action needs a label and a command"
+        ));
+    }
+    let path = match (path, tag) {
+        (Some(path), None) => Some(crate::normalize_path(path)?.display().to_string()),
+        (None, Some(tag)) => {
+            crate::tag_tree::validate_tag(tag)?;
+            None
+        }
+        _ => anyhow::bail!(
+            CoreError::invalid_input("an action belongs to either a path or a tag")
+                .with_hint("pass exactly one of path and tag")
+        ),
+    };
+    let action = CustomAction {
+        id: Uuid::new_v4(),
+        label: label.trim().to_string(),
+        command: command.trim().to_string(),
+        path,
+        tag: tag.map(str::to_string),
+        background,
+    };
+    let mut store = STORE.inner.lock();
+    store.actions.push(action.clone());
+    STORE.persist(&mut store)?;
+    Ok(action)
+}
+
+pub(crate) fn remove_action(id: Uuid) -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    remove(&mut store.actions, id)?;
+    STORE.persist(&mut store)?;
+    Ok(())
+}
+
+fn remove(actions: &mut Vec<CustomAction>, id: Uuid) -> anyhow::Result<()> {
+    let before = actions.len();
+    actions.retain(|action| action.id != id);
+    if actions.len() == before {
+        anyhow::bail!(CoreError::not_found("action not found"));
+    }
+    Ok(())
+}
+
+/// Every registered action, by label.
+pub(crate) fn list_actions() -> Vec<CustomAction> {
+    sorted(STORE.inner.lock().actions.clone())
+}
+
+fn sorted(mut actions: Vec<CustomAction>) -> Vec<CustomAction> {
+    actions.sort_by_key(|action| action.label.to_lowercase());
+    actions
+}
+
+/// Actions registered for `path` itself, then those for its tags, each in the order they
+/// were added.
+pub(crate) fn actions_for(path: &str) -> anyhow::Result<Vec<CustomAction>> {
+    let normalized = crate::normalize_path(path)?;
+    let key = normalized.display().to_string();
+    let inherit = config::config().tags.inherit;
+    let store = STORE.inner.lock();
+    let tags = crate::tag_tree::tags_for(&store.tags, &normalized, inherit);
+    Ok(offered(&store.actions, &key, &tags))
+}
+
+/// Actions for the path `key`, then those for any of `tags`.
+fn offered(actions: &[CustomAction], key: &str, tags: &[TaggedPath]) -> Vec<CustomAction> {
+    let for_path = actions
+        .iter()
+        .filter(|action| action.path.as_deref() == Some(key));
+    let for_tags = actions.iter().filter(|action| {
+        action.tag.as_deref().is_some_and(|tag| {
+            tags.iter()
+                .any(|tagged| tagged.tag.eq_ignore_ascii_case(tag))
+        })
+    });
+    for_path.chain(for_tags).cloned().collect()
+}
+
+/// `command` with `{path}` and `{name}` filled in for `path`, in one pass so placeholders
+/// inside the filled-in values stay as they are.
+fn expand(command: &str, path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let placeholders = [
+        ("{path}", quote(&path.display().to_string())),
+        ("{name}", quote(&name)),
+    ];
+    let mut expanded = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                expanded.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Runs action `id` at `path` in a new terminal, or in the background when the action says
+/// so. The action must be offered for `path`. Returns the command as run.
+pub(crate) fn run_action(path: &str, id: Uuid) -> anyhow::Result<String> {
+    let normalized = crate::normalize_path(path)?;
+    let Some(action) = actions_for(path)?
+        .into_iter()
+        .find(|action| action.id == id)
+    else {
+        anyhow::bail!(
+            CoreError::not_found(format!("no action {id} for {}", normalized.display()))
+                .with_hint("list the actions offered here with actions_for")
+        );
+    };
+    let command = expand(&action.command, &normalized);
+    let started = std::time::Instant::now();
+    let request = LaunchRequest {
+        path: normalized.display().to_string(),
+        terminal: None,
+        command: Some(command.clone()),
+        args: Vec::new(),
+        use_shell: false,
+        pre_command: None,
+        post_launch_command: None,
+        background: action.background,
+        profile: None,
+        profile_id: None,
+        windows: 1,
+        env: [
+            (String::from("TERMINAUT_ACTION"), action.label),
+            (
+                String::from("TERMINAUT_PATH"),
+                normalized.display().to_string(),
+            ),
+        ]
+        .into(),
+        wsl_distro: None,
+    };
+    crate::launcher::launch_action(&request, action.id)?;
+    crate::stats::record(crate::stats::Usage::Launch {
+        profile: None,
+        took: started.elapsed(),
+    });
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn action(label: &str, path: Option<&str>, tag: Option<&str>) -> CustomAction {
+        CustomAction {
+            id: Uuid::new_v4(),
+            label: label.to_string(),
+            command: String::from("true"),
+            path: path.map(str::to_string),
+            tag: tag.map(str::to_string),
+            background: false,
+        }
+    }
+
+    fn tagged(tag: &str) -> TaggedPath {
+        TaggedPath {
+            path: String::from("/w/api"),
+            tag: tag.to_string(),
+            color: crate::DEFAULT_TAG_COLOR.to_string(),
+            inherited: false,
+        }
+    }
+
+    fn labels(actions: &[CustomAction]) -> Vec<&str> {
+        actions.iter().map(|action| action.label.as_str()).collect()
+    }
+
+    #[test]
+    fn actions_need_a_label_and_a_command() {
+        for (label, command) in [(" ", "make"), ("Deploy", "")] {
+            let err = add_action(label, command, None, Some("deploy"), false).unwrap_err();
+            assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn actions_belong_to_exactly_one_of_a_path_or_a_tag() {
+        let both = add_action("Both", "true", Some("/w"), Some("deploy"), false).unwrap_err();
+        assert_eq!(error_code(&both), ErrorCode::InvalidInput);
+        let neither = add_action("Neither", "true", None, None, false).unwrap_err();
+        assert_eq!(error_code(&neither), ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn tag_actions_need_a_valid_tag() {
+        let err = add_action("Deploy", "true", None, Some("a//b"), false).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn path_actions_come_before_tag_actions() {
+        let actions = [
+            action("Deploy", None, Some("deploy")),
+            action("Logs", Some("/w/api"), None),
+            action("Other", Some("/w/web"), None),
+        ];
+        assert_eq!(
+            labels(&offered(&actions, "/w/api", &[tagged("deploy")])),
+            ["Logs", "Deploy"]
+        );
+    }
+
+    #[test]
+    fn tag_actions_match_tags_ignoring_case() {
+        let actions = [action("Deploy", None, Some("Deploy"))];
+        assert_eq!(
+            labels(&offered(&actions, "/w/api", &[tagged("deploy")])),
+            ["Deploy"]
+        );
+        assert!(offered(&actions, "/w/api", &[tagged("deploy-old")]).is_empty());
+        assert!(offered(&actions, "/w/api", &[]).is_empty());
+    }
+
+    #[test]
+    fn actions_list_by_label_ignoring_case() {
+        let actions = vec![
+            action("deploy", None, Some("x")),
+            action("Build", None, Some("x")),
+            action("Clean", None, Some("x")),
+        ];
+        assert_eq!(labels(&sorted(actions)), ["Build", "Clean", "deploy"]);
+    }
+
+    #[test]
+    fn removing_an_unknown_action_is_not_found() {
+        let kept = action("Deploy", None, Some("deploy"));
+        let mut actions = vec![kept.clone(), action("Logs", Some("/w"), None)];
+        let removed = actions[1].id;
+        remove(&mut actions, removed).unwrap();
+        assert_eq!(labels(&actions), ["Deploy"]);
+        let err = remove(&mut actions, removed).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn placeholders_are_quoted_as_one_word() {
+        assert_eq!(
+            expand("cd {path} && echo {name}", Path::new("/w/it's")),
+            "cd '/w/it'\\''s' && echo 'it'\\''s'"
+        );
+        assert_eq!(expand("ls {name}", Path::new("/")), "ls /");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn placeholders_inside_the_path_are_not_filled_in() {
+        let path = Path::new("/tmp/{name}/x;touch pwned");
+        assert_eq!(expand("cd {path}", path), "cd '/tmp/{name}/x;touch pwned'");
+        let path = Path::new("/tmp/{path}");
+        assert_eq!(
+            expand("echo {name} {path} {other}", path),
+            "echo {path} /tmp/{path} {other}"
+        );
+    }
+
+    #[test]
+    fn actions_not_offered_at_a_path_do_not_run() {
+        let dir = std::env::temp_dir().join(format!("terminaut-actions-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let err = run_action(&dir.display().to_string(), Uuid::new_v4()).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
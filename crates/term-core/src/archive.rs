@@ -0,0 +1,383 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::{CoreError, DirectoryEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZ,
+}
+
+impl ArchiveKind {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".7z") {
+            Some(Self::SevenZ)
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry as the archive stores it: a `/`-separated path and, when known, its mtime.
+struct Member {
+    name: String,
+    is_dir: bool,
+    mod_date: Option<i64>,
+}
+
+/// Splits a path into the archive file it passes through and the path inside it, for
+/// `/downloads/src.zip` (`""` inside) or `/downloads/src.zip/lib/util`. `None` when no
+/// component is an archive file.
+pub(crate) fn split_archive_path(path: &Path) -> Option<(PathBuf, String)> {
+    path.ancestors()
+        .find(|ancestor| ArchiveKind::of(ancestor).is_some() && ancestor.is_file())
+        .map(|archive| {
+            let inner = path
+                .strip_prefix(archive)
+                .unwrap_or(Path::new(""))
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (archive.to_path_buf(), inner)
+        })
+}
+
+/// Entry names without a leading `./` or `/` and without a trailing `/`.
+fn clean(name: &str) -> &str {
+    let name = name.trim_start_matches("./").trim_start_matches('/');
+    name.trim_end_matches('/')
+}
+
+#[cfg(feature = "archives")]
+fn read_members(archive: &Path, kind: ArchiveKind) -> anyhow::Result<Vec<Member>> {
+    use anyhow::Context;
+    use chrono::NaiveDate;
+    use std::fs::File;
+
+    let open =
+        || File::open(archive).with_context(|| format!("failed to open {}", archive.display()));
+    let tar_members = |mut tar: tar::Archive<Box<dyn std::io::Read>>| -> anyhow::Result<_> {
+        let mut members = Vec::new();
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let header = entry.header();
+            members.push(Member {
+                name: entry.path()?.to_string_lossy().into_owned(),
+                is_dir: header.entry_type().is_dir(),
+                mod_date: header.mtime().ok().map(|mtime| mtime as i64),
+            });
+        }
+        Ok(members)
+    };
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(open()?)?;
+            (0..zip.len())
+                .map(|index| {
+                    let file = zip.by_index_raw(index)?;
+                    let mod_date = file.last_modified().and_then(|time| {
+                        NaiveDate::from_ymd_opt(
+                            time.year().into(),
+                            time.month().into(),
+                            time.day().into(),
+                        )?
+                        .and_hms_opt(
+                            time.hour().into(),
+                            time.minute().into(),
+                            time.second().into(),
+                        )
+                        .map(|time| time.and_utc().timestamp())
+                    });
+                    Ok(Member {
+                        name: file.name().to_string(),
+                        is_dir: file.is_dir(),
+                        mod_date,
+                    })
+                })
+                .collect()
+        }
+        ArchiveKind::Tar => tar_members(tar::Archive::new(Box::new(open()?))),
+        ArchiveKind::TarGz => tar_members(tar::Archive::new(Box::new(
+            flate2::read::GzDecoder::new(open()?),
+        ))),
+        ArchiveKind::SevenZ => {
+            let archive = sevenz_rust::Archive::open(archive)
+                .map_err(|err| anyhow::anyhow!("failed to read {}: {err}", archive.display()))?;
+            Ok(archive
+                .files
+                .into_iter()
+                .map(|file| Member {
+                    mod_date: file
+                        .has_last_modified_date
+                        .then(|| file.last_modified_date.to_unix_time()),
+                    name: file.name,
+                    is_dir: file.is_directory,
+                })
+                .collect())
+        }
+    }
+}
+
+#[cfg(not(feature = "archives"))]
+fn read_members(_archive: &Path, _kind: ArchiveKind) -> anyhow::Result<Vec<Member>> {
+    anyhow::bail!(CoreError::invalid_input(
+        "this build does not include archive support"
+    ))
+}
+
+/// The direct children of `inner` among `members`, as entries under `base`. Directories that
+/// only appear as a prefix of deeper entries are listed too.
+fn children(members: Vec<Member>, inner: &str, base: &Path) -> Option<Vec<DirectoryEntry>> {
+    let inner = clean(inner);
+    let mut found_inner = inner.is_empty();
+    let mut entries: BTreeMap<String, DirectoryEntry> = BTreeMap::new();
+    for member in members {
+        let name = clean(&member.name);
+        let rest = match inner.is_empty() {
+            true => name,
+            false => match name.strip_prefix(inner) {
+                Some("") => {
+                    found_inner |= member.is_dir;
+                    continue;
+                }
+                Some(rest) => match rest.strip_prefix('/') {
+                    Some(rest) => rest,
+                    None => continue,
+                },
+                None => continue,
+            },
+        };
+        if rest.is_empty() {
+            continue;
+        }
+        found_inner = true;
+        let (child, deeper) = match rest.split_once('/') {
+            Some((child, _)) => (child, true),
+            None => (rest, false),
+        };
+        let entry = entries
+            .entry(child.to_string())
+            .or_insert_with(|| DirectoryEntry {
+                name: child.to_string(),
+                path: base.join(child).display().to_string(),
+                is_dir: deeper,
+                mod_date: None,
+                git_status: None,
+            });
+        entry.is_dir |= deeper || member.is_dir;
+        if !deeper {
+            entry.mod_date = member.mod_date;
+        }
+    }
+    if !found_inner {
+        return None;
+    }
+    let mut entries: Vec<DirectoryEntry> = entries.into_values().collect();
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    Some(entries)
+}
+
+/// Lists a directory inside a `.zip`, `.tar`, `.tar.gz`, or `.7z` file without extracting it.
+/// `path` is the archive itself or a path through it, like `/downloads/src.zip/lib`; entry
+/// paths continue it, so listing a directory entry drills further in.
+pub(crate) fn list_archive(path: &Path) -> anyhow::Result<Vec<DirectoryEntry>> {
+    let (archive, inner) = split_archive_path(path).ok_or_else(|| {
+        CoreError::invalid_input(format!("{} is not inside an archive", path.display()))
+            .with_hint("archives ending in .zip, .tar, .tar.gz, .tgz, or .7z can be listed")
+    })?;
+    let kind = ArchiveKind::of(&archive).expect("split_archive_path only finds archives");
+    let members = read_members(&archive, kind)?;
+    children(members, &inner, path).ok_or_else(|| {
+        CoreError::not_found(format!(
+            "{inner} is not a directory in {}",
+            archive.display()
+        ))
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terminaut-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn member(name: &str, is_dir: bool) -> Member {
+        Member {
+            name: name.to_string(),
+            is_dir,
+            mod_date: None,
+        }
+    }
+
+    fn names(entries: Vec<DirectoryEntry>) -> Vec<(String, bool)> {
+        entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.is_dir))
+            .collect()
+    }
+
+    #[cfg(feature = "archives")]
+    fn write_zip(path: &Path) {
+        use std::io::Write;
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("lib/util/mod.rs", options).unwrap();
+        zip.write_all(b"// util").unwrap();
+        zip.add_directory("lib/empty/", options).unwrap();
+        zip.start_file("README.md", options).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn archive_kinds_come_from_the_extension() {
+        let kind = |name: &str| ArchiveKind::of(Path::new(name));
+        assert_eq!(kind("/dl/src.ZIP"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("/dl/src.tar"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("/dl/src.tar.gz"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("/dl/src.TGZ"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("/dl/src.7z"), Some(ArchiveKind::SevenZ));
+        assert_eq!(kind("/dl/src.gz"), None);
+        assert_eq!(kind("/"), None);
+    }
+
+    #[test]
+    fn member_names_lose_leading_and_trailing_separators() {
+        assert_eq!(clean("./pkg/bin/"), "pkg/bin");
+        assert_eq!(clean("/pkg"), "pkg");
+        assert_eq!(clean("./"), "");
+    }
+
+    #[test]
+    fn paths_split_at_the_archive_file() {
+        let dir = temp_dir();
+        let archive = dir.join("src.zip");
+        std::fs::write(&archive, b"").unwrap();
+        assert_eq!(
+            split_archive_path(&archive.join("lib/util")),
+            Some((archive.clone(), String::from("lib/util")))
+        );
+        assert_eq!(
+            split_archive_path(&archive),
+            Some((archive.clone(), String::new()))
+        );
+        // A directory named like an archive isn't one.
+        let named = dir.join("old.zip");
+        std::fs::create_dir_all(named.join("lib")).unwrap();
+        assert_eq!(split_archive_path(&named.join("lib")), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directories_implied_by_deeper_members_are_listed() {
+        let members = vec![member("a/b/c.txt", false), member("a/d.txt", false)];
+        let top = children(members, "", Path::new("/x.zip")).unwrap();
+        assert_eq!(names(top), [(String::from("a"), true)]);
+        let members = vec![member("a/b/c.txt", false), member("a/d.txt", false)];
+        let inner = children(members, "a/", Path::new("/x.zip/a")).unwrap();
+        assert_eq!(
+            names(inner),
+            [(String::from("b"), true), (String::from("d.txt"), false)]
+        );
+    }
+
+    #[test]
+    fn files_and_missing_names_are_not_directories() {
+        let members = || vec![member("README.md", false), member("lib/", true)];
+        assert!(children(members(), "README.md", Path::new("/x")).is_none());
+        assert!(children(members(), "missing", Path::new("/x")).is_none());
+        // A prefix of a name isn't its parent.
+        assert!(children(members(), "li", Path::new("/x")).is_none());
+        assert_eq!(
+            children(members(), "lib", Path::new("/x")).unwrap().len(),
+            0
+        );
+    }
+
+    #[test]
+    fn paths_outside_archives_are_rejected() {
+        let err = list_archive(&std::env::temp_dir()).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn zips_list_one_level_at_a_time() {
+        let dir = temp_dir();
+        let zip_path = dir.join("src.zip");
+        write_zip(&zip_path);
+        let top = list_archive(&zip_path).unwrap();
+        assert_eq!(top[0].path, zip_path.join("lib").display().to_string());
+        assert_eq!(
+            names(top),
+            [("lib".to_string(), true), ("README.md".to_string(), false)]
+        );
+        assert_eq!(
+            names(list_archive(&zip_path.join("lib")).unwrap()),
+            [("empty".to_string(), true), ("util".to_string(), true)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn missing_directories_inside_an_archive_are_not_found() {
+        let dir = temp_dir();
+        let zip_path = dir.join("src.zip");
+        write_zip(&zip_path);
+        let err = list_archive(&zip_path.join("missing")).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn corrupt_archives_fail_to_list() {
+        let dir = temp_dir();
+        let zip_path = dir.join("broken.zip");
+        std::fs::write(&zip_path, b"not a zip").unwrap();
+        assert!(list_archive(&zip_path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn tarballs_list_with_modification_times() {
+        let dir = temp_dir();
+        let tar_path = dir.join("src.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&tar_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        tar.append_data(&mut header, "./pkg/bin/run", &b"run"[..])
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let bin = list_archive(&tar_path.join("pkg/bin")).unwrap();
+        assert_eq!(bin[0].name, "run");
+        assert_eq!(bin[0].mod_date, Some(1_700_000_000));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
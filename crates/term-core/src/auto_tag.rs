@@ -0,0 +1,304 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use globset::GlobMatcher;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, EventKind, StoreChange, StoreEvent};
+use crate::hooks::{self, HookEvent};
+use crate::ranking::path_glob;
+use crate::tag_colors::resolve_color;
+use crate::{config, CoreError, TaggedPath, DEFAULT_TAG_COLOR, STORE};
+
+/// A `[[projects.tag_rules]]` entry: paths matching `glob` (or below a directory matching
+/// it) and containing `marker` get `tag`. A rule needs at least one of the two; with both,
+/// a path must satisfy both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagRule {
+    pub tag: String,
+    /// Glob over the full path, e.g. `~/work/clients/acme`; a leading `~` is the home directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// File or directory that must exist directly inside the path, e.g. `Cargo.toml`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+    /// Color of new tags, as [`crate::api::set_tag`] takes it; defaults to blue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// What [`crate::api::auto_tag`] did, or with `dry_run` would do.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutoTagReport {
+    pub dry_run: bool,
+    /// Registered projects, favorites, and recents the rules were checked against.
+    pub candidates: usize,
+    /// Tags added, or that would be added.
+    pub added: Vec<TaggedPath>,
+    /// Matches that already had their tag.
+    pub unchanged: usize,
+}
+
+pub(crate) struct CompiledTagRule {
+    glob: Option<GlobMatcher>,
+    rule: TagRule,
+}
+
+impl CompiledTagRule {
+    fn matches(&self, path: &Path) -> bool {
+        self.glob
+            .as_ref()
+            .is_none_or(|glob| path.ancestors().any(|ancestor| glob.is_match(ancestor)))
+            && self
+                .rule
+                .marker
+                .as_ref()
+                .is_none_or(|marker| path.join(marker).exists())
+    }
+}
+
+pub(crate) fn compile_tag_rules(rules: &[TagRule]) -> anyhow::Result<Vec<CompiledTagRule>> {
+    rules
+        .iter()
+        .map(|rule| {
+            crate::tag_tree::validate_tag(&rule.tag)?;
+            if rule.glob.is_none() && rule.marker.is_none() {
+                anyhow::bail!(CoreError::invalid_input(format!(
+                    "tag rule {:?} needs a glob or a marker",
+                    rule.tag
+                )));
+            }
+            Ok(CompiledTagRule {
+                glob: rule.glob.as_deref().map(path_glob).transpose()?,
+                rule: rule.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Tags `paths` by `rules`. Matching reads the filesystem, so it happens before the store is
+/// locked; tags the paths gained meanwhile count as unchanged.
+fn apply(
+    rules: &[CompiledTagRule],
+    paths: BTreeSet<String>,
+    dry_run: bool,
+) -> anyhow::Result<AutoTagReport> {
+    let colors = rules
+        .iter()
+        .map(|rule| resolve_color(rule.rule.color.as_deref().unwrap_or(DEFAULT_TAG_COLOR)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let matches: Vec<TaggedPath> = paths
+        .iter()
+        .flat_map(|path| {
+            rules
+                .iter()
+                .zip(&colors)
+                .filter(|(rule, _)| rule.matches(Path::new(path)))
+                .map(|(rule, color)| TaggedPath {
+                    path: path.clone(),
+                    tag: rule.rule.tag.clone(),
+                    color: color.clone(),
+                    inherited: false,
+                })
+        })
+        .collect();
+    let mut report = AutoTagReport {
+        dry_run,
+        candidates: paths.len(),
+        ..AutoTagReport::default()
+    };
+    let mut store = STORE.inner.lock();
+    sort_matches(&store.tags, matches, &mut report);
+    if dry_run || report.added.is_empty() {
+        return Ok(report);
+    }
+    store.tags.extend(report.added.iter().cloned());
+    STORE.persist(&mut store)?;
+    drop(store);
+    for tagged in &report.added {
+        events::publish(
+            StoreEvent::new(EventKind::Tag, StoreChange::Added, &tagged.path).with_tag(&tagged.tag),
+        );
+        hooks::fire(
+            HookEvent::TagAdded,
+            &[
+                ("path", &tagged.path),
+                ("tag", &tagged.tag),
+                ("color", &tagged.color),
+            ],
+        );
+    }
+    Ok(report)
+}
+
+/// Splits `matches` into tags to add and ones `tags`, or an earlier match, already carries.
+fn sort_matches(tags: &[TaggedPath], matches: Vec<TaggedPath>, report: &mut AutoTagReport) {
+    for tagged in matches {
+        let present = tags
+            .iter()
+            .chain(&report.added)
+            .any(|entry| entry.path == tagged.path && entry.tag.eq_ignore_ascii_case(&tagged.tag));
+        match present {
+            true => report.unchanged += 1,
+            false => report.added.push(tagged),
+        }
+    }
+}
+
+/// Applies `rules` (by default, `projects.tag_rules`) to every registered project, favorite,
+/// and recent.
+pub(crate) fn auto_tag(rules: &[TagRule], dry_run: bool) -> anyhow::Result<AutoTagReport> {
+    let rules = match rules.is_empty() {
+        true => config::config().projects.tag_rules.clone(),
+        false => rules.to_vec(),
+    };
+    if rules.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("no tag rules to apply")
+            .with_hint("pass rules or add [[projects.tag_rules]] to the config"));
+    }
+    let rules = compile_tag_rules(&rules)?;
+    let paths = {
+        let store = STORE.inner.lock();
+        store
+            .projects
+            .iter()
+            .map(|project| project.path.clone())
+            .chain(store.favorites.iter().cloned())
+            .chain(store.recents.iter().map(|recent| recent.path.clone()))
+            .collect()
+    };
+    apply(&rules, paths, dry_run)
+}
+
+/// Applies `projects.tag_rules` to the projects a scan just found. Returns the tags added.
+pub(crate) fn tag_scanned(paths: BTreeSet<String>) -> anyhow::Result<Vec<TaggedPath>> {
+    let rules = config::config().projects.tag_rules.clone();
+    if rules.is_empty() || paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(apply(&compile_tag_rules(&rules)?, paths, false)?.added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn temp_root() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("terminaut-autotag-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn rule(tag: &str, glob: Option<String>, marker: Option<&str>) -> TagRule {
+        TagRule {
+            tag: tag.to_string(),
+            glob,
+            marker: marker.map(str::to_string),
+            color: None,
+        }
+    }
+
+    fn compiled(rule: TagRule) -> CompiledTagRule {
+        compile_tag_rules(&[rule]).unwrap().remove(0)
+    }
+
+    fn tagged(path: &str, tag: &str) -> TaggedPath {
+        TaggedPath {
+            path: path.to_string(),
+            tag: tag.to_string(),
+            color: DEFAULT_TAG_COLOR.to_string(),
+            inherited: false,
+        }
+    }
+
+    #[test]
+    fn globs_match_a_path_or_any_of_its_ancestors() {
+        let acme = compiled(rule("acme", Some("/work/clients/acme".into()), None));
+        assert!(acme.matches(Path::new("/work/clients/acme")));
+        assert!(acme.matches(Path::new("/work/clients/acme/api/src")));
+        assert!(!acme.matches(Path::new("/work/clients/acme-old")));
+        assert!(!acme.matches(Path::new("/work/clients")));
+    }
+
+    #[test]
+    fn glob_stars_stay_within_one_component() {
+        let clients = compiled(rule("client", Some("/work/*/acme".into()), None));
+        assert!(clients.matches(Path::new("/work/clients/acme/api")));
+        assert!(!clients.matches(Path::new("/work/a/b/acme")));
+    }
+
+    #[test]
+    fn markers_must_sit_directly_inside_the_path() {
+        let root = temp_root();
+        std::fs::create_dir_all(root.join("tool/src")).unwrap();
+        std::fs::write(root.join("tool/Cargo.toml"), "").unwrap();
+        let rust = compiled(rule("rust", None, Some("Cargo.toml")));
+        assert!(rust.matches(&root.join("tool")));
+        assert!(!rust.matches(&root.join("tool/src")));
+        assert!(!rust.matches(&root.join("missing")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rules_with_a_glob_and_a_marker_need_both() {
+        let root = temp_root();
+        for dir in ["clients/api", "clients/site", "oss/tool"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("clients/api/Cargo.toml"), "").unwrap();
+        std::fs::write(root.join("oss/tool/Cargo.toml"), "").unwrap();
+        let both = compiled(rule(
+            "client-rust",
+            Some(format!("{}/clients/*", root.display())),
+            Some("Cargo.toml"),
+        ));
+        assert!(both.matches(&root.join("clients/api")));
+        assert!(!both.matches(&root.join("clients/site")));
+        assert!(!both.matches(&root.join("oss/tool")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rules_need_a_glob_or_a_marker() {
+        let err = compile_tag_rules(&[rule("empty", None, None)])
+            .err()
+            .unwrap();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn rules_with_bad_tags_or_globs_are_rejected() {
+        let err = compile_tag_rules(&[rule("client//acme", None, Some("x"))])
+            .err()
+            .unwrap();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        assert!(compile_tag_rules(&[rule("acme", Some("/work/[".into()), None)]).is_err());
+    }
+
+    #[test]
+    fn tags_already_carried_count_as_unchanged() {
+        let mut report = AutoTagReport::default();
+        sort_matches(
+            &[tagged("/work/api", "Rust")],
+            vec![tagged("/work/api", "rust"), tagged("/work/site", "rust")],
+            &mut report,
+        );
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.added[0].path, "/work/site");
+    }
+
+    #[test]
+    fn overlapping_rules_add_a_tag_once() {
+        let mut report = AutoTagReport::default();
+        sort_matches(
+            &[],
+            vec![tagged("/work/api", "rust"), tagged("/work/api", "RUST")],
+            &mut report,
+        );
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.unchanged, 1);
+    }
+}
@@ -0,0 +1,285 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use uuid::Uuid;
+
+use crate::CoreError;
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const LABEL: &str = "org.terminaut.autostart";
+
+/// Login items can't be taken back by skipping a save, so read-only mode refuses them.
+fn ensure_writable() -> anyhow::Result<()> {
+    if crate::read_only() {
+        anyhow::bail!(CoreError::invalid_input(
+            "login items cannot be changed in read-only mode"
+        ));
+    }
+    Ok(())
+}
+
+/// The CLI that login items run: this executable when it is the CLI, else `term-core-cli`
+/// next to it or on `PATH`.
+fn cli_path() -> anyhow::Result<PathBuf> {
+    let name = if cfg!(windows) {
+        "term-core-cli.exe"
+    } else {
+        "term-core-cli"
+    };
+    let current = std::env::current_exe().ok();
+    current
+        .as_ref()
+        .filter(|exe| exe.file_name().is_some_and(|file| file == name))
+        .cloned()
+        .or_else(|| {
+            let sibling = current.as_ref()?.parent()?.join(name);
+            sibling.is_file().then_some(sibling)
+        })
+        .or_else(|| crate::launcher::find_program(name))
+        .ok_or_else(|| {
+            CoreError::not_found("term-core-cli was not found")
+                .with_hint("install it on PATH so login items can launch profiles")
+                .into()
+        })
+}
+
+fn launch_args(id: Uuid) -> [String; 3] {
+    [
+        String::from("profiles"),
+        String::from("launch"),
+        id.to_string(),
+    ]
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A launchd agent that runs the CLI once when the user logs in.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launchd_plist(cli: &Path, id: Uuid) -> String {
+    let arguments: String = std::iter::once(cli.display().to_string())
+        .chain(launch_args(id))
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+    <key>Label</key>
+    <string>{LABEL}.{id}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"
+    )
+}
+
+/// A systemd user unit started with the graphical session. `RemainAfterExit` keeps systemd
+/// from stopping the terminal the CLI leaves behind.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn systemd_unit(cli: &Path, id: Uuid) -> String {
+    let exec = std::iter::once(cli.display().to_string())
+        .chain(launch_args(id))
+        .map(|arg| {
+            format!(
+                "\"{}\"",
+                arg.replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('%', "%%")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]
+Description=Terminaut: launch profile {id} at login
+PartOf=graphical-session.target
+After=graphical-session.target
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+ExecStart={exec}
+
+[Install]
+WantedBy=graphical-session.target
+"
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn plist_path(id: Uuid) -> anyhow::Result<PathBuf> {
+    let home = dirs::home_dir().context("no home directory")?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{LABEL}.{id}.plist")))
+}
+
+#[cfg(target_os = "macos")]
+fn install_entry(id: Uuid) -> anyhow::Result<String> {
+    let path = plist_path(id)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, launchd_plist(&cli_path()?, id))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn remove_entry(id: Uuid) -> anyhow::Result<()> {
+    remove_file(&plist_path(id)?)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn unit_dir() -> anyhow::Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .context("no config directory")?
+        .join("systemd/user"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn unit_name(id: Uuid) -> String {
+    format!("terminaut-autostart-{id}.service")
+}
+
+/// Asks the user's systemd to pick up unit changes; it also reads them at the next login.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn reload_systemd() {
+    let reloaded = std::process::Command::new("systemctl")
+        .args(["--user", "daemon-reload"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    if !reloaded.is_ok_and(|status| status.success()) {
+        tracing::debug!(
+            "systemctl --user daemon-reload did not run; the unit applies at next login"
+        );
+    }
+}
+
+/// Writes the unit and enables it the way `systemctl --user enable` does, by linking it
+/// from the target's `.wants` directory.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn install_entry(id: Uuid) -> anyhow::Result<String> {
+    let dir = unit_dir()?;
+    let wants = dir.join("graphical-session.target.wants");
+    std::fs::create_dir_all(&wants)
+        .with_context(|| format!("failed to create {}", wants.display()))?;
+    let unit = dir.join(unit_name(id));
+    std::fs::write(&unit, systemd_unit(&cli_path()?, id))
+        .with_context(|| format!("failed to write {}", unit.display()))?;
+    let link = wants.join(unit_name(id));
+    remove_file(&link)?;
+    std::os::unix::fs::symlink(&unit, &link)
+        .with_context(|| format!("failed to enable {}", unit.display()))?;
+    reload_systemd();
+    Ok(unit.display().to_string())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn remove_entry(id: Uuid) -> anyhow::Result<()> {
+    let dir = unit_dir()?;
+    remove_file(
+        &dir.join("graphical-session.target.wants")
+            .join(unit_name(id)),
+    )?;
+    remove_file(&dir.join(unit_name(id)))?;
+    reload_systemd();
+    Ok(())
+}
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(windows)]
+fn run_value(id: Uuid) -> String {
+    format!("Terminaut {id}")
+}
+
+#[cfg(windows)]
+fn install_entry(id: Uuid) -> anyhow::Result<String> {
+    let command = std::iter::once(cli_path()?.display().to_string())
+        .chain(launch_args(id))
+        .map(|arg| format!("\"{arg}\""))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = std::process::Command::new("reg")
+        .args(["add", RUN_KEY, "/v", &run_value(id), "/t", "REG_SZ", "/d"])
+        .arg(&command)
+        .arg("/f")
+        .output()
+        .context("failed to run reg")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "reg add failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(format!(r"{RUN_KEY}\{}", run_value(id)))
+}
+
+#[cfg(windows)]
+fn remove_entry(id: Uuid) -> anyhow::Result<()> {
+    // `reg delete` fails when the value is already gone, which is the state we want.
+    let _ = std::process::Command::new("reg")
+        .args(["delete", RUN_KEY, "/v", &run_value(id), "/f"])
+        .output()
+        .context("failed to run reg")?;
+    Ok(())
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+fn remove_file(path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(anyhow::Error::new(err).context(format!("failed to remove {}", path.display())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Registers a login item that launches profile `id`, returning where it was written.
+pub(crate) fn install(id: Uuid) -> anyhow::Result<String> {
+    ensure_writable()?;
+    install_entry(id)
+}
+
+/// Removes the login item for profile `id`, if there is one.
+pub(crate) fn uninstall(id: Uuid) -> anyhow::Result<()> {
+    ensure_writable()?;
+    remove_entry(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_items_quote_the_cli_path() {
+        let id = Uuid::nil();
+        let cli = Path::new("/opt/my tools/term-core-cli");
+
+        let unit = systemd_unit(cli, id);
+        assert!(unit.contains(&format!(
+            "ExecStart=\"/opt/my tools/term-core-cli\" \"profiles\" \"launch\" \"{id}\"\n"
+        )));
+        assert!(unit.contains("RemainAfterExit=yes"));
+
+        let plist = launchd_plist(Path::new("/Apps/R&D/term-core-cli"), id);
+        assert!(plist.contains("<string>/Apps/R&amp;D/term-core-cli</string>"));
+        assert!(plist.contains(&format!("<string>{LABEL}.{id}</string>")));
+    }
+}
@@ -0,0 +1,235 @@
+use std::any::Any;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+/// Where the persisted state (favorites, recents, tags, profiles, ...) is kept. The state is
+/// a single JSON document; backends only load and save it whole.
+///
+/// The default is [`FileBackend`] on platforms with a filesystem and [`MemoryBackend`] on
+/// `wasm32`. Embedders swap it with [`set_state_backend`], e.g. for IndexedDB in a browser.
+pub trait StateBackend: Send + Sync {
+    /// The saved document, or `None` when nothing has been saved yet.
+    fn load(&self) -> anyhow::Result<Option<String>>;
+    fn save(&self, json: &str) -> anyhow::Result<()>;
+    /// Where the state lives, for error messages (a path, a database name, ...).
+    fn location(&self) -> String;
+    /// Moves a saved document that does not parse out of the way so a new one can be saved,
+    /// returning where it went. Backends that cannot keep a copy return `None`, and the
+    /// store then refuses to save over the document.
+    fn quarantine(&self) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+    /// Keeps other processes sharing the document from saving until the returned guard is
+    /// dropped. The store holds it from reading the saved generation until its own save is
+    /// written. Backends only one process uses need no lock.
+    fn lock(&self) -> anyhow::Result<Box<dyn Any>> {
+        Ok(Box::new(()))
+    }
+}
+
+/// A JSON file on disk.
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl StateBackend for FileBackend {
+    fn load(&self) -> anyhow::Result<Option<String>> {
+        if !self.path.is_file() {
+            return Ok(None);
+        }
+        std::fs::read_to_string(&self.path)
+            .map(Some)
+            .with_context(|| format!("failed to read state file at {}", self.path.display()))
+    }
+
+    /// Writes a temporary file beside the state file, syncs it, and renames it over the state
+    /// file, so a crash leaves either the old document or the new one, never a torn one.
+    fn save(&self, json: &str) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let temp = PathBuf::from(temp);
+        let written = (|| {
+            let mut file = std::fs::File::create_new(&temp)?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&temp, &self.path)
+        })();
+        if let Err(err) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(err)
+                .with_context(|| format!("failed to save state file at {}", self.path.display()));
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Renames the file to `state.json.corrupt-<UTC time>`, numbered when that name is taken,
+    /// so earlier quarantined copies are kept.
+    fn quarantine(&self) -> anyhow::Result<Option<String>> {
+        let mut base = self.path.clone().into_os_string();
+        base.push(format!(
+            ".corrupt-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+        ));
+        let moved_to = (1..)
+            .map(|n| match n {
+                1 => PathBuf::from(&base),
+                n => {
+                    let mut numbered = base.clone();
+                    numbered.push(format!("-{n}"));
+                    PathBuf::from(numbered)
+                }
+            })
+            .find(|candidate| candidate.symlink_metadata().is_err())
+            .expect("an unused name");
+        std::fs::rename(&self.path, &moved_to)
+            .with_context(|| format!("failed to move {} aside", self.path.display()))?;
+        Ok(Some(moved_to.display().to_string()))
+    }
+
+    /// An exclusive lock on `state.json.lock` beside the file, released when the guard (the
+    /// open lock file) is dropped.
+    fn lock(&self) -> anyhow::Result<Box<dyn Any>> {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("failed to lock {}", path.display()))?;
+        Ok(Box::new(file))
+    }
+}
+
+/// Keeps the document in memory; nothing survives the process.
+#[derive(Default)]
+pub struct MemoryBackend {
+    json: Mutex<Option<String>>,
+}
+
+impl MemoryBackend {
+    /// Starts from a previously saved document, e.g. one read from browser storage.
+    pub fn with_state(json: impl Into<String>) -> Self {
+        Self {
+            json: Mutex::new(Some(json.into())),
+        }
+    }
+}
+
+impl StateBackend for MemoryBackend {
+    fn load(&self) -> anyhow::Result<Option<String>> {
+        Ok(self.json.lock().clone())
+    }
+
+    fn save(&self, json: &str) -> anyhow::Result<()> {
+        *self.json.lock() = Some(json.to_string());
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        String::from("memory")
+    }
+}
+
+static BACKEND: OnceCell<Box<dyn StateBackend>> = OnceCell::new();
+
+/// Installs the backend the store loads from and saves to. Must be called before the first
+/// api call that touches stored state; fails once the store has been loaded.
+pub fn set_state_backend(backend: Box<dyn StateBackend>) -> anyhow::Result<()> {
+    BACKEND
+        .set(backend)
+        .map_err(|_| anyhow::anyhow!("the state backend is already in use"))
+}
+
+pub(crate) fn state_backend() -> &'static dyn StateBackend {
+    BACKEND.get_or_init(default_backend).as_ref()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_backend() -> Box<dyn StateBackend> {
+    if crate::ephemeral() {
+        return Box::new(MemoryBackend::default());
+    }
+    Box::new(FileBackend::new(crate::Store::default_store_path()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_backend() -> Box<dyn StateBackend> {
+    Box::new(MemoryBackend::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_backend_round_trips() {
+        let backend = MemoryBackend::default();
+        assert_eq!(backend.load().unwrap(), None);
+        backend.save("{\"favorites\":[]}").unwrap();
+        assert_eq!(
+            backend.load().unwrap().as_deref(),
+            Some("{\"favorites\":[]}")
+        );
+    }
+
+    #[test]
+    fn file_backend_replaces_the_file_whole() {
+        let dir = std::env::temp_dir().join(format!("terminaut-backend-{}", uuid::Uuid::new_v4()));
+        let backend = FileBackend::new(dir.join("state.json"));
+        backend.save("{\"favorites\":[\"/a\"]}").unwrap();
+        backend.save("{}").unwrap();
+        assert_eq!(backend.load().unwrap().as_deref(), Some("{}"));
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["state.json"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_backend_quarantines_beside_the_file() {
+        let dir = std::env::temp_dir().join(format!("terminaut-backend-{}", uuid::Uuid::new_v4()));
+        let backend = FileBackend::new(dir.join("state.json"));
+        backend.save("{not json").unwrap();
+        let moved_to = backend.quarantine().unwrap().unwrap();
+        assert!(PathBuf::from(&moved_to)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("state.json.corrupt-"));
+        assert_eq!(backend.load().unwrap(), None);
+        assert_eq!(std::fs::read_to_string(&moved_to).unwrap(), "{not json");
+
+        backend.save("{also not json").unwrap();
+        let moved_again = backend.quarantine().unwrap().unwrap();
+        assert_ne!(moved_again, moved_to);
+        assert_eq!(std::fs::read_to_string(&moved_to).unwrap(), "{not json");
+        assert_eq!(MemoryBackend::default().quarantine().unwrap(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+/// Most items handed over at once.
+pub(crate) const BATCH_SIZE: usize = 256;
+
+/// A partial batch is handed over once this long has passed since the last one, so a slow
+/// source (a network share, a cold disk) still shows progress.
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Hands `items` to `on_batch` in order, at most `size` at a time, flushing early after
+/// [`BATCH_INTERVAL`]. Stops when `on_batch` returns `false`; returns whether every item was
+/// delivered. An empty source delivers nothing.
+pub(crate) fn for_each_batch<T>(
+    items: impl IntoIterator<Item = T>,
+    size: usize,
+    mut on_batch: impl FnMut(Vec<T>) -> bool,
+) -> bool {
+    let size = size.max(1);
+    let mut batch = Vec::with_capacity(size);
+    let mut flushed = Instant::now();
+    for item in items {
+        batch.push(item);
+        if batch.len() >= size || flushed.elapsed() >= BATCH_INTERVAL {
+            if !on_batch(std::mem::replace(&mut batch, Vec::with_capacity(size))) {
+                return false;
+            }
+            flushed = Instant::now();
+        }
+    }
+    batch.is_empty() || on_batch(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_are_bounded_and_stoppable() {
+        let mut sizes = Vec::new();
+        assert!(for_each_batch(0..7, 3, |batch| {
+            sizes.push(batch.len());
+            true
+        }));
+        assert_eq!(sizes, [3, 3, 1]);
+
+        let mut seen = Vec::new();
+        assert!(!for_each_batch(0..7, 3, |batch| {
+            seen.extend(batch);
+            false
+        }));
+        assert_eq!(seen, [0, 1, 2]);
+        assert!(for_each_batch(std::iter::empty::<u8>(), 3, |_| false));
+    }
+}
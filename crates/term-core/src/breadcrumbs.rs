@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::project_marker;
+use crate::roots::{list_roots, VolumeRoot};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breadcrumb {
+    pub path: String,
+    /// `~` for the home directory, the volume name for mount points, the folder name otherwise.
+    pub name: String,
+    pub is_home: bool,
+    pub is_volume_root: bool,
+    pub is_project_root: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_marker: Option<String>,
+}
+
+fn display_name(
+    ancestor: &Path,
+    home: Option<&Path>,
+    volumes: &[VolumeRoot],
+) -> (String, bool, bool) {
+    if home.is_some_and(|home| home == ancestor) {
+        return (String::from("~"), true, false);
+    }
+    if let Some(volume) = volumes
+        .iter()
+        .find(|volume| Path::new(&volume.path) == ancestor)
+    {
+        return (volume.name.clone(), false, true);
+    }
+    let name = ancestor
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| ancestor.display().to_string());
+    // Filesystem roots without a matching volume entry still count as volume roots.
+    (name, false, ancestor.parent().is_none())
+}
+
+pub(crate) fn ancestors(path: &Path) -> Vec<Breadcrumb> {
+    let home = dirs::home_dir();
+    let volumes = list_roots().unwrap_or_default();
+    let mut crumbs: Vec<Breadcrumb> = path
+        .ancestors()
+        .map(|ancestor| {
+            let (name, is_home, is_volume_root) = display_name(ancestor, home.as_deref(), &volumes);
+            let marker = project_marker(ancestor);
+            Breadcrumb {
+                path: ancestor.display().to_string(),
+                name,
+                is_home,
+                is_volume_root,
+                is_project_root: marker.is_some(),
+                project_marker: marker,
+            }
+        })
+        .collect();
+    crumbs.reverse();
+    crumbs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup() -> Vec<VolumeRoot> {
+        vec![VolumeRoot {
+            path: String::from("/Volumes/Backup"),
+            name: String::from("Backup"),
+            fs_type: None,
+            is_network: false,
+            is_removable: true,
+        }]
+    }
+
+    #[test]
+    fn the_home_directory_is_a_tilde() {
+        let home = Path::new("/Users/me");
+        assert_eq!(
+            display_name(home, Some(home), &backup()),
+            (String::from("~"), true, false)
+        );
+        assert_eq!(
+            display_name(home, None, &backup()),
+            (String::from("me"), false, false)
+        );
+    }
+
+    #[test]
+    fn volumes_use_their_names() {
+        assert_eq!(
+            display_name(Path::new("/Volumes/Backup"), None, &backup()),
+            (String::from("Backup"), false, true)
+        );
+        assert_eq!(
+            display_name(Path::new("/Volumes/Backup/photos"), None, &backup()),
+            (String::from("photos"), false, false)
+        );
+    }
+
+    #[test]
+    fn a_home_on_a_volume_is_still_home() {
+        let home = Path::new("/Volumes/Backup");
+        assert_eq!(
+            display_name(home, Some(home), &backup()),
+            (String::from("~"), true, false)
+        );
+    }
+
+    #[test]
+    fn filesystem_roots_without_a_volume_are_volume_roots() {
+        assert_eq!(
+            display_name(Path::new("/"), Some(Path::new("/Users/me")), &[]),
+            (String::from("/"), false, true)
+        );
+    }
+
+    #[test]
+    fn crumbs_run_from_the_root_down_to_the_path() {
+        let crumbs = ancestors(Path::new("/Users/me/dev"));
+        let paths: Vec<&str> = crumbs.iter().map(|crumb| crumb.path.as_str()).collect();
+        assert_eq!(paths, ["/", "/Users", "/Users/me", "/Users/me/dev"]);
+        assert!(crumbs[0].is_volume_root);
+        assert_eq!(crumbs[3].name, "dev");
+        assert!(crumbs
+            .iter()
+            .all(|crumb| crumb.is_project_root == crumb.project_marker.is_some()));
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ignore::WalkState;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::ranking::RankBreakdown;
+use crate::search::{build_globs, matches_globs, walker};
+use crate::{SearchOptions, SearchResult};
+
+/// Directories one walk keeps; a walk that finds more stops there and says so.
+const MAX_CANDIDATES: usize = 500_000;
+
+/// Walks kept at once; caching another drops the oldest.
+const MAX_CACHED_WALKS: usize = 16;
+
+/// A directory found by a search walk, before matching.
+pub(crate) struct Candidate {
+    pub(crate) path: String,
+    pub(crate) name: String,
+    pub(crate) relative_path: String,
+}
+
+impl Candidate {
+    /// A folder-name match of this directory, not yet ranked.
+    pub(crate) fn to_result(&self, score: i64, indices: Vec<usize>) -> SearchResult {
+        SearchResult {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            relative_path: self.relative_path.clone(),
+            score,
+            indices,
+            rank: RankBreakdown::default(),
+            matched_text: None,
+        }
+    }
+}
+
+/// Everything one walk of a root found.
+pub(crate) struct Candidates {
+    pub(crate) entries: Vec<Candidate>,
+    /// The walk stopped at [`MAX_CANDIDATES`] or at the search's time budget.
+    pub(crate) truncated: bool,
+    /// It was the time budget; a later walk may get further, so this one isn't cached.
+    out_of_time: bool,
+}
+
+/// A root and the options that change which directories its walk visits. Matching and
+/// ranking options don't, so searches that differ only in those share a walk.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct WalkKey {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    respect_gitignore: bool,
+    follow_symlinks: bool,
+    hidden: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl WalkKey {
+    fn new(root: &Path, options: &SearchOptions) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            max_depth: options.max_depth,
+            respect_gitignore: options.respect_gitignore,
+            follow_symlinks: options.follow_symlinks,
+            hidden: options.hidden,
+            include: options.include.clone(),
+            exclude: options.exclude.clone(),
+        }
+    }
+}
+
+struct Cached {
+    candidates: Arc<Candidates>,
+    walked: Instant,
+}
+
+static CACHE: Lazy<Mutex<HashMap<WalkKey, Cached>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Walks under way, by id: their root, and whether an invalidation touching it arrived
+/// meanwhile, in which case the result isn't cached.
+static WALKING: Lazy<Mutex<HashMap<u64, (PathBuf, bool)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_WALK: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a change at `path` can alter what a walk of `root` finds.
+fn affects(path: &Path, root: &Path) -> bool {
+    path.starts_with(root) || root.starts_with(path)
+}
+
+/// Roots a watcher in this process invalidates walks for; see [`watching`].
+static WATCHED: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Marks `roots` as watched until the guard is dropped. The watcher must call [`invalidate`]
+/// for every change below them.
+pub(crate) fn watching(roots: &[PathBuf]) -> Watching {
+    WATCHED.lock().extend(roots.iter().cloned());
+    Watching(roots.to_vec())
+}
+
+pub(crate) struct Watching(Vec<PathBuf>);
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        let mut watched = WATCHED.lock();
+        for root in &self.0 {
+            if let Some(at) = watched.iter().position(|watched| watched == root) {
+                watched.remove(at);
+            }
+        }
+    }
+}
+
+/// How long a walk of `root` is reused: `search.cache_ttl_secs`, with `0` turning the cache
+/// off; unset, [`DEFAULT_CACHE_TTL_SECS`](crate::config::DEFAULT_CACHE_TTL_SECS) below a
+/// watched root and no caching elsewhere.
+pub(crate) fn cache_ttl(root: &Path) -> Option<Duration> {
+    let configured = crate::config::config().search.cache_ttl_secs;
+    let watched = || {
+        WATCHED
+            .lock()
+            .iter()
+            .any(|watched| root.starts_with(watched))
+    };
+    ttl(configured, watched)
+}
+
+fn ttl(configured: Option<u64>, watched: impl FnOnce() -> bool) -> Option<Duration> {
+    let secs = match configured {
+        Some(secs) => secs,
+        None if watched() => crate::config::DEFAULT_CACHE_TTL_SECS,
+        None => 0,
+    };
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Every directory below `root`, including `root` itself, that the search walk visits with
+/// `options`.
+fn walk(root: &Path, options: &SearchOptions) -> anyhow::Result<Candidates> {
+    let include = build_globs(&options.include)?;
+    let entries = Mutex::new(Vec::new());
+    let full = AtomicBool::new(false);
+    let deadline = options.time_budget();
+    let out_of_time = AtomicBool::new(false);
+    walker(root, options)?.build_parallel().run(|| {
+        let (include, entries, full) = (include.as_ref(), &entries, &full);
+        let out_of_time = &out_of_time;
+        Box::new(move |entry| {
+            if full.load(Ordering::Relaxed) || out_of_time.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            if deadline.is_some_and(|deadline| deadline.passed()) {
+                out_of_time.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                return WalkState::Continue;
+            }
+            if include.is_some_and(|include| !matches_globs(include, root, &entry)) {
+                return WalkState::Continue;
+            }
+            let Some(name) = entry.file_name().to_str() else {
+                return WalkState::Continue;
+            };
+            let mut entries = entries.lock();
+            if entries.len() >= MAX_CANDIDATES {
+                full.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            entries.push(Candidate {
+                path: entry.path().display().to_string(),
+                name: name.to_string(),
+                relative_path: entry
+                    .path()
+                    .strip_prefix(root)
+                    .map(|relative| relative.display().to_string())
+                    .unwrap_or_default(),
+            });
+            WalkState::Continue
+        })
+    });
+    let out_of_time = out_of_time.into_inner();
+    Ok(Candidates {
+        entries: entries.into_inner(),
+        truncated: full.into_inner() || out_of_time,
+        out_of_time,
+    })
+}
+
+/// The directories below `root` for `options`, from a walk less than [`cache_ttl`] old when
+/// there is one, otherwise from a fresh walk that is then kept.
+pub(crate) fn candidates(root: &Path, options: &SearchOptions) -> anyhow::Result<Arc<Candidates>> {
+    cached_walk(root, options, cache_ttl(root))
+}
+
+fn cached_walk(
+    root: &Path,
+    options: &SearchOptions,
+    ttl: Option<Duration>,
+) -> anyhow::Result<Arc<Candidates>> {
+    let Some(ttl) = ttl else {
+        return Ok(Arc::new(walk(root, options)?));
+    };
+    let key = WalkKey::new(root, options);
+    if let Some(cached) = CACHE.lock().get(&key) {
+        if cached.walked.elapsed() < ttl {
+            return Ok(Arc::clone(&cached.candidates));
+        }
+    }
+    let id = NEXT_WALK.fetch_add(1, Ordering::Relaxed);
+    WALKING.lock().insert(id, (root.to_path_buf(), false));
+    let walked = Instant::now();
+    let result = walk(root, options);
+    let stale = WALKING.lock().remove(&id).is_some_and(|(_, stale)| stale);
+    let candidates = Arc::new(result?);
+    // Something changed below the root while walking, or the walk ran out of time; the next
+    // search walks again.
+    if stale || candidates.out_of_time {
+        return Ok(candidates);
+    }
+    let mut cache = CACHE.lock();
+    cache.retain(|_, cached| cached.walked.elapsed() < ttl);
+    if cache.len() >= MAX_CACHED_WALKS {
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, cached)| cached.walked)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(
+        key,
+        Cached {
+            candidates: Arc::clone(&candidates),
+            walked,
+        },
+    );
+    Ok(candidates)
+}
+
+/// Forgets walks that `path` lies in or contains, after it was created, removed, or renamed.
+pub(crate) fn invalidate(path: &Path) {
+    for (root, stale) in WALKING.lock().values_mut() {
+        *stale |= affects(path, root);
+    }
+    CACHE.lock().retain(|key, _| !affects(path, &key.root));
+}
+
+/// Forgets every cached walk.
+pub(crate) fn invalidate_all() {
+    for (_, stale) in WALKING.lock().values_mut() {
+        *stale = true;
+    }
+    CACHE.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_are_cached_by_default_only_below_watched_roots() {
+        assert_eq!(ttl(None, || false), None);
+        assert_eq!(ttl(None, || true), Some(Duration::from_secs(30)));
+        assert_eq!(ttl(Some(0), || true), None);
+        assert_eq!(ttl(Some(5), || false), Some(Duration::from_secs(5)));
+
+        let root = std::env::temp_dir().join(format!("terminaut-watch-{}", uuid::Uuid::new_v4()));
+        let watched = || WATCHED.lock().iter().any(|w| root.join("x").starts_with(w));
+        let guard = watching(std::slice::from_ref(&root));
+        assert!(watched());
+        drop(guard);
+        assert!(!watched());
+    }
+
+    #[test]
+    fn walks_are_reused_until_invalidated() {
+        let ttl = Some(Duration::from_secs(60));
+        let candidates = |root: &Path, options: &SearchOptions| cached_walk(root, options, ttl);
+        let root = std::env::temp_dir().join(format!("terminaut-walk-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        let options = SearchOptions::default();
+        let first = candidates(&root, &options).unwrap();
+        assert_eq!(first.entries.len(), 2);
+
+        std::fs::create_dir(root.join("b")).unwrap();
+        assert!(Arc::ptr_eq(&first, &candidates(&root, &options).unwrap()));
+        let hidden = SearchOptions {
+            hidden: true,
+            ..SearchOptions::default()
+        };
+        assert!(!Arc::ptr_eq(&first, &candidates(&root, &hidden).unwrap()));
+
+        invalidate(&root.join("b"));
+        assert_eq!(candidates(&root, &options).unwrap().entries.len(), 3);
+        invalidate(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::WireFormat;
+
+/// Version of the `term_core_*` C functions; bumped when one is removed or changes its
+/// signature or ownership rules.
+pub const ABI_VERSION: u32 = 1;
+
+/// Version of the JSON shapes described by [`crate::api::json_schemas`]; bumped when a field
+/// is removed or changes meaning. Added fields don't bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Oldest [`SCHEMA_VERSION`] whose front-ends this build still serves.
+const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// What this build of the core offers, as returned by [`crate::api::capabilities`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// The crate version, e.g. `0.1.0`.
+    pub version: String,
+    pub abi_version: u32,
+    pub schema_version: u32,
+    pub min_schema_version: u32,
+    /// Cargo features compiled in, such as `fs` and `remote-api`.
+    pub features: Vec<String>,
+    /// Encodings accepted by [`crate::api::invoke_encoded`] and the `indexd` socket.
+    pub formats: Vec<WireFormat>,
+    /// Type names with a JSON schema.
+    pub schemas: Vec<String>,
+    /// Method names [`crate::api::invoke`] accepts.
+    pub methods: Vec<String>,
+}
+
+fn features() -> Vec<String> {
+    [
+        ("fs", cfg!(feature = "fs")),
+        ("keychain", cfg!(feature = "keychain")),
+        ("encryption", cfg!(feature = "encryption")),
+        ("clipboard", cfg!(feature = "clipboard")),
+        ("archives", cfg!(feature = "archives")),
+        ("remote-api", cfg!(feature = "remote-api")),
+        ("watch", cfg!(feature = "watch")),
+        ("msgpack", cfg!(feature = "msgpack")),
+        ("cbor", cfg!(feature = "cbor")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+pub(crate) fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        abi_version: ABI_VERSION,
+        schema_version: SCHEMA_VERSION,
+        min_schema_version: MIN_SCHEMA_VERSION,
+        features: features(),
+        formats: WireFormat::available(),
+        schemas: crate::schema::json_schemas().into_keys().collect(),
+        methods: crate::invoke::METHODS
+            .iter()
+            .map(|method| method.to_string())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_describe_this_build() {
+        let capabilities = capabilities();
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(capabilities.abi_version, ABI_VERSION);
+        assert!(capabilities.min_schema_version <= capabilities.schema_version);
+    }
+
+    #[test]
+    fn features_list_only_what_is_compiled_in() {
+        let features = capabilities().features;
+        assert_eq!(features.contains(&String::from("fs")), cfg!(feature = "fs"));
+        assert_eq!(
+            features.contains(&String::from("msgpack")),
+            cfg!(feature = "msgpack")
+        );
+        assert!(!features.contains(&String::from("default")));
+    }
+
+    #[test]
+    fn json_is_always_an_available_format() {
+        assert!(capabilities().formats.contains(&WireFormat::Json));
+    }
+
+    #[test]
+    fn schemas_and_methods_are_listed() {
+        let capabilities = capabilities();
+        assert!(capabilities.schemas.contains(&String::from("SearchResult")));
+        assert!(capabilities.methods.contains(&String::from("capabilities")));
+        assert_eq!(capabilities.methods.len(), crate::invoke::METHODS.len());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::CoreError;
+
+/// Whether there is no clipboard to write to: off macOS and Windows, the clipboard lives in
+/// the X11 server named by `DISPLAY`, which SSH sessions and CI runners don't have.
+#[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+fn headless(var: impl Fn(&str) -> Option<std::ffi::OsString>) -> bool {
+    cfg!(all(unix, not(target_os = "macos"))) && var("DISPLAY").is_none_or(|value| value.is_empty())
+}
+
+#[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+fn unavailable(reason: impl std::fmt::Display) -> CoreError {
+    CoreError::not_found(format!("no clipboard is available: {reason}"))
+        .with_hint("copy from a desktop session, or print the path instead")
+}
+
+/// Puts `text` on the system clipboard. On X11 and Wayland the text outlives this process
+/// only when a clipboard manager takes it over, which desktop sessions normally run.
+#[cfg(feature = "clipboard")]
+pub(crate) fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    if headless(|name| std::env::var_os(name)) {
+        anyhow::bail!(unavailable("DISPLAY is not set"));
+    }
+    let mut clipboard = arboard::Clipboard::new().map_err(unavailable)?;
+    clipboard
+        .set_text(text)
+        .context("failed to write to the clipboard")
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub(crate) fn copy_to_clipboard(_text: &str) -> anyhow::Result<()> {
+    anyhow::bail!(CoreError::invalid_input(
+        "this build does not include clipboard support"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    #[test]
+    fn sessions_without_a_display_are_headless() {
+        let display =
+            |value: &'static str| move |name: &str| (name == "DISPLAY").then(|| value.into());
+        let linux = cfg!(all(unix, not(target_os = "macos")));
+        assert_eq!(headless(|_| None), linux);
+        assert_eq!(headless(display("")), linux);
+        assert!(!headless(display(":0")));
+    }
+
+    #[test]
+    fn copying_without_a_clipboard_fails_cleanly() {
+        if cfg!(feature = "clipboard") && !headless(|name| std::env::var_os(name)) {
+            // A desktop session has a clipboard to write to; leave it alone.
+            return;
+        }
+        let err = copy_to_clipboard("/srv/api").unwrap_err();
+        let expected = match cfg!(feature = "clipboard") {
+            true => ErrorCode::NotFound,
+            false => ErrorCode::InvalidInput,
+        };
+        assert_eq!(error_code(&err), expected);
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// A command line split into the words a profile stores, as returned by
+/// [`crate::api::parse_command`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsedCommand {
+    /// The program, then its arguments. With `use_shell`, the whole line as one entry.
+    pub args: Vec<String>,
+    /// The line uses pipes, redirects, `;`/`&&`, variables, or globs, so only a shell can run
+    /// it as written.
+    pub use_shell: bool,
+}
+
+/// Characters that mean something to the shell outside quotes. `{` and `}` are left out so
+/// `{secret:NAME}` references stay plain arguments.
+const SHELL_SYNTAX: &[char] = &[
+    '|', '&', ';', '<', '>', '(', ')', '$', '`', '*', '?', '[', '\n',
+];
+
+/// Splits `line` into words the way a POSIX shell does: whitespace separates words, single
+/// quotes keep everything literal, and a backslash escapes the next character (inside double
+/// quotes only `"`, `\`, `$`, and `` ` ``). Also reports whether anything outside single
+/// quotes needs a shell to run.
+fn split(line: &str) -> anyhow::Result<(Vec<String>, bool)> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut needs_shell = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!(unterminated("'")),
+                    }
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => anyhow::bail!(unterminated("\"")),
+                        },
+                        Some(c) => {
+                            needs_shell |= matches!(c, '$' | '`');
+                            current.push(c);
+                        }
+                        None => anyhow::bail!(unterminated("\"")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+            },
+            c if c == ' ' || c == '\t' => words.extend(word.take()),
+            c => {
+                let current = word.get_or_insert_with(String::new);
+                needs_shell |= SHELL_SYNTAX.contains(&c)
+                    || (current.is_empty() && matches!(c, '~' | '#'))
+                    // `NAME=value program` sets the environment; `--flag=value` does not.
+                    || (c == '=' && words.is_empty() && !current.starts_with('-'));
+                current.push(c);
+            }
+        }
+    }
+    words.extend(word);
+    Ok((words, needs_shell))
+}
+
+fn unterminated(quote: &str) -> CoreError {
+    CoreError::invalid_input(format!("unterminated {quote} quote in command"))
+        .with_hint("close the quote, or escape it with a backslash")
+}
+
+/// Parses a command line into profile arguments. Lines that need a shell are kept whole, to be
+/// run by the shell as before.
+pub(crate) fn parse(line: &str) -> anyhow::Result<ParsedCommand> {
+    let (args, needs_shell) = split(line)?;
+    let parsed = match needs_shell {
+        true => ParsedCommand {
+            args: vec![line.trim().to_string()],
+            use_shell: true,
+        },
+        false => ParsedCommand {
+            args,
+            use_shell: false,
+        },
+    };
+    validate(&parsed.args)?;
+    Ok(parsed)
+}
+
+/// Rejects an empty program and arguments no process can receive.
+pub(crate) fn validate(args: &[String]) -> anyhow::Result<()> {
+    if args.first().is_none_or(|program| program.trim().is_empty()) {
+        anyhow::bail!(CoreError::invalid_input(
+            "the command has no program to run"
+        ));
+    }
+    if args.iter().any(|arg| arg.contains('\0')) {
+        anyhow::bail!(CoreError::invalid_input(
+            "command arguments cannot contain NUL characters"
+        ));
+    }
+    Ok(())
+}
+
+/// One argument as a single word for the platform shell that runs custom commands.
+pub(crate) fn quote(arg: &str) -> String {
+    quote_for(arg, cfg!(windows))
+}
+
+fn quote_for(arg: &str, windows: bool) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%{}".contains(c));
+    if plain {
+        arg.to_string()
+    } else if windows {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The line a terminal runs for `args`: each argument quoted, or joined as written with
+/// `use_shell`.
+pub(crate) fn join(args: &[String], use_shell: bool) -> String {
+    match use_shell {
+        true => args.join(" "),
+        false => args
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_split_like_a_shell() {
+        let parsed = parse(r#"git log --format="%h %s" 'it'\''s' a\ b --x=1"#).unwrap();
+        assert_eq!(
+            parsed.args,
+            ["git", "log", "--format=%h %s", "it's", "a b", "--x=1"]
+        );
+        assert!(!parsed.use_shell);
+        assert!(parse("cargo watch -x {secret:TOKEN}").is_ok_and(|parsed| !parsed.use_shell));
+
+        for line in [
+            "npm test && npm run dev",
+            "echo \"$HOME\"",
+            "ls *.rs",
+            "FOO=1 make",
+            "cat ~/notes",
+        ] {
+            let parsed = parse(line).unwrap();
+            assert!(parsed.use_shell, "{line}");
+            assert_eq!(parsed.args, [line]);
+        }
+        assert!(parse("echo 'unterminated").is_err());
+        assert!(parse("  ").is_err());
+
+        if cfg!(unix) {
+            let args = ["echo".to_string(), "it's".to_string(), "a b".to_string()];
+            assert_eq!(join(&args, false), r#"echo 'it'\''s' 'a b'"#);
+        }
+    }
+
+    #[test]
+    fn windows_quoting_escapes_embedded_quotes() {
+        assert_eq!(quote_for("C:/tools/nvim.exe", true), "C:/tools/nvim.exe");
+        assert_eq!(quote_for("my notes.md", true), r#""my notes.md""#);
+        assert_eq!(quote_for(r#"say "hi".md"#, true), r#""say \"hi\".md""#);
+        assert_eq!(quote_for("", true), r#""""#);
+        assert_eq!(quote_for("it's", false), r#"'it'\''s'"#);
+    }
+}
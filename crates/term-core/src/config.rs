@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use parking_lot::{RwLock, RwLockReadGuard};
+use serde::{Deserialize, Serialize};
+
+use crate::auto_tag::{compile_tag_rules, TagRule};
+use crate::hooks::HookEvent;
+use crate::launcher::KNOWN_TERMINALS;
+use crate::matching::MatcherKind;
+use crate::paths::SymlinkPolicy;
+use crate::ranking::{compile_rules, RankRule};
+use crate::recents::RecentsSort;
+use crate::search::build_globs;
+use crate::tag_colors::validate_palette;
+
+static CONFIG: Lazy<RwLock<Config>> = Lazy::new(|| {
+    crate::logging::install();
+    RwLock::new(load_or_default())
+});
+
+/// Why the config file last failed to load, while the defaults stand in for it.
+static LOAD_ERROR: RwLock<Option<String>> = RwLock::new(None);
+
+/// How long a watched root's walk is reused when `search.cache_ttl_secs` is unset.
+#[cfg(feature = "fs")]
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Default walk depth for directory search; `0` means unlimited.
+    pub max_depth: usize,
+    /// Globs excluded from search and grep unless a caller overrides them.
+    pub ignore: Vec<String>,
+    /// Boost, suppress, or pin paths in search ranking, applied in order.
+    pub rules: Vec<RankRule>,
+    /// Fuzzy matching backend: `skim` or `nucleo`.
+    pub matcher: MatcherKind,
+    /// Seconds the directories found by walking a root are reused by later searches of it,
+    /// unless something below it changes first; `0` walks for every search. Unset, walks are
+    /// reused for [`DEFAULT_CACHE_TTL_SECS`] only below roots this process watches (as
+    /// `indexd` does), since nothing else notices folders changed by other programs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+    /// Milliseconds a search walks before ranking the best matches found so far; `0` walks
+    /// the whole tree.
+    pub time_budget_ms: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            ignore: Vec::new(),
+            rules: Vec::new(),
+            matcher: MatcherKind::Skim,
+            cache_ttl_secs: None,
+            time_budget_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecentsConfig {
+    /// Oldest recents beyond this many are dropped.
+    pub cap: usize,
+    /// Order of `list_recents`: `frecency` or `recency`.
+    pub sort: RecentsSort,
+    /// Days after which a visit counts half as much toward a recent's score.
+    pub half_life_days: f64,
+}
+
+impl Default for RecentsConfig {
+    fn default() -> Self {
+        Self {
+            cap: 100,
+            sort: RecentsSort::Frecency,
+            half_life_days: 7.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectsConfig {
+    /// Files or directories whose presence marks a project root, checked in order.
+    pub markers: Vec<String>,
+    /// Directories scanned for the project registry when no roots are passed.
+    pub roots: Vec<String>,
+    /// How many levels below a root the scan looks for projects.
+    pub scan_depth: usize,
+    /// Age after which a configured root is rescanned by a refresh without explicit roots.
+    pub refresh_hours: u64,
+    /// Tags given to matching projects after each scan, and by `auto_tag` without rules.
+    pub tag_rules: Vec<TagRule>,
+}
+
+impl Default for ProjectsConfig {
+    fn default() -> Self {
+        Self {
+            markers: [
+                ".git",
+                "package.json",
+                "Cargo.toml",
+                "go.mod",
+                "bunfig.toml",
+            ]
+            .map(String::from)
+            .to_vec(),
+            roots: Vec::new(),
+            scan_depth: 3,
+            refresh_hours: 24,
+            tag_rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalConfig {
+    /// Terminal used when a launch doesn't name one.
+    pub default: Option<String>,
+    /// Seconds a profile's `pre_command` or `post_launch_command` may run before it is killed.
+    pub hook_timeout_secs: u64,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            default: None,
+            hook_timeout_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Whether normalized paths resolve symlinks (`resolve`) or keep them (`preserve`).
+    pub symlinks: SymlinkPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceMode {
+    /// Store changes are written to `state.json`.
+    #[default]
+    Disk,
+    /// Changes live only for the lifetime of the process.
+    Memory,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    pub persistence: PersistenceMode,
+    /// Encrypt `state.json` with a key kept in the OS keychain. Takes effect on the next save;
+    /// turning it off saves the state in plain text again.
+    pub encrypt: bool,
+    /// Save `state.json` without indentation, roughly half the size for large stores.
+    pub compact: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatsConfig {
+    /// Record local usage counts and timings in the state file. Nothing leaves the machine.
+    pub enabled: bool,
+    /// Weeks of counters kept before the oldest are dropped.
+    pub weeks: usize,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weeks: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagsConfig {
+    /// Treat a directory's tags as applying to everything below it: `tags_for` reports them
+    /// as inherited, and search ranks descendants as tagged.
+    pub inherit: bool,
+    /// Named tag colors as `#rrggbb`, added to (or replacing) the built-in names such as
+    /// `red` and `blue`, e.g. `accent-blue = "#3366ff"`.
+    pub palette: BTreeMap<String, String>,
+}
+
+/// User preferences read from `config.toml` in the platform config directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub search: SearchConfig,
+    pub recents: RecentsConfig,
+    pub projects: ProjectsConfig,
+    pub terminal: TerminalConfig,
+    pub paths: PathsConfig,
+    pub store: StoreConfig,
+    pub stats: StatsConfig,
+    pub tags: TagsConfig,
+    /// Shell commands run when an event fires, e.g. `favorite_added = ["notify-send added"]`.
+    pub hooks: BTreeMap<HookEvent, Vec<String>>,
+}
+
+impl Config {
+    fn validate(&self) -> anyhow::Result<()> {
+        build_globs(&self.search.ignore).context("search.ignore")?;
+        compile_rules(&self.search.rules).context("search.rules")?;
+        compile_tag_rules(&self.projects.tag_rules).context("projects.tag_rules")?;
+        validate_palette(&self.tags.palette).context("tags.palette")?;
+        if self.recents.cap == 0 {
+            anyhow::bail!("recents.cap must be at least 1");
+        }
+        if self.recents.half_life_days.is_nan() || self.recents.half_life_days <= 0.0 {
+            anyhow::bail!("recents.half_life_days must be greater than 0");
+        }
+        if self.terminal.hook_timeout_secs == 0 {
+            anyhow::bail!("terminal.hook_timeout_secs must be at least 1");
+        }
+        if self.stats.weeks == 0 {
+            anyhow::bail!("stats.weeks must be at least 1");
+        }
+        if self
+            .projects
+            .markers
+            .iter()
+            .any(|marker| marker.trim().is_empty())
+        {
+            anyhow::bail!("projects.markers must not contain empty names");
+        }
+        if let Some(terminal) = &self.terminal.default {
+            if !KNOWN_TERMINALS.contains(&terminal.to_ascii_lowercase().as_str()) {
+                anyhow::bail!(
+                    "terminal.default {terminal:?} is not one of terminal, iterm, ghostty, alacritty, windows-terminal, system"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn config_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("terminaut");
+    path.push("config.toml");
+    path
+}
+
+fn load() -> anyhow::Result<Config> {
+    let path = config_path();
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let config: Config =
+        toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+    config
+        .validate()
+        .with_context(|| format!("invalid {}", path.display()))?;
+    Ok(config)
+}
+
+/// The config file, or the defaults when it doesn't load. The failure is logged and kept for
+/// [`load_error`], so a typo doesn't quietly reset every setting.
+fn load_or_default() -> Config {
+    match load() {
+        Ok(config) => config,
+        Err(err) => {
+            tracing::error!("using the default config: {err:#}");
+            *LOAD_ERROR.write() = Some(format!("{err:#}"));
+            Config::default()
+        }
+    }
+}
+
+/// Why the config file didn't load, if the defaults are in use because of it.
+pub(crate) fn load_error() -> Option<String> {
+    Lazy::force(&CONFIG);
+    LOAD_ERROR.read().clone()
+}
+
+/// The configuration loaded at startup (or by the last `get_config`/`set_config`).
+pub(crate) fn config() -> RwLockReadGuard<'static, Config> {
+    CONFIG.read()
+}
+
+/// Re-reads the config file so edits made outside the process are picked up.
+pub(crate) fn get_config() -> anyhow::Result<Config> {
+    let config = load()?;
+    *CONFIG.write() = config.clone();
+    *LOAD_ERROR.write() = None;
+    Ok(config)
+}
+
+/// Reads one dotted key such as `search.max_depth`.
+pub(crate) fn get_config_value(key: &str) -> anyhow::Result<serde_json::Value> {
+    let config = serde_json::to_value(get_config()?)?;
+    key.split('.')
+        .try_fold(&config, |value, part| value.get(part))
+        .cloned()
+        .with_context(|| format!("unknown config key {key:?}"))
+}
+
+/// Parses `raw` as a TOML value, treating anything that isn't one as a bare string so
+/// `set terminal.default ghostty` works without quoting.
+fn parse_value(raw: &str) -> toml_edit::Value {
+    raw.parse()
+        .unwrap_or_else(|_| toml_edit::Value::from(raw.to_string()))
+}
+
+/// Sets one dotted key in the config file's text, creating the tables above it as needed.
+/// Everything else in `text`, comments and layout included, is kept as written.
+fn set_in_document(text: &str, key: &str, raw: &str) -> anyhow::Result<String> {
+    let known = toml::Value::try_from(Config::default())?;
+    let parts: Vec<&str> = key.split('.').collect();
+    let (last, parents) = parts.split_last().context("empty config key")?;
+    let parent = parents
+        .iter()
+        .try_fold(&known, |value, part| value.get(part))
+        .and_then(toml::Value::as_table);
+    if !parent.is_some_and(|table| table.contains_key(*last) || is_optional_key(key)) {
+        anyhow::bail!("unknown config key {key:?}");
+    }
+
+    let mut document: toml_edit::DocumentMut = text.parse()?;
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for part in parents {
+        if !table.contains_key(part) {
+            table.insert(part, toml_edit::table());
+        }
+        table = table
+            .get_mut(part)
+            .and_then(toml_edit::Item::as_table_like_mut)
+            .with_context(|| format!("{part} is not a table"))?;
+    }
+    let mut value = parse_value(raw);
+    // Keep a comment trailing the old value.
+    if let Some(old) = table.get(last).and_then(toml_edit::Item::as_value) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    table.insert(last, toml_edit::Item::Value(value));
+    Ok(document.to_string())
+}
+
+/// Sets one dotted key, validates the result, and writes the file (unless read-only). The
+/// rest of the file is left as the user wrote it.
+pub(crate) fn set_config(key: &str, raw: &str) -> anyhow::Result<Config> {
+    get_config()?;
+    let path = config_path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let text =
+        set_in_document(&text, key, raw).with_context(|| format!("invalid {}", path.display()))?;
+    let config: Config =
+        toml::from_str(&text).with_context(|| format!("invalid value for {key}"))?;
+    config.validate()?;
+
+    if !crate::read_only() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, text).with_context(|| format!("write {}", path.display()))?;
+    }
+    *CONFIG.write() = config.clone();
+    Ok(config)
+}
+
+/// Keys whose default is absent, so they don't appear in the serialized table.
+fn is_optional_key(key: &str) -> bool {
+    matches!(key, "terminal.default" | "search.cache_ttl_secs") || key.starts_with("hooks.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_parse_as_toml_or_strings() {
+        assert_eq!(parse_value("7").as_integer(), Some(7));
+        assert_eq!(parse_value("ghostty").as_str(), Some("ghostty"));
+        assert!(parse_value("[\"target\"]").is_array());
+        let config: Config = toml::from_str("[recents]\ncap = 0").unwrap();
+        assert!(config.validate().is_err());
+        assert!(toml::from_str::<Config>("[search]\ndepth = 3").is_err());
+    }
+
+    #[test]
+    fn setting_a_key_keeps_the_rest_of_the_file() {
+        let text = "# mine\n[recents]\ncap = 50 # plenty\n\n[search]\nmax_depth = 3\n";
+        let edited = set_in_document(text, "recents.cap", "20").unwrap();
+        assert_eq!(
+            edited,
+            "# mine\n[recents]\ncap = 20 # plenty\n\n[search]\nmax_depth = 3\n"
+        );
+        let added = set_in_document(&edited, "terminal.default", "ghostty").unwrap();
+        assert!(added.starts_with(&edited));
+        assert!(added.contains("[terminal]\ndefault = \"ghostty\"\n"));
+        assert!(set_in_document(text, "search.cache_ttl_secs", "0").is_ok());
+        assert!(set_in_document(text, "recents.size", "1").is_err());
+        assert!(set_in_document(text, "recents.cap.x", "1").is_err());
+    }
+}
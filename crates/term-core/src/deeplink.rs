@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::launcher::{launch, LaunchRequest, Secrets};
+use crate::search::{search_directories, SearchOptions, SearchResult};
+use crate::sessions::{launch_for, restore_session, ProfileChoice, RestoreReport};
+use crate::{add_favorite, normalize_path, touch_recent};
+
+pub(crate) const SCHEME: &str = "terminaut";
+
+/// A parsed `terminaut://` link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// `terminaut://open?path=~/dev/api&profile=tmux-dev`
+    Open {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+    /// `terminaut://search?q=infra&path=~/dev&limit=10`
+    Search {
+        query: String,
+        path: String,
+        limit: usize,
+    },
+    /// `terminaut://session?name=monday`
+    Session { name: String },
+    /// `terminaut://favorite?path=~/dev/api`
+    Favorite { path: String },
+}
+
+/// What dispatching a link did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UrlOutcome {
+    /// Nothing yet: the link runs a command, restores a session, or changes favorites, and
+    /// any web page can send one. Ask the user with `prompt`, then handle the link again
+    /// as confirmed.
+    Confirm {
+        link: DeepLink,
+        prompt: String,
+    },
+    Open {
+        launch: LaunchRequest,
+    },
+    Search {
+        results: Vec<SearchResult>,
+    },
+    Session {
+        report: RestoreReport,
+    },
+    Favorite {
+        path: String,
+    },
+}
+
+fn required(url: &Url, key: &str) -> anyhow::Result<String> {
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.into_owned())
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| anyhow::anyhow!("{url} is missing the `{key}` parameter"))
+}
+
+fn optional(url: &Url, key: &str) -> Option<String> {
+    required(url, key).ok()
+}
+
+pub(crate) fn parse_url(link: &str) -> anyhow::Result<DeepLink> {
+    let url =
+        Url::parse(link.trim()).map_err(|err| anyhow::anyhow!("invalid link {link:?}: {err}"))?;
+    if url.scheme() != SCHEME {
+        anyhow::bail!("expected a {SCHEME}:// link, got {}://", url.scheme());
+    }
+    // `terminaut://open?...` puts the action in the host; `terminaut:open?...` in the path.
+    let action = url
+        .host_str()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| url.path().trim_matches('/'))
+        .to_ascii_lowercase();
+    match action.as_str() {
+        "open" => Ok(DeepLink::Open {
+            path: required(&url, "path")?,
+            profile: optional(&url, "profile"),
+        }),
+        "search" => Ok(DeepLink::Search {
+            query: required(&url, "q")?,
+            path: optional(&url, "path").unwrap_or_else(|| String::from("~")),
+            limit: match optional(&url, "limit") {
+                Some(limit) => limit
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("limit must be a number, got {limit:?}"))?,
+                None => 20,
+            },
+        }),
+        "session" => Ok(DeepLink::Session {
+            name: required(&url, "name")?,
+        }),
+        "favorite" => Ok(DeepLink::Favorite {
+            path: required(&url, "path")?,
+        }),
+        other => anyhow::bail!("unknown action {other:?} (open, search, session, favorite)"),
+    }
+}
+
+/// A plain shell at `path`. The project's `.terminaut.toml` is not consulted: its default
+/// profile and env (`PROMPT_COMMAND`, `BASH_ENV`, ...) can run commands.
+fn plain_shell(path: String) -> LaunchRequest {
+    LaunchRequest {
+        path,
+        terminal: None,
+        command: None,
+        args: Vec::new(),
+        use_shell: false,
+        pre_command: None,
+        post_launch_command: None,
+        background: false,
+        profile: None,
+        profile_id: None,
+        windows: 1,
+        env: Default::default(),
+        wsl_distro: None,
+    }
+}
+
+/// What the user is asked before `link` runs, or `None` when it runs nothing and changes
+/// nothing.
+fn confirmation(link: &DeepLink) -> anyhow::Result<Option<String>> {
+    Ok(match link {
+        DeepLink::Open { profile: None, .. } | DeepLink::Search { .. } => None,
+        DeepLink::Open {
+            path,
+            profile: Some(profile),
+        } => {
+            let path = normalize_path(path)?.display().to_string();
+            let request = launch_for(path.clone(), ProfileChoice::Named(profile))?;
+            let runs = [
+                &request.pre_command,
+                &request.command,
+                &request.post_launch_command,
+            ]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain((!request.args.is_empty()).then(|| request.args.join(" ")))
+            .collect::<Vec<_>>();
+            Some(match runs.is_empty() {
+                true => format!("Open {path} with the {profile} profile?"),
+                false => format!(
+                    "Open {path} with the {profile} profile? It runs: {}",
+                    runs.join("; ")
+                ),
+            })
+        }
+        DeepLink::Session { name } => {
+            let launches = crate::sessions::session_launches(name)?;
+            Some(format!(
+                "Restore the {name} session, opening {} terminal{}?",
+                launches.len(),
+                if launches.len() == 1 { "" } else { "s" }
+            ))
+        }
+        DeepLink::Favorite { path } => Some(format!(
+            "Add {} to favorites?",
+            normalize_path(path)?.display()
+        )),
+    })
+}
+
+/// Performs `link`. Unless `confirmed`, links that run commands or change state only return
+/// [`UrlOutcome::Confirm`].
+pub(crate) fn handle_url(link: &str, confirmed: bool) -> anyhow::Result<UrlOutcome> {
+    let link = parse_url(link)?;
+    if !confirmed {
+        if let Some(prompt) = confirmation(&link)? {
+            return Ok(UrlOutcome::Confirm { link, prompt });
+        }
+    }
+    match link {
+        DeepLink::Open { path, profile } => {
+            let path = normalize_path(&path)?.display().to_string();
+            let request = match &profile {
+                Some(name) => launch_for(path.clone(), ProfileChoice::Named(name))?,
+                None => plain_shell(path.clone()),
+            };
+            // A link can come from any web page, so it never gets the user's secrets.
+            launch(&request, Secrets::Leave)?;
+            touch_recent(&path, None)?;
+            Ok(UrlOutcome::Open { launch: request })
+        }
+        DeepLink::Search { query, path, limit } => {
+            let root = normalize_path(&path)?;
+            let results = search_directories(&root, &query, limit, &SearchOptions::default())?;
+            Ok(UrlOutcome::Search { results })
+        }
+        DeepLink::Session { name } => Ok(UrlOutcome::Session {
+            report: restore_session(&name)?,
+        }),
+        DeepLink::Favorite { path } => {
+            add_favorite(&path)?;
+            Ok(UrlOutcome::Favorite {
+                path: normalize_path(&path)?.display().to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_actions_and_decodes_parameters() {
+        assert_eq!(
+            parse_url("terminaut://open?path=~/dev/my%20api&profile=tmux-dev").unwrap(),
+            DeepLink::Open {
+                path: String::from("~/dev/my api"),
+                profile: Some(String::from("tmux-dev")),
+            }
+        );
+        assert_eq!(
+            parse_url("terminaut://search?q=infra").unwrap(),
+            DeepLink::Search {
+                query: String::from("infra"),
+                path: String::from("~"),
+                limit: 20,
+            }
+        );
+        assert!(parse_url("terminaut://open").is_err());
+        assert!(parse_url("https://open?path=/").is_err());
+        assert!(parse_url("terminaut://explode?path=/").is_err());
+    }
+
+    #[test]
+    fn links_that_run_or_change_anything_need_confirming() {
+        let dir = std::env::temp_dir();
+        let favorite = format!("terminaut://favorite?path={}", dir.display());
+        match handle_url(&favorite, false).unwrap() {
+            UrlOutcome::Confirm { link, prompt } => {
+                assert!(matches!(link, DeepLink::Favorite { .. }));
+                assert!(prompt.starts_with("Add "));
+            }
+            other => panic!("expected a confirmation, got {other:?}"),
+        }
+        assert!(!crate::api::list_favorites().contains(&dir.display().to_string()));
+
+        let open = DeepLink::Open {
+            path: dir.display().to_string(),
+            profile: None,
+        };
+        assert_eq!(confirmation(&open).unwrap(), None);
+        let request = plain_shell(dir.display().to_string());
+        assert!(request.command.is_none() && request.env.is_empty());
+    }
+}
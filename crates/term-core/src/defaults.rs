@@ -0,0 +1,377 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Where a system default was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultSource {
+    /// The LaunchServices handler for shell scripts on macOS.
+    LaunchServices,
+    /// `$TERM_PROGRAM`, the terminal this process was started from.
+    TermProgram,
+    /// The `x-terminal-emulator` link in `/etc/alternatives`.
+    Alternatives,
+    /// `HKCU\Console\%%Startup`, the "default terminal application" Windows setting.
+    Registry,
+    /// The default profile in Windows Terminal's `settings.json`.
+    TerminalSettings,
+    /// The login shell in the user database (`/etc/passwd`, Directory Services).
+    UserDatabase,
+    /// `$TERMINAL`, `$SHELL`, or `%COMSPEC%`.
+    Environment,
+    /// Nothing is configured; this is what the OS falls back to.
+    Builtin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultTerminal {
+    /// The [`LaunchRequest::terminal`](crate::LaunchRequest::terminal) name that opens it, or
+    /// `None` when Terminaut can't drive this terminal.
+    pub terminal: Option<String>,
+    /// The terminal as the OS names it: a bundle id, program path, or app name.
+    pub app: String,
+    pub source: DefaultSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultShell {
+    /// Path of the login shell; on Windows, the command line of the default profile.
+    pub path: String,
+    pub source: DefaultSource,
+}
+
+/// The Terminaut terminal name for a bundle id, `$TERM_PROGRAM` value, or program.
+fn terminal_name(app: &str) -> Option<&'static str> {
+    let stem = Path::new(app)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match app.to_ascii_lowercase().as_str() {
+        "com.apple.terminal" | "apple_terminal" => Some("terminal"),
+        "com.googlecode.iterm2" | "iterm.app" => Some("iterm"),
+        "com.mitchellh.ghostty" | "ghostty" => Some("ghostty"),
+        _ if stem == "ghostty" => Some("ghostty"),
+        "org.alacritty" | "alacritty" => Some("alacritty"),
+        _ if stem == "alacritty" => Some("alacritty"),
+        _ if stem == "wt" || stem == "windowsterminal" => Some("windows-terminal"),
+        _ => None,
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// The bundle id LaunchServices opens shell scripts with, from its `LSHandlers` list.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_services_handler(handlers: &plist::Value) -> Option<String> {
+    const CONTENT_TYPES: [&str; 3] = [
+        "public.unix-executable",
+        "com.apple.terminal.shell-script",
+        "public.shell-script",
+    ];
+    let handlers = handlers.as_dictionary()?.get("LSHandlers")?.as_array()?;
+    CONTENT_TYPES.iter().find_map(|content_type| {
+        handlers.iter().find_map(|handler| {
+            let handler = handler.as_dictionary()?;
+            if handler.get("LSHandlerContentType")?.as_string()? != *content_type {
+                return None;
+            }
+            ["LSHandlerRoleShell", "LSHandlerRoleAll"]
+                .iter()
+                .find_map(|role| handler.get(role)?.as_string())
+                .filter(|bundle| !bundle.is_empty() && *bundle != "-")
+                .map(str::to_string)
+        })
+    })
+}
+
+/// The login shell of `user` (or of the account whose home is `home`) in `/etc/passwd` text.
+#[cfg_attr(any(target_os = "macos", not(unix)), allow(dead_code))]
+fn passwd_shell(passwd: &str, user: Option<&str>, home: Option<&Path>) -> Option<String> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        let [name, _, _, _, _, dir, shell] = fields[..] else {
+            return None;
+        };
+        let matches = match user {
+            Some(user) => name == user,
+            None => home.is_some_and(|home| Path::new(dir) == home),
+        };
+        (matches && !shell.is_empty()).then(|| shell.to_string())
+    })
+}
+
+/// The data of `name` in `reg query` output:
+///
+/// ```text
+/// HKEY_CURRENT_USER\Console\%%Startup
+///     DelegationTerminal    REG_SZ    {E12CFF52-A866-4C77-9A90-F570A7AA2C6B}
+/// ```
+#[cfg_attr(not(windows), allow(dead_code))]
+fn reg_value(output: &str, name: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if !fields.next()?.eq_ignore_ascii_case(name) {
+            return None;
+        }
+        fields.next().filter(|kind| kind.starts_with("REG_"))?;
+        Some(fields.collect::<Vec<_>>().join(" "))
+    })
+}
+
+/// The command line of Windows Terminal's default profile in its `settings.json`.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn terminal_settings_shell(settings: &serde_json::Value) -> Option<String> {
+    let default = settings.get("defaultProfile")?.as_str()?;
+    let profiles = settings.get("profiles")?;
+    let list = profiles.get("list").unwrap_or(profiles).as_array()?;
+    let profile = list.iter().find(|profile| {
+        ["guid", "name"]
+            .iter()
+            .any(|key| profile.get(key).and_then(|v| v.as_str()) == Some(default))
+    })?;
+    if let Some(command) = profile.get("commandline").and_then(|v| v.as_str()) {
+        return Some(command.to_string());
+    }
+    let name = profile
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    match profile.get("source").and_then(|v| v.as_str())? {
+        "Windows.Terminal.PowershellCore" => Some(String::from("pwsh.exe")),
+        "Windows.Terminal.Wsl" => Some(format!("wsl.exe -d {name}")),
+        _ => None,
+    }
+}
+
+/// The terminal the OS opens by default: the LaunchServices shell handler or `$TERM_PROGRAM`
+/// on macOS, `$TERMINAL` or the `x-terminal-emulator` alternative on Linux, and the default
+/// terminal application setting on Windows.
+pub(crate) fn default_terminal() -> DefaultTerminal {
+    let found = |app: String, source| DefaultTerminal {
+        terminal: terminal_name(&app).map(str::to_string),
+        app,
+        source,
+    };
+    #[cfg(target_os = "macos")]
+    {
+        let handler = dirs::home_dir()
+            .map(|home| {
+                home.join("Library/Preferences/com.apple.LaunchServices")
+                    .join("com.apple.launchservices.secure.plist")
+            })
+            .and_then(|path| plist::Value::from_file(path).ok())
+            .and_then(|handlers| launch_services_handler(&handlers));
+        if let Some(bundle) = handler {
+            return found(bundle, DefaultSource::LaunchServices);
+        }
+        if let Some(program) =
+            env("TERM_PROGRAM").filter(|program| terminal_name(program).is_some())
+        {
+            return found(program, DefaultSource::TermProgram);
+        }
+        found(String::from("com.apple.Terminal"), DefaultSource::Builtin)
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // Anything other than Ghostty or Alacritty is what the `terminal` launcher starts anyway.
+        let linux = |app: String, source| {
+            let mut default = found(app, source);
+            default
+                .terminal
+                .get_or_insert_with(|| String::from("terminal"));
+            default
+        };
+        if let Some(program) = env("TERMINAL") {
+            return linux(program, DefaultSource::Environment);
+        }
+        match std::fs::canonicalize("/etc/alternatives/x-terminal-emulator") {
+            Ok(target) => linux(target.display().to_string(), DefaultSource::Alternatives),
+            Err(_) => linux(String::from("x-terminal-emulator"), DefaultSource::Builtin),
+        }
+    }
+    #[cfg(windows)]
+    {
+        const WINDOWS_TERMINAL: [&str; 2] = [
+            "{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}",
+            "{86633F1F-6454-40EC-89CE-DA4EBA977EE2}",
+        ];
+        const CONSOLE_HOST: &str = "{B23D10C0-E52E-411E-9D5B-C09FDF709C7D}";
+        let delegation = std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKCU\Console\%%Startup",
+                "/v",
+                "DelegationTerminal",
+            ])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                reg_value(
+                    &String::from_utf8_lossy(&output.stdout),
+                    "DelegationTerminal",
+                )
+            });
+        match delegation {
+            Some(id)
+                if WINDOWS_TERMINAL
+                    .iter()
+                    .any(|wt| wt.eq_ignore_ascii_case(&id)) =>
+            {
+                found(String::from("wt.exe"), DefaultSource::Registry)
+            }
+            Some(id) if id.eq_ignore_ascii_case(CONSOLE_HOST) => DefaultTerminal {
+                terminal: Some(String::from("terminal")),
+                app: String::from("conhost.exe"),
+                source: DefaultSource::Registry,
+            },
+            // "Let Windows decide" picks Windows Terminal when it is installed.
+            _ if crate::launcher::find_program("wt.exe").is_some() => {
+                found(String::from("wt.exe"), DefaultSource::Builtin)
+            }
+            _ => DefaultTerminal {
+                terminal: Some(String::from("terminal")),
+                app: String::from("conhost.exe"),
+                source: DefaultSource::Builtin,
+            },
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = found;
+        DefaultTerminal {
+            terminal: None,
+            app: String::new(),
+            source: DefaultSource::Builtin,
+        }
+    }
+}
+
+/// The user's login shell: Directory Services or `/etc/passwd`, then `$SHELL`; on Windows,
+/// Windows Terminal's default profile, then `%COMSPEC%`.
+pub(crate) fn default_shell() -> DefaultShell {
+    let found = |path: String, source| DefaultShell { path, source };
+    #[cfg(target_os = "macos")]
+    {
+        let user = env("USER").or_else(|| env("LOGNAME"));
+        let shell = user.and_then(|user| {
+            let output = std::process::Command::new("/usr/bin/dscl")
+                .args([".", "-read", &format!("/Users/{user}"), "UserShell"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .strip_prefix("UserShell:")
+                .map(|shell| shell.trim().to_string())
+                .filter(|shell| !shell.is_empty())
+        });
+        if let Some(shell) = shell {
+            return found(shell, DefaultSource::UserDatabase);
+        }
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let user = env("USER").or_else(|| env("LOGNAME"));
+        let shell = std::fs::read_to_string("/etc/passwd")
+            .ok()
+            .and_then(|passwd| passwd_shell(&passwd, user.as_deref(), dirs::home_dir().as_deref()));
+        if let Some(shell) = shell {
+            return found(shell, DefaultSource::UserDatabase);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let shell = dirs::data_local_dir()
+            .map(|dir| {
+                dir.join(r"Packages\Microsoft.WindowsTerminal_8wekyb3d8bbwe\LocalState")
+                    .join("settings.json")
+            })
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .and_then(|settings| terminal_settings_shell(&settings));
+        if let Some(shell) = shell {
+            return found(shell, DefaultSource::TerminalSettings);
+        }
+        match env("COMSPEC") {
+            Some(shell) => found(shell, DefaultSource::Environment),
+            None => found(String::from("cmd.exe"), DefaultSource::Builtin),
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        match env("SHELL") {
+            Some(shell) => found(shell, DefaultSource::Environment),
+            None if cfg!(target_os = "macos") => {
+                found(String::from("/bin/zsh"), DefaultSource::Builtin)
+            }
+            None => found(String::from("/bin/sh"), DefaultSource::Builtin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_parse_from_os_settings() {
+        assert_eq!(terminal_name("com.googlecode.iterm2"), Some("iterm"));
+        assert_eq!(terminal_name("/usr/bin/ghostty"), Some("ghostty"));
+        assert_eq!(terminal_name("org.alacritty"), Some("alacritty"));
+        assert_eq!(terminal_name("WezTerm"), None);
+
+        let mut handler = plist::Dictionary::new();
+        handler.insert(
+            "LSHandlerContentType".into(),
+            "public.unix-executable".into(),
+        );
+        handler.insert("LSHandlerRoleShell".into(), "com.mitchellh.ghostty".into());
+        let mut handlers = plist::Dictionary::new();
+        handlers.insert(
+            "LSHandlers".into(),
+            vec![plist::Value::from(handler)].into(),
+        );
+        assert_eq!(
+            launch_services_handler(&handlers.into()).as_deref(),
+            Some("com.mitchellh.ghostty")
+        );
+
+        let passwd = "root:x:0:0:root:/root:/bin/bash\nme:x:1000:1000::/home/me:/usr/bin/fish\n";
+        assert_eq!(
+            passwd_shell(passwd, Some("me"), None).as_deref(),
+            Some("/usr/bin/fish")
+        );
+        assert_eq!(
+            passwd_shell(passwd, None, Some(Path::new("/root"))).as_deref(),
+            Some("/bin/bash")
+        );
+
+        let reg =
+            "\r\nHKEY_CURRENT_USER\\Console\\%%Startup\r\n    DelegationTerminal    REG_SZ    \
+                   {E12CFF52-A866-4C77-9A90-F570A7AA2C6B}\r\n";
+        assert_eq!(
+            reg_value(reg, "DelegationTerminal").as_deref(),
+            Some("{E12CFF52-A866-4C77-9A90-F570A7AA2C6B}")
+        );
+
+        let settings = serde_json::json!({
+            "defaultProfile": "{574e775e-4f2a-5b96-ac1e-a2962a402336}",
+            "profiles": { "list": [
+                { "guid": "{0caa0dad-35be-5f56-a8ff-afceeeaa6101}", "commandline": "cmd.exe" },
+                { "guid": "{574e775e-4f2a-5b96-ac1e-a2962a402336}", "name": "PowerShell",
+                  "source": "Windows.Terminal.PowershellCore" },
+            ] },
+        });
+        assert_eq!(
+            terminal_settings_shell(&settings).as_deref(),
+            Some("pwsh.exe")
+        );
+    }
+}
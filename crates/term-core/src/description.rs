@@ -0,0 +1,438 @@
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::find_project_root;
+
+const README_READ_BYTES: u64 = 32 * 1024;
+const SUMMARY_CHARS: usize = 280;
+const README_NAMES: [&str; 5] = [
+    "README.md",
+    "README.markdown",
+    "README.rst",
+    "README.txt",
+    "README",
+];
+
+/// A one-line identity for a project card, from its manifest and README.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectDescription {
+    pub root: String,
+    /// The README's first heading.
+    pub title: Option<String>,
+    /// The manifest `description`, else the README's first paragraph, as plain text.
+    pub summary: Option<String>,
+    /// File the summary came from, e.g. `package.json` or `README.md`.
+    pub source: Option<String>,
+    /// Web URL of the source repository declared in the manifest, else of the git remote.
+    pub repository: Option<String>,
+}
+
+/// Turns `git+https://host/org/repo.git`, `git@host:org/repo.git`, and the `github:org/repo`
+/// or `org/repo` shorthands of package.json into `https://host/org/repo`.
+fn repository_url(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let value = value.strip_prefix("git+").unwrap_or(value);
+    let shorthand = value.split_once(':').and_then(|(scheme, path)| {
+        let host = match scheme {
+            "github" => "github.com",
+            "gitlab" => "gitlab.com",
+            "bitbucket" => "bitbucket.org",
+            _ => return None,
+        };
+        Some(format!("https://{host}/{path}"))
+    });
+    let url = if let Some(rest) = value.strip_prefix("git@") {
+        let (host, path) = rest.split_once(':')?;
+        format!("https://{host}/{path}")
+    } else if let Some(url) = shorthand {
+        url
+    } else if !value.contains(':') && value.matches('/').count() == 1 {
+        format!("https://github.com/{value}")
+    } else {
+        value
+            .replacen("git://", "https://", 1)
+            .replacen("ssh://git@", "https://", 1)
+    };
+    Some(
+        url.trim_end_matches('/')
+            .trim_end_matches(".git")
+            .to_string(),
+    )
+}
+
+/// `description` and repository from `package.json`.
+fn package_json(root: &Path) -> Option<(Option<String>, Option<String>)> {
+    let text = std::fs::read_to_string(root.join("package.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&text).ok()?;
+    let description = manifest["description"].as_str().map(str::to_string);
+    let repository = match &manifest["repository"] {
+        serde_json::Value::String(url) => Some(url.as_str()),
+        repository => repository["url"].as_str(),
+    }
+    .and_then(repository_url);
+    Some((description, repository))
+}
+
+/// `description` and `repository` from `[package]` (or `[workspace.package]`) in `Cargo.toml`.
+fn cargo_toml(root: &Path) -> Option<(Option<String>, Option<String>)> {
+    let text = std::fs::read_to_string(root.join("Cargo.toml")).ok()?;
+    let manifest: toml::Table = text.parse().ok()?;
+    let package = manifest
+        .get("package")
+        .or_else(|| manifest.get("workspace")?.get("package"))?;
+    let field = |key: &str| package.get(key)?.as_str().map(str::to_string);
+    Some((
+        field("description"),
+        field("repository").as_deref().and_then(repository_url),
+    ))
+}
+
+/// The package name declared in `package.json`, `Cargo.toml`, or `pyproject.toml`.
+pub(crate) fn package_name(root: &Path) -> Option<String> {
+    let json = || {
+        let text = std::fs::read_to_string(root.join("package.json")).ok()?;
+        let manifest: serde_json::Value = serde_json::from_str(&text).ok()?;
+        manifest["name"].as_str().map(str::to_string)
+    };
+    let toml = |file: &str, tables: [&[&str]; 2]| {
+        let text = std::fs::read_to_string(root.join(file)).ok()?;
+        let manifest: toml::Table = text.parse().ok()?;
+        tables.iter().find_map(|path| {
+            let mut value = manifest.get(path[0])?;
+            for key in &path[1..] {
+                value = value.get(key)?;
+            }
+            value.get("name")?.as_str().map(str::to_string)
+        })
+    };
+    json()
+        .or_else(|| toml("Cargo.toml", [&["package"], &["workspace", "package"]]))
+        .or_else(|| toml("pyproject.toml", [&["project"], &["tool", "poetry"]]))
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// Removes each `open…](…)` span of `text`, keeping the bracketed label when `keep_label`.
+fn strip_spans(text: &str, open: &str, keep_label: bool) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        let after = &rest[start + open.len()..];
+        let Some((label, tail)) = after.split_once("](") else {
+            break;
+        };
+        match tail.find(')').filter(|_| !label.contains(']')) {
+            Some(end) => {
+                plain.push_str(&rest[..start]);
+                if keep_label {
+                    plain.push_str(label);
+                }
+                rest = &tail[end + 1..];
+            }
+            None => {
+                plain.push_str(&rest[..start + open.len()]);
+                rest = after;
+            }
+        }
+    }
+    plain.push_str(rest);
+    plain
+}
+
+/// Markdown inline syntax reduced to its text: images and HTML tags dropped, `[text](url)`
+/// to `text`, emphasis and code markers removed.
+fn plain_markdown(line: &str) -> String {
+    let mut text = strip_spans(&strip_spans(line, "![", false), "[", true);
+    while let Some(start) = text.find('<') {
+        let Some(length) = text[start..].find('>') else {
+            break;
+        };
+        text.replace_range(start..start + length + 1, " ");
+    }
+    text.replace(['*', '`'], "")
+        .replace("__", "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_rst_underline(line: &str) -> bool {
+    let mut chars = line.chars();
+    chars.next().is_some_and(|first| {
+        "=-~^\"'`#*+:._".contains(first) && chars.all(|c| c == first) && line.len() >= 3
+    })
+}
+
+/// The first heading and first prose paragraph of a Markdown or reStructuredText README.
+fn readme(text: &str, rst: bool) -> (Option<String>, Option<String>) {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut title = None;
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim();
+        let next = lines.get(index + 1).map(|next| next.trim()).unwrap_or("");
+        index += 1;
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        let heading = if in_fence {
+            None
+        } else if rst {
+            (!line.is_empty() && !is_rst_underline(line) && is_rst_underline(next))
+                .then(|| line.to_string())
+        } else if let Some(heading) = line.strip_prefix('#') {
+            Some(heading.trim_start_matches('#').trim().to_string())
+        } else {
+            (!line.is_empty() && next.len() >= 3 && next.chars().all(|c| c == '=' || c == '-'))
+                .then(|| line.to_string())
+        };
+        if let Some(heading) = heading {
+            if !paragraph.is_empty() {
+                break;
+            }
+            // Skip the underline of a setext or reStructuredText heading.
+            if rst || !line.starts_with('#') {
+                index += 1;
+            }
+            title.get_or_insert_with(|| plain_markdown(&heading));
+            continue;
+        }
+        let skip = in_fence
+            || (rst && (line.starts_with("..") || line.starts_with(':') || is_rst_underline(line)))
+            || line.starts_with('<')
+            || line.starts_with('|')
+            || line.starts_with('>')
+            || line.starts_with("- ")
+            || line.starts_with("* ");
+        let text = if skip {
+            String::new()
+        } else {
+            plain_markdown(line)
+        };
+        if text.is_empty() {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(text);
+    }
+    let summary = (!paragraph.is_empty()).then(|| paragraph.join(" "));
+    (title.filter(|title| !title.is_empty()), summary)
+}
+
+/// `text` cut at a word boundary to at most [`SUMMARY_CHARS`] characters.
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= SUMMARY_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(SUMMARY_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end_matches(['.', ',', ';', ':']))
+}
+
+/// Describes the project containing `path` from its README, `package.json`, `Cargo.toml`, and
+/// git remote.
+pub(crate) fn project_description(path: &Path) -> ProjectDescription {
+    let (root, _) = find_project_root(path);
+    let mut description = ProjectDescription {
+        root: root.display().to_string(),
+        ..Default::default()
+    };
+    for (file, manifest) in [
+        ("package.json", package_json(&root)),
+        ("Cargo.toml", cargo_toml(&root)),
+    ] {
+        let Some((summary, repository)) = manifest else {
+            continue;
+        };
+        if description.summary.is_none() {
+            if let Some(summary) = summary.filter(|summary| !summary.trim().is_empty()) {
+                description.summary = Some(shorten(summary.trim()));
+                description.source = Some(file.to_string());
+            }
+        }
+        description.repository = description.repository.or(repository);
+    }
+    if description.repository.is_none() {
+        description.repository = crate::remote::read_remote(&root).map(|remote| remote.web_url);
+    }
+    let readme_file = README_NAMES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|file| file.is_file());
+    if let Some(file) = readme_file {
+        let mut text = String::new();
+        let read = std::fs::File::open(&file)
+            .and_then(|handle| handle.take(README_READ_BYTES).read_to_string(&mut text));
+        if read.is_ok() {
+            let rst = file.extension().is_some_and(|ext| ext == "rst");
+            let (title, summary) = readme(&text, rst);
+            description.title = title;
+            if description.summary.is_none() {
+                if let Some(summary) = summary {
+                    description.summary = Some(shorten(&summary));
+                    description.source = file
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> std::path::PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("terminaut-description-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn markdown_badges_html_and_links_reduce_to_text() {
+        let markdown = "[![CI](https://ci/badge.svg)](https://ci)\n\n# My *Tool*\n\n\
+                        <p align=\"center\"><img src=\"logo.png\"></p>\n\n\
+                        A **fast** tool for [paths](https://x.dev) and\n`terminals`.\n\n\
+                        ## Install\n";
+        let (title, summary) = readme(markdown, false);
+        assert_eq!(title.as_deref(), Some("My Tool"));
+        assert_eq!(
+            summary.as_deref(),
+            Some("A fast tool for paths and terminals.")
+        );
+    }
+
+    #[test]
+    fn rst_directives_and_overlines_are_skipped() {
+        let rst = "=====\nTitle\n=====\n\n.. image:: badge.svg\n\nSome text\nhere.\n";
+        let (title, summary) = readme(rst, true);
+        assert_eq!(title.as_deref(), Some("Title"));
+        assert_eq!(summary.as_deref(), Some("Some text here."));
+    }
+
+    #[test]
+    fn setext_headings_are_titles() {
+        let setext = "Tool\n====\n\nDoes things.\n";
+        assert_eq!(
+            readme(setext, false),
+            (Some("Tool".into()), Some("Does things.".into()))
+        );
+    }
+
+    #[test]
+    fn code_fences_lists_and_quotes_are_not_summaries() {
+        let markdown =
+            "# Tool\n\n```\ncargo install tool\n```\n\n- a list\n\n> a quote\n\nProse.\n";
+        assert_eq!(readme(markdown, false).1.as_deref(), Some("Prose."));
+    }
+
+    #[test]
+    fn the_summary_ends_at_the_next_heading() {
+        let markdown = "# Tool\nFirst paragraph.\n## Usage\nRun it.\n";
+        assert_eq!(
+            readme(markdown, false).1.as_deref(),
+            Some("First paragraph.")
+        );
+    }
+
+    #[test]
+    fn empty_readmes_describe_nothing() {
+        assert_eq!(readme("", false), (None, None));
+        assert_eq!(readme("#\n\n", false), (None, None));
+    }
+
+    #[test]
+    fn repository_urls_become_web_urls() {
+        let url = |value: &str| repository_url(value);
+        assert_eq!(
+            url("git+https://github.com/a/b.git").as_deref(),
+            Some("https://github.com/a/b")
+        );
+        assert_eq!(
+            url("git@gitlab.com:a/b.git").as_deref(),
+            Some("https://gitlab.com/a/b")
+        );
+        assert_eq!(
+            url("ssh://git@host/a/b/").as_deref(),
+            Some("https://host/a/b")
+        );
+        assert_eq!(url("github:a/b").as_deref(), Some("https://github.com/a/b"));
+        assert_eq!(url("a/b").as_deref(), Some("https://github.com/a/b"));
+        assert_eq!(url("  "), None);
+    }
+
+    #[test]
+    fn long_summaries_are_cut_at_a_word() {
+        assert_eq!(shorten("Short."), "Short.");
+        let long = shorten(&"word, ".repeat(100));
+        assert!(long.ends_with("word…"));
+        assert!(long.chars().count() <= SUMMARY_CHARS + 1);
+    }
+
+    #[test]
+    fn manifests_give_descriptions_and_repositories() {
+        let root = temp_root();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"name": "tool", "description": "JS tool", "repository": {"url": "git+https://github.com/a/js.git"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace.package]\ndescription = \"Rust tool\"\nrepository = \"https://github.com/a/rs\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            package_json(&root),
+            Some((
+                Some("JS tool".into()),
+                Some("https://github.com/a/js".into())
+            ))
+        );
+        assert_eq!(
+            cargo_toml(&root),
+            Some((
+                Some("Rust tool".into()),
+                Some("https://github.com/a/rs".into())
+            ))
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn unreadable_manifests_are_ignored() {
+        let root = temp_root();
+        std::fs::write(root.join("package.json"), "{ not json").unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[dependencies]\n").unwrap();
+        assert_eq!(package_json(&root), None);
+        assert_eq!(cargo_toml(&root), None);
+        assert_eq!(package_name(&root), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn package_names_skip_blank_ones() {
+        let root = temp_root();
+        std::fs::write(
+            root.join("pyproject.toml"),
+            "[tool.poetry]\nname = \"py\"\n",
+        )
+        .unwrap();
+        assert_eq!(package_name(&root).as_deref(), Some("py"));
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \" \"\n").unwrap();
+        assert_eq!(package_name(&root), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
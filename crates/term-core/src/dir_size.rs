@@ -0,0 +1,362 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use chrono::Utc;
+use ignore::WalkBuilder;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::Store;
+
+static CACHE: Lazy<SizeCache> = Lazy::new(SizeCache::load);
+/// The cancel flag of the one job per path that is queued or running.
+static JOBS: Lazy<Mutex<HashMap<PathBuf, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static QUEUE: Lazy<Mutex<Queue>> = Lazy::new(|| Mutex::new(Queue::default()));
+
+/// Walks running at once; further requests wait in the queue.
+const MAX_WORKERS: usize = 2;
+
+/// Requests kept waiting; a newer one pushes out the oldest, which a caller scrolling through
+/// a listing has most likely moved past.
+const MAX_QUEUED: usize = 64;
+
+struct Job {
+    path: PathBuf,
+    mtime_ns: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Queue {
+    waiting: VecDeque<Job>,
+    workers: usize,
+}
+
+impl Queue {
+    /// Queues `job`, returning the one pushed out to make room.
+    fn push(&mut self, job: Job) -> Option<Job> {
+        let dropped = (self.waiting.len() >= MAX_QUEUED)
+            .then(|| self.waiting.pop_front())
+            .flatten();
+        self.waiting.push_back(job);
+        dropped
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeStatus {
+    Ready,
+    Pending,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirSize {
+    pub path: String,
+    pub status: SizeStatus,
+    /// Total bytes of all regular files below `path`; `None` until computed.
+    pub bytes: Option<u64>,
+    pub files: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub computed_utc: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSize {
+    mtime_ns: u64,
+    bytes: u64,
+    files: u64,
+    computed_utc: i64,
+}
+
+struct SizeCache {
+    /// `None` in ephemeral mode, where sizes are cached for the process only.
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<String, CachedSize>>,
+}
+
+impl SizeCache {
+    fn load() -> Self {
+        let path = (!crate::ephemeral() && !cfg!(test))
+            .then(|| Store::default_store_path().with_file_name("sizes.json"));
+        let entries = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn get(&self, path: &Path, mtime_ns: u64) -> Option<CachedSize> {
+        self.entries
+            .lock()
+            .get(&path.display().to_string())
+            .filter(|cached| cached.mtime_ns == mtime_ns)
+            .cloned()
+    }
+
+    fn insert(&self, path: &Path, cached: CachedSize) -> anyhow::Result<()> {
+        let mut entries = self.entries.lock();
+        entries.insert(path.display().to_string(), cached);
+        let Some(file) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, serde_json::to_string(&*entries)?)?;
+        Ok(())
+    }
+}
+
+fn mtime_ns(path: &Path) -> anyhow::Result<u64> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default())
+}
+
+fn ready(path: &Path, cached: CachedSize) -> DirSize {
+    DirSize {
+        path: path.display().to_string(),
+        status: SizeStatus::Ready,
+        bytes: Some(cached.bytes),
+        files: Some(cached.files),
+        computed_utc: Some(cached.computed_utc),
+    }
+}
+
+fn unresolved(path: &Path, status: SizeStatus) -> DirSize {
+    DirSize {
+        path: path.display().to_string(),
+        status,
+        bytes: None,
+        files: None,
+        computed_utc: None,
+    }
+}
+
+/// Walks `path` summing file sizes; returns `None` if `cancel` is raised mid-walk.
+fn compute(path: &Path, cancel: &AtomicBool) -> Option<(u64, u64)> {
+    let mut bytes = 0;
+    let mut files = 0;
+    let walker = WalkBuilder::new(path)
+        .standard_filters(false)
+        .follow_links(false)
+        .build();
+    for entry in walker.flatten() {
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        if let Ok(md) = entry.metadata() {
+            if md.is_file() {
+                bytes += md.len();
+                files += 1;
+            }
+        }
+    }
+    Some((bytes, files))
+}
+
+fn run_job(path: &Path, mtime_ns: u64, cancel: &Arc<AtomicBool>) -> DirSize {
+    let result = compute(path, cancel);
+    {
+        let mut jobs = JOBS.lock();
+        // A cancelled job may already have been replaced by a newer one for the same path.
+        if jobs
+            .get(path)
+            .is_some_and(|current| Arc::ptr_eq(current, cancel))
+        {
+            jobs.remove(path);
+        }
+    }
+    match result {
+        Some((bytes, files)) => {
+            let cached = CachedSize {
+                mtime_ns,
+                bytes,
+                files,
+                computed_utc: Utc::now().timestamp(),
+            };
+            if let Err(err) = CACHE.insert(path, cached.clone()) {
+                tracing::warn!("failed to save size cache: {err:#}");
+            }
+            ready(path, cached)
+        }
+        None => unresolved(path, SizeStatus::Cancelled),
+    }
+}
+
+/// Takes queued jobs until none are left.
+fn work() {
+    loop {
+        let job = {
+            let mut queue = QUEUE.lock();
+            match queue.waiting.pop_front() {
+                Some(job) => job,
+                None => {
+                    queue.workers -= 1;
+                    return;
+                }
+            }
+        };
+        run_job(&job.path, job.mtime_ns, &job.cancel);
+    }
+}
+
+/// Queues a computation of `path` unless one is already queued or running, starting a worker
+/// when fewer than [`MAX_WORKERS`] are busy.
+fn enqueue(path: &Path, mtime_ns: u64) {
+    let cancel = {
+        let mut jobs = JOBS.lock();
+        if jobs.contains_key(path) {
+            return;
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        jobs.insert(path.to_path_buf(), Arc::clone(&cancel));
+        cancel
+    };
+    let mut queue = QUEUE.lock();
+    if let Some(dropped) = queue.push(Job {
+        path: path.to_path_buf(),
+        mtime_ns,
+        cancel,
+    }) {
+        let mut jobs = JOBS.lock();
+        if jobs
+            .get(&dropped.path)
+            .is_some_and(|current| Arc::ptr_eq(current, &dropped.cancel))
+        {
+            jobs.remove(&dropped.path);
+        }
+    }
+    if queue.workers < MAX_WORKERS {
+        queue.workers += 1;
+        std::thread::spawn(work);
+    }
+}
+
+/// Returns the cached size when fresh, otherwise queues a background computation and
+/// reports `Pending`. Call again later to pick up the result.
+pub(crate) fn dir_size(path: &Path) -> anyhow::Result<DirSize> {
+    let mtime_ns = mtime_ns(path)?;
+    if let Some(cached) = CACHE.get(path, mtime_ns) {
+        return Ok(ready(path, cached));
+    }
+    enqueue(path, mtime_ns);
+    Ok(unresolved(path, SizeStatus::Pending))
+}
+
+/// Computes the size on the calling thread, still consulting and filling the cache. A
+/// background job already working on `path` is left alone, and [`cancel_dir_size`] stops that
+/// one; otherwise this computation is the one it stops.
+pub(crate) fn dir_size_blocking(path: &Path) -> anyhow::Result<DirSize> {
+    let mtime_ns = mtime_ns(path)?;
+    if let Some(cached) = CACHE.get(path, mtime_ns) {
+        return Ok(ready(path, cached));
+    }
+    let cancel = Arc::new(AtomicBool::new(false));
+    JOBS.lock()
+        .entry(path.to_path_buf())
+        .or_insert_with(|| Arc::clone(&cancel));
+    Ok(run_job(path, mtime_ns, &cancel))
+}
+
+/// Signals a running computation for `path` to stop. Returns whether one was running.
+pub(crate) fn cancel_dir_size(path: &Path) -> bool {
+    match JOBS.lock().remove(path) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Non-blocking lookup for callers that only want a number when one is already known. A
+/// missing or stale size is queued like [`dir_size`] does, so a later lookup can find it.
+pub(crate) fn cached_bytes(path: &Path) -> Option<u64> {
+    dir_size(path).ok().and_then(|size| size.bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn tree() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terminaut-size-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a"), [0; 10]).unwrap();
+        std::fs::write(dir.join("sub/b"), [0; 5]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn blocking_sizes_are_cached() {
+        let dir = tree();
+        let size = dir_size_blocking(&dir).unwrap();
+        assert_eq!(size.status, SizeStatus::Ready);
+        assert_eq!((size.bytes, size.files), (Some(15), Some(2)));
+        assert_eq!(cached_bytes(&dir), Some(15));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn background_sizes_arrive_later() {
+        let dir = tree();
+        assert_eq!(dir_size(&dir).unwrap().status, SizeStatus::Pending);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let size = loop {
+            let size = dir_size(&dir).unwrap();
+            if size.status == SizeStatus::Ready || Instant::now() > deadline {
+                break size;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(size.bytes, Some(15));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn blocking_leaves_a_running_job_alone() {
+        let dir = tree();
+        let running = Arc::new(AtomicBool::new(false));
+        JOBS.lock().insert(dir.clone(), Arc::clone(&running));
+        assert_eq!(dir_size_blocking(&dir).unwrap().bytes, Some(15));
+        assert!(JOBS
+            .lock()
+            .get(&dir)
+            .is_some_and(|current| Arc::ptr_eq(current, &running)));
+        assert!(cancel_dir_size(&dir));
+        assert!(running.load(Ordering::Relaxed));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_queue_is_bounded() {
+        let mut queue = Queue::default();
+        let job = |n: usize| Job {
+            path: PathBuf::from(format!("/{n}")),
+            mtime_ns: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+        for n in 0..MAX_QUEUED {
+            assert!(queue.push(job(n)).is_none());
+        }
+        let dropped = queue.push(job(MAX_QUEUED)).unwrap();
+        assert_eq!(dropped.path, Path::new("/0"));
+        assert_eq!(queue.waiting.len(), MAX_QUEUED);
+    }
+}
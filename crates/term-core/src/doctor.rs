@@ -0,0 +1,312 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::state_backend;
+use crate::config::PersistenceMode;
+use crate::launcher::{find_program, locate_terminal};
+use crate::{PersistedState, STORE};
+
+/// Searches slower than this on the sample tree are reported as a warning.
+#[cfg(feature = "fs")]
+const SLOW_SEARCH_MS: u128 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(name: impl Into<String>, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoctorReport {
+    /// False when any check failed; warnings don't count.
+    pub ok: bool,
+    pub checks: Vec<DoctorCheck>,
+}
+
+fn config_check() -> DoctorCheck {
+    let path = crate::config::config_path();
+    match crate::config::get_config() {
+        Ok(_) if !path.is_file() => DoctorCheck::new(
+            "config",
+            CheckStatus::Ok,
+            format!("{} not present; using defaults", path.display()),
+        ),
+        Ok(_) => DoctorCheck::new("config", CheckStatus::Ok, path.display().to_string()),
+        Err(err) => {
+            DoctorCheck::new("config", CheckStatus::Fail, format!("{err:#}")).hint(format!(
+                "fix {} by hand; defaults are used until it loads",
+                path.display()
+            ))
+        }
+    }
+}
+
+fn recovery_hint(location: &str) -> String {
+    format!(
+        "fix the JSON in {location}, or move it aside (e.g. to {location}.corrupt) and restart \
+         to begin with empty state"
+    )
+}
+
+/// Whether the state loaded at startup and still parses now.
+fn store_readable_check() -> DoctorCheck {
+    let location = state_backend().location();
+    if let Some(reason) = &STORE.load_error {
+        return DoctorCheck::new("store_readable", CheckStatus::Fail, reason.clone())
+            .hint(recovery_hint(&location));
+    }
+    if let Some((moved_to, reason)) = &STORE.quarantined {
+        return DoctorCheck::new(
+            "store_readable",
+            CheckStatus::Warn,
+            format!("{reason}; started with empty state"),
+        )
+        .hint(format!(
+            "the unreadable state was moved to {moved_to}; merge what you need back with \
+             `state merge` once it is fixed"
+        ));
+    }
+    let reloaded = state_backend().load().and_then(|json| {
+        json.map(|json| {
+            let json = crate::encryption::open(&json)?;
+            Ok(serde_json::from_str::<PersistedState>(&json)?)
+        })
+        .transpose()
+    });
+    match reloaded {
+        Ok(_) => {
+            let store = STORE.inner.lock();
+            DoctorCheck::new(
+                "store_readable",
+                CheckStatus::Ok,
+                format!(
+                    "{location}: {} favorites, {} recents, {} tags, {} profiles",
+                    store.favorites.len(),
+                    store.recents.len(),
+                    store.tags.len(),
+                    store.profiles.len()
+                ),
+            )
+        }
+        Err(err) => DoctorCheck::new(
+            "store_readable",
+            CheckStatus::Fail,
+            format!("{location} changed since startup and no longer loads: {err:#}"),
+        )
+        .hint(recovery_hint(&location)),
+    }
+}
+
+/// Saves the current state unchanged to prove the backend accepts writes.
+fn store_writable_check() -> DoctorCheck {
+    let location = state_backend().location();
+    if crate::read_only() {
+        return DoctorCheck::new(
+            "store_writable",
+            CheckStatus::Warn,
+            "read-only mode; changes are not saved",
+        );
+    }
+    if STORE.in_memory {
+        return DoctorCheck::new(
+            "store_writable",
+            CheckStatus::Warn,
+            "TERMINAUT_EPHEMERAL is set; changes are not saved",
+        );
+    }
+    if crate::config::config().store.persistence == PersistenceMode::Memory {
+        return DoctorCheck::new(
+            "store_writable",
+            CheckStatus::Warn,
+            "store.persistence is memory; changes are not saved",
+        );
+    }
+    if STORE.load_error.is_some() {
+        return DoctorCheck::new(
+            "store_writable",
+            CheckStatus::Warn,
+            "skipped: saving is disabled until the state loads",
+        );
+    }
+    let mut store = STORE.inner.lock();
+    match STORE.persist(&mut store) {
+        Ok(()) => DoctorCheck::new("store_writable", CheckStatus::Ok, location),
+        Err(err) => DoctorCheck::new("store_writable", CheckStatus::Fail, format!("{err:#}")).hint(
+            format!("check the permissions of {location} and its directory"),
+        ),
+    }
+}
+
+/// The default terminal plus every terminal a profile names.
+fn terminal_checks() -> Vec<DoctorCheck> {
+    let mut names: BTreeSet<Option<String>> = BTreeSet::from([None]);
+    names.extend(
+        STORE
+            .inner
+            .lock()
+            .profiles
+            .iter()
+            .filter_map(|profile| profile.terminal.as_ref())
+            .map(|terminal| Some(terminal.trim().to_ascii_lowercase())),
+    );
+    names
+        .into_iter()
+        .map(|name| {
+            let label = match &name {
+                Some(name) => format!("terminal:{name}"),
+                None => String::from("terminal:default"),
+            };
+            match locate_terminal(name.as_deref()) {
+                Ok(Some(path)) => {
+                    DoctorCheck::new(label, CheckStatus::Ok, path.display().to_string())
+                }
+                Ok(None) => DoctorCheck::new(label, CheckStatus::Fail, "not installed")
+                    .hint("install it, or point terminal.default and profiles at one that is"),
+                Err(err) => DoctorCheck::new(label, CheckStatus::Fail, format!("{err:#}"))
+                    .hint("use terminal, iterm, ghostty, or alacritty"),
+            }
+        })
+        .collect()
+}
+
+/// The mosh, et, and kubectl clients that remote profiles connect with.
+fn transport_checks() -> Vec<DoctorCheck> {
+    let transports: BTreeSet<&str> = STORE
+        .inner
+        .lock()
+        .profiles
+        .iter()
+        .filter_map(|profile| match (&profile.ssh_host, &profile.kube_pod) {
+            (_, Some(_)) => Some("kubectl"),
+            (Some(_), None) if !profile.transport.is_ssh() => Some(profile.transport.as_str()),
+            _ => None,
+        })
+        .collect();
+    transports
+        .into_iter()
+        .map(|transport| {
+            let label = format!("transport:{transport}");
+            match find_program(transport) {
+                Some(path) => DoctorCheck::new(label, CheckStatus::Ok, path.display().to_string()),
+                None if transport == "kubectl" => {
+                    DoctorCheck::new(label, CheckStatus::Fail, "not installed")
+                        .hint("install kubectl, or remove the pod targets of those profiles")
+                }
+                None => DoctorCheck::new(
+                    label,
+                    CheckStatus::Warn,
+                    "not installed; launches fall back to ssh",
+                )
+                .hint(format!(
+                    "install {transport}, or switch those profiles to ssh"
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Times a search over a generated tree of `groups * modules` project-like directories.
+#[cfg(feature = "fs")]
+fn search_check(groups: usize, modules: usize) -> DoctorCheck {
+    let root = std::env::temp_dir().join(format!("terminaut-doctor-{}", std::process::id()));
+    let build = || -> std::io::Result<()> {
+        for group in 0..groups {
+            for module in 0..modules {
+                std::fs::create_dir_all(root.join(format!("group-{group}/module-{module}/src")))?;
+            }
+        }
+        std::fs::create_dir_all(root.join("group-0/module-0/doctor-needle"))
+    };
+    if let Err(err) = build() {
+        std::fs::remove_dir_all(&root).ok();
+        return DoctorCheck::new(
+            "search",
+            CheckStatus::Warn,
+            format!("could not create a sample tree: {err}"),
+        );
+    }
+    let started = std::time::Instant::now();
+    let options = crate::SearchOptions {
+        max_depth: None,
+        ..crate::SearchOptions::default()
+    };
+    let result = crate::search::search_untracked(&root, "doctor-needle", 5, &options);
+    let elapsed = started.elapsed().as_millis();
+    std::fs::remove_dir_all(&root).ok();
+    let dirs = groups * modules * 2 + groups + 1;
+    match result {
+        Ok(results) if results.iter().any(|r| r.name == "doctor-needle") => {
+            let message = format!("searched {dirs} directories in {elapsed} ms");
+            if elapsed > SLOW_SEARCH_MS {
+                DoctorCheck::new("search", CheckStatus::Warn, message).hint(
+                    "a slow disk or on-access scanning may be involved; lower search.max_depth \
+                     or add search.ignore globs for large trees",
+                )
+            } else {
+                DoctorCheck::new("search", CheckStatus::Ok, message)
+            }
+        }
+        Ok(_) => DoctorCheck::new(
+            "search",
+            CheckStatus::Fail,
+            "search missed the sample directory",
+        ),
+        Err(err) => DoctorCheck::new("search", CheckStatus::Fail, format!("{err:#}")),
+    }
+}
+
+pub(crate) fn doctor() -> DoctorReport {
+    let mut checks = vec![
+        config_check(),
+        store_readable_check(),
+        store_writable_check(),
+    ];
+    checks.extend(terminal_checks());
+    checks.extend(transport_checks());
+    #[cfg(feature = "fs")]
+    checks.push(search_check(10, 10));
+    DoctorReport {
+        ok: checks.iter().all(|check| check.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_check_finds_the_sample() {
+        let check = search_check(2, 2);
+        assert_ne!(check.status, CheckStatus::Fail, "{}", check.message);
+        assert!(check.message.starts_with("searched 11 directories"));
+    }
+}
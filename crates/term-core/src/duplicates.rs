@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{RecentEntry, STORE};
+
+/// What the checkouts of a [`DuplicateGroup`] have in common.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMatch {
+    /// The same remote repository, however its URL is spelled.
+    Remote,
+    /// The same folder name, for projects without a git remote.
+    Name,
+}
+
+/// One checkout of a repository found more than once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCheckout {
+    pub path: String,
+    /// Total bytes of the files below `path`; `None` when it could not be measured.
+    pub bytes: Option<u64>,
+    /// When the checkout or anything below it was last opened, from recents.
+    pub last_opened_utc: Option<i64>,
+    /// When git last touched the index, else when the directory itself changed.
+    pub modified_utc: Option<i64>,
+}
+
+/// Checkouts of the same repository, as returned by [`crate::api::find_duplicate_projects`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// `host/owner/repo` for [`DuplicateMatch::Remote`], else the folder name, lowercased.
+    pub key: String,
+    #[serde(rename = "match")]
+    pub matched_by: DuplicateMatch,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_url: Option<String>,
+    /// Most recently used first, so the clones worth cleaning up come last.
+    pub checkouts: Vec<DuplicateCheckout>,
+    /// Bytes of every checkout but the first.
+    pub reclaimable_bytes: u64,
+}
+
+fn modified_utc(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Utc>::from(modified).timestamp())
+}
+
+/// Latest open of `path` or anything below it.
+fn last_opened(path: &Path, recents: &[RecentEntry]) -> Option<i64> {
+    recents
+        .iter()
+        .filter(|recent| Path::new(&recent.path).starts_with(path))
+        .map(|recent| recent.last_opened_utc)
+        .max()
+}
+
+/// How a project is grouped: its own repository's remote, else its folder name.
+fn duplicate_key(path: &Path) -> (String, DuplicateMatch, Option<String>) {
+    // A project without its own `.git` would otherwise pick up an enclosing repository's remote.
+    let remote = crate::git::git_dir(path).and_then(|_| crate::remote::read_remote(path));
+    match remote {
+        Some(remote) => (
+            format!("{}/{}/{}", remote.host, remote.owner, remote.repo).to_lowercase(),
+            DuplicateMatch::Remote,
+            Some(remote.web_url),
+        ),
+        None => (
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            DuplicateMatch::Name,
+            None,
+        ),
+    }
+}
+
+fn checkout(path: &Path, recents: &[RecentEntry]) -> DuplicateCheckout {
+    let index = crate::git::git_dir(path).map(|dir| dir.join("index"));
+    DuplicateCheckout {
+        path: path.display().to_string(),
+        bytes: crate::dir_size::dir_size_blocking(path)
+            .ok()
+            .and_then(|size| size.bytes),
+        last_opened_utc: last_opened(path, recents),
+        modified_utc: index
+            .and_then(|index| modified_utc(&index))
+            .or_else(|| modified_utc(path)),
+    }
+}
+
+/// Projects below `root` checked out more than once, grouped by remote or folder name. Sizes
+/// are computed on this thread, so the first call over large checkouts takes a while.
+pub(crate) fn find_duplicate_projects(root: &Path) -> anyhow::Result<Vec<DuplicateGroup>> {
+    let mut groups: BTreeMap<(String, DuplicateMatch), (Option<String>, Vec<_>)> = BTreeMap::new();
+    for (path, _) in crate::projects::discover_projects(root)? {
+        let (key, matched_by, web_url) = duplicate_key(&path);
+        if key.is_empty() {
+            continue;
+        }
+        let group = groups.entry((key, matched_by)).or_default();
+        group.0 = group.0.take().or(web_url);
+        group.1.push(path);
+    }
+    let recents = STORE.inner.lock().recents.clone();
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, (_, paths))| paths.len() > 1)
+        .map(|((key, matched_by), (web_url, paths))| {
+            let mut checkouts: Vec<DuplicateCheckout> =
+                paths.iter().map(|path| checkout(path, &recents)).collect();
+            checkouts.sort_by(|a, b| {
+                b.last_opened_utc
+                    .cmp(&a.last_opened_utc)
+                    .then(b.modified_utc.cmp(&a.modified_utc))
+            });
+            DuplicateGroup {
+                reclaimable_bytes: checkouts.iter().skip(1).filter_map(|c| c.bytes).sum(),
+                key,
+                matched_by,
+                web_url,
+                checkouts,
+            }
+        })
+        .collect();
+    duplicates.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then(a.key.cmp(&b.key))
+    });
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn temp_root() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("terminaut-dupes-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn clone(root: &Path, dir: &str, url: &str) {
+        std::fs::create_dir_all(root.join(dir).join(".git")).unwrap();
+        std::fs::write(
+            root.join(dir).join(".git/config"),
+            format!("[remote \"origin\"]\n\turl = {url}\n"),
+        )
+        .unwrap();
+    }
+
+    fn recent(path: &str, last_opened_utc: i64) -> RecentEntry {
+        RecentEntry {
+            path: path.to_string(),
+            last_opened_utc,
+            ..RecentEntry::default()
+        }
+    }
+
+    #[test]
+    fn remotes_spelled_differently_are_one_group() {
+        let root = temp_root();
+        clone(&root, "a/api", "git@github.com:Acme/API.git");
+        clone(&root, "b/api-old", "https://github.com/acme/api");
+        let groups = find_duplicate_projects(&root).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "github.com/acme/api");
+        assert_eq!(groups[0].matched_by, DuplicateMatch::Remote);
+        assert_eq!(groups[0].checkouts.len(), 2);
+        assert!(groups[0].web_url.is_some());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn projects_without_a_remote_group_by_folder_name() {
+        let root = temp_root();
+        for dir in ["a/Notes", "b/notes"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("go.mod"), "module notes\n").unwrap();
+        }
+        let groups = find_duplicate_projects(&root).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            (groups[0].key.as_str(), groups[0].matched_by),
+            ("notes", DuplicateMatch::Name)
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn single_checkouts_are_not_duplicates() {
+        let root = temp_root();
+        clone(&root, "api", "https://github.com/acme/api.git");
+        clone(&root, "web", "https://github.com/acme/web.git");
+        assert!(find_duplicate_projects(&root).unwrap().is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn nested_projects_do_not_take_the_enclosing_remote() {
+        let root = temp_root();
+        clone(&root, "api", "https://github.com/acme/api.git");
+        std::fs::create_dir_all(root.join("api/tools")).unwrap();
+        let (key, matched_by, web_url) = duplicate_key(&root.join("api/tools"));
+        assert_eq!(
+            (key.as_str(), matched_by, web_url),
+            ("tools", DuplicateMatch::Name, None)
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn last_opened_counts_opens_below_the_checkout() {
+        let recents = [
+            recent("/work/api", 10),
+            recent("/work/api/src", 30),
+            recent("/work/api-old", 50),
+        ];
+        assert_eq!(last_opened(Path::new("/work/api"), &recents), Some(30));
+        assert_eq!(last_opened(Path::new("/work/web"), &recents), None);
+    }
+
+    #[test]
+    fn missing_roots_are_not_found() {
+        let root = std::env::temp_dir().join(format!("terminaut-dupes-{}", uuid::Uuid::new_v4()));
+        let err = find_duplicate_projects(&root).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+    }
+}
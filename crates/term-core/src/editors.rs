@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::command_line::quote;
+use crate::launcher::find_program;
+use crate::{CoreError, LaunchRequest, STORE};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Editor {
+    /// `code`, `cursor`, `nvim`, `zed`, or `idea`.
+    pub id: String,
+    pub name: String,
+    /// Runs inside a terminal window rather than as its own app.
+    pub terminal: bool,
+    /// The command-line launcher that was found, or `None` when the editor isn't installed.
+    pub program: Option<String>,
+}
+
+struct EditorSpec {
+    id: &'static str,
+    name: &'static str,
+    terminal: bool,
+    /// Launchers looked up on `PATH`, in order.
+    commands: &'static [&'static str],
+    /// Launchers inside app bundles, for when the shell command was never installed.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    bundled: &'static [&'static str],
+}
+
+const EDITORS: [EditorSpec; 5] = [
+    EditorSpec {
+        id: "code",
+        name: "Visual Studio Code",
+        terminal: false,
+        commands: &["code", "code.cmd"],
+        bundled: &["Visual Studio Code.app/Contents/Resources/app/bin/code"],
+    },
+    EditorSpec {
+        id: "cursor",
+        name: "Cursor",
+        terminal: false,
+        commands: &["cursor", "cursor.cmd"],
+        bundled: &["Cursor.app/Contents/Resources/app/bin/cursor"],
+    },
+    EditorSpec {
+        id: "nvim",
+        name: "Neovim",
+        terminal: true,
+        commands: &["nvim", "nvim.exe"],
+        bundled: &[],
+    },
+    EditorSpec {
+        id: "zed",
+        name: "Zed",
+        terminal: false,
+        commands: &["zed", "zeditor", "zed.exe"],
+        bundled: &["Zed.app/Contents/MacOS/cli"],
+    },
+    EditorSpec {
+        id: "idea",
+        name: "IntelliJ IDEA",
+        terminal: false,
+        commands: &["idea", "idea.sh", "idea64.exe"],
+        bundled: &[
+            "IntelliJ IDEA.app/Contents/MacOS/idea",
+            "IntelliJ IDEA CE.app/Contents/MacOS/idea",
+        ],
+    },
+];
+
+impl EditorSpec {
+    fn locate(&self) -> Option<PathBuf> {
+        if let Some(found) = self
+            .commands
+            .iter()
+            .find_map(|command| find_program(command))
+        {
+            return Some(found);
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let mut roots = vec![PathBuf::from("/Applications")];
+            roots.extend(dirs::home_dir().map(|home| home.join("Applications")));
+            for root in roots {
+                if let Some(found) = self
+                    .bundled
+                    .iter()
+                    .map(|bundled| root.join(bundled))
+                    .find(|launcher| launcher.is_file())
+                {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    fn editor(&self, program: Option<&Path>) -> Editor {
+        Editor {
+            id: self.id.to_string(),
+            name: self.name.to_string(),
+            terminal: self.terminal,
+            program: program.map(|program| program.display().to_string()),
+        }
+    }
+}
+
+fn spec(id: &str) -> anyhow::Result<&'static EditorSpec> {
+    let id = id.trim().to_ascii_lowercase();
+    EDITORS.iter().find(|spec| spec.id == id).ok_or_else(|| {
+        let known: Vec<&str> = EDITORS.iter().map(|spec| spec.id).collect();
+        CoreError::invalid_input(format!("unknown editor {id:?} ({})", known.join(", "))).into()
+    })
+}
+
+/// Every known editor, with where it is installed.
+pub(crate) fn editors() -> Vec<Editor> {
+    EDITORS
+        .iter()
+        .map(|spec| spec.editor(spec.locate().as_deref()))
+        .collect()
+}
+
+/// The editor bound to `path` or its closest bound ancestor in `bindings`.
+fn bound_editor(bindings: &BTreeMap<String, String>, path: &Path) -> Option<String> {
+    path.ancestors()
+        .find_map(|dir| bindings.get(&dir.display().to_string()))
+        .cloned()
+}
+
+/// The editor bound to `path` or its closest bound ancestor.
+pub(crate) fn preferred_editor(path: &str) -> anyhow::Result<Option<String>> {
+    let normalized = crate::normalize_path(path)?;
+    Ok(bound_editor(&STORE.inner.lock().editors, &normalized))
+}
+
+/// Binds `path` to `editor`, or unbinds it. Returns whether the bindings changed.
+fn bind(
+    bindings: &mut BTreeMap<String, String>,
+    path: String,
+    editor: Option<&EditorSpec>,
+) -> bool {
+    let previous = match editor {
+        Some(spec) => bindings.insert(path, spec.id.to_string()),
+        None => bindings.remove(&path),
+    };
+    previous.as_deref() != editor.map(|spec| spec.id)
+}
+
+/// Binds `path` (and everything under it) to `editor`; `None` removes the binding.
+pub(crate) fn set_preferred_editor(path: &str, editor: Option<&str>) -> anyhow::Result<()> {
+    let editor = editor.map(spec).transpose()?;
+    let normalized = crate::normalize_path(path)?.display().to_string();
+    let mut store = STORE.inner.lock();
+    if bind(&mut store.editors, normalized, editor) {
+        STORE.persist(&mut store)?;
+    }
+    Ok(())
+}
+
+/// Opens `path` in `editor`, else the editor bound to it, else the first installed one.
+/// Terminal editors open in a new terminal window at the directory. Returns the editor used.
+pub(crate) fn open_in_editor(path: &str, editor: Option<&str>) -> anyhow::Result<Editor> {
+    let normalized = crate::normalize_path(path)?;
+    if !normalized.exists() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "{} does not exist",
+            normalized.display()
+        )));
+    }
+    let chosen = match editor {
+        Some(id) => Some(spec(id)?),
+        None => preferred_editor(path)?.as_deref().map(spec).transpose()?,
+    };
+    let (spec, program) = match chosen {
+        Some(spec) => {
+            let program = spec.locate().ok_or_else(|| {
+                CoreError::not_found(format!("{} is not installed", spec.name))
+                    .with_hint(format!("put `{}` on PATH", spec.commands[0]))
+            })?;
+            (spec, program)
+        }
+        None => EDITORS
+            .iter()
+            .find_map(|spec| Some((spec, spec.locate()?)))
+            .ok_or_else(|| {
+                CoreError::not_found("no supported editor is installed")
+                    .with_hint("install code, cursor, nvim, zed, or idea and put it on PATH")
+            })?,
+    };
+
+    if spec.terminal {
+        let (dir, file) = match normalized.is_dir() {
+            true => (normalized.as_path(), None),
+            false => (
+                normalized.parent().unwrap_or(Path::new("/")),
+                normalized.file_name(),
+            ),
+        };
+        let target = file.map_or_else(|| String::from("."), |file| quote(&file.to_string_lossy()));
+        crate::launcher::launch(
+            &LaunchRequest {
+                path: dir.display().to_string(),
+                terminal: None,
+                command: Some(format!(
+                    "{} {target}",
+                    quote(&program.display().to_string())
+                )),
+                args: Vec::new(),
+                use_shell: false,
+                pre_command: None,
+                post_launch_command: None,
+                background: false,
+                profile: None,
+                profile_id: None,
+                windows: 1,
+                env: Default::default(),
+                wsl_distro: None,
+            },
+            crate::launcher::Secrets::Leave,
+        )?;
+    } else {
+        Command::new(&program)
+            .arg(&normalized)
+            .spawn()
+            .with_context(|| format!("failed to start {}", spec.name))?;
+    }
+    Ok(spec.editor(Some(&program)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    #[test]
+    fn editor_ids_are_unique_and_have_a_launcher() {
+        for (index, editor) in EDITORS.iter().enumerate() {
+            assert!(!editor.commands.is_empty(), "{}", editor.id);
+            assert!(EDITORS[..index].iter().all(|other| other.id != editor.id));
+        }
+        assert_eq!(
+            editors().iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            ["code", "cursor", "nvim", "zed", "idea"]
+        );
+    }
+
+    #[test]
+    fn editors_are_looked_up_by_id() {
+        assert_eq!(spec(" NVim ").unwrap().name, "Neovim");
+        assert!(spec("nvim").unwrap().terminal);
+        let unknown = spec("emacs").err().unwrap();
+        assert_eq!(error_code(&unknown), ErrorCode::InvalidInput);
+        assert!(format!("{unknown:#}").contains("code, cursor, nvim, zed, idea"));
+    }
+
+    #[test]
+    fn the_closest_bound_ancestor_wins() {
+        let mut bindings = BTreeMap::new();
+        bind(
+            &mut bindings,
+            String::from("/srv"),
+            Some(spec("code").unwrap()),
+        );
+        bind(
+            &mut bindings,
+            String::from("/srv/api"),
+            Some(spec("zed").unwrap()),
+        );
+        let bound = |path: &str| bound_editor(&bindings, Path::new(path));
+        assert_eq!(bound("/srv/api/src").as_deref(), Some("zed"));
+        assert_eq!(bound("/srv/api").as_deref(), Some("zed"));
+        assert_eq!(bound("/srv/web").as_deref(), Some("code"));
+        // A sibling whose name starts the same isn't below the binding.
+        assert_eq!(bound("/srv-old").as_deref(), None);
+    }
+
+    #[test]
+    fn binding_reports_only_real_changes() {
+        let mut bindings = BTreeMap::new();
+        let code = spec("code").unwrap();
+        assert!(bind(&mut bindings, String::from("/srv"), Some(code)));
+        assert!(!bind(&mut bindings, String::from("/srv"), Some(code)));
+        assert!(bind(
+            &mut bindings,
+            String::from("/srv"),
+            Some(spec("zed").unwrap())
+        ));
+        assert!(bind(&mut bindings, String::from("/srv"), None));
+        assert!(!bind(&mut bindings, String::from("/srv"), None));
+        assert!(bindings.is_empty());
+    }
+}
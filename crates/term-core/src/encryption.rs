@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// Written in place of the plain state document when `store.encrypt` is on.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope {
+    /// The cipher, so a future format can be told apart.
+    encrypted: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[cfg(feature = "encryption")]
+const CIPHER: &str = "xchacha20poly1305";
+
+fn envelope(contents: &str) -> Option<Envelope> {
+    serde_json::from_str(contents).ok()
+}
+
+/// Returns the state JSON in `contents`, decrypting it first if it was saved encrypted.
+/// Plain documents pass through, so turning encryption on or off never strands a file.
+pub(crate) fn open(contents: &str) -> anyhow::Result<String> {
+    match envelope(contents) {
+        Some(envelope) => decrypt(&envelope),
+        None => Ok(contents.to_string()),
+    }
+}
+
+#[cfg(feature = "encryption")]
+mod keychain {
+    use anyhow::Context;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use chacha20poly1305::aead::{KeyInit, OsRng};
+    use chacha20poly1305::{Key, XChaCha20Poly1305};
+    use once_cell::sync::Lazy;
+    use parking_lot::Mutex;
+
+    use crate::CoreError;
+
+    const SERVICE: &str = "Terminaut";
+    const ACCOUNT: &str = "state-encryption-key";
+
+    /// Looked up once per process so the keychain prompts at most once.
+    static KEY: Lazy<Mutex<Option<Key>>> = Lazy::new(|| Mutex::new(None));
+
+    fn decode(encoded: &str, source: &str) -> anyhow::Result<Key> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .filter(|bytes| bytes.len() == 32)
+            .ok_or_else(|| {
+                CoreError::invalid_input(format!(
+                    "{source} does not hold a base64-encoded 32-byte key"
+                ))
+            })?;
+        Ok(*Key::from_slice(&bytes))
+    }
+
+    /// The state key from `TERMINAUT_STATE_KEY` (base64; for machines without a keychain) or
+    /// the OS keychain. With `create`, a missing keychain key is generated and stored.
+    pub(super) fn key(create: bool) -> anyhow::Result<Key> {
+        let mut cached = KEY.lock();
+        if let Some(key) = *cached {
+            return Ok(key);
+        }
+        let key = if let Ok(encoded) = std::env::var("TERMINAUT_STATE_KEY") {
+            decode(&encoded, "TERMINAUT_STATE_KEY")?
+        } else {
+            let entry =
+                keyring::Entry::new(SERVICE, ACCOUNT).context("failed to open the OS keychain")?;
+            match entry.get_password() {
+                Ok(encoded) => decode(&encoded, "the keychain entry")?,
+                Err(keyring::Error::NoEntry) if create => {
+                    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+                    entry
+                        .set_password(&STANDARD.encode(key))
+                        .context("failed to store the state key in the OS keychain")?;
+                    key
+                }
+                Err(keyring::Error::NoEntry) => {
+                    return Err(CoreError::not_found("the state encryption key is missing")
+                        .with_hint(format!(
+                            "the state file is encrypted but the keychain has no \
+                             {SERVICE}/{ACCOUNT} entry; restore it or set TERMINAUT_STATE_KEY"
+                        ))
+                        .into())
+                }
+                Err(err) => {
+                    return Err(anyhow::Error::new(err).context("failed to read the OS keychain"))
+                }
+            }
+        };
+        *cached = Some(key);
+        Ok(key)
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn decrypt(envelope: &Envelope) -> anyhow::Result<String> {
+    decrypt_with(&keychain::key(false)?, envelope)
+}
+
+#[cfg(feature = "encryption")]
+fn decrypt_with(key: &chacha20poly1305::Key, envelope: &Envelope) -> anyhow::Result<String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+    if envelope.encrypted != CIPHER {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "unsupported state encryption {:?}",
+            envelope.encrypted
+        )));
+    }
+    let nonce = STANDARD
+        .decode(&envelope.nonce)
+        .ok()
+        .filter(|n| n.len() == 24);
+    let ciphertext = STANDARD.decode(&envelope.ciphertext).ok();
+    let (Some(nonce), Some(ciphertext)) = (nonce, ciphertext) else {
+        anyhow::bail!(CoreError::invalid_input("the encrypted state is malformed"));
+    };
+    let plain = XChaCha20Poly1305::new(key)
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| {
+            CoreError::invalid_input("the state could not be decrypted")
+                .with_hint("the keychain key does not match the one the state was saved with")
+        })?;
+    Ok(String::from_utf8(plain)?)
+}
+
+/// Encrypts the state JSON with the keychain key, creating the key on first use.
+#[cfg(feature = "encryption")]
+pub(crate) fn seal(json: &str) -> anyhow::Result<String> {
+    seal_with(&keychain::key(true)?, json)
+}
+
+#[cfg(feature = "encryption")]
+fn seal_with(key: &chacha20poly1305::Key, json: &str) -> anyhow::Result<String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::XChaCha20Poly1305;
+
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key)
+        .encrypt(&nonce, json.as_bytes())
+        .map_err(|_| anyhow::anyhow!("failed to encrypt the state"))?;
+    Ok(serde_json::to_string_pretty(&Envelope {
+        encrypted: String::from(CIPHER),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })?)
+}
+
+#[cfg(not(feature = "encryption"))]
+fn decrypt(_envelope: &Envelope) -> anyhow::Result<String> {
+    anyhow::bail!(CoreError::invalid_input(
+        "the state file is encrypted but this build has no encryption support"
+    ))
+}
+
+#[cfg(not(feature = "encryption"))]
+pub(crate) fn seal(_json: &str) -> anyhow::Result<String> {
+    anyhow::bail!(CoreError::invalid_input(
+        "store.encrypt is set but this build has no encryption support"
+    ))
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_state_round_trips_and_rejects_other_keys() {
+        let key = chacha20poly1305::Key::from([7; 32]);
+        let sealed = seal_with(&key, "{\"favorites\":[\"/srv/acme\"]}").unwrap();
+        assert!(!sealed.contains("acme"));
+        assert!(envelope("{\"favorites\":[]}").is_none());
+        let sealed = envelope(&sealed).unwrap();
+        assert_eq!(
+            decrypt_with(&key, &sealed).unwrap(),
+            "{\"favorites\":[\"/srv/acme\"]}"
+        );
+        let other = chacha20poly1305::Key::from([8; 32]);
+        assert!(decrypt_with(&other, &sealed).is_err());
+    }
+}
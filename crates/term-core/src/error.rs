@@ -0,0 +1,107 @@
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Broad failure categories callers can branch on; the CLI maps them to exit codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    InvalidInput,
+    Io,
+    StoreCorrupt,
+    /// A mutation's `expected_generation` no longer matches the store; reload and retry.
+    Conflict,
+    Internal,
+}
+
+/// An error with a category and an optional hint for fixing it. Functions still return
+/// `anyhow::Result`; use [`error_code`] to recover the category from any error chain.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CoreError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl CoreError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CoreError {}
+
+/// Classifies an error by the first recognizable cause in its chain.
+pub fn error_code(err: &anyhow::Error) -> ErrorCode {
+    for cause in err.chain() {
+        if let Some(core) = cause.downcast_ref::<CoreError>() {
+            return core.code;
+        }
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            return match io.kind() {
+                std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+                std::io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+                _ => ErrorCode::Io,
+            };
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<toml::de::Error>() {
+            return ErrorCode::InvalidInput;
+        }
+    }
+    ErrorCode::Internal
+}
+
+/// The hint attached to the first [`CoreError`] in the chain, if any.
+pub fn error_hint(err: &anyhow::Error) -> Option<String> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<CoreError>())
+        .and_then(|core| core.hint.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn codes_survive_context() {
+        let err = Err::<(), _>(CoreError::not_found("session not found"))
+            .context("restore")
+            .unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+        let io = std::fs::read("/definitely/missing/terminaut").context("read");
+        assert_eq!(error_code(&io.unwrap_err()), ErrorCode::NotFound);
+        assert_eq!(error_code(&anyhow::anyhow!("boom")), ErrorCode::Internal);
+    }
+}
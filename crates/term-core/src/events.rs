@@ -0,0 +1,185 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// The list a [`StoreEvent`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Favorite,
+    Recent,
+    Tag,
+    Profile,
+}
+
+impl EventKind {
+    pub(crate) const ALL: [Self; 4] = [Self::Favorite, Self::Recent, Self::Tag, Self::Profile];
+}
+
+impl FromStr for EventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "favorite" | "favorites" => Ok(Self::Favorite),
+            "recent" | "recents" => Ok(Self::Recent),
+            "tag" | "tags" => Ok(Self::Tag),
+            "profile" | "profiles" => Ok(Self::Profile),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown event kind {other:?} (favorite, recent, tag, profile)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreChange {
+    Added,
+    Removed,
+    Updated,
+    /// Many entries changed at once (import, merge, prune, undo, moved paths); reload the
+    /// whole list.
+    Reset,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreEvent {
+    pub kind: EventKind,
+    pub change: StoreChange,
+    /// The favorite, recent, or tagged path, or the profile id. Absent for `reset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// The tag name, for tag events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl StoreEvent {
+    pub(crate) fn new(kind: EventKind, change: StoreChange, subject: impl Into<String>) -> Self {
+        Self {
+            kind,
+            change,
+            subject: Some(subject.into()),
+            tag: None,
+        }
+    }
+
+    pub(crate) fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+}
+
+pub type EventCallback = Arc<dyn Fn(&StoreEvent) + Send + Sync>;
+
+enum Sink {
+    Channel(Sender<StoreEvent>),
+    Callback(EventCallback),
+}
+
+struct Subscriber {
+    id: u64,
+    /// `None` receives every kind.
+    kind: Option<EventKind>,
+    sink: Sink,
+}
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<Subscriber>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn add(kind: Option<EventKind>, sink: Sink) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    SUBSCRIBERS.lock().push(Subscriber { id, kind, sink });
+    id
+}
+
+/// Events of `kind` (every kind for `None`) from now on. Dropping the receiver unsubscribes.
+pub(crate) fn subscribe(kind: Option<EventKind>) -> Receiver<StoreEvent> {
+    let (sender, receiver) = channel();
+    add(kind, Sink::Channel(sender));
+    receiver
+}
+
+/// Calls `callback` for each event of `kind` until [`unsubscribe`] is called with the
+/// returned id.
+pub(crate) fn subscribe_callback(kind: Option<EventKind>, callback: EventCallback) -> u64 {
+    add(kind, Sink::Callback(callback))
+}
+
+pub(crate) fn unsubscribe(id: u64) -> bool {
+    let mut subscribers = SUBSCRIBERS.lock();
+    let before = subscribers.len();
+    subscribers.retain(|subscriber| subscriber.id != id);
+    subscribers.len() != before
+}
+
+/// Delivers `event` to its subscribers. Callers must not hold the store lock, since callbacks
+/// run on this thread and may read the store.
+pub(crate) fn publish(event: StoreEvent) {
+    let callbacks: Vec<EventCallback> = {
+        let mut subscribers = SUBSCRIBERS.lock();
+        subscribers.retain(|subscriber| match &subscriber.sink {
+            Sink::Channel(sender) if subscriber.kind.is_none_or(|kind| kind == event.kind) => {
+                sender.send(event.clone()).is_ok()
+            }
+            _ => true,
+        });
+        subscribers
+            .iter()
+            .filter(|subscriber| subscriber.kind.is_none_or(|kind| kind == event.kind))
+            .filter_map(|subscriber| match &subscriber.sink {
+                Sink::Callback(callback) => Some(callback.clone()),
+                Sink::Channel(_) => None,
+            })
+            .collect()
+    };
+    for callback in callbacks {
+        callback(&event);
+    }
+}
+
+/// One `reset` event per kind in `kinds`.
+pub(crate) fn publish_reset(kinds: &[EventKind]) {
+    for &kind in kinds {
+        publish(StoreEvent {
+            kind,
+            change: StoreChange::Reset,
+            subject: None,
+            tag: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_receive_their_kind_only() {
+        let tags = subscribe(Some(EventKind::Tag));
+        let everything = subscribe(None);
+        publish(StoreEvent::new(
+            EventKind::Favorite,
+            StoreChange::Added,
+            "/a",
+        ));
+        publish(StoreEvent::new(EventKind::Tag, StoreChange::Removed, "/b").with_tag("work"));
+
+        let received: Vec<StoreEvent> = tags.try_iter().collect();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].tag.as_deref(), Some("work"));
+        assert_eq!(everything.try_iter().count(), 2);
+
+        drop(tags);
+        publish(StoreEvent::new(EventKind::Tag, StoreChange::Added, "/c"));
+        assert_eq!(everything.try_iter().count(), 1);
+    }
+}
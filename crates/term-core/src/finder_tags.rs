@@ -0,0 +1,301 @@
+use std::path::Path;
+
+use crate::{CoreError, TaggedPath, DEFAULT_TAG_COLOR, STORE};
+
+/// Finder keeps a file's tags here as a binary plist array of `"Name\n<color>"` strings.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const ATTRIBUTE: &str = "com.apple.metadata:_kMDItemUserTags";
+
+/// Finder's label colors by index, as the system colors they are drawn with. Index 0 is a tag
+/// without a color.
+const COLORS: [(u8, &str); 7] = [
+    (1, "#8e8e93"),
+    (2, "#34c759"),
+    (3, "#af52de"),
+    (4, "#0a84ff"),
+    (5, "#ffcc00"),
+    (6, "#ff3b30"),
+    (7, "#ff9500"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FinderTag {
+    name: String,
+    color: u8,
+}
+
+impl FinderTag {
+    fn parse(entry: &str) -> Self {
+        match entry.rsplit_once('\n') {
+            Some((name, color)) => Self {
+                name: name.to_string(),
+                color: color.parse().unwrap_or(0),
+            },
+            None => Self {
+                name: entry.to_string(),
+                color: 0,
+            },
+        }
+    }
+
+    fn format(&self) -> String {
+        match self.color {
+            0 => self.name.clone(),
+            color => format!("{}\n{color}", self.name),
+        }
+    }
+
+    /// The Terminaut color for this tag; `None` (the default color) when it has none.
+    fn hex(&self) -> Option<&'static str> {
+        COLORS
+            .iter()
+            .find(|(index, _)| *index == self.color)
+            .map(|(_, hex)| *hex)
+    }
+}
+
+fn rgb(hex: &str) -> Option<[i32; 3]> {
+    let hex = hex.trim().strip_prefix('#').unwrap_or(hex.trim());
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |at: usize| i32::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// The Finder label closest to `hex`, or 0 (no color) when it isn't a `#rrggbb` color.
+fn finder_color(hex: &str) -> u8 {
+    let Some(target) = rgb(hex) else {
+        return 0;
+    };
+    COLORS
+        .iter()
+        .filter_map(|(index, color)| {
+            let distance: i32 = rgb(color)?
+                .iter()
+                .zip(target)
+                .map(|(a, b)| (a - b).pow(2))
+                .sum();
+            Some((distance, *index))
+        })
+        .min()
+        .map_or(0, |(_, index)| index)
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn decode(bytes: &[u8]) -> anyhow::Result<Vec<FinderTag>> {
+    let entries: Vec<String> = plist::from_bytes(bytes)?;
+    Ok(entries
+        .iter()
+        .map(|entry| FinderTag::parse(entry))
+        .collect())
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn encode(tags: &[FinderTag]) -> anyhow::Result<Vec<u8>> {
+    let entries: Vec<String> = tags.iter().map(FinderTag::format).collect();
+    let mut bytes = Vec::new();
+    plist::to_writer_binary(&mut bytes, &entries)?;
+    Ok(bytes)
+}
+
+#[cfg(target_os = "macos")]
+fn read(path: &Path) -> anyhow::Result<Vec<FinderTag>> {
+    use anyhow::Context;
+
+    let bytes = xattr::get(path, ATTRIBUTE)
+        .with_context(|| format!("failed to read Finder tags of {}", path.display()))?;
+    match bytes {
+        Some(bytes) => decode(&bytes),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn write(path: &Path, tags: &[FinderTag]) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    xattr::set(path, ATTRIBUTE, &encode(tags)?)
+        .with_context(|| format!("failed to write Finder tags of {}", path.display()))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn read(_path: &Path) -> anyhow::Result<Vec<FinderTag>> {
+    ensure_supported().map(|()| Vec::new())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn write(_path: &Path, _tags: &[FinderTag]) -> anyhow::Result<()> {
+    ensure_supported()
+}
+
+/// Fails off macOS, before any state is touched.
+fn ensure_supported() -> anyhow::Result<()> {
+    if !cfg!(target_os = "macos") {
+        anyhow::bail!(CoreError::invalid_input(
+            "Finder tags are only available on macOS"
+        ));
+    }
+    Ok(())
+}
+
+/// Finder tags live on the files themselves, where skipping a save can't take them back, so
+/// read-only mode refuses to write them.
+fn ensure_writable() -> anyhow::Result<()> {
+    if crate::read_only() {
+        anyhow::bail!(CoreError::invalid_input(
+            "Finder tags cannot be written in read-only mode"
+        ));
+    }
+    Ok(())
+}
+
+/// The color to import `tag` with: Finder's, else the default for a tag new here. `None` for a
+/// colorless Finder tag that Terminaut already has, whose color is left as it is.
+fn import_color(tag: &FinderTag, existing: Option<&str>) -> Option<&'static str> {
+    match (tag.hex(), existing) {
+        (Some(hex), _) => Some(hex),
+        (None, Some(_)) => None,
+        (None, None) => Some(DEFAULT_TAG_COLOR),
+    }
+}
+
+/// Favorites and recents, the paths Finder tags are imported from by default.
+fn known_paths() -> Vec<String> {
+    let store = STORE.inner.lock();
+    let mut paths = store.favorites.clone();
+    for recent in &store.recents {
+        if !paths.contains(&recent.path) {
+            paths.push(recent.path.clone());
+        }
+    }
+    paths
+}
+
+/// Copies the Finder tags of `path`, or of every favorite and recent, into Terminaut tags and
+/// returns them. Tags already present get Finder's color, if it has one. A dry run only
+/// returns them.
+pub(crate) fn import(path: Option<&str>, dry_run: bool) -> anyhow::Result<Vec<TaggedPath>> {
+    ensure_supported()?;
+    let paths = match path {
+        Some(path) => vec![crate::normalize_path(path)?.display().to_string()],
+        None => known_paths(),
+    };
+    let mut imported = Vec::new();
+    for normalized in paths {
+        let tags = match read(Path::new(&normalized)) {
+            Ok(tags) => tags,
+            Err(err) if path.is_some() => return Err(err),
+            // A stale favorite or recent shouldn't stop the rest from importing.
+            Err(err) => {
+                tracing::debug!(path = %normalized, "skipping Finder tags: {err:#}");
+                continue;
+            }
+        };
+        for tag in tags {
+            let existing = STORE
+                .inner
+                .lock()
+                .tags
+                .iter()
+                .find(|entry| entry.path == normalized && entry.tag.eq_ignore_ascii_case(&tag.name))
+                .map(|entry| entry.color.clone());
+            let Some(color) = import_color(&tag, existing.as_deref()) else {
+                continue;
+            };
+            if !dry_run {
+                crate::set_tag(&normalized, &tag.name, Some(color))?;
+            }
+            imported.push(TaggedPath {
+                path: normalized.clone(),
+                tag: tag.name,
+                color: color.to_string(),
+                inherited: false,
+            });
+        }
+    }
+    Ok(imported)
+}
+
+/// Writes the Terminaut tags of `path`, or of every tagged path, to Finder. Finder tags that
+/// Terminaut doesn't have are kept. Without `path`, paths Finder can't be read or written at
+/// are skipped, as import skips them. Returns the tags written; a dry run only returns them.
+pub(crate) fn export(path: Option<&str>, dry_run: bool) -> anyhow::Result<Vec<TaggedPath>> {
+    ensure_supported()?;
+    if !dry_run {
+        ensure_writable()?;
+    }
+    let tags: Vec<TaggedPath> = match path {
+        Some(path) => crate::tags_for_path(path)?,
+        None => STORE.inner.lock().tags.clone(),
+    };
+    let mut paths: Vec<&str> = tags.iter().map(|tag| tag.path.as_str()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+    let mut written = Vec::new();
+    for tagged in paths {
+        let mut finder = match read(Path::new(tagged)) {
+            Ok(finder) => finder,
+            Err(err) if path.is_some() => return Err(err),
+            // A tag on a deleted folder shouldn't stop the rest from exporting.
+            Err(err) => {
+                tracing::debug!(path = %tagged, "skipping Finder tags: {err:#}");
+                continue;
+            }
+        };
+        let ours: Vec<&TaggedPath> = tags.iter().filter(|tag| tag.path == tagged).collect();
+        for tag in &ours {
+            let color = finder_color(&tag.color);
+            match finder
+                .iter_mut()
+                .find(|existing| existing.name.eq_ignore_ascii_case(&tag.tag))
+            {
+                Some(existing) => existing.color = color,
+                None => finder.push(FinderTag {
+                    name: tag.tag.clone(),
+                    color,
+                }),
+            }
+        }
+        if !dry_run {
+            match write(Path::new(tagged), &finder) {
+                Ok(()) => {}
+                Err(err) if path.is_some() => return Err(err),
+                Err(err) => {
+                    tracing::warn!(path = %tagged, "skipping Finder tags: {err:#}");
+                    continue;
+                }
+            }
+        }
+        written.extend(ours.into_iter().cloned());
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finder_tags_round_trip_with_colors() {
+        let tags =
+            decode(&encode(&[FinderTag::parse("Client\n6"), FinderTag::parse("Archive")]).unwrap())
+                .unwrap();
+        assert_eq!(tags[0].name, "Client");
+        assert_eq!(tags[0].hex(), Some("#ff3b30"));
+        assert_eq!(tags[1].format(), "Archive");
+        assert_eq!(tags[1].hex(), None);
+        assert_eq!(finder_color("#0a84ff"), 4);
+        assert_eq!(finder_color("#e01010"), 6);
+        assert_eq!(finder_color("blue"), 0);
+    }
+
+    #[test]
+    fn colorless_finder_tags_keep_the_color_set_here() {
+        let red = FinderTag::parse("Client\n6");
+        let plain = FinderTag::parse("Client");
+        assert_eq!(import_color(&red, Some("#123456")), Some("#ff3b30"));
+        assert_eq!(import_color(&plain, Some("#123456")), None);
+        assert_eq!(import_color(&plain, None), Some(DEFAULT_TAG_COLOR));
+    }
+}
@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::{forget_stored_paths, relocate_stored_paths, CoreError};
+
+/// File operations can't be undone by skipping a save, so read-only mode refuses them.
+fn ensure_writable() -> anyhow::Result<()> {
+    if crate::read_only() {
+        anyhow::bail!(CoreError::invalid_input(
+            "file operations are disabled in read-only mode"
+        ));
+    }
+    Ok(())
+}
+
+fn ensure_absent(path: &Path) -> anyhow::Result<()> {
+    if path.symlink_metadata().is_ok() {
+        anyhow::bail!("destination already exists: {}", path.display());
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> anyhow::Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        anyhow::bail!(CoreError::invalid_input(format!("invalid name: {name:?}")));
+    }
+    if name.contains(['/', '\\']) {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "name must not contain path separators: {name:?}"
+        )));
+    }
+    Ok(name)
+}
+
+/// Copies a file or directory tree; used when `rename` cannot cross filesystems.
+fn copy_recursive(from: &Path, to: &Path) -> anyhow::Result<()> {
+    let metadata = from.symlink_metadata()?;
+    if metadata.file_type().is_symlink() {
+        let target = std::fs::read_link(from)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&target, to)?;
+        #[cfg(windows)]
+        {
+            if from.is_dir() {
+                std::os::windows::fs::symlink_dir(&target, to)?;
+            } else {
+                std::os::windows::fs::symlink_file(&target, to)?;
+            }
+        }
+    } else if metadata.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Removes a file, a symlink (not its target), or a directory tree.
+fn remove(path: &Path) -> std::io::Result<()> {
+    match path.symlink_metadata()?.is_dir() {
+        true => std::fs::remove_dir_all(path),
+        false => std::fs::remove_file(path),
+    }
+}
+
+/// Renames `from` to `to` and moves the stored paths under it along.
+fn relocate(from: &Path, to: &Path) -> anyhow::Result<()> {
+    rename_on_disk(from, to)?;
+    crate::candidates::invalidate(from);
+    crate::candidates::invalidate(to);
+    relocate_stored_paths(from, to);
+    Ok(())
+}
+
+/// Renames `from` to `to`, copying and then deleting only when they are on different
+/// filesystems. Any other rename failure is returned with the source untouched.
+fn rename_on_disk(from: &Path, to: &Path) -> anyhow::Result<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(err) = copy_recursive(from, to) {
+                // Leave the source as it was, without a partial copy beside it.
+                let _ = remove(to);
+                return Err(err.context(format!(
+                    "failed to copy {} to {}",
+                    from.display(),
+                    to.display()
+                )));
+            }
+            remove(from).with_context(|| format!("failed to remove {}", from.display()))?;
+        }
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to move {} to {}", from.display(), to.display()))
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn create_dir(path: &Path) -> anyhow::Result<PathBuf> {
+    ensure_writable()?;
+    ensure_absent(path)?;
+    std::fs::create_dir_all(path)
+        .with_context(|| format!("failed to create directory {}", path.display()))?;
+    crate::candidates::invalidate(path);
+    Ok(path.to_path_buf())
+}
+
+/// Renames `path` in place, keeping it in the same parent directory.
+pub(crate) fn rename(path: &Path, new_name: &str) -> anyhow::Result<PathBuf> {
+    ensure_writable()?;
+    let new_name = validate_name(new_name)?;
+    let parent = path
+        .parent()
+        .with_context(|| format!("cannot rename {}", path.display()))?;
+    let target = parent.join(new_name);
+    ensure_absent(&target)?;
+    relocate(path, &target)?;
+    Ok(target)
+}
+
+/// Moves `path` into the directory `destination`, keeping its name.
+pub(crate) fn move_path(path: &Path, destination: &Path) -> anyhow::Result<PathBuf> {
+    ensure_writable()?;
+    if !destination.is_dir() {
+        anyhow::bail!("destination is not a directory: {}", destination.display());
+    }
+    if destination.starts_with(path) {
+        anyhow::bail!("cannot move {} into itself", path.display());
+    }
+    let name = path
+        .file_name()
+        .with_context(|| format!("cannot move {}", path.display()))?;
+    let target = destination.join(name);
+    ensure_absent(&target)?;
+    relocate(path, &target)?;
+    Ok(target)
+}
+
+/// Moves `path` to the system trash and drops store entries that pointed at or below it.
+pub(crate) fn trash(path: &Path) -> anyhow::Result<()> {
+    ensure_writable()?;
+    if path.symlink_metadata().is_err() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "no such file or directory: {}",
+            path.display()
+        )));
+    }
+    trash::delete(path).with_context(|| format!("failed to trash {}", path.display()))?;
+    crate::candidates::invalidate(path);
+    forget_stored_paths(path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_with_separators() {
+        assert!(validate_name("ok-name").is_ok());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("  ").is_err());
+    }
+
+    #[test]
+    fn failed_renames_keep_the_source() {
+        let dir = std::env::temp_dir().join(format!("terminaut-fs-ops-{}", uuid::Uuid::new_v4()));
+        let source = dir.join("src");
+        std::fs::create_dir_all(source.join("inner")).unwrap();
+        let err = rename_on_disk(&source, &dir.join("missing").join("dest")).unwrap_err();
+        assert!(format!("{err:#}").contains("failed to move"));
+        assert!(source.join("inner").is_dir());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn renaming_a_symlink_leaves_its_target() {
+        let dir = std::env::temp_dir().join(format!("terminaut-fs-ops-{}", uuid::Uuid::new_v4()));
+        let target = dir.join("target");
+        std::fs::create_dir_all(&target).unwrap();
+        std::os::unix::fs::symlink(&target, dir.join("link")).unwrap();
+        let link = crate::api::normalize_path_with(
+            &dir.join("link").display().to_string(),
+            crate::SymlinkPolicy::Preserve,
+        )
+        .unwrap();
+        rename_on_disk(Path::new(&link), &dir.join("renamed")).unwrap();
+        assert!(target.is_dir());
+        assert!(dir.join("renamed").symlink_metadata().unwrap().is_symlink());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
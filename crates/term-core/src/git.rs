@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitState {
+    pub root: String,
+    /// Current branch name, or `None` for a detached HEAD.
+    pub branch: Option<String>,
+    /// Abbreviated commit id when HEAD is detached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detached_at: Option<String>,
+    /// Whether the working tree has uncommitted changes; `None` when `git` is unavailable.
+    pub dirty: Option<bool>,
+}
+
+/// A listing entry's state in `git status`. Directories take the most pressing state of
+/// anything inside them, in reverse order of declaration.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileStatus {
+    Ignored,
+    Untracked,
+    Added,
+    Renamed,
+    Deleted,
+    Modified,
+    /// Unmerged, with conflicts to resolve.
+    Conflicted,
+}
+
+impl GitFileStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ignored => "ignored",
+            Self::Untracked => "untracked",
+            Self::Added => "added",
+            Self::Renamed => "renamed",
+            Self::Deleted => "deleted",
+            Self::Modified => "modified",
+            Self::Conflicted => "conflicted",
+        }
+    }
+
+    /// From the two status letters of `git status --porcelain`.
+    fn from_porcelain(code: &str) -> Self {
+        let (index, worktree) = {
+            let mut chars = code.chars();
+            (chars.next().unwrap_or(' '), chars.next().unwrap_or(' '))
+        };
+        match (index, worktree) {
+            ('?', '?') => Self::Untracked,
+            ('!', '!') => Self::Ignored,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => Self::Conflicted,
+            ('D', _) | (_, 'D') => Self::Deleted,
+            ('R', _) | (_, 'R') => Self::Renamed,
+            ('A', ' ') => Self::Added,
+            _ => Self::Modified,
+        }
+    }
+}
+
+/// Parses `git status --porcelain -z` into repo-relative paths and their states. Renames
+/// carry the old path as an extra field, which is skipped.
+fn parse_porcelain(output: &str) -> Vec<(&str, GitFileStatus)> {
+    let mut fields = output.split('\0');
+    let mut entries = Vec::new();
+    while let Some(field) = fields.next() {
+        let (Some(code), Some(path)) = (field.get(..2), field.get(3..)) else {
+            continue;
+        };
+        let status = GitFileStatus::from_porcelain(code);
+        if code.contains(['R', 'C']) {
+            fields.next();
+        }
+        entries.push((path, status));
+    }
+    entries
+}
+
+/// The git state of each child of `dir` that has one, by file name, from a single
+/// `git status` call. `None` outside a repository or when `git` is unavailable.
+pub(crate) fn entry_statuses(dir: &Path) -> Option<HashMap<String, GitFileStatus>> {
+    let root = find_repo_root(dir)?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=normal",
+            "--ignored=matching",
+            "--",
+            ".",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let prefix: Vec<String> = dir
+        .strip_prefix(&root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let mut statuses: HashMap<String, GitFileStatus> = HashMap::new();
+    for (path, status) in parse_porcelain(&String::from_utf8_lossy(&output.stdout)) {
+        let mut parts = path.trim_end_matches('/').split('/');
+        if !prefix.iter().all(|dir| parts.next() == Some(dir.as_str())) {
+            continue;
+        }
+        if let Some(child) = parts.next().filter(|child| !child.is_empty()) {
+            let entry = statuses.entry(child.to_string()).or_insert(status);
+            *entry = (*entry).max(status);
+        }
+    }
+    Some(statuses)
+}
+
+/// Walks up from `path` to the nearest directory containing `.git`.
+pub(crate) fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Resolves the git directory, following the `gitdir:` pointer used by worktrees and submodules.
+pub(crate) fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let target = contents.strip_prefix("gitdir:")?.trim();
+    Some(root.join(target))
+}
+
+fn read_head(git_dir: &Path) -> (Option<String>, Option<String>) {
+    let head = match std::fs::read_to_string(git_dir.join("HEAD")) {
+        Ok(head) => head,
+        Err(_) => return (None, None),
+    };
+    let head = head.trim();
+    if let Some(reference) = head.strip_prefix("ref:") {
+        let reference = reference.trim();
+        let branch = reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(reference)
+            .to_string();
+        (Some(branch), None)
+    } else {
+        (None, Some(head.chars().take(7).collect()))
+    }
+}
+
+fn is_dirty(root: &Path) -> Option<bool> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["status", "--porcelain", "--untracked-files=normal"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(!output.stdout.is_empty())
+}
+
+pub(crate) fn git_state(path: &Path) -> Option<GitState> {
+    let root = find_repo_root(path)?;
+    let (branch, detached_at) = git_dir(&root)
+        .map(|dir| read_head(&dir))
+        .unwrap_or((None, None));
+    Some(GitState {
+        root: root.display().to_string(),
+        branch,
+        detached_at,
+        dirty: is_dirty(&root),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("terminaut-git-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn porcelain_codes_map_to_statuses() {
+        let status = GitFileStatus::from_porcelain;
+        assert_eq!(status(" M"), GitFileStatus::Modified);
+        assert_eq!(status("MM"), GitFileStatus::Modified);
+        assert_eq!(status("A "), GitFileStatus::Added);
+        assert_eq!(status("AM"), GitFileStatus::Modified);
+        assert_eq!(status(" D"), GitFileStatus::Deleted);
+        assert_eq!(status("R "), GitFileStatus::Renamed);
+        assert_eq!(status("??"), GitFileStatus::Untracked);
+        assert_eq!(status("!!"), GitFileStatus::Ignored);
+    }
+
+    #[test]
+    fn both_sided_changes_are_conflicts() {
+        for code in ["UU", "AU", "UD", "AA", "DD"] {
+            assert_eq!(
+                GitFileStatus::from_porcelain(code),
+                GitFileStatus::Conflicted
+            );
+        }
+    }
+
+    #[test]
+    fn renames_skip_their_old_path() {
+        let output = " M src/lib.rs\0R  new.rs\0old.rs\0?? notes/\0";
+        assert_eq!(
+            parse_porcelain(output),
+            [
+                ("src/lib.rs", GitFileStatus::Modified),
+                ("new.rs", GitFileStatus::Renamed),
+                ("notes/", GitFileStatus::Untracked),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_porcelain_fields_are_skipped() {
+        assert_eq!(parse_porcelain(""), []);
+        assert_eq!(
+            parse_porcelain("M\0?? a\0"),
+            [("a", GitFileStatus::Untracked)]
+        );
+    }
+
+    #[test]
+    fn pressing_states_order_last() {
+        assert!(GitFileStatus::Conflicted > GitFileStatus::Modified);
+        assert!(GitFileStatus::Modified > GitFileStatus::Untracked);
+        assert!(GitFileStatus::Untracked > GitFileStatus::Ignored);
+    }
+
+    #[test]
+    fn heads_give_a_branch_or_a_short_commit() {
+        let root = temp_root();
+        std::fs::write(root.join("HEAD"), "ref: refs/heads/feature/x\n").unwrap();
+        assert_eq!(read_head(&root), (Some("feature/x".into()), None));
+        std::fs::write(root.join("HEAD"), "0123456789abcdef\n").unwrap();
+        assert_eq!(read_head(&root), (None, Some("0123456".into())));
+        std::fs::remove_file(root.join("HEAD")).unwrap();
+        assert_eq!(read_head(&root), (None, None));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn worktrees_follow_their_gitdir_pointer() {
+        let root = temp_root();
+        std::fs::create_dir_all(root.join("main/.git/worktrees/wt")).unwrap();
+        std::fs::create_dir_all(root.join("wt/src")).unwrap();
+        std::fs::write(root.join("wt/.git"), "gitdir: ../main/.git/worktrees/wt\n").unwrap();
+        assert_eq!(find_repo_root(&root.join("wt/src")), Some(root.join("wt")));
+        assert_eq!(
+            git_dir(&root.join("wt")),
+            Some(root.join("wt/../main/.git/worktrees/wt"))
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn paths_outside_a_repository_have_no_git_state() {
+        let root = temp_root();
+        assert_eq!(git_dir(&root), None);
+        std::fs::write(root.join(".git"), "not a pointer").unwrap();
+        assert_eq!(git_dir(&root), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
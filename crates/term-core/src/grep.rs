@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use anyhow::Context;
+use globset::GlobSet;
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::sinks::UTF8;
+use grep_searcher::{BinaryDetection, SearcherBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::search::build_globs;
+
+/// Lines longer than this are cut down around the match so one minified file can't flood the UI.
+const MAX_LINE_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub relative_path: String,
+    /// 1-based line number.
+    pub line_number: u64,
+    /// 1-based character column of the first match on the line.
+    pub column: usize,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrepOptions {
+    /// Treat the pattern as a literal string rather than a regular expression.
+    pub fixed_strings: bool,
+    /// Case-insensitive unless the pattern contains an uppercase character.
+    pub smart_case: bool,
+    pub case_insensitive: bool,
+    pub max_results: usize,
+    pub max_depth: Option<usize>,
+    pub respect_gitignore: bool,
+    pub hidden: bool,
+    /// Files larger than this many bytes are skipped.
+    pub max_filesize: Option<u64>,
+    /// When non-empty, only files whose name or relative path matches one of these globs.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for GrepOptions {
+    /// Exclusions come from `search.ignore` in the config.
+    fn default() -> Self {
+        Self {
+            fixed_strings: false,
+            smart_case: true,
+            case_insensitive: false,
+            max_results: 200,
+            max_depth: None,
+            respect_gitignore: true,
+            hidden: false,
+            max_filesize: Some(4 * 1024 * 1024),
+            include: Vec::new(),
+            exclude: crate::config::config().search.ignore.clone(),
+        }
+    }
+}
+
+fn build_matcher(pattern: &str, options: &GrepOptions) -> anyhow::Result<RegexMatcher> {
+    RegexMatcherBuilder::new()
+        .fixed_strings(options.fixed_strings)
+        .case_smart(options.smart_case && !options.case_insensitive)
+        .case_insensitive(options.case_insensitive)
+        .build(pattern)
+        .with_context(|| format!("invalid pattern {pattern:?}"))
+}
+
+fn trim_line(line: &str, match_start: usize) -> String {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let skip = match_start.saturating_sub(MAX_LINE_CHARS / 4);
+    line.chars().skip(skip).take(MAX_LINE_CHARS).collect()
+}
+
+fn glob_matches(globs: &GlobSet, root: &Path, path: &Path) -> bool {
+    path.file_name().is_some_and(|name| globs.is_match(name))
+        || path
+            .strip_prefix(root)
+            .is_ok_and(|relative| globs.is_match(relative))
+}
+
+pub(crate) fn grep(
+    root: &Path,
+    pattern: &str,
+    options: &GrepOptions,
+) -> anyhow::Result<Vec<GrepMatch>> {
+    if pattern.is_empty() {
+        anyhow::bail!("pattern required");
+    }
+    let matcher = build_matcher(pattern, options)?;
+    let include = build_globs(&options.include)?;
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .max_depth(options.max_depth)
+        .max_filesize(options.max_filesize)
+        .hidden(!options.hidden)
+        .parents(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .sort_by_file_name(|a, b| a.cmp(b));
+    crate::ignore_file::apply(&mut builder);
+    // Excluded directories are pruned, not walked and then skipped entry by entry.
+    if let Some(exclude) = build_globs(&options.exclude)? {
+        let root = root.to_path_buf();
+        builder.filter_entry(move |entry| !glob_matches(&exclude, &root, entry.path()));
+    }
+
+    let mut matches = Vec::new();
+    for entry in builder.build().flatten() {
+        if matches.len() >= options.max_results {
+            break;
+        }
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        if include
+            .as_ref()
+            .is_some_and(|globs| !glob_matches(globs, root, path))
+        {
+            continue;
+        }
+        let relative_path = path
+            .strip_prefix(root)
+            .map(|relative| relative.display().to_string())
+            .unwrap_or_default();
+        // Unreadable files and invalid UTF-8 are skipped rather than failing the whole search.
+        let _ = searcher.search_path(
+            &matcher,
+            path,
+            UTF8(|line_number, line| {
+                let start = matcher
+                    .find(line.as_bytes())
+                    .ok()
+                    .flatten()
+                    .map(|m| line[..m.start()].chars().count())
+                    .unwrap_or(0);
+                matches.push(GrepMatch {
+                    path: path.display().to_string(),
+                    relative_path: relative_path.clone(),
+                    line_number,
+                    column: start + 1,
+                    line: trim_line(line, start),
+                });
+                Ok(matches.len() < options.max_results)
+            }),
+        );
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_lines_and_skips_binaries() {
+        let dir = std::env::temp_dir().join("terminaut-grep-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "alpha\nNeedle here\nomega\n").unwrap();
+        std::fs::write(dir.join("blob.bin"), b"needle\x00\x01\x02").unwrap();
+        let found = grep(&dir, "needle", &GrepOptions::default()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].relative_path, "notes.txt");
+        assert_eq!(found[0].line_number, 2);
+        assert_eq!(found[0].column, 1);
+        assert_eq!(found[0].line, "Needle here");
+    }
+
+    #[test]
+    fn excluded_directories_are_not_entered() {
+        let dir = std::env::temp_dir().join(format!("terminaut-grep-{}", uuid::Uuid::new_v4()));
+        let excluded = dir.join("node_modules");
+        std::fs::create_dir_all(excluded.join("inner")).unwrap();
+        std::fs::write(dir.join("main.js"), "needle\n").unwrap();
+        std::fs::write(excluded.join("inner/dep.js"), "needle\n").unwrap();
+        let options = GrepOptions {
+            exclude: vec![String::from("node_modules")],
+            ..GrepOptions::default()
+        };
+        // `node_modules` matches the directory only, so files below it are skipped only when
+        // the walk doesn't go in.
+        let found = grep(&dir, "needle", &options).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].relative_path, "main.js");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+use std::cmp::Reverse;
+use std::path::Path;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{normalize_path, STORE};
+
+const HISTORY_CAP: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHistoryEntry {
+    pub query: String,
+    pub root: String,
+    pub last_used_utc: i64,
+    pub count: u32,
+}
+
+/// Counts a use of `query` under `root` at `now`, dropping the oldest entries past the cap.
+fn record(history: &mut Vec<SearchHistoryEntry>, root: String, query: &str, now: i64) {
+    match history
+        .iter_mut()
+        .find(|entry| entry.root == root && entry.query == query)
+    {
+        Some(entry) => {
+            entry.count = entry.count.saturating_add(1);
+            entry.last_used_utc = now;
+        }
+        None => history.push(SearchHistoryEntry {
+            query: query.to_string(),
+            root,
+            last_used_utc: now,
+            count: 1,
+        }),
+    }
+    if history.len() > HISTORY_CAP {
+        history.sort_by_key(|entry| Reverse(entry.last_used_utc));
+        history.truncate(HISTORY_CAP);
+    }
+}
+
+pub(crate) fn record_search(root: &Path, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    let mut store = STORE.inner.lock();
+    record(
+        &mut store.search_history,
+        root.display().to_string(),
+        query,
+        Utc::now().timestamp(),
+    );
+    if let Err(err) = STORE.persist(&mut store) {
+        tracing::warn!("failed to save search history: {err:#}");
+    }
+}
+
+fn normalized_root(root: Option<&str>) -> anyhow::Result<Option<String>> {
+    root.map(|root| normalize_path(root).map(|p| p.display().to_string()))
+        .transpose()
+}
+
+fn recent(
+    history: &[SearchHistoryEntry],
+    root: Option<&str>,
+    limit: usize,
+) -> Vec<SearchHistoryEntry> {
+    let mut entries: Vec<_> = history
+        .iter()
+        .filter(|entry| root.is_none_or(|root| entry.root == root))
+        .cloned()
+        .collect();
+    entries.sort_by_key(|entry| Reverse(entry.last_used_utc));
+    entries.truncate(limit);
+    entries
+}
+
+/// Most recent queries first, optionally restricted to one search root.
+pub(crate) fn search_history(
+    root: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<Vec<SearchHistoryEntry>> {
+    let root = normalized_root(root)?;
+    Ok(recent(
+        &STORE.inner.lock().search_history,
+        root.as_deref(),
+        limit,
+    ))
+}
+
+fn suggest(
+    history: &[SearchHistoryEntry],
+    root: Option<&str>,
+    prefix: &str,
+    limit: usize,
+) -> Vec<String> {
+    let prefix = prefix.trim().to_lowercase();
+    let mut candidates: Vec<&SearchHistoryEntry> = history
+        .iter()
+        .filter(|entry| {
+            let query = entry.query.to_lowercase();
+            query.starts_with(&prefix) && query != prefix
+        })
+        .collect();
+    candidates.sort_by_key(|entry| {
+        let same_root = root.is_some_and(|root| entry.root == root);
+        (
+            Reverse(same_root),
+            Reverse(entry.count),
+            Reverse(entry.last_used_utc),
+        )
+    });
+    let mut suggestions: Vec<String> = Vec::new();
+    for entry in candidates {
+        if suggestions.len() >= limit {
+            break;
+        }
+        if !suggestions.contains(&entry.query) {
+            suggestions.push(entry.query.clone());
+        }
+    }
+    suggestions
+}
+
+/// Past queries starting with `prefix` (case-insensitively). Queries used under `root` rank
+/// ahead of those from other roots, then by use count and recency.
+pub(crate) fn search_suggestions(
+    root: Option<&str>,
+    prefix: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<String>> {
+    let root = normalized_root(root)?;
+    Ok(suggest(
+        &STORE.inner.lock().search_history,
+        root.as_deref(),
+        prefix,
+        limit,
+    ))
+}
+
+pub(crate) fn clear_search_history() -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    store.search_history.clear();
+    STORE.persist(&mut store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(uses: &[(&str, &str, i64)]) -> Vec<SearchHistoryEntry> {
+        let mut history = Vec::new();
+        for (root, query, at) in uses {
+            record(&mut history, root.to_string(), query, *at);
+        }
+        history
+    }
+
+    #[test]
+    fn repeated_queries_are_counted_per_root() {
+        let history = history(&[("/a", "api", 1), ("/a", "api", 5), ("/b", "api", 3)]);
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].count, history[0].last_used_utc), (2, 5));
+        assert_eq!(history[1].count, 1);
+    }
+
+    #[test]
+    fn the_oldest_queries_go_past_the_cap() {
+        let mut history = Vec::new();
+        for at in 0..=HISTORY_CAP as i64 {
+            record(&mut history, String::from("/a"), &format!("q{at}"), at);
+        }
+        assert_eq!(history.len(), HISTORY_CAP);
+        assert!(history.iter().all(|entry| entry.query != "q0"));
+    }
+
+    #[test]
+    fn history_is_newest_first_and_filtered_by_root() {
+        let history = history(&[("/a", "one", 1), ("/b", "two", 2), ("/a", "three", 3)]);
+        let queries = |entries: Vec<SearchHistoryEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.query).collect()
+        };
+        assert_eq!(queries(recent(&history, None, 10)), ["three", "two", "one"]);
+        assert_eq!(queries(recent(&history, Some("/a"), 10)), ["three", "one"]);
+        assert_eq!(queries(recent(&history, None, 1)), ["three"]);
+        assert!(recent(&history, Some("/c"), 10).is_empty());
+    }
+
+    #[test]
+    fn suggestions_prefer_the_same_root_then_use() {
+        let history = history(&[
+            ("/b", "api-gateway", 1),
+            ("/b", "api-gateway", 2),
+            ("/a", "api-docs", 1),
+            ("/a", "API-v2", 3),
+            ("/b", "api-docs", 4),
+            ("/a", "web", 5),
+        ]);
+        assert_eq!(
+            suggest(&history, Some("/a"), "Api", 10),
+            ["API-v2", "api-docs", "api-gateway"]
+        );
+        assert_eq!(
+            suggest(&history, None, "api", 10),
+            ["api-gateway", "api-docs", "API-v2"]
+        );
+        assert_eq!(suggest(&history, None, "api", 1), ["api-gateway"]);
+    }
+
+    #[test]
+    fn exact_matches_are_not_suggested() {
+        let history = history(&[("/a", "web", 1)]);
+        assert!(suggest(&history, None, "WEB ", 10).is_empty());
+        assert_eq!(suggest(&history, None, "", 10), ["web"]);
+    }
+}
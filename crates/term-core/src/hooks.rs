@@ -0,0 +1,134 @@
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+/// Store and launch events users can attach commands to under `[hooks]` in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    FavoriteAdded,
+    FavoriteRemoved,
+    RecentTouched,
+    TagAdded,
+    TagRemoved,
+    ProfileLaunched,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            Self::FavoriteAdded => "favorite_added",
+            Self::FavoriteRemoved => "favorite_removed",
+            Self::RecentTouched => "recent_touched",
+            Self::TagAdded => "tag_added",
+            Self::TagRemoved => "tag_removed",
+            Self::ProfileLaunched => "profile_launched",
+        }
+    }
+}
+
+pub(crate) fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// The process for one hook command, described as [`fire`] describes the event.
+fn command(event: HookEvent, command: &str, vars: &[(&str, &str)]) -> Command {
+    let mut child = shell(command);
+    child
+        .env("TERMINAUT_EVENT", event.name())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    for (name, value) in vars {
+        child.env(format!("TERMINAUT_{}", name.to_ascii_uppercase()), value);
+    }
+    child
+}
+
+/// Runs every command configured for `event` in the background. The event is described to the
+/// command through `TERMINAUT_EVENT` plus one `TERMINAUT_<NAME>` variable per entry in `vars`.
+/// Hook failures never affect the operation that triggered them. Read-only mode runs none.
+pub(crate) fn fire(event: HookEvent, vars: &[(&str, &str)]) {
+    if crate::read_only() {
+        return;
+    }
+    let commands = crate::config::config()
+        .hooks
+        .get(&event)
+        .cloned()
+        .unwrap_or_default();
+    for hook in commands {
+        match command(event, &hook, vars).spawn() {
+            Ok(mut child) => {
+                // Reap in the background so long-running hooks don't leave zombies behind.
+                std::thread::spawn(move || child.wait());
+            }
+            Err(err) => tracing::warn!("hook {} failed to start: {err}", event.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_names_match_the_config_keys() {
+        for event in [
+            HookEvent::FavoriteAdded,
+            HookEvent::FavoriteRemoved,
+            HookEvent::RecentTouched,
+            HookEvent::TagAdded,
+            HookEvent::TagRemoved,
+            HookEvent::ProfileLaunched,
+        ] {
+            assert_eq!(serde_json::to_value(event).unwrap(), event.name());
+        }
+    }
+
+    #[test]
+    fn hooks_see_the_event_in_their_environment() {
+        let hook = command(
+            HookEvent::TagAdded,
+            "exit 0",
+            &[("path", "/srv/api"), ("tag", "client work")],
+        );
+        let envs: Vec<(String, String)> = hook
+            .get_envs()
+            .filter_map(|(name, value)| {
+                Some((name.to_str()?.to_string(), value?.to_str()?.to_string()))
+            })
+            .collect();
+        assert_eq!(
+            envs,
+            [
+                ("TERMINAUT_EVENT", "tag_added"),
+                ("TERMINAUT_PATH", "/srv/api"),
+                ("TERMINAUT_TAG", "client work"),
+            ]
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_run_through_the_shell() {
+        let check = r#"test "$TERMINAUT_EVENT:$TERMINAUT_TAG" = "tag_added:a b""#;
+        let status = command(HookEvent::TagAdded, check, &[("tag", "a b")])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let status = command(HookEvent::TagRemoved, check, &[("tag", "a b")])
+            .status()
+            .unwrap();
+        assert!(!status.success());
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+#[cfg(feature = "fs")]
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+#[cfg(feature = "fs")]
+use ignore::WalkBuilder;
+
+/// Per-directory ignore file, in gitignore syntax. Patterns apply to the directory holding
+/// the file and everything below it.
+#[cfg(feature = "fs")]
+pub(crate) const IGNORE_FILE: &str = ".terminautignore";
+
+/// The global ignore file next to `config.toml`, applied under every root. Its patterns are
+/// matched by name, so anchored ones like `/build` have no effect there.
+#[cfg(feature = "fs")]
+pub(crate) fn global_ignore_file() -> PathBuf {
+    crate::config::config_path().with_file_name(IGNORE_FILE)
+}
+
+/// Makes a walk skip what the global and per-directory `.terminautignore` files exclude.
+#[cfg(feature = "fs")]
+pub(crate) fn apply(builder: &mut WalkBuilder) {
+    builder.add_custom_ignore_filename(IGNORE_FILE);
+    let global = global_ignore_file();
+    if global.is_file() {
+        if let Some(err) = builder.add_ignore(&global) {
+            tracing::warn!("skipping part of {}: {err}", global.display());
+        }
+    }
+}
+
+/// Ignore files in effect during a hand-rolled walk: the global one, then one per directory
+/// entered that has its own. Deeper files win, as in git.
+pub(crate) struct IgnoreStack {
+    #[cfg(feature = "fs")]
+    stack: Vec<Gitignore>,
+}
+
+#[cfg(feature = "fs")]
+impl IgnoreStack {
+    fn load(dir: &Path, file: &Path) -> Option<Gitignore> {
+        if !file.is_file() {
+            return None;
+        }
+        let mut builder = GitignoreBuilder::new(dir);
+        if let Some(err) = builder.add(file) {
+            tracing::warn!("skipping part of {}: {err}", file.display());
+        }
+        builder
+            .build()
+            .inspect_err(|err| tracing::warn!("ignoring {}: {err}", file.display()))
+            .ok()
+    }
+
+    pub(crate) fn new() -> Self {
+        let global = global_ignore_file();
+        Self {
+            stack: Self::load(Path::new(""), &global).into_iter().collect(),
+        }
+    }
+
+    /// Picks up `dir`'s own ignore file; returns whether one was pushed for [`Self::leave`].
+    pub(crate) fn enter(&mut self, dir: &Path) -> bool {
+        match Self::load(dir, &dir.join(IGNORE_FILE)) {
+            Some(ignore) => {
+                self.stack.push(ignore);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for ignore in self.stack.iter().rev() {
+            let matched = ignore.matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+/// Without the `fs` feature there is no gitignore matcher, so nothing is ignored.
+#[cfg(not(feature = "fs"))]
+impl IgnoreStack {
+    pub(crate) fn new() -> Self {
+        Self {}
+    }
+
+    pub(crate) fn enter(&mut self, _dir: &Path) -> bool {
+        false
+    }
+
+    pub(crate) fn leave(&mut self) {}
+
+    pub(crate) fn is_ignored(&self, _path: &Path, _is_dir: bool) -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("terminaut-ignore-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("app/vendor")).unwrap();
+        std::fs::write(root.join(IGNORE_FILE), "vendor/\ndist\n").unwrap();
+        std::fs::write(root.join("app").join(IGNORE_FILE), "!vendor/\n").unwrap();
+        root
+    }
+
+    fn empty() -> IgnoreStack {
+        IgnoreStack { stack: Vec::new() }
+    }
+
+    #[test]
+    fn nothing_is_ignored_without_ignore_files() {
+        assert!(!empty().is_ignored(Path::new("/work/vendor"), true));
+    }
+
+    #[test]
+    fn patterns_apply_below_their_directory() {
+        let root = temp_root();
+        let mut ignores = empty();
+        assert!(ignores.enter(&root));
+        assert!(ignores.is_ignored(&root.join("vendor"), true));
+        assert!(ignores.is_ignored(&root.join("app/dist"), true));
+        assert!(!ignores.is_ignored(&root.join("app/src"), true));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn directory_patterns_do_not_match_files() {
+        let root = temp_root();
+        let mut ignores = empty();
+        ignores.enter(&root);
+        assert!(!ignores.is_ignored(&root.join("vendor"), false));
+        assert!(ignores.is_ignored(&root.join("dist"), false));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn deeper_negations_win_until_left() {
+        let root = temp_root();
+        let mut ignores = empty();
+        ignores.enter(&root);
+        assert!(ignores.enter(&root.join("app")));
+        assert!(!ignores.is_ignored(&root.join("app/vendor"), true));
+        ignores.leave();
+        assert!(ignores.is_ignored(&root.join("app/vendor"), true));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn directories_without_an_ignore_file_push_nothing() {
+        let root = temp_root();
+        let mut ignores = empty();
+        assert!(!ignores.enter(&root.join("app/vendor")));
+        assert!(!ignores.enter(&root.join("missing")));
+        assert!(ignores.stack.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn walks_skip_what_ignore_files_exclude() {
+        let root = temp_root();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        let mut builder = WalkBuilder::new(&root);
+        builder.hidden(false);
+        apply(&mut builder);
+        let walked: Vec<PathBuf> = builder
+            .build()
+            .filter_map(Result::ok)
+            .map(|entry| entry.into_path())
+            .collect();
+        assert!(!walked.contains(&root.join("dist")));
+        assert!(!walked.contains(&root.join("vendor")));
+        assert!(walked.contains(&root.join("app/vendor")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
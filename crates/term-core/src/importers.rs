@@ -0,0 +1,661 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, EventKind};
+use crate::hooks::{self, HookEvent};
+use crate::{paths, CoreError, RecentEntry, STORE};
+
+/// Where imported entries came from. Recorded with each imported favorite as its `source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// `~/.config/gtk-3.0/bookmarks`, the Files/Nautilus sidebar on Linux.
+    GtkBookmarks,
+    /// The Favorites section of the macOS Finder sidebar.
+    FinderSidebar,
+    /// Folders opened in VS Code, VS Code Insiders, or VSCodium.
+    Vscode,
+    /// `recentProjects.xml` of every JetBrains IDE (and Android Studio) version installed.
+    Jetbrains,
+    /// The directories zoxide has learned from `cd`.
+    Zoxide,
+}
+
+impl ImportSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GtkBookmarks => "gtk_bookmarks",
+            Self::FinderSidebar => "finder_sidebar",
+            Self::Vscode => "vscode",
+            Self::Jetbrains => "jetbrains",
+            Self::Zoxide => "zoxide",
+        }
+    }
+
+    /// Whether this machine has anything to import from `self`, without reading it.
+    pub fn is_available(self) -> bool {
+        let config = dirs::config_dir();
+        match self {
+            Self::GtkBookmarks => gtk_bookmarks_file().is_ok_and(|file| file.is_file()),
+            Self::FinderSidebar => finder_sidebar_file().is_ok_and(|file| file.is_file()),
+            Self::Vscode => config.is_some_and(|config| {
+                VSCODE_PRODUCTS
+                    .iter()
+                    .any(|product| config.join(product).join("User").is_dir())
+            }),
+            Self::Jetbrains => config.is_some_and(|config| {
+                ["JetBrains", "Google"]
+                    .iter()
+                    .any(|vendor| config.join(vendor).is_dir())
+            }),
+            Self::Zoxide => zoxide_file().is_ok_and(|file| file.is_file()),
+        }
+    }
+
+    /// The file manager bookmarks of this platform.
+    pub fn platform_bookmarks() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::FinderSidebar
+        } else {
+            Self::GtkBookmarks
+        }
+    }
+}
+
+impl FromStr for ImportSource {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().replace('-', "_").as_str() {
+            "gtk" | "gtk_bookmarks" => Ok(Self::GtkBookmarks),
+            "finder" | "finder_sidebar" => Ok(Self::FinderSidebar),
+            "vscode" | "code" => Ok(Self::Vscode),
+            "jetbrains" | "idea" => Ok(Self::Jetbrains),
+            "zoxide" | "z" => Ok(Self::Zoxide),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown import source {other:?} (gtk, finder, vscode, jetbrains, zoxide)"
+            ))),
+        }
+    }
+}
+
+/// What an import added, or with `dry_run` would add.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub dry_run: bool,
+    pub favorites_added: Vec<String>,
+    pub recents_added: Vec<String>,
+    /// Entries that were already present. Recents among them keep the newer timestamp.
+    pub existing: usize,
+    /// Entries that aren't local directories, such as network locations or deleted folders.
+    pub skipped: Vec<String>,
+}
+
+/// A directory named by another app, with when it was last opened there if known.
+struct Candidate {
+    path: String,
+    opened_utc: Option<i64>,
+}
+
+impl From<String> for Candidate {
+    fn from(path: String) -> Self {
+        Self {
+            path,
+            opened_utc: None,
+        }
+    }
+}
+
+fn read_source(path: &Path) -> anyhow::Result<Vec<u8>> {
+    if !path.is_file() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "nothing to import at {}",
+            path.display()
+        )));
+    }
+    std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn gtk_bookmarks_file() -> anyhow::Result<PathBuf> {
+    let config = dirs::config_dir().context("no config directory")?;
+    let current = config.join("gtk-3.0").join("bookmarks");
+    let legacy = dirs::home_dir().map(|home| home.join(".gtk-bookmarks"));
+    Ok(match legacy {
+        Some(legacy) if !current.exists() && legacy.exists() => legacy,
+        _ => current,
+    })
+}
+
+/// One `URI [label]` per line. Non-`file:` URIs (`sftp://`, `smb://`, ...) are returned as
+/// given so they can be reported as skipped.
+fn parse_gtk_bookmarks(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|uri| match paths::file_uri_path(uri) {
+            Ok(Some(path)) => path,
+            _ => uri.to_string(),
+        })
+        .collect()
+}
+
+fn finder_sidebar_file() -> anyhow::Result<PathBuf> {
+    let support = dirs::home_dir()
+        .context("no home directory")?
+        .join("Library/Application Support/com.apple.sharedfilelist");
+    let newest = support.join("com.apple.LSSharedFileList.FavoriteItems.sfl3");
+    Ok(if newest.exists() {
+        newest
+    } else {
+        support.join("com.apple.LSSharedFileList.FavoriteItems.sfl2")
+    })
+}
+
+/// The sidebar list is a keyed archive whose items hold macOS bookmark blobs (older lists
+/// hold `file://` URL strings instead); each one found is returned in order.
+fn parse_finder_sidebar(bytes: &[u8]) -> anyhow::Result<Vec<String>> {
+    let archive: plist::Value = plist::from_bytes(bytes).map_err(|err| {
+        CoreError::invalid_input(format!("the Finder sidebar list is unreadable: {err}"))
+    })?;
+    let objects = archive
+        .as_dictionary()
+        .and_then(|archive| archive.get("$objects"))
+        .and_then(plist::Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut found = Vec::new();
+    for object in objects {
+        let path = match object {
+            plist::Value::Data(data) => bookmark_path(data),
+            plist::Value::String(uri) => paths::file_uri_path(uri).ok().flatten(),
+            _ => None,
+        };
+        if let Some(path) = path.filter(|path| !found.contains(path)) {
+            found.push(path);
+        }
+    }
+    Ok(found)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<usize> {
+    let word = bytes.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes(word.try_into().ok()?) as usize)
+}
+
+/// The target path of a macOS bookmark (`book` blob): the path-components array (key
+/// `0x1004`) from its first table of contents. Offsets are relative to the data area that
+/// follows the header.
+fn bookmark_path(blob: &[u8]) -> Option<String> {
+    const PATH_COMPONENTS: usize = 0x1004;
+    const STRING: usize = 0x0101;
+    const ARRAY: usize = 0x0601;
+
+    if !blob.starts_with(b"book") {
+        return None;
+    }
+    let data = blob.get(u32_at(blob, 12)?..)?;
+    let record = |offset: usize| -> Option<(usize, &[u8])> {
+        let length = u32_at(data, offset)?;
+        let kind = u32_at(data, offset + 4)?;
+        Some((kind, data.get(offset + 8..offset + 8 + length)?))
+    };
+    let toc = u32_at(data, 0)?;
+    let count = u32_at(data, toc + 16)?;
+    let components = (0..count).find_map(|index| {
+        let entry = toc + 20 + index * 12;
+        (u32_at(data, entry)? == PATH_COMPONENTS).then(|| u32_at(data, entry + 4))?
+    })?;
+    let (ARRAY, items) = record(components)? else {
+        return None;
+    };
+    let mut path = String::new();
+    for item in items.chunks_exact(4) {
+        let (STRING, name) = record(u32_at(item, 0)?)? else {
+            return None;
+        };
+        path.push('/');
+        path.push_str(std::str::from_utf8(name).ok()?);
+    }
+    Some(if path.is_empty() {
+        String::from("/")
+    } else {
+        path
+    })
+}
+
+fn modified_utc(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Utc>::from(modified).timestamp())
+}
+
+/// VS Code and its builds that keep per-user state in the same layout.
+const VSCODE_PRODUCTS: [&str; 3] = ["Code", "Code - Insiders", "VSCodium"];
+
+/// Every `file:` URI in a VS Code `storage.json`, as a value or as a key (`profileAssociations`
+/// is keyed by workspace URI). Files among them are skipped later.
+fn storage_paths(value: &serde_json::Value, found: &mut Vec<String>) {
+    fn push(uri: &str, found: &mut Vec<String>) {
+        if let Ok(Some(path)) = paths::file_uri_path(uri) {
+            if !found.contains(&path) {
+                found.push(path);
+            }
+        }
+    }
+    match value {
+        serde_json::Value::String(uri) => push(uri, found),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                storage_paths(item, found);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map {
+                push(key, found);
+                storage_paths(item, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn vscode_candidates() -> anyhow::Result<Vec<Candidate>> {
+    let config = dirs::config_dir().context("no config directory")?;
+    let users: Vec<PathBuf> = VSCODE_PRODUCTS
+        .iter()
+        .map(|product| config.join(product).join("User"))
+        .filter(|user| user.is_dir())
+        .collect();
+    if users.is_empty() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "no VS Code settings under {}",
+            config.display()
+        )));
+    }
+    let mut candidates = Vec::new();
+    for user in users {
+        // One directory per opened folder; its `workspace.json` names the folder.
+        let workspaces = std::fs::read_dir(user.join("workspaceStorage"))
+            .into_iter()
+            .flatten();
+        for workspace in workspaces.flatten() {
+            let dir = workspace.path();
+            let folder = std::fs::read_to_string(dir.join("workspace.json"))
+                .ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .and_then(|json| paths::file_uri_path(json.get("folder")?.as_str()?).ok()?);
+            if let Some(path) = folder {
+                candidates.push(Candidate {
+                    path,
+                    opened_utc: modified_utc(&dir.join("state.vscdb")).or(modified_utc(&dir)),
+                });
+            }
+        }
+        let storage = user.join("globalStorage").join("storage.json");
+        let Some(json) = std::fs::read_to_string(&storage)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+        else {
+            continue;
+        };
+        let mut found = Vec::new();
+        storage_paths(&json, &mut found);
+        candidates.extend(found.into_iter().map(Candidate::from));
+    }
+    Ok(candidates)
+}
+
+/// Parses `recentProjects.xml`: an `<entry key="PATH">` per project holding an
+/// `activationTimestamp` option in milliseconds, or a plain `recentPaths` list of
+/// `<option value="PATH"/>` in older versions. `$USER_HOME$` is replaced with `home`.
+fn parse_jetbrains(xml: &str, home: Option<&Path>) -> anyhow::Result<Vec<Candidate>> {
+    use quick_xml::events::Event;
+
+    let expand = |path: String| match home {
+        Some(home) => path.replace("$USER_HOME$", &home.display().to_string()),
+        None => path,
+    };
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut in_entry = false;
+    loop {
+        let event = reader.read_event().map_err(|err| {
+            CoreError::invalid_input(format!("malformed JetBrains project list: {err}"))
+        })?;
+        let opens = matches!(event, Event::Start(_));
+        match event {
+            Event::Start(element) | Event::Empty(element) => {
+                let attribute = |name: &str| {
+                    let value = element.try_get_attribute(name).ok()??;
+                    Some(value.unescape_value().ok()?.into_owned())
+                };
+                match element.name().as_ref() {
+                    b"entry" => {
+                        if let Some(key) = attribute("key") {
+                            candidates.push(Candidate::from(expand(key)));
+                            in_entry = opens;
+                        }
+                    }
+                    b"option" => match (attribute("name").as_deref(), attribute("value")) {
+                        (Some("activationTimestamp" | "projectOpenTimestamp"), Some(value))
+                            if in_entry =>
+                        {
+                            let opened = value.parse::<i64>().ok().map(|millis| millis / 1000);
+                            if let Some(entry) = candidates.last_mut() {
+                                entry.opened_utc = entry.opened_utc.max(opened);
+                            }
+                        }
+                        (None, Some(value)) => candidates.push(Candidate::from(expand(value))),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            Event::End(element) if element.name().as_ref() == b"entry" => in_entry = false,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(candidates)
+}
+
+fn jetbrains_candidates() -> anyhow::Result<Vec<Candidate>> {
+    let config = dirs::config_dir().context("no config directory")?;
+    let mut files = Vec::new();
+    for vendor in ["JetBrains", "Google"] {
+        let products = std::fs::read_dir(config.join(vendor)).into_iter().flatten();
+        for product in products.flatten() {
+            for name in ["recentProjects.xml", "recentSolutions.xml"] {
+                let file = product.path().join("options").join(name);
+                if file.is_file() {
+                    files.push(file);
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "no JetBrains project lists under {}",
+            config.display()
+        )));
+    }
+    let home = dirs::home_dir();
+    let mut candidates = Vec::new();
+    for file in files {
+        let xml = String::from_utf8_lossy(&read_source(&file)?).into_owned();
+        match parse_jetbrains(&xml, home.as_deref()) {
+            Ok(found) => candidates.extend(found),
+            Err(err) => tracing::warn!(file = %file.display(), "skipping: {err:#}"),
+        }
+    }
+    Ok(candidates)
+}
+
+/// `$_ZO_DATA_DIR/db.zo`, else `db.zo` in zoxide's folder of the local data directory.
+fn zoxide_file() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("_ZO_DATA_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("db.zo"));
+    }
+    Ok(dirs::data_local_dir()
+        .context("no data directory")?
+        .join("zoxide")
+        .join("db.zo"))
+}
+
+/// Parses zoxide's database: a little-endian `u32` format version (3), then the directories
+/// as bincode, a `u64` count followed by a length-prefixed path, an `f64` rank, and a `u64`
+/// last-access time for each.
+fn parse_zoxide(bytes: &[u8]) -> anyhow::Result<Vec<Candidate>> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+        if bytes.len() < len {
+            anyhow::bail!(CoreError::invalid_input("the zoxide database is truncated"));
+        }
+        let (taken, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Ok(taken)
+    }
+    fn u64_from(bytes: &mut &[u8]) -> anyhow::Result<u64> {
+        Ok(u64::from_le_bytes(take(bytes, 8)?.try_into()?))
+    }
+
+    let mut rest = bytes;
+    let version = u32::from_le_bytes(take(&mut rest, 4)?.try_into()?);
+    if version != 3 {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "unsupported zoxide database version {version}"
+        ))
+        .with_hint("upgrade zoxide to 0.8 or later"));
+    }
+    let count = u64_from(&mut rest)?;
+    let mut candidates = Vec::new();
+    for _ in 0..count {
+        let len = usize::try_from(u64_from(&mut rest)?)?;
+        let path = String::from_utf8_lossy(take(&mut rest, len)?).into_owned();
+        let _rank = take(&mut rest, 8)?;
+        let accessed = i64::try_from(u64_from(&mut rest)?).ok();
+        candidates.push(Candidate {
+            path,
+            opened_utc: accessed,
+        });
+    }
+    Ok(candidates)
+}
+
+/// Adds the directories among `candidates` as favorites tagged with `source`, or as recents.
+/// A dry run works on a copy of the state and saves nothing.
+fn apply(
+    source: ImportSource,
+    candidates: Vec<Candidate>,
+    as_favorites: bool,
+    dry_run: bool,
+) -> anyhow::Result<ImportReport> {
+    let mut report = ImportReport {
+        source,
+        dry_run,
+        favorites_added: Vec::new(),
+        recents_added: Vec::new(),
+        existing: 0,
+        skipped: Vec::new(),
+    };
+    let mut found: Vec<(String, Option<i64>)> = Vec::new();
+    for candidate in candidates {
+        let normalized = Path::new(&candidate.path)
+            .is_absolute()
+            .then(|| crate::normalize_path(&candidate.path).ok())
+            .flatten()
+            .filter(|path| path.is_dir());
+        let Some(normalized) = normalized else {
+            report.skipped.push(candidate.path);
+            continue;
+        };
+        let path = normalized.display().to_string();
+        match found.iter_mut().find(|(seen, _)| *seen == path) {
+            Some((_, opened)) => *opened = (*opened).max(candidate.opened_utc),
+            None => found.push((path, candidate.opened_utc)),
+        }
+    }
+
+    let now = Utc::now().timestamp();
+    let mut guard = STORE.inner.lock();
+    let mut copy = dry_run.then(|| guard.clone());
+    let store = copy.as_mut().unwrap_or(&mut *guard);
+    for (path, opened) in found {
+        if as_favorites {
+            if store.favorites.contains(&path) {
+                report.existing += 1;
+                continue;
+            }
+            store.favorites.push(path.clone());
+            store
+                .favorite_sources
+                .insert(path.clone(), source.as_str().to_string());
+            report.favorites_added.push(path);
+        } else {
+            let opened = opened.unwrap_or(now);
+            match store.recents.iter_mut().find(|entry| entry.path == path) {
+                Some(entry) => {
+                    entry.last_opened_utc = entry.last_opened_utc.max(opened);
+                    report.existing += 1;
+                }
+                None => {
+                    store.recents.push(RecentEntry {
+                        path: path.clone(),
+                        last_opened_utc: opened,
+                        ..RecentEntry::default()
+                    });
+                    report.recents_added.push(path);
+                }
+            }
+        }
+    }
+    if !as_favorites {
+        let cap = crate::config::config().recents.cap;
+        store
+            .recents
+            .sort_by_key(|entry| std::cmp::Reverse(entry.last_opened_utc));
+        store.recents.truncate(cap);
+        let recents = &store.recents;
+        report
+            .recents_added
+            .retain(|path| recents.iter().any(|entry| entry.path == *path));
+    }
+    if dry_run {
+        return Ok(report);
+    }
+    STORE.persist(&mut guard)?;
+    drop(guard);
+    events::publish_reset(&[match as_favorites {
+        true => EventKind::Favorite,
+        false => EventKind::Recent,
+    }]);
+    for path in &report.favorites_added {
+        hooks::fire(HookEvent::FavoriteAdded, &[("path", path)]);
+    }
+    for path in &report.recents_added {
+        hooks::fire(HookEvent::RecentTouched, &[("path", path)]);
+    }
+    Ok(report)
+}
+
+/// Imports the directories `source` knows about as favorites or recents.
+pub(crate) fn import(
+    source: ImportSource,
+    as_favorites: bool,
+    dry_run: bool,
+) -> anyhow::Result<ImportReport> {
+    let candidates = match source {
+        ImportSource::GtkBookmarks => {
+            let bytes = read_source(&gtk_bookmarks_file()?)?;
+            let paths = parse_gtk_bookmarks(&String::from_utf8_lossy(&bytes));
+            paths.into_iter().map(Candidate::from).collect()
+        }
+        ImportSource::FinderSidebar => {
+            let paths = parse_finder_sidebar(&read_source(&finder_sidebar_file()?)?)?;
+            paths.into_iter().map(Candidate::from).collect()
+        }
+        ImportSource::Vscode => vscode_candidates()?,
+        ImportSource::Jetbrains => jetbrains_candidates()?,
+        ImportSource::Zoxide => parse_zoxide(&read_source(&zoxide_file()?)?)?,
+    };
+    apply(source, candidates, as_favorites, dry_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gtk_bookmarks_parse_file_uris() {
+        let text = "file:///home/me/dev%20work Work\nsftp://host/srv\n\nfile:///tmp\n";
+        assert_eq!(
+            parse_gtk_bookmarks(text),
+            ["/home/me/dev work", "sftp://host/srv", "/tmp"]
+        );
+    }
+
+    #[test]
+    fn jetbrains_projects_parse_with_timestamps() {
+        let xml = r#"<application>
+  <component name="RecentProjectsManager">
+    <option name="additionalInfo">
+      <map>
+        <entry key="$USER_HOME$/dev/api">
+          <value>
+            <RecentProjectMetaInfo frameTitle="api">
+              <option name="activationTimestamp" value="1700000000000" />
+            </RecentProjectMetaInfo>
+          </value>
+        </entry>
+      </map>
+    </option>
+    <option name="recentPaths">
+      <list>
+        <option value="/srv/legacy &amp; old" />
+      </list>
+    </option>
+  </component>
+</application>"#;
+        let found = parse_jetbrains(xml, Some(Path::new("/home/me"))).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path, "/home/me/dev/api");
+        assert_eq!(found[0].opened_utc, Some(1_700_000_000));
+        assert_eq!(found[1].path, "/srv/legacy & old");
+        assert_eq!(found[1].opened_utc, None);
+    }
+
+    #[test]
+    fn zoxide_database_parses_paths_and_access_times() {
+        let mut db = 3u32.to_le_bytes().to_vec();
+        db.extend(2u64.to_le_bytes());
+        for (path, accessed) in [("/home/me/dev/api", 1_700_000_000u64), ("/tmp", 42)] {
+            db.extend((path.len() as u64).to_le_bytes());
+            db.extend(path.as_bytes());
+            db.extend(12.5f64.to_le_bytes());
+            db.extend(accessed.to_le_bytes());
+        }
+        let found = parse_zoxide(&db).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path, "/home/me/dev/api");
+        assert_eq!(found[0].opened_utc, Some(1_700_000_000));
+        assert_eq!(found[1].opened_utc, Some(42));
+        assert!(parse_zoxide(&db[..db.len() - 3]).is_err());
+        assert!(parse_zoxide(&2u32.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn bookmark_blobs_yield_their_path() {
+        let word = |value: u32| value.to_le_bytes();
+        let mut data = word(0).to_vec();
+        let string = |data: &mut Vec<u8>, text: &str| {
+            let at = data.len() as u32;
+            data.extend(word(text.len() as u32));
+            data.extend(word(0x0101));
+            data.extend(text.as_bytes());
+            data.resize(data.len().next_multiple_of(4), 0);
+            at
+        };
+        let users = string(&mut data, "Users");
+        let me = string(&mut data, "me");
+        let array = data.len() as u32;
+        data.extend(word(8));
+        data.extend(word(0x0601));
+        data.extend(word(users));
+        data.extend(word(me));
+        let toc = data.len() as u32;
+        for value in [12, 0xffff_fffe, 1, 0, 1, 0x1004, array, 0] {
+            data.extend(word(value));
+        }
+        data[..4].copy_from_slice(&word(toc));
+        let mut blob = b"book".to_vec();
+        blob.extend(word(0x30 + data.len() as u32));
+        blob.extend(word(0x1004_0000));
+        blob.extend(word(0x30));
+        blob.resize(0x30, 0);
+        blob.extend(data);
+        assert_eq!(bookmark_path(&blob).as_deref(), Some("/Users/me"));
+        assert_eq!(bookmark_path(b"book"), None);
+    }
+}
@@ -0,0 +1,662 @@
+use std::collections::BTreeMap;
+#[cfg(unix)]
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use notify::event::ModifyKind;
+use notify::{EventKind as FsEventKind, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{config, error_code, error_hint, wire, CoreError, Store, WireFormat, STORE};
+
+/// Quiet time after the last change below a root before it is rescanned, so a clone or an
+/// `npm install` costs one scan rather than thousands.
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// How often the watch loop wakes to check for settled roots and a stop request.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Largest binary frame read or written on the socket.
+#[cfg(unix)]
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+/// Longest JSON request line read from the socket.
+#[cfg(unix)]
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// The [`crate::api::invoke`] methods the daemon answers besides `status` and `stop`: queries
+/// that change nothing, so a client can't launch, run, write, or delete anything through it.
+pub(crate) const QUERIES: &[&str] = &[
+    "version",
+    "capabilities",
+    "normalize_path",
+    "display_path",
+    "list_directory",
+    "list_directory_with_git",
+    "list_roots",
+    "ancestors",
+    "suggest_next",
+    "list_favorites",
+    "list_favorites_with_status",
+    "list_recents",
+    "list_recents_with_status",
+    "recents_grouped",
+    "store_generation",
+    "store_status",
+    "fuzzy_filter",
+    "rank_paths",
+    "detect_projects",
+    "project_info",
+    "project_status",
+    "list_projects",
+    "get_config",
+    "get_config_value",
+    "list_tags",
+    "list_tags_with_status",
+    "tags_for",
+    "paths_with_tag_prefix",
+    "tag_tree",
+    "list_tag_colors",
+    "get_note",
+    "list_notes",
+    "search_notes",
+    "list_actions",
+    "actions_for",
+    "list_profiles",
+    "list_sessions",
+    "list_saved_searches",
+    "undo_history",
+    "search",
+    "grep",
+    "search_history",
+    "search_suggestions",
+];
+
+/// What a running `indexd` reports through [`crate::api::indexd_status`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexdStatus {
+    pub pid: u32,
+    pub socket: String,
+    pub started_utc: i64,
+    /// The `projects.roots` being watched.
+    pub roots: Vec<String>,
+    /// Roots that changed and are rescanned once they have been quiet for two seconds.
+    pub pending: Vec<String>,
+    /// Filesystem events received.
+    pub events: u64,
+    /// Rescans run, counting the one at startup.
+    pub scans: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_scan_utc: Option<i64>,
+    /// Registered projects after the last scan.
+    pub projects: usize,
+    /// Why the last rescan failed, until one succeeds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// One line a client writes to the socket.
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// One line the daemon writes back: `{"result": ...}` or `{"error": {"code", ...}}`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Result(Value),
+    Error(CoreError),
+}
+
+impl Response {
+    fn from_result(result: anyhow::Result<Value>) -> Self {
+        match result {
+            Ok(value) => Self::Result(value),
+            Err(err) => {
+                let mut core = CoreError::new(error_code(&err), format!("{err:#}"));
+                core.hint = error_hint(&err);
+                Self::Error(core)
+            }
+        }
+    }
+}
+
+/// `indexd.sock` in an `indexd` directory next to the state file, or for ephemeral state in
+/// the user's runtime directory, falling back to a directory of the user's own in the temp
+/// directory. [`private_dir`] makes sure no one else can enter it.
+pub(crate) fn socket_path() -> PathBuf {
+    let dir = match crate::ephemeral() {
+        true => match dirs::runtime_dir() {
+            Some(runtime) => runtime.join("terminaut"),
+            None => std::env::temp_dir().join(format!("terminaut-{}", user_id())),
+        },
+        false => Store::default_store_path().with_file_name("indexd"),
+    };
+    dir.join("indexd.sock")
+}
+
+#[cfg(unix)]
+fn user_id() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn user_id() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
+
+/// Creates `dir` readable only by the user, or checks that an existing one is the user's own
+/// and makes it so; a directory someone else owns could hand the daemon's clients a
+/// planted socket.
+#[cfg(unix)]
+fn private_dir(dir: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .map_err(|err| anyhow::anyhow!("could not create {}: {err}", dir.display()))?;
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != user_id() {
+        anyhow::bail!(CoreError::conflict(format!(
+            "{} is not a directory of your own",
+            dir.display()
+        ))
+        .with_hint("remove it; the daemon creates it again on its next start"));
+    }
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+fn watched_roots() -> anyhow::Result<Vec<PathBuf>> {
+    let mut roots: Vec<PathBuf> = config::config()
+        .projects
+        .roots
+        .iter()
+        .filter_map(|root| crate::normalize_path(root).ok())
+        .filter(|root| root.is_dir())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    if roots.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("no project roots to watch")
+            .with_hint("add folders to projects.roots, e.g. with `term-core-cli init`"));
+    }
+    Ok(roots)
+}
+
+/// The innermost root `path` lies in, unless it is inside a `.git` directory, whose churn
+/// says nothing about which projects exist.
+fn root_of<'a>(path: &Path, roots: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    let root = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())?;
+    let inside_git = path.strip_prefix(root).ok()?.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|c| c == Component::Normal(".git".as_ref()))
+    });
+    (!inside_git).then_some(root)
+}
+
+/// Creations, removals, and renames can add or drop projects; content changes can't.
+fn changes_projects(kind: &FsEventKind) -> bool {
+    matches!(
+        kind,
+        FsEventKind::Create(_) | FsEventKind::Remove(_) | FsEventKind::Modify(ModifyKind::Name(_))
+    )
+}
+
+fn rescan(roots: &[PathBuf], status: &Mutex<IndexdStatus>) {
+    // Pick up what other processes saved first, so the scan doesn't write back stale state.
+    let roots: Vec<String> = roots
+        .iter()
+        .map(|root| root.display().to_string())
+        .collect();
+    let result = STORE
+        .refresh()
+        .and_then(|_| crate::projects::refresh_projects(&roots));
+    let mut status = status.lock();
+    status.scans += 1;
+    status.last_scan_utc = Some(Utc::now().timestamp());
+    match result {
+        Ok(report) => {
+            status.projects = report.total;
+            status.last_error = None;
+        }
+        Err(err) => {
+            tracing::warn!("indexd rescan of {} failed: {err:#}", roots.join(", "));
+            status.last_error = Some(format!("{err:#}"));
+        }
+    }
+}
+
+/// `params` of a `search` with `options.record_history` off: the daemon answers for other
+/// programs, whose queries aren't the user's own.
+fn without_history(mut params: Value) -> Value {
+    if let Value::Object(params) = &mut params {
+        let options = params
+            .entry("options")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Value::Object(options) = options {
+            options.insert(String::from("record_history"), Value::Bool(false));
+        }
+    }
+    params
+}
+
+fn handle(request: Request, status: &Mutex<IndexdStatus>, stop: &AtomicBool) -> Response {
+    Response::from_result(match request.method.as_str() {
+        "status" => serde_json::to_value(&*status.lock()).map_err(Into::into),
+        "stop" => {
+            stop.store(true, Ordering::SeqCst);
+            Ok(Value::Null)
+        }
+        // Answer from what other processes saved meanwhile, not the state loaded at startup.
+        "search" => STORE
+            .refresh()
+            .and_then(|_| crate::invoke::invoke("search", without_history(request.params))),
+        method if QUERIES.contains(&method) => STORE
+            .refresh()
+            .and_then(|_| crate::invoke::invoke(method, request.params)),
+        method => Err(
+            CoreError::invalid_input(format!("indexd does not serve {method:?}"))
+                .with_hint("indexd answers status, stop, and read-only queries; call anything else in-process")
+                .into(),
+        ),
+    })
+}
+
+/// Watches `projects.roots` and rescans each root for projects shortly after something is
+/// created, removed, or renamed below it, so the registry (and the project text search
+/// matches) stays current without a cold walk. Serves [`IndexdStatus`], `stop`, and the
+/// [`QUERIES`] on [`socket_path`], which only the user can open, one JSON request per line, or
+/// binary frames after a `set_format` request (see [`serve`]). Runs until stopped.
+#[cfg(unix)]
+pub(crate) fn run_indexd() -> anyhow::Result<()> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let roots = watched_roots()?;
+    let socket = socket_path();
+    if let Some(dir) = socket.parent() {
+        private_dir(dir)?;
+    }
+    if UnixStream::connect(&socket).is_ok() {
+        anyhow::bail!(CoreError::conflict(format!(
+            "indexd is already running on {}",
+            socket.display()
+        ))
+        .with_hint("stop it with `term-core-cli indexd --stop`"));
+    }
+    // Nothing answered, so a socket left there is from a daemon that didn't shut down.
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!(CoreError::conflict(format!(
+                "{} exists and is not a socket",
+                socket.display()
+            )));
+        }
+        std::fs::remove_file(&socket)?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .map_err(|err| anyhow::anyhow!("could not listen on {}: {err}", socket.display()))?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+
+    let status = Arc::new(Mutex::new(IndexdStatus {
+        pid: std::process::id(),
+        socket: socket.display().to_string(),
+        started_utc: Utc::now().timestamp(),
+        roots: roots
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect(),
+        pending: Vec::new(),
+        events: 0,
+        scans: 0,
+        last_scan_utc: None,
+        projects: 0,
+        last_error: None,
+    }));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let (sender, events) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for root in &roots {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+    // Searches in this process may reuse walks of the roots; the loop below invalidates them.
+    let _watching = crate::candidates::watching(&roots);
+    {
+        let (status, stop) = (Arc::clone(&status), Arc::clone(&stop));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (status, stop) = (Arc::clone(&status), Arc::clone(&stop));
+                std::thread::spawn(move || serve(stream, &status, &stop));
+            }
+        });
+    }
+    tracing::info!(
+        "indexd watching {} on {}",
+        status.lock().roots.join(", "),
+        socket.display()
+    );
+
+    rescan(&roots, &status);
+    let mut pending: BTreeMap<PathBuf, Instant> = BTreeMap::new();
+    while !stop.load(Ordering::SeqCst) {
+        match events.recv_timeout(TICK) {
+            Ok(Ok(event)) => {
+                status.lock().events += 1;
+                if changes_projects(&event.kind) {
+                    for path in &event.paths {
+                        crate::candidates::invalidate(path);
+                        if let Some(root) = root_of(path, &roots) {
+                            pending.insert(root.clone(), Instant::now());
+                        }
+                    }
+                }
+            }
+            Ok(Err(err)) => tracing::warn!("indexd watch error: {err}"),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE)
+            .map(|(root, _)| root.clone())
+            .collect();
+        for root in &settled {
+            pending.remove(root);
+        }
+        status.lock().pending = pending
+            .keys()
+            .map(|root| root.display().to_string())
+            .collect();
+        if !settled.is_empty() {
+            rescan(&settled, &status);
+        }
+    }
+    drop(watcher);
+    let _ = std::fs::remove_file(&socket);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn run_indexd() -> anyhow::Result<()> {
+    watched_roots()?;
+    anyhow::bail!(CoreError::invalid_input(
+        "indexd needs Unix domain sockets, which this platform lacks"
+    ))
+}
+
+/// Reads the next message: a line of JSON, or once the connection has switched with
+/// `set_format`, a 4-byte big-endian length followed by that many bytes. `None` once the
+/// other side hangs up.
+#[cfg(unix)]
+fn read_message(reader: &mut impl BufRead, format: WireFormat) -> io::Result<Option<Vec<u8>>> {
+    if format == WireFormat::Json {
+        loop {
+            let mut line = Vec::new();
+            let limit = MAX_LINE_BYTES as u64 + 1;
+            if io::Read::take(&mut *reader, limit).read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            if line.len() > MAX_LINE_BYTES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("request line longer than {MAX_LINE_BYTES} bytes"),
+                ));
+            }
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(line));
+            }
+        }
+    }
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes is too large"),
+        ));
+    }
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
+
+#[cfg(unix)]
+fn write_message(writer: &mut impl Write, message: &[u8], format: WireFormat) -> io::Result<()> {
+    if format == WireFormat::Json {
+        writer.write_all(message)?;
+        return writer.write_all(b"\n");
+    }
+    let len = u32::try_from(message.len())
+        .ok()
+        .filter(|len| *len as usize <= MAX_FRAME_BYTES)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "message is too large"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(message)
+}
+
+/// The format a `set_format` request asks for, if this build has it.
+fn requested_format(params: &Value) -> anyhow::Result<WireFormat> {
+    let Some(format) = params.get("format").and_then(Value::as_str) else {
+        anyhow::bail!(CoreError::invalid_input("set_format needs a format"));
+    };
+    let format: WireFormat = format.parse()?;
+    if !WireFormat::available().contains(&format) {
+        return Err(format.missing());
+    }
+    Ok(format)
+}
+
+/// Answers each request on `stream` until the client hangs up. A connection starts out as
+/// JSON lines; `set_format` answers in the current format, then switches both directions.
+#[cfg(unix)]
+fn serve(stream: std::os::unix::net::UnixStream, status: &Mutex<IndexdStatus>, stop: &AtomicBool) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut format = WireFormat::Json;
+    while let Ok(Some(message)) = read_message(&mut reader, format) {
+        let mut switch_to = None;
+        let response = match wire::decode::<Request>(&message, format) {
+            Ok(request) if request.method == "set_format" => {
+                Response::from_result(requested_format(&request.params).map(|requested| {
+                    switch_to = Some(requested);
+                    serde_json::json!({ "format": requested })
+                }))
+            }
+            Ok(request) => handle(request, status, stop),
+            Err(err) => Response::Error(CoreError::invalid_input(format!(
+                "malformed request: {err:#}"
+            ))),
+        };
+        let Ok(bytes) = wire::to_bytes(&response, format) else {
+            return;
+        };
+        if write_message(&mut writer, &bytes, format).is_err() {
+            return;
+        }
+        format = switch_to.unwrap_or(format);
+    }
+}
+
+/// Writes one request and reads its response.
+#[cfg(unix)]
+fn call(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    method: &str,
+    params: Value,
+    format: WireFormat,
+) -> anyhow::Result<Value> {
+    let request = Request {
+        method: method.to_string(),
+        params,
+    };
+    write_message(writer, &wire::to_bytes(&request, format)?, format)?;
+    let Some(reply) = read_message(reader, format)? else {
+        anyhow::bail!("indexd closed the connection");
+    };
+    match wire::decode(&reply, format)? {
+        Response::Result(value) => Ok(value),
+        Response::Error(err) => Err(err.into()),
+    }
+}
+
+/// Sends one request to the running daemon, encoded as `format`, and returns its result.
+#[cfg(unix)]
+pub(crate) fn request_as(method: &str, params: Value, format: WireFormat) -> anyhow::Result<Value> {
+    use std::os::unix::net::UnixStream;
+
+    let socket = socket_path();
+    let mut writer = UnixStream::connect(&socket).map_err(|_| {
+        CoreError::not_found("indexd is not running")
+            .with_hint("start it with `term-core-cli indexd`")
+    })?;
+    writer.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(writer.try_clone()?);
+    if format != WireFormat::Json {
+        let params = serde_json::json!({ "format": format });
+        call(
+            &mut reader,
+            &mut writer,
+            "set_format",
+            params,
+            WireFormat::Json,
+        )?;
+    }
+    call(&mut reader, &mut writer, method, params, format)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn request_as(
+    _method: &str,
+    _params: Value,
+    _format: WireFormat,
+) -> anyhow::Result<Value> {
+    anyhow::bail!(CoreError::not_found("indexd is not running")
+        .with_hint("indexd needs Unix domain sockets, which this platform lacks"))
+}
+
+pub(crate) fn indexd_status() -> anyhow::Result<IndexdStatus> {
+    let status = request_as("status", Value::Null, WireFormat::Json)?;
+    Ok(serde_json::from_value(status)?)
+}
+
+pub(crate) fn stop_indexd() -> anyhow::Result<()> {
+    request_as("stop", Value::Null, WireFormat::Json).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_map_to_their_innermost_root() {
+        let roots = [PathBuf::from("/w"), PathBuf::from("/w/clients")];
+        assert_eq!(
+            root_of(Path::new("/w/api/Cargo.toml"), &roots),
+            Some(&roots[0])
+        );
+        assert_eq!(
+            root_of(Path::new("/w/clients/acme"), &roots),
+            Some(&roots[1])
+        );
+        assert_eq!(root_of(Path::new("/w/api/.git/index.lock"), &roots), None);
+        assert_eq!(root_of(Path::new("/w/api/.git"), &roots), Some(&roots[0]));
+        assert_eq!(root_of(Path::new("/elsewhere"), &roots), None);
+
+        let response = Response::from_result(Err(CoreError::not_found("gone")
+            .with_hint("look elsewhere")
+            .into()));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["error"]["code"], "not_found");
+        assert_eq!(json["error"]["hint"], "look elsewhere");
+
+        let params = without_history(serde_json::json!({ "path": "/w" }));
+        assert_eq!(params["options"]["record_history"], false);
+        for method in QUERIES {
+            assert!(crate::invoke::METHODS.contains(method), "{method}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn long_lines_are_refused() {
+        let mut long = vec![b'x'; MAX_LINE_BYTES + 10];
+        long.push(b'\n');
+        let mut reader = BufReader::new(&long[..]);
+        assert!(read_message(&mut reader, WireFormat::Json).is_err());
+        let mut reader = BufReader::new(&b"{}\n"[..]);
+        assert_eq!(
+            read_message(&mut reader, WireFormat::Json).unwrap(),
+            Some(b"{}\n".to_vec())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn connections_switch_to_binary_frames() {
+        use std::os::unix::net::UnixStream;
+
+        let (client, server) = UnixStream::pair().unwrap();
+        let status = Mutex::new(IndexdStatus {
+            pid: 1,
+            socket: String::from("test"),
+            started_utc: 0,
+            roots: Vec::new(),
+            pending: Vec::new(),
+            events: 0,
+            scans: 7,
+            last_scan_utc: None,
+            projects: 0,
+            last_error: None,
+        });
+        let stop = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve(server, &status, &stop));
+            let mut writer = client.try_clone().unwrap();
+            let mut reader = BufReader::new(client);
+            let mut call = |method: &str, params: Value, format: WireFormat| {
+                call(&mut reader, &mut writer, method, params, format)
+            };
+            let format = *WireFormat::available().last().unwrap();
+            let switched = call(
+                "set_format",
+                serde_json::json!({ "format": format }),
+                WireFormat::Json,
+            );
+            assert_eq!(switched.unwrap()["format"], serde_json::json!(format));
+            assert_eq!(call("status", Value::Null, format).unwrap()["scans"], 7);
+            assert_eq!(
+                call("version", Value::Null, format).unwrap(),
+                env!("CARGO_PKG_VERSION")
+            );
+            assert!(call("nope", Value::Null, format).is_err());
+            let launch = call("launch", serde_json::json!({ "path": "/" }), format);
+            assert!(launch.unwrap_err().to_string().contains("does not serve"));
+            call("stop", Value::Null, format).unwrap();
+        });
+        assert!(stop.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,624 @@
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "fs")]
+use crate::GrepOptions;
+use crate::{
+    api, error_code, error_hint, wire, CoreError, ExpectedGeneration, LaunchRequest, ListOptions,
+    MatchOptions, RankWeights, SearchOptions, TaskOptions, Transport, WireFormat,
+};
+
+/// Every method [`invoke`] understands; each is named after the `api` function it calls.
+pub(crate) const METHODS: &[&str] = &[
+    "version",
+    "normalize_path",
+    "display_path",
+    "translate_path",
+    "wsl_distros",
+    "editors",
+    "open_in_editor",
+    "preferred_editor",
+    "set_preferred_editor",
+    "default_terminal",
+    "installed_terminals",
+    "default_shell",
+    "terminal_settings",
+    "list_directory",
+    "list_directory_with_git",
+    "list_directory_with",
+    "list_archive",
+    "list_roots",
+    "ancestors",
+    "suggest_next",
+    "list_favorites",
+    "list_favorites_with_status",
+    "add_favorite",
+    "remove_favorite",
+    "list_recents",
+    "list_recents_with_status",
+    "recents_grouped",
+    "touch_recent",
+    "set_read_only",
+    "is_read_only",
+    "is_ephemeral",
+    "store_generation",
+    "init_store",
+    "store_status",
+    "doctor",
+    "stats",
+    "clear_stats",
+    "fuzzy_filter",
+    "rank_paths",
+    "detect_projects",
+    "project_info",
+    "run_task",
+    "project_status",
+    "project_description",
+    "project_remote",
+    "list_projects",
+    "refresh_projects",
+    #[cfg(feature = "fs")]
+    "find_duplicate_projects",
+    #[cfg(feature = "watch")]
+    "indexd_status",
+    #[cfg(feature = "watch")]
+    "stop_indexd",
+    "get_config",
+    "get_config_value",
+    "set_config",
+    "config_path",
+    "list_tags",
+    "list_tags_with_status",
+    "set_tag",
+    "remove_tag",
+    "tags_for",
+    "auto_tag",
+    "paths_with_tag_prefix",
+    "tag_tree",
+    "list_tag_colors",
+    "set_note",
+    "get_note",
+    "list_notes",
+    "search_notes",
+    "add_action",
+    "remove_action",
+    "list_actions",
+    "actions_for",
+    "run_action",
+    "import_finder_tags",
+    "import_bookmarks",
+    "import_projects",
+    "export_finder_tags",
+    "prune_state",
+    "compact_state",
+    "preview_prune",
+    "list_profiles",
+    "save_profile",
+    "set_profile_args",
+    "set_profile_hooks",
+    "launch_profile",
+    "set_profile_remote",
+    "set_profile_kube",
+    "list_kube_contexts",
+    "launch_logs",
+    "set_autostart",
+    "parse_command",
+    "delete_profile",
+    "undo_last",
+    "undo_history",
+    "merge_state",
+    "json_schemas",
+    "capabilities",
+    #[cfg(feature = "fs")]
+    "run_script",
+    "launch",
+    "open_terminal",
+    "copy_to_clipboard",
+    "set_secret",
+    "delete_secret",
+    "setting_get",
+    "setting_set",
+    "setting_delete",
+    "settings",
+    "parse_url",
+    "handle_url",
+    "list_sessions",
+    "save_session",
+    "delete_session",
+    "session_launches",
+    "restore_session",
+    "list_saved_searches",
+    "save_saved_search",
+    "delete_saved_search",
+    "run_saved_search",
+    #[cfg(feature = "fs")]
+    "create_dir",
+    #[cfg(feature = "fs")]
+    "rename",
+    #[cfg(feature = "fs")]
+    "move_path",
+    #[cfg(feature = "fs")]
+    "trash",
+    "search",
+    #[cfg(feature = "fs")]
+    "invalidate_search_cache",
+    #[cfg(feature = "fs")]
+    "start_search_session",
+    #[cfg(feature = "fs")]
+    "refine_search_session",
+    #[cfg(feature = "fs")]
+    "end_search_session",
+    #[cfg(feature = "fs")]
+    "grep",
+    "search_history",
+    "search_suggestions",
+    "clear_search_history",
+    "preview",
+    #[cfg(feature = "fs")]
+    "dir_size",
+    #[cfg(feature = "fs")]
+    "dir_size_blocking",
+    #[cfg(feature = "fs")]
+    "cancel_dir_size",
+];
+
+/// Named parameters of one call.
+struct Params(Map<String, Value>);
+
+impl Params {
+    fn optional<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<Option<T>> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => T::deserialize(value)
+                .map(Some)
+                .with_context(|| format!("invalid `{key}` parameter")),
+        }
+    }
+
+    fn required<T: DeserializeOwned>(&self, key: &str) -> anyhow::Result<T> {
+        self.optional(key)?
+            .ok_or_else(|| CoreError::invalid_input(format!("missing `{key}` parameter")).into())
+    }
+
+    fn string(&self, key: &str) -> anyhow::Result<String> {
+        self.required(key)
+    }
+
+    fn or<T: DeserializeOwned>(&self, key: &str, default: T) -> anyhow::Result<T> {
+        Ok(self.optional(key)?.unwrap_or(default))
+    }
+
+    fn whole<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        T::deserialize(Value::Object(self.0.clone())).context("invalid parameters")
+    }
+}
+
+fn json<T: Serialize>(value: T) -> anyhow::Result<Value> {
+    serde_json::to_value(value).context("serialize result")
+}
+
+/// Calls the `api` function named `method` with named `params` (an object, or null when it
+/// takes none). Functions without a result return null. Any call may pass
+/// `expected_generation`; it then fails with a conflict instead of running when the store
+/// has changed since, or instead of saving when the store changes while it runs; see
+/// [`api::check_generation`].
+pub(crate) fn invoke(method: &str, params: Value) -> anyhow::Result<Value> {
+    let mut params = match params {
+        Value::Null => Params(Map::new()),
+        Value::Object(map) => Params(map),
+        _ => anyhow::bail!(CoreError::invalid_input("params must be a JSON object")),
+    };
+    let expected = params.optional::<u64>("expected_generation")?;
+    params.0.remove("expected_generation");
+    // Checked up front so nothing runs against a stale view, and again by every save the
+    // call makes, under the store lock.
+    let _expected = match expected {
+        Some(expected) => {
+            api::check_generation(expected)?;
+            Some(ExpectedGeneration::new(expected))
+        }
+        None => None,
+    };
+    let p = &params;
+    match method {
+        "version" => json(env!("CARGO_PKG_VERSION")),
+        "normalize_path" => match p.optional("symlinks")? {
+            Some(symlinks) => json(api::normalize_path_with(&p.string("path")?, symlinks)?),
+            None => json(api::normalize_path(&p.string("path")?)?),
+        },
+        "display_path" => json(api::display_path(
+            &p.string("path")?,
+            p.optional::<String>("relative_to")?.as_deref(),
+        )?),
+        "translate_path" => json(api::translate_path(
+            &p.string("path")?,
+            p.required("target")?,
+            p.optional::<String>("distro")?.as_deref(),
+        )?),
+        "wsl_distros" => json(api::wsl_distros()),
+        "editors" => json(api::editors()),
+        "open_in_editor" => json(api::open_in_editor(
+            &p.string("path")?,
+            p.optional::<String>("editor")?.as_deref(),
+        )?),
+        "preferred_editor" => json(api::preferred_editor(&p.string("path")?)?),
+        "set_preferred_editor" => json(api::set_preferred_editor(
+            &p.string("path")?,
+            p.optional::<String>("editor")?.as_deref(),
+        )?),
+        "default_terminal" => json(api::default_terminal()),
+        "installed_terminals" => json(api::installed_terminals()),
+        "default_shell" => json(api::default_shell()),
+        "terminal_settings" => json(api::terminal_settings(
+            p.optional::<String>("terminal")?.as_deref(),
+        )?),
+        "list_directory" => json(api::list_directory(&p.string("path")?)?),
+        "list_directory_with_git" => json(api::list_directory_with_git(&p.string("path")?)?),
+        "list_directory_with" => json(api::list_directory_with(
+            &p.string("path")?,
+            &p.or("options", ListOptions::default())?,
+        )?),
+        "list_archive" => json(api::list_archive(&p.string("path")?)?),
+        "list_roots" => json(api::list_roots()?),
+        "ancestors" => json(api::ancestors(&p.string("path")?)?),
+        "suggest_next" => json(api::suggest_next(&p.string("path")?, p.or("limit", 10)?)?),
+        "list_favorites" => json(api::list_favorites()),
+        "list_favorites_with_status" => json(api::list_favorites_with_status()),
+        "add_favorite" => json(api::add_favorite(&p.string("path")?)?),
+        "remove_favorite" => json(api::remove_favorite(&p.string("path")?)?),
+        "list_recents" => match p.optional::<String>("source")? {
+            Some(source) => json(api::list_recents_from(&source)),
+            None => match p.optional("sort")? {
+                Some(sort) => json(api::list_recents_sorted(sort)),
+                None => json(api::list_recents()),
+            },
+        },
+        "list_recents_with_status" => json(api::list_recents_with_status()),
+        "recents_grouped" => json(api::recents_grouped(p.required("by")?)),
+        "touch_recent" => match p.optional::<String>("source")? {
+            Some(source) => json(api::touch_recent_from(&p.string("path")?, &source)?),
+            None => json(api::touch_recent(&p.string("path")?)?),
+        },
+        "set_read_only" => {
+            api::set_read_only(p.required("enabled")?);
+            Ok(Value::Null)
+        }
+        "is_read_only" => json(api::is_read_only()),
+        "is_ephemeral" => json(api::is_ephemeral()),
+        "store_generation" => json(api::store_generation()),
+        "init_store" => json(api::init_store()?),
+        "store_status" => json(api::store_status()),
+        "doctor" => json(api::doctor()),
+        "stats" => json(api::stats(p.or("weeks", 4)?)),
+        "clear_stats" => json(api::clear_stats()?),
+        "fuzzy_filter" => {
+            let candidates: Vec<String> = p.required("candidates")?;
+            let options = p.or("options", MatchOptions::default())?;
+            json(api::fuzzy_filter(
+                &p.or("query", String::new())?,
+                &candidates,
+                &options,
+            ))
+        }
+        "rank_paths" => {
+            let paths: Vec<String> = p.required("paths")?;
+            let weights = p.or("weights", RankWeights::default())?;
+            json(api::rank_paths(&paths, &weights))
+        }
+        "detect_projects" => json(api::detect_projects(&p.string("path")?)?),
+        "project_info" => json(api::project_info(&p.string("path")?)?),
+        "project_status" => json(api::project_status(&p.string("path")?)?),
+        // Output isn't streamed here; front-ends that show it call `api::run_task` directly.
+        "run_task" => json(api::run_task(
+            &p.string("path")?,
+            &p.string("task")?,
+            &p.or("options", TaskOptions::default())?,
+            None,
+        )?),
+        "project_description" => json(api::project_description(&p.string("path")?)?),
+        "project_remote" => json(api::project_remote(&p.string("path")?)?),
+        "list_projects" => json(api::list_projects()),
+        "refresh_projects" => json(api::refresh_projects(&p.or("roots", Vec::new())?)?),
+        #[cfg(feature = "fs")]
+        "find_duplicate_projects" => json(api::find_duplicate_projects(&p.string("root")?)?),
+        #[cfg(feature = "watch")]
+        "indexd_status" => json(api::indexd_status()?),
+        #[cfg(feature = "watch")]
+        "stop_indexd" => json(api::stop_indexd()?),
+        "get_config" => json(api::get_config()?),
+        "get_config_value" => json(api::get_config_value(&p.string("key")?)?),
+        "set_config" => json(api::set_config(&p.string("key")?, &p.string("value")?)?),
+        "config_path" => json(api::config_path()),
+        "list_tags" => json(api::list_tags()),
+        "list_tags_with_status" => json(api::list_tags_with_status()),
+        "set_tag" => {
+            let color: Option<String> = p.optional("color")?;
+            json(api::set_tag(
+                &p.string("path")?,
+                &p.string("tag")?,
+                color.as_deref(),
+            )?)
+        }
+        "remove_tag" => json(api::remove_tag(&p.string("path")?, &p.string("tag")?)?),
+        "tags_for" => json(api::tags_for(&p.string("path")?)?),
+        "paths_with_tag_prefix" => json(api::paths_with_tag_prefix(&p.string("prefix")?)),
+        "tag_tree" => json(api::tag_tree()),
+        "list_tag_colors" => json(api::list_tag_colors()),
+        "set_note" => json(api::set_note(&p.string("path")?, &p.string("markdown")?)?),
+        "get_note" => json(api::get_note(&p.string("path")?)?),
+        "list_notes" => json(api::list_notes()),
+        "search_notes" => json(api::search_notes(&p.string("query")?)),
+        "add_action" => {
+            let (path, tag): (Option<String>, Option<String>) =
+                (p.optional("path")?, p.optional("tag")?);
+            json(api::add_action(
+                &p.string("label")?,
+                &p.string("command")?,
+                path.as_deref(),
+                tag.as_deref(),
+                p.or("background", false)?,
+            )?)
+        }
+        "remove_action" => json(api::remove_action(p.required("id")?)?),
+        "list_actions" => json(api::list_actions()),
+        "actions_for" => json(api::actions_for(&p.string("path")?)?),
+        "run_action" => json(api::run_action(
+            &p.string("path")?,
+            p.required("action_id")?,
+        )?),
+        "auto_tag" => json(api::auto_tag(
+            &p.or("rules", Vec::new())?,
+            p.or("dry_run", false)?,
+        )?),
+        "import_bookmarks" => json(api::import_bookmarks(
+            p.optional("source")?,
+            p.or("dry_run", false)?,
+        )?),
+        "import_projects" => json(api::import_projects(
+            p.required("source")?,
+            p.or("as_favorites", false)?,
+            p.or("dry_run", false)?,
+        )?),
+        "import_finder_tags" => json(api::import_finder_tags(
+            p.optional::<String>("path")?.as_deref(),
+            p.or("dry_run", false)?,
+        )?),
+        "export_finder_tags" => json(api::export_finder_tags(
+            p.optional::<String>("path")?.as_deref(),
+            p.or("dry_run", false)?,
+        )?),
+        "prune_state" => json(api::prune_state()?),
+        "compact_state" => json(api::compact_state(
+            p.or("drop_missing", false)?,
+            p.or("dry_run", false)?,
+        )?),
+        "preview_prune" => json(api::preview_prune()),
+        "list_profiles" => json(api::list_profiles()),
+        "save_profile" => json(api::save_profile(
+            p.optional("id")?,
+            &p.string("name")?,
+            p.optional("command")?,
+            p.optional("working_dir")?,
+            p.optional("terminal")?,
+            p.optional("windows")?,
+            p.optional("wsl_distro")?,
+        )?),
+        "set_profile_args" => json(api::set_profile_args(
+            p.required("id")?,
+            p.required("args")?,
+            p.or("use_shell", false)?,
+        )?),
+        "set_profile_hooks" => json(api::set_profile_hooks(
+            p.required("id")?,
+            p.optional("pre_command")?,
+            // Also accepted under its old name.
+            match p.optional("post_launch_command")? {
+                Some(command) => Some(command),
+                None => p.optional("post_command")?,
+            },
+        )?),
+        "launch_profile" => json(api::launch_profile(
+            p.required("id")?,
+            p.optional::<String>("path")?.as_deref(),
+            p.or("background", false)?,
+        )?),
+        "set_profile_remote" => json(api::set_profile_remote(
+            p.required("id")?,
+            p.optional("host")?,
+            p.optional("dir")?,
+            p.or("transport", Transport::Ssh)?,
+        )?),
+        "set_profile_kube" => json(api::set_profile_kube(
+            p.required("id")?,
+            p.required("target")?,
+        )?),
+        "list_kube_contexts" => json(api::list_kube_contexts()?),
+        "launch_logs" => json(api::launch_logs(p.required("profile_id")?)),
+        "set_autostart" => json(api::set_autostart(
+            p.required("profile_id")?,
+            p.required("enabled")?,
+        )?),
+        "parse_command" => json(api::parse_command(&p.string("line")?)?),
+        "delete_profile" => json(api::delete_profile(p.required("id")?)?),
+        "undo_last" => json(api::undo_last()?),
+        "undo_history" => json(api::undo_history()),
+        "merge_state" => json(api::merge_state(&p.string("path")?)?),
+        "json_schemas" => json(api::json_schemas()),
+        "capabilities" => json(api::capabilities()),
+        #[cfg(feature = "fs")]
+        "run_script" => {
+            let args: Vec<String> = p.or("args", Vec::new())?;
+            api::run_script(&p.string("path")?, &args)
+        }
+        "launch" => json(api::launch(&p.whole::<LaunchRequest>()?)?),
+        "open_terminal" => json(api::open_terminal(
+            &p.string("path")?,
+            p.optional::<String>("terminal")?.as_deref(),
+        )?),
+        "copy_to_clipboard" => json(api::copy_to_clipboard(&p.string("text")?)?),
+        "set_secret" => json(api::set_secret(&p.string("name")?, &p.string("value")?)?),
+        "delete_secret" => json(api::delete_secret(&p.string("name")?)?),
+        "setting_get" => json(api::setting_get(
+            &p.string("namespace")?,
+            &p.string("key")?,
+        )?),
+        "setting_set" => json(api::setting_set(
+            &p.string("namespace")?,
+            &p.string("key")?,
+            &p.string("value")?,
+        )?),
+        "setting_delete" => json(api::setting_delete(
+            &p.string("namespace")?,
+            &p.string("key")?,
+        )?),
+        "settings" => json(api::settings(&p.string("namespace")?)?),
+        "parse_url" => json(api::parse_url(&p.string("url")?)?),
+        "handle_url" => json(api::handle_url(
+            &p.string("url")?,
+            p.or("confirmed", false)?,
+        )?),
+        "list_sessions" => json(api::list_sessions()),
+        "save_session" => json(api::save_session(
+            &p.string("name")?,
+            p.required("entries")?,
+        )?),
+        "delete_session" => json(api::delete_session(&p.string("name")?)?),
+        "session_launches" => json(api::session_launches(&p.string("name")?)?),
+        "restore_session" => json(api::restore_session(&p.string("name")?)?),
+        "list_saved_searches" => json(api::list_saved_searches()),
+        "save_saved_search" => json(api::save_saved_search(
+            &p.string("name")?,
+            &p.string("root")?,
+            &p.or("query", String::new())?,
+            p.or("limit", 20)?,
+            p.or("options", SearchOptions::default())?,
+        )?),
+        "delete_saved_search" => json(api::delete_saved_search(&p.string("name")?)?),
+        "run_saved_search" => json(api::run_saved_search(&p.string("name")?)?),
+        #[cfg(feature = "fs")]
+        "create_dir" => json(api::create_dir(&p.string("path")?)?),
+        #[cfg(feature = "fs")]
+        "rename" => json(api::rename(&p.string("path")?, &p.string("new_name")?)?),
+        #[cfg(feature = "fs")]
+        "move_path" => json(api::move_path(
+            &p.string("path")?,
+            &p.string("destination")?,
+        )?),
+        #[cfg(feature = "fs")]
+        "trash" => json(api::trash(&p.string("path")?)?),
+        "search" => json(api::search(
+            &p.string("path")?,
+            &p.or("query", String::new())?,
+            p.or("limit", 20)?,
+            &p.or("options", SearchOptions::default())?,
+        )?),
+        #[cfg(feature = "fs")]
+        "invalidate_search_cache" => json(api::invalidate_search_cache(
+            p.optional::<String>("path")?.as_deref(),
+        )?),
+        #[cfg(feature = "fs")]
+        "start_search_session" => json(api::start_search_session(
+            &p.string("path")?,
+            &p.or("options", SearchOptions::default())?,
+        )?),
+        #[cfg(feature = "fs")]
+        "refine_search_session" => json(api::refine_search_session(
+            p.required("id")?,
+            &p.or("query", String::new())?,
+            p.or("limit", 20)?,
+        )?),
+        #[cfg(feature = "fs")]
+        "end_search_session" => json(api::end_search_session(p.required("id")?)),
+        #[cfg(feature = "fs")]
+        "grep" => json(api::grep(
+            &p.string("path")?,
+            &p.string("pattern")?,
+            &p.or("options", GrepOptions::default())?,
+        )?),
+        "search_history" => {
+            let root: Option<String> = p.optional("root")?;
+            json(api::search_history(root.as_deref(), p.or("limit", 50)?)?)
+        }
+        "search_suggestions" => {
+            let root: Option<String> = p.optional("root")?;
+            json(api::search_suggestions(
+                root.as_deref(),
+                &p.or("prefix", String::new())?,
+                p.or("limit", 10)?,
+            )?)
+        }
+        "clear_search_history" => json(api::clear_search_history()?),
+        "preview" => json(api::preview(&p.string("path")?)?),
+        #[cfg(feature = "fs")]
+        "dir_size" => json(api::dir_size(&p.string("path")?)?),
+        #[cfg(feature = "fs")]
+        "dir_size_blocking" => json(api::dir_size_blocking(&p.string("path")?)?),
+        #[cfg(feature = "fs")]
+        "cancel_dir_size" => json(api::cancel_dir_size(&p.string("path")?)?),
+        other => Err(CoreError::not_found(format!("unknown method {other:?}"))
+            .with_hint(format!("available methods: {}", METHODS.join(", ")))
+            .into()),
+    }
+}
+
+/// What `term_core_invoke` returns for `result`: `{"result": ...}` or
+/// `{"error": {"code", "message", "hint"}}`, each with the store `generation` after the call.
+pub(crate) fn envelope(result: anyhow::Result<Value>) -> Value {
+    let generation = api::store_generation();
+    match result {
+        Ok(value) => serde_json::json!({ "result": value, "generation": generation }),
+        Err(err) => {
+            let error = CoreError {
+                code: error_code(&err),
+                message: format!("{err:#}"),
+                hint: error_hint(&err),
+            };
+            serde_json::json!({ "error": error, "generation": generation })
+        }
+    }
+}
+
+/// [`invoke`] with `params` (empty for none) and the [`envelope`] encoded as `format`. Fails
+/// only when this build lacks `format`; bad params come back as an error envelope.
+pub(crate) fn invoke_encoded(
+    method: &str,
+    params: &[u8],
+    format: WireFormat,
+) -> anyhow::Result<Box<[u8]>> {
+    if !WireFormat::available().contains(&format) {
+        return Err(format.missing());
+    }
+    let result = match params.is_empty() {
+        true => Ok(Value::Null),
+        false => wire::decode(params, format).map_err(|err| {
+            CoreError::invalid_input(format!("could not decode params: {err:#}")).into()
+        }),
+    }
+    .and_then(|params| invoke(method, params));
+    wire::to_bytes(&envelope(result), format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+
+    #[test]
+    fn dispatches_and_classifies_errors() {
+        assert_eq!(
+            invoke("version", Value::Null).unwrap(),
+            env!("CARGO_PKG_VERSION")
+        );
+        let missing = invoke("normalize_path", serde_json::json!({})).unwrap_err();
+        assert_eq!(error_code(&missing), ErrorCode::InvalidInput);
+        let unknown = invoke("nope", Value::Null).unwrap_err();
+        assert_eq!(error_code(&unknown), ErrorCode::NotFound);
+        let wrong_type = invoke("search", serde_json::json!({"path": "/", "limit": "x"}));
+        assert_eq!(
+            error_code(&wrong_type.unwrap_err()),
+            ErrorCode::InvalidInput
+        );
+    }
+}
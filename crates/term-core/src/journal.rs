@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, EventKind};
+use crate::hooks::{self, HookEvent};
+use crate::maintenance::{PruneAction, PruneChange, PruneReport, StoreSection};
+use crate::{CoreError, LaunchProfile, PersistedState, RecentEntry, TaggedPath, STORE};
+
+/// Undoable operations kept in the state file; older ones are forgotten.
+const JOURNAL_CAP: usize = 20;
+
+/// What a destructive operation removed, with enough detail to put it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum JournalOperation {
+    RemoveFavorite {
+        path: String,
+        index: usize,
+        /// Import source of the favorite, if it had one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+    RemoveTag {
+        tags: Vec<TaggedPath>,
+    },
+    DeleteProfile {
+        profile: Box<LaunchProfile>,
+        index: usize,
+    },
+    Prune {
+        favorites: Vec<String>,
+        /// Where each of `favorites` stood in the list; undo puts them back there.
+        favorite_indices: Vec<usize>,
+        /// Import sources of the dropped favorites that had one.
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        favorite_sources: BTreeMap<String, String>,
+        recents: Vec<RecentEntry>,
+        tags: Vec<TaggedPath>,
+        /// Entries moved to a new path; undo moves them back.
+        rewritten: Vec<PruneChange>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at_utc: i64,
+    #[serde(flatten)]
+    pub operation: JournalOperation,
+}
+
+/// Adds `operation` to the journal in `state`; the caller persists.
+pub(crate) fn record(state: &mut PersistedState, operation: JournalOperation) {
+    state.journal.push(JournalEntry {
+        at_utc: Utc::now().timestamp(),
+        operation,
+    });
+    let excess = state.journal.len().saturating_sub(JOURNAL_CAP);
+    state.journal.drain(..excess);
+}
+
+/// The journal entry for a prune that turned `before` into its current state.
+pub(crate) fn prune_operation(before: &PersistedState, report: &PruneReport) -> JournalOperation {
+    let mut favorites = Vec::new();
+    let mut favorite_indices = Vec::new();
+    let mut favorite_sources = BTreeMap::new();
+    let mut recents = Vec::new();
+    let mut tags = Vec::new();
+    let mut rewritten = Vec::new();
+    for change in &report.changes {
+        let merged = match &change.action {
+            // A rewrite onto a path the section already had was merged away by dedupe, so
+            // the original entry comes back as it was instead.
+            PruneAction::Rewrite { to } => match change.section {
+                StoreSection::Favorite => before.favorites.contains(to),
+                StoreSection::Recent => before.recents.iter().any(|entry| &entry.path == to),
+                StoreSection::Tag => before
+                    .tags
+                    .iter()
+                    .any(|tag| &tag.path == to && Some(&tag.tag) == change.tag.as_ref()),
+            },
+            PruneAction::Drop => true,
+        };
+        if !merged {
+            rewritten.push(change.clone());
+            continue;
+        }
+        match change.section {
+            StoreSection::Favorite => {
+                let Some(index) = before.favorites.iter().position(|p| *p == change.path) else {
+                    continue;
+                };
+                favorites.push(change.path.clone());
+                favorite_indices.push(index);
+                if let Some(source) = before.favorite_sources.get(&change.path) {
+                    favorite_sources.insert(change.path.clone(), source.clone());
+                }
+            }
+            StoreSection::Recent => recents.extend(
+                before
+                    .recents
+                    .iter()
+                    .find(|entry| entry.path == change.path)
+                    .cloned(),
+            ),
+            StoreSection::Tag => tags.extend(
+                before
+                    .tags
+                    .iter()
+                    .find(|tag| tag.path == change.path && Some(&tag.tag) == change.tag.as_ref())
+                    .cloned(),
+            ),
+        }
+    }
+    JournalOperation::Prune {
+        favorites,
+        favorite_indices,
+        favorite_sources,
+        recents,
+        tags,
+        rewritten,
+    }
+}
+
+pub(crate) fn journal() -> Vec<JournalEntry> {
+    let mut entries = STORE.inner.lock().journal.clone();
+    entries.reverse();
+    entries
+}
+
+fn has_tag(state: &PersistedState, path: &str, tag: &str) -> bool {
+    state
+        .tags
+        .iter()
+        .any(|entry| entry.path == path && entry.tag.eq_ignore_ascii_case(tag))
+}
+
+/// Puts back what the most recent journaled operation removed. Entries added since then
+/// are kept; restored entries that exist again are skipped.
+pub(crate) fn undo_last() -> anyhow::Result<JournalEntry> {
+    let mut store = STORE.inner.lock();
+    let Some(entry) = store.journal.pop() else {
+        anyhow::bail!(CoreError::not_found("nothing to undo"));
+    };
+    let (restored_favorites, restored_tags) = restore(&mut store, &entry.operation);
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish_reset(&EventKind::ALL);
+    for path in &restored_favorites {
+        hooks::fire(HookEvent::FavoriteAdded, &[("path", path)]);
+    }
+    for tag in &restored_tags {
+        hooks::fire(
+            HookEvent::TagAdded,
+            &[("path", &tag.path), ("tag", &tag.tag)],
+        );
+    }
+    Ok(entry)
+}
+
+/// Applies the undo of `operation` to `store`. Returns the favorites and tags put back that
+/// fire hooks.
+fn restore(
+    store: &mut PersistedState,
+    operation: &JournalOperation,
+) -> (Vec<String>, Vec<TaggedPath>) {
+    let mut restored_favorites = Vec::new();
+    let mut restored_tags = Vec::new();
+    match operation {
+        JournalOperation::RemoveFavorite {
+            path,
+            index,
+            source,
+        } => {
+            if !store.favorites.contains(path) {
+                let index = (*index).min(store.favorites.len());
+                store.favorites.insert(index, path.clone());
+                if let Some(source) = source {
+                    store
+                        .favorite_sources
+                        .entry(path.clone())
+                        .or_insert_with(|| source.clone());
+                }
+                restored_favorites.push(path.clone());
+            }
+        }
+        JournalOperation::RemoveTag { tags } => {
+            for tag in tags {
+                if !has_tag(store, &tag.path, &tag.tag) {
+                    store.tags.push(tag.clone());
+                    restored_tags.push(tag.clone());
+                }
+            }
+        }
+        JournalOperation::DeleteProfile { profile, index } => {
+            if !store
+                .profiles
+                .iter()
+                .any(|existing| existing.id == profile.id)
+            {
+                let index = (*index).min(store.profiles.len());
+                store.profiles.insert(index, LaunchProfile::clone(profile));
+            }
+        }
+        JournalOperation::Prune {
+            favorites,
+            favorite_indices,
+            favorite_sources,
+            recents,
+            tags,
+            rewritten,
+        } => {
+            for change in rewritten {
+                let PruneAction::Rewrite { to } = &change.action else {
+                    continue;
+                };
+                match change.section {
+                    StoreSection::Favorite => {
+                        if !store.favorites.contains(&change.path) {
+                            if let Some(path) = store.favorites.iter_mut().find(|p| *p == to) {
+                                *path = change.path.clone();
+                            }
+                        }
+                    }
+                    StoreSection::Recent => {
+                        if !store.recents.iter().any(|entry| entry.path == change.path) {
+                            if let Some(entry) =
+                                store.recents.iter_mut().find(|entry| &entry.path == to)
+                            {
+                                entry.path = change.path.clone();
+                            }
+                        }
+                    }
+                    StoreSection::Tag => {
+                        let tag = change.tag.as_deref().unwrap_or_default();
+                        if !has_tag(store, &change.path, tag) {
+                            if let Some(entry) = store.tags.iter_mut().find(|entry| {
+                                &entry.path == to && entry.tag.eq_ignore_ascii_case(tag)
+                            }) {
+                                entry.path = change.path.clone();
+                            }
+                        }
+                    }
+                }
+            }
+            // Inserting in order of the original positions rebuilds the list as it was.
+            let mut positioned: Vec<(usize, &String)> =
+                favorite_indices.iter().copied().zip(favorites).collect();
+            positioned.sort_by_key(|(index, _)| *index);
+            for (index, path) in positioned {
+                if !store.favorites.contains(path) {
+                    let index = index.min(store.favorites.len());
+                    store.favorites.insert(index, path.clone());
+                    if let Some(source) = favorite_sources.get(path) {
+                        store
+                            .favorite_sources
+                            .entry(path.clone())
+                            .or_insert_with(|| source.clone());
+                    }
+                }
+            }
+            for recent in recents {
+                if !store.recents.iter().any(|entry| entry.path == recent.path) {
+                    store.recents.push(recent.clone());
+                }
+            }
+            for tag in tags {
+                if !has_tag(store, &tag.path, &tag.tag) {
+                    store.tags.push(tag.clone());
+                }
+            }
+        }
+    }
+    (restored_favorites, restored_tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_journal_separates_drops_from_rewrites() {
+        let before = PersistedState {
+            favorites: vec![String::from("/gone"), String::from("/Moved")],
+            recents: vec![
+                RecentEntry {
+                    path: String::from("/old"),
+                    last_opened_utc: 5,
+                    ..RecentEntry::default()
+                },
+                RecentEntry {
+                    path: String::from("/new"),
+                    last_opened_utc: 9,
+                    ..RecentEntry::default()
+                },
+            ],
+            ..PersistedState::default()
+        };
+        let change = |section, path: &str, action| PruneChange {
+            section,
+            path: path.to_string(),
+            tag: None,
+            action,
+        };
+        let report = PruneReport {
+            dropped: 1,
+            rewritten: 2,
+            changes: vec![
+                change(StoreSection::Favorite, "/gone", PruneAction::Drop),
+                change(
+                    StoreSection::Favorite,
+                    "/Moved",
+                    PruneAction::Rewrite {
+                        to: String::from("/moved"),
+                    },
+                ),
+                change(
+                    StoreSection::Recent,
+                    "/old",
+                    PruneAction::Rewrite {
+                        to: String::from("/new"),
+                    },
+                ),
+            ],
+        };
+        let JournalOperation::Prune {
+            favorites,
+            recents,
+            rewritten,
+            ..
+        } = prune_operation(&before, &report)
+        else {
+            panic!("expected a prune entry");
+        };
+        assert_eq!(favorites, ["/gone"]);
+        assert_eq!(recents.len(), 1);
+        assert_eq!(recents[0].last_opened_utc, 5);
+        assert_eq!(rewritten.len(), 1);
+        assert_eq!(rewritten[0].path, "/Moved");
+    }
+
+    fn favorites(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    fn drops(paths: &[&str]) -> PruneReport {
+        PruneReport {
+            dropped: paths.len(),
+            rewritten: 0,
+            changes: paths
+                .iter()
+                .map(|path| PruneChange {
+                    section: StoreSection::Favorite,
+                    path: path.to_string(),
+                    tag: None,
+                    action: PruneAction::Drop,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pruned_favorites_come_back_where_they_were() {
+        let mut state = PersistedState {
+            favorites: favorites(&["/a", "/gone1", "/b", "/gone2", "/c"]),
+            favorite_sources: BTreeMap::from([(String::from("/gone2"), String::from("vscode"))]),
+            ..PersistedState::default()
+        };
+        let operation = prune_operation(&state, &drops(&["/gone2", "/gone1"]));
+        let JournalOperation::Prune {
+            favorite_indices, ..
+        } = &operation
+        else {
+            panic!("expected a prune entry");
+        };
+        assert_eq!(favorite_indices, &[3, 1]);
+
+        state.favorites = favorites(&["/a", "/b", "/c"]);
+        state.favorite_sources.clear();
+        restore(&mut state, &operation);
+        assert_eq!(state.favorites, ["/a", "/gone1", "/b", "/gone2", "/c"]);
+        assert_eq!(state.favorite_sources["/gone2"], "vscode");
+    }
+
+    #[test]
+    fn restored_favorites_keep_their_index_among_newer_ones() {
+        let before = PersistedState {
+            favorites: favorites(&["/a", "/gone"]),
+            ..PersistedState::default()
+        };
+        let operation = prune_operation(&before, &drops(&["/gone"]));
+        let mut state = PersistedState {
+            favorites: favorites(&["/new", "/a"]),
+            ..PersistedState::default()
+        };
+        restore(&mut state, &operation);
+        assert_eq!(state.favorites, ["/new", "/gone", "/a"]);
+        let mut shorter = PersistedState::default();
+        restore(&mut shorter, &operation);
+        assert_eq!(shorter.favorites, ["/gone"]);
+    }
+
+    #[test]
+    fn favorites_back_again_are_not_restored_twice() {
+        let before = PersistedState {
+            favorites: favorites(&["/a", "/gone"]),
+            ..PersistedState::default()
+        };
+        let operation = prune_operation(&before, &drops(&["/gone"]));
+        let mut state = PersistedState {
+            favorites: favorites(&["/gone", "/a"]),
+            ..PersistedState::default()
+        };
+        restore(&mut state, &operation);
+        assert_eq!(state.favorites, ["/gone", "/a"]);
+    }
+
+    #[test]
+    fn removed_favorites_come_back_with_their_source() {
+        let operation = JournalOperation::RemoveFavorite {
+            path: String::from("/gone"),
+            index: 1,
+            source: Some(String::from("gtk")),
+        };
+        let mut state = PersistedState {
+            favorites: favorites(&["/a", "/b"]),
+            ..PersistedState::default()
+        };
+        restore(&mut state, &operation);
+        assert_eq!(state.favorites, ["/a", "/gone", "/b"]);
+        assert_eq!(
+            state.favorite_sources.get("/gone").map(String::as_str),
+            Some("gtk")
+        );
+    }
+
+    #[test]
+    fn journal_keeps_the_newest_entries() {
+        let mut state = PersistedState::default();
+        for index in 0..JOURNAL_CAP + 3 {
+            record(
+                &mut state,
+                JournalOperation::RemoveFavorite {
+                    path: format!("/{index}"),
+                    index,
+                    source: None,
+                },
+            );
+        }
+        assert_eq!(state.journal.len(), JOURNAL_CAP);
+        assert!(matches!(
+            &state.journal[0].operation,
+            JournalOperation::RemoveFavorite { index: 3, .. }
+        ));
+    }
+}
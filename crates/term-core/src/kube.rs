@@ -0,0 +1,251 @@
+use std::process::Command;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// One context from the user's kubeconfig, as returned by [`crate::api::list_kube_contexts`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KubeContext {
+    pub name: String,
+    pub cluster: String,
+    /// The namespace commands in this context default to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Whether this is kubectl's `current-context`.
+    pub current: bool,
+}
+
+/// Where [`crate::api::set_profile_kube`] points a profile; no `pod` makes it local again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KubeTarget {
+    #[serde(default)]
+    pub pod: Option<String>,
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// kubeconfig context; `None` uses kubectl's current context.
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ContextDetails {
+    #[serde(default)]
+    cluster: String,
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NamedContext {
+    name: String,
+    context: ContextDetails,
+}
+
+/// The parts of `kubectl config view -o json` that name contexts.
+#[derive(Deserialize)]
+struct KubeConfig {
+    /// `null` when no kubeconfig exists.
+    #[serde(default)]
+    contexts: Option<Vec<NamedContext>>,
+    #[serde(default, rename = "current-context")]
+    current_context: String,
+}
+
+fn kubectl() -> anyhow::Result<std::path::PathBuf> {
+    let name = if cfg!(windows) {
+        "kubectl.exe"
+    } else {
+        "kubectl"
+    };
+    crate::launcher::find_program(name).ok_or_else(|| {
+        CoreError::not_found("kubectl was not found")
+            .with_hint("install kubectl and put it on PATH")
+            .into()
+    })
+}
+
+fn parse_contexts(json: &str) -> anyhow::Result<Vec<KubeContext>> {
+    let config: KubeConfig =
+        serde_json::from_str(json).context("kubectl printed an unexpected config")?;
+    Ok(config
+        .contexts
+        .into_iter()
+        .flatten()
+        .map(|named| KubeContext {
+            current: named.name == config.current_context,
+            name: named.name,
+            cluster: named.context.cluster,
+            namespace: named.context.namespace.filter(|ns| !ns.is_empty()),
+        })
+        .collect())
+}
+
+/// The contexts of the merged kubeconfig (`$KUBECONFIG`, else `~/.kube/config`).
+pub(crate) fn list_contexts() -> anyhow::Result<Vec<KubeContext>> {
+    let output = Command::new(kubectl()?)
+        .args(["config", "view", "-o", "json"])
+        .output()
+        .context("failed to run kubectl")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "kubectl config view failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_contexts(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Rejects names kubectl would read as an option.
+pub(crate) fn validate_name(kind: &str, name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.starts_with('-') || name.chars().any(char::is_whitespace) {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "{name:?} is not a valid {kind} name"
+        )));
+    }
+    Ok(())
+}
+
+/// Where a profile execs into: the pod, and optionally its container, namespace, and context.
+pub(crate) struct PodTarget<'a> {
+    pub pod: &'a str,
+    pub container: Option<&'a str>,
+    pub namespace: Option<&'a str>,
+    pub context: Option<&'a str>,
+}
+
+/// `kubectl exec -it` into the target, running `command` first. Containers often lack bash,
+/// so the session continues in it only when it's there, else in `sh`.
+pub(crate) fn exec_args(target: &PodTarget<'_>, command: Option<&str>) -> Vec<String> {
+    let mut args = vec![String::from("kubectl")];
+    if let Some(context) = target.context {
+        args.extend([String::from("--context"), context.to_string()]);
+    }
+    if let Some(namespace) = target.namespace {
+        args.extend([String::from("--namespace"), namespace.to_string()]);
+    }
+    args.extend([
+        String::from("exec"),
+        String::from("-it"),
+        target.pod.to_string(),
+    ]);
+    if let Some(container) = target.container {
+        args.extend([String::from("--container"), container.to_string()]);
+    }
+    let shell = "command -v bash >/dev/null && exec bash; exec sh";
+    let line = match command {
+        Some(command) => format!("{command}; {shell}"),
+        None => shell.to_string(),
+    };
+    args.extend(["--", "sh", "-c", &line].map(str::to_string));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn target(pod: &str) -> PodTarget<'_> {
+        PodTarget {
+            pod,
+            container: None,
+            namespace: None,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn contexts_carry_their_cluster_namespace_and_current_flag() {
+        let contexts = parse_contexts(
+            r#"{"contexts":[{"name":"prod","context":{"cluster":"eks-prod","namespace":"web"}},
+                {"name":"kind-dev","context":{"cluster":"kind-dev","user":"kind-dev"}}],
+                "current-context":"kind-dev"}"#,
+        )
+        .unwrap();
+        assert_eq!(contexts[0].cluster, "eks-prod");
+        assert_eq!(contexts[0].namespace.as_deref(), Some("web"));
+        assert!(!contexts[0].current && contexts[1].current);
+    }
+
+    #[test]
+    fn empty_namespaces_are_unset() {
+        let contexts = parse_contexts(
+            r#"{"contexts":[{"name":"a","context":{"cluster":"c","namespace":""}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(contexts[0].namespace, None);
+        assert!(!contexts[0].current);
+    }
+
+    #[test]
+    fn a_missing_kubeconfig_has_no_contexts() {
+        assert!(parse_contexts(r#"{"contexts":null,"current-context":""}"#)
+            .unwrap()
+            .is_empty());
+        assert!(parse_contexts("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unexpected_kubectl_output_is_an_error() {
+        assert!(parse_contexts("error: no config").is_err());
+        assert!(parse_contexts(r#"{"contexts":[{"name":"a"}]}"#).is_err());
+    }
+
+    #[test]
+    fn names_that_look_like_options_are_rejected() {
+        for name in ["", "--all", "-n", "two words"] {
+            let err = validate_name("pod", name).unwrap_err();
+            assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        }
+        assert!(validate_name("pod", "api-7d9f").is_ok());
+    }
+
+    #[test]
+    fn exec_args_place_global_flags_before_exec() {
+        let target = PodTarget {
+            pod: "api-7d9f",
+            container: Some("app"),
+            namespace: Some("web"),
+            context: Some("prod"),
+        };
+        assert_eq!(
+            exec_args(&target, Some("rails c")),
+            [
+                "kubectl",
+                "--context",
+                "prod",
+                "--namespace",
+                "web",
+                "exec",
+                "-it",
+                "api-7d9f",
+                "--container",
+                "app",
+                "--",
+                "sh",
+                "-c",
+                "rails c; command -v bash >/dev/null && exec bash; exec sh",
+            ]
+        );
+    }
+
+    #[test]
+    fn exec_without_a_command_only_starts_a_shell() {
+        assert_eq!(
+            exec_args(&target("api"), None),
+            [
+                "kubectl",
+                "exec",
+                "-it",
+                "api",
+                "--",
+                "sh",
+                "-c",
+                "command -v bash >/dev/null && exec bash; exec sh",
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Store;
+
+/// Logs kept per profile; older ones are deleted as new launches start.
+const KEEP: usize = 20;
+
+/// Directory for launches that did not come from a profile.
+const ADHOC: &str = "adhoc";
+
+/// Starts the line written after a background command's output once it exits.
+pub(crate) const EXIT_MARKER: &str = "[terminaut] exit code ";
+
+/// One background launch's output file, as listed by [`crate::api::launch_logs`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchLog {
+    pub path: String,
+    pub started_utc: i64,
+    pub bytes: u64,
+    /// How the process exited, when the launching process was still around to see it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+/// `logs/` next to `state.json`, or under the temp dir when the store is ephemeral.
+fn logs_dir() -> PathBuf {
+    match crate::ephemeral() {
+        true => std::env::temp_dir().join("terminaut-logs"),
+        false => Store::default_store_path().with_file_name("logs"),
+    }
+}
+
+fn profile_dir(profile_id: Option<&str>) -> PathBuf {
+    logs_dir().join(profile_id.unwrap_or(ADHOC))
+}
+
+/// Opens a new log for a launch of `profile_id` and drops the oldest beyond [`KEEP`].
+pub(crate) fn create(profile_id: Option<&str>) -> anyhow::Result<(PathBuf, File)> {
+    if let Some(id) = profile_id.filter(|id| uuid::Uuid::parse_str(id).is_err()) {
+        anyhow::bail!(crate::CoreError::invalid_input(format!(
+            "profile_id {id:?} is not a profile id"
+        )));
+    }
+    let dir = profile_dir(profile_id);
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let now = Utc::now();
+    let path = dir.join(format!(
+        "{}-{}.log",
+        now.format("%Y%m%dT%H%M%S%.3fZ"),
+        std::process::id()
+    ));
+    let file =
+        File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+    prune(&dir);
+    Ok((path, file))
+}
+
+/// Deletes the logs in `dir` beyond the newest [`KEEP`].
+fn prune(dir: &Path) {
+    for old in log_files(dir).into_iter().skip(KEEP) {
+        let _ = std::fs::remove_file(old);
+    }
+}
+
+/// Appends the exit code of the process that wrote `path`, where no shell wrapper does.
+pub(crate) fn record_exit(path: &Path, code: Option<i32>) {
+    let line = match code {
+        Some(code) => format!("\n{EXIT_MARKER}{code}\n"),
+        None => format!("\n{EXIT_MARKER}none (killed by a signal)\n"),
+    };
+    let written = std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(err) = written {
+        tracing::warn!("failed to finish {}: {err}", path.display());
+    }
+}
+
+/// Log files in `dir`, newest first. Names start with the launch time, so they sort by it.
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+    files
+}
+
+fn started_utc(path: &Path) -> Option<i64> {
+    let stem = path.file_stem()?.to_str()?;
+    let (stamp, _pid) = stem.rsplit_once('-')?;
+    let time = chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%dT%H%M%S%.3fZ").ok()?;
+    Some(Utc.from_utc_datetime(&time).timestamp())
+}
+
+/// The exit code written by [`record_exit`], read from the end of the file.
+fn exit_code(path: &Path, bytes: u64) -> Option<i32> {
+    let mut file = File::open(path).ok()?;
+    file.seek(SeekFrom::Start(bytes.saturating_sub(64))).ok()?;
+    let mut tail = String::new();
+    file.read_to_string(&mut tail).ok()?;
+    let last = tail.lines().rev().find(|line| !line.is_empty())?;
+    last.strip_prefix(EXIT_MARKER)?.parse().ok()
+}
+
+/// Logs of background launches of `profile_id` (ad hoc launches for `None`), newest first.
+pub(crate) fn launch_logs(profile_id: Option<&str>) -> Vec<LaunchLog> {
+    log_files(&profile_dir(profile_id))
+        .into_iter()
+        .filter_map(|path| {
+            let bytes = path.metadata().ok()?.len();
+            Some(LaunchLog {
+                started_utc: started_utc(&path)?,
+                exit_code: exit_code(&path, bytes),
+                path: path.display().to_string(),
+                bytes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terminaut-logs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn logged_exit(code: Option<i32>) -> Option<i32> {
+        let dir = temp_dir();
+        let path = dir.join("20261016T093000.250Z-42.log");
+        std::fs::write(&path, "building\nfailed\n").unwrap();
+        record_exit(&path, code);
+        let found = exit_code(&path, path.metadata().unwrap().len());
+        std::fs::remove_dir_all(&dir).unwrap();
+        found
+    }
+
+    #[test]
+    fn log_names_give_the_start_time() {
+        assert_eq!(
+            started_utc(Path::new("/logs/20261016T093000.250Z-42.log")),
+            Some(1_792_143_000)
+        );
+        assert_eq!(started_utc(Path::new("/logs/notes.log")), None);
+        assert_eq!(started_utc(Path::new("/logs/2026-10-16-42.log")), None);
+    }
+
+    #[test]
+    fn logs_list_newest_first_and_skip_other_files() {
+        let dir = temp_dir();
+        for name in [
+            "20261015T093000.000Z-7.log",
+            "20261016T093000.250Z-42.log",
+            "notes.txt",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        assert_eq!(
+            log_files(&dir),
+            [
+                dir.join("20261016T093000.250Z-42.log"),
+                dir.join("20261015T093000.000Z-7.log"),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_log_directories_list_nothing() {
+        assert!(log_files(&std::env::temp_dir().join("terminaut-logs-missing")).is_empty());
+    }
+
+    #[test]
+    fn recorded_exit_codes_are_read_back() {
+        assert_eq!(logged_exit(Some(2)), Some(2));
+        assert_eq!(logged_exit(Some(0)), Some(0));
+    }
+
+    #[test]
+    fn signals_and_unfinished_logs_have_no_exit_code() {
+        assert_eq!(logged_exit(None), None);
+        let dir = temp_dir();
+        let path = dir.join("20261016T093000.250Z-42.log");
+        std::fs::write(&path, "still running\n").unwrap();
+        assert_eq!(exit_code(&path, 14), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn only_the_newest_logs_are_kept() {
+        let dir = temp_dir();
+        for second in 0..KEEP + 3 {
+            let name = format!("20261016T0930{second:02}.000Z-1.log");
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        prune(&dir);
+        let files = log_files(&dir);
+        assert_eq!(files.len(), KEEP);
+        assert_eq!(
+            files[0],
+            dir.join(format!("20261016T0930{:02}.000Z-1.log", KEEP + 2))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn profile_ids_must_be_uuids() {
+        let err = create(Some("../escape")).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+}
@@ -0,0 +1,1146 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::hooks::HookEvent;
+
+pub(crate) const KNOWN_TERMINALS: [&str; 8] = [
+    "system",
+    "terminal",
+    "iterm",
+    "iterm2",
+    "ghostty",
+    "alacritty",
+    "windows-terminal",
+    "wt",
+];
+
+/// Mirrors the app's `TerminalLauncher.open` cap on windows per launch.
+const MAX_WINDOWS: u8 = 5;
+
+/// Whether a launch fills in `{secret:NAME}` references from the keychain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Secrets {
+    /// A profile saved in the store, which only the user edits.
+    Resolve,
+    /// Anything else: requests from callers, links, and project files could otherwise send
+    /// the user's secrets anywhere, so their references are left as written.
+    Leave,
+}
+
+/// One terminal launch: where to open, which terminal app, and what to run there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchRequest {
+    pub path: String,
+    /// `terminal`, `iterm`, `ghostty`, `alacritty`, `windows-terminal`, or `system` for the OS
+    /// default;
+    /// `None` uses the configured default, then the OS default.
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// Command line to run, for requests built by hand; `args` wins when both are set.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// The program and its arguments, each passed as one word; see
+    /// [`crate::LaunchProfile::args`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Joins `args` unquoted so the shell interprets them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub use_shell: bool,
+    /// See [`crate::LaunchProfile::pre_command`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_command: Option<String>,
+    /// See [`crate::LaunchProfile::post_launch_command`].
+    #[serde(
+        default,
+        alias = "post_command",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub post_launch_command: Option<String>,
+    /// Name of the launch profile this request came from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Id of that profile; background launches keep their logs under it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<String>,
+    /// Run the command without a terminal, writing its output to a log file listed by
+    /// [`crate::api::launch_logs`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub background: bool,
+    #[serde(default = "default_windows")]
+    pub windows: u8,
+    /// Extra environment for the launched shell, e.g. from a project's `.terminaut.toml`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Open the shell in this WSL distro (empty for the default one) at the translated path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+}
+
+fn default_windows() -> u8 {
+    1
+}
+
+/// A step of a launch, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchPhase {
+    /// The request's `pre_command`.
+    Pre,
+    /// Opening the terminal windows.
+    Launch,
+    /// The request's `post_launch_command`, once the terminal has opened.
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseStatus {
+    Succeeded,
+    Failed,
+    /// Killed after `terminal.hook_timeout_secs`.
+    TimedOut,
+    /// Not run because an earlier phase failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseResult {
+    pub phase: LaunchPhase,
+    pub status: PhaseStatus,
+    /// Exit code of a pre or post command that ran to the end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// What a pre or post command printed, up to its last 64 KiB.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// How each phase of one launch went; see [`crate::api::launch_profile`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchReport {
+    pub request: LaunchRequest,
+    /// The phases that apply to the request, in order. Pre and post appear only when the
+    /// request has those commands.
+    pub phases: Vec<PhaseResult>,
+    /// Where a background launch writes its output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+    /// What the launch did differently than asked, such as connecting with ssh because mosh
+    /// isn't installed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl LaunchReport {
+    /// Whether every phase succeeded.
+    pub fn succeeded(&self) -> bool {
+        self.phases
+            .iter()
+            .all(|phase| phase.status == PhaseStatus::Succeeded)
+    }
+}
+
+/// Output kept per stream of a pre or post command.
+const OUTPUT_LIMIT: usize = 64 * 1024;
+
+impl PhaseResult {
+    fn skipped(phase: LaunchPhase) -> Self {
+        Self {
+            phase,
+            status: PhaseStatus::Skipped,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+            duration_ms: 0,
+        }
+    }
+
+    fn finished<T>(phase: LaunchPhase, started: Instant, result: &anyhow::Result<T>) -> Self {
+        Self {
+            status: match result {
+                Ok(_) => PhaseStatus::Succeeded,
+                Err(_) => PhaseStatus::Failed,
+            },
+            error: result.as_ref().err().map(|err| format!("{err:#}")),
+            duration_ms: started.elapsed().as_millis() as u64,
+            ..Self::skipped(phase)
+        }
+    }
+
+    /// Why the phase did not succeed, for errors and logs.
+    fn describe(&self, timeout: Duration) -> String {
+        match (self.status, &self.error, self.exit_code) {
+            (PhaseStatus::TimedOut, _, _) => format!("timed out after {}s", timeout.as_secs()),
+            (_, Some(error), _) => format!("failed: {error}"),
+            (_, None, Some(code)) => match self
+                .stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+            {
+                Some(line) => format!("exited with {code}: {}", line.trim()),
+                None => format!("exited with {code}"),
+            },
+            _ => String::from("failed"),
+        }
+    }
+}
+
+/// Reads `stream` into a buffer holding its last [`OUTPUT_LIMIT`] bytes as they arrive, so a
+/// killed command still reports what it printed.
+fn capture(
+    stream: Option<impl Read + Send + 'static>,
+) -> (Arc<Mutex<Vec<u8>>>, Option<JoinHandle<()>>) {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let reader = stream.map(|mut stream| {
+        let buffer = buffer.clone();
+        std::thread::spawn(move || {
+            let mut chunk = [0u8; 8192];
+            while let Ok(read @ 1..) = stream.read(&mut chunk) {
+                let mut buffer = buffer.lock();
+                buffer.extend_from_slice(&chunk[..read]);
+                let excess = buffer.len().saturating_sub(OUTPUT_LIMIT);
+                buffer.drain(..excess);
+            }
+        })
+    });
+    (buffer, reader)
+}
+
+/// What a capture buffer holds, waiting briefly for its reader to drain the pipe. Background
+/// processes started by the command can keep the pipe open, so the reader is not joined.
+fn captured(buffer: &Mutex<Vec<u8>>, reader: Option<JoinHandle<()>>) -> String {
+    let deadline = Instant::now() + Duration::from_millis(500);
+    while reader.as_ref().is_some_and(|reader| !reader.is_finished()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    String::from_utf8_lossy(&buffer.lock()).into_owned()
+}
+
+/// Runs a pre or post command through the platform shell in the launch directory with the
+/// launch's env, killing it after `timeout`.
+fn run_phase(
+    phase: LaunchPhase,
+    command: &str,
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    timeout: Duration,
+) -> PhaseResult {
+    let started = Instant::now();
+    let mut result = PhaseResult {
+        status: PhaseStatus::Failed,
+        ..PhaseResult::skipped(phase)
+    };
+    let spawned = crate::hooks::shell(command)
+        .current_dir(&request.path)
+        .envs(&request.env)
+        .envs(secret_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            result.error = Some(format!("could not start the shell: {err}"));
+            return result;
+        }
+    };
+    let (stdout, stdout_reader) = capture(child.stdout.take());
+    let (stderr, stderr_reader) = capture(child.stderr.take());
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                result.exit_code = status.code();
+                if status.success() {
+                    result.status = PhaseStatus::Succeeded;
+                }
+                break;
+            }
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                result.status = PhaseStatus::TimedOut;
+                break;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(err) => {
+                let _ = child.kill();
+                result.error = Some(err.to_string());
+                break;
+            }
+        }
+    }
+    result.stdout = captured(&stdout, stdout_reader);
+    result.stderr = captured(&stderr, stderr_reader);
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TerminalKind {
+    /// Terminal.app on macOS, `$TERMINAL` or `x-terminal-emulator` on Linux, the console on
+    /// Windows.
+    Terminal,
+    Iterm,
+    Ghostty,
+    Alacritty,
+    WindowsTerminal,
+}
+
+impl TerminalKind {
+    /// Parses a terminal name; `None` falls back to `terminal.default` from the config, then
+    /// to [`Self::system_default`].
+    fn parse(value: Option<&str>) -> anyhow::Result<Self> {
+        let configured = crate::config::config().terminal.default.clone();
+        let value = value.map(str::to_string).or(configured);
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("system") => Ok(Self::system_default()),
+            Some(name) => Self::named(name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown terminal {name:?} (terminal, iterm, ghostty, alacritty, windows-terminal, system)"
+                )
+            }),
+        }
+    }
+
+    fn named(name: &str) -> Option<Self> {
+        match name {
+            "terminal" => Some(Self::Terminal),
+            "iterm" | "iterm2" => Some(Self::Iterm),
+            "ghostty" => Some(Self::Ghostty),
+            "alacritty" => Some(Self::Alacritty),
+            "windows-terminal" | "wt" => Some(Self::WindowsTerminal),
+            _ => None,
+        }
+    }
+
+    /// The OS default terminal when Terminaut can open it, otherwise [`Self::Terminal`].
+    fn system_default() -> Self {
+        crate::defaults::default_terminal()
+            .terminal
+            .and_then(|name| Self::named(&name))
+            .unwrap_or(Self::Terminal)
+    }
+}
+
+/// Finds `program` on `PATH` (or at its path when it has one).
+pub(crate) fn find_program(program: &str) -> Option<std::path::PathBuf> {
+    let program = std::path::Path::new(program);
+    if program.components().count() > 1 {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Where the terminal `name` (or the configured default) is installed, or `None` when it
+/// can't be found. Fails only for names that aren't a known terminal.
+pub(crate) fn locate_terminal(name: Option<&str>) -> anyhow::Result<Option<std::path::PathBuf>> {
+    let kind = TerminalKind::parse(name)?;
+    #[cfg(target_os = "macos")]
+    {
+        let bundle = match kind {
+            TerminalKind::Terminal => "Utilities/Terminal.app",
+            TerminalKind::Iterm => "iTerm.app",
+            TerminalKind::Ghostty => "Ghostty.app",
+            TerminalKind::Alacritty => "Alacritty.app",
+            TerminalKind::WindowsTerminal => return Ok(None),
+        };
+        let mut roots = vec![
+            std::path::PathBuf::from("/System/Applications"),
+            std::path::PathBuf::from("/Applications"),
+        ];
+        roots.extend(dirs::home_dir().map(|home| home.join("Applications")));
+        Ok(roots
+            .into_iter()
+            .map(|root| root.join(bundle))
+            .find(|app| app.is_dir()))
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        Ok(match kind {
+            TerminalKind::Ghostty => find_program("ghostty"),
+            TerminalKind::Alacritty => find_program("alacritty"),
+            TerminalKind::Terminal => find_program(
+                &std::env::var("TERMINAL").unwrap_or_else(|_| String::from("x-terminal-emulator")),
+            ),
+            TerminalKind::Iterm | TerminalKind::WindowsTerminal => None,
+        })
+    }
+    #[cfg(windows)]
+    {
+        Ok(match kind {
+            TerminalKind::Terminal => find_program("cmd.exe"),
+            TerminalKind::WindowsTerminal => find_program("wt.exe"),
+            TerminalKind::Alacritty => find_program("alacritty.exe"),
+            TerminalKind::Iterm | TerminalKind::Ghostty => None,
+        })
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = kind;
+        Ok(None)
+    }
+}
+
+/// A terminal Terminaut can drive that is installed here, as listed by
+/// [`crate::api::installed_terminals`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledTerminal {
+    /// The [`LaunchRequest::terminal`] name that opens it.
+    pub name: String,
+    pub path: String,
+}
+
+pub(crate) fn installed_terminals() -> Vec<InstalledTerminal> {
+    [
+        "terminal",
+        "iterm",
+        "ghostty",
+        "alacritty",
+        "windows-terminal",
+    ]
+    .into_iter()
+    .filter_map(|name| {
+        let path = locate_terminal(Some(name)).ok()??;
+        Some(InstalledTerminal {
+            name: name.to_string(),
+            path: path.display().to_string(),
+        })
+    })
+    .collect()
+}
+
+/// Shell and environment settings from the config file of terminal `name` (or the configured
+/// default). Terminals whose config Terminaut doesn't read report none.
+pub(crate) fn terminal_settings(name: Option<&str>) -> anyhow::Result<crate::TerminalSettings> {
+    Ok(match TerminalKind::parse(name)? {
+        TerminalKind::Ghostty => crate::terminal_config::ghostty(),
+        TerminalKind::Alacritty => crate::terminal_config::alacritty(),
+        _ => crate::TerminalSettings::default(),
+    })
+}
+
+fn shell_escape(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn apple_script_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The line to run in the new shell: `args` quoted into one line, else the legacy `command`.
+fn custom_command(request: &LaunchRequest) -> Option<String> {
+    if !request.args.is_empty() {
+        return Some(crate::command_line::join(&request.args, request.use_shell));
+    }
+    request
+        .command
+        .clone()
+        .filter(|command| !command.trim().is_empty())
+}
+
+/// Whether `key` can be exported by a POSIX shell: `[A-Za-z_][A-Za-z0-9_]*`. Anything else
+/// would be shell syntax once typed into `export {key}=...`.
+pub(crate) fn is_env_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn env_exports(env: &BTreeMap<String, String>) -> String {
+    env.iter()
+        .map(|(key, value)| format!("export {key}={} && ", shell_escape(value)))
+        .collect()
+}
+
+/// What a window runs for a custom command: the command, then the shell the terminal's own
+/// config sets (Ghostty and Alacritty), else the login shell, so the window stays open.
+#[cfg_attr(windows, allow(dead_code))]
+fn keep_open(kind: TerminalKind, custom: &str) -> String {
+    let settings = match kind {
+        TerminalKind::Ghostty => crate::terminal_config::ghostty(),
+        TerminalKind::Alacritty => crate::terminal_config::alacritty(),
+        _ => crate::TerminalSettings::default(),
+    };
+    let shell = settings
+        .shell_line()
+        .unwrap_or_else(|| String::from("\"${SHELL:-sh}\""));
+    format!("{custom}; exec {shell}")
+}
+
+/// The start of a line that reads the launch's secrets from `env_file` and deletes it.
+fn source(env_file: Option<&std::path::Path>) -> String {
+    env_file
+        .map(|file| {
+            let file = shell_escape(&file.display().to_string());
+            format!(". {file} && rm -f {file} && ")
+        })
+        .unwrap_or_default()
+}
+
+/// The shell line typed into Terminal and iTerm windows.
+fn shell_command(request: &LaunchRequest, env_file: Option<&std::path::Path>) -> String {
+    let base = format!(
+        "{}{}cd {}",
+        source(env_file),
+        env_exports(&request.env),
+        shell_escape(&request.path)
+    );
+    match custom_command(request) {
+        Some(command) => format!("{base} && {command}"),
+        None => format!("{base} && exec $SHELL -l"),
+    }
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn apple_script(
+    kind: TerminalKind,
+    request: &LaunchRequest,
+    count: u8,
+    env_file: Option<&std::path::Path>,
+) -> String {
+    match kind {
+        TerminalKind::Terminal => format!(
+            "tell application \"Terminal\"\n    activate\n    repeat {count}\n        do script \"{}\"\n    end repeat\nend tell",
+            apple_script_escape(&shell_command(request, env_file))
+        ),
+        TerminalKind::Iterm => format!(
+            "tell application \"iTerm2\"\n    activate\n    repeat {count}\n        create window with default profile\n        tell current session of current window\n            write text \"{}\"\n        end tell\n    end repeat\nend tell",
+            apple_script_escape(&shell_command(request, env_file))
+        ),
+        TerminalKind::Ghostty | TerminalKind::Alacritty => {
+            let custom = custom_command(request).map(|command| {
+                shell_escape(&keep_open(
+                    kind,
+                    &format!(
+                        "{}{}cd {} && {command}",
+                        source(env_file),
+                        env_exports(&request.env),
+                        shell_escape(&request.path)
+                    ),
+                ))
+            });
+            let dir = shell_escape(&request.path);
+            let line = match (kind, custom) {
+                (TerminalKind::Ghostty, Some(custom)) => {
+                    format!("open -na Ghostty --args --working-directory={dir} --command {custom}")
+                }
+                (TerminalKind::Ghostty, None) => {
+                    format!("open -na Ghostty --args --working-directory={dir}")
+                }
+                (_, Some(custom)) => format!(
+                    "open -na Alacritty --args --working-directory {dir} -e sh -c {custom}"
+                ),
+                (_, None) => format!("open -na Alacritty --args --working-directory {dir}"),
+            };
+            format!(
+                "repeat {count}\n    do shell script \"{}\"\nend repeat",
+                apple_script_escape(&line)
+            )
+        }
+        TerminalKind::WindowsTerminal => unreachable!("rejected before scripting"),
+    }
+}
+
+#[cfg_attr(all(unix, not(target_os = "macos")), allow(dead_code))]
+fn run(mut command: Command) -> anyhow::Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run {program}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Windows opened through AppleScript don't inherit the environment, so a launch with
+/// secrets opens them one at a time, each reading its own env file.
+#[cfg(target_os = "macos")]
+fn launch_windows(
+    kind: TerminalKind,
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    if kind == TerminalKind::WindowsTerminal {
+        anyhow::bail!("Windows Terminal is only available on Windows");
+    }
+    if secret_env.is_empty() || custom_command(request).is_none() {
+        let mut command = Command::new("/usr/bin/osascript");
+        command
+            .arg("-e")
+            .arg(apple_script(kind, request, count, None));
+        return run(command);
+    }
+    for _ in 0..count {
+        let env_file = crate::secrets::env_file(secret_env)?;
+        let mut command = Command::new("/usr/bin/osascript");
+        command
+            .arg("-e")
+            .arg(apple_script(kind, request, 1, Some(&env_file)));
+        if let Err(err) = run(command) {
+            let _ = std::fs::remove_file(&env_file);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn launch_windows(
+    kind: TerminalKind,
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    let program = match kind {
+        TerminalKind::Ghostty => String::from("ghostty"),
+        TerminalKind::Alacritty => String::from("alacritty"),
+        TerminalKind::Terminal => {
+            std::env::var("TERMINAL").unwrap_or_else(|_| String::from("x-terminal-emulator"))
+        }
+        TerminalKind::Iterm => anyhow::bail!("iTerm2 is only available on macOS"),
+        TerminalKind::WindowsTerminal => {
+            anyhow::bail!("Windows Terminal is only available on Windows")
+        }
+    };
+    for _ in 0..count {
+        let mut command = Command::new(&program);
+        command
+            .current_dir(&request.path)
+            .envs(&request.env)
+            .envs(secret_env);
+        match kind {
+            TerminalKind::Ghostty => {
+                command.arg(format!("--working-directory={}", request.path));
+            }
+            TerminalKind::Alacritty => {
+                command.args(["--working-directory", &request.path]);
+            }
+            _ => {}
+        }
+        if let Some(custom) = custom_command(request) {
+            command
+                .arg("-e")
+                .arg("sh")
+                .arg("-c")
+                .arg(keep_open(kind, &custom));
+        }
+        command
+            .spawn()
+            .with_context(|| format!("failed to start {program}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn launch_windows(
+    kind: TerminalKind,
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    if let Some(distro) = &request.wsl_distro {
+        return launch_wsl(distro, request, secret_env, count);
+    }
+    match kind {
+        TerminalKind::Terminal => {}
+        TerminalKind::WindowsTerminal => {
+            return launch_windows_terminal(request, secret_env, count)
+        }
+        TerminalKind::Alacritty => return launch_alacritty(request, secret_env, count),
+        TerminalKind::Iterm | TerminalKind::Ghostty => {
+            anyhow::bail!(
+                "only the console, Windows Terminal, and Alacritty are supported on Windows"
+            )
+        }
+    }
+    for _ in 0..count {
+        let mut command = Command::new("cmd");
+        command
+            .args(["/C", "start", "", "/D", &request.path, "cmd"])
+            .envs(&request.env)
+            .envs(secret_env);
+        if let Some(custom) = custom_command(request) {
+            command.args(["/K", &custom]);
+        }
+        run(command)?;
+    }
+    Ok(())
+}
+
+/// One new Windows Terminal window per launch, in the default profile's shell or `cmd /K` for a
+/// custom command.
+#[cfg(windows)]
+fn launch_windows_terminal(
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    for _ in 0..count {
+        let mut command = Command::new("wt.exe");
+        command
+            .args(["-w", "new", "-d", &request.path])
+            .envs(&request.env)
+            .envs(secret_env);
+        if let Some(custom) = custom_command(request) {
+            command.args(["cmd", "/K", &custom]);
+        }
+        command
+            .spawn()
+            .context("failed to start Windows Terminal")?;
+    }
+    Ok(())
+}
+
+/// One Alacritty window per launch, running `cmd /K` for a custom command.
+#[cfg(windows)]
+fn launch_alacritty(
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    for _ in 0..count {
+        let mut command = Command::new("alacritty.exe");
+        command
+            .args(["--working-directory", &request.path])
+            .envs(&request.env)
+            .envs(secret_env);
+        if let Some(custom) = custom_command(request) {
+            command.args(["-e", "cmd", "/K", &custom]);
+        }
+        command.spawn().context("failed to start Alacritty")?;
+    }
+    Ok(())
+}
+
+/// Opens `wsl.exe` in its own console, starting in the Linux form of the request's path.
+/// Secrets cross into the distro through `WSLENV`.
+#[cfg(windows)]
+fn launch_wsl(
+    distro: &str,
+    request: &LaunchRequest,
+    secret_env: &BTreeMap<String, String>,
+    count: u8,
+) -> anyhow::Result<()> {
+    let dir = crate::wsl::translate_path(&request.path, crate::wsl::PathFlavor::Wsl, None)?;
+    let wslenv = std::env::var("WSLENV")
+        .ok()
+        .into_iter()
+        .filter(|existing| !existing.is_empty())
+        .chain(secret_env.keys().cloned())
+        .collect::<Vec<_>>()
+        .join(":");
+    for _ in 0..count {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", "", "wsl.exe"]);
+        if !secret_env.is_empty() {
+            command.envs(secret_env).env("WSLENV", &wslenv);
+        }
+        if !distro.is_empty() {
+            command.args(["-d", distro]);
+        }
+        command.args(["--cd", &dir]);
+        let custom = custom_command(request);
+        if custom.is_some() || !request.env.is_empty() {
+            let line = format!(
+                "{}{}exec \"${{SHELL:-sh}}\"",
+                env_exports(&request.env),
+                custom
+                    .map(|custom| format!("{custom}; "))
+                    .unwrap_or_default()
+            );
+            command.args(["-e", "sh", "-c", &line]);
+        }
+        run(command)?;
+    }
+    Ok(())
+}
+
+/// Checks that `request` can be launched here and, for a saved profile, fills in its secrets.
+fn prepare(
+    request: &LaunchRequest,
+    secrets: Secrets,
+) -> anyhow::Result<(TerminalKind, crate::secrets::Resolved)> {
+    let kind = TerminalKind::parse(request.terminal.as_deref())?;
+    if request.wsl_distro.is_some() && !cfg!(windows) {
+        anyhow::bail!(crate::CoreError::invalid_input(
+            "WSL distros can only be targeted from Windows"
+        )
+        .with_hint("remove wsl_distro from the profile"));
+    }
+    if !std::path::Path::new(&request.path).is_dir() {
+        anyhow::bail!("not a directory: {}", request.path);
+    }
+    if let Some(key) = request.env.keys().find(|key| !is_env_name(key)) {
+        anyhow::bail!(crate::CoreError::invalid_input(format!(
+            "env key {key:?} is not a valid variable name"
+        ))
+        .with_hint("use letters, digits, and '_', not starting with a digit"));
+    }
+    let resolved = match secrets {
+        Secrets::Resolve => crate::secrets::resolve_request(request)?,
+        Secrets::Leave => {
+            if crate::secrets::has_references(request) {
+                tracing::warn!(
+                    path = %request.path,
+                    "secret references are only filled in for saved profiles; launching as written"
+                );
+            }
+            crate::secrets::Resolved::unresolved(request)
+        }
+    };
+    Ok((kind, resolved))
+}
+
+/// The command a background launch runs. On Unix a wrapping `sh` appends the exit code to the
+/// log, so it is recorded even when the launching process has exited by then.
+fn background_command(resolved: &LaunchRequest) -> Option<Command> {
+    let plain = resolved.args.split_first().filter(|_| !resolved.use_shell);
+    if cfg!(unix) {
+        let (run, args) = match plain {
+            Some(_) => ("\"$@\"", resolved.args.clone()),
+            None => ("sh -c \"$1\"", vec![custom_command(resolved)?]),
+        };
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!(
+                "{run}; printf '\\n{}%s\\n' \"$?\"",
+                crate::launch_logs::EXIT_MARKER
+            ))
+            .arg("sh")
+            .args(args);
+        return Some(command);
+    }
+    match plain {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            Some(command)
+        }
+        None => custom_command(resolved).map(|line| crate::hooks::shell(&line)),
+    }
+}
+
+/// Starts the command on its own, with stdout and stderr going to a new launch log under
+/// `log_key`, and returns the log's path. The command outlives the launch.
+fn start_background(
+    request: &LaunchRequest,
+    resolved: &crate::secrets::Resolved,
+    log_key: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    if request.wsl_distro.is_some() {
+        anyhow::bail!(crate::CoreError::invalid_input(
+            "background launches cannot target a WSL distro"
+        ));
+    }
+    let Some(mut command) = background_command(&resolved.request) else {
+        anyhow::bail!(crate::CoreError::invalid_input(
+            "a background launch needs a command to run"
+        )
+        .with_hint("give the profile a command, or launch it in a terminal"));
+    };
+    let (path, log) = crate::launch_logs::create(log_key)?;
+    let mut child = command
+        .current_dir(&request.path)
+        .envs(&resolved.request.env)
+        .envs(&resolved.env)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()
+        .with_context(|| format!("failed to start {:?}", command.get_program()))?;
+    let log = path.clone();
+    // Reap in the background; elsewhere than Unix this is also what records the exit code.
+    std::thread::spawn(move || {
+        if let Ok(status) = child.wait() {
+            if !cfg!(unix) {
+                crate::launch_logs::record_exit(&log, status.code());
+            }
+        }
+    });
+    Ok(path)
+}
+
+/// Opens the terminal windows, or starts a background launch and returns its log.
+fn open(
+    kind: TerminalKind,
+    request: &LaunchRequest,
+    resolved: &crate::secrets::Resolved,
+    log_key: Option<&str>,
+) -> anyhow::Result<Option<PathBuf>> {
+    let log_file = match request.background {
+        true => {
+            let log_file = start_background(request, resolved, log_key)?;
+            tracing::info!(path = %request.path, log = %log_file.display(), "started in the background");
+            Some(log_file)
+        }
+        false => {
+            launch_windows(
+                kind,
+                &resolved.request,
+                &resolved.env,
+                request.windows.clamp(1, MAX_WINDOWS),
+            )?;
+            tracing::info!(path = %request.path, windows = request.windows, "launched {kind:?}");
+            None
+        }
+    };
+    if let Some(vars) = profile_hook_vars(request) {
+        let vars: Vec<(&str, &str)> = vars
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        crate::hooks::fire(HookEvent::ProfileLaunched, &vars);
+    }
+    Ok(log_file)
+}
+
+/// What `profile_launched` hooks are told about a launch, or `None` when it didn't go through
+/// a saved profile and so fires no hook.
+fn profile_hook_vars(request: &LaunchRequest) -> Option<Vec<(&'static str, String)>> {
+    request.profile_id.as_ref()?;
+    Some(vec![
+        ("path", request.path.clone()),
+        ("profile", request.profile.clone().unwrap_or_default()),
+        ("terminal", request.terminal.clone().unwrap_or_default()),
+        ("command", custom_command(request).unwrap_or_default()),
+    ])
+}
+
+/// Runs the pre command, opens the terminal, then runs the post-launch command without waiting
+/// for the session to end. The error is the one that stopped the launch; a failed post-launch
+/// command is only reported, since the terminal is already open by then. A background
+/// launch logs under `log_key`.
+fn run_launch(
+    request: &LaunchRequest,
+    secrets: Secrets,
+    log_key: Option<&str>,
+) -> (LaunchReport, anyhow::Result<()>) {
+    let mut phases = Vec::new();
+    let mut log_file = None;
+    let started = Instant::now();
+    let pre = request
+        .pre_command
+        .as_deref()
+        .filter(|c| !c.trim().is_empty());
+    let post = request
+        .post_launch_command
+        .as_deref()
+        .filter(|c| !c.trim().is_empty());
+    let result = prepare(request, secrets).and_then(|(kind, resolved)| {
+        let timeout = Duration::from_secs(crate::config::config().terminal.hook_timeout_secs);
+        // The resolved commands, reading secrets from their variables, are the ones that run.
+        if pre.is_some() {
+            let pre = run_phase(
+                LaunchPhase::Pre,
+                resolved.request.pre_command.as_deref().unwrap_or_default(),
+                &resolved.request,
+                &resolved.env,
+                timeout,
+            );
+            let failure = (pre.status != PhaseStatus::Succeeded).then(|| pre.describe(timeout));
+            phases.push(pre);
+            if let Some(failure) = failure {
+                phases.push(PhaseResult::skipped(LaunchPhase::Launch));
+                phases.extend(post.map(|_| PhaseResult::skipped(LaunchPhase::Post)));
+                anyhow::bail!("pre_command {failure}");
+            }
+        }
+        let started = Instant::now();
+        let opened = open(kind, request, &resolved, log_key);
+        phases.push(PhaseResult::finished(LaunchPhase::Launch, started, &opened));
+        log_file = opened?;
+        if post.is_some() {
+            let post = run_phase(
+                LaunchPhase::Post,
+                resolved.request.post_launch_command.as_deref().unwrap_or_default(),
+                &resolved.request,
+                &resolved.env,
+                timeout,
+            );
+            if post.status != PhaseStatus::Succeeded {
+                tracing::warn!(path = %request.path, "post_launch_command {}", post.describe(timeout));
+            }
+            phases.push(post);
+        }
+        Ok(())
+    });
+    if phases.is_empty() {
+        // Nothing ran: the request itself was rejected.
+        phases.push(PhaseResult::finished(LaunchPhase::Launch, started, &result));
+    }
+    let report = LaunchReport {
+        request: request.clone(),
+        phases,
+        log_file: log_file.map(|path| path.display().to_string()),
+        warnings: Vec::new(),
+    };
+    (report, result)
+}
+
+pub(crate) fn launch(request: &LaunchRequest, secrets: Secrets) -> anyhow::Result<()> {
+    run_launch(request, secrets, request.profile_id.as_deref()).1
+}
+
+/// Like [`launch`], but reports each phase instead of stopping at the first error.
+pub(crate) fn launch_report(request: &LaunchRequest, secrets: Secrets) -> LaunchReport {
+    run_launch(request, secrets, request.profile_id.as_deref()).0
+}
+
+/// Launches custom action `action_id`, whose background runs log under its id. It isn't a
+/// profile, so no `profile_launched` hook fires.
+pub(crate) fn launch_action(request: &LaunchRequest, action_id: uuid::Uuid) -> anyhow::Result<()> {
+    run_launch(request, Secrets::Leave, Some(&action_id.to_string())).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_escape_paths_and_commands() {
+        let request = LaunchRequest {
+            path: String::from("/tmp/it's here"),
+            terminal: None,
+            command: Some(String::from("echo \"hi\"")),
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 1,
+            env: BTreeMap::new(),
+            wsl_distro: None,
+        };
+        let script = apple_script(TerminalKind::Terminal, &request, 1, None);
+        assert!(script.contains(r#"do script "cd '/tmp/it'\\''s here' && echo \"hi\"""#));
+        let env_file = std::path::Path::new("/tmp/s.env");
+        let script = apple_script(TerminalKind::Terminal, &request, 1, Some(env_file));
+        assert!(script.contains("do script \". '/tmp/s.env' && rm -f '/tmp/s.env' && cd "));
+        assert!(TerminalKind::parse(Some("warp")).is_err());
+        assert_eq!(
+            TerminalKind::parse(Some("iTerm2")).unwrap(),
+            TerminalKind::Iterm
+        );
+    }
+
+    #[test]
+    fn only_profile_launches_fire_the_profile_hook() {
+        let plain: LaunchRequest =
+            serde_json::from_str(r#"{"path": "/srv", "command": "make watch"}"#).unwrap();
+        assert!(profile_hook_vars(&plain).is_none());
+        let profiled = LaunchRequest {
+            profile: Some(String::from("Watch")),
+            profile_id: Some(uuid::Uuid::new_v4().to_string()),
+            ..plain
+        };
+        let vars = profile_hook_vars(&profiled).unwrap();
+        assert_eq!(vars[1], ("profile", String::from("Watch")));
+        assert_eq!(vars[3], ("command", String::from("make watch")));
+    }
+
+    #[test]
+    fn post_command_is_read_under_its_old_name() {
+        let request: LaunchRequest =
+            serde_json::from_str(r#"{"path": "/srv", "post_command": "make watch"}"#).unwrap();
+        assert_eq!(request.post_launch_command.as_deref(), Some("make watch"));
+    }
+
+    #[test]
+    fn env_keys_must_be_variable_names() {
+        for key in ["PATH", "_x", "A1_B"] {
+            assert!(is_env_name(key), "{key}");
+        }
+        for key in ["", "1A", "X=1; rm -rf ~ #", "A-B", "A B", "É"] {
+            assert!(!is_env_name(key), "{key}");
+        }
+        let request = LaunchRequest {
+            path: std::env::temp_dir().display().to_string(),
+            terminal: None,
+            command: None,
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 1,
+            env: [(String::from("X=1; rm -rf ~ #"), String::new())].into(),
+            wsl_distro: None,
+        };
+        let err = launch(&request, Secrets::Leave).unwrap_err();
+        assert_eq!(crate::error_code(&err), crate::ErrorCode::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn phases_capture_output_and_time_out() {
+        let request = LaunchRequest {
+            path: std::env::temp_dir().display().to_string(),
+            terminal: None,
+            command: None,
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 1,
+            env: [(String::from("GREETING"), String::from("hi"))].into(),
+            wsl_distro: None,
+        };
+        let timeout = Duration::from_secs(5);
+
+        let done = run_phase(
+            LaunchPhase::Pre,
+            "echo $GREETING; echo oops >&2; exit 3",
+            &request,
+            &BTreeMap::new(),
+            timeout,
+        );
+        assert_eq!(done.status, PhaseStatus::Failed);
+        assert_eq!(done.exit_code, Some(3));
+        assert_eq!(done.stdout, "hi\n");
+        assert_eq!(done.describe(timeout), "exited with 3: oops");
+
+        let slow = run_phase(
+            LaunchPhase::Post,
+            "echo started; sleep 5",
+            &request,
+            &BTreeMap::new(),
+            Duration::from_millis(200),
+        );
+        assert_eq!(slow.status, PhaseStatus::TimedOut);
+        assert_eq!(slow.stdout, "started\n");
+    }
+}
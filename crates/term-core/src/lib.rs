@@ -1,70 +1,340 @@
-use std::ffi::{c_char, CStr, CString};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::{c_char, c_void, CStr, CString};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use anyhow::Context;
 use chrono::Utc;
 use dirs::data_dir;
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-use ignore::WalkBuilder;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-static STORE: Lazy<Store> = Lazy::new(|| Store::initialize().unwrap_or_default());
+mod actions;
+mod archive;
+mod auto_tag;
+mod autostart;
+mod backend;
+mod batches;
+mod breadcrumbs;
+#[cfg(feature = "fs")]
+mod candidates;
+mod capabilities;
+mod clipboard;
+mod command_line;
+mod config;
+mod deeplink;
+mod defaults;
+mod description;
+#[cfg(feature = "fs")]
+mod dir_size;
+mod doctor;
+#[cfg(feature = "fs")]
+mod duplicates;
+mod editors;
+mod encryption;
+mod error;
+mod events;
+mod finder_tags;
+#[cfg(feature = "fs")]
+mod fs_ops;
+mod git;
+#[cfg(feature = "fs")]
+mod grep;
+mod history;
+mod hooks;
+mod ignore_file;
+mod importers;
+#[cfg(feature = "watch")]
+mod indexd;
+mod invoke;
+mod journal;
+mod kube;
+mod launch_logs;
+mod launcher;
+mod listing;
+mod logging;
+mod maintenance;
+mod matching;
+mod merge;
+mod notes;
+mod paths;
+mod preview;
+mod projects;
+mod ranking;
+mod recents;
+mod remote;
+mod roots;
+mod saved_search;
+mod schema;
+#[cfg(feature = "fs")]
+mod script;
+mod search;
+#[cfg(feature = "fs")]
+mod search_session;
+mod secrets;
+mod sessions;
+mod settings;
+mod ssh;
+mod stats;
+mod status;
+mod suggest;
+mod tag_colors;
+mod tag_tree;
+mod tasks;
+mod terminal_config;
+mod wire;
+mod workspace;
+mod wsl;
+
+pub use actions::CustomAction;
+pub use auto_tag::{AutoTagReport, TagRule};
+pub use backend::{set_state_backend, FileBackend, MemoryBackend, StateBackend};
+pub use breadcrumbs::Breadcrumb;
+pub use capabilities::{Capabilities, ABI_VERSION, SCHEMA_VERSION};
+pub use command_line::ParsedCommand;
+pub use config::{
+    Config, PathsConfig, PersistenceMode, ProjectsConfig, RecentsConfig, SearchConfig, StatsConfig,
+    StoreConfig, TagsConfig, TerminalConfig,
+};
+pub use deeplink::{DeepLink, UrlOutcome};
+pub use defaults::{DefaultShell, DefaultSource, DefaultTerminal};
+pub use description::ProjectDescription;
+#[cfg(feature = "fs")]
+pub use dir_size::{DirSize, SizeStatus};
+pub use doctor::{CheckStatus, DoctorCheck, DoctorReport};
+#[cfg(feature = "fs")]
+pub use duplicates::{DuplicateCheckout, DuplicateGroup, DuplicateMatch};
+pub use editors::Editor;
+pub use error::{error_code, error_hint, CoreError, ErrorCode};
+pub use events::{EventCallback, EventKind, StoreChange, StoreEvent};
+pub use git::{GitFileStatus, GitState};
+#[cfg(feature = "fs")]
+pub use grep::{GrepMatch, GrepOptions};
+pub use history::SearchHistoryEntry;
+pub use hooks::HookEvent;
+pub use importers::{ImportReport, ImportSource};
+#[cfg(feature = "watch")]
+pub use indexd::IndexdStatus;
+pub use journal::{JournalEntry, JournalOperation};
+pub use kube::{KubeContext, KubeTarget};
+pub use launch_logs::LaunchLog;
+pub use launcher::{
+    InstalledTerminal, LaunchPhase, LaunchReport, LaunchRequest, PhaseResult, PhaseStatus,
+};
+pub use listing::{ListOptions, ListSort};
+pub use logging::{LogCallback, LogConfig, LogFile, LogLevel, LogRotation};
+pub use maintenance::{CompactReport, PruneAction, PruneChange, PruneReport, StoreSection};
+pub use matching::{CaseMatching, FuzzyMatch, MatchOptions, MatcherKind, UnicodeNormalization};
+pub use merge::{ConflictKind, MergeConflict, MergeReport};
+pub use notes::Note;
+pub use paths::SymlinkPolicy;
+pub use preview::{DirectoryPreview, ReadmeExcerpt};
+pub use projects::{KnownProject, ScanReport};
+pub use ranking::{RankBreakdown, RankRule, RankWeights};
+pub use recents::{RecentGroup, RecentGrouping, RecentsSort};
+pub use remote::{ProjectRemote, RemoteProvider};
+pub use roots::VolumeRoot;
+pub use saved_search::SavedSearch;
+pub use search::{SearchOptions, SearchResult};
+#[cfg(feature = "fs")]
+pub use search_session::SearchSession;
+pub use sessions::{LaunchFailure, RestoreReport, Session, SessionEntry};
+pub use ssh::Transport;
+pub use stats::{UsageCount, UsageStats, WeekStats};
+pub use status::{FavoriteEntry, PathStatus, WithStatus};
+pub use suggest::{Relation, Suggestion};
+pub use tag_colors::TagColor;
+pub use tag_tree::TagNode;
+pub use tasks::{
+    ProjectStatus, TaskCallback, TaskLine, TaskOptions, TaskResult, TaskStatus, TaskStream,
+};
+pub use terminal_config::TerminalSettings;
+pub use wire::WireFormat;
+pub use workspace::{ProjectInfo, WorkspaceFile, WorkspaceProfile};
+pub use wsl::{PathFlavor, WslDistro};
+
+static STORE: Lazy<Store> = Lazy::new(|| {
+    // Loading can already warn, e.g. about a quarantined state file.
+    logging::install();
+    match ephemeral() {
+        true => Store::in_memory(),
+        false => Store::initialize().unwrap_or_else(Store::unreadable),
+    }
+});
+
+/// Whether the environment variable `name` is set to anything but empty, `0`, `false`, or
+/// `no`.
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| {
+        !matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "" | "0" | "false" | "no"
+        )
+    })
+}
+
+/// Starts from `TERMINAUT_READONLY`; changed by [`api::set_read_only`].
+static READ_ONLY: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(env_flag("TERMINAUT_READONLY")));
+
+/// `TERMINAUT_EPHEMERAL`, read once: the store starts empty and is never loaded or saved.
+static EPHEMERAL: Lazy<bool> = Lazy::new(|| env_flag("TERMINAUT_EPHEMERAL"));
+
+fn ephemeral() -> bool {
+    *EPHEMERAL
+}
+
+/// In read-only mode mutations apply in memory only: nothing is saved, no hooks run, and
+/// file operations are refused.
+fn read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct PersistedState {
+    /// Bumped on every save; written by [`Store::persist`] from [`Store::generation`].
+    #[serde(default, skip_serializing)]
+    generation: u64,
     #[serde(default)]
     favorites: Vec<String>,
+    /// Where imported favorites came from, by path; see [`ImportSource`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    favorite_sources: BTreeMap<String, String>,
     #[serde(default)]
     recents: Vec<RecentEntry>,
     #[serde(default)]
     tags: Vec<TaggedPath>,
     #[serde(default)]
     profiles: Vec<LaunchProfile>,
+    #[serde(default)]
+    saved_searches: Vec<SavedSearch>,
+    #[serde(default)]
+    search_history: Vec<SearchHistoryEntry>,
+    #[serde(default)]
+    sessions: Vec<Session>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stats: Vec<stats::WeekCounters>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    journal: Vec<JournalEntry>,
+    /// Preferred editor ids by directory; see [`api::set_preferred_editor`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    editors: BTreeMap<String, String>,
+    /// Projects found by [`api::refresh_projects`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    projects: Vec<KnownProject>,
+    /// When each scan root was last walked.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    project_scans: BTreeMap<String, i64>,
+    /// Last [`api::run_task`] result by project root, then task name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    task_results: BTreeMap<String, BTreeMap<String, TaskStatus>>,
+    /// Markdown notes on directories; see [`api::set_note`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<Note>,
+    /// Commands offered per path or tag; see [`api::actions_for`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    actions: Vec<CustomAction>,
+    /// Front-end settings by namespace, then key; see [`api::setting_get`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    settings: BTreeMap<String, BTreeMap<String, String>>,
 }
 
-impl Default for PersistedState {
-    fn default() -> Self {
-        Self {
-            favorites: Vec::new(),
-            recents: Vec::new(),
-            tags: Vec::new(),
-            profiles: Vec::new(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RecentEntry {
     pub path: String,
     pub last_opened_utc: i64,
+    /// Times the directory was opened.
+    #[serde(default = "first_visit")]
+    pub visits: u32,
+    /// Visits decayed with `recents.half_life_days`: stored as of `last_opened_utc`, and as
+    /// of now in listings.
+    #[serde(default = "first_score")]
+    pub score: f64,
+    /// Last open time per source that reported one, such as a terminal, profile, or app name
+    /// passed to [`api::touch_recent_from`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TaggedPath {
     pub path: String,
     pub tag: String,
     pub color: String,
+    /// Set by [`api::tags_for`] on tags of an ancestor directory when `tags.inherit` is on;
+    /// `path` is then the ancestor's.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inherited: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LaunchProfile {
     pub id: Uuid,
     pub name: String,
+    /// The program and its arguments, passed through without a shell; see `use_shell`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Run `args` joined into one line by the user's shell, for pipes, `&&`, and variables.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub use_shell: bool,
+    /// Command line saved before `args`; moved into `args` when the state loads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub command: Option<String>,
+    /// Shell command run to completion in the launch directory before the terminal opens,
+    /// e.g. `git pull`. A failure or timeout cancels the launch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_command: Option<String>,
+    /// Shell command run in the launch directory as soon as the terminal has opened, e.g. to
+    /// start a file watcher. It does not wait for the session to end, so it is no place for
+    /// cleanup. Saved as `post_command` by earlier versions.
+    #[serde(
+        default,
+        alias = "post_command",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub post_launch_command: Option<String>,
+    /// Launched in its `working_dir` when the user logs in; see [`api::set_autostart`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub autostart: bool,
     pub working_dir: Option<String>,
     pub terminal: Option<String>,
     pub windows: u8,
+    /// Open the shell in this WSL distro (empty for the default one); Windows only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+    /// Open the shell on this host (`host`, `user@host`, or a `~/.ssh/config` alias) and run
+    /// the command there; see [`api::set_profile_remote`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_host: Option<String>,
+    /// Directory on `ssh_host` to start in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Transport::is_ssh")]
+    pub transport: Transport,
+    /// Open the shell in this pod with `kubectl exec -it` and run the command there; see
+    /// [`api::set_profile_kube`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_pod: Option<String>,
+    /// Container in `kube_pod`; kubectl picks the pod's default container when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_container: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_namespace: Option<String>,
+    /// kubeconfig context; `None` uses kubectl's current context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube_context: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub path: String,
-    pub name: String,
-    pub score: i64,
+/// Entries saved before visits were counted were opened at least once.
+fn first_visit() -> u32 {
+    1
+}
+
+fn first_score() -> f64 {
+    1.0
 }
 
 impl Default for RecentEntry {
@@ -72,7 +342,43 @@ impl Default for RecentEntry {
         Self {
             path: String::new(),
             last_opened_utc: Utc::now().timestamp(),
+            visits: first_visit(),
+            score: first_score(),
+            sources: BTreeMap::new(),
+        }
+    }
+}
+
+impl LaunchProfile {
+    /// Whether the shell opens on an SSH host or in a pod rather than locally.
+    pub(crate) fn is_remote(&self) -> bool {
+        self.ssh_host.is_some() || self.kube_pod.is_some()
+    }
+
+    /// Moves a legacy `command` line into `args`. Lines that need a shell, or that do not
+    /// parse, are kept whole with `use_shell` so they run exactly as before.
+    fn migrate_command(&mut self) {
+        let Some(command) = self.command.take() else {
+            return;
+        };
+        if !self.args.is_empty() || command.trim().is_empty() {
+            return;
         }
+        let parsed = command_line::parse(&command).unwrap_or_else(|_| ParsedCommand {
+            args: vec![command.trim().to_string()],
+            use_shell: true,
+        });
+        self.args = parsed.args;
+        self.use_shell = parsed.use_shell;
+    }
+}
+
+impl PersistedState {
+    fn migrate(mut self) -> Self {
+        self.profiles
+            .iter_mut()
+            .for_each(LaunchProfile::migrate_command);
+        self
     }
 }
 
@@ -81,49 +387,154 @@ impl Default for LaunchProfile {
         Self {
             id: Uuid::new_v4(),
             name: String::from("Quick Launch"),
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            autostart: false,
             command: None,
             working_dir: None,
             terminal: None,
             windows: 1,
+            wsl_distro: None,
+            ssh_host: None,
+            ssh_dir: None,
+            transport: Transport::Ssh,
+            kube_pod: None,
+            kube_container: None,
+            kube_namespace: None,
+            kube_context: None,
         }
     }
 }
 
+#[derive(Default)]
 struct Store {
-    path: PathBuf,
     inner: Mutex<PersistedState>,
+    /// Why the saved state could not be loaded. While set, the store runs on empty state and
+    /// refuses to overwrite the saved copy so it can be repaired by hand.
+    load_error: Option<String>,
+    /// Never loaded from or saved to the state backend; see [`Store::in_memory`].
+    in_memory: bool,
+    /// Where an unparseable state file was moved at startup, and the parse error.
+    quarantined: Option<(String, String)>,
+    /// Counts changes to the state, including changes saved by other processes once
+    /// [`Store::refresh`] has picked them up.
+    generation: AtomicU64,
 }
 
-impl Default for Store {
-    fn default() -> Self {
-        let path = Store::default_store_path();
-        Self {
-            path,
-            inner: Mutex::new(PersistedState::default()),
-        }
+/// The state as saved, with the generation it was saved at.
+#[derive(Serialize)]
+struct SavedState<'a> {
+    generation: u64,
+    #[serde(flatten)]
+    state: &'a PersistedState,
+}
+
+/// `state` as saved at `generation`, in the format `store.compact` and `store.encrypt` select.
+fn encode(generation: u64, state: &PersistedState) -> anyhow::Result<String> {
+    let (compact, encrypt) = {
+        let config = config::config();
+        (config.store.compact, config.store.encrypt)
+    };
+    let saved = SavedState { generation, state };
+    let json = match compact {
+        true => serde_json::to_string(&saved)?,
+        false => serde_json::to_string_pretty(&saved)?,
+    };
+    match encrypt {
+        true => encryption::seal(&json),
+        false => Ok(json),
     }
 }
 
+/// How the store came up; see [`api::store_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreState {
+    /// The saved state loaded, or there was none yet.
+    Loaded,
+    /// `TERMINAUT_EPHEMERAL` is set; nothing is loaded or saved.
+    Ephemeral,
+    /// The saved state did not parse and was moved aside; the store started empty and saves
+    /// normally.
+    Quarantined,
+    /// The saved state could not be read or decrypted. The store runs on empty state and
+    /// refuses to save until the file is repaired.
+    Unreadable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreStatus {
+    pub state: StoreState,
+    /// Where the state is loaded from and saved to.
+    pub location: String,
+    /// Why the saved state could not be loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the unparseable state file now is, for `quarantined`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantined_to: Option<String>,
+    /// See [`api::store_generation`].
+    pub generation: u64,
+    /// Why the config file didn't load; the defaults are in use until it does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_error: Option<String>,
+}
+
 impl Store {
+    /// Loads the saved state. A document that reads but does not parse is moved aside when
+    /// the backend can keep a copy, and the store starts empty; read and decryption failures
+    /// are returned, since restoring access or the key fixes them.
     fn initialize() -> anyhow::Result<Self> {
-        let path = Store::default_store_path();
-        if path.is_file() {
-            let contents = std::fs::read_to_string(&path)
-                .with_context(|| format!("failed to read state file at {}", path.display()))?;
-            let state: PersistedState = serde_json::from_str(&contents)
-                .with_context(|| format!("failed to parse state file at {}", path.display()))?;
-            Ok(Self {
-                path,
-                inner: Mutex::new(state),
-            })
-        } else {
-            if let Some(parent) = path.parent() {
-                std::fs::create_dir_all(parent)?;
+        let backend = backend::state_backend();
+        let Some(contents) = backend.load()? else {
+            return Ok(Self::default());
+        };
+        let err = match serde_json::from_str(&encryption::open(&contents)?) {
+            Ok(state) => {
+                let state = PersistedState::migrate(state);
+                return Ok(Self {
+                    generation: AtomicU64::new(state.generation),
+                    inner: Mutex::new(state),
+                    ..Self::default()
+                });
             }
-            Ok(Self {
-                path,
-                inner: Mutex::new(PersistedState::default()),
-            })
+            Err(err) => anyhow::Error::new(err).context(format!(
+                "failed to parse state file at {}",
+                backend.location()
+            )),
+        };
+        match backend.quarantine() {
+            Ok(Some(moved_to)) => {
+                tracing::error!("{err:#}; moved it to {moved_to} and started with empty state");
+                Ok(Self {
+                    quarantined: Some((moved_to, format!("{err:#}"))),
+                    ..Self::default()
+                })
+            }
+            Ok(None) => Err(err),
+            Err(move_err) => {
+                tracing::warn!("{move_err:#}");
+                Err(err)
+            }
+        }
+    }
+
+    /// Empty state that lives only as long as the process. Every api call works against it as
+    /// usual, but the state backend is never read or written.
+    fn in_memory() -> Self {
+        Self {
+            in_memory: true,
+            ..Self::default()
+        }
+    }
+
+    fn unreadable(err: anyhow::Error) -> Self {
+        tracing::error!("state unreadable, running on empty state: {err:#}");
+        Self {
+            load_error: Some(format!("{err:#}")),
+            ..Self::default()
         }
     }
 
@@ -134,18 +545,184 @@ impl Store {
         dir
     }
 
-    fn persist(&self) -> anyhow::Result<()> {
-        let inner = self.inner.lock();
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent)?;
+    fn load_failure(&self) -> Option<CoreError> {
+        let reason = self.load_error.as_ref()?;
+        Some(
+            CoreError::new(ErrorCode::StoreCorrupt, reason.clone()).with_hint(format!(
+                "fix or remove {} and try again; changes are not saved until then",
+                backend::state_backend().location()
+            )),
+        )
+    }
+
+    fn status(&self) -> StoreStatus {
+        let (state, error, quarantined_to) = if self.in_memory {
+            (StoreState::Ephemeral, None, None)
+        } else if let Some(reason) = &self.load_error {
+            (StoreState::Unreadable, Some(reason.clone()), None)
+        } else if let Some((moved_to, reason)) = &self.quarantined {
+            (
+                StoreState::Quarantined,
+                Some(reason.clone()),
+                Some(moved_to.clone()),
+            )
+        } else {
+            (StoreState::Loaded, None, None)
+        };
+        StoreStatus {
+            state,
+            location: backend::state_backend().location(),
+            error,
+            quarantined_to,
+            generation: self.generation(),
+            config_error: config::load_error(),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Takes over the saved state when another process saved a newer generation since this
+    /// one loaded or last saved.
+    fn refresh(&self) -> anyhow::Result<()> {
+        if self.in_memory || self.load_error.is_some() {
+            return Ok(());
+        }
+        self.reload(backend::state_backend())
+    }
+
+    fn reload(&self, backend: &dyn StateBackend) -> anyhow::Result<()> {
+        let Some(saved) = Self::load_saved(backend)? else {
+            return Ok(());
+        };
+        let mut state = self.inner.lock();
+        if saved.generation <= self.generation() {
+            return Ok(());
+        }
+        self.generation.store(saved.generation, Ordering::SeqCst);
+        *state = saved.migrate();
+        drop(state);
+        events::publish_reset(&EventKind::ALL);
+        Ok(())
+    }
+
+    fn load_saved(backend: &dyn StateBackend) -> anyhow::Result<Option<PersistedState>> {
+        let Some(contents) = backend.load()? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&encryption::open(&contents)?)
+            .map(Some)
+            .with_context(|| format!("failed to parse state file at {}", backend.location()))
+    }
+
+    /// Saves `state` through the state backend. Callers pass the guard they already hold so
+    /// mutations and the write happen under a single lock acquisition.
+    ///
+    /// Fails with [`ErrorCode::Conflict`] when the current call expected an older generation
+    /// (see [`ExpectedGeneration`]) or another process saved since this one last loaded or
+    /// saved. `state` is then put back to the saved document, dropping the caller's change, so
+    /// a retry starts from what is on disk; a store without one keeps the change. The
+    /// generation only moves when the state is saved, or on every change when the store lives
+    /// in memory only.
+    fn persist(&self, state: &mut PersistedState) -> anyhow::Result<()> {
+        if read_only() {
+            return Ok(());
+        }
+        if self.in_memory || config::config().store.persistence == PersistenceMode::Memory {
+            let current = self.generation();
+            check_expected(current)?;
+            self.saved(current + 1);
+            return Ok(());
+        }
+        if let Some(err) = self.load_failure() {
+            return Err(err.into());
+        }
+        self.save(backend::state_backend(), state)
+    }
+
+    fn save(&self, backend: &dyn StateBackend, state: &mut PersistedState) -> anyhow::Result<()> {
+        let _lock = backend.lock()?;
+        let current = self.generation();
+        let saved = Self::load_saved(backend)?;
+        let conflict = match &saved {
+            Some(saved) if saved.generation > current => Err(CoreError::conflict(format!(
+                "another process saved the state (generation {}) since this one loaded \
+                 generation {current}",
+                saved.generation
+            ))
+            .with_hint("the saved state has been reloaded; try again")
+            .into()),
+            _ => check_expected(current),
+        };
+        if let Err(err) = conflict {
+            if let Some(saved) = saved {
+                self.generation
+                    .store(saved.generation.max(current), Ordering::SeqCst);
+                *state = saved.migrate();
+                // Subscribers run on the publishing thread and would block on the store lock
+                // the caller holds, so the reset goes out from another thread.
+                if let Err(spawn_err) =
+                    std::thread::Builder::new().spawn(|| events::publish_reset(&EventKind::ALL))
+                {
+                    tracing::warn!("failed to announce the reloaded state: {spawn_err}");
+                }
+            }
+            return Err(err);
         }
-        let json = serde_json::to_string_pretty(&*inner)?;
-        std::fs::write(&self.path, json)?;
+        let generation = current + 1;
+        backend.save(&encode(generation, state)?)?;
+        self.saved(generation);
         Ok(())
     }
+
+    /// Moves the counter to a generation that has just been saved, and the current call's
+    /// expectation along with it so the call can save again.
+    fn saved(&self, generation: u64) {
+        self.generation.store(generation, Ordering::SeqCst);
+        EXPECTED.with(|expected| {
+            if expected.get().is_some() {
+                expected.set(Some(generation));
+            }
+        });
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+thread_local! {
+    /// The generation the call running on this thread was made against; see
+    /// [`ExpectedGeneration`].
+    static EXPECTED: std::cell::Cell<Option<u64>> = const { std::cell::Cell::new(None) };
+}
+
+/// While alive, every save on this thread fails with a conflict unless the store is still
+/// at the expected generation. The check runs under the store lock in [`Store::persist`], so
+/// it holds against other threads, typed calls, and other processes alike.
+pub(crate) struct ExpectedGeneration(Option<u64>);
+
+impl ExpectedGeneration {
+    pub(crate) fn new(generation: u64) -> Self {
+        Self(EXPECTED.with(|expected| expected.replace(Some(generation))))
+    }
+}
+
+impl Drop for ExpectedGeneration {
+    fn drop(&mut self) {
+        EXPECTED.with(|expected| expected.set(self.0));
+    }
+}
+
+fn check_expected(current: u64) -> anyhow::Result<()> {
+    match EXPECTED.with(std::cell::Cell::get) {
+        Some(expected) if expected != current => Err(CoreError::conflict(format!(
+            "the state is at generation {current}, not {expected}"
+        ))
+        .with_hint("reload what you are showing and try again")
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DirectoryEntry {
     pub name: String,
     pub path: String,
@@ -153,6 +730,9 @@ pub struct DirectoryEntry {
     /// Modification time as Unix timestamp (seconds since epoch), if available.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mod_date: Option<i64>,
+    /// State in `git status`, filled in by [`api::list_directory_with_git`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<GitFileStatus>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -162,27 +742,76 @@ pub struct ProjectRoot {
 }
 
 fn normalize_path(input: &str) -> anyhow::Result<PathBuf> {
+    let policy = config::config().paths.symlinks;
+    normalize_path_with(input, policy)
+}
+
+fn resolve(path: PathBuf, symlinks: SymlinkPolicy) -> PathBuf {
+    let resolved = match symlinks {
+        SymlinkPolicy::Resolve => std::fs::canonicalize(&path).unwrap_or(path),
+        SymlinkPolicy::Preserve => paths::absolute_preserving(&path),
+    };
+    paths::for_display(resolved)
+}
+
+fn normalize_path_with(input: &str, symlinks: SymlinkPolicy) -> anyhow::Result<PathBuf> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
-        anyhow::bail!("empty path");
+        anyhow::bail!(CoreError::invalid_input("empty path"));
     }
-    let expanded = if trimmed.starts_with('~') {
-        if let Some(home) = dirs::home_dir() {
-            home.join(trimmed.trim_start_matches('~'))
-        } else {
-            PathBuf::from(trimmed)
-        }
-    } else {
-        PathBuf::from(trimmed)
-    };
+    if let Some(path) = paths::file_uri_path(trimmed)? {
+        return Ok(resolve(PathBuf::from(path), symlinks));
+    }
+    let home = dirs::home_dir();
+    let literal = paths::expand_home(trimmed, home.as_deref());
+    // `$` and `%` are legal in names (`D:\$RECYCLE.BIN`, `dir%foo%`), so variables are only
+    // expanded when the path as written does not exist.
+    if literal.exists() {
+        return Ok(resolve(literal, symlinks));
+    }
+    let expanded = paths::expand_env(trimmed, |name| {
+        std::env::var(name).ok().or_else(|| {
+            // Let `$HOME` and `%USERPROFILE%` work on either platform.
+            matches!(name.to_ascii_uppercase().as_str(), "HOME" | "USERPROFILE")
+                .then(|| home.as_ref().map(|home| home.display().to_string()))
+                .flatten()
+        })
+    })?;
+    let expanded = paths::decode_if_missing(paths::expand_home(&expanded, home.as_deref()));
+    Ok(resolve(expanded, symlinks))
+}
 
-    let canonical = std::fs::canonicalize(&expanded).unwrap_or(expanded);
-    Ok(canonical)
+fn display_path(path: &str, relative_to: Option<&str>) -> anyhow::Result<String> {
+    let path = normalize_path(path)?;
+    let root = relative_to.map(normalize_path).transpose()?;
+    // Match both spellings of a symlinked home, e.g. `/home/me` and `/data/home/me`.
+    let mut homes: Vec<PathBuf> = dirs::home_dir().into_iter().collect();
+    if let Some(real) = homes
+        .first()
+        .and_then(|home| std::fs::canonicalize(home).ok())
+    {
+        let real = paths::for_display(real);
+        if !homes.contains(&real) {
+            homes.push(real);
+        }
+    }
+    Ok(paths::display_path(&path, &homes, root.as_deref()))
 }
 
 fn list_directory(path: &Path) -> anyhow::Result<Vec<DirectoryEntry>> {
+    let mut entries: Vec<_> = directory_entries(path)?.collect();
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    Ok(entries)
+}
+
+/// The entries of `path` as the directory yields them, unsorted, each read only when asked
+/// for. Archives are read whole first.
+fn directory_entries(path: &Path) -> anyhow::Result<Box<dyn Iterator<Item = DirectoryEntry>>> {
     use std::time::UNIX_EPOCH;
-    let mut entries: Vec<_> = std::fs::read_dir(path)?
+    if !path.is_dir() && archive::split_archive_path(path).is_some() {
+        return Ok(Box::new(archive::list_archive(path)?.into_iter()));
+    }
+    let entries = std::fs::read_dir(path)?
         .filter_map(|res| res.ok())
         .filter_map(|entry| {
             let file_type = entry.file_type().ok()?;
@@ -198,39 +827,36 @@ fn list_directory(path: &Path) -> anyhow::Result<Vec<DirectoryEntry>> {
                 path: entry.path().display().to_string(),
                 is_dir: file_type.is_dir(),
                 mod_date,
+                git_status: None,
             })
-        })
-        .collect();
-    entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(entries)
+        });
+    Ok(Box::new(entries))
+}
+
+/// Returns the first configured project marker present directly inside `dir`.
+fn project_marker(dir: &Path) -> Option<String> {
+    config::config()
+        .projects
+        .markers
+        .iter()
+        .find(|marker| dir.join(marker).exists())
+        .cloned()
 }
 
 fn detect_projects(path: &Path) -> Vec<ProjectRoot> {
-    const MARKERS: [&str; 5] = [
-        ".git",
-        "package.json",
-        "Cargo.toml",
-        "go.mod",
-        "bunfig.toml",
-    ];
-    let mut results = Vec::new();
-    for ancestor in path.ancestors() {
-        for marker in &MARKERS {
-            if ancestor.join(marker).exists() {
-                results.push(ProjectRoot {
-                    path: ancestor.display().to_string(),
-                    marker: marker.to_string(),
-                });
-                break;
-            }
-        }
-    }
-    results
+    path.ancestors()
+        .filter_map(|ancestor| {
+            project_marker(ancestor).map(|marker| ProjectRoot {
+                path: ancestor.display().to_string(),
+                marker,
+            })
+        })
+        .collect()
 }
 
 fn list_recent_directories() -> Vec<RecentEntry> {
     let mut state = STORE.inner.lock().recents.clone();
-    state.sort_by(|a, b| b.last_opened_utc.cmp(&a.last_opened_utc));
+    state.sort_by_key(|entry| Reverse(entry.last_opened_utc));
     state
 }
 
@@ -248,63 +874,369 @@ fn add_favorite(path: &str) -> anyhow::Result<()> {
         .iter()
         .any(|p| p == normalized.to_string_lossy().as_ref())
     {
-        store.favorites.push(normalized.display().to_string());
-        STORE.persist().ok();
+        let path = normalized.display().to_string();
+        store.favorites.push(path.clone());
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish(StoreEvent::new(
+            EventKind::Favorite,
+            StoreChange::Added,
+            &path,
+        ));
+        hooks::fire(HookEvent::FavoriteAdded, &[("path", &path)]);
     }
     Ok(())
 }
 
 fn remove_favorite(path: &str) -> anyhow::Result<()> {
-    let normalized = normalize_path(path)?;
-    let normalized = normalized.display().to_string();
+    // A favorite whose directory is gone can hold a `$` or `%` that no longer expands; it is
+    // removed by its stored spelling.
+    let normalized = match normalize_path(path) {
+        Ok(normalized) => normalized.display().to_string(),
+        Err(err) => match STORE
+            .inner
+            .lock()
+            .favorites
+            .iter()
+            .find(|p| *p == path.trim())
+        {
+            Some(stored) => stored.clone(),
+            None => return Err(err),
+        },
+    };
     let mut store = STORE.inner.lock();
-    store.favorites.retain(|p| p != &normalized);
-    STORE.persist().ok();
+    if let Some(index) = store.favorites.iter().position(|p| p == &normalized) {
+        store.favorites.retain(|p| p != &normalized);
+        let source = store.favorite_sources.remove(&normalized);
+        journal::record(
+            &mut store,
+            JournalOperation::RemoveFavorite {
+                path: normalized.clone(),
+                index,
+                source,
+            },
+        );
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish(StoreEvent::new(
+            EventKind::Favorite,
+            StoreChange::Removed,
+            &normalized,
+        ));
+        hooks::fire(HookEvent::FavoriteRemoved, &[("path", &normalized)]);
+    }
     Ok(())
 }
 
-fn touch_recent(path: &str) -> anyhow::Result<()> {
+/// Recents touched by `source` (matched case-insensitively), newest first by when that source
+/// last opened them.
+fn list_recents_from(source: &str) -> Vec<RecentEntry> {
+    let source = source.trim().to_lowercase();
+    let mut recents: Vec<RecentEntry> = STORE
+        .inner
+        .lock()
+        .recents
+        .iter()
+        .filter_map(|entry| {
+            let opened = *entry.sources.get(&source)?;
+            Some(RecentEntry {
+                last_opened_utc: opened,
+                ..entry.clone()
+            })
+        })
+        .collect();
+    recents.sort_by_key(|entry| Reverse(entry.last_opened_utc));
+    recents
+}
+
+fn touch_recent(path: &str, source: Option<&str>) -> anyhow::Result<()> {
     let normalized = normalize_path(path)?;
     let normalized = normalized.display().to_string();
+    let now = Utc::now().timestamp();
     let mut store = STORE.inner.lock();
-    store.recents.retain(|entry| entry.path != normalized);
-    store.recents.push(RecentEntry {
-        path: normalized,
-        last_opened_utc: Utc::now().timestamp(),
-    });
-    if store.recents.len() > 100 {
+    let (mut entry, change) = match store
+        .recents
+        .iter()
+        .position(|entry| entry.path == normalized)
+    {
+        Some(index) => {
+            let mut entry = store.recents.remove(index);
+            entry.visit(now);
+            (entry, StoreChange::Updated)
+        }
+        None => (
+            RecentEntry {
+                path: normalized.clone(),
+                last_opened_utc: now,
+                ..RecentEntry::default()
+            },
+            StoreChange::Added,
+        ),
+    };
+    if let Some(source) = source.map(str::trim).filter(|source| !source.is_empty()) {
+        entry.sources.insert(source.to_lowercase(), now);
+    }
+    store.recents.retain(|existing| existing.path != normalized);
+    store.recents.push(entry);
+    let cap = config::config().recents.cap;
+    if store.recents.len() > cap {
         store
             .recents
-            .sort_by(|a, b| b.last_opened_utc.cmp(&a.last_opened_utc));
-        store.recents.truncate(100);
+            .sort_by_key(|entry| Reverse(entry.last_opened_utc));
+        store.recents.truncate(cap);
     }
-    STORE.persist().ok();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(EventKind::Recent, change, &normalized));
+    hooks::fire(HookEvent::RecentTouched, &[("path", &normalized)]);
+    stats::record(stats::Usage::DirOpened(&normalized));
     Ok(())
 }
 
+#[cfg(feature = "fs")]
+/// Maps `path` from below `from` to the same position below `to`, if it lies under `from`.
+fn relocated(path: &str, from: &Path, to: &Path) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(from).ok()?;
+    let target = if rest.as_os_str().is_empty() {
+        to.to_path_buf()
+    } else {
+        to.join(rest)
+    };
+    Some(target.display().to_string())
+}
+
+#[cfg(feature = "fs")]
+/// Rewrites stored paths after `from` moved to `to`, saving and announcing the state only when
+/// something was under `from`.
+fn relocate_stored_paths(from: &Path, to: &Path) {
+    let mut store = STORE.inner.lock();
+    if !relocate_state(&mut store, from, to) {
+        return;
+    }
+    if let Err(err) = STORE.persist(&mut store) {
+        tracing::warn!("failed to save relocated paths: {err:#}");
+    }
+    drop(store);
+    events::publish_reset(&EventKind::ALL);
+}
+
+#[cfg(feature = "fs")]
+/// Rewrites favorites, recents, tags, notes, actions, profile working dirs, session entries,
+/// registered projects, preferred editors, and task results after `from` moved to `to`.
+/// Returns whether any of them moved.
+fn relocate_state(state: &mut PersistedState, from: &Path, to: &Path) -> bool {
+    let mut moved_any = false;
+    let mut relocate = |path: &mut String| {
+        if let Some(moved) = relocated(path, from, to) {
+            *path = moved;
+            moved_any = true;
+        }
+    };
+    state.favorites.iter_mut().for_each(&mut relocate);
+    for entry in state.recents.iter_mut() {
+        relocate(&mut entry.path);
+    }
+    for tag in state.tags.iter_mut() {
+        relocate(&mut tag.path);
+    }
+    for note in state.notes.iter_mut() {
+        relocate(&mut note.path);
+    }
+    for action in state.actions.iter_mut() {
+        action.path.iter_mut().for_each(&mut relocate);
+    }
+    for profile in state.profiles.iter_mut() {
+        profile.working_dir.iter_mut().for_each(&mut relocate);
+    }
+    for entry in state
+        .sessions
+        .iter_mut()
+        .flat_map(|session| session.entries.iter_mut())
+    {
+        relocate(&mut entry.path);
+    }
+    for project in state.projects.iter_mut() {
+        relocate(&mut project.path);
+    }
+    fn rekey<V>(map: &mut BTreeMap<String, V>, from: &Path, to: &Path) -> bool {
+        let mut moved = false;
+        *map = std::mem::take(map)
+            .into_iter()
+            .map(|(path, value)| match relocated(&path, from, to) {
+                Some(path) => {
+                    moved = true;
+                    (path, value)
+                }
+                None => (path, value),
+            })
+            .collect();
+        moved
+    }
+    moved_any |= rekey(&mut state.favorite_sources, from, to);
+    moved_any |= rekey(&mut state.editors, from, to);
+    moved_any |= rekey(&mut state.task_results, from, to);
+    if moved_any {
+        dedupe_state(state);
+    }
+    moved_any
+}
+
+/// Collapses entries that ended up pointing at the same path after a rewrite, keeping the
+/// first favorite (where it was in the user's order), the most recent recent and note, and
+/// the first tag per (path, tag) pair. Import sources of paths no longer favorites go too.
+fn dedupe_state(state: &mut PersistedState) {
+    let mut seen = HashSet::new();
+    state.favorites.retain(|path| seen.insert(path.clone()));
+    state.favorite_sources.retain(|path, _| seen.contains(path));
+
+    state
+        .recents
+        .sort_by_key(|entry| Reverse(entry.last_opened_utc));
+    let mut seen = HashSet::new();
+    state
+        .recents
+        .retain(|entry| seen.insert(entry.path.clone()));
+
+    let mut seen = HashSet::new();
+    state
+        .tags
+        .retain(|tag| seen.insert((tag.path.clone(), tag.tag.to_lowercase())));
+
+    state.notes.sort_by_key(|note| Reverse(note.updated_utc));
+    let mut seen = HashSet::new();
+    state.notes.retain(|note| seen.insert(note.path.clone()));
+}
+
+#[cfg(feature = "fs")]
+/// Drops favorites, recents, tags, notes, path actions, and registered projects at or below a
+/// path that no longer exists.
+fn forget_stored_paths(root: &Path) {
+    let mut store = STORE.inner.lock();
+    let under = |path: &str| Path::new(path).starts_with(root);
+    store.favorites.retain(|path| !under(path));
+    store.favorite_sources.retain(|path, _| !under(path));
+    store.recents.retain(|entry| !under(&entry.path));
+    store.tags.retain(|tag| !under(&tag.path));
+    store.notes.retain(|note| !under(&note.path));
+    store
+        .actions
+        .retain(|action| !action.path.as_deref().is_some_and(under));
+    store.projects.retain(|project| !under(&project.path));
+    if let Err(err) = STORE.persist(&mut store) {
+        tracing::warn!("failed to save forgotten paths: {err:#}");
+    }
+    drop(store);
+    events::publish_reset(&[EventKind::Favorite, EventKind::Recent, EventKind::Tag]);
+}
+
+/// What [`prune_state`] would change, worked out on a copy of the state.
+fn preview_prune() -> PruneReport {
+    let mut state = STORE.inner.lock().clone();
+    maintenance::prune(&mut state)
+}
+
+fn prune_state() -> anyhow::Result<PruneReport> {
+    let mut store = STORE.inner.lock();
+    let before = store.clone();
+    let report = maintenance::prune(&mut store);
+    if !report.changes.is_empty() {
+        dedupe_state(&mut store);
+        let operation = journal::prune_operation(&before, &report);
+        journal::record(&mut store, operation);
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish_reset(&[EventKind::Favorite, EventKind::Recent, EventKind::Tag]);
+    }
+    Ok(report)
+}
+
+/// Size of the document the state backend holds now.
+fn saved_len() -> anyhow::Result<usize> {
+    Ok(backend::state_backend()
+        .load()?
+        .map_or(0, |json| json.len()))
+}
+
+fn compact_state(drop_missing: bool, dry_run: bool) -> anyhow::Result<CompactReport> {
+    let cap = config::config().recents.cap;
+    let mut report = CompactReport {
+        dry_run,
+        bytes_before: saved_len()?,
+        ..CompactReport::default()
+    };
+    let mut guard = STORE.inner.lock();
+    // A dry run compacts a copy and measures it as it would be saved.
+    let mut copy = dry_run.then(|| guard.clone());
+    let store = copy.as_mut().unwrap_or(&mut *guard);
+    if drop_missing {
+        let before = store.clone();
+        let pruned = maintenance::prune(store);
+        if !pruned.changes.is_empty() {
+            let operation = journal::prune_operation(&before, &pruned);
+            journal::record(store, operation);
+        }
+        report.pruned = Some(pruned);
+    }
+    let entries =
+        |state: &PersistedState| state.favorites.len() + state.recents.len() + state.tags.len();
+    let count = entries(store);
+    dedupe_state(store);
+    report.duplicates = count - entries(store);
+    // Deduplication left the recents newest first.
+    report.trimmed = store.recents.len().saturating_sub(cap);
+    store.recents.truncate(cap);
+    if dry_run {
+        report.bytes_after = encode(STORE.generation() + 1, store)?.len();
+        return Ok(report);
+    }
+    STORE.persist(&mut guard)?;
+    drop(guard);
+    events::publish_reset(&[EventKind::Favorite, EventKind::Recent, EventKind::Tag]);
+    report.bytes_after = saved_len()?;
+    Ok(report)
+}
+
 fn list_tags() -> Vec<TaggedPath> {
     STORE.inner.lock().tags.clone()
 }
 
+/// Color of tags added without one.
+const DEFAULT_TAG_COLOR: &str = "#0a84ff";
+
 fn set_tag(path: &str, tag: &str, color: Option<&str>) -> anyhow::Result<()> {
+    tag_tree::validate_tag(tag)?;
+    let color = tag_colors::resolve_color(color.unwrap_or(DEFAULT_TAG_COLOR))?;
     let normalized = normalize_path(path)?;
     let normalized = normalized.display().to_string();
     let mut store = STORE.inner.lock();
-    let color = color.unwrap_or("#0a84ff").to_string();
     if let Some(existing) = store
         .tags
         .iter_mut()
         .find(|entry| entry.path == normalized && entry.tag.eq_ignore_ascii_case(tag))
     {
         existing.color = color;
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish(
+            StoreEvent::new(EventKind::Tag, StoreChange::Updated, &normalized).with_tag(tag),
+        );
     } else {
         store.tags.push(TaggedPath {
-            path: normalized,
+            path: normalized.clone(),
             tag: tag.to_string(),
-            color,
+            color: color.clone(),
+            inherited: false,
         });
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish(
+            StoreEvent::new(EventKind::Tag, StoreChange::Added, &normalized).with_tag(tag),
+        );
+        hooks::fire(
+            HookEvent::TagAdded,
+            &[("path", &normalized), ("tag", tag), ("color", &color)],
+        );
     }
-    STORE.persist().ok();
     Ok(())
 }
 
@@ -312,29 +1244,38 @@ fn remove_tag(path: &str, tag: &str) -> anyhow::Result<()> {
     let normalized = normalize_path(path)?;
     let normalized = normalized.display().to_string();
     let mut store = STORE.inner.lock();
-    store
-        .tags
-        .retain(|entry| !(entry.path == normalized && entry.tag.eq_ignore_ascii_case(tag)));
-    STORE.persist().ok();
+    let (removed, kept) = std::mem::take(&mut store.tags)
+        .into_iter()
+        .partition(|entry| entry.path == normalized && entry.tag.eq_ignore_ascii_case(tag));
+    store.tags = kept;
+    if !removed.is_empty() {
+        journal::record(&mut store, JournalOperation::RemoveTag { tags: removed });
+        STORE.persist(&mut store)?;
+        drop(store);
+        events::publish(
+            StoreEvent::new(EventKind::Tag, StoreChange::Removed, &normalized).with_tag(tag),
+        );
+        hooks::fire(
+            HookEvent::TagRemoved,
+            &[("path", &normalized), ("tag", tag)],
+        );
+    }
     Ok(())
 }
 
 fn tags_for_path(path: &str) -> anyhow::Result<Vec<TaggedPath>> {
     let normalized = normalize_path(path)?;
-    let normalized = normalized.display().to_string();
-    Ok(STORE
-        .inner
-        .lock()
-        .tags
-        .iter()
-        .filter(|entry| entry.path == normalized)
-        .cloned()
-        .collect())
+    let inherit = config::config().tags.inherit;
+    Ok(tag_tree::tags_for(
+        &STORE.inner.lock().tags,
+        &normalized,
+        inherit,
+    ))
 }
 
 fn list_profiles() -> Vec<LaunchProfile> {
     let mut profiles = STORE.inner.lock().profiles.clone();
-    profiles.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    profiles.sort_by_key(|profile| profile.name.to_lowercase());
     profiles
 }
 
@@ -345,95 +1286,479 @@ fn save_profile(
     working_dir: Option<String>,
     terminal: Option<String>,
     windows: Option<u8>,
+    wsl_distro: Option<String>,
 ) -> anyhow::Result<LaunchProfile> {
     if name.trim().is_empty() {
-        anyhow::bail!("profile name required");
+        anyhow::bail!(CoreError::invalid_input("profile name required"));
     }
     let mut store = STORE.inner.lock();
     let profile_id = id.unwrap_or_else(Uuid::new_v4);
-    let profile = LaunchProfile {
+    let mut profile = LaunchProfile {
         id: profile_id,
         name: name.trim().to_string(),
-        command,
+        args: Vec::new(),
+        use_shell: false,
+        command: None,
+        pre_command: None,
+        post_launch_command: None,
+        autostart: false,
         working_dir,
         terminal,
         windows: windows.unwrap_or(1).clamp(1, 10),
+        wsl_distro,
+        ssh_host: None,
+        ssh_dir: None,
+        transport: Transport::Ssh,
+        kube_pod: None,
+        kube_container: None,
+        kube_namespace: None,
+        kube_context: None,
     };
+    if let Some(command) = command.filter(|command| !command.trim().is_empty()) {
+        let parsed = command_line::parse(&command)?;
+        profile.args = parsed.args;
+        profile.use_shell = parsed.use_shell;
+    }
 
-    if let Some(existing) = store.profiles.iter_mut().find(|p| p.id == profile_id) {
+    let change = if let Some(existing) = store.profiles.iter_mut().find(|p| p.id == profile_id) {
+        profile.pre_command = existing.pre_command.take();
+        profile.post_launch_command = existing.post_launch_command.take();
+        profile.autostart = existing.autostart;
+        profile.ssh_host = existing.ssh_host.take();
+        profile.ssh_dir = existing.ssh_dir.take();
+        profile.transport = existing.transport;
+        profile.kube_pod = existing.kube_pod.take();
+        profile.kube_container = existing.kube_container.take();
+        profile.kube_namespace = existing.kube_namespace.take();
+        profile.kube_context = existing.kube_context.take();
         *existing = profile.clone();
+        StoreChange::Updated
     } else {
         store.profiles.push(profile.clone());
+        StoreChange::Added
+    };
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        change,
+        profile_id.to_string(),
+    ));
+    Ok(profile)
+}
+
+fn set_profile_args(id: Uuid, args: Vec<String>, use_shell: bool) -> anyhow::Result<LaunchProfile> {
+    if !args.is_empty() {
+        command_line::validate(&args)?;
     }
-    STORE.persist().ok();
+    let mut store = STORE.inner.lock();
+    let Some(profile) = store.profiles.iter_mut().find(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    profile.args = args;
+    profile.use_shell = use_shell && !profile.args.is_empty();
+    profile.command = None;
+    let profile = profile.clone();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Updated,
+        id.to_string(),
+    ));
     Ok(profile)
 }
 
-fn delete_profile(id: Uuid) -> anyhow::Result<()> {
+fn set_profile_hooks(
+    id: Uuid,
+    pre_command: Option<String>,
+    post_launch_command: Option<String>,
+) -> anyhow::Result<LaunchProfile> {
+    let blank_to_none =
+        |command: Option<String>| command.filter(|command| !command.trim().is_empty());
     let mut store = STORE.inner.lock();
-    let before = store.profiles.len();
-    store.profiles.retain(|profile| profile.id != id);
-    if before == store.profiles.len() {
-        anyhow::bail!("profile not found");
-    }
-    STORE.persist().ok();
-    Ok(())
+    let Some(profile) = store.profiles.iter_mut().find(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    profile.pre_command = blank_to_none(pre_command);
+    profile.post_launch_command = blank_to_none(post_launch_command);
+    let profile = profile.clone();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Updated,
+        id.to_string(),
+    ));
+    Ok(profile)
 }
 
-fn search_directories(path: &str, query: &str, limit: usize) -> anyhow::Result<Vec<SearchResult>> {
-    if query.trim().is_empty() {
-        anyhow::bail!("query required");
+fn set_profile_remote(
+    id: Uuid,
+    host: Option<String>,
+    dir: Option<String>,
+    transport: Transport,
+) -> anyhow::Result<LaunchProfile> {
+    let host = host
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty());
+    if let Some(host) = &host {
+        ssh::validate_host(host)?;
     }
-    let normalized = normalize_path(path)?;
-    let matcher = SkimMatcherV2::default();
-    let walker = WalkBuilder::new(&normalized)
-        .max_depth(Some(5))
-        .standard_filters(true)
-        .build();
-
-    let mut results = Vec::new();
-    for entry in walker.flatten() {
-        if results.len() >= limit.saturating_mul(2) {
-            break;
-        }
-        let md = match entry.metadata() {
-            Ok(md) => md,
-            Err(_) => continue,
-        };
-        if !md.is_dir() {
-            continue;
-        }
-        let name = match entry.file_name().to_str() {
-            Some(name) => name,
-            None => continue,
-        };
-        if let Some(score) = matcher.fuzzy_match(name, query) {
-            results.push(SearchResult {
-                path: entry.path().display().to_string(),
-                name: name.to_string(),
-                score,
-            });
-        }
+    let mut store = STORE.inner.lock();
+    let Some(profile) = store.profiles.iter_mut().find(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    if host.is_some() && profile.kube_pod.is_some() {
+        anyhow::bail!(
+            CoreError::conflict(format!("{} targets a pod", profile.name))
+                .with_hint("remove the pod target first")
+        );
     }
-
-    results.sort_by(|a, b| b.score.cmp(&a.score).then(a.name.cmp(&b.name)));
-    results.truncate(limit.max(1));
-    Ok(results)
-}
+    profile.ssh_dir = dir.filter(|dir| host.is_some() && !dir.trim().is_empty());
+    profile.transport = if host.is_some() {
+        transport
+    } else {
+        Transport::Ssh
+    };
+    profile.ssh_host = host;
+    let profile = profile.clone();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Updated,
+        id.to_string(),
+    ));
+    Ok(profile)
+}
+
+fn set_profile_kube(id: Uuid, target: KubeTarget) -> anyhow::Result<LaunchProfile> {
+    let name = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let pod = name(target.pod);
+    let (container, namespace, context) = match pod {
+        Some(_) => (
+            name(target.container),
+            name(target.namespace),
+            name(target.context),
+        ),
+        None => (None, None, None),
+    };
+    for (kind, value) in [
+        ("pod", &pod),
+        ("container", &container),
+        ("namespace", &namespace),
+        ("context", &context),
+    ] {
+        if let Some(value) = value {
+            kube::validate_name(kind, value)?;
+        }
+    }
+    let mut store = STORE.inner.lock();
+    let Some(profile) = store.profiles.iter_mut().find(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    if pod.is_some() && profile.ssh_host.is_some() {
+        anyhow::bail!(
+            CoreError::conflict(format!("{} targets an SSH host", profile.name))
+                .with_hint("remove the SSH host first")
+        );
+    }
+    profile.kube_pod = pod;
+    profile.kube_container = container;
+    profile.kube_namespace = namespace;
+    profile.kube_context = context;
+    let profile = profile.clone();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Updated,
+        id.to_string(),
+    ));
+    Ok(profile)
+}
+
+fn launch_profile(id: Uuid, path: Option<&str>, background: bool) -> anyhow::Result<LaunchReport> {
+    let profile = STORE
+        .inner
+        .lock()
+        .profiles
+        .iter()
+        .find(|profile| profile.id == id)
+        .cloned()
+        .ok_or_else(|| CoreError::not_found("profile not found"))?;
+    // A remote profile's shell opens on the host or in the pod, so any local directory will do.
+    let home = || dirs::home_dir().map(|home| home.display().to_string());
+    let Some(path) = path
+        .map(str::to_string)
+        .or(profile.working_dir.clone())
+        .or_else(|| profile.is_remote().then(home).flatten())
+    else {
+        anyhow::bail!(CoreError::invalid_input("no directory to launch in")
+            .with_hint("pass a path, or set the profile's working_dir"));
+    };
+    let path = normalize_path(&path)?.display().to_string();
+    let request = LaunchRequest {
+        background,
+        ..sessions::launch_for(path, sessions::ProfileChoice::Global(&profile))?
+    };
+    let started = std::time::Instant::now();
+    let mut report = launcher::launch_report(&request, launcher::Secrets::Resolve);
+    if profile.kube_pod.is_none() && profile.ssh_host.is_some() {
+        report
+            .warnings
+            .extend(ssh::fallback_warning(profile.transport));
+    }
+    if report.succeeded() {
+        stats::record(stats::Usage::Launch {
+            profile: request.profile.as_deref(),
+            took: started.elapsed(),
+        });
+    }
+    Ok(report)
+}
+
+fn set_autostart(id: Uuid, enabled: bool) -> anyhow::Result<LaunchProfile> {
+    let mut store = STORE.inner.lock();
+    let Some(profile) = store.profiles.iter_mut().find(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    if enabled {
+        if profile.working_dir.is_none() {
+            anyhow::bail!(CoreError::invalid_input(format!(
+                "{} has no working directory to open at login",
+                profile.name
+            ))
+            .with_hint("set the profile's working_dir first"));
+        }
+        let entry = autostart::install(id)?;
+        tracing::info!("{} launches at login via {entry}", profile.name);
+    } else {
+        autostart::uninstall(id)?;
+    }
+    profile.autostart = enabled;
+    let profile = profile.clone();
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Updated,
+        id.to_string(),
+    ));
+    Ok(profile)
+}
+
+fn delete_profile(id: Uuid) -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    let Some(index) = store.profiles.iter().position(|profile| profile.id == id) else {
+        anyhow::bail!(CoreError::not_found("profile not found"));
+    };
+    let mut profile = store.profiles.remove(index);
+    if profile.autostart {
+        // Undo brings the profile back without its login item.
+        if let Err(err) = autostart::uninstall(id) {
+            tracing::warn!(
+                "failed to remove the login item of {}: {err:#}",
+                profile.name
+            );
+        }
+        profile.autostart = false;
+    }
+    journal::record(
+        &mut store,
+        JournalOperation::DeleteProfile {
+            profile: Box::new(profile),
+            index,
+        },
+    );
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish(StoreEvent::new(
+        EventKind::Profile,
+        StoreChange::Removed,
+        id.to_string(),
+    ));
+    Ok(())
+}
 
 pub mod api {
     use super::*;
 
+    /// Accepts `file:` URIs, expands `~`, `$VAR`, `${VAR}`, and `%VAR%` (`$$`/`%%` for
+    /// literals), then resolves the path per `paths.symlinks`. Variables are left alone when
+    /// the path as written exists. Every path-taking function here goes through the same
+    /// steps.
     pub fn normalize_path(path: &str) -> anyhow::Result<String> {
         let normalized = super::normalize_path(path)?;
         Ok(normalized.display().to_string())
     }
 
+    /// `path` the way people write it: `~/dev/api` under the home directory, or relative to
+    /// `relative_to` (e.g. `api`, or `.` for the root itself) when it lies inside that root.
+    pub fn display_path(path: &str, relative_to: Option<&str>) -> anyhow::Result<String> {
+        super::display_path(path, relative_to)
+    }
+
+    /// Converts between Windows and WSL forms of a path, e.g. `C:\Users\me` and
+    /// `/mnt/c/Users/me`. `distro` is needed for Linux paths outside `/mnt` when translating
+    /// to Windows from outside WSL.
+    pub fn translate_path(
+        path: &str,
+        target: PathFlavor,
+        distro: Option<&str>,
+    ) -> anyhow::Result<String> {
+        super::wsl::translate_path(path, target, distro)
+    }
+
+    pub fn wsl_distros() -> Vec<WslDistro> {
+        super::wsl::wsl_distros()
+    }
+
+    /// Known editors (`code`, `cursor`, `nvim`, `zed`, `idea`) and where each is installed.
+    pub fn editors() -> Vec<Editor> {
+        super::editors::editors()
+    }
+
+    /// Opens `path` in `editor`, or in the editor bound to it with [`set_preferred_editor`],
+    /// or in the first installed one. Returns the editor used.
+    pub fn open_in_editor(path: &str, editor: Option<&str>) -> anyhow::Result<Editor> {
+        super::editors::open_in_editor(path, editor)
+    }
+
+    /// The editor bound to `path` or its closest bound parent directory.
+    pub fn preferred_editor(path: &str) -> anyhow::Result<Option<String>> {
+        super::editors::preferred_editor(path)
+    }
+
+    /// Binds a directory to an editor for [`open_in_editor`]; `None` removes the binding.
+    pub fn set_preferred_editor(path: &str, editor: Option<&str>) -> anyhow::Result<()> {
+        super::editors::set_preferred_editor(path, editor)
+    }
+
+    /// The terminal the OS opens by default, and the `terminal` name that launches it.
+    pub fn default_terminal() -> DefaultTerminal {
+        super::defaults::default_terminal()
+    }
+
+    /// The terminals Terminaut can open that are installed on this machine.
+    pub fn installed_terminals() -> Vec<InstalledTerminal> {
+        super::launcher::installed_terminals()
+    }
+
+    /// The shell and environment the config file of `terminal` (or the default terminal)
+    /// sets for its windows. Read for Ghostty and Alacritty; empty for other terminals.
+    pub fn terminal_settings(terminal: Option<&str>) -> anyhow::Result<TerminalSettings> {
+        super::launcher::terminal_settings(terminal)
+    }
+
+    /// The user's login shell as the OS records it.
+    pub fn default_shell() -> DefaultShell {
+        super::defaults::default_shell()
+    }
+
+    /// [`normalize_path`] with an explicit symlink policy instead of `paths.symlinks`.
+    pub fn normalize_path_with(path: &str, symlinks: SymlinkPolicy) -> anyhow::Result<String> {
+        let normalized = super::normalize_path_with(path, symlinks)?;
+        Ok(normalized.display().to_string())
+    }
+
+    /// Entries of a directory. An archive, or a path through one like `/dl/src.zip/lib`, is
+    /// listed as in [`list_archive`].
     pub fn list_directory(path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
         let normalized = super::normalize_path(path)?;
         super::list_directory(&normalized)
     }
 
+    /// [`list_directory`] in batches of at most 256 entries, each handed to `on_batch` as
+    /// soon as it is read, so a huge folder can be shown while it is still being listed.
+    /// Entries come in the order the directory yields them, not sorted. Return `false` from
+    /// `on_batch` to stop early; the result says whether every entry was delivered.
+    pub fn list_directory_batches(
+        path: &str,
+        on_batch: impl FnMut(Vec<DirectoryEntry>) -> bool,
+    ) -> anyhow::Result<bool> {
+        let normalized = super::normalize_path(path)?;
+        let entries = super::directory_entries(&normalized)?;
+        Ok(super::batches::for_each_batch(
+            entries,
+            super::batches::BATCH_SIZE,
+            on_batch,
+        ))
+    }
+
+    /// [`list_directory`] with each entry's `git status` state (modified, untracked, ignored,
+    /// ...) from one `git` call. Entries outside a repository or without changes have none.
+    pub fn list_directory_with_git(path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
+        let options = ListOptions {
+            git: true,
+            ..ListOptions::default()
+        };
+        list_directory_with(path, &options)
+    }
+
+    /// [`list_directory`] ordered by `options.sort`, optionally with `git status` states.
+    /// [`ListSort::Frecency`] puts the subdirectories opened most (counting recents below
+    /// them) first.
+    pub fn list_directory_with(
+        path: &str,
+        options: &ListOptions,
+    ) -> anyhow::Result<Vec<DirectoryEntry>> {
+        let normalized = super::normalize_path(path)?;
+        let mut entries = super::list_directory(&normalized)?;
+        if options.git {
+            if let Some(statuses) = super::git::entry_statuses(&normalized) {
+                for entry in &mut entries {
+                    entry.git_status = statuses.get(&entry.name).copied();
+                }
+            }
+        }
+        if options.sort == ListSort::Frecency {
+            let recents = super::STORE.inner.lock().recents.clone();
+            let now = Utc::now().timestamp();
+            super::listing::sort_by_frecency(&mut entries, &recents, now);
+        }
+        Ok(entries)
+    }
+
+    /// Entries inside a `.zip`, `.tar`, `.tar.gz`, or `.7z` file, without extracting it.
+    /// `path` is the archive or a directory inside it, like `/dl/src.zip/lib`; entry paths
+    /// continue it, so they can be listed in turn.
+    pub fn list_archive(path: &str) -> anyhow::Result<Vec<DirectoryEntry>> {
+        let normalized = super::normalize_path(path)?;
+        super::archive::list_archive(&normalized)
+    }
+
+    /// Drives, volumes, and user-visible mount points to use as browsing starting points.
+    pub fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+        super::roots::list_roots()
+    }
+
+    /// Up to `limit` directories below `path` or beside it that the user is likely to open
+    /// next, ranked by the frecency of their recents. Deeper directories and siblings rank
+    /// lower; directories that no longer exist are left out.
+    pub fn suggest_next(path: &str, limit: usize) -> anyhow::Result<Vec<Suggestion>> {
+        let normalized = super::normalize_path(path)?;
+        let recents = super::STORE.inner.lock().recents.clone();
+        Ok(
+            super::suggest::rank(&normalized, &recents, Utc::now().timestamp())
+                .into_iter()
+                .filter(|suggestion| Path::new(&suggestion.path).is_dir())
+                .take(limit)
+                .collect(),
+        )
+    }
+
+    /// Ancestors of `path` from the volume root down to `path` itself, for breadcrumb bars.
+    pub fn ancestors(path: &str) -> anyhow::Result<Vec<Breadcrumb>> {
+        let normalized = super::normalize_path(path)?;
+        Ok(super::breadcrumbs::ancestors(&normalized))
+    }
+
     pub fn list_favorites() -> Vec<String> {
         super::list_favorites()
     }
@@ -446,12 +1771,160 @@ pub mod api {
         super::remove_favorite(path)
     }
 
+    /// Recents in the `recents.sort` order from the config: frecency unless set to `recency`.
     pub fn list_recents() -> Vec<RecentEntry> {
-        super::list_recent_directories()
+        let sort = config::config().recents.sort;
+        list_recents_sorted(sort)
+    }
+
+    /// Recents in `sort` order, with scores decayed to now.
+    pub fn list_recents_sorted(sort: RecentsSort) -> Vec<RecentEntry> {
+        let recents = super::list_recent_directories();
+        super::recents::sorted(recents, sort, Utc::now().timestamp())
+    }
+
+    /// Recents bucketed by enclosing project or by local day or week, for "Today / Yesterday
+    /// / Last week" sections. Groups and their entries are newest first.
+    pub fn recents_grouped(by: RecentGrouping) -> Vec<RecentGroup> {
+        super::recents::group(super::list_recent_directories(), by, chrono::Local::now())
+    }
+
+    /// Turns read-only mode on or off for this process. While on, mutating calls succeed
+    /// against in-memory state but nothing is saved, hooks don't run, and file operations
+    /// fail. `TERMINAUT_READONLY=1` turns it on at startup.
+    pub fn set_read_only(enabled: bool) {
+        READ_ONLY.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_read_only() -> bool {
+        super::read_only()
+    }
+
+    /// Loads the store now instead of on first use and returns how that went. Fails with
+    /// [`ErrorCode::StoreCorrupt`] when the saved state could not be read, so a host can warn
+    /// at startup rather than on the first failed save.
+    pub fn init_store() -> anyhow::Result<StoreStatus> {
+        match STORE.load_failure() {
+            Some(err) => Err(err.into()),
+            None => Ok(STORE.status()),
+        }
+    }
+
+    /// How the store came up: loaded, ephemeral, quarantined (a corrupt file was moved aside),
+    /// or unreadable (saving is disabled).
+    pub fn store_status() -> StoreStatus {
+        STORE.status()
+    }
+
+    /// A number that grows with every change to the stored state, including changes saved by
+    /// other processes (picked up by [`check_generation`]). Pass it back as
+    /// `expected_generation` to make a mutation fail instead of overwriting a newer state.
+    pub fn store_generation() -> u64 {
+        STORE.generation()
+    }
+
+    /// Fails with [`ErrorCode::Conflict`] unless the store is still at `expected`. Reloads the
+    /// state first when another process saved a newer one.
+    pub fn check_generation(expected: u64) -> anyhow::Result<()> {
+        STORE.refresh()?;
+        let _expected = ExpectedGeneration::new(expected);
+        check_expected(STORE.generation())
+    }
+
+    /// Store changes of `kind` from now on, so a UI can update a list without re-polling it.
+    /// Dropping the receiver unsubscribes.
+    pub fn subscribe(kind: EventKind) -> std::sync::mpsc::Receiver<StoreEvent> {
+        super::events::subscribe(Some(kind))
+    }
+
+    /// Like [`subscribe`], for every kind.
+    pub fn subscribe_all() -> std::sync::mpsc::Receiver<StoreEvent> {
+        super::events::subscribe(None)
+    }
+
+    /// Calls `callback` on the changing thread for each store change of `kind` (every kind
+    /// for `None`), after the change is saved. Returns an id for [`unsubscribe`].
+    pub fn subscribe_callback(kind: Option<EventKind>, callback: EventCallback) -> u64 {
+        super::events::subscribe_callback(kind, callback)
+    }
+
+    pub fn unsubscribe(id: u64) -> bool {
+        super::events::unsubscribe(id)
+    }
+
+    /// Whether `TERMINAUT_EPHEMERAL=1` was set at startup, so the store began empty and
+    /// nothing is loaded from or saved to the state file.
+    pub fn is_ephemeral() -> bool {
+        super::ephemeral()
+    }
+
+    /// Checks the config, the state store, the configured terminals, and search speed.
+    pub fn doctor() -> DoctorReport {
+        super::doctor::doctor()
+    }
+
+    /// Local usage counts for the last `weeks` weeks, newest first. Empty unless
+    /// `stats.enabled` is set in the config.
+    pub fn stats(weeks: usize) -> UsageStats {
+        super::stats::stats(weeks)
+    }
+
+    pub fn clear_stats() -> anyhow::Result<()> {
+        super::stats::clear_stats()
+    }
+
+    /// Fuzzy-matches `query` against in-memory strings, best match first.
+    pub fn fuzzy_filter(
+        query: &str,
+        candidates: &[String],
+        options: &MatchOptions,
+    ) -> Vec<FuzzyMatch> {
+        super::matching::fuzzy_filter(query, candidates, options)
+    }
+
+    /// Store-based rank for each path, as search would blend it with a fuzzy score of zero.
+    pub fn rank_paths(paths: &[String], weights: &RankWeights) -> Vec<RankBreakdown> {
+        let signals = super::ranking::StoreSignals::snapshot();
+        paths
+            .iter()
+            .map(|path| signals.rank(path, 0, weights))
+            .collect()
+    }
+
+    /// Like [`list_favorites`], with each entry flagged by whether it still exists.
+    pub fn list_favorites_with_status() -> Vec<WithStatus<FavoriteEntry>> {
+        let sources = STORE.inner.lock().favorite_sources.clone();
+        let favorites = super::list_favorites()
+            .into_iter()
+            .map(|path| FavoriteEntry {
+                source: sources.get(&path).cloned(),
+                path,
+            })
+            .collect();
+        status::annotate(favorites, |entry| &entry.path)
+    }
+
+    pub fn list_recents_with_status() -> Vec<WithStatus<RecentEntry>> {
+        status::annotate(list_recents(), |entry| &entry.path)
+    }
+
+    pub fn list_tags_with_status() -> Vec<WithStatus<TaggedPath>> {
+        status::annotate(super::list_tags(), |entry| &entry.path)
     }
 
     pub fn touch_recent(path: &str) -> anyhow::Result<()> {
-        super::touch_recent(path)
+        super::touch_recent(path, None)
+    }
+
+    /// [`touch_recent`] on behalf of `source`, e.g. `gui`, `shell`, or a terminal or profile
+    /// name, so [`list_recents_from`] can tell where directories were opened from.
+    pub fn touch_recent_from(path: &str, source: &str) -> anyhow::Result<()> {
+        super::touch_recent(path, Some(source))
+    }
+
+    /// Recents that `source` touched, newest first by that source's last open.
+    pub fn list_recents_from(source: &str) -> Vec<RecentEntry> {
+        super::list_recents_from(source)
     }
 
     pub fn detect_projects(path: &str) -> anyhow::Result<Vec<ProjectRoot>> {
@@ -459,10 +1932,144 @@ pub mod api {
         Ok(super::detect_projects(&normalized))
     }
 
+    /// The enclosing project of `path`, merged with its `.terminaut.toml` when present.
+    pub fn project_info(path: &str) -> anyhow::Result<ProjectInfo> {
+        let normalized = super::normalize_path(path)?;
+        super::workspace::project_info(&normalized)
+    }
+
+    /// Runs task `task_name` from the `.terminaut.toml` of the project containing `path`, in
+    /// the project root, passing each output line to `on_line` as it is printed. A task that
+    /// fails still returns its result; errors mean it could not be found or started.
+    pub fn run_task(
+        path: &str,
+        task_name: &str,
+        options: &TaskOptions,
+        on_line: Option<TaskCallback>,
+    ) -> anyhow::Result<TaskResult> {
+        let normalized = super::normalize_path(path)?;
+        super::tasks::run_task(&normalized, task_name, options, on_line)
+    }
+
+    /// The last [`run_task`] result of each task the project containing `path` declares, for
+    /// pass/fail badges in project lists.
+    pub fn project_status(path: &str) -> anyhow::Result<ProjectStatus> {
+        let normalized = super::normalize_path(path)?;
+        super::tasks::project_status(&normalized)
+    }
+
+    /// Title, summary, and repository URL of the project containing `path`, from its README,
+    /// `package.json`, and `Cargo.toml`, for project cards.
+    pub fn project_description(path: &str) -> anyhow::Result<ProjectDescription> {
+        let normalized = super::normalize_path(path)?;
+        Ok(super::description::project_description(&normalized))
+    }
+
+    /// Host, owner, repo, and web URL of the git remote of the project containing `path`;
+    /// `None` outside a repository or without a remote. When a GitHub or GitLab token is set
+    /// (`GITHUB_TOKEN`/`GITLAB_TOKEN`, or the `github_token`/`gitlab_token` secret), also
+    /// fetches stars, description, and default branch from the API.
+    pub fn project_remote(path: &str) -> anyhow::Result<Option<ProjectRemote>> {
+        let normalized = super::normalize_path(path)?;
+        Ok(super::remote::project_remote(&normalized))
+    }
+
+    /// Projects registered by earlier scans, without touching the filesystem.
+    pub fn list_projects() -> Vec<KnownProject> {
+        super::projects::list_projects()
+    }
+
+    /// Walks `roots` for projects and updates the registry. With no roots, rescans the
+    /// configured `projects.roots` whose last scan is older than `projects.refresh_hours`.
+    pub fn refresh_projects(roots: &[String]) -> anyhow::Result<ScanReport> {
+        super::projects::refresh_projects(roots)
+    }
+
+    #[cfg(feature = "watch")]
+    /// Runs the index daemon until it is stopped: watches `projects.roots`, rescans a root
+    /// for projects shortly after something below it is created, removed, or renamed, and
+    /// answers `status`, `stop`, and read-only [`invoke`] queries over a Unix socket that only
+    /// the user can open.
+    pub fn run_indexd() -> anyhow::Result<()> {
+        super::indexd::run_indexd()
+    }
+
+    #[cfg(feature = "watch")]
+    /// What the running index daemon is watching and when it last rescanned; fails with
+    /// not found when no daemon is running.
+    pub fn indexd_status() -> anyhow::Result<IndexdStatus> {
+        super::indexd::indexd_status()
+    }
+
+    #[cfg(feature = "watch")]
+    pub fn stop_indexd() -> anyhow::Result<()> {
+        super::indexd::stop_indexd()
+    }
+
+    /// Runs the read-only [`invoke`] query `method` in the running `indexd`, talking `format`
+    /// on its socket, so frequent calls reuse the daemon's warm state. Anything that changes
+    /// state is refused there.
+    #[cfg(feature = "watch")]
+    pub fn indexd_invoke(
+        method: &str,
+        params: serde_json::Value,
+        format: WireFormat,
+    ) -> anyhow::Result<serde_json::Value> {
+        super::indexd::request_as(method, params, format)
+    }
+
+    #[cfg(feature = "fs")]
+    /// Repositories checked out more than once below `root`, matched by git remote (or by
+    /// folder name without one), with each checkout's size and when it was last opened.
+    /// Sizes are measured on the calling thread; see [`dir_size_blocking`].
+    pub fn find_duplicate_projects(root: &str) -> anyhow::Result<Vec<DuplicateGroup>> {
+        let normalized = super::normalize_path(root)?;
+        super::duplicates::find_duplicate_projects(&normalized)
+    }
+
+    /// Re-reads and returns the config file, or the defaults when it doesn't exist.
+    pub fn get_config() -> anyhow::Result<Config> {
+        super::config::get_config()
+    }
+
+    /// Reads one dotted key, e.g. `search.max_depth`.
+    pub fn get_config_value(key: &str) -> anyhow::Result<serde_json::Value> {
+        super::config::get_config_value(key)
+    }
+
+    /// Sets one dotted key to `value` (TOML syntax; bare words are strings) and saves.
+    pub fn set_config(key: &str, value: &str) -> anyhow::Result<Config> {
+        super::config::set_config(key, value)
+    }
+
+    pub fn config_path() -> PathBuf {
+        super::config::config_path()
+    }
+
     pub fn list_tags() -> Vec<TaggedPath> {
         super::list_tags()
     }
 
+    /// Drops stored paths that no longer exist, rewriting them instead when a case or
+    /// symlink variant of the path is still present.
+    pub fn prune_state() -> anyhow::Result<PruneReport> {
+        super::prune_state()
+    }
+
+    pub fn preview_prune() -> PruneReport {
+        super::preview_prune()
+    }
+
+    /// Merges duplicate entries, trims recents to `recents.cap`, and rewrites the state file in
+    /// the format `store.compact` selects. With `drop_missing`, also prunes dead paths as
+    /// [`prune_state`] does, which [`undo_last`] can revert. With `dry_run`, reports what it
+    /// would do without changing anything.
+    pub fn compact_state(drop_missing: bool, dry_run: bool) -> anyhow::Result<CompactReport> {
+        super::compact_state(drop_missing, dry_run)
+    }
+
+    /// Tags `path`, or recolors an existing tag. `color` is `#rrggbb`, `#rgb`, or a name from
+    /// [`list_tag_colors`]; without one, tags are blue.
     pub fn set_tag(path: &str, tag: &str, color: Option<&str>) -> anyhow::Result<()> {
         super::set_tag(path, tag, color)
     }
@@ -471,14 +2078,141 @@ pub mod api {
         super::remove_tag(path, tag)
     }
 
+    /// Tags of `path`. With `tags.inherit` on, also those of its ancestors not already on it,
+    /// nearest first and marked `inherited`.
     pub fn tags_for(path: &str) -> anyhow::Result<Vec<TaggedPath>> {
         super::tags_for_path(path)
     }
 
+    /// Tagged paths whose tag is `prefix` or namespaced below it, ignoring case: `client/`
+    /// returns paths tagged `client/acme` and `client/globex/web`.
+    pub fn paths_with_tag_prefix(prefix: &str) -> Vec<TaggedPath> {
+        super::list_tags()
+            .into_iter()
+            .filter(|entry| super::tag_tree::has_prefix(&entry.tag, prefix))
+            .collect()
+    }
+
+    /// Attaches a Markdown note to `path`, such as "deploy from here with make release",
+    /// replacing any earlier one; blank `markdown` removes it. Notes show in [`preview`] and
+    /// match in [`search`] and [`search_notes`]. Returns the saved note.
+    pub fn set_note(path: &str, markdown: &str) -> anyhow::Result<Option<Note>> {
+        super::notes::set_note(path, markdown)
+    }
+
+    pub fn get_note(path: &str) -> anyhow::Result<Option<Note>> {
+        super::notes::get_note(path)
+    }
+
+    pub fn list_notes() -> Vec<Note> {
+        super::notes::list_notes()
+    }
+
+    /// Notes containing every word of `query`, ignoring case, most recently updated first.
+    pub fn search_notes(query: &str) -> Vec<Note> {
+        super::notes::search_notes(query)
+    }
+
+    /// Registers a command offered for `path`, or for every directory tagged `tag` (exactly one
+    /// of the two), such as "Deploy" running `make deploy`. `{path}` and `{name}` in `command`
+    /// are replaced with the directory and its folder name when it runs.
+    pub fn add_action(
+        label: &str,
+        command: &str,
+        path: Option<&str>,
+        tag: Option<&str>,
+        background: bool,
+    ) -> anyhow::Result<CustomAction> {
+        super::actions::add_action(label, command, path, tag, background)
+    }
+
+    pub fn remove_action(id: Uuid) -> anyhow::Result<()> {
+        super::actions::remove_action(id)
+    }
+
+    pub fn list_actions() -> Vec<CustomAction> {
+        super::actions::list_actions()
+    }
+
+    /// The actions a context menu should offer for `path`: its own, then those of its tags,
+    /// counting inherited tags when `tags.inherit` is on.
+    pub fn actions_for(path: &str) -> anyhow::Result<Vec<CustomAction>> {
+        super::actions::actions_for(path)
+    }
+
+    /// Runs action `action_id` at `path` in a new terminal, or in the background for
+    /// background actions. Returns the command line as run.
+    pub fn run_action(path: &str, action_id: Uuid) -> anyhow::Result<String> {
+        super::actions::run_action(path, action_id)
+    }
+
+    /// The named tag colors front-ends should offer: the built-ins, then those from the
+    /// config's `tags.palette`.
+    pub fn list_tag_colors() -> Vec<TagColor> {
+        super::tag_colors::list_tag_colors()
+    }
+
+    /// Every tag split on `/` into a tree, with the number of paths at and below each node.
+    pub fn tag_tree() -> Vec<TagNode> {
+        super::tag_tree::tag_tree(&super::list_tags())
+    }
+
+    /// Tags registered projects, favorites, and recents by `rules` (by default, the config's
+    /// `projects.tag_rules`), e.g. everything under `~/work/clients/acme` with `acme` and every
+    /// directory holding a `Cargo.toml` with `rust`. With `dry_run`, only reports the tags it
+    /// would add.
+    pub fn auto_tag(rules: &[TagRule], dry_run: bool) -> anyhow::Result<AutoTagReport> {
+        super::auto_tag::auto_tag(rules, dry_run)
+    }
+
+    /// Adds the directories bookmarked in the file manager (GTK bookmarks or the Finder
+    /// sidebar; by default, this platform's) as favorites, recording where each came from.
+    /// With `dry_run`, only reports what it would add.
+    pub fn import_bookmarks(
+        source: Option<ImportSource>,
+        dry_run: bool,
+    ) -> anyhow::Result<ImportReport> {
+        let source = source.unwrap_or_else(ImportSource::platform_bookmarks);
+        super::importers::import(source, true, dry_run)
+    }
+
+    /// Adds the projects recently opened in an editor (`vscode` or `jetbrains`), or the
+    /// directories zoxide knows (`zoxide`), as recents with their last-opened times, or as
+    /// favorites with `as_favorites`. With `dry_run`, only reports what it would add.
+    pub fn import_projects(
+        source: ImportSource,
+        as_favorites: bool,
+        dry_run: bool,
+    ) -> anyhow::Result<ImportReport> {
+        super::importers::import(source, as_favorites, dry_run)
+    }
+
+    /// Adds the macOS Finder tags of `path` (by default, of every favorite and recent) as
+    /// tags, mapping Finder's label colors. Returns the tags imported, or with `dry_run`
+    /// the tags it would import.
+    pub fn import_finder_tags(
+        path: Option<&str>,
+        dry_run: bool,
+    ) -> anyhow::Result<Vec<TaggedPath>> {
+        super::finder_tags::import(path, dry_run)
+    }
+
+    /// Writes the tags of `path` (by default, of every tagged path) to Finder, keeping Finder
+    /// tags that have no counterpart here. Returns the tags written, or with `dry_run` the
+    /// tags it would write. Refused in read-only mode, since Finder tags aren't in the state.
+    pub fn export_finder_tags(
+        path: Option<&str>,
+        dry_run: bool,
+    ) -> anyhow::Result<Vec<TaggedPath>> {
+        super::finder_tags::export(path, dry_run)
+    }
+
     pub fn list_profiles() -> Vec<LaunchProfile> {
         super::list_profiles()
     }
 
+    /// Creates or replaces a profile. `command` is split into `args` with
+    /// [`parse_command`]; see [`set_profile_args`] to store arguments exactly.
     pub fn save_profile(
         id: Option<Uuid>,
         name: &str,
@@ -486,68 +2220,518 @@ pub mod api {
         working_dir: Option<String>,
         terminal: Option<String>,
         windows: Option<u8>,
+        wsl_distro: Option<String>,
     ) -> anyhow::Result<LaunchProfile> {
-        super::save_profile(id, name, command, working_dir, terminal, windows)
+        super::save_profile(
+            id,
+            name,
+            command,
+            working_dir,
+            terminal,
+            windows,
+            wsl_distro,
+        )
     }
 
-    pub fn delete_profile(id: Uuid) -> anyhow::Result<()> {
-        super::delete_profile(id)
+    /// Replaces the profile's command with `args`: the program, then its arguments, passed
+    /// as is. With `use_shell` they are joined into one line for the shell instead. Empty
+    /// `args` opens a plain shell.
+    pub fn set_profile_args(
+        id: Uuid,
+        args: Vec<String>,
+        use_shell: bool,
+    ) -> anyhow::Result<LaunchProfile> {
+        super::set_profile_args(id, args, use_shell)
     }
 
-    pub fn search(path: &str, query: &str, limit: usize) -> anyhow::Result<Vec<SearchResult>> {
-        super::search_directories(path, query, limit)
+    /// Sets the shell commands run before the profile's terminal opens and after it has
+    /// opened; `None` or a blank command clears one. [`save_profile`] keeps them as they are.
+    pub fn set_profile_hooks(
+        id: Uuid,
+        pre_command: Option<String>,
+        post_launch_command: Option<String>,
+    ) -> anyhow::Result<LaunchProfile> {
+        super::set_profile_hooks(id, pre_command, post_launch_command)
     }
-}
 
-fn c_string_or_null(result: anyhow::Result<String>) -> *mut c_char {
-    match result {
-        Ok(value) => CString::new(value)
-            .map(|s| s.into_raw())
-            .unwrap_or(std::ptr::null_mut()),
-        Err(err) => {
-            eprintln!("term-core error: {err:#}");
-            std::ptr::null_mut()
-        }
+    /// Launches profile `id` at `path`, or at its `working_dir` without one, and reports the
+    /// pre command, the launch, and the post command separately. Only a missing profile or
+    /// directory is an error; failed phases are in the report. With `background`, the
+    /// profile's command runs without a terminal and its output goes to a log file; see
+    /// [`launch_logs`].
+    pub fn launch_profile(
+        id: Uuid,
+        path: Option<&str>,
+        background: bool,
+    ) -> anyhow::Result<LaunchReport> {
+        super::launch_profile(id, path, background)
     }
-}
 
-fn c_string_from_json<T: Serialize>(value: &T) -> *mut c_char {
-    match serde_json::to_string(value) {
-        Ok(json) => CString::new(json)
-            .map(|s| s.into_raw())
-            .unwrap_or(std::ptr::null_mut()),
-        Err(err) => {
-            eprintln!("term-core json error: {err:#}");
-            std::ptr::null_mut()
-        }
+    /// Makes the profile open its shell on `host` over `transport`, starting in `dir` there,
+    /// and run its command remotely. When mosh or et isn't installed, launches fall back to
+    /// ssh and say so in [`LaunchReport::warnings`]. `None` for `host` makes the profile local again.
+    pub fn set_profile_remote(
+        id: Uuid,
+        host: Option<String>,
+        dir: Option<String>,
+        transport: Transport,
+    ) -> anyhow::Result<LaunchProfile> {
+        super::set_profile_remote(id, host, dir, transport)
     }
-}
 
-fn c_str_to_string(input: *const c_char) -> anyhow::Result<String> {
-    if input.is_null() {
-        anyhow::bail!("null pointer");
+    /// Makes the profile open its shell in a pod with `kubectl exec -it` and run its command
+    /// there. A target without `pod` makes the profile local again.
+    pub fn set_profile_kube(id: Uuid, target: KubeTarget) -> anyhow::Result<LaunchProfile> {
+        super::set_profile_kube(id, target)
     }
-    let c_str = unsafe { CStr::from_ptr(input) };
-    Ok(c_str.to_string_lossy().to_string())
-}
 
-#[no_mangle]
-pub extern "C" fn term_core_version() -> *mut c_char {
-    CString::new(env!("CARGO_PKG_VERSION")).unwrap().into_raw()
-}
+    /// Contexts of the user's kubeconfig, read with `kubectl config view`.
+    pub fn list_kube_contexts() -> anyhow::Result<Vec<KubeContext>> {
+        super::kube::list_contexts()
+    }
 
-#[no_mangle]
-pub extern "C" fn term_core_string_free(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        unsafe {
-            drop(CString::from_raw(ptr));
-        }
+    /// Registers or removes a login item (a launchd agent, a systemd user unit, or a Windows
+    /// `Run` entry) that runs `term-core-cli profiles launch <id>` when the user logs in.
+    /// Enabling needs the profile to have a `working_dir` and `term-core-cli` to be installed.
+    pub fn set_autostart(profile_id: Uuid, enabled: bool) -> anyhow::Result<LaunchProfile> {
+        super::set_autostart(profile_id, enabled)
     }
-}
 
-#[no_mangle]
-pub extern "C" fn term_core_normalize_path(path: *const c_char) -> *mut c_char {
-    c_string_or_null(c_str_to_string(path).and_then(|p| {
+    /// Output logs of the profile's background launches, newest first. The last 20 are kept.
+    pub fn launch_logs(profile_id: Uuid) -> Vec<LaunchLog> {
+        super::launch_logs::launch_logs(Some(&profile_id.to_string()))
+    }
+
+    /// Splits a command line into profile arguments, keeping lines that need a shell whole.
+    pub fn parse_command(line: &str) -> anyhow::Result<ParsedCommand> {
+        super::command_line::parse(line)
+    }
+
+    pub fn delete_profile(id: Uuid) -> anyhow::Result<()> {
+        super::delete_profile(id)
+    }
+
+    /// Reverts the most recent favorite removal, tag removal, profile deletion, or prune and
+    /// returns it. The last 20 such operations are kept with the saved state.
+    pub fn undo_last() -> anyhow::Result<JournalEntry> {
+        super::journal::undo_last()
+    }
+
+    /// Undoable operations, most recent first.
+    pub fn undo_history() -> Vec<JournalEntry> {
+        super::journal::journal()
+    }
+
+    /// Folds another installation's state file into this one, e.g. when syncing between
+    /// machines. Conflicting tags and profiles keep the local version and are reported.
+    pub fn merge_state(path: &str) -> anyhow::Result<MergeReport> {
+        let path = super::normalize_path(path)?;
+        super::merge::merge_state(&path)
+    }
+
+    /// Calls the function named `method` with named `params`, for bindings that marshal
+    /// everything as JSON. See `invoke.rs` for the method list.
+    pub fn invoke(method: &str, params: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+        super::invoke::invoke(method, params)
+    }
+
+    /// [`invoke`] for callers that speak `format` rather than JSON: `params` is an encoded
+    /// object (empty for none) and the result is the encoded `{"result"}` or `{"error"}`
+    /// envelope of `term_core_invoke`, with the store `generation`. Fails only when this
+    /// build lacks `format`; see [`capabilities`].
+    pub fn invoke_encoded(
+        method: &str,
+        params: &[u8],
+        format: WireFormat,
+    ) -> anyhow::Result<Vec<u8>> {
+        super::invoke::invoke_encoded(method, params, format).map(Vec::from)
+    }
+
+    /// Sends core log events at or above `level` to `callback` (`None` removes it). The
+    /// callback runs on whichever thread logged and must not block.
+    pub fn set_log_callback(callback: Option<LogCallback>, level: LogLevel) {
+        super::logging::set_log_callback(callback, level)
+    }
+
+    /// Sets the stderr level and the optional rotating log file. Until then, warnings and
+    /// errors go to stderr from the first use of the store on. A host with its own `tracing`
+    /// subscriber must install it before that to receive core events instead.
+    pub fn configure_logging(config: &LogConfig) -> anyhow::Result<()> {
+        super::logging::configure_logging(config)
+    }
+
+    /// The core version, ABI and schema versions, compiled-in features, and invoke methods,
+    /// so a front-end bundled with a different core build can check what it may call.
+    pub fn capabilities() -> Capabilities {
+        super::capabilities::capabilities()
+    }
+
+    /// JSON Schema for the public data types, keyed by type name (`Error` for [`CoreError`]).
+    pub fn json_schemas() -> std::collections::BTreeMap<String, serde_json::Value> {
+        super::schema::json_schemas()
+    }
+
+    #[cfg(feature = "fs")]
+    /// Runs a Rhai script with this module's functions available; see `script.rs`.
+    pub fn run_script(path: &str, args: &[String]) -> anyhow::Result<serde_json::Value> {
+        let normalized = super::normalize_path(path)?;
+        super::script::run_script(&normalized, args)
+    }
+
+    /// Opens a terminal at `request.path`. On success, a launch with a `profile_id` fires the
+    /// `profile_launched` hook.
+    /// `{secret:NAME}` references are left as written; only [`launch_profile`] fills them in.
+    pub fn launch(request: &LaunchRequest) -> anyhow::Result<()> {
+        let normalized = super::normalize_path(&request.path)?;
+        let started = std::time::Instant::now();
+        super::launcher::launch(
+            &LaunchRequest {
+                path: normalized.display().to_string(),
+                ..request.clone()
+            },
+            super::launcher::Secrets::Leave,
+        )?;
+        super::stats::record(super::stats::Usage::Launch {
+            profile: request.profile.as_deref(),
+            took: started.elapsed(),
+        });
+        Ok(())
+    }
+
+    /// Opens a single shell at `path` in `terminal`, or in the configured or platform default
+    /// terminal (Terminal.app, `x-terminal-emulator`, Windows Terminal), without a profile.
+    pub fn open_terminal(path: &str, terminal: Option<&str>) -> anyhow::Result<()> {
+        launch(&LaunchRequest {
+            path: path.to_string(),
+            terminal: terminal.map(str::to_string),
+            command: None,
+            args: Vec::new(),
+            use_shell: false,
+            pre_command: None,
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 1,
+            env: Default::default(),
+            wsl_distro: None,
+        })
+    }
+
+    /// Puts `text`, typically a path, on the system clipboard.
+    pub fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+        super::clipboard::copy_to_clipboard(text)
+    }
+
+    /// Stores `value` in the OS keychain for `{secret:NAME}` references in the commands of saved
+    /// profiles. [`launch_profile`] passes the value to the launched processes in their
+    /// environment, so it never reaches the state file or a typed command line.
+    pub fn set_secret(name: &str, value: &str) -> anyhow::Result<()> {
+        super::secrets::set_secret(name, value)
+    }
+
+    pub fn delete_secret(name: &str) -> anyhow::Result<()> {
+        super::secrets::delete_secret(name)
+    }
+
+    /// A front-end's own setting, such as the last window size, kept with the saved state
+    /// under `namespace` (e.g. `macos`) so apps don't each need a settings file.
+    pub fn setting_get(namespace: &str, key: &str) -> anyhow::Result<Option<String>> {
+        super::settings::setting_get(namespace, key)
+    }
+
+    /// Values are strings of up to 16 KiB; store structured ones as JSON.
+    pub fn setting_set(namespace: &str, key: &str, value: &str) -> anyhow::Result<()> {
+        super::settings::setting_set(namespace, key, value)
+    }
+
+    /// Returns whether `key` was set.
+    pub fn setting_delete(namespace: &str, key: &str) -> anyhow::Result<bool> {
+        super::settings::setting_delete(namespace, key)
+    }
+
+    pub fn settings(namespace: &str) -> anyhow::Result<BTreeMap<String, String>> {
+        super::settings::settings(namespace)
+    }
+
+    /// Parses a `terminaut://` link without acting on it.
+    pub fn parse_url(url: &str) -> anyhow::Result<DeepLink> {
+        super::deeplink::parse_url(url)
+    }
+
+    /// Parses and performs a `terminaut://` link: open, search, session, or favorite. An
+    /// `open` link without a profile opens a plain shell. Links that would run a profile's
+    /// commands, restore a session, or add a favorite return [`UrlOutcome::Confirm`] unless
+    /// `confirmed`: ask the user, then call again with `confirmed`.
+    pub fn handle_url(url: &str, confirmed: bool) -> anyhow::Result<UrlOutcome> {
+        super::deeplink::handle_url(url, confirmed)
+    }
+
+    pub fn list_sessions() -> Vec<Session> {
+        super::sessions::list_sessions()
+    }
+
+    /// Stores a named session, replacing any existing one with the same name.
+    pub fn save_session(name: &str, entries: Vec<SessionEntry>) -> anyhow::Result<Session> {
+        super::sessions::save_session(name, entries)
+    }
+
+    pub fn delete_session(name: &str) -> anyhow::Result<()> {
+        super::sessions::delete_session(name)
+    }
+
+    /// The launches `restore_session` would perform, without opening any terminals.
+    pub fn session_launches(name: &str) -> anyhow::Result<Vec<LaunchRequest>> {
+        super::sessions::session_launches(name)
+    }
+
+    pub fn restore_session(name: &str) -> anyhow::Result<RestoreReport> {
+        super::sessions::restore_session(name)
+    }
+
+    pub fn list_saved_searches() -> Vec<SavedSearch> {
+        super::saved_search::list_saved_searches()
+    }
+
+    /// Stores a named search, replacing any existing one with the same name.
+    pub fn save_saved_search(
+        name: &str,
+        root: &str,
+        query: &str,
+        limit: usize,
+        options: SearchOptions,
+    ) -> anyhow::Result<SavedSearch> {
+        super::saved_search::save_saved_search(name, root, query, limit, options)
+    }
+
+    pub fn delete_saved_search(name: &str) -> anyhow::Result<()> {
+        super::saved_search::delete_saved_search(name)
+    }
+
+    pub fn run_saved_search(name: &str) -> anyhow::Result<Vec<SearchResult>> {
+        super::saved_search::run_saved_search(name)
+    }
+
+    #[cfg(feature = "fs")]
+    /// Creates a directory (and any missing parents); fails if it already exists.
+    pub fn create_dir(path: &str) -> anyhow::Result<String> {
+        let normalized = super::normalize_path(path)?;
+        Ok(super::fs_ops::create_dir(&normalized)?
+            .display()
+            .to_string())
+    }
+
+    #[cfg(feature = "fs")]
+    /// Renames `path` within its parent directory; stored paths follow the rename. A symlink
+    /// is renamed itself, not its target.
+    pub fn rename(path: &str, new_name: &str) -> anyhow::Result<String> {
+        let normalized = super::normalize_path_with(path, SymlinkPolicy::Preserve)?;
+        Ok(super::fs_ops::rename(&normalized, new_name)?
+            .display()
+            .to_string())
+    }
+
+    #[cfg(feature = "fs")]
+    /// Moves `path` into `destination`; stored paths follow the move. A symlink is moved
+    /// itself, not its target.
+    pub fn move_path(path: &str, destination: &str) -> anyhow::Result<String> {
+        let normalized = super::normalize_path_with(path, SymlinkPolicy::Preserve)?;
+        let destination = super::normalize_path(destination)?;
+        Ok(super::fs_ops::move_path(&normalized, &destination)?
+            .display()
+            .to_string())
+    }
+
+    #[cfg(feature = "fs")]
+    /// Sends `path` to the system trash (never a permanent delete). A symlink is trashed
+    /// itself, not its target.
+    pub fn trash(path: &str) -> anyhow::Result<()> {
+        let normalized = super::normalize_path_with(path, SymlinkPolicy::Preserve)?;
+        super::fs_ops::trash(&normalized)
+    }
+
+    /// Directories below `path` whose names match `query`, best first. The walk stops at
+    /// `options.time_budget_ms` and is reused by searches of the same root for
+    /// `search.cache_ttl_secs` (by default only below roots a running `indexd` in this
+    /// process watches); see [`invalidate_search_cache`].
+    pub fn search(
+        path: &str,
+        query: &str,
+        limit: usize,
+        options: &SearchOptions,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        let normalized = super::normalize_path(path)?;
+        let results = super::search::search_directories(&normalized, query, limit, options)?;
+        if options.record_history {
+            super::history::record_search(&normalized, query);
+        }
+        Ok(results)
+    }
+
+    /// Forgets the cached directory walks of [`search`] at or around `path`, or all of them,
+    /// so the next search walks again. Changes made through this library (and seen by a
+    /// running `indexd`) do this on their own; call it after changing folders some other way
+    /// within `search.cache_ttl_secs`.
+    #[cfg(feature = "fs")]
+    pub fn invalidate_search_cache(path: Option<&str>) -> anyhow::Result<()> {
+        match path {
+            Some(path) => super::candidates::invalidate(&super::normalize_path(path)?),
+            None => super::candidates::invalidate_all(),
+        }
+        Ok(())
+    }
+
+    /// Walks `path` once for a typeahead search box and keeps the directories found, so each
+    /// keystroke's [`refine_search_session`] matches in memory instead of walking again. The
+    /// candidates are as of the walk; start a new session to see later changes.
+    #[cfg(feature = "fs")]
+    pub fn start_search_session(
+        path: &str,
+        options: &SearchOptions,
+    ) -> anyhow::Result<SearchSession> {
+        let normalized = super::normalize_path(path)?;
+        super::search_session::start_search_session(normalized, options.clone())
+    }
+
+    /// [`search`] among the directories of session `id`. Typing on ("te", "ter", "term")
+    /// only re-matches what the previous query matched. Keystrokes aren't recorded in the
+    /// search history; see [`end_search_session`].
+    #[cfg(feature = "fs")]
+    pub fn refine_search_session(
+        id: Uuid,
+        query: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<SearchResult>> {
+        super::search_session::refine_search_session(id, query, limit)
+    }
+
+    /// Drops session `id` and its candidates; returns whether it was open. The last query
+    /// refined goes into the search history when the session's options say to record it.
+    #[cfg(feature = "fs")]
+    pub fn end_search_session(id: Uuid) -> bool {
+        super::search_session::end_search_session(id)
+    }
+
+    #[cfg(feature = "fs")]
+    /// Searches file contents below `path`, honoring the same ignore rules as [`search`].
+    pub fn grep(
+        path: &str,
+        pattern: &str,
+        options: &GrepOptions,
+    ) -> anyhow::Result<Vec<GrepMatch>> {
+        let normalized = super::normalize_path(path)?;
+        super::grep::grep(&normalized, pattern, options)
+    }
+
+    /// Past queries, newest first; `root` restricts them to one search root.
+    pub fn search_history(
+        root: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<SearchHistoryEntry>> {
+        super::history::search_history(root, limit)
+    }
+
+    /// Completions for the search box drawn from past queries starting with `prefix`.
+    pub fn search_suggestions(
+        root: Option<&str>,
+        prefix: &str,
+        limit: usize,
+    ) -> anyhow::Result<Vec<String>> {
+        super::history::search_suggestions(root, prefix, limit)
+    }
+
+    pub fn clear_search_history() -> anyhow::Result<()> {
+        super::history::clear_search_history()
+    }
+
+    pub fn preview(path: &str) -> anyhow::Result<DirectoryPreview> {
+        let normalized = super::normalize_path(path)?;
+        super::preview::preview(&normalized)
+    }
+
+    #[cfg(feature = "fs")]
+    /// Returns the cached size or starts a background computation and reports `Pending`.
+    pub fn dir_size(path: &str) -> anyhow::Result<DirSize> {
+        let normalized = super::normalize_path(path)?;
+        super::dir_size::dir_size(&normalized)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn dir_size_blocking(path: &str) -> anyhow::Result<DirSize> {
+        let normalized = super::normalize_path(path)?;
+        super::dir_size::dir_size_blocking(&normalized)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn cancel_dir_size(path: &str) -> anyhow::Result<bool> {
+        let normalized = super::normalize_path(path)?;
+        Ok(super::dir_size::cancel_dir_size(&normalized))
+    }
+}
+
+fn c_string_or_null(result: anyhow::Result<String>) -> *mut c_char {
+    match result {
+        Ok(value) => CString::new(value)
+            .map(|s| s.into_raw())
+            .unwrap_or(std::ptr::null_mut()),
+        Err(err) => {
+            logging::install();
+            tracing::error!("{err:#}");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+fn c_string_from_json<T: Serialize>(value: &T) -> *mut c_char {
+    match wire::to_c_json(value) {
+        Ok(json) => json.into_raw(),
+        Err(err) => {
+            logging::install();
+            tracing::error!("serialize result: {err:#}");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+fn c_null_logged(err: anyhow::Error) -> *mut c_char {
+    logging::install();
+    tracing::error!("{err:#}");
+    std::ptr::null_mut()
+}
+
+fn c_str_to_string(input: *const c_char) -> anyhow::Result<String> {
+    if input.is_null() {
+        anyhow::bail!("null pointer");
+    }
+    let c_str = unsafe { CStr::from_ptr(input) };
+    Ok(c_str.to_string_lossy().to_string())
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_version() -> *mut c_char {
+    CString::new(env!("CARGO_PKG_VERSION")).unwrap().into_raw()
+}
+
+/// [`api::capabilities`] as JSON. Check `abi_version` before calling anything else.
+#[no_mangle]
+pub extern "C" fn term_core_capabilities() -> *mut c_char {
+    c_string_from_json(&api::capabilities())
+}
+
+/// # Safety
+///
+/// `ptr` must be null or a string previously returned by one of the `term_core_*` functions,
+/// and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn term_core_string_free(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_normalize_path(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
         let normalized = normalize_path(&p)?;
         Ok(normalized.display().to_string())
     }))
@@ -555,10 +2739,124 @@ pub extern "C" fn term_core_normalize_path(path: *const c_char) -> *mut c_char {
 
 #[no_mangle]
 pub extern "C" fn term_core_list_directory(path: *const c_char) -> *mut c_char {
+    c_str_to_string(path)
+        .and_then(|p| api::list_directory(&p))
+        .map_or_else(c_null_logged, |entries| c_string_from_json(&entries))
+}
+
+/// A byte buffer returned by the C API; free it with [`term_core_bytes_free`]. `data` is null
+/// on failure.
+#[repr(C)]
+pub struct TermCoreBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// [`term_core_list_directory`] encoded as `format`: 0 for JSON (without a trailing NUL), 1
+/// for MessagePack when built with the `msgpack` feature.
+#[no_mangle]
+pub extern "C" fn term_core_list_directory_encoded(
+    path: *const c_char,
+    format: u8,
+) -> TermCoreBytes {
+    c_bytes_or_null(c_str_to_string(path).and_then(|p| {
+        let format = WireFormat::from_u8(format)?;
+        wire::to_bytes(&api::list_directory(&p)?, format)
+    }))
+}
+
+fn c_bytes_or_null(result: anyhow::Result<Box<[u8]>>) -> TermCoreBytes {
+    match result {
+        Ok(bytes) => TermCoreBytes {
+            len: bytes.len(),
+            data: Box::into_raw(bytes).cast(),
+        },
+        Err(err) => {
+            c_null_logged(err);
+            TermCoreBytes {
+                data: std::ptr::null_mut(),
+                len: 0,
+            }
+        }
+    }
+}
+
+/// # Safety
+///
+/// `bytes` must have been returned by one of the `term_core_*` functions and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn term_core_bytes_free(bytes: TermCoreBytes) {
+    if !bytes.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            bytes.data, bytes.len,
+        )));
+    }
+}
+
+/// Receives one batch of a streamed listing as a NUL-terminated JSON array that is only valid
+/// during the call, along with the caller's `ctx`. Returns 1 to continue and 0 to stop.
+pub type TermCoreBatchCallback = extern "C" fn(batch_json: *const c_char, ctx: *mut c_void) -> u8;
+
+/// [`api::list_directory_batches`]: calls `callback` on this thread with each batch of
+/// [`DirectoryEntry`] JSON until the listing ends or the callback returns 0, then returns.
+/// Returns 1 when every entry was delivered and 0 when stopped early or on error.
+#[no_mangle]
+pub extern "C" fn term_core_list_directory_stream(
+    path: *const c_char,
+    callback: Option<TermCoreBatchCallback>,
+    ctx: *mut c_void,
+) -> u8 {
+    c_str_to_string(path)
+        .and_then(|p| {
+            let callback = callback.context("null callback")?;
+            let mut failure = None;
+            let complete = api::list_directory_batches(&p, |batch| {
+                match wire::to_c_json(&batch).context("serialize directory entries") {
+                    Ok(json) => callback(json.as_ptr(), ctx) != 0,
+                    Err(err) => {
+                        failure = Some(err);
+                        false
+                    }
+                }
+            })?;
+            failure.map_or(Ok(complete), Err)
+        })
+        .map(u8::from)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_directory_with_git(path: *const c_char) -> *mut c_char {
+    c_str_to_string(path)
+        .and_then(|p| api::list_directory_with_git(&p))
+        .map_or_else(c_null_logged, |entries| c_string_from_json(&entries))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_archive(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let entries = api::list_archive(&p)?;
+        serde_json::to_string(&entries).context("serialize archive entries")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_roots() -> *mut c_char {
+    c_string_or_null(
+        roots::list_roots()
+            .and_then(|roots| serde_json::to_string(&roots).context("serialize volume roots")),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_ancestors(path: *const c_char) -> *mut c_char {
     c_string_or_null(c_str_to_string(path).and_then(|p| {
         let normalized = normalize_path(&p)?;
-        let entries = list_directory(&normalized)?;
-        serde_json::to_string(&entries).context("serialize directory entries")
+        let crumbs = breadcrumbs::ancestors(&normalized);
+        serde_json::to_string(&crumbs).context("serialize breadcrumbs")
     }))
 }
 
@@ -567,6 +2865,11 @@ pub extern "C" fn term_core_list_favorites() -> *mut c_char {
     c_string_from_json(&list_favorites())
 }
 
+#[no_mangle]
+pub extern "C" fn term_core_list_favorites_with_status() -> *mut c_char {
+    c_string_from_json(&api::list_favorites_with_status())
+}
+
 #[no_mangle]
 pub extern "C" fn term_core_add_favorite(path: *const c_char) -> u8 {
     c_str_to_string(path)
@@ -588,14 +2891,48 @@ pub extern "C" fn term_core_list_recents() -> *mut c_char {
     c_string_from_json(&list_recent_directories())
 }
 
+#[no_mangle]
+pub extern "C" fn term_core_list_recents_with_status() -> *mut c_char {
+    c_string_from_json(&api::list_recents_with_status())
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_tags_with_status() -> *mut c_char {
+    c_string_from_json(&api::list_tags_with_status())
+}
+
+/// `by` is `project`, `day`, or `week`.
+#[no_mangle]
+pub extern "C" fn term_core_recents_grouped(by: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(by).and_then(|by| {
+        let groups = api::recents_grouped(by.parse()?);
+        serde_json::to_string(&groups).context("serialize recent groups")
+    }))
+}
+
 #[no_mangle]
 pub extern "C" fn term_core_touch_recent(path: *const c_char) -> u8 {
     c_str_to_string(path)
-        .and_then(|p| touch_recent(&p))
+        .and_then(|p| touch_recent(&p, None))
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_touch_recent_from(path: *const c_char, source: *const c_char) -> u8 {
+    c_str_to_string(path)
+        .and_then(|p| api::touch_recent_from(&p, &c_str_to_string(source)?))
         .map(|_| 1u8)
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub extern "C" fn term_core_list_recents_from(source: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(source).and_then(|source| {
+        serde_json::to_string(&api::list_recents_from(&source)).context("serialize recents")
+    }))
+}
+
 #[no_mangle]
 pub extern "C" fn term_core_detect_projects(path: *const c_char) -> *mut c_char {
     c_string_or_null(c_str_to_string(path).and_then(|p| {
@@ -605,23 +2942,825 @@ pub extern "C" fn term_core_detect_projects(path: *const c_char) -> *mut c_char
     }))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[no_mangle]
+pub extern "C" fn term_core_project_info(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let info = api::project_info(&p)?;
+        serde_json::to_string(&info).context("serialize project info")
+    }))
+}
 
-    #[test]
-    fn recent_entries_sort() {
-        let mut entries = vec![
-            RecentEntry {
-                path: "b".into(),
-                last_opened_utc: 1,
-            },
-            RecentEntry {
-                path: "a".into(),
-                last_opened_utc: 5,
-            },
-        ];
-        entries.sort_by(|a, b| b.last_opened_utc.cmp(&a.last_opened_utc));
-        assert_eq!(entries[0].path, "a");
+#[no_mangle]
+pub extern "C" fn term_core_project_description(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let description = api::project_description(&p)?;
+        serde_json::to_string(&description).context("serialize project description")
+    }))
+}
+
+/// JSON `null` when the project has no git remote.
+#[no_mangle]
+pub extern "C" fn term_core_project_remote(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let remote = api::project_remote(&p)?;
+        serde_json::to_string(&remote).context("serialize project remote")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_projects() -> *mut c_char {
+    c_string_from_json(&api::list_projects())
+}
+
+/// `roots_json` is an array of paths; null or `[]` refreshes the configured roots that are due.
+#[no_mangle]
+pub extern "C" fn term_core_refresh_projects(roots_json: *const c_char) -> *mut c_char {
+    let roots_json = (!roots_json.is_null())
+        .then(|| c_str_to_string(roots_json))
+        .transpose();
+    c_string_or_null(roots_json.and_then(|roots_json| {
+        let roots: Vec<String> = match roots_json {
+            Some(json) => serde_json::from_str(&json).context("parse roots")?,
+            None => Vec::new(),
+        };
+        let report = api::refresh_projects(&roots)?;
+        serde_json::to_string(&report).context("serialize scan report")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_get_config() -> *mut c_char {
+    c_string_or_null(
+        api::get_config()
+            .and_then(|config| serde_json::to_string(&config).context("serialize config")),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_set_config(key: *const c_char, value: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(key).and_then(|key| {
+        let config = api::set_config(&key, &c_str_to_string(value)?)?;
+        serde_json::to_string(&config).context("serialize config")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_preview(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let normalized = normalize_path(&p)?;
+        let preview = preview::preview(&normalized)?;
+        serde_json::to_string(&preview).context("serialize directory preview")
+    }))
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_dir_size(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let normalized = normalize_path(&p)?;
+        let size = dir_size::dir_size(&normalized)?;
+        serde_json::to_string(&size).context("serialize directory size")
+    }))
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_cancel_dir_size(path: *const c_char) -> u8 {
+    c_str_to_string(path)
+        .and_then(|p| normalize_path(&p))
+        .map(|p| dir_size::cancel_dir_size(&p) as u8)
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "fs")]
+fn c_str_to_uuid(input: *const c_char) -> anyhow::Result<Uuid> {
+    let id = c_str_to_string(input)?;
+    Uuid::parse_str(id.trim())
+        .map_err(|err| CoreError::invalid_input(format!("invalid id {id:?}: {err}")).into())
+}
+
+/// [`api::start_search_session`] as [`SearchSession`] JSON; `options_json` may be null to use
+/// the default [`SearchOptions`].
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_start_search_session(
+    path: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    c_str_to_string(path)
+        .and_then(|p| {
+            let options: SearchOptions = if options_json.is_null() {
+                SearchOptions::default()
+            } else {
+                serde_json::from_str(&c_str_to_string(options_json)?)
+                    .context("parse search options")?
+            };
+            api::start_search_session(&p, &options)
+        })
+        .map_or_else(c_null_logged, |session| c_string_from_json(&session))
+}
+
+/// [`api::refine_search_session`]: the results for `query` in session `id`, as JSON.
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_refine_search_session(
+    id: *const c_char,
+    query: *const c_char,
+    limit: u32,
+) -> *mut c_char {
+    c_str_to_uuid(id)
+        .and_then(|id| api::refine_search_session(id, &c_str_to_string(query)?, limit as usize))
+        .map_or_else(c_null_logged, |results| c_string_from_json(&results))
+}
+
+/// Closes session `id`; 1 if it was open.
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_end_search_session(id: *const c_char) -> u8 {
+    c_str_to_uuid(id)
+        .map(api::end_search_session)
+        .map(u8::from)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// `options_json` may be null to use the default [`SearchOptions`].
+#[no_mangle]
+pub extern "C" fn term_core_search(
+    path: *const c_char,
+    query: *const c_char,
+    limit: u32,
+    options_json: *const c_char,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let query = c_str_to_string(query)?;
+        let options: SearchOptions = if options_json.is_null() {
+            SearchOptions::default()
+        } else {
+            serde_json::from_str(&c_str_to_string(options_json)?).context("parse search options")?
+        };
+        let results = api::search(&p, &query, limit as usize, &options)?;
+        serde_json::to_string(&results).context("serialize search results")
+    }))
+}
+
+/// `options_json` may be null to use the default grep options.
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_grep(
+    path: *const c_char,
+    pattern: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let pattern = c_str_to_string(pattern)?;
+        let options: GrepOptions = if options_json.is_null() {
+            GrepOptions::default()
+        } else {
+            serde_json::from_str(&c_str_to_string(options_json)?).context("parse grep options")?
+        };
+        let matches = api::grep(&p, &pattern, &options)?;
+        serde_json::to_string(&matches).context("serialize grep matches")
+    }))
+}
+
+/// `root` may be null to draw suggestions from every search root.
+#[no_mangle]
+pub extern "C" fn term_core_search_suggestions(
+    root: *const c_char,
+    prefix: *const c_char,
+    limit: u32,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(prefix).and_then(|prefix| {
+        let root = (!root.is_null())
+            .then(|| c_str_to_string(root))
+            .transpose()?;
+        let suggestions = api::search_suggestions(root.as_deref(), &prefix, limit as usize)?;
+        serde_json::to_string(&suggestions).context("serialize search suggestions")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_saved_searches() -> *mut c_char {
+    c_string_from_json(&api::list_saved_searches())
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_run_saved_search(name: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(name).and_then(|name| {
+        let results = api::run_saved_search(&name)?;
+        serde_json::to_string(&results).context("serialize search results")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_prune_state() -> *mut c_char {
+    c_string_or_null(
+        prune_state()
+            .and_then(|report| serde_json::to_string(&report).context("serialize prune report")),
+    )
+}
+
+/// Compacts the store; `drop_missing` non-zero also prunes dead paths. Returns the report as
+/// JSON, or null on failure.
+#[no_mangle]
+pub extern "C" fn term_core_compact_state(drop_missing: u8) -> *mut c_char {
+    c_string_or_null(
+        compact_state(drop_missing != 0, false).and_then(|report| {
+            serde_json::to_string(&report).context("serialize compaction report")
+        }),
+    )
+}
+
+/// Merges the state file at `path` into the store and returns the merge report as JSON.
+#[no_mangle]
+pub extern "C" fn term_core_merge_state(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        api::merge_state(&p)
+            .and_then(|report| serde_json::to_string(&report).context("serialize merge report"))
+    }))
+}
+
+/// Undoes the most recent destructive operation and returns it as JSON; null when there is
+/// nothing to undo.
+#[no_mangle]
+pub extern "C" fn term_core_undo_last() -> *mut c_char {
+    c_string_or_null(
+        api::undo_last()
+            .and_then(|entry| serde_json::to_string(&entry).context("serialize journal entry")),
+    )
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_create_dir(path: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| api::create_dir(&p)))
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_rename(path: *const c_char, new_name: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let new_name = c_str_to_string(new_name)?;
+        api::rename(&p, &new_name)
+    }))
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_move_path(
+    path: *const c_char,
+    destination: *const c_char,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|p| {
+        let destination = c_str_to_string(destination)?;
+        api::move_path(&p, &destination)
+    }))
+}
+
+#[cfg(feature = "fs")]
+#[no_mangle]
+pub extern "C" fn term_core_trash(path: *const c_char) -> u8 {
+    c_str_to_string(path)
+        .and_then(|p| api::trash(&p))
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+/// `request_json` is a serialized [`LaunchRequest`].
+#[no_mangle]
+pub extern "C" fn term_core_launch(request_json: *const c_char) -> u8 {
+    c_str_to_string(request_json)
+        .and_then(|json| serde_json::from_str(&json).context("parse launch request"))
+        .and_then(|request: LaunchRequest| api::launch(&request))
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+/// `terminal` may be null for the default terminal.
+#[no_mangle]
+pub extern "C" fn term_core_open_terminal(path: *const c_char, terminal: *const c_char) -> u8 {
+    c_str_to_string(path)
+        .and_then(|path| {
+            let terminal = (!terminal.is_null())
+                .then(|| c_str_to_string(terminal))
+                .transpose()?;
+            api::open_terminal(&path, terminal.as_deref())
+        })
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+/// Handles a link that the user hasn't confirmed: links that run or change anything come
+/// back as a `confirm` outcome; see [`api::handle_url`].
+#[no_mangle]
+pub extern "C" fn term_core_handle_url(url: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(url).and_then(|url| {
+        let outcome = api::handle_url(&url, false)?;
+        serde_json::to_string(&outcome).context("serialize url outcome")
+    }))
+}
+
+/// Handles a link after the user confirmed its `confirm` outcome.
+#[no_mangle]
+pub extern "C" fn term_core_handle_url_confirmed(url: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(url).and_then(|url| {
+        let outcome = api::handle_url(&url, true)?;
+        serde_json::to_string(&outcome).context("serialize url outcome")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_set_read_only(enabled: u8) {
+    api::set_read_only(enabled != 0);
+}
+
+/// How the store came up as JSON; the first call loads it. Check at startup so the app can
+/// warn when the state file was quarantined or cannot be read.
+#[no_mangle]
+pub extern "C" fn term_core_store_status() -> *mut c_char {
+    c_string_from_json(&api::store_status())
+}
+
+/// Usage stats for the last `weeks` weeks as JSON.
+#[no_mangle]
+pub extern "C" fn term_core_stats(weeks: u32) -> *mut c_char {
+    c_string_from_json(&api::stats(weeks as usize))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_list_sessions() -> *mut c_char {
+    c_string_from_json(&api::list_sessions())
+}
+
+/// `entries_json` is an array of `{"path": ..., "profile_id": ...}` objects.
+#[no_mangle]
+pub extern "C" fn term_core_save_session(
+    name: *const c_char,
+    entries_json: *const c_char,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(name).and_then(|name| {
+        let entries: Vec<SessionEntry> =
+            serde_json::from_str(&c_str_to_string(entries_json)?).context("parse session")?;
+        let session = api::save_session(&name, entries)?;
+        serde_json::to_string(&session).context("serialize session")
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_delete_session(name: *const c_char) -> u8 {
+    c_str_to_string(name)
+        .and_then(|name| api::delete_session(&name))
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_restore_session(name: *const c_char) -> *mut c_char {
+    c_string_or_null(c_str_to_string(name).and_then(|name| {
+        let report = api::restore_session(&name)?;
+        serde_json::to_string(&report).context("serialize restore report")
+    }))
+}
+
+/// Receives a log level (1 error .. 5 trace) and a NUL-terminated message that is only valid
+/// during the call.
+pub type TermCoreLogCallback = extern "C" fn(level: u8, message: *const c_char);
+
+/// Routes core log events at or above `level` (0 off, 1 error .. 5 trace) to `callback`;
+/// a null callback removes it. The callback may run on any thread.
+#[no_mangle]
+pub extern "C" fn term_core_set_log_callback(callback: Option<TermCoreLogCallback>, level: u8) {
+    let callback = callback.map(|callback| -> LogCallback {
+        std::sync::Arc::new(move |level: LogLevel, message: &str| {
+            if let Ok(message) = CString::new(message) {
+                callback(level as u8, message.as_ptr());
+            }
+        })
+    });
+    api::set_log_callback(callback, LogLevel::from_u8(level));
+}
+
+/// Receives a [`StoreEvent`] as NUL-terminated JSON that is only valid during the call.
+pub type TermCoreEventCallback = extern "C" fn(event_json: *const c_char);
+
+/// Calls `callback` for each change to favorites, recents, tags, or profiles; `kind` (null for
+/// all) narrows it to one of them. The callback may run on any thread. Returns an id for
+/// [`term_core_unsubscribe`], or 0 when `kind` is unknown.
+#[no_mangle]
+pub extern "C" fn term_core_subscribe(kind: *const c_char, callback: TermCoreEventCallback) -> u64 {
+    let kind = (!kind.is_null())
+        .then(|| c_str_to_string(kind).and_then(|kind| kind.parse::<EventKind>()))
+        .transpose();
+    match kind {
+        Ok(kind) => api::subscribe_callback(
+            kind,
+            std::sync::Arc::new(move |event: &StoreEvent| {
+                let json = serde_json::to_string(event).ok();
+                if let Some(json) = json.and_then(|json| CString::new(json).ok()) {
+                    callback(json.as_ptr());
+                }
+            }),
+        ),
+        Err(err) => {
+            tracing::error!("{err:#}");
+            0
+        }
+    }
+}
+
+/// Stops the callback registered under `id`; 1 if there was one.
+#[no_mangle]
+pub extern "C" fn term_core_unsubscribe(id: u64) -> u8 {
+    api::unsubscribe(id) as u8
+}
+
+/// `config_json` is a serialized [`LogConfig`], e.g.
+/// `{"stderr": "off", "file": {"level": "debug", "rotation": "daily", "max_files": 7}}`.
+#[no_mangle]
+pub extern "C" fn term_core_configure_logging(config_json: *const c_char) -> u8 {
+    c_str_to_string(config_json)
+        .and_then(|json| serde_json::from_str(&json).context("parse log config"))
+        .and_then(|config: LogConfig| api::configure_logging(&config))
+        .map(|_| 1u8)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// Puts `text` on the system clipboard; 1 on success.
+#[no_mangle]
+pub extern "C" fn term_core_copy_to_clipboard(text: *const c_char) -> u8 {
+    c_str_to_string(text)
+        .and_then(|text| api::copy_to_clipboard(&text))
+        .map(|_| 1u8)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// Stores a secret for `{secret:NAME}` references in launch commands; 1 on success.
+#[no_mangle]
+pub extern "C" fn term_core_set_secret(name: *const c_char, value: *const c_char) -> u8 {
+    c_str_to_string(name)
+        .and_then(|name| Ok((name, c_str_to_string(value)?)))
+        .and_then(|(name, value)| api::set_secret(&name, &value))
+        .map(|_| 1u8)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// The setting as a string, or null when it is not set.
+#[no_mangle]
+pub extern "C" fn term_core_setting_get(
+    namespace: *const c_char,
+    key: *const c_char,
+) -> *mut c_char {
+    let value = c_str_to_string(namespace)
+        .and_then(|namespace| api::setting_get(&namespace, &c_str_to_string(key)?));
+    match value {
+        Ok(Some(value)) => c_string_or_null(Ok(value)),
+        Ok(None) => std::ptr::null_mut(),
+        Err(err) => c_string_or_null(Err(err)),
+    }
+}
+
+/// 1 on success.
+#[no_mangle]
+pub extern "C" fn term_core_setting_set(
+    namespace: *const c_char,
+    key: *const c_char,
+    value: *const c_char,
+) -> u8 {
+    c_str_to_string(namespace)
+        .and_then(|namespace| {
+            api::setting_set(&namespace, &c_str_to_string(key)?, &c_str_to_string(value)?)
+        })
+        .map(|_| 1u8)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// 1 when the setting was removed, 0 when it was not set or on error.
+#[no_mangle]
+pub extern "C" fn term_core_setting_delete(namespace: *const c_char, key: *const c_char) -> u8 {
+    c_str_to_string(namespace)
+        .and_then(|namespace| api::setting_delete(&namespace, &c_str_to_string(key)?))
+        .map(u8::from)
+        .unwrap_or_else(|err| {
+            tracing::error!("{err:#}");
+            0
+        })
+}
+
+/// `relative_to` may be null.
+#[no_mangle]
+pub extern "C" fn term_core_display_path(
+    path: *const c_char,
+    relative_to: *const c_char,
+) -> *mut c_char {
+    let relative_to = (!relative_to.is_null())
+        .then(|| c_str_to_string(relative_to))
+        .transpose();
+    c_string_or_null(
+        c_str_to_string(path).and_then(|path| api::display_path(&path, relative_to?.as_deref())),
+    )
+}
+
+/// `target` is `windows` or `wsl`; `distro` may be null.
+#[no_mangle]
+pub extern "C" fn term_core_translate_path(
+    path: *const c_char,
+    target: *const c_char,
+    distro: *const c_char,
+) -> *mut c_char {
+    let distro = (!distro.is_null())
+        .then(|| c_str_to_string(distro))
+        .transpose();
+    c_string_or_null(c_str_to_string(path).and_then(|path| {
+        let target: PathFlavor = c_str_to_string(target)?.parse()?;
+        api::translate_path(&path, target, distro?.as_deref())
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_wsl_distros() -> *mut c_char {
+    c_string_from_json(&api::wsl_distros())
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_editors() -> *mut c_char {
+    c_string_from_json(&api::editors())
+}
+
+/// `editor` may be null to use the preferred or first installed editor. Returns the editor
+/// used as JSON.
+#[no_mangle]
+pub extern "C" fn term_core_open_in_editor(
+    path: *const c_char,
+    editor: *const c_char,
+) -> *mut c_char {
+    c_string_or_null(c_str_to_string(path).and_then(|path| {
+        let editor = (!editor.is_null())
+            .then(|| c_str_to_string(editor))
+            .transpose()?;
+        let used = api::open_in_editor(&path, editor.as_deref())?;
+        serde_json::to_string(&used).context("serialize editor")
+    }))
+}
+
+/// `editor` may be null to remove the binding.
+#[no_mangle]
+pub extern "C" fn term_core_set_preferred_editor(path: *const c_char, editor: *const c_char) -> u8 {
+    c_str_to_string(path)
+        .and_then(|path| {
+            let editor = (!editor.is_null())
+                .then(|| c_str_to_string(editor))
+                .transpose()?;
+            api::set_preferred_editor(&path, editor.as_deref())
+        })
+        .map(|_| 1u8)
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_default_terminal() -> *mut c_char {
+    c_string_from_json(&api::default_terminal())
+}
+
+#[no_mangle]
+pub extern "C" fn term_core_default_shell() -> *mut c_char {
+    c_string_from_json(&api::default_shell())
+}
+
+/// Generic entry point: `method` names an `api` function and `params_json` is an object of
+/// its named parameters (or null). Returns `{"result": ...}` on success and
+/// `{"error": {"code", "message", "hint"}}` on failure, so unlike the typed functions it
+/// never returns null for a failed call. Both carry the store `generation` after the call;
+/// pass it back as the `expected_generation` parameter of a mutation to have it fail with a
+/// `conflict` error if anything changed the store in between.
+#[no_mangle]
+pub extern "C" fn term_core_invoke(
+    method: *const c_char,
+    params_json: *const c_char,
+) -> *mut c_char {
+    let result = c_str_to_string(method).and_then(|method| {
+        let params = if params_json.is_null() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_str(&c_str_to_string(params_json)?).context("parse params")?
+        };
+        api::invoke(&method, params)
+    });
+    c_string_from_json(&invoke::envelope(result))
+}
+
+/// [`term_core_invoke`] with `params` (`params_len` bytes, or null for none) and the response
+/// encoded as `format`: 0 JSON, 1 MessagePack, 2 CBOR. Which ones this build has is listed
+/// under `formats` in [`term_core_capabilities`]. Free the result with
+/// [`term_core_bytes_free`]; its `data` is null only for a format the build lacks.
+///
+/// # Safety
+///
+/// `params` must be null or point to `params_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn term_core_invoke_encoded(
+    method: *const c_char,
+    params: *const u8,
+    params_len: usize,
+    format: u8,
+) -> TermCoreBytes {
+    let params = match params.is_null() {
+        true => &[][..],
+        false => std::slice::from_raw_parts(params, params_len),
+    };
+    let response = c_str_to_string(method)
+        .and_then(|method| invoke::invoke_encoded(&method, params, WireFormat::from_u8(format)?));
+    c_bytes_or_null(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorites(paths: &[&str]) -> PersistedState {
+        PersistedState {
+            favorites: paths.iter().map(|path| path.to_string()).collect(),
+            ..PersistedState::default()
+        }
+    }
+
+    #[test]
+    fn dedupe_keeps_the_favorites_order() {
+        let mut state = favorites(&["/z", "/a", "/z", "/m", "/a"]);
+        dedupe_state(&mut state);
+        assert_eq!(state.favorites, ["/z", "/a", "/m"]);
+    }
+
+    #[test]
+    fn dedupe_drops_sources_of_paths_no_longer_favorites() {
+        let mut state = favorites(&["/a"]);
+        for path in ["/a", "/gone"] {
+            state
+                .favorite_sources
+                .insert(path.to_string(), String::from("gtk"));
+        }
+        dedupe_state(&mut state);
+        assert_eq!(state.favorite_sources.keys().collect::<Vec<_>>(), ["/a"]);
+    }
+
+    #[test]
+    fn existing_paths_are_not_expanded() {
+        let dir = std::env::temp_dir().join(format!("terminaut-literal-{}", Uuid::new_v4()));
+        for name in ["$RECYCLE.BIN", "dir%foo%"] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            let normalized =
+                normalize_path_with(&path.display().to_string(), SymlinkPolicy::Preserve).unwrap();
+            assert_eq!(normalized, path);
+        }
+        let missing = dir.join("$TERMINAUT_UNSET_FOR_TEST").display().to_string();
+        let err = normalize_path_with(&missing, SymlinkPolicy::Preserve).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_save_over_a_newer_generation_conflicts_and_reloads() {
+        let dir = std::env::temp_dir().join(format!("terminaut-generation-{}", Uuid::new_v4()));
+        let backend = FileBackend::new(dir.join("state.json"));
+        let (first, second) = (Store::default(), Store::default());
+        first.save(&backend, &mut favorites(&["/a"])).unwrap();
+        assert_eq!(first.generation(), 1);
+
+        let mut state = favorites(&["/b"]);
+        let err = second.save(&backend, &mut state).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::Conflict);
+        assert_eq!(state.favorites, ["/a"]);
+        assert_eq!(second.generation(), 1);
+
+        state.favorites.push(String::from("/b"));
+        second.save(&backend, &mut state).unwrap();
+        let saved = Store::load_saved(&backend).unwrap().unwrap();
+        assert_eq!(
+            (saved.generation, saved.favorites),
+            (2, vec!["/a".into(), "/b".into()])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reload_takes_only_newer_generations() {
+        let backend = MemoryBackend::default();
+        let (first, second) = (Store::default(), Store::default());
+        first.save(&backend, &mut favorites(&["/a"])).unwrap();
+        second.reload(&backend).unwrap();
+        assert_eq!(second.generation(), 1);
+        assert_eq!(second.inner.lock().favorites, ["/a"]);
+
+        second.inner.lock().favorites.clear();
+        second.reload(&backend).unwrap();
+        assert!(second.inner.lock().favorites.is_empty());
+    }
+
+    struct FailingBackend;
+
+    impl StateBackend for FailingBackend {
+        fn load(&self) -> anyhow::Result<Option<String>> {
+            Ok(None)
+        }
+
+        fn save(&self, _: &str) -> anyhow::Result<()> {
+            anyhow::bail!("disk full")
+        }
+
+        fn location(&self) -> String {
+            String::from("nowhere")
+        }
+    }
+
+    #[test]
+    fn failed_saves_keep_the_generation() {
+        let store = Store::default();
+        assert!(store
+            .save(&FailingBackend, &mut favorites(&["/a"]))
+            .is_err());
+        assert_eq!(store.generation(), 0);
+    }
+
+    #[test]
+    fn saves_check_the_expected_generation() {
+        let (backend, store) = (MemoryBackend::default(), Store::default());
+        {
+            let _expected = ExpectedGeneration::new(5);
+            let err = store.save(&backend, &mut favorites(&["/a"])).unwrap_err();
+            assert_eq!(error_code(&err), ErrorCode::Conflict);
+            assert_eq!(store.generation(), 0);
+        }
+        let _expected = ExpectedGeneration::new(0);
+        store.save(&backend, &mut favorites(&["/a"])).unwrap();
+        store.save(&backend, &mut favorites(&["/a", "/b"])).unwrap();
+        assert_eq!(store.generation(), 2);
+    }
+
+    #[test]
+    fn recent_entries_sort() {
+        let mut entries = [
+            RecentEntry {
+                path: "b".into(),
+                last_opened_utc: 1,
+                ..RecentEntry::default()
+            },
+            RecentEntry {
+                path: "a".into(),
+                last_opened_utc: 5,
+                ..RecentEntry::default()
+            },
+        ];
+        entries.sort_by_key(|entry| Reverse(entry.last_opened_utc));
+        assert_eq!(entries[0].path, "a");
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn relocated_paths_follow_moves() {
+        let from = Path::new("/dev/old");
+        let to = Path::new("/dev/new");
+        assert_eq!(relocated("/dev/old", from, to).as_deref(), Some("/dev/new"));
+        assert_eq!(
+            relocated("/dev/old/api", from, to).as_deref(),
+            Some("/dev/new/api")
+        );
+        assert_eq!(relocated("/dev/older", from, to), None);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn relocating_reports_whether_anything_moved() {
+        let mut state = favorites(&["/dev/old/api", "/dev/other"]);
+        state
+            .editors
+            .insert(String::from("/dev/old"), String::from("zed"));
+        assert!(!relocate_state(
+            &mut state,
+            Path::new("/dev/elsewhere"),
+            Path::new("/dev/new")
+        ));
+        assert_eq!(state.favorites, ["/dev/old/api", "/dev/other"]);
+        assert!(relocate_state(
+            &mut state,
+            Path::new("/dev/old"),
+            Path::new("/dev/new")
+        ));
+        assert_eq!(state.favorites, ["/dev/new/api", "/dev/other"]);
+        assert_eq!(state.editors.keys().collect::<Vec<_>>(), ["/dev/new"]);
     }
 }
@@ -0,0 +1,104 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CoreError, DirectoryEntry, RecentEntry};
+
+/// Order of [`crate::api::list_directory_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    /// Case-insensitive by name.
+    #[default]
+    Name,
+    /// By the decayed visits of recents at or below each entry, so the subdirectory opened
+    /// most comes first. Entries never opened follow by name.
+    Frecency,
+}
+
+impl FromStr for ListSort {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "frecency" => Ok(Self::Frecency),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown listing sort {other:?} (name, frecency)"
+            ))),
+        }
+    }
+}
+
+/// Options of [`crate::api::list_directory_with`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListOptions {
+    pub sort: ListSort,
+    /// Annotate entries with their `git status` state, as
+    /// [`crate::api::list_directory_with_git`] does.
+    pub git: bool,
+}
+
+/// Frecency of everything opened at or below `path`, as of `now`.
+fn subtree_frecency(path: &Path, recents: &[RecentEntry], now: i64) -> f64 {
+    recents
+        .iter()
+        .filter(|recent| Path::new(&recent.path).starts_with(path))
+        .map(|recent| recent.frecency(now))
+        .sum()
+}
+
+/// Sorts `entries` (already by name) by [`ListSort::Frecency`]; the sort is stable, so ties
+/// keep name order.
+pub(crate) fn sort_by_frecency(entries: &mut [DirectoryEntry], recents: &[RecentEntry], now: i64) {
+    let mut scored: Vec<(f64, DirectoryEntry)> = entries
+        .iter()
+        .map(|entry| {
+            let score = match entry.is_dir {
+                true => subtree_frecency(Path::new(&entry.path), recents, now),
+                false => 0.0,
+            };
+            (score, entry.clone())
+        })
+        .collect();
+    scored.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    for (slot, (_, entry)) in entries.iter_mut().zip(scored) {
+        *slot = entry;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frecency_floats_opened_subtrees() {
+        let dir = |name: &str| DirectoryEntry {
+            name: name.to_string(),
+            path: format!("/work/{name}"),
+            is_dir: true,
+            mod_date: None,
+            git_status: None,
+        };
+        let mut entries = vec![dir("alpha"), dir("beta"), dir("gamma"), dir("zeta")];
+        let now = 1_800_000_000;
+        let recent = |path: &str, score: f64| RecentEntry {
+            path: path.to_string(),
+            last_opened_utc: now,
+            score,
+            ..RecentEntry::default()
+        };
+        let recents = [
+            recent("/work/gamma/src", 3.0),
+            recent("/work/gamma", 1.0),
+            recent("/work/beta", 2.0),
+            recent("/work/betamax", 9.0),
+        ];
+        sort_by_frecency(&mut entries, &recents, now);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["gamma", "beta", "alpha", "zeta"]);
+    }
+}
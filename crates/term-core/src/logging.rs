@@ -0,0 +1,345 @@
+use std::fmt::{self, Write as _};
+#[cfg(feature = "fs")]
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Once};
+
+#[cfg(feature = "fs")]
+use chrono::{SecondsFormat, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+use crate::CoreError;
+
+/// Most verbose level a log sink receives. The numeric values are what the FFI takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    /// Values above 5 mean [`LogLevel::Trace`].
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Off,
+            1 => Self::Error,
+            2 => Self::Warn,
+            3 => Self::Info,
+            4 => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    fn of(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => Self::Error,
+            Level::WARN => Self::Warn,
+            Level::INFO => Self::Info,
+            Level::DEBUG => Self::Debug,
+            Level::TRACE => Self::Trace,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warn" | "warning" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown log level {other:?} (off, error, warn, info, debug, trace)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+/// Rotating log files named `terminaut.<date>.log`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFile {
+    /// Defaults to a `logs` directory next to the state file.
+    pub directory: Option<PathBuf>,
+    pub level: LogLevel,
+    pub rotation: LogRotation,
+    /// Older files beyond this count are deleted on rotation.
+    pub max_files: usize,
+}
+
+impl Default for LogFile {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            level: LogLevel::Info,
+            rotation: LogRotation::default(),
+            max_files: 7,
+        }
+    }
+}
+
+/// Where core log events go besides the callback set with [`set_log_callback`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub stderr: LogLevel,
+    pub file: Option<LogFile>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            stderr: LogLevel::Warn,
+            file: None,
+        }
+    }
+}
+
+/// Receives each event's level and formatted message (`target: message key=value`).
+pub type LogCallback = Arc<dyn Fn(LogLevel, &str) + Send + Sync>;
+
+#[cfg(feature = "fs")]
+type FileSink = Mutex<tracing_appender::rolling::RollingFileAppender>;
+#[cfg(not(feature = "fs"))]
+type FileSink = Mutex<std::io::Sink>;
+
+struct Sinks {
+    stderr: LogLevel,
+    callback: Option<(LogLevel, LogCallback)>,
+    file: Option<(LogLevel, FileSink)>,
+}
+
+impl Sinks {
+    fn max_level(&self) -> LogLevel {
+        let callback = self
+            .callback
+            .as_ref()
+            .map_or(LogLevel::Off, |(level, _)| *level);
+        let file = self
+            .file
+            .as_ref()
+            .map_or(LogLevel::Off, |(level, _)| *level);
+        self.stderr.max(callback).max(file)
+    }
+}
+
+static SINKS: Lazy<RwLock<Sinks>> = Lazy::new(|| {
+    RwLock::new(Sinks {
+        stderr: LogConfig::default().stderr,
+        callback: None,
+        file: None,
+    })
+});
+
+static INSTALL: Once = Once::new();
+
+/// Installs the core's subscriber as the global default, once. This happens on the first use
+/// of the store or config, so warnings reach stderr without any setup. When the host already
+/// set its own, events go there instead and the sinks here stay unused.
+pub(crate) fn install() {
+    INSTALL.call_once(|| {
+        let _ = tracing::subscriber::set_global_default(Registry::default().with(SinkLayer));
+    });
+}
+
+pub(crate) fn set_log_callback(callback: Option<LogCallback>, level: LogLevel) {
+    install();
+    SINKS.write().callback = callback
+        .filter(|_| level != LogLevel::Off)
+        .map(|callback| (level, callback));
+}
+
+pub(crate) fn configure_logging(config: &LogConfig) -> anyhow::Result<()> {
+    install();
+    let file = match &config.file {
+        Some(file) if file.level != LogLevel::Off => Some((file.level, open_log_file(file)?)),
+        _ => None,
+    };
+    let mut sinks = SINKS.write();
+    sinks.stderr = config.stderr;
+    sinks.file = file;
+    Ok(())
+}
+
+#[cfg(feature = "fs")]
+fn open_log_file(file: &LogFile) -> anyhow::Result<FileSink> {
+    use anyhow::Context;
+    use tracing_appender::rolling::{Builder, Rotation};
+
+    let directory = file.directory.clone().unwrap_or_else(default_log_dir);
+    let rotation = match file.rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    let appender = Builder::new()
+        .rotation(rotation)
+        .filename_prefix("terminaut")
+        .filename_suffix("log")
+        .max_log_files(file.max_files.max(1))
+        .build(&directory)
+        .with_context(|| format!("failed to open log directory {}", directory.display()))?;
+    Ok(Mutex::new(appender))
+}
+
+#[cfg(not(feature = "fs"))]
+fn open_log_file(_file: &LogFile) -> anyhow::Result<FileSink> {
+    anyhow::bail!(CoreError::invalid_input(
+        "file logging is not available in this build"
+    ))
+}
+
+#[cfg(feature = "fs")]
+fn default_log_dir() -> PathBuf {
+    let mut dir = crate::Store::default_store_path();
+    dir.set_file_name("logs");
+    dir
+}
+
+/// Collects an event's `message` and remaining fields into one line.
+#[derive(Default)]
+struct Message {
+    message: String,
+    fields: String,
+}
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+struct SinkLayer;
+
+impl<S: Subscriber> Layer<S> for SinkLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = LogLevel::of(event.metadata().level());
+        let callback = {
+            let sinks = SINKS.read();
+            if level > sinks.max_level() {
+                return;
+            }
+            let mut message = Message::default();
+            event.record(&mut message);
+            let line = format!(
+                "{}: {}{}",
+                event.metadata().target(),
+                message.message,
+                message.fields
+            );
+            if level <= sinks.stderr {
+                eprintln!("{} {line}", level.label());
+            }
+            #[cfg(feature = "fs")]
+            if let Some((_, file)) = sinks.file.as_ref().filter(|(max, _)| level <= *max) {
+                let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+                let _ = writeln!(file.lock(), "{timestamp} {} {line}", level.label());
+            }
+            sinks
+                .callback
+                .as_ref()
+                .filter(|(max, _)| level <= *max)
+                .map(|(_, callback)| (callback.clone(), line))
+        };
+        // Called without the sink lock so the callback may reconfigure logging.
+        if let Some((callback, line)) = callback {
+            callback(level, &line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_parse_and_order() {
+        assert_eq!("warning".parse::<LogLevel>().unwrap(), LogLevel::Warn);
+        assert!("loud".parse::<LogLevel>().is_err());
+        assert_eq!(LogLevel::from_u8(9), LogLevel::Trace);
+        assert!(LogLevel::Error < LogLevel::Debug);
+    }
+
+    #[test]
+    fn loading_the_config_installs_the_subscriber() {
+        drop(crate::config::config());
+        assert!(INSTALL.is_completed());
+        assert!(tracing::dispatcher::has_been_set());
+    }
+
+    #[test]
+    fn callback_receives_events_at_or_above_its_level() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        set_log_callback(
+            Some(Arc::new(move |level, line: &str| {
+                sink.lock().push((level, line.to_string()))
+            })),
+            LogLevel::Info,
+        );
+        tracing::info!(target: "logging_test", path = "/tmp", "opened");
+        tracing::debug!(target: "logging_test", "hidden");
+        set_log_callback(None, LogLevel::Off);
+        let seen: Vec<_> = seen
+            .lock()
+            .iter()
+            .filter(|(_, line)| line.starts_with("logging_test"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            seen,
+            [(
+                LogLevel::Info,
+                String::from("logging_test: opened path=/tmp")
+            )]
+        );
+    }
+}
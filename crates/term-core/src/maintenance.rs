@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::PersistedState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreSection {
+    Favorite,
+    Recent,
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PruneAction {
+    Drop,
+    Rewrite { to: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneChange {
+    pub section: StoreSection,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(flatten)]
+    pub action: PruneAction,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub dropped: usize,
+    pub rewritten: usize,
+    pub changes: Vec<PruneChange>,
+}
+
+/// What [`crate::api::compact_state`] did, or with `dry_run` would do.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactReport {
+    pub dry_run: bool,
+    /// Favorites, recents, and tags merged into an equal entry.
+    pub duplicates: usize,
+    /// Oldest recents dropped to stay within `recents.cap`.
+    pub trimmed: usize,
+    /// Dead paths dropped or rewritten, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pruned: Option<PruneReport>,
+    /// Size of the saved state before and after, in bytes.
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// Rebuilds `path` component by component, matching missing components case-insensitively
+/// against their parent's entries.
+fn case_variant(path: &Path) -> Option<PathBuf> {
+    let mut current = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                let candidate = current.join(name);
+                if candidate.symlink_metadata().is_ok() {
+                    current = candidate;
+                    continue;
+                }
+                let wanted = name.to_string_lossy().to_lowercase();
+                let matched = std::fs::read_dir(&current)
+                    .ok()?
+                    .flatten()
+                    .find(|entry| entry.file_name().to_string_lossy().to_lowercase() == wanted)?;
+                current.push(matched.file_name());
+            }
+            other => current.push(other.as_os_str()),
+        }
+    }
+    Some(current)
+}
+
+/// Resolves the deepest existing ancestor through its symlinks and re-appends the rest, which
+/// recovers paths stored via a symlinked parent that has since been re-pointed.
+fn symlink_variant(path: &Path) -> Option<PathBuf> {
+    let ancestor = path.ancestors().skip(1).find(|a| a.exists())?;
+    let canonical = std::fs::canonicalize(ancestor).ok()?;
+    if canonical == ancestor {
+        return None;
+    }
+    let candidate = canonical.join(path.strip_prefix(ancestor).ok()?);
+    candidate.exists().then_some(candidate)
+}
+
+/// `None` when the path still exists; otherwise the action to take for it.
+fn verdict(path: &str) -> Option<PruneAction> {
+    let path = Path::new(path);
+    if path.exists() {
+        return None;
+    }
+    let variant = case_variant(path)
+        .filter(|candidate| candidate.exists())
+        .or_else(|| symlink_variant(path));
+    Some(match variant {
+        Some(to) => PruneAction::Rewrite {
+            to: to.display().to_string(),
+        },
+        None => PruneAction::Drop,
+    })
+}
+
+struct Pruner {
+    verdicts: HashMap<String, Option<PruneAction>>,
+    report: PruneReport,
+}
+
+impl Pruner {
+    /// Records the change for `path` and returns its replacement: `None` to drop the entry.
+    fn check(&mut self, section: StoreSection, path: &str, tag: Option<&str>) -> Option<String> {
+        let action = self
+            .verdicts
+            .entry(path.to_string())
+            .or_insert_with(|| verdict(path))
+            .clone();
+        let Some(action) = action else {
+            return Some(path.to_string());
+        };
+        let replacement = match &action {
+            PruneAction::Drop => {
+                self.report.dropped += 1;
+                None
+            }
+            PruneAction::Rewrite { to } => {
+                self.report.rewritten += 1;
+                Some(to.clone())
+            }
+        };
+        self.report.changes.push(PruneChange {
+            section,
+            path: path.to_string(),
+            tag: tag.map(str::to_string),
+            action,
+        });
+        replacement
+    }
+}
+
+/// Applies prune decisions to `state` in place and reports every change made.
+pub(crate) fn prune(state: &mut PersistedState) -> PruneReport {
+    let mut pruner = Pruner {
+        verdicts: HashMap::new(),
+        report: PruneReport::default(),
+    };
+    // Import sources follow their favorite to its rewritten path, or go with it.
+    let mut sources = std::mem::take(&mut state.favorite_sources);
+    state.favorites = std::mem::take(&mut state.favorites)
+        .into_iter()
+        .filter_map(|path| {
+            let kept = pruner.check(StoreSection::Favorite, &path, None)?;
+            if let Some(source) = sources.remove(&path) {
+                state.favorite_sources.insert(kept.clone(), source);
+            }
+            Some(kept)
+        })
+        .collect();
+    state.recents = std::mem::take(&mut state.recents)
+        .into_iter()
+        .filter_map(|mut entry| {
+            entry.path = pruner.check(StoreSection::Recent, &entry.path, None)?;
+            Some(entry)
+        })
+        .collect();
+    state.tags = std::mem::take(&mut state.tags)
+        .into_iter()
+        .filter_map(|mut tag| {
+            tag.path = pruner.check(StoreSection::Tag, &tag.path, Some(&tag.tag))?;
+            Some(tag)
+        })
+        .collect();
+    pruner.report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_paths_are_kept() {
+        let existing = std::env::temp_dir().display().to_string();
+        assert_eq!(verdict(&existing), None);
+        assert_eq!(
+            verdict("/definitely/not/a/real/terminaut/path"),
+            Some(PruneAction::Drop)
+        );
+    }
+
+    #[test]
+    fn pruned_favorites_lose_their_source() {
+        let existing = std::env::temp_dir().display().to_string();
+        let missing = String::from("/definitely/not/a/real/terminaut/path");
+        let mut state = PersistedState {
+            favorites: vec![existing.clone(), missing.clone()],
+            ..PersistedState::default()
+        };
+        for path in [&existing, &missing] {
+            state
+                .favorite_sources
+                .insert(path.clone(), String::from("gtk"));
+        }
+        prune(&mut state);
+        assert_eq!(state.favorites, [existing.as_str()]);
+        assert_eq!(
+            state.favorite_sources.keys().collect::<Vec<_>>(),
+            [&existing]
+        );
+    }
+
+    #[test]
+    fn case_variants_are_rewritten() {
+        let dir = std::env::temp_dir().join("terminaut-prune-CaseTest");
+        std::fs::create_dir_all(&dir).unwrap();
+        let stored = dir.with_file_name("terminaut-prune-casetest");
+        let action = verdict(&stored.display().to_string());
+        std::fs::remove_dir_all(&dir).ok();
+        if cfg!(any(target_os = "macos", windows)) {
+            // Case-insensitive filesystems report the stored path as existing.
+            assert!(matches!(action, None | Some(PruneAction::Rewrite { .. })));
+        } else {
+            assert_eq!(
+                action,
+                Some(PruneAction::Rewrite {
+                    to: dir.display().to_string()
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::str::FromStr;
+
+use std::cell::RefCell;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use nucleo_matcher::pattern::{self, AtomKind, Normalization, Pattern};
+use nucleo_matcher::Utf32Str;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::{canonical_combining_class, is_combining_mark};
+use unicode_normalization::UnicodeNormalization as _;
+
+use crate::{config, CoreError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseMatching {
+    /// Case-insensitive unless the query contains an uppercase character.
+    #[default]
+    Smart,
+    Insensitive,
+    Sensitive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnicodeNormalization {
+    #[default]
+    None,
+    Nfc,
+    Nfkd,
+}
+
+/// The fuzzy matching algorithm behind search and [`crate::api::fuzzy_filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatcherKind {
+    /// `fuzzy-matcher`'s skim algorithm; the query is one needle, spaces included.
+    #[default]
+    Skim,
+    /// The algorithm of the `nucleo` picker (fzf scoring, faster on large candidate sets).
+    /// Space-separated words in the query must each match.
+    Nucleo,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchOptions {
+    pub case: CaseMatching,
+    pub normalization: UnicodeNormalization,
+    /// Strip accents so `tést` and `test` match each other. Implies NFKD decomposition.
+    pub fold_diacritics: bool,
+    /// Overrides `search.matcher` from the config.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<MatcherKind>,
+}
+
+impl FromStr for CaseMatching {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "smart" => Ok(Self::Smart),
+            "insensitive" | "ignore" => Ok(Self::Insensitive),
+            "sensitive" | "respect" => Ok(Self::Sensitive),
+            other => anyhow::bail!("unknown case mode {other:?} (smart, insensitive, sensitive)"),
+        }
+    }
+}
+
+impl FromStr for MatcherKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "skim" => Ok(Self::Skim),
+            "nucleo" => Ok(Self::Nucleo),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown matcher {other:?} (skim, nucleo)"
+            ))),
+        }
+    }
+}
+
+impl FromStr for UnicodeNormalization {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "nfc" => Ok(Self::Nfc),
+            "nfkd" => Ok(Self::Nfkd),
+            other => anyhow::bail!("unknown normalization {other:?} (none, nfc, nfkd)"),
+        }
+    }
+}
+
+impl MatchOptions {
+    fn rewrites_text(&self) -> bool {
+        self.fold_diacritics || self.normalization != UnicodeNormalization::None
+    }
+
+    fn normalize_segment(&self, segment: &str, out: &mut String) {
+        if self.fold_diacritics {
+            out.extend(segment.nfkd().filter(|c| !is_combining_mark(*c)));
+        } else {
+            match self.normalization {
+                UnicodeNormalization::None => out.push_str(segment),
+                UnicodeNormalization::Nfc => out.extend(segment.nfc()),
+                UnicodeNormalization::Nfkd => out.extend(segment.nfkd()),
+            }
+        }
+    }
+
+    /// Normalizes `text`, returning the new string plus, for each of its characters, the index
+    /// of the character in `text` it came from. Text is processed one starter-led segment at a
+    /// time so composition and decomposition never cross segment boundaries.
+    fn normalize(&self, text: &str) -> (String, Vec<usize>) {
+        let mut normalized = String::with_capacity(text.len());
+        let mut origins = Vec::with_capacity(text.len());
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut end = start + 1;
+            while end < chars.len() && canonical_combining_class(chars[end].1) != 0 {
+                end += 1;
+            }
+            let byte_start = chars[start].0;
+            let byte_end = chars.get(end).map(|(i, _)| *i).unwrap_or(text.len());
+            let before = normalized.chars().count();
+            self.normalize_segment(&text[byte_start..byte_end], &mut normalized);
+            let added = normalized.chars().count() - before;
+            origins.extend(std::iter::repeat_n(start, added));
+            start = end;
+        }
+        (normalized, origins)
+    }
+}
+
+/// A fuzzy matching backend holding an already normalized query.
+pub(crate) trait Matcher: Send {
+    /// Returns the score and matched character positions within `text`, or `None` when the
+    /// query does not match.
+    fn fuzzy_indices(&self, text: &str) -> Option<(i64, Vec<usize>)>;
+}
+
+struct SkimBackend {
+    matcher: SkimMatcherV2,
+    query: String,
+}
+
+impl SkimBackend {
+    fn new(query: &str, case: CaseMatching) -> Self {
+        let matcher = match case {
+            CaseMatching::Smart => SkimMatcherV2::default().smart_case(),
+            CaseMatching::Insensitive => SkimMatcherV2::default().ignore_case(),
+            CaseMatching::Sensitive => SkimMatcherV2::default().respect_case(),
+        };
+        Self {
+            matcher,
+            query: query.to_string(),
+        }
+    }
+}
+
+impl Matcher for SkimBackend {
+    fn fuzzy_indices(&self, text: &str) -> Option<(i64, Vec<usize>)> {
+        self.matcher.fuzzy_indices(text, &self.query)
+    }
+}
+
+struct NucleoBackend {
+    pattern: Pattern,
+    /// Nucleo matches through `&mut` scratch space, reused across candidates.
+    matcher: RefCell<nucleo_matcher::Matcher>,
+}
+
+impl NucleoBackend {
+    fn new(query: &str, case: CaseMatching) -> Self {
+        let case = match case {
+            CaseMatching::Smart => pattern::CaseMatching::Smart,
+            CaseMatching::Insensitive => pattern::CaseMatching::Ignore,
+            CaseMatching::Sensitive => pattern::CaseMatching::Respect,
+        };
+        // Normalization already happened in `MatchOptions::normalize`, on both sides.
+        Self {
+            pattern: Pattern::new(query, case, Normalization::Never, AtomKind::Fuzzy),
+            matcher: RefCell::new(nucleo_matcher::Matcher::new(
+                nucleo_matcher::Config::DEFAULT,
+            )),
+        }
+    }
+}
+
+impl Matcher for NucleoBackend {
+    fn fuzzy_indices(&self, text: &str) -> Option<(i64, Vec<usize>)> {
+        let mut buf = Vec::new();
+        let mut indices = Vec::new();
+        let score = self.pattern.indices(
+            Utf32Str::new(text, &mut buf),
+            &mut self.matcher.borrow_mut(),
+            &mut indices,
+        )?;
+        // Each word appends its own positions.
+        indices.sort_unstable();
+        indices.dedup();
+        Some((
+            score.into(),
+            indices.into_iter().map(|i| i as usize).collect(),
+        ))
+    }
+}
+
+/// A query prepared once and matched against many candidate names.
+pub(crate) struct QueryMatcher {
+    matcher: Box<dyn Matcher>,
+    options: MatchOptions,
+}
+
+impl QueryMatcher {
+    pub(crate) fn new(query: &str, options: &MatchOptions) -> Self {
+        let query = if options.rewrites_text() {
+            options.normalize(query).0
+        } else {
+            query.to_string()
+        };
+        let kind = options
+            .matcher
+            .unwrap_or_else(|| config::config().search.matcher);
+        let matcher: Box<dyn Matcher> = match kind {
+            MatcherKind::Skim => Box::new(SkimBackend::new(&query, options.case)),
+            MatcherKind::Nucleo => Box::new(NucleoBackend::new(&query, options.case)),
+        };
+        Self {
+            matcher,
+            options: options.clone(),
+        }
+    }
+
+    /// Returns the score and matched character positions within the original `name`.
+    pub(crate) fn match_name(&self, name: &str) -> Option<(i64, Vec<usize>)> {
+        if !self.options.rewrites_text() {
+            return self.matcher.fuzzy_indices(name);
+        }
+        let (normalized, origins) = self.options.normalize(name);
+        let (score, indices) = self.matcher.fuzzy_indices(&normalized)?;
+        let mut mapped: Vec<usize> = indices.into_iter().map(|i| origins[i]).collect();
+        mapped.dedup();
+        Some((score, mapped))
+    }
+}
+
+/// Whether `text` contains every whitespace-separated word of `query`, ignoring case. Used
+/// for prose such as descriptions and notes, where fuzzy matching would accept nearly anything.
+pub(crate) fn contains_words(text: &str, query: &str) -> bool {
+    let text = text.to_lowercase();
+    let mut words = query.split_whitespace().peekable();
+    words.peek().is_some() && words.all(|word| text.contains(&word.to_lowercase()))
+}
+
+/// One candidate accepted by [`fuzzy_filter`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    pub text: String,
+    pub score: i64,
+    /// Matched character positions within `text`.
+    pub indices: Vec<usize>,
+}
+
+/// Matches `query` against in-memory candidates with the same scoring as directory search,
+/// best first. Needs no filesystem, so it also serves the wasm build.
+pub(crate) fn fuzzy_filter(
+    query: &str,
+    candidates: &[String],
+    options: &MatchOptions,
+) -> Vec<FuzzyMatch> {
+    let matcher = QueryMatcher::new(query, options);
+    let mut matches: Vec<FuzzyMatch> = candidates
+        .iter()
+        .filter_map(|text| {
+            let (score, indices) = matcher.match_name(text)?;
+            Some(FuzzyMatch {
+                text: text.clone(),
+                score,
+                indices,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.text.cmp(&b.text)));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diacritic_folding_matches_both_ways() {
+        let options = MatchOptions {
+            fold_diacritics: true,
+            ..MatchOptions::default()
+        };
+        assert!(QueryMatcher::new("test", &options)
+            .match_name("tést")
+            .is_some());
+        assert!(QueryMatcher::new("tést", &options)
+            .match_name("test")
+            .is_some());
+        assert!(QueryMatcher::new("tést", &MatchOptions::default())
+            .match_name("test")
+            .is_none());
+    }
+
+    #[test]
+    fn indices_map_to_original_characters() {
+        let options = MatchOptions {
+            fold_diacritics: true,
+            ..MatchOptions::default()
+        };
+        // "e" followed by a combining acute accent occupies two original characters.
+        let name = "cafe\u{301}-app";
+        let (_, indices) = QueryMatcher::new("eapp", &options)
+            .match_name(name)
+            .unwrap();
+        assert_eq!(indices, vec![3, 6, 7, 8]);
+    }
+
+    #[test]
+    fn case_modes() {
+        let sensitive = MatchOptions {
+            case: CaseMatching::Sensitive,
+            ..MatchOptions::default()
+        };
+        assert!(QueryMatcher::new("api", &sensitive)
+            .match_name("API")
+            .is_none());
+        assert!(QueryMatcher::new("api", &MatchOptions::default())
+            .match_name("API")
+            .is_some());
+        assert!(QueryMatcher::new("Api", &MatchOptions::default())
+            .match_name("api")
+            .is_none());
+    }
+
+    #[test]
+    fn fuzzy_filter_orders_by_score() {
+        let candidates = [
+            String::from("xaxpxi"),
+            String::from("api"),
+            String::from("web"),
+        ];
+        let matches = fuzzy_filter("api", &candidates, &MatchOptions::default());
+        let texts: Vec<&str> = matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["api", "xaxpxi"]);
+        assert_eq!(matches[0].indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn nucleo_matches_words_anywhere() {
+        let options = MatchOptions {
+            matcher: Some(MatcherKind::Nucleo),
+            ..MatchOptions::default()
+        };
+        let candidates = [
+            String::from("terminaut-core"),
+            String::from("core-terminal"),
+            String::from("web"),
+        ];
+        let matches = fuzzy_filter("core term", &candidates, &options);
+        assert_eq!(matches.len(), 2);
+        assert!(fuzzy_filter("core term", &candidates, &MatchOptions::default()).is_empty());
+        let (_, indices) = QueryMatcher::new("tc", &options)
+            .match_name("terminaut-core")
+            .unwrap();
+        assert_eq!(indices, vec![0, 10]);
+        assert!(QueryMatcher::new("Tc", &options)
+            .match_name("terminaut-core")
+            .is_none());
+    }
+}
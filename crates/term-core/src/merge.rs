@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, EventKind};
+use crate::{normalize_path, CoreError, PersistedState, STORE};
+
+/// Why an entry from the other state was not taken; the local one is kept in both cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The same tag on the same path with a different color.
+    TagColor,
+    /// A profile with the same id but different settings.
+    Profile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub kind: ConflictKind,
+    /// The tagged path, or the profile id.
+    pub key: String,
+    pub local: serde_json::Value,
+    pub other: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub favorites_added: usize,
+    pub recents_added: usize,
+    /// Recents whose timestamp was moved forward to the other state's.
+    pub recents_updated: usize,
+    pub tags_added: usize,
+    pub profiles_added: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// The path as the local store would record it, without trailing or doubled separators, so
+/// the same directory written two ways compares equal.
+fn merge_key(path: &str) -> String {
+    normalize_path(path)
+        .map(|path| path.components().collect::<PathBuf>().display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Folds `other` into `local`. Favorites, tags, and profiles are unioned; recents keep the
+/// newest timestamp per path and per source and the larger visit count; editor bindings are
+/// added where `local` has none. Paths from `other` are stored normalized.
+pub(crate) fn merge(local: &mut PersistedState, mut other: PersistedState) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    let mut favorites: Vec<String> = local.favorites.iter().map(|p| merge_key(p)).collect();
+    for original in other.favorites {
+        let path = merge_key(&original);
+        if !favorites.contains(&path) {
+            // An imported favorite keeps its source under the path it is stored at here.
+            if let Some(source) = other.favorite_sources.remove(&original) {
+                local.favorite_sources.insert(path.clone(), source);
+            }
+            favorites.push(path.clone());
+            local.favorites.push(path);
+            report.favorites_added += 1;
+        }
+    }
+
+    let mut recents: HashMap<String, usize> = local
+        .recents
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (merge_key(&entry.path), index))
+        .collect();
+    for mut entry in other.recents {
+        entry.path = merge_key(&entry.path);
+        match recents.get(&entry.path) {
+            Some(&index) => {
+                let existing = &mut local.recents[index];
+                // Both machines saw the same visits at best, so keep the larger count.
+                let latest = existing.last_opened_utc.max(entry.last_opened_utc);
+                existing.score = existing.frecency(latest).max(entry.frecency(latest));
+                existing.visits = existing.visits.max(entry.visits);
+                if entry.last_opened_utc > existing.last_opened_utc {
+                    existing.last_opened_utc = entry.last_opened_utc;
+                    report.recents_updated += 1;
+                }
+                for (source, opened) in entry.sources {
+                    let last = existing.sources.entry(source).or_insert(opened);
+                    *last = (*last).max(opened);
+                }
+            }
+            None => {
+                recents.insert(entry.path.clone(), local.recents.len());
+                local.recents.push(entry);
+                report.recents_added += 1;
+            }
+        }
+    }
+    let cap = crate::config::config().recents.cap;
+    local
+        .recents
+        .sort_by_key(|entry| std::cmp::Reverse(entry.last_opened_utc));
+    local.recents.truncate(cap);
+
+    for mut tag in other.tags {
+        tag.path = merge_key(&tag.path);
+        let existing = local.tags.iter().find(|existing| {
+            merge_key(&existing.path) == tag.path && existing.tag.eq_ignore_ascii_case(&tag.tag)
+        });
+        match existing {
+            Some(existing) if !existing.color.eq_ignore_ascii_case(&tag.color) => {
+                report.conflicts.push(MergeConflict {
+                    kind: ConflictKind::TagColor,
+                    key: tag.path.clone(),
+                    local: serde_json::json!(existing),
+                    other: serde_json::json!(tag),
+                });
+            }
+            Some(_) => {}
+            None => {
+                local.tags.push(tag);
+                report.tags_added += 1;
+            }
+        }
+    }
+
+    for (path, editor) in other.editors {
+        local.editors.entry(merge_key(&path)).or_insert(editor);
+    }
+
+    // The note edited last wins, as with task results.
+    for mut note in other.notes {
+        note.path = merge_key(&note.path);
+        match local
+            .notes
+            .iter_mut()
+            .find(|existing| merge_key(&existing.path) == note.path)
+        {
+            Some(existing) if existing.updated_utc < note.updated_utc => *existing = note,
+            Some(_) => {}
+            None => local.notes.push(note),
+        }
+    }
+
+    // Local settings win; only keys missing here are taken.
+    for (namespace, settings) in other.settings {
+        let local = local.settings.entry(namespace).or_default();
+        for (key, value) in settings {
+            local.entry(key).or_insert(value);
+        }
+    }
+
+    for mut action in other.actions {
+        if local
+            .actions
+            .iter()
+            .any(|existing| existing.id == action.id)
+        {
+            continue;
+        }
+        action.path = action.path.as_deref().map(merge_key);
+        local.actions.push(action);
+    }
+
+    for (root, tasks) in other.task_results {
+        let recorded = local.task_results.entry(merge_key(&root)).or_default();
+        for (task, status) in tasks {
+            let newer = recorded
+                .get(&task)
+                .is_none_or(|existing| existing.finished_utc < status.finished_utc);
+            if newer {
+                recorded.insert(task, status);
+            }
+        }
+    }
+
+    for profile in other.profiles {
+        match local
+            .profiles
+            .iter()
+            .find(|existing| existing.id == profile.id)
+        {
+            Some(existing) => {
+                let (local_json, other_json) =
+                    (serde_json::json!(existing), serde_json::json!(profile));
+                if local_json != other_json {
+                    report.conflicts.push(MergeConflict {
+                        kind: ConflictKind::Profile,
+                        key: profile.id.to_string(),
+                        local: local_json,
+                        other: other_json,
+                    });
+                }
+            }
+            None => {
+                local.profiles.push(profile);
+                report.profiles_added += 1;
+            }
+        }
+    }
+
+    report
+}
+
+pub(crate) fn merge_state(other: &Path) -> anyhow::Result<MergeReport> {
+    if !other.is_file() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "no state file at {}",
+            other.display()
+        )));
+    }
+    let contents = std::fs::read_to_string(other)
+        .with_context(|| format!("failed to read {}", other.display()))?;
+    let contents = crate::encryption::open(&contents)?;
+    let other: PersistedState = serde_json::from_str(&contents).map_err(|err| {
+        CoreError::invalid_input(format!("{} is not a state file: {err}", other.display()))
+    })?;
+    let mut store = STORE.inner.lock();
+    let report = merge(&mut store, other.migrate());
+    STORE.persist(&mut store)?;
+    drop(store);
+    events::publish_reset(&EventKind::ALL);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RecentEntry, TaggedPath};
+
+    fn recent(path: &str, last_opened_utc: i64) -> RecentEntry {
+        RecentEntry {
+            path: path.to_string(),
+            last_opened_utc,
+            ..RecentEntry::default()
+        }
+    }
+
+    fn tag(path: &str, color: &str) -> TaggedPath {
+        TaggedPath {
+            path: path.to_string(),
+            tag: String::from("client"),
+            color: color.to_string(),
+            inherited: false,
+        }
+    }
+
+    #[test]
+    fn merge_unions_and_reports_conflicts() {
+        let mut local = PersistedState {
+            favorites: vec![String::from("/nowhere/a")],
+            recents: vec![recent("/nowhere/a", 10), recent("/nowhere/b", 30)],
+            tags: vec![tag("/nowhere/a", "#ff0000")],
+            ..PersistedState::default()
+        };
+        let other = PersistedState {
+            favorites: vec![String::from("/nowhere/a/"), String::from("/nowhere/c")],
+            favorite_sources: [("/nowhere/a/", "gtk"), ("/nowhere/c", "zoxide")]
+                .into_iter()
+                .map(|(path, source)| (path.to_string(), source.to_string()))
+                .collect(),
+            recents: vec![recent("/nowhere//a", 20), recent("/nowhere/b", 5)],
+            tags: vec![tag("/nowhere/a", "#00ff00"), tag("/nowhere/c", "#00ff00")],
+            ..PersistedState::default()
+        };
+        let report = merge(&mut local, other);
+        assert_eq!(local.favorites, ["/nowhere/a", "/nowhere/c"]);
+        assert_eq!(local.favorite_sources.len(), 1);
+        assert_eq!(local.favorite_sources["/nowhere/c"], "zoxide");
+        assert_eq!(report.favorites_added, 1);
+        assert_eq!(report.recents_added, 0);
+        assert_eq!(report.recents_updated, 1);
+        assert_eq!(local.recents[1].path, "/nowhere/a");
+        assert_eq!(local.recents[1].last_opened_utc, 20);
+        assert_eq!(report.tags_added, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kind, ConflictKind::TagColor);
+        assert_eq!(local.tags[0].color, "#ff0000");
+    }
+}
@@ -0,0 +1,151 @@
+use std::cmp::Reverse;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::matching::contains_words;
+use crate::{CoreError, STORE};
+
+/// Notes longer than this are rejected; they are meant as context, not documents.
+const NOTE_MAX_BYTES: usize = 64 * 1024;
+
+/// Markdown attached to a directory with [`crate::api::set_note`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub path: String,
+    pub markdown: String,
+    pub updated_utc: i64,
+}
+
+/// Attaches `markdown` to `path`, replacing its note; blank markdown removes it. Returns the
+/// note as saved.
+pub(crate) fn set_note(path: &str, markdown: &str) -> anyhow::Result<Option<Note>> {
+    let path = crate::normalize_path(path)?.display().to_string();
+    let note = build(&path, markdown, Utc::now().timestamp())?;
+    let mut store = STORE.inner.lock();
+    if replace(&mut store.notes, &path, note.clone()) {
+        STORE.persist(&mut store)?;
+    }
+    Ok(note)
+}
+
+/// The note `markdown` makes for `path`; `None` when it is blank.
+fn build(path: &str, markdown: &str, now: i64) -> anyhow::Result<Option<Note>> {
+    if markdown.len() > NOTE_MAX_BYTES {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "notes are limited to {} KiB",
+            NOTE_MAX_BYTES / 1024
+        )));
+    }
+    Ok((!markdown.trim().is_empty()).then(|| Note {
+        path: path.to_string(),
+        markdown: markdown.trim_end().to_string(),
+        updated_utc: now,
+    }))
+}
+
+/// Replaces the note of `path` with `note`. Returns whether `notes` changed.
+fn replace(notes: &mut Vec<Note>, path: &str, note: Option<Note>) -> bool {
+    let before = notes.len();
+    notes.retain(|existing| existing.path != path);
+    if note.is_none() && notes.len() == before {
+        return false;
+    }
+    notes.extend(note);
+    true
+}
+
+pub(crate) fn get_note(path: &str) -> anyhow::Result<Option<Note>> {
+    let path = crate::normalize_path(path)?.display().to_string();
+    Ok(STORE
+        .inner
+        .lock()
+        .notes
+        .iter()
+        .find(|note| note.path == path)
+        .cloned())
+}
+
+/// Every note, by path.
+pub(crate) fn list_notes() -> Vec<Note> {
+    let mut notes = STORE.inner.lock().notes.clone();
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    notes
+}
+
+/// Notes containing every word of `query`, ignoring case, most recently updated first.
+pub(crate) fn search_notes(query: &str) -> Vec<Note> {
+    matching(&STORE.inner.lock().notes, query)
+}
+
+fn matching(notes: &[Note], query: &str) -> Vec<Note> {
+    let mut notes: Vec<Note> = notes
+        .iter()
+        .filter(|note| contains_words(&note.markdown, query))
+        .cloned()
+        .collect();
+    notes.sort_by_key(|note| Reverse(note.updated_utc));
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn note(path: &str, markdown: &str, updated_utc: i64) -> Note {
+        build(path, markdown, updated_utc).unwrap().unwrap()
+    }
+
+    #[test]
+    fn trailing_whitespace_is_trimmed() {
+        let note = note("/work", "  Deploy with `make release`\n\n", 1);
+        assert_eq!(note.markdown, "  Deploy with `make release`");
+    }
+
+    #[test]
+    fn blank_markdown_makes_no_note() {
+        assert_eq!(build("/work", " \n\t", 1).unwrap(), None);
+    }
+
+    #[test]
+    fn oversized_notes_are_rejected() {
+        let err = build("/work", &"x".repeat(NOTE_MAX_BYTES + 1), 1).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        assert!(build("/work", &"x".repeat(NOTE_MAX_BYTES), 1).is_ok());
+    }
+
+    #[test]
+    fn setting_a_note_replaces_the_old_one() {
+        let mut notes = vec![note("/work", "draft", 1), note("/other", "keep", 1)];
+        assert!(replace(
+            &mut notes,
+            "/work",
+            Some(note("/work", "final", 2))
+        ));
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[1].markdown, "final");
+    }
+
+    #[test]
+    fn clearing_a_missing_note_changes_nothing() {
+        let mut notes = vec![note("/work", "draft", 1)];
+        assert!(!replace(&mut notes, "/other", None));
+        assert!(replace(&mut notes, "/work", None));
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn searches_need_every_word_and_list_recent_notes_first() {
+        let notes = [
+            note("/a", "Deploy with make release", 1),
+            note("/b", "release notes, then DEPLOY", 2),
+            note("/c", "deploy only", 3),
+        ];
+        let found: Vec<String> = matching(&notes, "RELEASE deploy")
+            .into_iter()
+            .map(|note| note.path)
+            .collect();
+        assert_eq!(found, ["/b", "/a"]);
+    }
+}
@@ -0,0 +1,398 @@
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// What `normalize_path` does with symlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkPolicy {
+    /// Resolve symlinks to the real path, so one directory is stored once.
+    #[default]
+    Resolve,
+    /// Keep the path as written, made absolute, with `.` and `..` removed lexically. For
+    /// symlink farms whose link paths are the ones worth seeing.
+    Preserve,
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "resolve" => Ok(Self::Resolve),
+            "preserve" => Ok(Self::Preserve),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown symlink policy {other:?} (resolve, preserve)"
+            ))),
+        }
+    }
+}
+
+/// Removes `.` components and folds `..` into its parent without touching the file system.
+/// `..` at the root stays at the root; leading `..` of a relative path are kept.
+pub(crate) fn clean(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match cleaned.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    cleaned.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => cleaned.push(".."),
+            },
+            other => cleaned.push(other.as_os_str()),
+        }
+    }
+    cleaned
+}
+
+/// `path` made absolute against the working directory and cleaned, symlinks left in place.
+pub(crate) fn absolute_preserving(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    clean(&absolute)
+}
+
+fn is_var_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_var_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Expands `$NAME`, `${NAME}`, and `%NAME%` using `lookup`. `$$` and `%%` stand for a literal
+/// `$` or `%`; a `$` or `%` not followed by a variable name is kept as is. Fails on unset
+/// variables rather than leaving a path that silently points somewhere else.
+pub(crate) fn expand_env(
+    input: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<String> {
+    let value = |name: &str| {
+        lookup(name).ok_or_else(|| {
+            CoreError::invalid_input(format!("environment variable {name} is not set"))
+                .with_hint("write $$ or %% for a literal $ or %")
+        })
+    };
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(at) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..at]);
+        let sigil = &rest[at..at + 1];
+        let after = &rest[at + 1..];
+        if let Some(after) = after.strip_prefix(sigil) {
+            expanded.push_str(sigil);
+            rest = after;
+            continue;
+        }
+        let name_len = if after.starts_with(is_var_start) {
+            after.find(|c| !is_var_char(c)).unwrap_or(after.len())
+        } else {
+            0
+        };
+        if sigil == "$" && after.starts_with('{') {
+            let Some(end) = after.find('}') else {
+                anyhow::bail!(CoreError::invalid_input(format!(
+                    "unterminated ${{ in {input:?}"
+                )));
+            };
+            expanded.push_str(&value(&after[1..end])?);
+            rest = &after[end + 1..];
+        } else if sigil == "$" && name_len > 0 {
+            expanded.push_str(&value(&after[..name_len])?);
+            rest = &after[name_len..];
+        } else if sigil == "%" && name_len > 0 && after[name_len..].starts_with('%') {
+            expanded.push_str(&value(&after[..name_len])?);
+            rest = &after[name_len + 1..];
+        } else {
+            expanded.push_str(sigil);
+            rest = after;
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Expands a leading `~` to `home`. `~user` forms and paths without a home prefix are
+/// returned as given.
+pub(crate) fn expand_home(input: &str, home: Option<&Path>) -> PathBuf {
+    let Some(home) = home else {
+        return PathBuf::from(input);
+    };
+    let Some(rest) = input.strip_prefix('~') else {
+        return PathBuf::from(input);
+    };
+    if rest.is_empty() {
+        return home.to_path_buf();
+    }
+    match rest.strip_prefix(['/', '\\']) {
+        Some(rest) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(input),
+    }
+}
+
+/// Decodes `%XX` escapes; `None` when there are none or the result isn't UTF-8. A `%` not
+/// followed by two hex digits is kept.
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut escapes = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                escapes += 1;
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    (escapes > 0)
+        .then(|| String::from_utf8(decoded).ok())
+        .flatten()
+}
+
+/// The path in a `file:` URI, decoded: `file:///Users/me/dev%20stuff` is
+/// `/Users/me/dev stuff`, `file:///C:/dev` is `C:/dev`, and `file://nas/share` is the UNC path
+/// `//nas/share`. `None` when `input` is not a file URI.
+pub(crate) fn file_uri_path(input: &str) -> anyhow::Result<Option<String>> {
+    if !input
+        .get(..5)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file:"))
+    {
+        return Ok(None);
+    }
+    let url = url::Url::parse(input)
+        .map_err(|err| CoreError::invalid_input(format!("invalid file URI {input:?}: {err}")))?;
+    let path = percent_decode(url.path()).unwrap_or_else(|| url.path().to_string());
+    let host = url
+        .host_str()
+        .filter(|host| !host.is_empty() && !host.eq_ignore_ascii_case("localhost"));
+    Ok(Some(match host {
+        Some(host) => format!("//{host}{path}"),
+        None if has_drive_letter(path.trim_start_matches('/')) => {
+            path.trim_start_matches('/').to_string()
+        }
+        None => path,
+    }))
+}
+
+/// Drag and drop sometimes hands over percent-encoded paths without the `file:` scheme. The
+/// decoded form is used only when the path as written doesn't exist and the decoded one does.
+pub(crate) fn decode_if_missing(path: PathBuf) -> PathBuf {
+    if path.exists() {
+        return path;
+    }
+    path.to_str()
+        .and_then(percent_decode)
+        .map(PathBuf::from)
+        .filter(|decoded| decoded.exists())
+        .unwrap_or(path)
+}
+
+/// Rewrites a Windows path the way Explorer shows it: `\\?\C:\x` becomes `C:\x`,
+/// `\\?\UNC\server\share` becomes `\\server\share`, UNC paths get backslashes, drive letters
+/// are upper-cased, and a bare `C:` means the drive root. Anything else is returned unchanged.
+///
+/// Dropping the verbatim prefix is safe for long paths: std re-adds it when a path over
+/// `MAX_PATH` is passed to the file system.
+pub(crate) fn tidy_windows_path(path: &str) -> String {
+    let path = if let Some(share) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{share}")
+    } else if let Some(rest) = path
+        .strip_prefix(r"\\?\")
+        .filter(|rest| has_drive_letter(rest))
+    {
+        rest.to_string()
+    } else {
+        path.to_string()
+    };
+    if is_unc(&path) {
+        return path.replace('/', "\\");
+    }
+    if !has_drive_letter(&path) {
+        return path;
+    }
+    let (drive, rest) = path.split_at(1);
+    let rest = if rest == ":" { r":\" } else { rest };
+    format!("{}{rest}", drive.to_ascii_uppercase())
+}
+
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && bytes.get(2).is_none_or(|c| matches!(c, b'\\' | b'/'))
+}
+
+/// A `\\server\share` (or `//server/share`) network path.
+fn is_unc(path: &str) -> bool {
+    let rest = path.strip_prefix(r"\\").or_else(|| path.strip_prefix("//"));
+    rest.is_some_and(|rest| !rest.starts_with(['?', '.']) && !rest.is_empty())
+}
+
+/// The form a canonicalized path is stored and shown in on this platform.
+pub(crate) fn for_display(path: PathBuf) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(tidy_windows_path(&path.to_string_lossy()))
+    } else {
+        path
+    }
+}
+
+/// `path` for showing to people: relative to `root` when it lies inside it (`.` for the root
+/// itself), otherwise with `home` contracted to `~`. Matching is by whole components, so
+/// `/home/meow` is not under `/home/me`.
+pub(crate) fn display_path(path: &Path, home: &[PathBuf], root: Option<&Path>) -> String {
+    if let Some(relative) = root.and_then(|root| path.strip_prefix(root).ok()) {
+        return match relative.as_os_str().is_empty() {
+            true => String::from("."),
+            false => relative.display().to_string(),
+        };
+    }
+    let relative = home.iter().find_map(|home| path.strip_prefix(home).ok());
+    match relative {
+        Some(relative) if relative.as_os_str().is_empty() => String::from("~"),
+        Some(relative) => format!("~{}{}", std::path::MAIN_SEPARATOR, relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_prefixes_expand() {
+        let home = Path::new("/home/me");
+        assert_eq!(expand_home("~", Some(home)), home);
+        assert_eq!(expand_home("~/src/app", Some(home)), home.join("src/app"));
+        assert_eq!(expand_home(r"~\src", Some(home)), home.join("src"));
+        assert_eq!(expand_home("~bob/x", Some(home)), Path::new("~bob/x"));
+        assert_eq!(expand_home("/srv/~", Some(home)), Path::new("/srv/~"));
+        assert_eq!(expand_home("~/x", None), Path::new("~/x"));
+    }
+
+    #[test]
+    fn env_vars_expand() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some(String::from("/home/me")),
+            "APPDATA" => Some(String::from(r"C:\Users\me\AppData")),
+            _ => None,
+        };
+        assert_eq!(expand_env("$HOME/dev", lookup).unwrap(), "/home/me/dev");
+        assert_eq!(expand_env("${HOME}/api", lookup).unwrap(), "/home/me/api");
+        assert_eq!(
+            expand_env(r"%APPDATA%\tools", lookup).unwrap(),
+            r"C:\Users\me\AppData\tools"
+        );
+        assert_eq!(expand_env("/a/$$x/100%%", lookup).unwrap(), "/a/$x/100%");
+        assert_eq!(expand_env("/a/$/50%/b%", lookup).unwrap(), "/a/$/50%/b%");
+        let err = expand_env("$PROJECTS/api", lookup).unwrap_err();
+        assert_eq!(err.to_string(), "environment variable PROJECTS is not set");
+        assert!(expand_env("${HOME", lookup).is_err());
+    }
+
+    #[test]
+    fn file_uris_and_escapes_decode() {
+        let path = |uri: &str| file_uri_path(uri).unwrap();
+        assert_eq!(
+            path("file:///Users/me/dev%20stuff").as_deref(),
+            Some("/Users/me/dev stuff")
+        );
+        assert_eq!(path("FILE://localhost/tmp").as_deref(), Some("/tmp"));
+        assert_eq!(path("file:///C:/dev/app").as_deref(), Some("C:/dev/app"));
+        assert_eq!(path("file://nas/share/x").as_deref(), Some("//nas/share/x"));
+        assert_eq!(path("/tmp/file:x"), None);
+        assert_eq!(percent_decode("caf%C3%A9").as_deref(), Some("café"));
+        assert_eq!(percent_decode("100%"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+
+    #[test]
+    fn display_paths_contract_home_and_root() {
+        let home = [PathBuf::from("/home/me")];
+        let show = |path: &str, root: Option<&str>| {
+            display_path(Path::new(path), &home, root.map(Path::new))
+        };
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(show("/home/me/dev/api", None), format!("~{sep}dev/api"));
+        assert_eq!(show("/home/me", None), "~");
+        assert_eq!(show("/home/meow", None), "/home/meow");
+        assert_eq!(show("/srv/app", None), "/srv/app");
+        assert_eq!(show("/home/me/dev/api", Some("/home/me/dev")), "api");
+        assert_eq!(show("/home/me/dev", Some("/home/me/dev/")), ".");
+        assert_eq!(
+            show("/home/me/notes", Some("/home/me/dev")),
+            format!("~{sep}notes")
+        );
+    }
+
+    #[test]
+    fn clean_folds_dots_lexically() {
+        assert_eq!(clean(Path::new("/a/./b/../c/")), Path::new("/a/c"));
+        assert_eq!(clean(Path::new("/../a")), Path::new("/a"));
+        assert_eq!(clean(Path::new("../x/../y")), Path::new("../y"));
+        assert_eq!(
+            "Preserve".parse::<SymlinkPolicy>().unwrap(),
+            SymlinkPolicy::Preserve
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preserve_keeps_symlinks() {
+        let root = std::env::temp_dir().join(format!("terminaut-links-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("real")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+        let kept = absolute_preserving(&root.join("link/./sub/.."));
+        let resolved = std::fs::canonicalize(root.join("link")).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(kept, root.join("link"));
+        assert!(resolved.ends_with("real"));
+    }
+
+    #[test]
+    fn windows_paths_tidy() {
+        assert_eq!(tidy_windows_path(r"\\?\C:\Users\me"), r"C:\Users\me");
+        assert_eq!(
+            tidy_windows_path(r"\\?\UNC\nas\share\dir"),
+            r"\\nas\share\dir"
+        );
+        assert_eq!(tidy_windows_path(r"d:\work"), r"D:\work");
+        assert_eq!(tidy_windows_path("e:"), r"E:\");
+        assert_eq!(
+            tidy_windows_path(r"\\?\Volume{1234}\x"),
+            r"\\?\Volume{1234}\x"
+        );
+        assert_eq!(tidy_windows_path("/usr/local"), "/usr/local");
+        assert_eq!(tidy_windows_path("//nas/share/dir"), r"\\nas\share\dir");
+        assert!(is_unc(r"\\nas\share"));
+        assert!(is_unc("//nas/share"));
+        assert!(!is_unc(r"\\?\C:\x"));
+        assert!(!is_unc(r"C:\x"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn canonical_windows_paths_drop_the_verbatim_prefix() {
+        let temp = std::env::temp_dir();
+        let normalized = crate::normalize_path(&temp.display().to_string()).unwrap();
+        assert!(!normalized.display().to_string().starts_with(r"\\?\"));
+        assert!(normalized.is_dir());
+    }
+}
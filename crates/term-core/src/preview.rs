@@ -0,0 +1,122 @@
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::dir_size::cached_bytes;
+use crate::git::{git_state, GitState};
+use crate::{list_directory, tags_for_path, DirectoryEntry, Note, TaggedPath};
+
+const PREVIEW_ENTRY_LIMIT: usize = 12;
+const README_PREVIEW_LINES: usize = 5;
+const README_READ_BYTES: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadmeExcerpt {
+    pub file: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryPreview {
+    pub path: String,
+    /// The first entries of the directory, in listing order.
+    pub entries: Vec<DirectoryEntry>,
+    pub total_entries: usize,
+    pub dir_count: usize,
+    pub file_count: usize,
+    pub readme: Option<ReadmeExcerpt>,
+    pub git: Option<GitState>,
+    /// Recursive size in bytes when already cached; otherwise `None` while a background
+    /// computation fills the cache for the next preview.
+    pub total_size: Option<u64>,
+    pub tags: Vec<TaggedPath>,
+    /// The note set with [`crate::api::set_note`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<Note>,
+}
+
+fn is_readme(entry: &DirectoryEntry) -> bool {
+    if entry.is_dir {
+        return false;
+    }
+    let lower = entry.name.to_lowercase();
+    lower == "readme" || lower.starts_with("readme.")
+}
+
+fn readme_excerpt(entries: &[DirectoryEntry]) -> Option<ReadmeExcerpt> {
+    let entry = entries
+        .iter()
+        .filter(|entry| is_readme(entry))
+        // Prefer README.md over README.txt and friends when both exist.
+        .min_by_key(|entry| !entry.name.to_lowercase().ends_with(".md"))?;
+    let mut contents = String::new();
+    std::fs::File::open(&entry.path)
+        .ok()?
+        .take(README_READ_BYTES)
+        .read_to_string(&mut contents)
+        .ok()?;
+    let lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(README_PREVIEW_LINES)
+        .map(str::to_string)
+        .collect();
+    Some(ReadmeExcerpt {
+        file: entry.name.clone(),
+        lines,
+    })
+}
+
+/// Sizes come from the `dir_size` cache, which needs the `fs` feature.
+#[cfg(not(feature = "fs"))]
+fn cached_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+pub(crate) fn preview(path: &Path) -> anyhow::Result<DirectoryPreview> {
+    if !path.is_dir() {
+        anyhow::bail!("not a directory: {}", path.display());
+    }
+    let all_entries = list_directory(path)?;
+    let dir_count = all_entries.iter().filter(|entry| entry.is_dir).count();
+    let readme = readme_excerpt(&all_entries);
+    let display = path.display().to_string();
+    Ok(DirectoryPreview {
+        total_entries: all_entries.len(),
+        dir_count,
+        file_count: all_entries.len() - dir_count,
+        entries: all_entries.into_iter().take(PREVIEW_ENTRY_LIMIT).collect(),
+        readme,
+        git: git_state(path),
+        total_size: cached_bytes(path),
+        tags: tags_for_path(&display)?,
+        note: crate::notes::get_note(&display)?,
+        path: display,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool) -> DirectoryEntry {
+        DirectoryEntry {
+            name: name.into(),
+            path: name.into(),
+            is_dir,
+            mod_date: None,
+            git_status: None,
+        }
+    }
+
+    #[test]
+    fn readme_detection() {
+        assert!(is_readme(&entry("README.md", false)));
+        assert!(is_readme(&entry("readme", false)));
+        assert!(!is_readme(&entry("README", true)));
+        assert!(!is_readme(&entry("READMEFIRST.txt", false)));
+    }
+}
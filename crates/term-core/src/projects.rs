@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+
+use crate::ignore_file::IgnoreStack;
+use crate::matching::contains_words;
+use crate::search::build_globs;
+use crate::{config, normalize_path, project_marker, CoreError, TaggedPath, STORE};
+
+/// A project found below one of the scanned roots, kept in the store between scans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownProject {
+    pub path: String,
+    pub name: String,
+    /// Marker file that identified the root.
+    pub marker: String,
+    /// The scan root it was found under; rescanning that root replaces its projects.
+    pub scan_root: String,
+    pub first_seen_utc: i64,
+    pub last_scanned_utc: i64,
+    /// The README's first heading, indexed so search can match it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The package name from the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// The manifest description, else the README's first paragraph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl KnownProject {
+    /// The first of the title, package name, and summary that contains every word of `query`,
+    /// ignoring case.
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
+    pub(crate) fn matching_text(&self, query: &str) -> Option<&str> {
+        [&self.title, &self.package, &self.summary]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .find(|text| contains_words(text, query))
+    }
+}
+
+/// A project root found by [`discover`], with the text [`refresh_projects`] indexes.
+struct Found {
+    path: PathBuf,
+    marker: String,
+    title: Option<String>,
+    package: Option<String>,
+    summary: Option<String>,
+}
+
+impl Found {
+    fn read(path: PathBuf, marker: String) -> Self {
+        let description = crate::description::project_description(&path);
+        Self {
+            package: crate::description::package_name(&path),
+            title: description.title,
+            summary: description.summary,
+            path,
+            marker,
+        }
+    }
+}
+
+/// What [`refresh_projects`] did, root by root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanReport {
+    /// Roots walked during this refresh.
+    pub scanned: Vec<String>,
+    /// Configured roots left alone because their last scan is still fresh.
+    pub skipped: Vec<String>,
+    pub added: Vec<String>,
+    /// Projects that were not found again under their scan root.
+    pub removed: Vec<String>,
+    /// Projects in the registry afterwards.
+    pub total: usize,
+    /// Tags `projects.tag_rules` gave the scanned projects.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tagged: Vec<TaggedPath>,
+}
+
+/// Walks down from `dir` to project roots, stopping at each so nested packages of a monorepo
+/// are not listed separately. Hidden, `search.ignore`d, and `.terminautignore`d directories
+/// are not entered.
+fn discover(
+    dir: &Path,
+    depth: usize,
+    exclude: Option<&GlobSet>,
+    ignores: &mut IgnoreStack,
+    found: &mut Vec<(PathBuf, String)>,
+) {
+    if let Some(marker) = project_marker(dir) {
+        found.push((dir.to_path_buf(), marker));
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let pushed = ignores.enter(dir);
+    for entry in entries.filter_map(Result::ok) {
+        if !entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name();
+        let path = entry.path();
+        if name.to_string_lossy().starts_with('.')
+            || exclude.is_some_and(|globs| globs.is_match(&name))
+            || ignores.is_ignored(&path, true)
+        {
+            continue;
+        }
+        discover(&path, depth - 1, exclude, ignores, found);
+    }
+    if pushed {
+        ignores.leave();
+    }
+}
+
+fn project_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Project roots below `root` with their markers, by path, down to `projects.scan_depth` and
+/// skipping `search.ignore`.
+pub(crate) fn discover_projects(root: &Path) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    if !root.is_dir() {
+        anyhow::bail!(CoreError::not_found(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+    let (depth, exclude) = {
+        let config = config::config();
+        (
+            config.projects.scan_depth,
+            build_globs(&config.search.ignore)?,
+        )
+    };
+    let mut found = Vec::new();
+    discover(
+        root,
+        depth,
+        exclude.as_ref(),
+        &mut IgnoreStack::new(),
+        &mut found,
+    );
+    found.sort();
+    Ok(found)
+}
+
+/// Configured roots whose last scan is older than `projects.refresh_hours`.
+fn due_roots(report: &mut ScanReport) -> anyhow::Result<Vec<PathBuf>> {
+    let (roots, refresh_secs) = {
+        let config = config::config();
+        (
+            config.projects.roots.clone(),
+            config.projects.refresh_hours as i64 * 3600,
+        )
+    };
+    if roots.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("no project roots to scan")
+            .with_hint("pass roots to scan or set projects.roots in the config"));
+    }
+    let now = Utc::now().timestamp();
+    let mut due = Vec::new();
+    for root in roots {
+        let root = normalize_path(&root)?;
+        let key = root.display().to_string();
+        let scanned = STORE.inner.lock().project_scans.get(&key).copied();
+        match scanned {
+            Some(at) if now - at < refresh_secs => report.skipped.push(key),
+            _ => due.push(root),
+        }
+    }
+    Ok(due)
+}
+
+/// Rescans `roots` for projects and updates the registry. With no roots, the configured
+/// `projects.roots` are rescanned once their last scan is `projects.refresh_hours` old, so
+/// hosts can call this on a timer or at startup and only pay for stale roots.
+pub(crate) fn refresh_projects(roots: &[String]) -> anyhow::Result<ScanReport> {
+    let mut report = ScanReport::default();
+    let roots = match roots.is_empty() {
+        true => due_roots(&mut report)?,
+        false => roots
+            .iter()
+            .map(|root| normalize_path(root))
+            .collect::<anyhow::Result<_>>()?,
+    };
+    let mut scans: BTreeMap<String, Vec<Found>> = BTreeMap::new();
+    for root in &roots {
+        // READMEs and manifests are read here, outside the store lock.
+        let found = discover_projects(root)?
+            .into_iter()
+            .map(|(path, marker)| Found::read(path, marker))
+            .collect();
+        scans.insert(root.display().to_string(), found);
+    }
+
+    let now = Utc::now().timestamp();
+    let mut store = STORE.inner.lock();
+    for (root, found) in scans {
+        let previous: BTreeMap<String, KnownProject> = store
+            .projects
+            .iter()
+            .filter(|project| project.scan_root == root)
+            .map(|project| (project.path.clone(), project.clone()))
+            .collect();
+        store.projects.retain(|project| project.scan_root != root);
+        for found in found {
+            let path = found.path;
+            let key = path.display().to_string();
+            // A project already registered under another root keeps its place there.
+            if store.projects.iter().any(|project| project.path == key) {
+                continue;
+            }
+            let first_seen_utc = match previous.get(&key) {
+                Some(project) => project.first_seen_utc,
+                None => {
+                    report.added.push(key.clone());
+                    now
+                }
+            };
+            store.projects.push(KnownProject {
+                name: project_name(&path),
+                path: key,
+                marker: found.marker,
+                scan_root: root.clone(),
+                first_seen_utc,
+                last_scanned_utc: now,
+                title: found.title,
+                package: found.package,
+                summary: found.summary,
+            });
+        }
+        report.removed.extend(
+            previous
+                .into_keys()
+                .filter(|path| !store.projects.iter().any(|project| &project.path == path)),
+        );
+        store.project_scans.insert(root.clone(), now);
+        report.scanned.push(root);
+    }
+    report.total = store.projects.len();
+    STORE.persist(&mut store)?;
+    let scanned = store
+        .projects
+        .iter()
+        .filter(|project| report.scanned.contains(&project.scan_root))
+        .map(|project| project.path.clone())
+        .collect();
+    drop(store);
+    report.tagged = crate::auto_tag::tag_scanned(scanned)?;
+    Ok(report)
+}
+
+/// The registry as of the last scans, by name. Reads the store only, so it is instant.
+pub(crate) fn list_projects() -> Vec<KnownProject> {
+    let mut projects = STORE.inner.lock().projects.clone();
+    projects.sort_by_key(|project| (project.name.to_lowercase(), project.path.clone()));
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_stops_at_project_roots() {
+        let root =
+            std::env::temp_dir().join(format!("terminaut-projects-{}", uuid::Uuid::new_v4()));
+        for dir in ["work/app/packages/ui", "work/.cache/tool", "work/notes"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("work/app/package.json"), "{}").unwrap();
+        std::fs::write(root.join("work/app/packages/ui/package.json"), "{}").unwrap();
+        std::fs::write(root.join("work/.cache/tool/Cargo.toml"), "").unwrap();
+
+        let mut found = Vec::new();
+        discover(&root, 3, None, &mut IgnoreStack::new(), &mut found);
+        assert_eq!(
+            found,
+            [(root.join("work/app"), String::from("package.json"))]
+        );
+        found.clear();
+        discover(&root, 1, None, &mut IgnoreStack::new(), &mut found);
+        assert!(found.is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn readme_and_manifest_text_is_indexed() {
+        let root = std::env::temp_dir().join(format!("terminaut-text-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("pyproject.toml"),
+            "[tool.poetry]\nname = \"pmt-core\"\ndescription = \"Ledger and refunds\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("README.md"),
+            "# Payments Service\n\nMoves money.\n",
+        )
+        .unwrap();
+        let found = Found::read(root.clone(), String::from("pyproject.toml"));
+        let project = KnownProject {
+            path: root.display().to_string(),
+            name: String::from("svc-pmt-042"),
+            marker: found.marker,
+            scan_root: String::new(),
+            first_seen_utc: 0,
+            last_scanned_utc: 0,
+            title: found.title,
+            package: found.package,
+            summary: found.summary,
+        };
+        assert_eq!(
+            project.matching_text("payments SERVICE"),
+            Some("Payments Service")
+        );
+        assert_eq!(project.matching_text("pmt core"), Some("pmt-core"));
+        assert_eq!(project.matching_text("money moves"), Some("Moves money."));
+        assert_eq!(project.matching_text("refunds"), None);
+        assert_eq!(project.matching_text(" "), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::Utc;
+use globset::{GlobBuilder, GlobMatcher};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::STORE;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Weights for blending the fuzzy score with what the store knows about a path.
+/// Setting every store weight to zero yields pure fuzzy ordering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankWeights {
+    pub fuzzy: f64,
+    /// Bonus for a path opened just now; decays with `recency_half_life_days`.
+    pub recent: f64,
+    pub favorite: f64,
+    pub tagged: f64,
+    pub recency_half_life_days: f64,
+}
+
+impl Default for RankWeights {
+    fn default() -> Self {
+        Self {
+            fuzzy: 1.0,
+            recent: 40.0,
+            favorite: 50.0,
+            tagged: 20.0,
+            recency_half_life_days: 14.0,
+        }
+    }
+}
+
+/// A `[[search.rules]]` entry: paths matching `glob`, or below a directory matching it, are
+/// boosted, suppressed, or pinned in search results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RankRule {
+    /// Glob over the full path, e.g. `~/work/*`; a leading `~` is the home directory.
+    pub glob: String,
+    /// Added to the rank; negative values push matches down.
+    #[serde(default)]
+    pub boost: f64,
+    /// Matches sort above every result that isn't pinned.
+    #[serde(default)]
+    pub pin: bool,
+}
+
+pub(crate) struct CompiledRule {
+    matcher: GlobMatcher,
+    rule: RankRule,
+}
+
+/// Compiles a path glob, expanding a leading `~` so it compares against normalized paths.
+pub(crate) fn path_glob(glob: &str) -> anyhow::Result<GlobMatcher> {
+    let home = dirs::home_dir().map(|home| home.display().to_string());
+    let pattern = match (glob.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) => format!("{home}{rest}"),
+        _ => glob.to_string(),
+    };
+    Ok(GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("invalid glob {glob:?}"))?
+        .compile_matcher())
+}
+
+/// Compiles `rules`, expanding `~` so globs compare against normalized paths.
+pub(crate) fn compile_rules(rules: &[RankRule]) -> anyhow::Result<Vec<CompiledRule>> {
+    rules
+        .iter()
+        .map(|rule| {
+            Ok(CompiledRule {
+                matcher: path_glob(&rule.glob)?,
+                rule: rule.clone(),
+            })
+        })
+        .collect()
+}
+
+/// How a result's final rank was assembled; `total` is what results are sorted by, after
+/// `pinned`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RankBreakdown {
+    pub fuzzy: f64,
+    pub recent: f64,
+    pub favorite: f64,
+    pub tagged: f64,
+    /// Sum of the boosts of matching `search.rules`.
+    #[serde(default)]
+    pub rules: f64,
+    /// A matching rule pins the result above unpinned ones.
+    #[serde(default)]
+    pub pinned: bool,
+    pub total: f64,
+    /// Each non-zero component in words, e.g. `favorite +50` or `rule ~/work/* +30`.
+    #[serde(default)]
+    pub explanation: Vec<String>,
+}
+
+/// A snapshot of store membership and rank rules, taken once per search.
+pub(crate) struct StoreSignals {
+    recents: HashMap<String, i64>,
+    favorites: HashSet<String>,
+    tagged: HashSet<String>,
+    /// `tags.inherit`: paths below a tagged directory count as tagged.
+    inherit_tags: bool,
+    rules: Vec<CompiledRule>,
+    now: i64,
+}
+
+impl StoreSignals {
+    pub(crate) fn snapshot() -> Self {
+        // The config rejects invalid globs, so this only fails for a hand-built `Config`.
+        let rules = compile_rules(&crate::config::config().search.rules).unwrap_or_else(|err| {
+            tracing::warn!("ignoring search.rules: {err:#}");
+            Vec::new()
+        });
+        let inherit_tags = crate::config::config().tags.inherit;
+        let store = STORE.inner.lock();
+        Self {
+            recents: store
+                .recents
+                .iter()
+                .map(|entry| (entry.path.clone(), entry.last_opened_utc))
+                .collect(),
+            favorites: store.favorites.iter().cloned().collect(),
+            tagged: store.tags.iter().map(|tag| tag.path.clone()).collect(),
+            inherit_tags,
+            rules,
+            now: Utc::now().timestamp(),
+        }
+    }
+
+    /// Signals where only `favorites` are known, for tests elsewhere.
+    #[cfg(all(test, feature = "fs"))]
+    pub(crate) fn with_favorites(favorites: &[&str]) -> Self {
+        Self {
+            recents: HashMap::new(),
+            favorites: favorites.iter().map(|path| path.to_string()).collect(),
+            tagged: HashSet::new(),
+            inherit_tags: false,
+            rules: Vec::new(),
+            now: 0,
+        }
+    }
+
+    fn inherits_tag(&self, path: &str) -> bool {
+        self.inherit_tags
+            && Path::new(path)
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| self.tagged.contains(&*ancestor.to_string_lossy()))
+    }
+
+    pub(crate) fn rank(
+        &self,
+        path: &str,
+        fuzzy_score: i64,
+        weights: &RankWeights,
+    ) -> RankBreakdown {
+        self.breakdown(path, fuzzy_score, weights, true)
+    }
+
+    /// [`Self::rank`] without the explanation, for ordering matches before the final cut.
+    #[cfg(feature = "fs")]
+    pub(crate) fn quick_rank(
+        &self,
+        path: &str,
+        fuzzy_score: i64,
+        weights: &RankWeights,
+    ) -> RankBreakdown {
+        self.breakdown(path, fuzzy_score, weights, false)
+    }
+
+    fn breakdown(
+        &self,
+        path: &str,
+        fuzzy_score: i64,
+        weights: &RankWeights,
+        explain: bool,
+    ) -> RankBreakdown {
+        let fuzzy = fuzzy_score as f64 * weights.fuzzy;
+        let recent = self
+            .recents
+            .get(path)
+            .map(|opened| {
+                let age_days = (self.now - opened).max(0) as f64 / SECONDS_PER_DAY;
+                let half_life = weights.recency_half_life_days.max(f64::EPSILON);
+                weights.recent * 0.5f64.powf(age_days / half_life)
+            })
+            .unwrap_or(0.0);
+        let favorite = if self.favorites.contains(path) {
+            weights.favorite
+        } else {
+            0.0
+        };
+        let tagged = if self.tagged.contains(path) || self.inherits_tag(path) {
+            weights.tagged
+        } else {
+            0.0
+        };
+        let mut explanation: Vec<String> = [
+            ("fuzzy match", fuzzy),
+            ("opened recently", recent),
+            ("favorite", favorite),
+            ("tagged", tagged),
+        ]
+        .into_iter()
+        .filter(|(_, value)| explain && *value != 0.0)
+        .map(|(label, value)| format!("{label} {value:+.1}"))
+        .collect();
+        let mut rules = 0.0;
+        let mut pinned = false;
+        for CompiledRule { matcher, rule } in &self.rules {
+            if !Path::new(path)
+                .ancestors()
+                .any(|ancestor| matcher.is_match(ancestor))
+            {
+                continue;
+            }
+            rules += rule.boost;
+            pinned |= rule.pin;
+            if !explain {
+                continue;
+            }
+            if rule.boost != 0.0 {
+                explanation.push(format!("rule {} {:+.1}", rule.glob, rule.boost));
+            }
+            if rule.pin {
+                explanation.push(format!("pinned by rule {}", rule.glob));
+            }
+        }
+        RankBreakdown {
+            fuzzy,
+            recent,
+            favorite,
+            tagged,
+            rules,
+            pinned,
+            total: fuzzy + recent + favorite + tagged + rules,
+            explanation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals() -> StoreSignals {
+        let now = 1_000_000;
+        StoreSignals {
+            recents: HashMap::from([
+                (String::from("/fresh"), now),
+                (String::from("/stale"), now - 14 * 86_400),
+            ]),
+            favorites: HashSet::from([String::from("/fav")]),
+            tagged: HashSet::from([String::from("/fav")]),
+            inherit_tags: false,
+            rules: Vec::new(),
+            now,
+        }
+    }
+
+    #[test]
+    fn recency_decays_by_half_life() {
+        let weights = RankWeights::default();
+        let signals = signals();
+        assert_eq!(signals.rank("/fresh", 10, &weights).recent, 40.0);
+        assert!((signals.rank("/stale", 10, &weights).recent - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn components_sum_to_total() {
+        let rank = signals().rank("/fav", 30, &RankWeights::default());
+        assert_eq!(rank.total, 30.0 + 50.0 + 20.0);
+    }
+
+    #[test]
+    fn rules_boost_and_pin_paths_below_them() {
+        let rule = |glob: &str, boost: f64, pin: bool| RankRule {
+            glob: glob.to_string(),
+            boost,
+            pin,
+        };
+        let mut signals = signals();
+        signals.rules = compile_rules(&[
+            rule("/work/*", 30.0, false),
+            rule("/tmp/**", -25.0, false),
+            rule("/work/keep", 0.0, true),
+        ])
+        .unwrap();
+        let weights = RankWeights::default();
+
+        let deep = signals.rank("/work/api/src", 10, &weights);
+        assert_eq!((deep.rules, deep.total, deep.pinned), (30.0, 40.0, false));
+        assert_eq!(
+            deep.explanation,
+            ["fuzzy match +10.0", "rule /work/* +30.0"]
+        );
+        let kept = signals.rank("/work/keep", 0, &weights);
+        assert!(kept.pinned);
+        assert_eq!(
+            kept.explanation.last().unwrap(),
+            "pinned by rule /work/keep"
+        );
+        assert_eq!(signals.rank("/tmp/x", 10, &weights).total, -15.0);
+        assert!(compile_rules(&[rule("/work/[", 1.0, false)]).is_err());
+    }
+}
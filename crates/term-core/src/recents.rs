@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{config, detect_projects, CoreError, RecentEntry};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Order of [`crate::api::list_recents`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentsSort {
+    /// By visit count decayed with `recents.half_life_days`, so a directory opened daily
+    /// stays above one visited once a moment ago.
+    #[default]
+    Frecency,
+    /// By last open time only.
+    Recency,
+}
+
+impl FromStr for RecentsSort {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "frecency" => Ok(Self::Frecency),
+            "recency" => Ok(Self::Recency),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown recents sort {other:?} (frecency, recency)"
+            ))),
+        }
+    }
+}
+
+/// `score` recorded at `from`, halved for every `half_life_days` until `to`.
+pub(crate) fn decay(score: f64, from: i64, to: i64, half_life_days: f64) -> f64 {
+    let days = (to - from).max(0) as f64 / SECONDS_PER_DAY;
+    score * 0.5f64.powf(days / half_life_days)
+}
+
+impl RecentEntry {
+    /// The entry's visit score decayed to `now`.
+    pub(crate) fn frecency(&self, now: i64) -> f64 {
+        let half_life = config::config().recents.half_life_days;
+        decay(self.score, self.last_opened_utc, now, half_life)
+    }
+
+    /// Counts a visit at `now`: the old score decays to `now` and the visit adds one.
+    pub(crate) fn visit(&mut self, now: i64) {
+        self.score = self.frecency(now) + 1.0;
+        self.visits = self.visits.saturating_add(1);
+        self.last_opened_utc = now;
+    }
+}
+
+/// `recents` in `sort` order, with each score decayed to `now`.
+pub(crate) fn sorted(
+    mut recents: Vec<RecentEntry>,
+    sort: RecentsSort,
+    now: i64,
+) -> Vec<RecentEntry> {
+    for entry in recents.iter_mut() {
+        entry.score = entry.frecency(now);
+    }
+    match sort {
+        RecentsSort::Frecency => recents.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.last_opened_utc.cmp(&a.last_opened_utc))
+        }),
+        RecentsSort::Recency => {
+            recents.sort_by_key(|entry| std::cmp::Reverse(entry.last_opened_utc))
+        }
+    }
+    recents
+}
+
+/// How [`crate::api::recents_grouped`] buckets recents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentGrouping {
+    /// By the nearest enclosing project root.
+    Project,
+    /// By local calendar day.
+    Day,
+    /// By local calendar week, starting on Monday.
+    Week,
+}
+
+impl FromStr for RecentGrouping {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "project" => Ok(Self::Project),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown grouping {other:?} (project, day, week)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentGroup {
+    /// Stable identifier: the project root (empty for recents outside any project), or the
+    /// first day of the bucket as `YYYY-MM-DD`.
+    pub key: String,
+    /// Section title such as `Today`, `Last week`, or the project name.
+    pub label: String,
+    /// The newest `last_opened_utc` in the group.
+    pub last_opened_utc: i64,
+    /// Newest first.
+    pub entries: Vec<RecentEntry>,
+}
+
+fn local_day(timestamp: i64) -> NaiveDate {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.date_naive())
+        .unwrap_or_default()
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Days::new(day.weekday().num_days_from_monday().into())
+}
+
+/// Key and label of the calendar bucket holding `timestamp`, relative to `today`.
+fn date_bucket(timestamp: i64, by: RecentGrouping, today: NaiveDate) -> (String, String) {
+    let day = local_day(timestamp);
+    let yesterday = today - Days::new(1);
+    match by {
+        RecentGrouping::Week => {
+            let start = week_start(day);
+            let label = match (week_start(today) - start).num_days() / 7 {
+                0 => String::from("This week"),
+                1 => String::from("Last week"),
+                _ => format!("Week of {}", start.format("%b %-d, %Y")),
+            };
+            (start.to_string(), label)
+        }
+        _ => {
+            let label = if day == today {
+                String::from("Today")
+            } else if day == yesterday {
+                String::from("Yesterday")
+            } else if (today - day).num_days() < 7 {
+                day.format("%A").to_string()
+            } else {
+                day.format("%b %-d, %Y").to_string()
+            };
+            (day.to_string(), label)
+        }
+    }
+}
+
+/// Key and label of the project holding `path`.
+fn project_bucket(path: &str) -> (String, String) {
+    match detect_projects(Path::new(path)).into_iter().next() {
+        Some(project) => {
+            let label = Path::new(&project.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| project.path.clone());
+            (project.path, label)
+        }
+        None => (String::new(), String::from("Other")),
+    }
+}
+
+/// Buckets `recents` (newest first) into groups ordered by their newest entry.
+pub(crate) fn group(
+    recents: Vec<RecentEntry>,
+    by: RecentGrouping,
+    now: DateTime<Local>,
+) -> Vec<RecentGroup> {
+    let today = now.date_naive();
+    let mut groups: Vec<RecentGroup> = Vec::new();
+    let mut index: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in recents {
+        let (key, label) = match by {
+            RecentGrouping::Project => project_bucket(&entry.path),
+            RecentGrouping::Day | RecentGrouping::Week => {
+                date_bucket(entry.last_opened_utc, by, today)
+            }
+        };
+        let at = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push(RecentGroup {
+                key,
+                label,
+                last_opened_utc: entry.last_opened_utc,
+                entries: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[at].entries.push(entry);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recents_bucket_by_calendar() {
+        let now = Local.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap();
+        let at = |days: i64| RecentEntry {
+            path: format!("/nowhere/{days}"),
+            last_opened_utc: now.timestamp() - days * 86_400,
+            ..RecentEntry::default()
+        };
+        let recents = vec![at(0), at(1), at(1), at(3), at(9), at(30)];
+
+        let days = group(recents.clone(), RecentGrouping::Day, now);
+        let labels: Vec<&str> = days.iter().map(|group| group.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "Today",
+                "Yesterday",
+                "Monday",
+                "Oct 6, 2026",
+                "Sep 15, 2026"
+            ]
+        );
+        assert_eq!(days[1].entries.len(), 2);
+        assert_eq!(days[0].key, "2026-10-15");
+
+        let weeks = group(recents, RecentGrouping::Week, now);
+        let labels: Vec<&str> = weeks.iter().map(|group| group.label.as_str()).collect();
+        assert_eq!(labels, ["This week", "Last week", "Week of Sep 14, 2026"]);
+        assert_eq!(weeks[0].entries.len(), 4);
+    }
+
+    #[test]
+    fn frequent_visits_outrank_one_recent_visit() {
+        let now = 1_800_000_000;
+        let day = 86_400;
+        let mut daily = RecentEntry {
+            path: String::from("/work"),
+            last_opened_utc: now - 10 * day,
+            ..RecentEntry::default()
+        };
+        for days_ago in (1..10).rev() {
+            daily.visit(now - days_ago * day);
+        }
+        let once = RecentEntry {
+            path: String::from("/tmp/once"),
+            last_opened_utc: now - 60,
+            ..RecentEntry::default()
+        };
+        assert_eq!(daily.visits, 10);
+
+        let frecent = sorted(
+            vec![once.clone(), daily.clone()],
+            RecentsSort::Frecency,
+            now,
+        );
+        assert_eq!(frecent[0].path, "/work");
+        let recent = sorted(vec![daily, once], RecentsSort::Recency, now);
+        assert_eq!(recent[0].path, "/tmp/once");
+        assert!((decay(4.0, 0, 7 * day, 7.0) - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,286 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git::{find_repo_root, git_dir};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteProvider {
+    Github,
+    Gitlab,
+    Bitbucket,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRemote {
+    /// The remote's name: `origin` when there is one, else `upstream`, else the first.
+    pub name: String,
+    /// The URL as configured.
+    pub url: String,
+    pub provider: RemoteProvider,
+    pub host: String,
+    /// User, organization, or (for nested GitLab groups) `group/subgroup`.
+    pub owner: String,
+    pub repo: String,
+    /// The repository's page, for "open on GitHub" actions.
+    pub web_url: String,
+    /// From the provider's API, only when a token is configured; see [`project_remote`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stars: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+}
+
+/// `[remote "NAME"]` sections of a git config file as `(name, url)`, in file order.
+fn config_remotes(config: &str) -> Vec<(String, String)> {
+    let mut remotes = Vec::new();
+    let mut section: Option<String> = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line
+                .strip_prefix("[remote \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(str::to_string);
+            continue;
+        }
+        let Some(name) = &section else {
+            continue;
+        };
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("url") {
+                remotes.push((name.clone(), value.trim().trim_matches('"').to_string()));
+            }
+        }
+    }
+    remotes
+}
+
+/// Host and repository path of an `https://`, `ssh://`, `git://`, or scp-style
+/// (`git@host:owner/repo.git`) remote URL. The port is kept for web URLs only.
+fn split_url(url: &str) -> Option<(String, String)> {
+    if let Some((scheme, rest)) = url.split_once("://") {
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+        let (authority, path) = rest.split_once('/')?;
+        let host = match scheme {
+            "http" | "https" => authority,
+            _ => authority.split(':').next()?,
+        };
+        return Some((host.to_string(), path.to_string()));
+    }
+    let (user_host, path) = url.split_once(':')?;
+    if user_host.contains('/') || path.starts_with("//") {
+        return None;
+    }
+    let host = user_host
+        .rsplit_once('@')
+        .map_or(user_host, |(_, host)| host);
+    Some((host.to_string(), path.to_string()))
+}
+
+fn parse_remote(name: &str, url: &str) -> Option<ProjectRemote> {
+    let (host, path) = split_url(url)?;
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.rsplit_once('/')?;
+    if owner.is_empty() || repo.is_empty() || host.is_empty() {
+        return None;
+    }
+    let lower = host.to_ascii_lowercase();
+    let provider = if lower.contains("github") {
+        RemoteProvider::Github
+    } else if lower.contains("gitlab") {
+        RemoteProvider::Gitlab
+    } else if lower.contains("bitbucket") {
+        RemoteProvider::Bitbucket
+    } else {
+        RemoteProvider::Other
+    };
+    Some(ProjectRemote {
+        name: name.to_string(),
+        url: url.to_string(),
+        provider,
+        web_url: format!("https://{host}/{owner}/{repo}"),
+        host,
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        stars: None,
+        description: None,
+        default_branch: None,
+    })
+}
+
+/// The preferred remote of the repository containing `path`, read from its config file.
+pub(crate) fn read_remote(path: &Path) -> Option<ProjectRemote> {
+    let root = find_repo_root(path)?;
+    let dir = git_dir(&root)?;
+    // Worktrees keep their remotes in the main repository's config.
+    let common = std::fs::read_to_string(dir.join("commondir"))
+        .map(|common| dir.join(common.trim()))
+        .unwrap_or(dir);
+    let config = std::fs::read_to_string(common.join("config")).ok()?;
+    let remotes = config_remotes(&config);
+    let preferred = ["origin", "upstream"]
+        .iter()
+        .find_map(|name| remotes.iter().find(|(remote, _)| remote == name))
+        .or_else(|| remotes.first())?;
+    parse_remote(&preferred.0, &preferred.1)
+}
+
+/// A token from `GITHUB_TOKEN`/`GITLAB_TOKEN`, else the `github_token`/`gitlab_token` secret.
+#[cfg(feature = "remote-api")]
+fn token(provider: RemoteProvider) -> Option<String> {
+    let (variable, secret) = match provider {
+        RemoteProvider::Github => ("GITHUB_TOKEN", "github_token"),
+        RemoteProvider::Gitlab => ("GITLAB_TOKEN", "gitlab_token"),
+        RemoteProvider::Bitbucket | RemoteProvider::Other => return None,
+    };
+    std::env::var(variable)
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .or_else(|| crate::secrets::get_secret(secret).ok())
+}
+
+/// Fills in stars, description, and default branch from the GitHub or GitLab API.
+#[cfg(feature = "remote-api")]
+fn enrich(remote: &mut ProjectRemote, token: &str) -> anyhow::Result<()> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(5))
+        .user_agent(concat!("terminaut/", env!("CARGO_PKG_VERSION")))
+        .build();
+    let (stars_key, response) = match remote.provider {
+        RemoteProvider::Github => {
+            let api = match remote.host.eq_ignore_ascii_case("github.com") {
+                true => String::from("https://api.github.com"),
+                false => format!("https://{}/api/v3", remote.host),
+            };
+            let response = agent
+                .get(&format!("{api}/repos/{}/{}", remote.owner, remote.repo))
+                .set("Authorization", &format!("Bearer {token}"))
+                .set("Accept", "application/vnd.github+json")
+                .call()?;
+            ("stargazers_count", response)
+        }
+        RemoteProvider::Gitlab => {
+            let id: String = url::form_urlencoded::byte_serialize(
+                format!("{}/{}", remote.owner, remote.repo).as_bytes(),
+            )
+            .collect();
+            let response = agent
+                .get(&format!("https://{}/api/v4/projects/{id}", remote.host))
+                .set("PRIVATE-TOKEN", token)
+                .call()?;
+            ("star_count", response)
+        }
+        RemoteProvider::Bitbucket | RemoteProvider::Other => return Ok(()),
+    };
+    let body: serde_json::Value = response.into_json()?;
+    remote.stars = body[stars_key].as_u64();
+    remote.description = body["description"]
+        .as_str()
+        .filter(|description| !description.is_empty())
+        .map(str::to_string);
+    remote.default_branch = body["default_branch"].as_str().map(str::to_string);
+    Ok(())
+}
+
+/// The git remote of the project containing `path`: host, owner, repo, and web URL. With a
+/// GitHub or GitLab token in the environment or the keychain, also asks the API for stars,
+/// description, and default branch; API failures leave those empty.
+pub(crate) fn project_remote(path: &Path) -> Option<ProjectRemote> {
+    #[cfg_attr(not(feature = "remote-api"), allow(unused_mut))]
+    let mut remote = read_remote(path)?;
+    #[cfg(feature = "remote-api")]
+    if let Some(token) = token(remote.provider) {
+        if let Err(err) = enrich(&mut remote, &token) {
+            tracing::debug!(web_url = %remote.web_url, "remote metadata unavailable: {err:#}");
+        }
+    }
+    Some(remote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_remotes_come_in_file_order() {
+        let config = "[core]\n\tbare = false\n[remote \"upstream\"]\n\turl = https://github.com/up/tool.git\n\
+                      [remote \"origin\"]\n\turl = git@github.com:me/tool.git\n\tfetch = +refs/heads/*\n";
+        assert_eq!(
+            config_remotes(config),
+            [
+                ("upstream".into(), "https://github.com/up/tool.git".into()),
+                ("origin".into(), "git@github.com:me/tool.git".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn urls_outside_remote_sections_are_ignored() {
+        let config = "[submodule \"lib\"]\n\turl = https://github.com/a/lib\n[remote \"origin\"]\n\
+                      \tURL = \"https://github.com/a/app\"\n";
+        assert_eq!(
+            config_remotes(config),
+            [("origin".into(), "https://github.com/a/app".into())]
+        );
+    }
+
+    #[test]
+    fn scp_style_urls_parse() {
+        let origin = parse_remote("origin", "git@github.com:me/tool.git").unwrap();
+        assert_eq!(origin.provider, RemoteProvider::Github);
+        assert_eq!(
+            (origin.owner.as_str(), origin.repo.as_str()),
+            ("me", "tool")
+        );
+        assert_eq!(origin.web_url, "https://github.com/me/tool");
+    }
+
+    #[test]
+    fn ssh_ports_are_dropped_and_nested_groups_kept() {
+        let nested = parse_remote(
+            "origin",
+            "ssh://git@gitlab.example.com:2222/group/sub/app.git",
+        )
+        .unwrap();
+        assert_eq!(nested.provider, RemoteProvider::Gitlab);
+        assert_eq!(nested.owner, "group/sub");
+        assert_eq!(nested.web_url, "https://gitlab.example.com/group/sub/app");
+    }
+
+    #[test]
+    fn https_ports_are_kept() {
+        let local = parse_remote("origin", "https://git.local:8443/team/app/").unwrap();
+        assert_eq!(local.provider, RemoteProvider::Other);
+        assert_eq!(local.web_url, "https://git.local:8443/team/app");
+    }
+
+    #[test]
+    fn local_and_ownerless_remotes_do_not_parse() {
+        assert!(parse_remote("origin", "/srv/git/app.git").is_none());
+        assert!(parse_remote("origin", "file:///srv/app.git").is_none());
+        assert!(parse_remote("origin", "https://github.com/app").is_none());
+        assert!(parse_remote("origin", "").is_none());
+    }
+
+    #[test]
+    fn origin_is_preferred_over_upstream_and_the_rest() {
+        let root = std::env::temp_dir().join(format!("terminaut-remote-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        let write = |config: &str| std::fs::write(root.join(".git/config"), config).unwrap();
+        write(
+            "[remote \"fork\"]\n\turl = https://github.com/f/app\n[remote \"upstream\"]\n\
+               \turl = https://github.com/u/app\n",
+        );
+        assert_eq!(read_remote(&root).unwrap().name, "upstream");
+        write("[remote \"fork\"]\n\turl = https://github.com/f/app\n");
+        assert_eq!(read_remote(&root).unwrap().owner, "f");
+        write("[core]\n");
+        assert!(read_remote(&root).is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeRoot {
+    pub path: String,
+    /// Display name: the volume label where the OS exposes one, otherwise the mount point name.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fs_type: Option<String>,
+    pub is_network: bool,
+    pub is_removable: bool,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const NETWORK_FS_TYPES: [&str; 11] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "afpfs",
+    "webdav",
+    "fuse.sshfs",
+    "sshfs",
+    "9p",
+    "afs",
+];
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn is_network_fs(fs_type: &str) -> bool {
+    NETWORK_FS_TYPES.contains(&fs_type)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    const PSEUDO_FS_TYPES: [&str; 24] = [
+        "proc",
+        "sysfs",
+        "devtmpfs",
+        "devpts",
+        "tmpfs",
+        "cgroup",
+        "cgroup2",
+        "pstore",
+        "bpf",
+        "tracefs",
+        "debugfs",
+        "securityfs",
+        "mqueue",
+        "hugetlbfs",
+        "fusectl",
+        "configfs",
+        "autofs",
+        "binfmt_misc",
+        "squashfs",
+        "nsfs",
+        "ramfs",
+        "efivarfs",
+        "rpc_pipefs",
+        "selinuxfs",
+    ];
+    const SYSTEM_PREFIXES: [&str; 6] = ["/proc", "/sys", "/dev", "/run", "/snap", "/boot"];
+
+    fn mount_name(path: &str) -> String {
+        std::path::Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    /// `/proc/mounts` escapes whitespace in mount points as octal sequences like `\040`.
+    fn unescape(field: &str) -> String {
+        let bytes = field.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'\\' && i + 3 < bytes.len() {
+                let digits = std::str::from_utf8(&bytes[i + 1..i + 4]).ok();
+                if let Some(value) = digits.and_then(|d| u8::from_str_radix(d, 8).ok()) {
+                    out.push(value);
+                    i += 4;
+                    continue;
+                }
+            }
+            out.push(bytes[i]);
+            i += 1;
+        }
+        String::from_utf8_lossy(&out).to_string()
+    }
+
+    fn is_user_visible(mount_point: &str, fs_type: &str) -> bool {
+        if mount_point == "/" || is_network_fs(fs_type) {
+            return true;
+        }
+        if PSEUDO_FS_TYPES.contains(&fs_type) {
+            return false;
+        }
+        if mount_point.starts_with("/run/media/") {
+            return true;
+        }
+        !SYSTEM_PREFIXES
+            .iter()
+            .any(|prefix| mount_point == *prefix || mount_point.starts_with(&format!("{prefix}/")))
+    }
+
+    pub(super) fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+        let mounts = std::fs::read_to_string("/proc/self/mounts")?;
+        let mut roots: Vec<VolumeRoot> = Vec::new();
+        for line in mounts.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(_device), Some(mount_point), Some(fs_type)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let mount_point = unescape(mount_point);
+            if !is_user_visible(&mount_point, fs_type)
+                || roots.iter().any(|root| root.path == mount_point)
+            {
+                continue;
+            }
+            roots.push(VolumeRoot {
+                name: mount_name(&mount_point),
+                is_network: is_network_fs(fs_type),
+                is_removable: mount_point.starts_with("/media/")
+                    || mount_point.starts_with("/run/media/"),
+                fs_type: Some(fs_type.to_string()),
+                path: mount_point,
+            });
+        }
+        Ok(roots)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn unescapes_octal_spaces() {
+            assert_eq!(unescape("/media/My\\040Disk"), "/media/My Disk");
+            assert_eq!(unescape("/plain"), "/plain");
+        }
+
+        #[test]
+        fn filters_pseudo_mounts() {
+            assert!(is_user_visible("/", "overlay"));
+            assert!(is_user_visible("/mnt/nas", "nfs4"));
+            assert!(is_user_visible("/run/media/me/USB", "vfat"));
+            assert!(!is_user_visible("/proc", "proc"));
+            assert!(!is_user_visible("/dev/shm", "tmpfs"));
+            assert!(!is_user_visible("/sys/fs/cgroup", "cgroup2"));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+    use std::process::Command;
+
+    /// Parses `mount` output lines such as `//me@nas/share on /Volumes/share (smbfs, nodev)`.
+    fn mounted_fs_types() -> Vec<(String, String)> {
+        let output = match Command::new("/sbin/mount").output() {
+            Ok(output) => output,
+            Err(_) => return Vec::new(),
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (_, rest) = line.split_once(" on ")?;
+                let (mount_point, options) = rest.rsplit_once(" (")?;
+                let fs_type = options.split([',', ')']).next()?.trim();
+                Some((mount_point.to_string(), fs_type.to_string()))
+            })
+            .collect()
+    }
+
+    pub(super) fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+        let fs_types = mounted_fs_types();
+        let fs_type_for = |path: &str| {
+            fs_types
+                .iter()
+                .find(|(mount_point, _)| mount_point == path)
+                .map(|(_, fs_type)| fs_type.clone())
+        };
+        let mut root_name = String::from("/");
+        let mut roots = Vec::new();
+        for entry in std::fs::read_dir("/Volumes")?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            // The boot volume shows up in /Volumes as a symlink back to `/`.
+            if std::fs::canonicalize(&path).is_ok_and(|target| target == std::path::Path::new("/"))
+            {
+                root_name = name;
+                continue;
+            }
+            let path = path.display().to_string();
+            let fs_type = fs_type_for(&path);
+            roots.push(VolumeRoot {
+                is_network: fs_type.as_deref().is_some_and(is_network_fs),
+                is_removable: fs_type
+                    .as_deref()
+                    .is_some_and(|fs_type| matches!(fs_type, "msdos" | "exfat")),
+                fs_type,
+                name,
+                path,
+            });
+        }
+        roots.sort_by_key(|root| root.name.to_lowercase());
+        roots.insert(
+            0,
+            VolumeRoot {
+                path: String::from("/"),
+                name: root_name,
+                fs_type: fs_type_for("/"),
+                is_network: false,
+                is_removable: false,
+            },
+        );
+        Ok(roots)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    const DRIVE_REMOVABLE: u32 = 2;
+    const DRIVE_REMOTE: u32 = 4;
+    const DRIVE_CDROM: u32 = 5;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+    }
+
+    pub(super) fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+        let mut roots = Vec::new();
+        for letter in b'A'..=b'Z' {
+            let path = format!("{}:\\", letter as char);
+            if !std::path::Path::new(&path).exists() {
+                continue;
+            }
+            let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+            let drive_type = unsafe { GetDriveTypeW(wide.as_ptr()) };
+            roots.push(VolumeRoot {
+                name: format!("{}:", letter as char),
+                fs_type: None,
+                is_network: drive_type == DRIVE_REMOTE,
+                is_removable: matches!(drive_type, DRIVE_REMOVABLE | DRIVE_CDROM),
+                path,
+            });
+        }
+        Ok(roots)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::*;
+
+    pub(super) fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+        Ok(vec![VolumeRoot {
+            path: String::from("/"),
+            name: String::from("/"),
+            fs_type: None,
+            is_network: false,
+            is_removable: false,
+        }])
+    }
+}
+
+pub(crate) fn list_roots() -> anyhow::Result<Vec<VolumeRoot>> {
+    platform::list_roots()
+}
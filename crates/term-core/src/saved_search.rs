@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+use crate::search::{search_directories, SearchOptions, SearchResult};
+use crate::{normalize_path, CoreError, PersistedState, STORE};
+
+fn default_limit() -> usize {
+    20
+}
+
+/// A named search re-run on demand, e.g. a sidebar "smart folder".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub root: String,
+    /// Fuzzy query; may be empty when `options.include` globs do the selecting.
+    #[serde(default)]
+    pub query: String,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub options: SearchOptions,
+}
+
+fn sorted(state: &PersistedState) -> Vec<SavedSearch> {
+    let mut searches = state.saved_searches.clone();
+    searches.sort_by_key(|search| search.name.to_lowercase());
+    searches
+}
+
+pub(crate) fn list_saved_searches() -> Vec<SavedSearch> {
+    sorted(&STORE.inner.lock())
+}
+
+/// Checks and normalizes a search before it is saved.
+fn build(
+    name: &str,
+    root: &str,
+    query: &str,
+    limit: usize,
+    options: SearchOptions,
+) -> anyhow::Result<SavedSearch> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("saved search name required"));
+    }
+    if query.trim().is_empty() && options.include.is_empty() {
+        anyhow::bail!(CoreError::invalid_input(
+            "saved search needs a query or include globs"
+        ));
+    }
+    Ok(SavedSearch {
+        name: name.to_string(),
+        root: normalize_path(root)?.display().to_string(),
+        query: query.trim().to_string(),
+        limit: limit.max(1),
+        options,
+    })
+}
+
+/// Adds `search`, replacing any saved under the same name in another case.
+fn upsert(state: &mut PersistedState, search: SavedSearch) {
+    state
+        .saved_searches
+        .retain(|existing| !existing.name.eq_ignore_ascii_case(&search.name));
+    state.saved_searches.push(search);
+}
+
+fn remove(state: &mut PersistedState, name: &str) -> anyhow::Result<()> {
+    let before = state.saved_searches.len();
+    state
+        .saved_searches
+        .retain(|search| !search.name.eq_ignore_ascii_case(name));
+    if before == state.saved_searches.len() {
+        anyhow::bail!(CoreError::not_found("saved search not found"));
+    }
+    Ok(())
+}
+
+fn find(state: &PersistedState, name: &str) -> anyhow::Result<SavedSearch> {
+    state
+        .saved_searches
+        .iter()
+        .find(|search| search.name.eq_ignore_ascii_case(name))
+        .cloned()
+        .ok_or_else(|| CoreError::not_found(format!("saved search not found: {name}")).into())
+}
+
+pub(crate) fn save_saved_search(
+    name: &str,
+    root: &str,
+    query: &str,
+    limit: usize,
+    options: SearchOptions,
+) -> anyhow::Result<SavedSearch> {
+    let search = build(name, root, query, limit, options)?;
+    let mut store = STORE.inner.lock();
+    upsert(&mut store, search.clone());
+    STORE.persist(&mut store)?;
+    Ok(search)
+}
+
+pub(crate) fn delete_saved_search(name: &str) -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    remove(&mut store, name)?;
+    STORE.persist(&mut store)?;
+    Ok(())
+}
+
+pub(crate) fn run_saved_search(name: &str) -> anyhow::Result<Vec<SearchResult>> {
+    let search = find(&STORE.inner.lock(), name)?;
+    let root = normalize_path(&search.root)?;
+    search_directories(&root, &search.query, search.limit, &search.options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn saved(name: &str, query: &str) -> SavedSearch {
+        build(
+            name,
+            &std::env::temp_dir().display().to_string(),
+            query,
+            20,
+            SearchOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn searches_need_a_name_and_something_to_match() {
+        let root = std::env::temp_dir().display().to_string();
+        let options = SearchOptions::default;
+        let unnamed = build("  ", &root, "api", 20, options()).unwrap_err();
+        assert_eq!(error_code(&unnamed), ErrorCode::InvalidInput);
+        let empty = build("all", &root, " ", 20, options()).unwrap_err();
+        assert_eq!(error_code(&empty), ErrorCode::InvalidInput);
+        let globbed = SearchOptions {
+            include: vec![String::from("**/.git")],
+            ..options()
+        };
+        assert!(build("repos", &root, "", 20, globbed).is_ok());
+    }
+
+    #[test]
+    fn saved_searches_are_trimmed_and_keep_a_limit() {
+        let search = build(
+            " api ",
+            &std::env::temp_dir().display().to_string(),
+            " svc ",
+            0,
+            SearchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(search.name, "api");
+        assert_eq!(search.query, "svc");
+        assert_eq!(search.limit, 1);
+        let stored: SavedSearch =
+            serde_json::from_str(r#"{"name": "api", "root": "/srv"}"#).unwrap();
+        assert_eq!(stored.limit, 20);
+        assert!(stored.query.is_empty());
+    }
+
+    #[test]
+    fn saving_under_a_name_in_another_case_replaces_it() {
+        let mut state = PersistedState::default();
+        upsert(&mut state, saved("Web", "site"));
+        upsert(&mut state, saved("api", "svc"));
+        upsert(&mut state, saved("WEB", "www"));
+        let names: Vec<String> = sorted(&state).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["api", "WEB"]);
+        assert_eq!(find(&state, "web").unwrap().query, "www");
+    }
+
+    #[test]
+    fn missing_searches_are_not_found() {
+        let mut state = PersistedState::default();
+        upsert(&mut state, saved("api", "svc"));
+        assert_eq!(
+            error_code(&find(&state, "web").unwrap_err()),
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            error_code(&remove(&mut state, "web").unwrap_err()),
+            ErrorCode::NotFound
+        );
+        remove(&mut state, "API").unwrap();
+        assert!(state.saved_searches.is_empty());
+    }
+}
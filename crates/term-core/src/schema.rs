@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use schemars::schema_for;
+
+use crate::{CoreError, DirectoryEntry, LaunchProfile, RecentEntry, SearchResult, TaggedPath};
+
+/// JSON Schema for each type that crosses the FFI and CLI boundary, keyed by type name.
+/// Front-ends generate their models from these so field changes surface at build time.
+pub(crate) fn json_schemas() -> BTreeMap<String, serde_json::Value> {
+    let schemas = [
+        ("DirectoryEntry", schema_for!(DirectoryEntry)),
+        ("RecentEntry", schema_for!(RecentEntry)),
+        ("TaggedPath", schema_for!(TaggedPath)),
+        ("LaunchProfile", schema_for!(LaunchProfile)),
+        ("SearchResult", schema_for!(SearchResult)),
+        ("Error", schema_for!(CoreError)),
+    ];
+    schemas
+        .into_iter()
+        .map(|(name, schema)| {
+            let schema = serde_json::to_value(schema).unwrap_or_default();
+            (name.to_string(), schema)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schemas_follow_serde_names() {
+        let schemas = json_schemas();
+        let entry = &schemas["DirectoryEntry"];
+        assert!(entry["properties"]["mod_date"].is_object());
+        let required = entry["required"].as_array().unwrap();
+        assert!(!required.contains(&serde_json::json!("mod_date")));
+        assert_eq!(
+            schemas["Error"]["properties"]["code"]["$ref"],
+            "#/definitions/ErrorCode"
+        );
+    }
+}
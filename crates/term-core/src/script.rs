@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use anyhow::Context;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, INT};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::api;
+use crate::{GrepOptions, SearchOptions};
+
+type ScriptResult = Result<Dynamic, Box<EvalAltResult>>;
+
+fn convert<T: Serialize>(result: anyhow::Result<T>) -> ScriptResult {
+    let value = result.map_err(|err| format!("{err:#}"))?;
+    to_dynamic(value)
+}
+
+fn options<T: DeserializeOwned>(map: Map) -> Result<T, Box<EvalAltResult>> {
+    from_dynamic(&Dynamic::from_map(map))
+}
+
+fn limit(value: INT) -> usize {
+    value.max(0) as usize
+}
+
+/// Registers the `api` module as script functions. Results come back as plain maps and arrays
+/// with the same field names as the JSON the CLI prints.
+fn register_api(engine: &mut Engine) {
+    engine
+        .register_fn("normalize_path", |path: &str| {
+            convert(api::normalize_path(path))
+        })
+        .register_fn("list_directory", |path: &str| {
+            convert(api::list_directory(path))
+        })
+        .register_fn("list_roots", || convert(api::list_roots()))
+        .register_fn("ancestors", |path: &str| convert(api::ancestors(path)))
+        .register_fn("preview", |path: &str| convert(api::preview(path)))
+        .register_fn("dir_size", |path: &str| {
+            convert(api::dir_size_blocking(path))
+        })
+        .register_fn("list_favorites", || convert(Ok(api::list_favorites())))
+        .register_fn("add_favorite", |path: &str| {
+            convert(api::add_favorite(path))
+        })
+        .register_fn("remove_favorite", |path: &str| {
+            convert(api::remove_favorite(path))
+        })
+        .register_fn("list_recents", || convert(Ok(api::list_recents())))
+        .register_fn("touch_recent", |path: &str| {
+            convert(api::touch_recent(path))
+        })
+        .register_fn("detect_projects", |path: &str| {
+            convert(api::detect_projects(path))
+        })
+        .register_fn("project_info", |path: &str| {
+            convert(api::project_info(path))
+        })
+        .register_fn("list_tags", || convert(Ok(api::list_tags())))
+        .register_fn("tags_for", |path: &str| convert(api::tags_for(path)))
+        .register_fn("set_tag", |path: &str, tag: &str| {
+            convert(api::set_tag(path, tag, None))
+        })
+        .register_fn("set_tag", |path: &str, tag: &str, color: &str| {
+            convert(api::set_tag(path, tag, Some(color)))
+        })
+        .register_fn("remove_tag", |path: &str, tag: &str| {
+            convert(api::remove_tag(path, tag))
+        })
+        .register_fn("list_profiles", || convert(Ok(api::list_profiles())))
+        .register_fn("list_sessions", || convert(Ok(api::list_sessions())))
+        .register_fn("restore_session", |name: &str| {
+            convert(api::restore_session(name))
+        })
+        .register_fn("list_saved_searches", || {
+            convert(Ok(api::list_saved_searches()))
+        })
+        .register_fn("run_saved_search", |name: &str| {
+            convert(api::run_saved_search(name))
+        })
+        .register_fn("search", |path: &str, query: &str, max: INT| {
+            let options = SearchOptions {
+                record_history: false,
+                ..SearchOptions::default()
+            };
+            convert(api::search(path, query, limit(max), &options))
+        })
+        .register_fn("search", |path: &str, query: &str, max: INT, opts: Map| {
+            let options: SearchOptions = options(opts)?;
+            convert(api::search(path, query, limit(max), &options))
+        })
+        .register_fn("grep", |path: &str, pattern: &str| {
+            convert(api::grep(path, pattern, &GrepOptions::default()))
+        })
+        .register_fn("grep", |path: &str, pattern: &str, opts: Map| {
+            let options: GrepOptions = options(opts)?;
+            convert(api::grep(path, pattern, &options))
+        })
+        .register_fn("create_dir", |path: &str| convert(api::create_dir(path)))
+        .register_fn("rename", |path: &str, new_name: &str| {
+            convert(api::rename(path, new_name))
+        })
+        .register_fn("move_path", |path: &str, destination: &str| {
+            convert(api::move_path(path, destination))
+        })
+        .register_fn("trash", |path: &str| convert(api::trash(path)))
+        .register_fn("prune_state", || convert(api::prune_state()))
+        .register_fn("get_config", || convert(api::get_config()))
+        .register_fn("set_config", |key: &str, value: &str| {
+            convert(api::set_config(key, value))
+        });
+}
+
+/// Runs a Rhai script with the api registered. `args` is available to the script as the
+/// `ARGS` array; the value of the script's final expression is returned as JSON.
+pub(crate) fn run_script(path: &Path, args: &[String]) -> anyhow::Result<serde_json::Value> {
+    let source =
+        std::fs::read_to_string(path).with_context(|| format!("read script {}", path.display()))?;
+    let mut engine = Engine::new();
+    register_api(&mut engine);
+    let mut scope = Scope::new();
+    let args: Array = args.iter().cloned().map(Dynamic::from).collect();
+    scope.push_constant("ARGS", args);
+    let result = engine
+        .eval_with_scope::<Dynamic>(&mut scope, &source)
+        .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+    if result.is_unit() {
+        return Ok(serde_json::Value::Null);
+    }
+    from_dynamic(&result).map_err(|err| anyhow::anyhow!("script result: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_see_args_and_api() {
+        let script = std::env::temp_dir().join("terminaut-script-test.rhai");
+        std::fs::write(
+            &script,
+            r#"let root = normalize_path(ARGS[0]); #{ root: root, count: ARGS.len() }"#,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().display().to_string();
+        let result = run_script(&script, &[dir]);
+        std::fs::remove_file(&script).ok();
+        let result = result.unwrap();
+        assert_eq!(result["count"], 1);
+        assert!(result["root"].is_string());
+    }
+}
@@ -0,0 +1,543 @@
+#[cfg(feature = "fs")]
+use std::cmp::Reverse;
+#[cfg(feature = "fs")]
+use std::collections::BinaryHeap;
+use std::path::Path;
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "fs")]
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+#[cfg(feature = "fs")]
+use ignore::{DirEntry, WalkBuilder, WalkState};
+#[cfg(feature = "fs")]
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::matching::MatchOptions;
+#[cfg(feature = "fs")]
+use crate::matching::QueryMatcher;
+#[cfg(feature = "fs")]
+use crate::ranking::StoreSignals;
+use crate::ranking::{RankBreakdown, RankWeights};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SearchResult {
+    pub path: String,
+    pub name: String,
+    /// Path relative to the search root, for compact display.
+    pub relative_path: String,
+    pub score: i64,
+    /// Character (not byte) positions in `name` that matched the query, for highlighting.
+    pub indices: Vec<usize>,
+    /// Components of the blended rank that results are ordered by.
+    pub rank: RankBreakdown,
+    /// For a registered project found by its README title, package name, or description, or a
+    /// directory found by its note, rather than by its folder name: the text that matched.
+    /// `indices` is then empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Maximum depth below the search root; `None` walks the whole tree.
+    pub max_depth: Option<usize>,
+    /// Honor `.gitignore`, `.ignore`, and git exclude files.
+    pub respect_gitignore: bool,
+    pub follow_symlinks: bool,
+    /// Include hidden (dot) directories.
+    pub hidden: bool,
+    /// When non-empty, only directories whose name or relative path matches one of these globs
+    /// are returned. Non-matching directories are still descended into.
+    pub include: Vec<String>,
+    /// Directories matching any of these globs are skipped along with everything below them.
+    pub exclude: Vec<String>,
+    /// Walker threads; `0` picks a count from the available cores. See `benches/search.rs`.
+    pub threads: usize,
+    pub matching: MatchOptions,
+    pub ranking: RankWeights,
+    /// Remember the query in the search history. Typeahead callers usually turn this off.
+    pub record_history: bool,
+    /// Also match projects in the registry (see [`crate::api::refresh_projects`]) by their
+    /// README title, package name, and manifest description, and directories by their note.
+    pub project_text: bool,
+    /// Stop walking after this many milliseconds and rank what was found by then; `None`
+    /// walks to the end.
+    pub time_budget_ms: Option<u64>,
+}
+
+impl Default for SearchOptions {
+    /// Depth and exclusions come from the `[search]` section of the config.
+    fn default() -> Self {
+        Self::from_config(&crate::config::config())
+    }
+}
+
+impl SearchOptions {
+    /// The defaults, with depth and exclusions from the `[search]` section of `config`.
+    fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            max_depth: Some(config.search.max_depth).filter(|depth| *depth > 0),
+            respect_gitignore: true,
+            follow_symlinks: false,
+            hidden: false,
+            include: Vec::new(),
+            exclude: config.search.ignore.clone(),
+            threads: 0,
+            matching: MatchOptions::default(),
+            ranking: RankWeights::default(),
+            record_history: true,
+            project_text: true,
+            time_budget_ms: Some(config.search.time_budget_ms).filter(|budget| *budget > 0),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// When a walk started now must stop, per [`SearchOptions::time_budget_ms`].
+    #[cfg(feature = "fs")]
+    pub(crate) fn time_budget(&self) -> Option<Deadline> {
+        self.time_budget_ms.map(|budget| Deadline {
+            at: Instant::now() + Duration::from_millis(budget),
+        })
+    }
+}
+
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy)]
+pub(crate) struct Deadline {
+    at: Instant,
+}
+
+#[cfg(feature = "fs")]
+impl Deadline {
+    pub(crate) fn passed(self) -> bool {
+        Instant::now() >= self.at
+    }
+}
+
+/// Folder-name matches kept per result asked for. They are ranked as they are found, so a
+/// favorite, a recent, or a pinned path with a weak fuzzy score is kept over stronger plain
+/// matches; the spare room is for project text matches, which join them before the cut.
+#[cfg(feature = "fs")]
+const POOL_PER_RESULT: usize = 4;
+
+/// Orders ranks the way results are listed: pinned first, then by total.
+#[cfg(feature = "fs")]
+fn by_rank(a: &RankBreakdown, b: &RankBreakdown) -> std::cmp::Ordering {
+    a.pinned.cmp(&b.pinned).then(a.total.total_cmp(&b.total))
+}
+
+/// A match ordered by rank, then by path so ties keep the same winners whatever order the
+/// walker threads find them in.
+#[cfg(feature = "fs")]
+struct ByRank(SearchResult);
+
+#[cfg(feature = "fs")]
+impl Ord for ByRank {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        by_rank(&self.0.rank, &other.0.rank).then_with(|| other.0.path.cmp(&self.0.path))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl PartialOrd for ByRank {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl PartialEq for ByRank {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Eq for ByRank {}
+
+/// The best-ranked matches seen so far, at most [`POOL_PER_RESULT`] per result, in a
+/// min-heap so the weakest is the one replaced. A strong match found late in a walk displaces
+/// weak ones found early instead of being cut off by them.
+#[cfg(feature = "fs")]
+pub(crate) struct TopMatches {
+    heap: BinaryHeap<Reverse<ByRank>>,
+    capacity: usize,
+}
+
+#[cfg(feature = "fs")]
+impl TopMatches {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            capacity: limit.max(1).saturating_mul(POOL_PER_RESULT),
+        }
+    }
+
+    /// Whether a match ranked `rank` (see [`StoreSignals::quick_rank`]) could be kept, so
+    /// callers can skip building the rest of a result that would be dropped straight away.
+    pub(crate) fn admits(&self, rank: &RankBreakdown) -> bool {
+        self.heap.len() < self.capacity
+            || self
+                .heap
+                .peek()
+                .is_some_and(|Reverse(weakest)| by_rank(rank, &weakest.0.rank).is_ge())
+    }
+
+    /// Keeps `result`, which must already carry its rank.
+    pub(crate) fn push(&mut self, result: SearchResult) {
+        self.heap.push(Reverse(ByRank(result)));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<SearchResult> {
+        self.heap
+            .into_iter()
+            .map(|Reverse(ByRank(result))| result)
+            .collect()
+    }
+}
+
+pub(crate) fn build_globs(patterns: &[String]) -> anyhow::Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid glob {pattern:?}"))?;
+        builder.add(glob);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn matches_globs(globs: &GlobSet, root: &Path, entry: &DirEntry) -> bool {
+    globs.is_match(entry.file_name())
+        || entry
+            .path()
+            .strip_prefix(root)
+            .is_ok_and(|relative| globs.is_match(relative))
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn walker(root: &Path, options: &SearchOptions) -> anyhow::Result<WalkBuilder> {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .max_depth(options.max_depth)
+        .hidden(!options.hidden)
+        .parents(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .follow_links(options.follow_symlinks)
+        .threads(options.threads);
+    crate::ignore_file::apply(&mut builder);
+    if let Some(exclude) = build_globs(&options.exclude)? {
+        let root = root.to_path_buf();
+        builder.filter_entry(move |entry| !matches_globs(&exclude, &root, entry));
+    }
+    Ok(builder)
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn search_directories(
+    root: &Path,
+    query: &str,
+    limit: usize,
+    options: &SearchOptions,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let started = std::time::Instant::now();
+    let results = search_untracked(root, query, limit, options)?;
+    crate::stats::record(crate::stats::Usage::Search(started.elapsed()));
+    Ok(results)
+}
+
+/// [`search_directories`] without counting toward usage stats, for self-tests.
+#[cfg(feature = "fs")]
+pub(crate) fn search_untracked(
+    root: &Path,
+    query: &str,
+    limit: usize,
+    options: &SearchOptions,
+) -> anyhow::Result<Vec<SearchResult>> {
+    if query.trim().is_empty() && options.include.is_empty() {
+        anyhow::bail!("query required");
+    }
+    if crate::candidates::cache_ttl(root).is_some() {
+        let candidates = crate::candidates::candidates(root, options)?;
+        let matcher = QueryMatcher::new(query, &options.matching);
+        let signals = StoreSignals::snapshot();
+        let mut top = TopMatches::new(limit);
+        for candidate in &candidates.entries {
+            if let Some((score, indices)) = matcher.match_name(&candidate.name) {
+                let rank = signals.quick_rank(&candidate.path, score, &options.ranking);
+                if top.admits(&rank) {
+                    top.push(SearchResult {
+                        rank,
+                        ..candidate.to_result(score, indices)
+                    });
+                }
+            }
+        }
+        return finish(root, query, limit, options, &signals, top.into_vec());
+    }
+    let signals = StoreSignals::snapshot();
+    let results = walk_matches(root, query, limit, options, &signals)?;
+    finish(root, query, limit, options, &signals, results)
+}
+
+/// The best matches of `query` below `root`, walking the tree and matching as it goes.
+#[cfg(feature = "fs")]
+fn walk_matches(
+    root: &Path,
+    query: &str,
+    limit: usize,
+    options: &SearchOptions,
+    signals: &StoreSignals,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let include = build_globs(&options.include)?;
+    let top = Mutex::new(TopMatches::new(limit));
+    let deadline = options.time_budget();
+    let out_of_time = AtomicBool::new(false);
+
+    walker(root, options)?.build_parallel().run(|| {
+        let matcher = QueryMatcher::new(query, &options.matching);
+        let include = include.as_ref();
+        let top = &top;
+        let out_of_time = &out_of_time;
+        Box::new(move |entry| {
+            if out_of_time.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            if deadline.is_some_and(|deadline| deadline.passed()) {
+                out_of_time.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                return WalkState::Continue;
+            }
+            if include.is_some_and(|include| !matches_globs(include, root, &entry)) {
+                return WalkState::Continue;
+            }
+            let Some(name) = entry.file_name().to_str() else {
+                return WalkState::Continue;
+            };
+            if let Some((score, indices)) = matcher.match_name(name) {
+                let path = entry.path().display().to_string();
+                let rank = signals.quick_rank(&path, score, &options.ranking);
+                let mut top = top.lock();
+                if !top.admits(&rank) {
+                    return WalkState::Continue;
+                }
+                let relative_path = entry
+                    .path()
+                    .strip_prefix(root)
+                    .map(|relative| relative.display().to_string())
+                    .unwrap_or_default();
+                top.push(SearchResult {
+                    path,
+                    name: name.to_string(),
+                    relative_path,
+                    score,
+                    indices,
+                    rank,
+                    matched_text: None,
+                });
+            }
+            WalkState::Continue
+        })
+    });
+
+    if out_of_time.into_inner() {
+        tracing::debug!(
+            "search of {} for {query:?} stopped at its time budget",
+            root.display()
+        );
+    }
+    Ok(top.into_inner().into_vec())
+}
+
+/// Adds project text matches to the folder-name matches in `results`, then ranks them with
+/// `signals`, explained, and keeps the best `limit`.
+#[cfg(feature = "fs")]
+pub(crate) fn finish(
+    root: &Path,
+    query: &str,
+    limit: usize,
+    options: &SearchOptions,
+    signals: &StoreSignals,
+    mut results: Vec<SearchResult>,
+) -> anyhow::Result<Vec<SearchResult>> {
+    if options.project_text && options.include.is_empty() {
+        add_text_matches(root, query, options, &mut results)?;
+    }
+    for result in results.iter_mut() {
+        result.rank = signals.rank(&result.path, result.score, &options.ranking);
+    }
+    results.sort_by(|a, b| {
+        b.rank
+            .pinned
+            .cmp(&a.rank.pinned)
+            .then(b.rank.total.total_cmp(&a.rank.total))
+            .then(a.name.cmp(&b.name))
+    });
+    results.truncate(limit.max(1));
+    Ok(results)
+}
+
+/// Adds registered projects below `root` whose folder name did not match `query` but whose
+/// indexed README title, package name, or description does, so "payments service" finds
+/// `svc-pmt-042`; likewise directories whose note matches. Depth, hidden, and exclude options
+/// apply as they do to the walk.
+#[cfg(feature = "fs")]
+fn add_text_matches(
+    root: &Path,
+    query: &str,
+    options: &SearchOptions,
+    results: &mut Vec<SearchResult>,
+) -> anyhow::Result<()> {
+    let exclude = build_globs(&options.exclude)?;
+    let matcher = QueryMatcher::new(query, &options.matching);
+    let projects = crate::projects::list_projects()
+        .into_iter()
+        .filter_map(|project| {
+            let text = project.matching_text(query)?.to_string();
+            Some((project.path, project.name, text))
+        });
+    let notes = crate::notes::search_notes(query).into_iter().map(|note| {
+        let name = Path::new(&note.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| note.path.clone());
+        (note.path, name, note.markdown)
+    });
+    for (candidate, name, text) in projects.chain(notes) {
+        let path = Path::new(&candidate);
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let names: Vec<_> = relative.components().map(|part| part.as_os_str()).collect();
+        if names.is_empty()
+            || options.max_depth.is_some_and(|depth| names.len() > depth)
+            || (!options.hidden
+                && names
+                    .iter()
+                    .any(|name| name.to_string_lossy().starts_with('.')))
+            || exclude.as_ref().is_some_and(|globs| {
+                globs.is_match(relative) || names.iter().any(|name| globs.is_match(name))
+            })
+            || results.iter().any(|result| result.path == candidate)
+            || !path.is_dir()
+        {
+            continue;
+        }
+        results.push(SearchResult {
+            score: matcher.match_name(&text).map_or(0, |(score, _)| score),
+            relative_path: relative.display().to_string(),
+            matched_text: Some(text),
+            name,
+            path: candidate,
+            indices: Vec::new(),
+            rank: RankBreakdown::default(),
+        });
+    }
+    Ok(())
+}
+
+/// Without the `fs` feature there is no directory walker; searches (including saved searches
+/// and search links) fail instead of returning nothing.
+#[cfg(not(feature = "fs"))]
+pub(crate) fn search_directories(
+    _root: &Path,
+    _query: &str,
+    _limit: usize,
+    _options: &SearchOptions,
+) -> anyhow::Result<Vec<SearchResult>> {
+    anyhow::bail!(crate::CoreError::invalid_input(
+        "directory search is not available in this build"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_options_match_legacy_walk() {
+        let options = SearchOptions::from_config(&crate::config::Config::default());
+        assert_eq!(options.max_depth, Some(5));
+        assert!(options.respect_gitignore);
+        assert!(!options.hidden);
+    }
+
+    #[test]
+    fn invalid_globs_are_rejected() {
+        assert!(build_globs(&[String::from("a[")]).is_err());
+        assert!(build_globs(&[]).unwrap().is_none());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn deep_strong_match_displaces_early_weak_ones() {
+        let root = std::env::temp_dir().join(format!("x-{}", uuid::Uuid::new_v4()));
+        for i in 0..40 {
+            std::fs::create_dir_all(root.join(format!("t-e-r-m-{i}"))).unwrap();
+        }
+        let deep = root.join("a/b/c/d/term");
+        std::fs::create_dir_all(&deep).unwrap();
+        let options = SearchOptions {
+            max_depth: None,
+            time_budget_ms: None,
+            ..SearchOptions::default()
+        };
+        let signals = StoreSignals::with_favorites(&[]);
+        let matches = walk_matches(&root, "term", 1, &options, &signals).unwrap();
+        assert_eq!(matches.len(), POOL_PER_RESULT);
+        let best = matches.iter().max_by_key(|result| result.score).unwrap();
+        assert_eq!(best.path, deep.display().to_string());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn weak_favorites_are_ranked_before_the_cut() {
+        let root = std::env::temp_dir().join(format!("x-{}", uuid::Uuid::new_v4()));
+        for i in 0..40 {
+            std::fs::create_dir_all(root.join(format!("term-{i}"))).unwrap();
+        }
+        let favorite = root.join("t-e-r-m");
+        std::fs::create_dir_all(&favorite).unwrap();
+        let favorite = favorite.display().to_string();
+        let options = SearchOptions {
+            time_budget_ms: None,
+            project_text: false,
+            ..SearchOptions::default()
+        };
+        let signals = StoreSignals::with_favorites(&[&favorite]);
+        let matches = walk_matches(&root, "term", 1, &options, &signals).unwrap();
+        assert!(matches.iter().any(|result| result.path == favorite));
+        let results = finish(&root, "term", 1, &options, &signals, matches).unwrap();
+        assert_eq!(results[0].path, favorite);
+        assert!(results[0]
+            .rank
+            .explanation
+            .iter()
+            .any(|line| line.starts_with("favorite")));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
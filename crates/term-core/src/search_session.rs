@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::candidates::Candidates;
+use crate::matching::QueryMatcher;
+use crate::ranking::StoreSignals;
+use crate::search::{finish, TopMatches};
+use crate::{CoreError, SearchOptions, SearchResult};
+
+/// Sessions kept open at once; starting another closes the one used least recently.
+const MAX_SESSIONS: usize = 8;
+
+/// An open typeahead session, as returned by [`crate::api::start_search_session`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSession {
+    pub id: Uuid,
+    pub root: String,
+    /// Directories found by the walk, which every query of the session is matched against.
+    pub candidates: usize,
+    /// The walk stopped at the candidate limit or the time budget, so deeper directories may
+    /// be missing.
+    pub truncated: bool,
+}
+
+struct Session {
+    root: PathBuf,
+    options: SearchOptions,
+    candidates: Arc<Candidates>,
+    /// The last query and the candidates it matched. A query that extends it can only match
+    /// a subset of those, so it is matched against them alone.
+    query: String,
+    matched: Vec<usize>,
+    used: Instant,
+}
+
+static SESSIONS: Lazy<Mutex<HashMap<Uuid, Arc<Mutex<Session>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Keeps the directories below `root` for [`refine_search_session`], walking unless a
+/// recent search already did.
+pub(crate) fn start_search_session(
+    root: PathBuf,
+    options: SearchOptions,
+) -> anyhow::Result<SearchSession> {
+    let candidates = crate::candidates::candidates(&root, &options)?;
+    let id = Uuid::new_v4();
+    let session = SearchSession {
+        id,
+        root: root.display().to_string(),
+        candidates: candidates.entries.len(),
+        truncated: candidates.truncated,
+    };
+    let mut sessions = SESSIONS.lock();
+    if sessions.len() >= MAX_SESSIONS {
+        let oldest = sessions
+            .iter()
+            .min_by_key(|(_, session)| session.lock().used)
+            .map(|(id, _)| *id);
+        if let Some(oldest) = oldest {
+            sessions.remove(&oldest);
+        }
+    }
+    sessions.insert(
+        id,
+        Arc::new(Mutex::new(Session {
+            root,
+            options,
+            matched: (0..candidates.entries.len()).collect(),
+            candidates,
+            query: String::new(),
+            used: Instant::now(),
+        })),
+    );
+    Ok(session)
+}
+
+/// Results of `query` among the session's directories, ranked as [`crate::api::search`]
+/// ranks them. When `query` extends the previous query only that query's matches are tried.
+pub(crate) fn refine_search_session(
+    id: Uuid,
+    query: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let Some(session) = SESSIONS.lock().get(&id).cloned() else {
+        anyhow::bail!(
+            CoreError::not_found(format!("no search session {id}")).with_hint(format!(
+                "sessions close with end_search_session or once {MAX_SESSIONS} newer ones are open"
+            ))
+        );
+    };
+    let mut session = session.lock();
+    if query.trim().is_empty() && session.options.include.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("query required"));
+    }
+    let started = Instant::now();
+    session.used = started;
+    let pool: Vec<usize> = match query.starts_with(session.query.as_str()) {
+        true => std::mem::take(&mut session.matched),
+        false => (0..session.candidates.entries.len()).collect(),
+    };
+    let matcher = QueryMatcher::new(query, &session.options.matching);
+    let mut matched = Vec::new();
+    let signals = StoreSignals::snapshot();
+    let mut top = TopMatches::new(limit);
+    for index in pool {
+        let candidate = &session.candidates.entries[index];
+        if let Some((score, indices)) = matcher.match_name(&candidate.name) {
+            matched.push(index);
+            let rank = signals.quick_rank(&candidate.path, score, &session.options.ranking);
+            if top.admits(&rank) {
+                top.push(SearchResult {
+                    rank,
+                    ..candidate.to_result(score, indices)
+                });
+            }
+        }
+    }
+    session.query = query.to_string();
+    session.matched = matched;
+    let results = finish(
+        &session.root,
+        query,
+        limit,
+        &session.options,
+        &signals,
+        top.into_vec(),
+    )?;
+    crate::stats::record(crate::stats::Usage::Search(started.elapsed()));
+    Ok(results)
+}
+
+/// Closes session `id`, recording its last query; returns whether it was open.
+pub(crate) fn end_search_session(id: Uuid) -> bool {
+    let Some(session) = SESSIONS.lock().remove(&id) else {
+        return false;
+    };
+    let session = session.lock();
+    if session.options.record_history && !session.query.trim().is_empty() {
+        crate::history::record_search(&session.root, &session.query);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refinement_narrows_without_rewalking() {
+        let root = std::env::temp_dir().join(format!("x-{}", Uuid::new_v4()));
+        for dir in ["terminal", "termite", "tests", "docs"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let options = SearchOptions {
+            project_text: false,
+            record_history: false,
+            ..SearchOptions::default()
+        };
+        let session = start_search_session(root.clone(), options).unwrap();
+        // The root, named so no query below matches it, and its four folders.
+        assert_eq!(session.candidates, 5);
+        let names = |query: &str| -> Vec<String> {
+            let mut names: Vec<String> = refine_search_session(session.id, query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.name)
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names("te"), ["terminal", "termite", "tests"]);
+        // Gone from disk, but the session still has it: no second walk happened.
+        std::fs::remove_dir(root.join("termite")).unwrap();
+        assert_eq!(names("ter"), ["terminal", "termite"]);
+        assert_eq!(names("termi"), ["terminal", "termite"]);
+        assert_eq!(names("do"), ["docs"]);
+        assert!(refine_search_session(session.id, " ", 10).is_err());
+
+        assert!(end_search_session(session.id));
+        assert!(refine_search_session(session.id, "te", 10).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+
+use crate::{CoreError, LaunchRequest};
+
+/// Keychain service the named secrets are filed under, apart from the state key.
+#[cfg(feature = "keychain")]
+const SERVICE: &str = "Terminaut secrets";
+
+const OPEN: &str = "{secret:";
+
+/// Resolved secrets reach launched processes in variables named this plus a number.
+const VAR_PREFIX: &str = "TERMINAUT_SECRET_";
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "invalid secret name {name:?}; use letters, digits, '-', '_', and '.'"
+        )));
+    }
+    Ok(())
+}
+
+/// A piece of text with secret references: literal text, or the name of a secret.
+enum Part<'a> {
+    Text(&'a str),
+    Secret(&'a str),
+}
+
+fn parts(text: &str) -> anyhow::Result<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        parts.push(Part::Text(&rest[..start]));
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find('}') else {
+            anyhow::bail!(CoreError::invalid_input(format!(
+                "unterminated secret reference in {text:?}"
+            )));
+        };
+        let name = &after[..end];
+        check_name(name)?;
+        parts.push(Part::Secret(name));
+        rest = &after[end + 1..];
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+/// The shell that expands a command's variables.
+#[derive(Clone, Copy)]
+enum Shell {
+    Posix,
+    Cmd,
+}
+
+impl Shell {
+    /// The shell that runs the launched command itself: `sh`, except on Windows outside WSL.
+    fn for_command(request: &LaunchRequest) -> Self {
+        match cfg!(windows) && request.wsl_distro.is_none() {
+            true => Self::Cmd,
+            false => Self::Posix,
+        }
+    }
+
+    /// The shell pre and post commands run in; see [`crate::hooks::shell`].
+    fn for_hooks() -> Self {
+        match cfg!(windows) {
+            true => Self::Cmd,
+            false => Self::Posix,
+        }
+    }
+
+    /// `var` expanded as one word.
+    fn reference(self, var: &str) -> String {
+        match self {
+            Self::Posix => format!("\"${{{var}}}\""),
+            Self::Cmd => format!("%{var}%"),
+        }
+    }
+
+    /// A plain argument holding references, as one double-quoted word whose variables the
+    /// shell expands and whose text it keeps as is.
+    fn word(self, parts: &[Part<'_>], vars: &Vars<'_>) -> String {
+        let mut word = String::from("\"");
+        for part in parts {
+            match (part, self) {
+                (Part::Text(text), Self::Posix) => {
+                    for c in text.chars() {
+                        if matches!(c, '"' | '\\' | '$' | '`') {
+                            word.push('\\');
+                        }
+                        word.push(c);
+                    }
+                }
+                (Part::Text(text), Self::Cmd) => word.push_str(&text.replace('"', "\\\"")),
+                (Part::Secret(name), Self::Posix) => {
+                    word.push_str(&format!("${{{}}}", vars.names[*name]));
+                }
+                (Part::Secret(name), Self::Cmd) => {
+                    word.push_str(&format!("%{}%", vars.names[*name]));
+                }
+            }
+        }
+        word.push('"');
+        word
+    }
+}
+
+/// The variable each secret is passed in, and the values, looked up once per name.
+struct Vars<'a> {
+    names: BTreeMap<String, String>,
+    env: BTreeMap<String, String>,
+    lookup: &'a mut dyn FnMut(&str) -> anyhow::Result<String>,
+}
+
+impl Vars<'_> {
+    fn var(&mut self, name: &str) -> anyhow::Result<String> {
+        if let Some(var) = self.names.get(name) {
+            return Ok(var.clone());
+        }
+        let value = (self.lookup)(name)?;
+        let var = format!("{VAR_PREFIX}{}", self.names.len());
+        self.names.insert(name.to_string(), var.clone());
+        self.env.insert(var.clone(), value);
+        Ok(var)
+    }
+
+    /// `text` with each reference replaced by its variable.
+    fn substitute(&mut self, text: &str, shell: Shell) -> anyhow::Result<String> {
+        let mut substituted = String::with_capacity(text.len());
+        for part in parts(text)? {
+            match part {
+                Part::Text(text) => substituted.push_str(text),
+                Part::Secret(name) => substituted.push_str(&shell.reference(&self.var(name)?)),
+            }
+        }
+        Ok(substituted)
+    }
+}
+
+/// A launch whose secret references point at environment variables, and those variables'
+/// values. The values reach the launched processes through their environment, never
+/// through a command line that is typed into a terminal or shows up in `ps`.
+pub(crate) struct Resolved {
+    pub(crate) request: LaunchRequest,
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+impl Resolved {
+    /// `request` as is, for launches that don't get secrets.
+    pub(crate) fn unresolved(request: &LaunchRequest) -> Self {
+        Self {
+            request: request.clone(),
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+fn resolve_with(
+    request: &LaunchRequest,
+    lookup: &mut dyn FnMut(&str) -> anyhow::Result<String>,
+) -> anyhow::Result<Resolved> {
+    let mut vars = Vars {
+        names: BTreeMap::new(),
+        env: BTreeMap::new(),
+        lookup,
+    };
+    let shell = Shell::for_command(request);
+    let mut resolved = request.clone();
+    if let Some(command) = &request.command {
+        resolved.command = Some(vars.substitute(command, shell)?);
+    }
+    for command in [&mut resolved.pre_command, &mut resolved.post_launch_command]
+        .into_iter()
+        .flatten()
+    {
+        *command = vars.substitute(command, Shell::for_hooks())?;
+    }
+    if request.use_shell {
+        for arg in resolved.args.iter_mut() {
+            *arg = vars.substitute(arg, shell)?;
+        }
+    } else if request.args.iter().any(|arg| arg.contains(OPEN)) {
+        // Plain arguments are passed without a shell, which is what expands the variables,
+        // so they become a command line with each argument quoted.
+        let mut words = Vec::with_capacity(request.args.len());
+        for arg in &request.args {
+            let parts = parts(arg)?;
+            if parts.len() == 1 {
+                words.push(crate::command_line::quote(arg));
+                continue;
+            }
+            for part in &parts {
+                if let Part::Secret(name) = part {
+                    vars.var(name)?;
+                }
+            }
+            words.push(shell.word(&parts, &vars));
+        }
+        resolved.command = Some(words.join(" "));
+        resolved.args = Vec::new();
+    }
+    Ok(Resolved {
+        request: resolved,
+        env: vars.env,
+    })
+}
+
+/// The request with the secret references in its commands and arguments pointing at
+/// variables that carry the values from the keychain. Only for profiles saved in the store:
+/// env values, which come from project files, are left as written.
+pub(crate) fn resolve_request(request: &LaunchRequest) -> anyhow::Result<Resolved> {
+    resolve_with(request, &mut get_secret)
+}
+
+/// Whether anything in `request` looks like a secret reference.
+pub(crate) fn has_references(request: &LaunchRequest) -> bool {
+    [
+        &request.command,
+        &request.pre_command,
+        &request.post_launch_command,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(&request.args)
+    .chain(request.env.values())
+    .any(|text| text.contains(OPEN))
+}
+
+/// Writes `env` as `export` lines to a new file only the user can read, for terminals whose
+/// environment can't be set from here; the launched line sources it and deletes it.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) fn env_file(env: &BTreeMap<String, String>) -> anyhow::Result<std::path::PathBuf> {
+    use std::io::Write;
+
+    use anyhow::Context;
+
+    let path = std::env::temp_dir().join(format!("terminaut-{}.env", uuid::Uuid::new_v4()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    for (key, value) in env {
+        writeln!(file, "export {key}='{}'", value.replace('\'', "'\\''"))?;
+    }
+    Ok(path)
+}
+
+#[cfg(feature = "keychain")]
+fn entry(name: &str) -> anyhow::Result<keyring::Entry> {
+    use anyhow::Context;
+
+    check_name(name)?;
+    keyring::Entry::new(SERVICE, name).context("failed to open the OS keychain")
+}
+
+#[cfg(feature = "keychain")]
+pub(crate) fn get_secret(name: &str) -> anyhow::Result<String> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(value),
+        Err(keyring::Error::NoEntry) => {
+            Err(CoreError::not_found(format!("no secret named {name:?}"))
+                .with_hint(format!("store it with `term-core-cli secrets set {name}`"))
+                .into())
+        }
+        Err(err) => Err(anyhow::Error::new(err).context("failed to read the OS keychain")),
+    }
+}
+
+#[cfg(feature = "keychain")]
+pub(crate) fn set_secret(name: &str, value: &str) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    entry(name)?
+        .set_password(value)
+        .context("failed to write the OS keychain")
+}
+
+#[cfg(feature = "keychain")]
+pub(crate) fn delete_secret(name: &str) -> anyhow::Result<()> {
+    match entry(name)?.delete_credential() {
+        Ok(()) => Ok(()),
+        Err(keyring::Error::NoEntry) => {
+            Err(CoreError::not_found(format!("no secret named {name:?}")).into())
+        }
+        Err(err) => Err(anyhow::Error::new(err).context("failed to write the OS keychain")),
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+pub(crate) fn get_secret(_name: &str) -> anyhow::Result<String> {
+    no_keychain()
+}
+
+#[cfg(not(feature = "keychain"))]
+pub(crate) fn set_secret(_name: &str, _value: &str) -> anyhow::Result<()> {
+    no_keychain()
+}
+
+#[cfg(not(feature = "keychain"))]
+pub(crate) fn delete_secret(_name: &str) -> anyhow::Result<()> {
+    no_keychain()
+}
+
+#[cfg(not(feature = "keychain"))]
+fn no_keychain<T>() -> anyhow::Result<T> {
+    anyhow::bail!(CoreError::invalid_input(
+        "secrets need the OS keychain, which this build does not include"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(command: &str, args: &[&str]) -> LaunchRequest {
+        LaunchRequest {
+            path: String::from("/tmp"),
+            terminal: None,
+            command: Some(command.to_string()),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            use_shell: false,
+            pre_command: Some(String::from("login {secret:db-pass}")),
+            post_launch_command: None,
+            background: false,
+            profile: None,
+            profile_id: None,
+            windows: 1,
+            env: [(
+                String::from("URL"),
+                String::from("pg://{secret:db-pass}@db"),
+            )]
+            .into(),
+            wsl_distro: None,
+        }
+    }
+
+    fn lookup(name: &str) -> anyhow::Result<String> {
+        match name {
+            "db-pass" => Ok(String::from("it's")),
+            other => anyhow::bail!("missing {other}"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn references_become_variables() {
+        let resolved = resolve_with(
+            &request("PGPASSWORD={secret:db-pass} psql", &[]),
+            &mut lookup,
+        )
+        .unwrap();
+        assert_eq!(
+            resolved.request.command.as_deref(),
+            Some(r#"PGPASSWORD="${TERMINAUT_SECRET_0}" psql"#)
+        );
+        assert_eq!(
+            resolved.request.pre_command.as_deref(),
+            Some(r#"login "${TERMINAUT_SECRET_0}""#)
+        );
+        assert_eq!(resolved.env["TERMINAUT_SECRET_0"], "it's");
+        // Env values come from project files and are not resolved.
+        assert_eq!(resolved.request.env["URL"], "pg://{secret:db-pass}@db");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn plain_arguments_with_references_become_a_quoted_line() {
+        let resolved = resolve_with(
+            &request("", &["psql", "--password=a $b {secret:db-pass}", "x y"]),
+            &mut lookup,
+        )
+        .unwrap();
+        assert!(resolved.request.args.is_empty());
+        assert_eq!(
+            resolved.request.command.as_deref(),
+            Some(r#"psql "--password=a \$b ${TERMINAUT_SECRET_0}" 'x y'"#)
+        );
+        assert_eq!(resolved.env.len(), 1);
+    }
+
+    #[test]
+    fn bad_references_are_rejected() {
+        for command in ["{secret:other}", "{secret:db-pass", "{secret:a b}"] {
+            assert!(resolve_with(&request(command, &[]), &mut lookup).is_err());
+        }
+        assert!(has_references(&request("no refs", &[])));
+        let plain = LaunchRequest {
+            pre_command: None,
+            env: BTreeMap::new(),
+            ..request("no refs", &[])
+        };
+        assert!(!has_references(&plain));
+    }
+}
@@ -0,0 +1,429 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::launcher::{launch, LaunchRequest, Secrets};
+use crate::workspace::{find_project_root, load_workspace, WorkspaceProfile};
+use crate::{normalize_path, CoreError, LaunchProfile, PersistedState, STORE};
+
+/// One location in a session, optionally opened through a saved launch profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<Uuid>,
+}
+
+/// A named set of locations reopened together, e.g. "Monday morning".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub entries: Vec<SessionEntry>,
+    pub saved_utc: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub launched: Vec<LaunchRequest>,
+    pub failed: Vec<LaunchFailure>,
+}
+
+pub(crate) fn list_sessions() -> Vec<Session> {
+    let mut sessions = STORE.inner.lock().sessions.clone();
+    sessions.sort_by_key(|session| session.name.to_lowercase());
+    sessions
+}
+
+/// Checks a session and normalizes its paths before it is saved.
+fn build(name: &str, entries: Vec<SessionEntry>) -> anyhow::Result<Session> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("session name required"));
+    }
+    if entries.is_empty() {
+        anyhow::bail!(CoreError::invalid_input("session needs at least one entry"));
+    }
+    let mut normalized = Vec::with_capacity(entries.len());
+    for entry in entries {
+        normalized.push(SessionEntry {
+            path: normalize_path(&entry.path)?.display().to_string(),
+            profile_id: entry.profile_id,
+        });
+    }
+    Ok(Session {
+        name: name.to_string(),
+        entries: normalized,
+        saved_utc: Utc::now().timestamp(),
+    })
+}
+
+/// Adds `session`, replacing any saved under the same name in another case. Every profile
+/// it names must exist.
+fn upsert(state: &mut PersistedState, session: Session) -> anyhow::Result<()> {
+    if let Some(missing) = session
+        .entries
+        .iter()
+        .filter_map(|entry| entry.profile_id)
+        .find(|id| !state.profiles.iter().any(|profile| profile.id == *id))
+    {
+        anyhow::bail!(CoreError::not_found(format!(
+            "profile not found: {missing}"
+        )));
+    }
+    state
+        .sessions
+        .retain(|existing| !existing.name.eq_ignore_ascii_case(&session.name));
+    state.sessions.push(session);
+    Ok(())
+}
+
+pub(crate) fn save_session(name: &str, entries: Vec<SessionEntry>) -> anyhow::Result<Session> {
+    let session = build(name, entries)?;
+    let mut store = STORE.inner.lock();
+    upsert(&mut store, session.clone())?;
+    STORE.persist(&mut store)?;
+    Ok(session)
+}
+
+pub(crate) fn delete_session(name: &str) -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    let before = store.sessions.len();
+    store
+        .sessions
+        .retain(|session| !session.name.eq_ignore_ascii_case(name));
+    if before == store.sessions.len() {
+        anyhow::bail!(CoreError::not_found("session not found"));
+    }
+    STORE.persist(&mut store)?;
+    Ok(())
+}
+
+/// Which profile a launch should use.
+pub(crate) enum ProfileChoice<'a> {
+    /// The project's default profile from `.terminaut.toml`, if it declares one.
+    ProjectDefault,
+    Global(&'a LaunchProfile),
+    /// A profile name (project profiles first) or a global profile id.
+    Named(&'a str),
+}
+
+/// For a profile with an `ssh_host` or `kube_pod`, the client command that runs its command
+/// on the host or in the pod.
+fn remote_args(profile: &LaunchProfile) -> Option<Vec<String>> {
+    let command = (!profile.args.is_empty())
+        .then(|| crate::command_line::join(&profile.args, profile.use_shell))
+        .or_else(|| profile.command.clone());
+    if let Some(pod) = profile.kube_pod.as_deref() {
+        let target = crate::kube::PodTarget {
+            pod,
+            container: profile.kube_container.as_deref(),
+            namespace: profile.kube_namespace.as_deref(),
+            context: profile.kube_context.as_deref(),
+        };
+        return Some(crate::kube::exec_args(&target, command.as_deref()));
+    }
+    Some(crate::ssh::command(
+        profile.transport,
+        profile.ssh_host.as_deref()?,
+        profile.ssh_dir.as_deref(),
+        command.as_deref(),
+    ))
+}
+
+/// Builds the launch for `path` with the chosen profile. The enclosing project's env applies
+/// whichever profile is used.
+pub(crate) fn launch_for(path: String, choice: ProfileChoice<'_>) -> anyhow::Result<LaunchRequest> {
+    let (root, _) = find_project_root(std::path::Path::new(&path));
+    let project = load_workspace(&root)?.unwrap_or_default();
+    let global = |profile: &LaunchProfile| LaunchRequest {
+        path: path.clone(),
+        terminal: profile.terminal.clone(),
+        command: profile.command.clone(),
+        args: remote_args(profile).unwrap_or_else(|| profile.args.clone()),
+        use_shell: profile.use_shell && !profile.is_remote(),
+        pre_command: profile.pre_command.clone(),
+        post_launch_command: profile.post_launch_command.clone(),
+        background: false,
+        profile: Some(profile.name.clone()),
+        profile_id: Some(profile.id.to_string()),
+        windows: profile.windows,
+        env: project.env.clone(),
+        wsl_distro: profile.wsl_distro.clone(),
+    };
+    let local = |profile: &WorkspaceProfile| LaunchRequest {
+        path: profile.working_dir.clone().unwrap_or_else(|| path.clone()),
+        terminal: profile.terminal.clone(),
+        command: profile.command.clone(),
+        pre_command: None,
+        post_launch_command: None,
+        background: false,
+        args: Vec::new(),
+        use_shell: false,
+        profile: Some(profile.name.clone()),
+        profile_id: None,
+        windows: profile.windows,
+        env: project.env.clone(),
+        wsl_distro: None,
+    };
+    let request = match choice {
+        ProfileChoice::Global(profile) => global(profile),
+        ProfileChoice::Named(name) => {
+            if let Some(profile) = project
+                .profiles
+                .iter()
+                .find(|profile| profile.name.eq_ignore_ascii_case(name))
+            {
+                local(profile)
+            } else {
+                let profiles = STORE.inner.lock().profiles.clone();
+                let profile = profiles
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(name) || p.id.to_string() == name)
+                    .ok_or_else(|| CoreError::not_found(format!("profile not found: {name}")))?;
+                global(profile)
+            }
+        }
+        ProfileChoice::ProjectDefault => match project.default_profile() {
+            Some(profile) => local(profile),
+            None => LaunchRequest {
+                path: path.clone(),
+                terminal: None,
+                command: None,
+                pre_command: None,
+                post_launch_command: None,
+                background: false,
+                args: Vec::new(),
+                use_shell: false,
+                profile: None,
+                profile_id: None,
+                windows: 1,
+                env: project.env.clone(),
+                wsl_distro: None,
+            },
+        },
+    };
+    Ok(request)
+}
+
+/// Each entry's path and its launch with `profiles`. An entry whose project file can't be
+/// read fails on its own rather than failing the session.
+fn resolve(
+    session: Session,
+    profiles: &[LaunchProfile],
+) -> Vec<(String, anyhow::Result<LaunchRequest>)> {
+    session
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let choice = entry
+                .profile_id
+                .and_then(|id| profiles.iter().find(|profile| profile.id == id))
+                .map_or(ProfileChoice::ProjectDefault, ProfileChoice::Global);
+            (entry.path.clone(), launch_for(entry.path, choice))
+        })
+        .collect()
+}
+
+/// The launches of the session called `name`, resolved against the current profiles.
+fn entry_launches(name: &str) -> anyhow::Result<Vec<(String, anyhow::Result<LaunchRequest>)>> {
+    let (session, profiles) = {
+        let store = STORE.inner.lock();
+        let session = store
+            .sessions
+            .iter()
+            .find(|session| session.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| CoreError::not_found("session not found"))?;
+        (session, store.profiles.clone())
+    };
+    Ok(resolve(session, &profiles))
+}
+
+/// Resolves a session's entries against the current profiles without launching anything.
+/// Entries without a profile prefer the default profile of their project's `.terminaut.toml`;
+/// entries whose profile has since been deleted open with the default terminal. Entries
+/// whose project file is broken are logged and left out.
+pub(crate) fn session_launches(name: &str) -> anyhow::Result<Vec<LaunchRequest>> {
+    Ok(entry_launches(name)?
+        .into_iter()
+        .filter_map(|(path, request)| {
+            request
+                .map_err(|err| tracing::warn!("session {name}: skipping {path}: {err:#}"))
+                .ok()
+        })
+        .collect())
+}
+
+/// Launches every entry, carrying on past individual failures so one missing directory or
+/// broken project file doesn't stop the rest of the session from opening.
+pub(crate) fn restore_session(name: &str) -> anyhow::Result<RestoreReport> {
+    Ok(restore(name, entry_launches(name)?, launch))
+}
+
+/// Opens each resolved entry with `launch`, recording what opened and what didn't.
+fn restore(
+    name: &str,
+    entries: Vec<(String, anyhow::Result<LaunchRequest>)>,
+    mut launch: impl FnMut(&LaunchRequest, Secrets) -> anyhow::Result<()>,
+) -> RestoreReport {
+    let mut report = RestoreReport::default();
+    for (path, request) in entries {
+        let request = match request {
+            Ok(request) => request,
+            Err(err) => {
+                tracing::warn!("session {name}: skipping {path}: {err:#}");
+                report.failed.push(LaunchFailure {
+                    path,
+                    error: format!("{err:#}"),
+                });
+                continue;
+            }
+        };
+        // Only entries opened through a saved profile carry its id.
+        let secrets = match request.profile_id {
+            Some(_) => Secrets::Resolve,
+            None => Secrets::Leave,
+        };
+        match launch(&request, secrets) {
+            Ok(()) => report.launched.push(request),
+            Err(err) => report.failed.push(LaunchFailure {
+                path: request.path,
+                error: format!("{err:#}"),
+            }),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn temp_root() -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("terminaut-session-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn entry(path: &std::path::Path, profile_id: Option<Uuid>) -> SessionEntry {
+        SessionEntry {
+            path: path.display().to_string(),
+            profile_id,
+        }
+    }
+
+    #[test]
+    fn sessions_need_a_name_and_entries() {
+        let root = temp_root();
+        let unnamed = build(" ", vec![entry(&root, None)]).unwrap_err();
+        assert_eq!(error_code(&unnamed), ErrorCode::InvalidInput);
+        let empty = build("work", Vec::new()).unwrap_err();
+        assert_eq!(error_code(&empty), ErrorCode::InvalidInput);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn saving_replaces_a_session_of_the_same_name() {
+        let root = temp_root();
+        let mut state = PersistedState::default();
+        upsert(&mut state, build("Work", vec![entry(&root, None)]).unwrap()).unwrap();
+        let nested = root.join("api");
+        std::fs::create_dir_all(&nested).unwrap();
+        upsert(
+            &mut state,
+            build(" work ", vec![entry(&nested, None)]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.sessions[0].name, "work");
+        assert_eq!(
+            state.sessions[0].entries[0].path,
+            normalize_path(&nested.display().to_string())
+                .unwrap()
+                .display()
+                .to_string()
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn sessions_cannot_name_missing_profiles() {
+        let root = temp_root();
+        let mut state = PersistedState::default();
+        let session = build("work", vec![entry(&root, Some(Uuid::new_v4()))]).unwrap();
+        let err = upsert(&mut state, session).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+        assert!(state.sessions.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn entries_resolve_against_the_current_profiles() {
+        let root = temp_root();
+        let profile = LaunchProfile {
+            name: String::from("Logs"),
+            command: Some(String::from("tail -f log")),
+            ..LaunchProfile::default()
+        };
+        let session = build(
+            "work",
+            vec![
+                entry(&root, Some(profile.id)),
+                // A profile deleted since the session was saved opens a plain shell.
+                entry(&root, Some(Uuid::new_v4())),
+            ],
+        )
+        .unwrap();
+        let launches = resolve(session, std::slice::from_ref(&profile));
+        let with_profile = launches[0].1.as_ref().unwrap();
+        assert_eq!(with_profile.command.as_deref(), Some("tail -f log"));
+        assert_eq!(with_profile.profile_id, Some(profile.id.to_string()));
+        let deleted = launches[1].1.as_ref().unwrap();
+        assert_eq!(deleted.command, None);
+        assert_eq!(deleted.profile, None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn restore_carries_on_past_failed_entries() {
+        let good = temp_root();
+        let broken = temp_root();
+        std::fs::write(broken.join(".terminaut.toml"), "profiles = 3").unwrap();
+        let missing = good.join("gone");
+        let session = Session {
+            name: String::from("work"),
+            entries: [&good, &broken, &missing]
+                .into_iter()
+                .map(|path| entry(path, None))
+                .collect(),
+            saved_utc: 0,
+        };
+        let mut secrets = Vec::new();
+        let report = restore("work", resolve(session, &[]), |request, resolve| {
+            secrets.push(resolve);
+            match std::path::Path::new(&request.path).is_dir() {
+                true => Ok(()),
+                false => anyhow::bail!("no such directory"),
+            }
+        });
+        assert_eq!(report.launched.len(), 1);
+        assert_eq!(report.launched[0].path, good.display().to_string());
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            failed,
+            [broken.display().to_string(), missing.display().to_string()]
+        );
+        assert!(report.failed[0].error.contains(".terminaut.toml"));
+        // Entries without a saved profile never get secrets filled in.
+        assert_eq!(secrets, [Secrets::Leave, Secrets::Leave]);
+        std::fs::remove_dir_all(&good).unwrap();
+        std::fs::remove_dir_all(&broken).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use crate::{CoreError, STORE};
+
+type Namespaces = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Values longer than this are rejected; settings are for window sizes and choices, not data.
+const SETTING_MAX_BYTES: usize = 16 * 1024;
+
+/// Namespaces and keys are short identifiers such as `macos` and `window.frame`.
+fn check_name(kind: &str, name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 128
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "invalid setting {kind} {name:?}; use letters, digits, '-', '_', and '.'"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn setting_get(namespace: &str, key: &str) -> anyhow::Result<Option<String>> {
+    check_name("namespace", namespace)?;
+    check_name("key", key)?;
+    Ok(STORE
+        .inner
+        .lock()
+        .settings
+        .get(namespace)
+        .and_then(|settings| settings.get(key))
+        .cloned())
+}
+
+pub(crate) fn setting_set(namespace: &str, key: &str, value: &str) -> anyhow::Result<()> {
+    check_name("namespace", namespace)?;
+    check_name("key", key)?;
+    if value.len() > SETTING_MAX_BYTES {
+        anyhow::bail!(CoreError::invalid_input(format!(
+            "setting values are limited to {} KiB",
+            SETTING_MAX_BYTES / 1024
+        )));
+    }
+    let mut store = STORE.inner.lock();
+    if insert(&mut store.settings, namespace, key, value) {
+        STORE.persist(&mut store)?;
+    }
+    Ok(())
+}
+
+/// Sets `key` in `namespace`. Returns whether its value changed.
+fn insert(namespaces: &mut Namespaces, namespace: &str, key: &str, value: &str) -> bool {
+    let settings = namespaces.entry(namespace.to_string()).or_default();
+    if settings.get(key).is_some_and(|existing| existing == value) {
+        return false;
+    }
+    settings.insert(key.to_string(), value.to_string());
+    true
+}
+
+/// Removes `key`, and the namespace with its last key. Returns whether it was set.
+pub(crate) fn setting_delete(namespace: &str, key: &str) -> anyhow::Result<bool> {
+    check_name("namespace", namespace)?;
+    check_name("key", key)?;
+    let mut store = STORE.inner.lock();
+    if !remove(&mut store.settings, namespace, key) {
+        return Ok(false);
+    }
+    STORE.persist(&mut store)?;
+    Ok(true)
+}
+
+fn remove(namespaces: &mut Namespaces, namespace: &str, key: &str) -> bool {
+    let Some(settings) = namespaces.get_mut(namespace) else {
+        return false;
+    };
+    if settings.remove(key).is_none() {
+        return false;
+    }
+    if settings.is_empty() {
+        namespaces.remove(namespace);
+    }
+    true
+}
+
+/// Every key of `namespace` with its value.
+pub(crate) fn settings(namespace: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    check_name("namespace", namespace)?;
+    Ok(STORE
+        .inner
+        .lock()
+        .settings
+        .get(namespace)
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    #[test]
+    fn names_are_short_identifiers() {
+        for name in ["macos", "window.frame", "a_b-c"] {
+            assert!(check_name("key", name).is_ok());
+        }
+        for name in ["", "bad key", "a/b", &"x".repeat(129)] {
+            let err = check_name("key", name).unwrap_err();
+            assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn oversized_values_are_rejected() {
+        let err = setting_set("test", "big", &"x".repeat(SETTING_MAX_BYTES + 1)).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn setting_a_key_again_replaces_its_value() {
+        let mut namespaces = Namespaces::new();
+        assert!(insert(&mut namespaces, "macos", "theme", "light"));
+        assert!(insert(&mut namespaces, "macos", "theme", "dark"));
+        assert_eq!(namespaces["macos"]["theme"], "dark");
+    }
+
+    #[test]
+    fn unchanged_values_are_not_saved_again() {
+        let mut namespaces = Namespaces::new();
+        insert(&mut namespaces, "macos", "theme", "dark");
+        assert!(!insert(&mut namespaces, "macos", "theme", "dark"));
+    }
+
+    #[test]
+    fn namespaces_keep_their_own_keys() {
+        let mut namespaces = Namespaces::new();
+        insert(&mut namespaces, "macos", "theme", "dark");
+        insert(&mut namespaces, "linux", "theme", "light");
+        assert_eq!(namespaces["macos"]["theme"], "dark");
+        assert_eq!(namespaces["linux"]["theme"], "light");
+    }
+
+    #[test]
+    fn deleting_the_last_key_drops_the_namespace() {
+        let mut namespaces = Namespaces::new();
+        insert(&mut namespaces, "macos", "theme", "dark");
+        insert(&mut namespaces, "macos", "window.frame", "0 0 800 600");
+        assert!(remove(&mut namespaces, "macos", "theme"));
+        assert!(namespaces.contains_key("macos"));
+        assert!(remove(&mut namespaces, "macos", "window.frame"));
+        assert!(!namespaces.contains_key("macos"));
+    }
+
+    #[test]
+    fn deleting_missing_keys_reports_nothing_removed() {
+        let mut namespaces = Namespaces::new();
+        assert!(!remove(&mut namespaces, "macos", "theme"));
+        insert(&mut namespaces, "macos", "theme", "dark");
+        assert!(!remove(&mut namespaces, "macos", "frame"));
+        assert!(!remove(&mut namespaces, "linux", "theme"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// How a profile with an `ssh_host` reaches it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    #[default]
+    Ssh,
+    /// Mosh, which survives roaming and sleep; needs `mosh-server` on the host.
+    Mosh,
+    /// Eternal Terminal (`et`); needs `etserver` running on the host.
+    Et,
+}
+
+impl Transport {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ssh => "ssh",
+            Self::Mosh => "mosh",
+            Self::Et => "et",
+        }
+    }
+
+    pub(crate) fn is_ssh(&self) -> bool {
+        *self == Self::Ssh
+    }
+}
+
+impl FromStr for Transport {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "ssh" => Ok(Self::Ssh),
+            "mosh" => Ok(Self::Mosh),
+            "et" | "eternal-terminal" => Ok(Self::Et),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown transport {other:?} (ssh, mosh, et)"
+            ))),
+        }
+    }
+}
+
+/// Rejects hosts the client would read as an option, or split into several arguments.
+pub(crate) fn validate_host(host: &str) -> anyhow::Result<()> {
+    if host.is_empty() || host.starts_with('-') || host.chars().any(char::is_whitespace) {
+        anyhow::bail!(
+            CoreError::invalid_input(format!("{host:?} is not an SSH host"))
+                .with_hint("use host, user@host, or a Host alias from ~/.ssh/config")
+        );
+    }
+    Ok(())
+}
+
+/// The warning for a launch over `transport` when its client isn't installed, so the launch
+/// connects with ssh instead.
+pub(crate) fn fallback_warning(transport: Transport) -> Option<String> {
+    let missing =
+        !transport.is_ssh() && crate::launcher::find_program(transport.as_str()).is_none();
+    missing.then(|| {
+        format!(
+            "{} is not installed; connecting with ssh instead",
+            transport.as_str()
+        )
+    })
+}
+
+/// `transport` when its client is installed, else plain ssh with a warning.
+fn available(transport: Transport) -> Transport {
+    match fallback_warning(transport) {
+        Some(warning) => {
+            tracing::warn!("{warning}");
+            Transport::Ssh
+        }
+        None => transport,
+    }
+}
+
+/// Quotes for the remote POSIX shell, leaving a leading `~/` unquoted so it still expands.
+fn quote(value: &str) -> String {
+    let quoted = |value: &str| format!("'{}'", value.replace('\'', "'\\''"));
+    match value.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", quoted(rest)),
+        None if value == "~" => String::from("~"),
+        None => quoted(value),
+    }
+}
+
+/// The client command that opens a shell on `host` in `dir`, running `command` there first.
+/// The session ends with a login shell, as a local launch does.
+fn client_args(
+    transport: Transport,
+    host: &str,
+    dir: Option<&str>,
+    command: Option<&str>,
+) -> Vec<String> {
+    let mut steps: Vec<String> = dir
+        .map(|dir| format!("cd {}", quote(dir)))
+        .into_iter()
+        .collect();
+    steps.extend(command.map(str::to_string));
+    let owned = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+    match transport {
+        // `et -c` types the line into the session's own shell, which then stays open.
+        Transport::Et if steps.is_empty() => owned(&["et", host]),
+        Transport::Et => owned(&["et", "-c", &steps.join(" && "), host]),
+        _ if steps.is_empty() => owned(&[transport.as_str(), host]),
+        _ => {
+            let line = format!("{}; exec \"$SHELL\" -l", steps.join(" && "));
+            match transport {
+                Transport::Mosh => owned(&["mosh", host, "--", "sh", "-c", &line]),
+                _ => owned(&["ssh", "-t", host, &line]),
+            }
+        }
+    }
+}
+
+/// [`client_args`] with the transport's client checked for first.
+pub(crate) fn command(
+    transport: Transport,
+    host: &str,
+    dir: Option<&str>,
+    command: Option<&str>,
+) -> Vec<String> {
+    client_args(available(transport), host, dir, command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transports_open_the_remote_dir() {
+        assert_eq!(
+            client_args(Transport::Ssh, "dev@box", Some("~/src/it's"), Some("make")),
+            [
+                "ssh",
+                "-t",
+                "dev@box",
+                "cd ~/'src/it'\\''s' && make; exec \"$SHELL\" -l"
+            ]
+        );
+        assert_eq!(
+            client_args(Transport::Mosh, "box", Some("/srv/app"), None),
+            [
+                "mosh",
+                "box",
+                "--",
+                "sh",
+                "-c",
+                "cd '/srv/app'; exec \"$SHELL\" -l"
+            ]
+        );
+        assert_eq!(
+            client_args(Transport::Et, "box", Some("/srv/app"), Some("htop")),
+            ["et", "-c", "cd '/srv/app' && htop", "box"]
+        );
+        assert_eq!(client_args(Transport::Et, "box", None, None), ["et", "box"]);
+        assert!(validate_host("-oProxyCommand=x").is_err());
+        assert_eq!("ET".parse::<Transport>().unwrap(), Transport::Et);
+    }
+
+    #[test]
+    fn missing_clients_fall_back_to_ssh_with_a_warning() {
+        assert_eq!(fallback_warning(Transport::Ssh), None);
+        for transport in [Transport::Mosh, Transport::Et] {
+            let installed = crate::launcher::find_program(transport.as_str()).is_some();
+            let warning = fallback_warning(transport);
+            assert_eq!(warning.is_none(), installed);
+            assert_eq!(available(transport).is_ssh(), !installed);
+            if let Some(warning) = warning {
+                assert!(warning.starts_with(transport.as_str()));
+            }
+        }
+    }
+}
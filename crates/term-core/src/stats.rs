@@ -0,0 +1,197 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::STORE;
+
+/// Entries shown per week in the most-used lists.
+const TOP_LIMIT: usize = 10;
+
+/// One ISO week of counters as kept in the state file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WeekCounters {
+    week: String,
+    searches: u64,
+    search_ms: u64,
+    launches: u64,
+    launch_ms: u64,
+    /// Opens per directory.
+    dirs: BTreeMap<String, u64>,
+    /// Launches per profile name; launches without a profile count under `""`.
+    profiles: BTreeMap<String, u64>,
+}
+
+/// Something worth counting. Nothing is recorded unless `stats.enabled` is set.
+pub(crate) enum Usage<'a> {
+    #[cfg_attr(not(feature = "fs"), allow(dead_code))]
+    Search(Duration),
+    DirOpened(&'a str),
+    Launch {
+        profile: Option<&'a str>,
+        took: Duration,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageCount {
+    pub name: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeekStats {
+    /// ISO week such as `2026-W42`.
+    pub week: String,
+    pub searches: u64,
+    pub avg_search_ms: Option<u64>,
+    pub dirs_opened: u64,
+    pub launches: u64,
+    pub avg_launch_ms: Option<u64>,
+    /// Most-opened directories, most first.
+    pub top_dirs: Vec<UsageCount>,
+    /// Most-launched profiles, most first; `default` stands for launches without one.
+    pub top_profiles: Vec<UsageCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Whether new usage is being recorded (`stats.enabled` in the config).
+    pub enabled: bool,
+    /// Newest week first.
+    pub weeks: Vec<WeekStats>,
+}
+
+fn current_week() -> String {
+    let week = Utc::now().iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+pub(crate) fn record(usage: Usage<'_>) {
+    let (enabled, keep) = {
+        let config = crate::config::config();
+        (config.stats.enabled, config.stats.weeks)
+    };
+    if !enabled {
+        return;
+    }
+    let week = current_week();
+    let mut store = STORE.inner.lock();
+    if !store.stats.iter().any(|counters| counters.week == week) {
+        store.stats.push(WeekCounters {
+            week: week.clone(),
+            ..WeekCounters::default()
+        });
+        // ISO week labels sort chronologically, so the oldest weeks end up last.
+        store.stats.sort_by(|a, b| b.week.cmp(&a.week));
+        store.stats.truncate(keep.max(1));
+    }
+    let Some(counters) = store
+        .stats
+        .iter_mut()
+        .find(|counters| counters.week == week)
+    else {
+        return;
+    };
+    match usage {
+        Usage::Search(took) => {
+            counters.searches += 1;
+            counters.search_ms = counters.search_ms.saturating_add(millis(took));
+        }
+        Usage::DirOpened(path) => *counters.dirs.entry(path.to_string()).or_default() += 1,
+        Usage::Launch { profile, took } => {
+            counters.launches += 1;
+            counters.launch_ms = counters.launch_ms.saturating_add(millis(took));
+            *counters
+                .profiles
+                .entry(profile.unwrap_or_default().to_string())
+                .or_default() += 1;
+        }
+    }
+    if let Err(err) = STORE.persist(&mut store) {
+        tracing::warn!("failed to save usage stats: {err:#}");
+    }
+}
+
+fn top(counts: &BTreeMap<String, u64>, unnamed: &str) -> Vec<UsageCount> {
+    let mut top: Vec<UsageCount> = counts
+        .iter()
+        .map(|(name, count)| UsageCount {
+            name: if name.is_empty() { unnamed } else { name }.to_string(),
+            count: *count,
+        })
+        .collect();
+    top.sort_by_key(|entry| Reverse(entry.count));
+    top.truncate(TOP_LIMIT);
+    top
+}
+
+fn average(total_ms: u64, count: u64) -> Option<u64> {
+    (count > 0).then(|| total_ms / count)
+}
+
+impl From<&WeekCounters> for WeekStats {
+    fn from(counters: &WeekCounters) -> Self {
+        Self {
+            week: counters.week.clone(),
+            searches: counters.searches,
+            avg_search_ms: average(counters.search_ms, counters.searches),
+            dirs_opened: counters.dirs.values().sum(),
+            launches: counters.launches,
+            avg_launch_ms: average(counters.launch_ms, counters.launches),
+            top_dirs: top(&counters.dirs, ""),
+            top_profiles: top(&counters.profiles, "default"),
+        }
+    }
+}
+
+/// The most recent `weeks` weeks that have any recorded usage.
+pub(crate) fn stats(weeks: usize) -> UsageStats {
+    let enabled = crate::config::config().stats.enabled;
+    let mut counters = STORE.inner.lock().stats.clone();
+    counters.sort_by(|a, b| b.week.cmp(&a.week));
+    UsageStats {
+        enabled,
+        weeks: counters.iter().take(weeks).map(WeekStats::from).collect(),
+    }
+}
+
+pub(crate) fn clear_stats() -> anyhow::Result<()> {
+    let mut store = STORE.inner.lock();
+    store.stats.clear();
+    STORE.persist(&mut store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn week_summary_ranks_and_averages() {
+        let counters = WeekCounters {
+            week: String::from("2026-W42"),
+            searches: 4,
+            search_ms: 100,
+            dirs: [(String::from("/a"), 1), (String::from("/b"), 5)].into(),
+            profiles: [(String::new(), 2)].into(),
+            launches: 2,
+            launch_ms: 0,
+        };
+        let week = WeekStats::from(&counters);
+        assert_eq!(week.avg_search_ms, Some(25));
+        assert_eq!(week.dirs_opened, 6);
+        assert_eq!(week.top_dirs[0].name, "/b");
+        assert_eq!(week.top_profiles[0].name, "default");
+        assert_eq!(
+            WeekStats::from(&WeekCounters::default()).avg_search_ms,
+            None
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Stat results are reused for this long so repeated list calls don't hammer slow volumes.
+const STATUS_TTL: Duration = Duration::from_secs(2);
+
+/// At most this many paths are cached; past it, expired entries go first, then everything.
+const STATUS_CACHE_CAP: usize = 4096;
+
+type StatusCache = HashMap<String, (Instant, PathStatus)>;
+
+static STATUS_CACHE: Lazy<Mutex<StatusCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathStatus {
+    pub exists: bool,
+    pub is_dir: bool,
+}
+
+/// A list entry annotated with whether its path is still present on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithStatus<T> {
+    #[serde(flatten)]
+    pub entry: T,
+    #[serde(flatten)]
+    pub status: PathStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteEntry {
+    pub path: String,
+    /// Set for imported favorites, e.g. `gtk_bookmarks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+pub(crate) fn path_status(path: &str) -> PathStatus {
+    let now = Instant::now();
+    if let Some((checked, status)) = STATUS_CACHE.lock().get(path) {
+        if now.duration_since(*checked) < STATUS_TTL {
+            return *status;
+        }
+    }
+    let metadata = std::fs::metadata(Path::new(path));
+    let status = PathStatus {
+        exists: metadata.is_ok(),
+        is_dir: metadata.map(|md| md.is_dir()).unwrap_or(false),
+    };
+    remember(&mut STATUS_CACHE.lock(), path, now, status);
+    status
+}
+
+fn remember(cache: &mut StatusCache, path: &str, now: Instant, status: PathStatus) {
+    if cache.len() >= STATUS_CACHE_CAP && !cache.contains_key(path) {
+        cache.retain(|_, (checked, _)| now.duration_since(*checked) < STATUS_TTL);
+        if cache.len() >= STATUS_CACHE_CAP {
+            cache.clear();
+        }
+    }
+    cache.insert(path.to_string(), (now, status));
+}
+
+pub(crate) fn annotate<T>(entries: Vec<T>, path_of: impl Fn(&T) -> &str) -> Vec<WithStatus<T>> {
+    entries
+        .into_iter()
+        .map(|entry| WithStatus {
+            status: path_status(path_of(&entry)),
+            entry,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_tells_directories_files_and_missing_paths_apart() {
+        let dir = std::env::temp_dir().join(format!("terminaut-status-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("notes.txt");
+        std::fs::write(&file, "").unwrap();
+        let status = |path: &Path| path_status(&path.display().to_string());
+        assert_eq!(
+            status(&dir),
+            PathStatus {
+                exists: true,
+                is_dir: true
+            }
+        );
+        assert_eq!(
+            status(&file),
+            PathStatus {
+                exists: true,
+                is_dir: false
+            }
+        );
+        assert!(!status(&dir.join("gone")).exists);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn statuses_are_reused_until_they_expire() {
+        let dir = std::env::temp_dir().join(format!("terminaut-status-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.display().to_string();
+        assert!(path_status(&path).exists);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(path_status(&path).exists);
+        let expired = Instant::now() - STATUS_TTL;
+        STATUS_CACHE.lock().get_mut(&path).unwrap().0 = expired;
+        assert!(!path_status(&path).exists);
+    }
+
+    #[test]
+    fn the_cache_stays_bounded() {
+        let mut cache = StatusCache::new();
+        let status = PathStatus {
+            exists: true,
+            is_dir: true,
+        };
+        let old = Instant::now() - STATUS_TTL;
+        for index in 0..STATUS_CACHE_CAP {
+            let checked = if index % 2 == 0 { old } else { Instant::now() };
+            remember(&mut cache, &format!("/p{index}"), checked, status);
+        }
+        assert_eq!(cache.len(), STATUS_CACHE_CAP);
+        // Expired entries make room first.
+        remember(&mut cache, "/new", Instant::now(), status);
+        assert_eq!(cache.len(), STATUS_CACHE_CAP / 2 + 1);
+        assert!(cache.contains_key("/p1") && !cache.contains_key("/p0"));
+    }
+
+    #[test]
+    fn annotated_entries_keep_their_order() {
+        let entries = vec![
+            std::env::temp_dir().display().to_string(),
+            String::from("/definitely/not/a/real/terminaut/path"),
+        ];
+        let annotated = annotate(entries.clone(), |path| path.as_str());
+        assert_eq!(annotated[0].entry, entries[0]);
+        assert!(annotated[0].status.is_dir);
+        assert!(!annotated[1].status.exists);
+        let json = serde_json::to_value(annotate(
+            vec![FavoriteEntry {
+                path: entries[1].clone(),
+                source: None,
+            }],
+            |entry| entry.path.as_str(),
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"path": entries[1], "exists": false, "is_dir": false}])
+        );
+    }
+}
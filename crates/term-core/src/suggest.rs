@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::RecentEntry;
+
+/// How a suggested directory relates to the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    /// Below the current directory.
+    Descendant,
+    /// Below the current directory's parent, outside the current directory.
+    Sibling,
+}
+
+/// A directory the user is likely to open next, as returned by [`crate::api::suggest_next`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub path: String,
+    pub relation: Relation,
+    /// Levels below the current directory (descendants) or its parent (siblings).
+    pub depth: usize,
+    /// The recent's frecency, weighted down with depth and for siblings.
+    pub score: f64,
+}
+
+/// Siblings count for less than descendants: moving down is the usual next step.
+const SIBLING_WEIGHT: f64 = 0.5;
+
+/// Recents below `path` or its parent, scored by frecency as of `now` and ranked best first.
+pub(crate) fn rank(path: &Path, recents: &[RecentEntry], now: i64) -> Vec<Suggestion> {
+    let parent = path.parent();
+    let mut suggestions: Vec<Suggestion> = recents
+        .iter()
+        .filter_map(|recent| {
+            let candidate = Path::new(&recent.path);
+            let (relation, base, weight) = if candidate.starts_with(path) {
+                (Relation::Descendant, path, 1.0)
+            } else {
+                let parent = parent.filter(|parent| candidate.starts_with(parent))?;
+                (Relation::Sibling, parent, SIBLING_WEIGHT)
+            };
+            let depth = candidate.strip_prefix(base).ok()?.components().count();
+            (depth > 0).then(|| Suggestion {
+                path: recent.path.clone(),
+                relation,
+                depth,
+                score: recent.frecency(now) * weight / depth as f64,
+            })
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_800_000_000;
+
+    fn recent(path: &str, score: f64) -> RecentEntry {
+        RecentEntry {
+            path: path.to_string(),
+            last_opened_utc: NOW,
+            score,
+            ..RecentEntry::default()
+        }
+    }
+
+    fn paths(suggestions: &[Suggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.path.as_str()).collect()
+    }
+
+    #[test]
+    fn descendants_outrank_equally_scored_siblings() {
+        let ranked = rank(
+            Path::new("/w/app"),
+            &[recent("/w/lib", 2.0), recent("/w/app/src", 2.0)],
+            NOW,
+        );
+        assert_eq!(paths(&ranked), ["/w/app/src", "/w/lib"]);
+        assert_eq!(ranked[0].relation, Relation::Descendant);
+        assert_eq!(ranked[1].relation, Relation::Sibling);
+    }
+
+    #[test]
+    fn deeper_paths_score_less() {
+        let ranked = rank(
+            Path::new("/w/app"),
+            &[
+                recent("/w/app/src/ui/forms", 4.0),
+                recent("/w/app/src", 2.0),
+            ],
+            NOW,
+        );
+        assert_eq!(paths(&ranked), ["/w/app/src", "/w/app/src/ui/forms"]);
+        assert_eq!(ranked[1].depth, 3);
+    }
+
+    #[test]
+    fn the_current_directory_and_unrelated_paths_are_not_suggested() {
+        let ranked = rank(
+            Path::new("/w/app"),
+            &[
+                recent("/w/app", 9.0),
+                recent("/elsewhere", 50.0),
+                recent("/w/app-old", 1.0),
+            ],
+            NOW,
+        );
+        assert_eq!(paths(&ranked), ["/w/app-old"]);
+    }
+
+    #[test]
+    fn filesystem_roots_have_no_siblings() {
+        let ranked = rank(Path::new("/"), &[recent("/w", 1.0)], NOW);
+        assert_eq!(paths(&ranked), ["/w"]);
+        assert_eq!(ranked[0].relation, Relation::Descendant);
+    }
+
+    #[test]
+    fn ties_rank_by_path() {
+        let ranked = rank(
+            Path::new("/w"),
+            &[recent("/w/b", 1.0), recent("/w/a", 1.0)],
+            NOW,
+        );
+        assert_eq!(paths(&ranked), ["/w/a", "/w/b"]);
+    }
+}
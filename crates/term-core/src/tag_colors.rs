@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// The built-in names, as the macOS system colors Finder draws its labels with.
+const BUILTIN: [(&str, &str); 11] = [
+    ("red", "#ff3b30"),
+    ("orange", "#ff9500"),
+    ("yellow", "#ffcc00"),
+    ("green", "#34c759"),
+    ("teal", "#5ac8fa"),
+    ("blue", "#0a84ff"),
+    ("indigo", "#5856d6"),
+    ("purple", "#af52de"),
+    ("pink", "#ff2d55"),
+    ("brown", "#a2845e"),
+    ("gray", "#8e8e93"),
+];
+
+/// A named tag color, as listed by [`crate::api::list_tag_colors`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagColor {
+    pub name: String,
+    /// `#rrggbb`, lowercase.
+    pub hex: String,
+    /// Defined or overridden in `tags.palette` rather than built in.
+    pub custom: bool,
+}
+
+/// `#rgb` or `#rrggbb` as lowercase `#rrggbb`.
+pub(crate) fn parse_hex(value: &str) -> anyhow::Result<String> {
+    let digits = value.strip_prefix('#').unwrap_or("");
+    if !matches!(digits.len(), 3 | 6) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!(
+            CoreError::invalid_input(format!("{value:?} is not a color"))
+                .with_hint("use #rrggbb, #rgb, or a palette name such as blue")
+        );
+    }
+    let digits = digits.to_ascii_lowercase();
+    Ok(match digits.len() {
+        3 => digits.chars().fold(String::from("#"), |mut hex, c| {
+            hex.extend([c, c]);
+            hex
+        }),
+        _ => format!("#{digits}"),
+    })
+}
+
+/// Checks the names and colors of a `tags.palette` table.
+pub(crate) fn validate_palette(palette: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    for (name, hex) in palette {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("{name:?} is not a color name (letters, digits, - and _)");
+        }
+        parse_hex(hex)?;
+    }
+    Ok(())
+}
+
+/// The built-in colors followed by the palette's own, with the palette overriding same-named
+/// built-ins.
+pub(crate) fn list_tag_colors() -> Vec<TagColor> {
+    with_palette(crate::config::config().tags.palette.clone())
+}
+
+/// The built-in colors with `palette` applied; entries that aren't colors are skipped.
+fn with_palette(palette: BTreeMap<String, String>) -> Vec<TagColor> {
+    let mut colors: Vec<TagColor> = BUILTIN
+        .iter()
+        .map(|(name, hex)| TagColor {
+            name: name.to_string(),
+            hex: hex.to_string(),
+            custom: false,
+        })
+        .collect();
+    for (name, hex) in palette {
+        let Ok(hex) = parse_hex(&hex) else {
+            continue;
+        };
+        match colors
+            .iter_mut()
+            .find(|color| color.name.eq_ignore_ascii_case(&name))
+        {
+            Some(color) => {
+                color.hex = hex;
+                color.custom = true;
+            }
+            None => colors.push(TagColor {
+                name,
+                hex,
+                custom: true,
+            }),
+        }
+    }
+    colors
+}
+
+/// A hex color or a palette name as the `#rrggbb` tags are stored with.
+pub(crate) fn resolve_color(color: &str) -> anyhow::Result<String> {
+    let color = color.trim();
+    if color.starts_with('#') {
+        return parse_hex(color);
+    }
+    let colors = list_tag_colors();
+    match colors
+        .iter()
+        .find(|known| known.name.eq_ignore_ascii_case(color))
+    {
+        Some(known) => Ok(known.hex.clone()),
+        None => {
+            let names: Vec<&str> = colors.iter().map(|known| known.name.as_str()).collect();
+            anyhow::bail!(
+                CoreError::invalid_input(format!("unknown tag color {color:?}"))
+                    .with_hint(format!("use #rrggbb or one of: {}", names.join(", ")))
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn palette(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, hex)| (name.to_string(), hex.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn hex_colors_are_lowercased_and_expanded() {
+        assert_eq!(parse_hex("#0A84FF").unwrap(), "#0a84ff");
+        assert_eq!(parse_hex("#f0a").unwrap(), "#ff00aa");
+    }
+
+    #[test]
+    fn malformed_hex_colors_are_rejected() {
+        for value in ["#12345", "#ggg", "0a84ff", "#", ""] {
+            let err = parse_hex(value).unwrap_err();
+            assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn builtin_names_resolve_ignoring_case_and_spaces() {
+        assert_eq!(resolve_color(" #0A84FF ").unwrap(), "#0a84ff");
+        let red = with_palette(BTreeMap::new())
+            .into_iter()
+            .find(|color| color.name == "red")
+            .unwrap();
+        assert_eq!(red.hex, "#ff3b30");
+        assert!(!red.custom);
+    }
+
+    #[test]
+    fn unknown_names_are_rejected_with_the_choices() {
+        let err = resolve_color("chartreuse-ish").unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn palettes_override_builtins_and_add_colors() {
+        let colors = with_palette(palette(&[("Red", "#c00"), ("accent", "#3366ff")]));
+        assert_eq!(colors.len(), BUILTIN.len() + 1);
+        assert_eq!(
+            colors[0],
+            TagColor {
+                name: String::from("red"),
+                hex: String::from("#cc0000"),
+                custom: true,
+            }
+        );
+        assert_eq!(colors.last().unwrap().name, "accent");
+    }
+
+    #[test]
+    fn palette_entries_that_are_not_colors_are_skipped() {
+        let colors = with_palette(palette(&[("red", "crimson"), ("accent", "#12")]));
+        assert_eq!(colors.len(), BUILTIN.len());
+        assert!(!colors[0].custom);
+    }
+
+    #[test]
+    fn palettes_are_validated_by_name_and_color() {
+        assert!(validate_palette(&palette(&[("accent-blue", "#3366ff")])).is_ok());
+        assert!(validate_palette(&palette(&[("bad name", "#3366ff")])).is_err());
+        assert!(validate_palette(&palette(&[("", "#3366ff")])).is_err());
+        assert!(validate_palette(&palette(&[("accent", "blue")])).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CoreError, TaggedPath};
+
+/// One segment of a namespaced tag such as `client/acme`, as returned by
+/// [`crate::api::tag_tree`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagNode {
+    /// This segment, e.g. `acme`.
+    pub name: String,
+    /// The full tag up to this segment, e.g. `client/acme`.
+    pub tag: String,
+    /// Paths tagged with exactly `tag`.
+    pub count: usize,
+    /// Distinct paths tagged with `tag` or any tag below it.
+    pub total: usize,
+    pub children: Vec<TagNode>,
+}
+
+/// Rejects empty tags and namespaces with empty segments, such as `client//acme` or `lang/`.
+pub(crate) fn validate_tag(tag: &str) -> anyhow::Result<()> {
+    if tag.trim().is_empty() || tag.split('/').any(|segment| segment.trim().is_empty()) {
+        anyhow::bail!(
+            CoreError::invalid_input(format!("{tag:?} is not a valid tag"))
+                .with_hint("separate namespaces with single slashes, e.g. client/acme")
+        );
+    }
+    Ok(())
+}
+
+/// Whether `tag` is `prefix` or lies below it, ignoring case and a trailing slash on `prefix`.
+pub(crate) fn has_prefix(tag: &str, prefix: &str) -> bool {
+    let (tag, prefix) = (
+        tag.to_lowercase(),
+        prefix.trim_end_matches('/').to_lowercase(),
+    );
+    prefix.is_empty()
+        || tag == prefix
+        || tag
+            .strip_prefix(&prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Tags of `path`, then with `inherit` those of its ancestors that it doesn't carry itself,
+/// nearest ancestor first.
+pub(crate) fn tags_for(tags: &[TaggedPath], path: &Path, inherit: bool) -> Vec<TaggedPath> {
+    let key = path.display().to_string();
+    let mut found: Vec<TaggedPath> = tags
+        .iter()
+        .filter(|entry| entry.path == key)
+        .cloned()
+        .collect();
+    if !inherit {
+        return found;
+    }
+    for ancestor in path.ancestors().skip(1) {
+        let key = ancestor.display().to_string();
+        for entry in tags.iter().filter(|entry| entry.path == key) {
+            if !found
+                .iter()
+                .any(|tagged| tagged.tag.eq_ignore_ascii_case(&entry.tag))
+            {
+                found.push(TaggedPath {
+                    inherited: true,
+                    ..entry.clone()
+                });
+            }
+        }
+    }
+    found
+}
+
+#[derive(Default)]
+struct Branch {
+    name: String,
+    paths: BTreeSet<String>,
+    children: BTreeMap<String, Branch>,
+}
+
+impl Branch {
+    /// Converts to a node, returning the distinct paths of the subtree alongside.
+    fn into_node(self, parent: &str) -> (TagNode, BTreeSet<String>) {
+        let tag = match parent.is_empty() {
+            true => self.name.clone(),
+            false => format!("{parent}/{}", self.name),
+        };
+        let mut all = self.paths.clone();
+        let children = self
+            .children
+            .into_values()
+            .map(|child| {
+                let (node, paths) = child.into_node(&tag);
+                all.extend(paths);
+                node
+            })
+            .collect();
+        let node = TagNode {
+            name: self.name,
+            count: self.paths.len(),
+            total: all.len(),
+            tag,
+            children,
+        };
+        (node, all)
+    }
+}
+
+/// Tags split on `/` into a tree, segments merged case-insensitively (the first spelling
+/// seen names the node) and sorted by name.
+pub(crate) fn tag_tree(tags: &[TaggedPath]) -> Vec<TagNode> {
+    let mut root = Branch::default();
+    for tagged in tags {
+        let mut branch = &mut root;
+        for segment in tagged.tag.split('/') {
+            branch = branch
+                .children
+                .entry(segment.to_lowercase())
+                .or_insert_with(|| Branch {
+                    name: segment.to_string(),
+                    ..Branch::default()
+                });
+        }
+        branch.paths.insert(tagged.path.clone());
+    }
+    root.children
+        .into_values()
+        .map(|branch| branch.into_node("").0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_nest_and_count_distinct_paths() {
+        let tagged = |path: &str, tag: &str| TaggedPath {
+            path: path.to_string(),
+            tag: tag.to_string(),
+            color: String::new(),
+            inherited: false,
+        };
+        let tree = tag_tree(&[
+            tagged("/w/a", "client/acme"),
+            tagged("/w/a", "Client"),
+            tagged("/w/b", "client/acme/web"),
+            tagged("/w/c", "client/globex"),
+            tagged("/w/a", "lang/rust"),
+        ]);
+        assert_eq!(tree.len(), 2);
+        let client = &tree[0];
+        assert_eq!(
+            (client.name.as_str(), client.count, client.total),
+            ("client", 1, 3)
+        );
+        let acme = &client.children[0];
+        assert_eq!(
+            (acme.tag.as_str(), acme.count, acme.total),
+            ("client/acme", 1, 2)
+        );
+        assert_eq!(acme.children[0].tag, "client/acme/web");
+
+        assert!(has_prefix("Client/Acme", "client/"));
+        assert!(has_prefix("client", "client/"));
+        assert!(!has_prefix("clients/x", "client"));
+        assert!(validate_tag("client//acme").is_err());
+        assert!(validate_tag("lang/").is_err());
+        assert!(validate_tag("lang/rust").is_ok());
+    }
+
+    #[test]
+    fn descendants_inherit_ancestor_tags_once() {
+        let tagged = |path: &str, tag: &str| TaggedPath {
+            path: path.to_string(),
+            tag: tag.to_string(),
+            color: String::new(),
+            inherited: false,
+        };
+        let tags = [
+            tagged("/w/clients", "Client"),
+            tagged("/w/clients/acme", "client/acme"),
+            tagged("/w/clients/acme/api", "client"),
+            tagged("/w/other", "other"),
+        ];
+        let path = Path::new("/w/clients/acme/api");
+        assert_eq!(tags_for(&tags, path, false).len(), 1);
+        let found = tags_for(&tags, path, true);
+        let found: Vec<(&str, &str, bool)> = found
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.tag.as_str(), entry.inherited))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/w/clients/acme/api", "client", false),
+                ("/w/clients/acme", "client/acme", true),
+            ]
+        );
+    }
+}
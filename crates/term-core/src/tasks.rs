@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::ProjectInfo;
+use crate::{CoreError, STORE};
+
+/// How [`crate::api::run_task`] runs a task.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskOptions {
+    /// Add the `env` table of the project's `.terminaut.toml` to the task's environment.
+    pub project_env: bool,
+    /// Kill the task after this many seconds; `None` waits for it however long it takes.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStream {
+    Stdout,
+    Stderr,
+}
+
+/// One line of a running task's output, without its line ending.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskLine {
+    pub stream: TaskStream,
+    pub line: String,
+}
+
+/// Receives output lines as the task prints them, from one thread per stream.
+pub type TaskCallback = Arc<dyn Fn(&TaskLine) + Send + Sync>;
+
+/// How a task run ended, as returned by [`crate::api::run_task`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskResult {
+    pub task: String,
+    pub command: String,
+    /// The project root the task ran in.
+    pub cwd: String,
+    /// `None` when the task was killed, by a signal or for running past `timeout_secs`.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+impl TaskResult {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// The last run of one task, kept in the store for [`crate::api::project_status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub finished_utc: i64,
+}
+
+/// Last results of the tasks a project declares, as returned by
+/// [`crate::api::project_status`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectStatus {
+    pub root: String,
+    /// By task name; tasks that never ran through [`crate::api::run_task`] are absent.
+    pub tasks: BTreeMap<String, TaskStatus>,
+    /// Whether every recorded task passed its last run; `None` when none has run.
+    pub passing: Option<bool>,
+}
+
+/// Keeps `result` as the last run of its task. Failing to save doesn't fail the run.
+fn record(result: &TaskResult) {
+    let status = status_of(result, chrono::Utc::now().timestamp());
+    let mut store = STORE.inner.lock();
+    store
+        .task_results
+        .entry(result.cwd.clone())
+        .or_default()
+        .insert(result.task.clone(), status);
+    if let Err(err) = STORE.persist(&mut store) {
+        tracing::warn!(
+            "failed to record the result of task {}: {err:#}",
+            result.task
+        );
+    }
+}
+
+fn status_of(result: &TaskResult, now: i64) -> TaskStatus {
+    TaskStatus {
+        passed: result.succeeded(),
+        exit_code: result.exit_code,
+        timed_out: result.timed_out,
+        duration_ms: result.duration_ms,
+        finished_utc: now,
+    }
+}
+
+/// Recorded results for the project containing `path`, limited to tasks it still declares.
+pub(crate) fn project_status(path: &Path) -> anyhow::Result<ProjectStatus> {
+    let project = crate::workspace::project_info(path)?;
+    let store = STORE.inner.lock();
+    let recorded = store.task_results.get(&project.root);
+    Ok(summarize(project.root.clone(), recorded, &project.tasks))
+}
+
+fn summarize(
+    root: String,
+    recorded: Option<&BTreeMap<String, TaskStatus>>,
+    declared: &BTreeMap<String, String>,
+) -> ProjectStatus {
+    let tasks: BTreeMap<String, TaskStatus> = recorded
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| declared.contains_key(*name))
+        .map(|(name, status)| (name.clone(), status.clone()))
+        .collect();
+    ProjectStatus {
+        passing: (!tasks.is_empty()).then(|| tasks.values().all(|status| status.passed)),
+        root,
+        tasks,
+    }
+}
+
+/// Reads `pipe` line by line into `callback`. Bytes that aren't UTF-8 are replaced.
+fn forward(
+    pipe: Option<impl Read + Send + 'static>,
+    stream: TaskStream,
+    callback: Option<TaskCallback>,
+) -> Option<JoinHandle<()>> {
+    let pipe = pipe?;
+    Some(std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buffer = Vec::new();
+        while reader
+            .read_until(b'\n', &mut buffer)
+            .is_ok_and(|read| read > 0)
+        {
+            if let Some(callback) = &callback {
+                let line = String::from_utf8_lossy(&buffer);
+                callback(&TaskLine {
+                    stream,
+                    line: line.trim_end_matches(['\n', '\r']).to_string(),
+                });
+            }
+            buffer.clear();
+        }
+    }))
+}
+
+/// Runs task `name` of the project containing `path` in the project root, with the platform
+/// shell, streaming its output to `callback`.
+pub(crate) fn run_task(
+    path: &Path,
+    name: &str,
+    options: &TaskOptions,
+    callback: Option<TaskCallback>,
+) -> anyhow::Result<TaskResult> {
+    let project = crate::workspace::project_info(path)?;
+    let result = execute(&project, name, options, callback)?;
+    record(&result);
+    Ok(result)
+}
+
+/// Runs task `name` of `project` without recording the result.
+fn execute(
+    project: &ProjectInfo,
+    name: &str,
+    options: &TaskOptions,
+    callback: Option<TaskCallback>,
+) -> anyhow::Result<TaskResult> {
+    let Some(command) = project.tasks.get(name).cloned() else {
+        let known = project.tasks.keys().cloned().collect::<Vec<_>>().join(", ");
+        let hint = match known.is_empty() {
+            true => String::from("declare tasks in the project's .terminaut.toml"),
+            false => format!("tasks in {}: {known}", project.name),
+        };
+        anyhow::bail!(CoreError::not_found(format!("no task named {name:?}")).with_hint(hint));
+    };
+    let mut shell = crate::hooks::shell(&command);
+    shell
+        .current_dir(&project.root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if options.project_env {
+        shell.envs(&project.env);
+    }
+    let started = Instant::now();
+    let mut child = shell
+        .spawn()
+        .map_err(|err| anyhow::anyhow!("could not start task {name}: {err}"))?;
+    let readers = [
+        forward(child.stdout.take(), TaskStream::Stdout, callback.clone()),
+        forward(child.stderr.take(), TaskStream::Stderr, callback),
+    ];
+    let timeout = options.timeout_secs.map(Duration::from_secs);
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            let _ = child.kill();
+            timed_out = true;
+            break child.wait()?;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    // A killed shell can leave children holding the pipes, so only wait for the readers of a
+    // task that exited by itself.
+    if !timed_out {
+        readers.into_iter().flatten().for_each(|reader| {
+            let _ = reader.join();
+        });
+    }
+    Ok(TaskResult {
+        task: name.to_string(),
+        command,
+        cwd: project.root.clone(),
+        exit_code: status.code().filter(|_| !timed_out),
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_code, ErrorCode};
+
+    fn status(passed: bool) -> TaskStatus {
+        TaskStatus {
+            passed,
+            exit_code: Some(if passed { 0 } else { 1 }),
+            timed_out: false,
+            duration_ms: 5,
+            finished_utc: 1,
+        }
+    }
+
+    fn project(tasks: &str) -> (std::path::PathBuf, ProjectInfo) {
+        let root = std::env::temp_dir().join(format!("terminaut-tasks-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join(crate::workspace::WORKSPACE_FILE),
+            format!("[env]\nGREETING = \"hi\"\n\n[tasks]\n{tasks}"),
+        )
+        .unwrap();
+        let info = crate::workspace::project_info(&root).unwrap();
+        (root, info)
+    }
+
+    fn collect() -> (TaskCallback, Arc<parking_lot::Mutex<Vec<TaskLine>>>) {
+        let lines = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        (
+            Arc::new(move |line: &TaskLine| sink.lock().push(line.clone())),
+            lines,
+        )
+    }
+
+    #[test]
+    fn only_exit_code_zero_passes() {
+        let result = |exit_code| TaskResult {
+            task: String::from("test"),
+            command: String::from("true"),
+            cwd: String::from("/work"),
+            exit_code,
+            timed_out: false,
+            duration_ms: 5,
+        };
+        assert!(status_of(&result(Some(0)), 9).passed);
+        assert!(!status_of(&result(Some(3)), 9).passed);
+        assert!(!status_of(&result(None), 9).passed);
+        assert_eq!(status_of(&result(None), 9).finished_utc, 9);
+    }
+
+    #[test]
+    fn statuses_cover_only_declared_tasks() {
+        let recorded = BTreeMap::from([
+            (String::from("test"), status(true)),
+            (String::from("removed"), status(false)),
+        ]);
+        let declared = BTreeMap::from([(String::from("test"), String::from("cargo test"))]);
+        let summary = summarize(String::from("/work"), Some(&recorded), &declared);
+        assert_eq!(summary.tasks.keys().collect::<Vec<_>>(), ["test"]);
+        assert_eq!(summary.passing, Some(true));
+    }
+
+    #[test]
+    fn one_failing_task_fails_the_project() {
+        let recorded = BTreeMap::from([
+            (String::from("lint"), status(true)),
+            (String::from("test"), status(false)),
+        ]);
+        let declared = recorded
+            .keys()
+            .map(|name| (name.clone(), String::new()))
+            .collect();
+        let summary = summarize(String::from("/work"), Some(&recorded), &declared);
+        assert_eq!(summary.passing, Some(false));
+    }
+
+    #[test]
+    fn projects_that_never_ran_a_task_have_no_verdict() {
+        let declared = BTreeMap::from([(String::from("test"), String::from("cargo test"))]);
+        let summary = summarize(String::from("/work"), None, &declared);
+        assert!(summary.tasks.is_empty());
+        assert_eq!(summary.passing, None);
+    }
+
+    #[test]
+    fn unknown_tasks_are_not_found_with_the_known_ones() {
+        let (root, project) = project("greet = \"echo hi\"\n");
+        let err = execute(&project, "deploy", &TaskOptions::default(), None).unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::NotFound);
+        let hint = err.downcast_ref::<CoreError>().unwrap().hint.clone();
+        assert!(hint.unwrap().ends_with(": greet"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_streams_line_by_line_with_the_exit_code() {
+        let (root, project) = project("greet = \"echo hi; echo oops >&2; exit 3\"\n");
+        let (callback, lines) = collect();
+        let result = execute(&project, "greet", &TaskOptions::default(), Some(callback)).unwrap();
+        assert_eq!(result.exit_code, Some(3));
+        assert!(!result.timed_out);
+        let lines = lines.lock();
+        assert!(lines.contains(&TaskLine {
+            stream: TaskStream::Stdout,
+            line: String::from("hi"),
+        }));
+        assert!(lines.contains(&TaskLine {
+            stream: TaskStream::Stderr,
+            line: String::from("oops"),
+        }));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_env_is_passed_only_when_asked() {
+        let (root, project) = project("greet = \"echo ${GREETING:-none}\"\n");
+        for (project_env, expected) in [(true, "hi"), (false, "none")] {
+            let (callback, lines) = collect();
+            let options = TaskOptions {
+                project_env,
+                timeout_secs: None,
+            };
+            execute(&project, "greet", &options, Some(callback)).unwrap();
+            assert_eq!(lines.lock()[0].line, expected);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tasks_run_in_the_project_root() {
+        let (root, project) = project("where = \"pwd\"\n");
+        let (callback, lines) = collect();
+        let result = execute(&project, "where", &TaskOptions::default(), Some(callback)).unwrap();
+        assert_eq!(result.cwd, project.root);
+        assert_eq!(
+            std::fs::canonicalize(&lines.lock()[0].line).unwrap(),
+            std::fs::canonicalize(&root).unwrap()
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timed_out_tasks_are_killed_without_an_exit_code() {
+        let (root, project) = project("slow = \"sleep 5\"\n");
+        let options = TaskOptions {
+            project_env: false,
+            timeout_secs: Some(0),
+        };
+        let result = execute(&project, "slow", &options, None).unwrap();
+        assert!(result.timed_out);
+        assert_eq!(result.exit_code, None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
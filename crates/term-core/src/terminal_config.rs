@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// What a terminal's own config file sets for the shells it opens, as returned by
+/// [`crate::api::terminal_settings`]. Only Ghostty and Alacritty configs are read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalSettings {
+    /// The config file the settings came from; `None` when there is none to read.
+    pub config_path: Option<String>,
+    /// The program the terminal runs instead of the login shell.
+    pub shell: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shell_args: Vec<String>,
+    /// Variables the terminal adds to the environment of its shells.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl TerminalSettings {
+    /// The configured shell as one quoted command line.
+    pub(crate) fn shell_line(&self) -> Option<String> {
+        let shell = self.shell.clone()?;
+        let words: Vec<String> = std::iter::once(shell)
+            .chain(self.shell_args.iter().cloned())
+            .collect();
+        Some(crate::command_line::join(&words, false))
+    }
+}
+
+/// `$XDG_CONFIG_HOME`, else `~/.config`. Ghostty and Alacritty use it on macOS as well.
+fn xdg_config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+}
+
+fn read_first(candidates: Vec<PathBuf>) -> Option<(PathBuf, String)> {
+    candidates
+        .into_iter()
+        .find_map(|path| Some((path.clone(), std::fs::read_to_string(&path).ok()?)))
+}
+
+/// Ghostty's config in `key = value` lines. `command` may be a whole command line; `env`
+/// repeats as `env = NAME=value`.
+fn parse_ghostty(text: &str) -> TerminalSettings {
+    let mut settings = TerminalSettings::default();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        match key.trim() {
+            "command" if value.is_empty() => {
+                settings.shell = None;
+                settings.shell_args.clear();
+            }
+            "command" => {
+                let words = crate::command_line::parse(value)
+                    .ok()
+                    .filter(|parsed| !parsed.use_shell)
+                    .map(|parsed| parsed.args)
+                    .unwrap_or_else(|| vec![value.to_string()]);
+                let (shell, args) = words.split_first().expect("parsed commands have a program");
+                settings.shell = Some(shell.clone());
+                settings.shell_args = args.to_vec();
+            }
+            "env" if value.is_empty() => settings.env.clear(),
+            "env" => {
+                if let Some((name, value)) = value.split_once('=') {
+                    settings
+                        .env
+                        .insert(name.trim().to_string(), value.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    settings
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AlacrittyShell {
+    Program(String),
+    Table {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Default, Deserialize)]
+struct AlacrittyTerminal {
+    shell: Option<AlacrittyShell>,
+}
+
+#[derive(Default, Deserialize)]
+struct AlacrittyConfig {
+    #[serde(default)]
+    terminal: AlacrittyTerminal,
+    /// Where `shell` lived before Alacritty 0.14.
+    shell: Option<AlacrittyShell>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// Alacritty's TOML config: `[terminal.shell]` (or the older top-level `shell`), either a
+/// program or `{ program, args }`, and the `[env]` table.
+fn parse_alacritty(text: &str) -> anyhow::Result<TerminalSettings> {
+    let config: AlacrittyConfig = toml::from_str(text)?;
+    let (shell, shell_args) = match config.terminal.shell.or(config.shell) {
+        Some(AlacrittyShell::Program(program)) => (Some(program), Vec::new()),
+        Some(AlacrittyShell::Table { program, args }) => (Some(program), args),
+        None => (None, Vec::new()),
+    };
+    Ok(TerminalSettings {
+        config_path: None,
+        shell,
+        shell_args,
+        env: config.env,
+    })
+}
+
+pub(crate) fn ghostty() -> TerminalSettings {
+    let mut candidates: Vec<PathBuf> = xdg_config_home()
+        .into_iter()
+        .flat_map(|dir| {
+            [
+                dir.join("ghostty/config"),
+                dir.join("ghostty/config.ghostty"),
+            ]
+        })
+        .collect();
+    if cfg!(target_os = "macos") {
+        candidates.extend(
+            dirs::home_dir()
+                .map(|home| home.join("Library/Application Support/com.mitchellh.ghostty/config")),
+        );
+    }
+    match read_first(candidates) {
+        Some((path, text)) => TerminalSettings {
+            config_path: Some(path.display().to_string()),
+            ..parse_ghostty(&text)
+        },
+        None => TerminalSettings::default(),
+    }
+}
+
+/// Alacritty's settings. A config that doesn't parse is reported and otherwise ignored, as
+/// Alacritty itself falls back to its defaults then.
+pub(crate) fn alacritty() -> TerminalSettings {
+    let mut candidates: Vec<PathBuf> = xdg_config_home()
+        .into_iter()
+        .map(|dir| dir.join("alacritty/alacritty.toml"))
+        .collect();
+    candidates.extend(dirs::home_dir().map(|home| home.join(".alacritty.toml")));
+    if cfg!(windows) {
+        candidates.extend(dirs::config_dir().map(|dir| dir.join("alacritty/alacritty.toml")));
+    }
+    let Some((path, text)) = read_first(candidates) else {
+        return TerminalSettings::default();
+    };
+    let settings = parse_alacritty(&text).unwrap_or_else(|err| {
+        tracing::warn!("ignoring {}: {err}", path.display());
+        TerminalSettings::default()
+    });
+    TerminalSettings {
+        config_path: Some(path.display().to_string()),
+        ..settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghostty_commands_split_into_shell_and_args() {
+        let ghostty =
+            parse_ghostty("# theme\nfont-size = 13\ncommand = /opt/homebrew/bin/fish --login\n");
+        assert_eq!(ghostty.shell.as_deref(), Some("/opt/homebrew/bin/fish"));
+        assert_eq!(ghostty.shell_args, ["--login"]);
+        assert_eq!(
+            ghostty.shell_line().as_deref(),
+            Some("/opt/homebrew/bin/fish --login")
+        );
+    }
+
+    #[test]
+    fn ghostty_env_lines_accumulate_and_an_empty_one_resets() {
+        let ghostty = parse_ghostty("env = EDITOR=hx\nenv=PAGER=less -R\n");
+        assert_eq!(ghostty.env["EDITOR"], "hx");
+        assert_eq!(ghostty.env["PAGER"], "less -R");
+        let reset = parse_ghostty("env = EDITOR=hx\nenv =\nenv = PAGER=less\n");
+        assert_eq!(reset.env.keys().collect::<Vec<_>>(), ["PAGER"]);
+    }
+
+    #[test]
+    fn later_ghostty_commands_win_and_an_empty_one_resets() {
+        let ghostty = parse_ghostty("command = zsh -l\ncommand = \"/bin/bash\"\n");
+        assert_eq!(ghostty.shell.as_deref(), Some("/bin/bash"));
+        assert!(ghostty.shell_args.is_empty());
+        let reset = parse_ghostty("command = zsh -l\ncommand =\n");
+        assert_eq!(reset, TerminalSettings::default());
+    }
+
+    #[test]
+    fn ghostty_commented_and_malformed_lines_are_ignored() {
+        let ghostty = parse_ghostty("# command = fish\ncommand\nenv = NOVALUE\n");
+        assert_eq!(ghostty, TerminalSettings::default());
+    }
+
+    #[test]
+    fn alacritty_terminal_shell_tables_give_program_and_args() {
+        let current = parse_alacritty(
+            "[terminal.shell]\nprogram = \"/bin/zsh\"\nargs = [\"-l\"]\n\n[env]\nTERM = \"xterm-256color\"\n",
+        )
+        .unwrap();
+        assert_eq!(current.shell.as_deref(), Some("/bin/zsh"));
+        assert_eq!(current.shell_args, ["-l"]);
+        assert_eq!(current.env["TERM"], "xterm-256color");
+    }
+
+    #[test]
+    fn alacritty_legacy_shells_are_read() {
+        let legacy = parse_alacritty("shell = \"/usr/bin/bash\"\n").unwrap();
+        assert_eq!(legacy.shell.as_deref(), Some("/usr/bin/bash"));
+        let both = parse_alacritty("shell = \"/usr/bin/bash\"\n[terminal]\nshell = \"/bin/zsh\"\n")
+            .unwrap();
+        assert_eq!(both.shell.as_deref(), Some("/bin/zsh"));
+    }
+
+    #[test]
+    fn alacritty_configs_without_a_shell_set_none() {
+        assert_eq!(
+            parse_alacritty("[window]\nopacity = 0.9\n").unwrap(),
+            TerminalSettings::default()
+        );
+    }
+
+    #[test]
+    fn unparseable_alacritty_configs_are_errors() {
+        assert!(parse_alacritty("[terminal\n").is_err());
+        assert!(parse_alacritty("[terminal.shell]\nargs = [\"-l\"]\n").is_err());
+    }
+
+    #[test]
+    fn no_shell_means_no_shell_line() {
+        assert_eq!(TerminalSettings::default().shell_line(), None);
+    }
+}
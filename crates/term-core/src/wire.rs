@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// A thread's buffer is let go of after encoding something bigger than this, so one huge
+/// listing doesn't pin its memory for the life of the thread.
+const KEEP_BUFFER_BYTES: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    static BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Encoding of requests and results on the C API and the `indexd` socket. Which ones a build
+/// has is listed in [`crate::Capabilities::formats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    /// MessagePack with structs as maps keyed by field name, so it decodes to the same shape
+    /// as the JSON. Needs the `msgpack` feature.
+    #[serde(rename = "msgpack")]
+    MsgPack,
+    /// CBOR (RFC 8949). Needs the `cbor` feature.
+    Cbor,
+}
+
+impl WireFormat {
+    /// The C functions' numbering: 0 JSON, 1 MessagePack, 2 CBOR.
+    pub fn from_u8(value: u8) -> anyhow::Result<Self> {
+        match value {
+            0 => Ok(Self::Json),
+            1 => Ok(Self::MsgPack),
+            2 => Ok(Self::Cbor),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown wire format {other} (0 json, 1 msgpack, 2 cbor)"
+            ))),
+        }
+    }
+
+    /// The formats this build can encode and decode, JSON first.
+    pub fn available() -> Vec<Self> {
+        [
+            (Self::Json, true),
+            (Self::MsgPack, cfg!(feature = "msgpack")),
+            (Self::Cbor, cfg!(feature = "cbor")),
+        ]
+        .into_iter()
+        .filter(|(_, built)| *built)
+        .map(|(format, _)| format)
+        .collect()
+    }
+
+    /// The error for using a format this build lacks.
+    pub(crate) fn missing(self) -> anyhow::Error {
+        let (name, feature) = match self {
+            Self::Json => ("JSON", "default"),
+            Self::MsgPack => ("MessagePack", "msgpack"),
+            Self::Cbor => ("CBOR", "cbor"),
+        };
+        CoreError::invalid_input(format!("this build has no {name} support"))
+            .with_hint(format!(
+                "rebuild term-core with the {feature} feature, or ask for json"
+            ))
+            .into()
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "msgpack" | "messagepack" => Ok(Self::MsgPack),
+            "cbor" => Ok(Self::Cbor),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown wire format {other:?} (json, msgpack, cbor)"
+            ))),
+        }
+    }
+}
+
+fn write<T: Serialize + ?Sized>(
+    buffer: &mut Vec<u8>,
+    value: &T,
+    format: WireFormat,
+) -> anyhow::Result<()> {
+    match format {
+        WireFormat::Json => serde_json::to_writer(buffer, value)?,
+        #[cfg(feature = "msgpack")]
+        WireFormat::MsgPack => rmp_serde::encode::write_named(buffer, value)?,
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor => ciborium::into_writer(value, buffer)?,
+        #[allow(unreachable_patterns)]
+        format => return Err(format.missing()),
+    }
+    Ok(())
+}
+
+/// Decodes `bytes` written in `format`.
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8], format: WireFormat) -> anyhow::Result<T> {
+    match format {
+        WireFormat::Json => Ok(serde_json::from_slice(bytes)?),
+        #[cfg(feature = "msgpack")]
+        WireFormat::MsgPack => Ok(rmp_serde::from_slice(bytes)?),
+        #[cfg(feature = "cbor")]
+        WireFormat::Cbor => Ok(ciborium::from_reader(bytes)?),
+        #[allow(unreachable_patterns)]
+        format => Err(format.missing()),
+    }
+}
+
+/// Encodes `value` into this thread's reused buffer and passes the bytes to `f`, so repeated
+/// calls don't regrow a fresh buffer each time.
+pub(crate) fn encode_with<T: Serialize + ?Sized, R>(
+    value: &T,
+    format: WireFormat,
+    f: impl FnOnce(&[u8]) -> R,
+) -> anyhow::Result<R> {
+    BUFFER.with(|buffer| {
+        // A callback that encodes again (e.g. while streaming) gets a buffer of its own.
+        let Ok(mut buffer) = buffer.try_borrow_mut() else {
+            let mut own = Vec::new();
+            write(&mut own, value, format)?;
+            return Ok(f(&own));
+        };
+        buffer.clear();
+        let written = write(&mut buffer, value, format);
+        let result = written.map(|_| f(&buffer));
+        if buffer.capacity() > KEEP_BUFFER_BYTES {
+            *buffer = Vec::new();
+        }
+        result
+    })
+}
+
+/// `value` as JSON in a C string allocated at its exact size.
+pub(crate) fn to_c_json<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<CString> {
+    // serde_json escapes NUL inside strings, so the only failure is a serialization error.
+    Ok(encode_with(value, WireFormat::Json, |json| {
+        CString::new(json)
+    })??)
+}
+
+/// `value` encoded as `format` in an exactly sized allocation.
+pub(crate) fn to_bytes<T: Serialize + ?Sized>(
+    value: &T,
+    format: WireFormat,
+) -> anyhow::Result<Box<[u8]>> {
+    encode_with(value, format, |bytes| Box::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings_reuse_the_buffer_and_round_trip() {
+        let entries = vec![crate::DirectoryEntry {
+            name: String::from("src"),
+            path: String::from("/w/src"),
+            is_dir: true,
+            mod_date: None,
+            git_status: None,
+        }];
+        let json = to_c_json(&entries).unwrap();
+        assert_eq!(
+            json.to_str().unwrap(),
+            serde_json::to_string(&entries).unwrap()
+        );
+        let capacity = BUFFER.with(|buffer| buffer.borrow().capacity());
+        to_c_json(&entries).unwrap();
+        assert_eq!(BUFFER.with(|buffer| buffer.borrow().capacity()), capacity);
+
+        let nested = encode_with(&1, WireFormat::Json, |outer| {
+            (outer.to_vec(), to_c_json(&2).unwrap())
+        })
+        .unwrap();
+        assert_eq!(nested.0, b"1");
+        assert_eq!(nested.1.to_str().unwrap(), "2");
+
+        for format in WireFormat::available() {
+            let bytes = to_bytes(&entries, format).unwrap();
+            let decoded: Vec<crate::DirectoryEntry> = decode(&bytes, format).unwrap();
+            assert_eq!(decoded[0].path, "/w/src");
+        }
+        assert_eq!(WireFormat::from_u8(1).unwrap(), WireFormat::MsgPack);
+        assert!(WireFormat::from_u8(9).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::git::{git_state, GitState};
+use crate::launcher::KNOWN_TERMINALS;
+use crate::{project_marker, STORE};
+
+pub(crate) const WORKSPACE_FILE: &str = ".terminaut.toml";
+
+/// A launch profile declared in a project's `.terminaut.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceProfile {
+    pub name: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Relative to the project root in the file; absolute once loaded.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub terminal: Option<String>,
+    #[serde(default = "default_windows")]
+    pub windows: u8,
+    /// Used when a location inside the project is opened without an explicit profile.
+    #[serde(default)]
+    pub default: bool,
+}
+
+fn default_windows() -> u8 {
+    1
+}
+
+/// The contents of a `.terminaut.toml` checked into a project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceFile {
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Task name to shell command.
+    pub tasks: BTreeMap<String, String>,
+    pub profiles: Vec<WorkspaceProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub root: String,
+    pub name: String,
+    /// Marker file that identified the root, if any.
+    pub marker: Option<String>,
+    /// Path of the `.terminaut.toml` that was merged in.
+    pub workspace_file: Option<String>,
+    pub git: Option<GitState>,
+    /// Store tags on the root followed by tags declared in the workspace file.
+    pub tags: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub tasks: BTreeMap<String, String>,
+    pub profiles: Vec<WorkspaceProfile>,
+}
+
+impl WorkspaceFile {
+    /// The profile to use for a launch with none chosen: the one marked `default`, else the
+    /// only profile when there is exactly one.
+    pub(crate) fn default_profile(&self) -> Option<&WorkspaceProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.default)
+            .or_else(|| (self.profiles.len() == 1).then(|| &self.profiles[0]))
+    }
+}
+
+fn stays_inside(relative: &Path) -> bool {
+    let mut depth = 0i32;
+    for component in relative.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth -= 1,
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+        if depth < 0 {
+            return false;
+        }
+    }
+    true
+}
+
+fn validate(file: &WorkspaceFile, root: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+    if file
+        .name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        problems.push(String::from("name must not be empty"));
+    }
+    for (index, tag) in file.tags.iter().enumerate() {
+        if tag.trim().is_empty() {
+            problems.push(format!("tags[{index}] must not be empty"));
+        }
+    }
+    for key in file.env.keys() {
+        if !crate::launcher::is_env_name(key) {
+            problems.push(format!("env key {key:?} is not a valid variable name"));
+        }
+    }
+    for (name, command) in &file.tasks {
+        if command.trim().is_empty() {
+            problems.push(format!("tasks.{name} has an empty command"));
+        }
+    }
+    let mut names = HashSet::new();
+    for (index, profile) in file.profiles.iter().enumerate() {
+        let at = format!("profiles[{index}]");
+        if profile.name.trim().is_empty() {
+            problems.push(format!("{at}.name must not be empty"));
+        } else if !names.insert(profile.name.to_lowercase()) {
+            problems.push(format!("{at}.name {:?} is used twice", profile.name));
+        }
+        if !(1..=10).contains(&profile.windows) {
+            problems.push(format!("{at}.windows must be between 1 and 10"));
+        }
+        if let Some(terminal) = &profile.terminal {
+            if !KNOWN_TERMINALS.contains(&terminal.to_ascii_lowercase().as_str()) {
+                problems.push(format!(
+                    "{at}.terminal {terminal:?} is not one of terminal, iterm, ghostty, alacritty, windows-terminal, system"
+                ));
+            }
+        }
+        if let Some(dir) = &profile.working_dir {
+            let relative = Path::new(dir);
+            if !stays_inside(relative) {
+                problems.push(format!(
+                    "{at}.working_dir {dir:?} must be relative to the project root"
+                ));
+            } else if !root.join(relative).is_dir() {
+                problems.push(format!("{at}.working_dir {dir:?} does not exist"));
+            }
+        }
+    }
+    if file
+        .profiles
+        .iter()
+        .filter(|profile| profile.default)
+        .count()
+        > 1
+    {
+        problems.push(String::from("only one profile may set default = true"));
+    }
+    problems
+}
+
+/// Parses and validates the workspace file in `root`. Every problem is reported at once so a
+/// broken file can be fixed in one pass.
+pub(crate) fn load_workspace(root: &Path) -> anyhow::Result<Option<WorkspaceFile>> {
+    let path = root.join(WORKSPACE_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+    };
+    let mut file: WorkspaceFile =
+        toml::from_str(&contents).with_context(|| format!("invalid {}", path.display()))?;
+    let problems = validate(&file, root);
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "invalid {}:\n  - {}",
+            path.display(),
+            problems.join("\n  - ")
+        );
+    }
+    for profile in file.profiles.iter_mut() {
+        let dir = match &profile.working_dir {
+            Some(dir) => root.join(dir),
+            None => root.to_path_buf(),
+        };
+        profile.working_dir = Some(dir.display().to_string());
+    }
+    Ok(Some(file))
+}
+
+/// The nearest ancestor holding a workspace file, falling back to the nearest project marker.
+pub(crate) fn find_project_root(path: &Path) -> (PathBuf, Option<String>) {
+    if let Some(root) = path
+        .ancestors()
+        .find(|ancestor| ancestor.join(WORKSPACE_FILE).is_file())
+    {
+        return (root.to_path_buf(), project_marker(root));
+    }
+    path.ancestors()
+        .find_map(|ancestor| project_marker(ancestor).map(|marker| (ancestor, marker)))
+        .map(|(root, marker)| (root.to_path_buf(), Some(marker)))
+        .unwrap_or_else(|| (path.to_path_buf(), None))
+}
+
+pub(crate) fn project_info(path: &Path) -> anyhow::Result<ProjectInfo> {
+    let (root, marker) = find_project_root(path);
+    let workspace = load_workspace(&root)?;
+    let root_string = root.display().to_string();
+
+    let mut tags: Vec<String> = STORE
+        .inner
+        .lock()
+        .tags
+        .iter()
+        .filter(|tag| tag.path == root_string)
+        .map(|tag| tag.tag.clone())
+        .collect();
+    let file = workspace.clone().unwrap_or_default();
+    for tag in file.tags {
+        if !tags
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&tag))
+        {
+            tags.push(tag);
+        }
+    }
+    let name = file
+        .name
+        .or_else(|| {
+            root.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| root_string.clone());
+
+    Ok(ProjectInfo {
+        name,
+        marker,
+        workspace_file: workspace
+            .is_some()
+            .then(|| root.join(WORKSPACE_FILE).display().to_string()),
+        git: git_state(&root),
+        tags,
+        env: file.env,
+        tasks: file.tasks,
+        profiles: file.profiles,
+        root: root_string,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let root = std::env::temp_dir();
+        let file: WorkspaceFile = toml::from_str(
+            r#"
+            tags = ["backend", ""]
+            [[profiles]]
+            name = "Dev"
+            windows = 0
+            working_dir = "../elsewhere"
+            [[profiles]]
+            name = "dev"
+            terminal = "warp"
+            [env]
+            GOOD = "1"
+            "X=1; rm -rf ~ #" = "1"
+            "#,
+        )
+        .unwrap();
+        let problems = validate(&file, &root);
+        assert_eq!(problems.len(), 6, "{problems:?}");
+        assert!(toml::from_str::<WorkspaceFile>("profile = []").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// Which side of a Windows/WSL boundary a path is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathFlavor {
+    /// `C:\Users\me`, or `\\wsl.localhost\Ubuntu\home\me` for files inside a distro.
+    Windows,
+    /// `/mnt/c/Users/me`, or `/home/me` inside the distro.
+    Wsl,
+}
+
+impl FromStr for PathFlavor {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "windows" => Ok(Self::Windows),
+            "wsl" | "linux" => Ok(Self::Wsl),
+            other => anyhow::bail!(CoreError::invalid_input(format!(
+                "unknown path flavor {other:?} (windows, wsl)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WslDistro {
+    pub name: String,
+    /// The distro `wsl.exe` opens when none is named.
+    pub default: bool,
+    pub running: bool,
+    /// WSL version, 1 or 2, when reported.
+    pub version: Option<u8>,
+}
+
+/// `\\wsl$\Distro\rest` or `\\wsl.localhost\Distro\rest` (either slash) as `(distro, rest)`.
+fn wsl_share(path: &str) -> Option<(&str, &str)> {
+    let lower = path.to_ascii_lowercase().replace('/', "\\");
+    let prefix = [r"\\wsl$\", r"\\wsl.localhost\"]
+        .into_iter()
+        .find(|prefix| lower.starts_with(prefix))?;
+    let rest = &path[prefix.len()..];
+    Some(match rest.find(['\\', '/']) {
+        Some(at) => (&rest[..at], &rest[at..]),
+        None => (rest, ""),
+    })
+}
+
+fn drive_path(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+    (rest.is_empty() || rest.starts_with(['\\', '/'])).then_some((drive, rest))
+}
+
+/// The distro this process runs in, when it runs inside WSL.
+fn current_distro() -> Option<String> {
+    std::env::var("WSL_DISTRO_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+/// Rewrites `path` for the `target` side. Linux paths outside `/mnt/<drive>` become
+/// `\\wsl.localhost\<distro>\...` and so need `distro` unless this runs inside WSL.
+pub(crate) fn translate_path(
+    path: &str,
+    target: PathFlavor,
+    distro: Option<&str>,
+) -> anyhow::Result<String> {
+    let path = path.trim();
+    match target {
+        PathFlavor::Wsl => {
+            if let Some((_, rest)) = wsl_share(path) {
+                let rest = rest.replace('\\', "/");
+                return Ok(if rest.is_empty() {
+                    String::from("/")
+                } else {
+                    rest
+                });
+            }
+            if path.starts_with('/') {
+                return Ok(path.to_string());
+            }
+            let (drive, rest) = drive_path(path).ok_or_else(|| {
+                CoreError::invalid_input(format!("{path} has no WSL equivalent"))
+                    .with_hint("use a drive path such as C:\\Users\\me")
+            })?;
+            Ok(format!(
+                "/mnt/{}{}",
+                drive.to_ascii_lowercase(),
+                rest.replace('\\', "/").trim_end_matches('/')
+            ))
+        }
+        PathFlavor::Windows => {
+            if drive_path(path).is_some() || path.starts_with(r"\\") {
+                return Ok(path.to_string());
+            }
+            if !path.starts_with('/') {
+                anyhow::bail!(CoreError::invalid_input(format!(
+                    "{path} is not an absolute Linux path"
+                )));
+            }
+            let mut parts = path.trim_start_matches('/').splitn(3, '/');
+            if let (Some("mnt"), Some(drive)) = (parts.next(), parts.next()) {
+                if drive.len() == 1 && drive.chars().all(|c| c.is_ascii_alphabetic()) {
+                    let rest = parts.next().unwrap_or_default().replace('/', "\\");
+                    return Ok(format!("{}:\\{rest}", drive.to_ascii_uppercase()));
+                }
+            }
+            let distro = distro
+                .map(str::to_string)
+                .or_else(current_distro)
+                .ok_or_else(|| {
+                    CoreError::invalid_input(format!("{path} is inside a WSL distro"))
+                        .with_hint("name the distro to translate it to a \\\\wsl.localhost path")
+                })?;
+            Ok(format!(
+                r"\\wsl.localhost\{distro}{}",
+                path.trim_end_matches('/').replace('/', "\\")
+            ))
+        }
+    }
+}
+
+/// `wsl.exe` writes UTF-16LE when its output is redirected.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn decode_output(bytes: &[u8]) -> String {
+    if bytes.len().is_multiple_of(2) && bytes.iter().skip(1).step_by(2).all(|b| *b == 0) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// Parses `wsl.exe --list --verbose`:
+///
+/// ```text
+///   NAME      STATE           VERSION
+/// * Ubuntu    Running         2
+///   Debian    Stopped         2
+/// ```
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_list(text: &str) -> Vec<WslDistro> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+            let (default, line) = match line.strip_prefix('*') {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, line),
+            };
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let state = fields.next().unwrap_or_default();
+            Some(WslDistro {
+                name,
+                default,
+                running: state.eq_ignore_ascii_case("running"),
+                version: fields.next().and_then(|version| version.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Installed distros on Windows; inside WSL, the current one. Empty elsewhere or when WSL
+/// isn't installed.
+pub(crate) fn wsl_distros() -> Vec<WslDistro> {
+    #[cfg(windows)]
+    {
+        std::process::Command::new("wsl.exe")
+            .args(["--list", "--verbose"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| parse_list(&decode_output(&output.stdout)))
+            .unwrap_or_default()
+    }
+    #[cfg(not(windows))]
+    {
+        current_distro()
+            .map(|name| WslDistro {
+                name,
+                default: true,
+                running: true,
+                version: None,
+            })
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_translate_both_ways() {
+        let wsl = |path| translate_path(path, PathFlavor::Wsl, None).unwrap();
+        let windows = |path| translate_path(path, PathFlavor::Windows, Some("Ubuntu")).unwrap();
+        assert_eq!(wsl(r"C:\Users\me"), "/mnt/c/Users/me");
+        assert_eq!(wsl("d:"), "/mnt/d");
+        assert_eq!(wsl(r"\\wsl$\Ubuntu\home\me"), "/home/me");
+        assert_eq!(wsl("//wsl.localhost/Debian/srv"), "/srv");
+        assert_eq!(wsl("/home/me"), "/home/me");
+        assert_eq!(windows("/mnt/c/Users/me"), r"C:\Users\me");
+        assert_eq!(windows("/mnt/d"), r"D:\");
+        assert_eq!(windows("/home/me/"), r"\\wsl.localhost\Ubuntu\home\me");
+        assert_eq!(windows(r"C:\x"), r"C:\x");
+        assert!(translate_path(r"\\nas\share", PathFlavor::Wsl, None).is_err());
+        assert!(translate_path("relative", PathFlavor::Windows, None).is_err());
+    }
+
+    #[test]
+    fn distro_list_parses_utf16_output() {
+        let text = "  NAME      STATE           VERSION\r\n* Ubuntu    Running         2\r\n  \
+                    Debian    Stopped         1\r\n";
+        let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let distros = parse_list(&decode_output(&bytes));
+        assert_eq!(distros.len(), 2);
+        assert!(distros[0].default && distros[0].running);
+        assert_eq!(distros[1].name, "Debian");
+        assert_eq!(distros[1].version, Some(1));
+        assert!(!distros[1].default);
+    }
+}